@@ -0,0 +1,89 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use wasmer::*;
+use wasmer_engine_jit::JIT;
+
+#[path = "../lib/compiler/tests/utils/mod.rs"]
+mod utils;
+
+use utils::{leb128, module, vector};
+
+/// The size in bytes of the data segment of the fixture module.
+const DATA_SIZE: usize = 64 << 20;
+
+/// A module with one `DATA_SIZE`-byte data segment filling its memory.
+///
+/// The module is built as a binary, since the text format would need an
+/// escaped string several times larger than the segment.
+fn large_segment_wasm() -> Vec<u8> {
+    // `(memory DATA_SIZE / 64 KiB)`
+    let mut memory = vec![0x00];
+    leb128(&mut memory, DATA_SIZE >> 16);
+
+    // `(data (i32.const 0) "...")`
+    let mut segment = vec![0x00, 0x41, 0x00, 0x0b];
+    leb128(&mut segment, DATA_SIZE);
+    segment.extend((0..DATA_SIZE).map(|i| (i % 251) as u8 + 1));
+
+    module(&[(0x05, vector(1, &memory)), (0x0b, vector(1, &segment))])
+}
+
+pub fn run_instantiate_large_data_segment(
+    engine: &dyn Engine,
+    compiler_name: &str,
+    c: &mut Criterion,
+) {
+    let wasm = large_segment_wasm();
+    for &(threshold, label) in &[
+        (None, "without prefault"),
+        (Some(0x10_0000), "with prefault"),
+    ] {
+        let mut tunables = Tunables::for_target(engine.target());
+        tunables.data_prefault_threshold = threshold;
+        let store = Store::new_with_tunables(engine, tunables);
+        let module = Module::new(&store, &wasm).unwrap();
+
+        c.bench_function(
+            &format!(
+                "instantiate {} MiB data segment {} {}",
+                DATA_SIZE >> 20,
+                label,
+                compiler_name
+            ),
+            |b| {
+                b.iter(|| {
+                    let instance = black_box(Instance::new(&module, &imports! {}).unwrap());
+                    drop(instance);
+                })
+            },
+        );
+    }
+}
+
+fn run_instantiate_benchmarks(c: &mut Criterion) {
+    #[cfg(feature = "llvm")]
+    {
+        let engine = JIT::new(&wasmer_compiler_llvm::LLVM::new()).engine();
+        run_instantiate_large_data_segment(&engine, "llvm", c);
+    }
+
+    #[cfg(feature = "cranelift")]
+    {
+        let engine = JIT::new(&wasmer_compiler_cranelift::Cranelift::new()).engine();
+        run_instantiate_large_data_segment(&engine, "cranelift", c);
+    }
+
+    #[cfg(feature = "singlepass")]
+    {
+        let engine = JIT::new(&wasmer_compiler_singlepass::Singlepass::new()).engine();
+        run_instantiate_large_data_segment(&engine, "singlepass", c);
+    }
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = run_instantiate_benchmarks
+}
+
+criterion_main!(benches);
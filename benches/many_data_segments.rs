@@ -0,0 +1,69 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use wasmer::*;
+use wasmer_engine_jit::JIT;
+
+/// The number of data segments of the fixture module.
+const SEGMENTS: usize = 4096;
+
+/// A module with `SEGMENTS` adjacent 8-byte data segments, like the ones
+/// emitted for every static object by some toolchains.
+///
+/// The memory is imported, so that every instantiation initializes the
+/// same memory instead of allocating a new one.
+fn many_segments_wat() -> String {
+    let mut wat = String::from("(module\n    (import \"env\" \"memory\" (memory 1))\n");
+    for i in 0..SEGMENTS {
+        wat.push_str(&format!(
+            "    (data (i32.const {}) \"\\{:02x}\\00\\00\\00\\00\\00\\00\\00\")\n",
+            i * 8,
+            i % 256
+        ));
+    }
+    wat.push(')');
+    wat
+}
+
+pub fn run_instantiate_many_data_segments(store: &Store, compiler_name: &str, c: &mut Criterion) {
+    let module = Module::new(&store, many_segments_wat()).unwrap();
+    let memory = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    let import_object = imports! {
+        "env" => {
+            "memory" => memory,
+        },
+    };
+
+    c.bench_function(
+        &format!("instantiate {} data segments {}", SEGMENTS, compiler_name),
+        |b| {
+            b.iter(|| {
+                let instance = black_box(Instance::new(&module, &import_object).unwrap());
+                drop(instance);
+            })
+        },
+    );
+}
+
+fn run_instantiate_benchmarks(c: &mut Criterion) {
+    #[cfg(feature = "llvm")]
+    {
+        let store = Store::new(&JIT::new(&wasmer_compiler_llvm::LLVM::new()).engine());
+        run_instantiate_many_data_segments(&store, "llvm", c);
+    }
+
+    #[cfg(feature = "cranelift")]
+    {
+        let store = Store::new(&JIT::new(&wasmer_compiler_cranelift::Cranelift::new()).engine());
+        run_instantiate_many_data_segments(&store, "cranelift", c);
+    }
+
+    #[cfg(feature = "singlepass")]
+    {
+        let store = Store::new(&JIT::new(&wasmer_compiler_singlepass::Singlepass::new()).engine());
+        run_instantiate_many_data_segments(&store, "singlepass", c);
+    }
+}
+
+criterion_group!(benches, run_instantiate_benchmarks);
+
+criterion_main!(benches);
@@ -0,0 +1,67 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use wasmer::*;
+use wasmer_engine_jit::JIT;
+
+/// The number of exported functions of the fixture module.
+const EXPORTS: usize = 5000;
+
+/// A module with `EXPORTS` exported functions, each returning its index.
+fn many_exports_wat() -> String {
+    let mut wat = String::from("(module\n");
+    for i in 0..EXPORTS {
+        wat.push_str(&format!(
+            "    (func (export \"f{}\") (result i32) (i32.const {}))\n",
+            i, i
+        ));
+    }
+    wat.push(')');
+    wat
+}
+
+pub fn run_instantiate_and_call_export(store: &Store, compiler_name: &str, c: &mut Criterion) {
+    let module = Module::new(&store, many_exports_wat()).unwrap();
+    let import_object = imports! {};
+    let name = format!("f{}", EXPORTS / 2);
+
+    c.bench_function(
+        &format!(
+            "instantiate and call an export of {} {}",
+            EXPORTS, compiler_name
+        ),
+        |b| {
+            b.iter(|| {
+                let instance = Instance::new(&module, &import_object).unwrap();
+                let f = instance
+                    .exports
+                    .get_native_function::<(), i32>(&name)
+                    .unwrap();
+                black_box(f.call().unwrap());
+            })
+        },
+    );
+}
+
+fn run_many_exports_benchmarks(c: &mut Criterion) {
+    #[cfg(feature = "llvm")]
+    {
+        let store = Store::new(&JIT::new(&wasmer_compiler_llvm::LLVM::new()).engine());
+        run_instantiate_and_call_export(&store, "llvm", c);
+    }
+
+    #[cfg(feature = "cranelift")]
+    {
+        let store = Store::new(&JIT::new(&wasmer_compiler_cranelift::Cranelift::new()).engine());
+        run_instantiate_and_call_export(&store, "cranelift", c);
+    }
+
+    #[cfg(feature = "singlepass")]
+    {
+        let store = Store::new(&JIT::new(&wasmer_compiler_singlepass::Singlepass::new()).engine());
+        run_instantiate_and_call_export(&store, "singlepass", c);
+    }
+}
+
+criterion_group!(benches, run_many_exports_benchmarks);
+
+criterion_main!(benches);
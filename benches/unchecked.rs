@@ -0,0 +1,82 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use wasmer::*;
+use wasmer_engine_jit::JIT;
+
+static WAT: &str = r#"(module
+    (memory 1)
+    (func (export "add") (param i32 i32) (result i32)
+       (i32.add (local.get 0)
+                (local.get 1)))
+    (func (export "sum") (param $len i32) (result i32)
+       (local $i i32)
+       (local $sum i32)
+       (block $done
+         (loop $next
+           (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+           (local.set $sum (i32.add (local.get $sum)
+                                    (i32.load (i32.shl (local.get $i) (i32.const 2)))))
+           (local.set $i (i32.add (local.get $i) (i32.const 1)))
+           (br $next)))
+       (local.get $sum))
+)"#;
+
+/// Benchmarks the calls and memory accesses of the engine of `store`, to
+/// compare checked and unchecked engines.
+pub fn run_calls_and_memory_accesses(store: &Store, name: &str, c: &mut Criterion) {
+    let module = Module::new(&store, WAT).unwrap();
+    let instance = Instance::new(&module, &imports! {}).unwrap();
+
+    let add: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("add").unwrap();
+    c.bench_function(&format!("call {}", name), |b| {
+        b.iter(|| {
+            let result = black_box(add.call(4, 6).unwrap());
+            assert_eq!(result, 10);
+        })
+    });
+
+    let sum: NativeFunc<i32, i32> = instance.exports.get_native_function("sum").unwrap();
+    c.bench_function(&format!("sum a memory page {}", name), |b| {
+        b.iter(|| {
+            let result = black_box(sum.call(0x4000).unwrap());
+            assert_eq!(result, 0);
+        })
+    });
+}
+
+fn run_unchecked_benchmarks(c: &mut Criterion) {
+    #[cfg(feature = "llvm")]
+    {
+        let store = Store::new(&JIT::new(&wasmer_compiler_llvm::LLVM::new()).engine());
+        run_calls_and_memory_accesses(&store, "llvm", c);
+        let store = Store::new(
+            &unsafe { JIT::new(&wasmer_compiler_llvm::LLVM::new()).unchecked() }.engine(),
+        );
+        run_calls_and_memory_accesses(&store, "unchecked llvm", c);
+    }
+
+    #[cfg(feature = "cranelift")]
+    {
+        let store = Store::new(&JIT::new(&wasmer_compiler_cranelift::Cranelift::new()).engine());
+        run_calls_and_memory_accesses(&store, "cranelift", c);
+        let store = Store::new(
+            &unsafe { JIT::new(&wasmer_compiler_cranelift::Cranelift::new()).unchecked() }.engine(),
+        );
+        run_calls_and_memory_accesses(&store, "unchecked cranelift", c);
+    }
+
+    #[cfg(feature = "singlepass")]
+    {
+        let store = Store::new(&JIT::new(&wasmer_compiler_singlepass::Singlepass::new()).engine());
+        run_calls_and_memory_accesses(&store, "singlepass", c);
+        let store = Store::new(
+            &unsafe { JIT::new(&wasmer_compiler_singlepass::Singlepass::new()).unchecked() }
+                .engine(),
+        );
+        run_calls_and_memory_accesses(&store, "unchecked singlepass", c);
+    }
+}
+
+criterion_group!(benches, run_unchecked_benchmarks);
+
+criterion_main!(benches);
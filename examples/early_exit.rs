@@ -49,7 +49,7 @@ fn main() -> anyhow::Result<()> {
 
     let import_object = imports! {
         "env" => {
-            "early_exit" => Function::new_native(&store, early_exit),
+            "early_exit" => Function::new_noreturn(&store, early_exit),
         }
     };
     let instance = Instance::new(&module, &import_object)?;
@@ -19,8 +19,7 @@
 //! Ready?
 
 use std::str::FromStr;
-use wasmer::{wat2wasm, Module, RuntimeError, Store};
-use wasmer_compiler::{CpuFeature, Target, Triple};
+use wasmer::{wat2wasm, CpuFeature, Module, RuntimeError, Store, Target, Triple};
 use wasmer_compiler_cranelift::Cranelift;
 use wasmer_engine_native::Native;
 
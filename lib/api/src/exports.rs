@@ -1,13 +1,16 @@
 use crate::externals::{Extern, Function, Global, Memory, Table};
 use crate::import_object::LikeNamespace;
 use crate::native::NativeFunc;
+use crate::store::Store;
 use crate::WasmTypeList;
 use indexmap::IndexMap;
+use once_cell::sync::OnceCell;
 use std::fmt;
 use std::iter::{ExactSizeIterator, FromIterator};
 use std::sync::Arc;
 use thiserror::Error;
-use wasmer_vm::Export;
+use wasmer_engine::Artifact;
+use wasmer_vm::{Export, InstanceHandle};
 
 /// The `ExportError` can happen when trying to get a specific
 /// export [`Extern`] from the [`Instance`] exports.
@@ -38,9 +41,16 @@ pub enum ExportError {
 
 /// Exports is a special kind of map that allows easily unwrapping
 /// the types of instances.
+///
+/// The exports of an instance are resolved on first use, so that
+/// instantiating a module with many exports doesn't pay for the ones that
+/// aren't used.
 #[derive(Clone, Default)]
 pub struct Exports {
     map: Arc<IndexMap<String, Extern>>,
+    /// The exports of the instance, not inserted in `map` until the
+    /// `Exports` are modified.
+    instance: Option<Arc<InstanceExports>>,
 }
 
 impl Exports {
@@ -53,12 +63,35 @@ impl Exports {
     pub fn with_capacity(n: usize) -> Self {
         Self {
             map: Arc::new(IndexMap::with_capacity(n)),
+            instance: None,
+        }
+    }
+
+    /// Creates the `Exports` of the instance `handle` of `artifact`.
+    pub(crate) fn from_instance(
+        store: &Store,
+        artifact: &Arc<dyn Artifact>,
+        handle: InstanceHandle,
+    ) -> Self {
+        let externs = artifact
+            .export_resolutions()
+            .iter()
+            .map(|_| OnceCell::new())
+            .collect();
+        Self {
+            map: Default::default(),
+            instance: Some(Arc::new(InstanceExports {
+                store: store.clone(),
+                artifact: artifact.clone(),
+                handle,
+                externs,
+            })),
         }
     }
 
     /// Return the number of exports in the `Exports` map.
     pub fn len(&self) -> usize {
-        self.map.len()
+        self.map.len() + self.instance.as_ref().map_or(0, |instance| instance.len())
     }
 
     /// Return whether or not there are no exports
@@ -72,6 +105,14 @@ impl Exports {
         S: Into<String>,
         E: Into<Extern>,
     {
+        if let Some(instance) = self.instance.take() {
+            self.map = Arc::new(
+                instance
+                    .iter()
+                    .map(|(name, extern_)| (name.clone(), extern_.clone()))
+                    .collect(),
+            );
+        }
         Arc::get_mut(&mut self.map)
             .unwrap()
             .insert(name.into(), value.into());
@@ -89,7 +130,7 @@ impl Exports {
     /// If you want to get an export dynamically handling manually
     /// type checking manually, please use `get_extern`.
     pub fn get<'a, T: Exportable<'a>>(&'a self, name: &str) -> Result<&'a T, ExportError> {
-        match self.map.get(name) {
+        match self.get_extern(name) {
             None => Err(ExportError::Missing(name.to_string())),
             Some(extern_) => T::get_self_from_extern(extern_),
         }
@@ -131,7 +172,10 @@ impl Exports {
 
     /// Get an export as an `Extern`.
     pub fn get_extern(&self, name: &str) -> Option<&Extern> {
-        self.map.get(name)
+        match &self.instance {
+            Some(instance) => instance.get(name),
+            None => self.map.get(name),
+        }
     }
 
     /// Returns true if the `Exports` contains the given export name.
@@ -139,17 +183,69 @@ impl Exports {
     where
         S: Into<String>,
     {
-        self.map.contains_key(&name.into())
+        let name = name.into();
+        match &self.instance {
+            Some(instance) => instance.contains(&name),
+            None => self.map.contains_key(&name),
+        }
     }
 
     /// Get an iterator over the exports.
     pub fn iter(&self) -> ExportsIterator<impl Iterator<Item = (&String, &Extern)>> {
         ExportsIterator {
-            iter: self.map.iter(),
+            iter: self
+                .map
+                .iter()
+                .chain(self.instance.iter().flat_map(|instance| instance.iter())),
         }
     }
 }
 
+/// The exports of an instance, resolved on first use with the export
+/// resolutions precomputed by its artifact.
+struct InstanceExports {
+    store: Store,
+    artifact: Arc<dyn Artifact>,
+    handle: InstanceHandle,
+    /// The resolved exports, in the order of the exports of the module.
+    externs: Vec<OnceCell<Extern>>,
+}
+
+// The instance handle is only used to read the definitions of the exports,
+// which don't change once it's created.
+unsafe impl Sync for InstanceExports {}
+
+impl InstanceExports {
+    fn len(&self) -> usize {
+        self.externs.len()
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.artifact.module_ref().exports.contains_key(name)
+    }
+
+    fn get(&self, name: &str) -> Option<&Extern> {
+        let (index, _, _) = self.artifact.module_ref().exports.get_full(name)?;
+        Some(self.extern_at(index))
+    }
+
+    fn extern_at(&self, index: usize) -> &Extern {
+        self.externs[index].get_or_init(|| {
+            let resolution = &self.artifact.export_resolutions()[index];
+            Extern::from_export(&self.store, self.handle.resolve_export(resolution))
+        })
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&String, &Extern)> {
+        self.artifact
+            .module_ref()
+            .exports
+            .keys()
+            .enumerate()
+            .map(move |(index, name)| (name, self.extern_at(index)))
+    }
+}
+
 impl fmt::Debug for Exports {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_set().entries(self.iter()).finish()
@@ -234,12 +330,11 @@ impl FromIterator<(String, Extern)> for Exports {
 
 impl LikeNamespace for Exports {
     fn get_namespace_export(&self, name: &str) -> Option<Export> {
-        self.map.get(name).map(|is_export| is_export.to_export())
+        self.get_extern(name).map(|is_export| is_export.to_export())
     }
 
     fn get_namespace_exports(&self) -> Vec<(String, Export)> {
-        self.map
-            .iter()
+        self.iter()
             .map(|(k, v)| (k.clone(), v.to_export()))
             .collect()
     }
@@ -1,18 +1,26 @@
 use crate::exports::{ExportError, Exportable};
 use crate::externals::Extern;
 use crate::store::Store;
-use crate::types::Val;
+use crate::types::{ParseFunctionTypeError, Val, ValType};
 use crate::FunctionType;
 use crate::NativeFunc;
 use crate::RuntimeError;
-pub use inner::{FromToNativeWasmType, HostFunction, WasmTypeList, WithEnv, WithoutEnv};
+pub use inner::{
+    CallError, FromToNativeWasmType, Function as StaticFunction, HostFunction, WasmTypeList,
+    WithEnv, WithoutEnv,
+};
+use once_cell::sync::Lazy;
+use smallvec::SmallVec;
 use std::cell::RefCell;
 use std::cmp::max;
+use std::collections::hash_map::RandomState;
 use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use wasmer_vm::{
-    raise_user_trap, resume_panic, wasmer_call_trampoline, Export, ExportFunction,
-    VMCallerCheckedAnyfunc, VMContext, VMDynamicFunctionContext, VMFunctionBody, VMFunctionKind,
-    VMTrampoline,
+    raise_user_trap, resume_panic, wasmer_call_trampoline, wasmer_call_trampoline_unchecked,
+    with_deferred_cleanups, Export, ExportFunction, Trap, VMCallerCheckedAnyfunc, VMContext,
+    VMDynamicFunctionContext, VMFunctionBody, VMFunctionKind, VMTrampoline,
 };
 
 /// A function defined in the Wasm module
@@ -20,6 +28,26 @@ use wasmer_vm::{
 pub struct WasmFunctionDefinition {
     // The trampoline to do the call
     pub(crate) trampoline: VMTrampoline,
+    // Whether the function runs unchecked, without catching its traps
+    pub(crate) unchecked: bool,
+}
+
+impl WasmFunctionDefinition {
+    /// Calls the function `callee` through the trampoline, catching its
+    /// traps unless it runs unchecked, see `wasmer_call_trampoline`.
+    pub(crate) unsafe fn call_trampoline(
+        &self,
+        vmctx: *mut VMContext,
+        callee: *const VMFunctionBody,
+        values_vec: *mut u8,
+    ) -> Result<(), Trap> {
+        if self.unchecked {
+            wasmer_call_trampoline_unchecked(vmctx, self.trampoline, callee, values_vec);
+            Ok(())
+        } else {
+            wasmer_call_trampoline(vmctx, self.trampoline, callee, values_vec)
+        }
+    }
 }
 
 /// A function defined in the Host
@@ -48,14 +76,82 @@ pub enum FunctionDefinition {
 /// The module instance is used to resolve references to other definitions
 /// during execution of the function.
 ///
+/// Two `Function`s are equal if they call the same code with the same
+/// `VMContext`, that is if they are the same function of the same instance,
+/// however they were obtained: from the exports of an instance, from a
+/// table, or from the exports of another instance that imported it.
+/// Every host function is a distinct instance: two host functions created
+/// separately are never equal, even from the same Rust function.
+///
+/// Cloning a `Function` is cheap: the clone is the same function instance,
+/// and shares its environment, if any, with the original.
+///
 /// Spec: https://webassembly.github.io/spec/core/exec/runtime.html#function-instances
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
 pub struct Function {
     pub(crate) store: Store,
     pub(crate) definition: FunctionDefinition,
     pub(crate) exported: ExportFunction,
 }
 
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        self.exported.address == other.exported.address
+            && self.exported.vmctx == other.exported.vmctx
+    }
+}
+
+impl Eq for Function {}
+
+impl Hash for Function {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.exported.address.hash(state);
+        self.exported.vmctx.hash(state);
+    }
+}
+
+/// The context of a native host function with an environment, pointed to
+/// by its `vmctx`.
+///
+/// It's `repr(C)` so that a pointer to it is a pointer to its environment,
+/// and it's never zero-sized so that every host function has its own
+/// `vmctx`, and thus its own identity, even with an empty environment.
+#[repr(C)]
+struct NativeFunctionContext<Env> {
+    env: Env,
+    _identity: u8,
+}
+
+impl<Env> NativeFunctionContext<Env> {
+    /// Moves `env` into a new context, which is never freed.
+    fn leak(env: Env) -> *mut VMContext {
+        Box::into_raw(Box::new(Self { env, _identity: 0 })) as *mut VMContext
+    }
+}
+
+/// Returns a new `vmctx` for a native host function without environment,
+/// which ignores it: it only gives the function its own identity.
+///
+/// It's a number never used by another one, rather than a pointer, so
+/// creating these functions doesn't allocate memory that is never freed.
+fn native_function_identity() -> *mut VMContext {
+    static NEXT_IDENTITY: AtomicUsize = AtomicUsize::new(1);
+    NEXT_IDENTITY.fetch_add(1, Ordering::Relaxed) as *mut VMContext
+}
+
+/// Returns an identifier of the function at `address` with the context
+/// `vmctx`, which `Debug` prints instead of the addresses.
+///
+/// The identifier is stable for the life of the process, but as a hash
+/// with a random key, it doesn't reveal the layout of the address space.
+fn function_id(address: *const VMFunctionBody, vmctx: *const VMContext) -> u64 {
+    static KEY: Lazy<RandomState> = Lazy::new(RandomState::new);
+    let mut hasher = KEY.build_hasher();
+    address.hash(&mut hasher);
+    vmctx.hash(&mut hasher);
+    hasher.finish()
+}
+
 impl Function {
     /// Creates a new host `Function` (dynamic) with the provided signature.
     ///
@@ -72,12 +168,43 @@ impl Function {
     ///     Ok(vec![Value::I32(sum)])
     /// });
     /// ```
-    #[allow(clippy::cast_ptr_alignment)]
     pub fn new<F>(store: &Store, ty: &FunctionType, func: F) -> Self
     where
         F: Fn(&[Val]) -> Result<Vec<Val>, RuntimeError> + 'static,
     {
-        let dynamic_ctx = VMDynamicFunctionContext::from_context(VMDynamicFunctionWithoutEnv {
+        Self::new_with_results(store, ty, move |args, results| {
+            move_results(func(args)?, results)
+        })
+    }
+
+    /// Creates a new host `Function` (dynamic) with the provided signature and environment.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Function, FunctionType, Type, Store, Value};
+    /// # let store = Store::default();
+    ///
+    /// struct Env {
+    ///   multiplier: i32,
+    /// };
+    /// let env = Env { multiplier: 2 };
+    ///
+    /// let signature = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+    ///
+    /// let f = Function::new_with_env(&store, &signature, env, |env, args| {
+    ///     let result = env.multiplier * (args[0].unwrap_i32() + args[1].unwrap_i32());
+    ///     Ok(vec![Value::I32(result)])
+    /// });
+    /// ```
+    #[allow(clippy::cast_ptr_alignment)]
+    pub fn new_with_env<F, Env>(store: &Store, ty: &FunctionType, env: Env, func: F) -> Self
+    where
+        F: Fn(&mut Env, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static,
+        Env: Sized + 'static,
+    {
+        let dynamic_ctx = VMDynamicFunctionContext::from_context(VMDynamicFunctionWithEnv {
+            env: RefCell::new(env),
             func: Box::new(func),
             function_type: ty.clone(),
         });
@@ -89,44 +216,72 @@ impl Function {
 
         Self {
             store: store.clone(),
-            definition: FunctionDefinition::Host(HostFunctionDefinition { has_env: false }),
+            definition: FunctionDefinition::Host(HostFunctionDefinition { has_env: true }),
             exported: ExportFunction {
                 address,
                 kind: VMFunctionKind::Dynamic,
                 vmctx,
                 signature: ty.clone(),
+                noreturn: false,
             },
         }
     }
 
-    /// Creates a new host `Function` (dynamic) with the provided signature and environment.
+    /// Creates a new host `Function` (dynamic) with the signature parsed
+    /// from `signature`, such as `"(i32, i64) -> f64"`.
+    ///
+    /// This is meant for bindings creating host functions at runtime; see
+    /// the `FromStr` implementation of `FunctionType` for the syntax.
     ///
     /// # Example
     ///
     /// ```
-    /// # use wasmer::{Function, FunctionType, Type, Store, Value};
+    /// # use wasmer::{Function, Store, Value};
     /// # let store = Store::default();
     ///
-    /// struct Env {
-    ///   multiplier: i32,
-    /// };
-    /// let env = Env { multiplier: 2 };
+    /// let f = Function::new_with_signature(&store, "(i32, i32) -> i32", |args| {
+    ///     let sum = args[0].unwrap_i32() + args[1].unwrap_i32();
+    ///     Ok(vec![Value::I32(sum)])
+    /// })?;
+    /// # Ok::<(), wasmer::ParseFunctionTypeError>(())
+    /// ```
+    pub fn new_with_signature<F>(
+        store: &Store,
+        signature: &str,
+        func: F,
+    ) -> Result<Self, ParseFunctionTypeError>
+    where
+        F: Fn(&[Val]) -> Result<Vec<Val>, RuntimeError> + 'static,
+    {
+        Ok(Self::new(store, &signature.parse()?, func))
+    }
+
+    /// Creates a new host `Function` (dynamic) with the provided signature,
+    /// which writes its results into a slice instead of returning them.
+    ///
+    /// `func` is given one value per result, initialized to zero (or a null
+    /// reference), to overwrite. Unless the function has many parameters or
+    /// results, calling it doesn't allocate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Function, FunctionType, Type, Store, Value};
+    /// # let store = Store::default();
     ///
     /// let signature = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
     ///
-    /// let f = Function::new_with_env(&store, &signature, env, |env, args| {
-    ///     let result = env.multiplier * (args[0].unwrap_i32() + args[1].unwrap_i32());
-    ///     Ok(vec![Value::I32(result)])
+    /// let f = Function::new_with_results(&store, &signature, |args, results| {
+    ///     results[0] = Value::I32(args[0].unwrap_i32() + args[1].unwrap_i32());
+    ///     Ok(())
     /// });
     /// ```
     #[allow(clippy::cast_ptr_alignment)]
-    pub fn new_with_env<F, Env>(store: &Store, ty: &FunctionType, env: Env, func: F) -> Self
+    pub fn new_with_results<F>(store: &Store, ty: &FunctionType, func: F) -> Self
     where
-        F: Fn(&mut Env, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static,
-        Env: Sized + 'static,
+        F: Fn(&[Val], &mut [Val]) -> Result<(), RuntimeError> + 'static,
     {
-        let dynamic_ctx = VMDynamicFunctionContext::from_context(VMDynamicFunctionWithEnv {
-            env: RefCell::new(env),
+        let dynamic_ctx = VMDynamicFunctionContext::from_context(VMDynamicFunctionWithoutEnv {
             func: Box::new(func),
             function_type: ty.clone(),
         });
@@ -138,12 +293,13 @@ impl Function {
 
         Self {
             store: store.clone(),
-            definition: FunctionDefinition::Host(HostFunctionDefinition { has_env: true }),
+            definition: FunctionDefinition::Host(HostFunctionDefinition { has_env: false }),
             exported: ExportFunction {
                 address,
                 kind: VMFunctionKind::Dynamic,
                 vmctx,
                 signature: ty.clone(),
+                noreturn: false,
             },
         }
     }
@@ -174,7 +330,7 @@ impl Function {
     {
         let function = inner::Function::<Args, Rets>::new(func);
         let address = function.address() as *const VMFunctionBody;
-        let vmctx = std::ptr::null_mut() as *mut _ as *mut VMContext;
+        let vmctx = native_function_identity();
         let signature = function.ty();
 
         Self {
@@ -185,6 +341,7 @@ impl Function {
                 vmctx,
                 signature,
                 kind: VMFunctionKind::Static,
+                noreturn: false,
             },
         }
     }
@@ -226,8 +383,9 @@ impl Function {
         // Wasm-defined functions have a `VMContext`.
         // In the case of Host-defined functions `VMContext` is whatever environment
         // the user want to attach to the function.
-        let box_env = Box::new(env);
-        let vmctx = Box::into_raw(box_env) as *mut _ as *mut VMContext;
+        // The environment is never freed, as the instances importing the
+        // function, which may call it at any time, are never freed either.
+        let vmctx = NativeFunctionContext::leak(env);
         let signature = function.ty();
 
         Self {
@@ -238,9 +396,54 @@ impl Function {
                 kind: VMFunctionKind::Static,
                 vmctx,
                 signature,
+                noreturn: false,
             },
         }
     }
+
+    /// Creates a new host `Function` from a native function that never
+    /// returns, because it always raises a trap with
+    /// [`RuntimeError::raise`], or exits the process.
+    ///
+    /// The function is called like the ones created with
+    /// [`Function::new_native`], and [`Function::is_noreturn`] tells that
+    /// it never returns. The marker is kept by the import resolution: an
+    /// instance importing the function, and exporting it again, exports a
+    /// function that never returns too. The compilers can't make use of it
+    /// yet, as modules are compiled before their imports are known.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Function, RuntimeError, Store};
+    /// # let store = Store::default();
+    ///
+    /// fn exit(code: i32) {
+    ///     RuntimeError::raise(Box::new(RuntimeError::new(format!("exit({})", code))));
+    /// }
+    ///
+    /// let f = Function::new_noreturn(&store, exit);
+    /// assert!(f.is_noreturn());
+    /// ```
+    pub fn new_noreturn<F, Args, Rets, Env>(store: &Store, func: F) -> Self
+    where
+        F: HostFunction<Args, Rets, WithoutEnv, Env>,
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+        Env: Sized + 'static,
+    {
+        let mut function = Self::new_native(store, func);
+        function.exported.noreturn = true;
+        function
+    }
+
+    /// Returns whether the `Function` is a host function that never
+    /// returns, created with [`Function::new_noreturn`], or an export of
+    /// one.
+    pub fn is_noreturn(&self) -> bool {
+        self.exported.noreturn
+    }
+
     /// Returns the [`FunctionType`] of the `Function`.
     pub fn ty(&self) -> &FunctionType {
         &self.exported.signature
@@ -299,9 +502,8 @@ impl Function {
 
         // Call the trampoline.
         if let Err(error) = unsafe {
-            wasmer_call_trampoline(
+            func.call_trampoline(
                 self.exported.vmctx,
-                func.trampoline,
                 self.exported.address,
                 values_vec.as_mut_ptr() as *mut u8,
             )
@@ -358,7 +560,10 @@ impl Function {
             .expect("Can't get call trampoline for the function");
         Self {
             store: store.clone(),
-            definition: FunctionDefinition::Wasm(WasmFunctionDefinition { trampoline }),
+            definition: FunctionDefinition::Wasm(WasmFunctionDefinition {
+                trampoline,
+                unchecked: store.engine().unchecked(),
+            }),
             exported: wasmer_export,
         }
     }
@@ -415,6 +620,7 @@ impl Function {
             self.exported.address,
             self.exported.vmctx,
             self.exported.kind,
+            self.exported.noreturn,
             self.definition.clone(),
         ))
     }
@@ -438,25 +644,89 @@ impl fmt::Debug for Function {
         formatter
             .debug_struct("Function")
             .field("ty", &self.ty())
+            .field(
+                "id",
+                &function_id(self.exported.address, self.exported.vmctx),
+            )
             .finish()
     }
 }
 
 /// This trait is one that all dynamic functions must fulfill.
 pub(crate) trait VMDynamicFunction {
-    fn call(&self, args: &[Val]) -> Result<Vec<Val>, RuntimeError>;
+    /// Calls the function, writing its results into `results`, which has
+    /// as many values as the function has results.
+    fn call(&self, args: &[Val], results: &mut [Val]) -> Result<(), RuntimeError>;
     fn function_type(&self) -> &FunctionType;
 }
 
+/// Calls the dynamic function `func`, checking the types of its results.
+///
+/// The values are kept on the stack, unless there are many.
+pub(crate) fn call_dynamic<T: VMDynamicFunction>(
+    func: &T,
+    args: &[Val],
+) -> Result<SmallVec<[Val; 8]>, RuntimeError> {
+    let result_types = func.function_type().results();
+    let mut results = result_types
+        .iter()
+        .map(|ty| zero_value(*ty))
+        .collect::<SmallVec<[Val; 8]>>();
+    func.call(args, &mut results)?;
+    if results
+        .iter()
+        .zip(result_types)
+        .any(|(result, ty)| result.ty() != *ty)
+    {
+        let expected = result_types
+            .iter()
+            .map(|ty| zero_value(*ty))
+            .collect::<Vec<_>>();
+        return Err(wrong_signature(&expected, &results));
+    }
+    Ok(results)
+}
+
+/// Moves the `returns` of a dynamic function into its `results`.
+fn move_results(returns: Vec<Val>, results: &mut [Val]) -> Result<(), RuntimeError> {
+    if returns.len() != results.len() {
+        return Err(wrong_signature(results, &returns));
+    }
+    for (result, value) in results.iter_mut().zip(returns) {
+        *result = value;
+    }
+    Ok(())
+}
+
+fn wrong_signature(expected: &[Val], returns: &[Val]) -> RuntimeError {
+    RuntimeError::new(format!(
+        "Dynamic function returned wrong signature. Expected {:?} but got {:?}",
+        expected.iter().map(Val::ty).collect::<Vec<_>>(),
+        returns.iter().map(Val::ty).collect::<Vec<_>>()
+    ))
+}
+
+/// The value a result of type `ty` is initialized to.
+fn zero_value(ty: ValType) -> Val {
+    match ty {
+        ValType::I32 => Val::I32(0),
+        ValType::I64 => Val::I64(0),
+        ValType::F32 => Val::F32(0.0),
+        ValType::F64 => Val::F64(0.0),
+        ValType::V128 => Val::V128(0),
+        ValType::ExternRef | ValType::FuncRef => Val::null(),
+    }
+}
+
 pub(crate) struct VMDynamicFunctionWithoutEnv {
     #[allow(clippy::type_complexity)]
-    func: Box<dyn Fn(&[Val]) -> Result<Vec<Val>, RuntimeError> + 'static>,
+    func: Box<dyn Fn(&[Val], &mut [Val]) -> Result<(), RuntimeError> + 'static>,
     function_type: FunctionType,
 }
 
 impl VMDynamicFunction for VMDynamicFunctionWithoutEnv {
-    fn call(&self, args: &[Val]) -> Result<Vec<Val>, RuntimeError> {
-        (*self.func)(&args)
+    fn call(&self, args: &[Val], results: &mut [Val]) -> Result<(), RuntimeError> {
+        (*self.func)(args, results)
     }
     fn function_type(&self) -> &FunctionType {
         &self.function_type
@@ -477,10 +747,11 @@ impl<Env> VMDynamicFunction for VMDynamicFunctionWithEnv<Env>
 where
     Env: Sized + 'static,
 {
-    fn call(&self, args: &[Val]) -> Result<Vec<Val>, RuntimeError> {
+    fn call(&self, args: &[Val], results: &mut [Val]) -> Result<(), RuntimeError> {
         // TODO: the `&mut *self.env.as_ptr()` is likely invoking some "mild"
         //      undefined behavior due to how it's used in the static fn call
-        unsafe { (*self.func)(&mut *self.env.as_ptr(), &args) }
+        let returns = unsafe { (*self.func)(&mut *self.env.as_ptr(), &args)? };
+        move_results(returns, results)
     }
     fn function_type(&self) -> &FunctionType {
         &self.function_type
@@ -515,27 +786,20 @@ impl<T: VMDynamicFunction> VMDynamicFunctionCall<T> for VMDynamicFunctionContext
     ) {
         use std::panic::{self, AssertUnwindSafe};
         let result = panic::catch_unwind(AssertUnwindSafe(|| {
-            let func_ty = self.ctx.function_type();
-            let mut args = Vec::with_capacity(func_ty.params().len());
-            for (i, ty) in func_ty.params().iter().enumerate() {
-                args.push(Val::read_value_from(values_vec.add(i), *ty));
-            }
-            let returns = self.ctx.call(&args)?;
-
-            // We need to dynamically check that the returns
-            // match the expected types, as well as expected length.
-            let return_types = returns.iter().map(|ret| ret.ty()).collect::<Vec<_>>();
-            if return_types != func_ty.results() {
-                return Err(RuntimeError::new(format!(
-                    "Dynamic function returned wrong signature. Expected {:?} but got {:?}",
-                    func_ty.results(),
-                    return_types
-                )));
-            }
-            for (i, ret) in returns.iter().enumerate() {
-                ret.write_value_to(values_vec.add(i));
-            }
-            Ok(())
+            with_deferred_cleanups(|| {
+                let func_ty = self.ctx.function_type();
+                let args = func_ty
+                    .params()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| Val::read_value_from(values_vec.add(i), *ty))
+                    .collect::<SmallVec<[Val; 8]>>();
+                let returns = call_dynamic(&self.ctx, &args)?;
+                for (i, ret) in returns.iter().enumerate() {
+                    ret.write_value_to(values_vec.add(i));
+                }
+                Ok::<(), RuntimeError>(())
+            })
         }));
 
         match result {
@@ -549,13 +813,22 @@ impl<T: VMDynamicFunction> VMDynamicFunctionCall<T> for VMDynamicFunctionContext
 /// This private inner module contains the low-level implementation
 /// for `Function` and its siblings.
 mod inner {
+    use super::function_id;
+    use crate::{RuntimeError, Val};
     use std::array::TryFromSliceError;
     use std::convert::{Infallible, TryInto};
     use std::error::Error;
+    use std::ffi::c_void;
+    use std::fmt;
     use std::marker::PhantomData;
+    use std::mem;
     use std::panic::{self, AssertUnwindSafe};
-    use wasmer_types::{FunctionType, NativeWasmType, Type};
-    use wasmer_vm::{raise_user_trap, resume_panic, VMFunctionBody};
+    use std::ptr;
+    use wasmer_types::{FunctionType, NativeWasmType, Type, V128};
+    use wasmer_vm::{
+        catch_traps_with_result, raise_user_trap, resume_panic, with_deferred_cleanups,
+        VMFunctionBody,
+    };
 
     /// A trait to convert a Rust value to a `WasmNativeType` value,
     /// or to convert `WasmNativeType` value to a Rust value.
@@ -646,6 +919,20 @@ mod inner {
         f64 => f64
     );
 
+    unsafe impl FromToNativeWasmType for V128 {
+        type Native = u128;
+
+        #[inline]
+        fn from_native(native: Self::Native) -> Self {
+            native.into()
+        }
+
+        #[inline]
+        fn to_native(self) -> Self::Native {
+            self.into()
+        }
+    }
+
     #[cfg(test)]
     mod test_from_to_native_wasm_type {
         use super::*;
@@ -668,6 +955,10 @@ mod inner {
         /// represented values.
         type CStruct;
 
+        /// The number of represented values, i.e. the length of
+        /// `Array`.
+        const LEN: usize;
+
         /// The array type that can hold all the represented values.
         ///
         /// Note that all values are stored in their binary form.
@@ -704,6 +995,14 @@ mod inner {
         /// Get the Wasm types for the tuple (list) of currently
         /// represented values.
         fn wasm_types() -> &'static [Type];
+
+        /// Get the Wasm types for the tuple (list) of currently
+        /// represented values, as an owned `Vec`.
+        ///
+        /// This is what a [`FunctionType`] is built from.
+        fn flatten_types() -> Vec<Type> {
+            Self::wasm_types().to_vec()
+        }
     }
 
     /// The `IntoResult` trait turns a `WasmTypeList` into a
@@ -814,31 +1113,55 @@ mod inner {
     /// the trait system to automatically generate the appropriate
     /// host functions.
     #[doc(hidden)]
-    pub trait HostFunctionKind {}
+    pub trait HostFunctionKind {
+        /// Whether the host function takes an environment.
+        const HAS_ENV: bool;
+    }
 
     /// An empty struct to help Rust typing to determine
     /// when a `HostFunction` does have an environment.
     pub struct WithEnv;
 
-    impl HostFunctionKind for WithEnv {}
+    impl HostFunctionKind for WithEnv {
+        const HAS_ENV: bool = true;
+    }
 
     /// An empty struct to help Rust typing to determine
     /// when a `HostFunction` does not have an environment.
     pub struct WithoutEnv;
 
-    impl HostFunctionKind for WithoutEnv {}
+    impl HostFunctionKind for WithoutEnv {
+        const HAS_ENV: bool = false;
+    }
 
     /// Represents a low-level Wasm static host function. See
     /// `super::Function::new` and `super::Function::new_env` to learn
     /// more.
-    #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+    ///
+    /// Two `Function`s are equal if they wrap the same Rust function.
+    #[derive(Clone, Hash, PartialEq, Eq)]
     pub struct Function<Args = (), Rets = ()> {
         address: *const VMFunctionBody,
+        has_env: bool,
         _phantom: PhantomData<(Args, Rets)>,
     }
 
     unsafe impl<Args, Rets> Send for Function<Args, Rets> {}
 
+    impl<Args, Rets> fmt::Debug for Function<Args, Rets>
+    where
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+    {
+        fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter
+                .debug_struct("Function")
+                .field("ty", &self.ty())
+                .field("id", &function_id(self.address, ptr::null()))
+                .finish()
+        }
+    }
+
     impl<Args, Rets> Function<Args, Rets>
     where
         Args: WasmTypeList,
@@ -853,19 +1176,101 @@ mod inner {
         {
             Self {
                 address: function.function_body_ptr(),
+                has_env: T::HAS_ENV,
                 _phantom: PhantomData,
             }
         }
 
         /// Get the function type of this `Function`.
         pub fn ty(&self) -> FunctionType {
-            FunctionType::new(Args::wasm_types(), Rets::wasm_types())
+            FunctionType::new(Args::flatten_types(), Rets::flatten_types())
         }
 
         /// Get the address of this `Function`.
         pub fn address(&self) -> *const VMFunctionBody {
             self.address
         }
+
+        /// Checks the arguments and the results of `call_checked`.
+        fn check_call(
+            &self,
+            func_type: &FunctionType,
+            args: &[Val],
+            results: &[Val],
+        ) -> Result<(), CallError> {
+            let ty = self.ty();
+            if *func_type != ty {
+                return Err(CallError::SignatureMismatch {
+                    expected: ty,
+                    got: func_type.clone(),
+                });
+            }
+            if args.len() != func_type.params().len() {
+                return Err(CallError::ArityMismatch {
+                    expected: func_type.params().len(),
+                    got: args.len(),
+                });
+            }
+            for (index, (arg, &expected)) in args.iter().zip(func_type.params()).enumerate() {
+                if arg.ty() != expected {
+                    return Err(CallError::TypeMismatch {
+                        index,
+                        expected,
+                        got: arg.ty(),
+                    });
+                }
+            }
+            if results.len() != func_type.results().len() {
+                return Err(CallError::ResultArityMismatch {
+                    expected: func_type.results().len(),
+                    got: results.len(),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    /// An error calling a `Function` with `call_checked`.
+    #[derive(thiserror::Error, Debug)]
+    pub enum CallError {
+        /// The function type doesn't match the type of the function.
+        #[error("the function has the type {expected:?}, not {got:?}")]
+        SignatureMismatch {
+            /// The type of the function.
+            expected: FunctionType,
+            /// The given function type.
+            got: FunctionType,
+        },
+        /// The number of arguments doesn't match the parameters.
+        #[error("expected {expected} arguments, got {got}")]
+        ArityMismatch {
+            /// The number of parameters.
+            expected: usize,
+            /// The number of arguments.
+            got: usize,
+        },
+        /// The type of an argument doesn't match its parameter.
+        #[error("the argument {index} must be a {expected}, but a {got} was given")]
+        TypeMismatch {
+            /// The position of the argument.
+            index: usize,
+            /// The type of the parameter.
+            expected: Type,
+            /// The type of the argument.
+            got: Type,
+        },
+        /// The number of results doesn't match the results of the
+        /// function.
+        #[error("expected room for {expected} results, got {got}")]
+        ResultArityMismatch {
+            /// The number of results of the function.
+            expected: usize,
+            /// The number of given results.
+            got: usize,
+        },
+        /// The function returned an error.
+        #[error(transparent)]
+        Runtime(#[from] RuntimeError),
     }
 
     macro_rules! impl_host_function {
@@ -891,6 +1296,8 @@ mod inner {
             {
                 type CStruct = $c_struct_name< $( $x ),* >;
 
+                const LEN: usize = count_idents!( $( $x ),* );
+
                 type Array = [i128; count_idents!( $( $x ),* )];
 
                 fn from_array(array: Self::Array) -> Self {
@@ -962,6 +1369,118 @@ mod inner {
                 }
             }
 
+            // Implement `call` for a function that has the same arity than the tuple.
+            #[allow(unused_parens)]
+            impl< $( $x, )* Rets >
+                Function<( $( $x ),* ), Rets>
+            where
+                $( $x: FromToNativeWasmType, )*
+                Rets: WasmTypeList,
+            {
+                /// Calls the host function, returning its results, or the
+                /// error it returned.
+                ///
+                /// # Panics
+                ///
+                /// Panics if the function takes an environment, see
+                /// `call_with_env`.
+                #[allow(non_snake_case)]
+                pub fn call(&self, $( $x: $x, )* ) -> Result<Rets, RuntimeError> {
+                    assert!(
+                        !self.has_env,
+                        "the function takes an environment, call it with `call_with_env`"
+                    );
+                    // The placeholder of the environment is ignored.
+                    unsafe { self.call_raw(ptr::null_mut(), $( $x, )* ) }
+                }
+
+                /// Calls the host function with the arguments in their
+                /// binary representation, writing its results in `rets`.
+                ///
+                /// It returns an error instead of panicking if the
+                /// function takes an environment.
+                #[allow(non_snake_case)]
+                pub fn call_array(
+                    &self,
+                    args: <( $( $x ),* ) as WasmTypeList>::Array,
+                    rets: &mut Rets::Array,
+                ) -> Result<(), RuntimeError> {
+                    if self.has_env {
+                        return Err(RuntimeError::new(
+                            "the function takes an environment, call it with `call_with_env`",
+                        ));
+                    }
+                    let ( $( $x ),* ) = <( $( $x ),* ) as WasmTypeList>::from_array(args);
+                    *rets = self.call( $( $x ),* )?.into_array();
+                    Ok(())
+                }
+
+                /// Calls the host function with untyped values, checking
+                /// them against `func_type` first, and writes its results
+                /// in `results`.
+                ///
+                /// Unlike `call`, it returns an error if the values don't
+                /// match the type of the function.
+                #[allow(non_snake_case)]
+                pub fn call_checked(
+                    &self,
+                    func_type: &FunctionType,
+                    args: &[Val],
+                    results: &mut [Val],
+                ) -> Result<(), CallError> {
+                    self.check_call(func_type, args, results)?;
+                    let mut args_array = <( $( $x ),* ) as WasmTypeList>::empty_array();
+                    for (slot, arg) in args_array.as_mut().iter_mut().zip(args) {
+                        // The types of the arguments have been checked.
+                        unsafe { arg.write_value_to(slot) };
+                    }
+                    let mut rets_array = Rets::empty_array();
+                    self.call_array(args_array, &mut rets_array)?;
+                    for ((result, slot), &ty) in results
+                        .iter_mut()
+                        .zip(rets_array.as_mut().iter())
+                        .zip(func_type.results())
+                    {
+                        *result = unsafe { Val::read_value_from(slot, ty) };
+                    }
+                    Ok(())
+                }
+
+                /// Calls the host function with the environment `env`,
+                /// returning its results, or the error it returned.
+                ///
+                /// # Panics
+                ///
+                /// Panics if the function doesn't take an environment,
+                /// see `call`.
+                ///
+                /// # Safety
+                ///
+                /// `Env` must be the type of the environment the function
+                /// takes.
+                #[allow(non_snake_case)]
+                pub unsafe fn call_with_env<Env>(&self, env: &mut Env, $( $x: $x, )* ) -> Result<Rets, RuntimeError> {
+                    assert!(
+                        self.has_env,
+                        "the function doesn't take an environment, call it with `call`"
+                    );
+                    self.call_raw(env as *mut Env as *mut c_void, $( $x, )* )
+                }
+
+                /// Calls the function body with `env` as its first
+                /// argument, catching the errors raised by the host
+                /// function.
+                #[allow(non_snake_case)]
+                unsafe fn call_raw(&self, env: *mut c_void, $( $x: $x, )* ) -> Result<Rets, RuntimeError> {
+                    let function = mem::transmute::<_, unsafe extern "C" fn(*mut c_void, $( $x::Native, )* ) -> Rets::CStruct>(self.address);
+                    let results = catch_traps_with_result(ptr::null_mut(), || {
+                        function(env, $( $x.to_native(), )* )
+                    })
+                    .map_err(RuntimeError::from_trap)?;
+                    Ok(Rets::from_c_struct(results))
+                }
+            }
+
             // Implement `HostFunction` for a function that has the same arity than the tuple.
             // This specific function has no environment.
             #[allow(unused_parens)]
@@ -989,7 +1508,9 @@ mod inner {
                     {
                         let func: &Func = unsafe { &*(&() as *const () as *const Func) };
                         let result = panic::catch_unwind(AssertUnwindSafe(|| {
-                            func( $( FromToNativeWasmType::from_native($x) ),* ).into_result()
+                            with_deferred_cleanups(|| {
+                                func( $( FromToNativeWasmType::from_native($x) ),* ).into_result()
+                            })
                         }));
 
                         match result {
@@ -1033,7 +1554,9 @@ mod inner {
                         let func: &Func = unsafe { &*(&() as *const () as *const Func) };
 
                         let result = panic::catch_unwind(AssertUnwindSafe(|| {
-                            func(env, $( FromToNativeWasmType::from_native($x) ),* ).into_result()
+                            with_deferred_cleanups(|| {
+                                func(env, $( FromToNativeWasmType::from_native($x) ),* ).into_result()
+                            })
                         }));
 
                         match result {
@@ -1098,6 +1621,7 @@ mod inner {
     // fail (with `Result<_, Infallible>`).
     impl WasmTypeList for Infallible {
         type CStruct = Self;
+        const LEN: usize = 0;
         type Array = [i128; 0];
 
         fn from_array(_: Self::Array) -> Self {
@@ -1161,6 +1685,42 @@ mod inner {
             );
         }
 
+        #[test]
+        fn test_array_round_trip() {
+            // Tuples with more than 12 items are neither `PartialEq` nor
+            // `Debug`, so the round trip is checked on their binary form.
+            fn round_trip<T>(t: T)
+            where
+                T: WasmTypeList + Copy,
+                T::Array: PartialEq + std::fmt::Debug,
+            {
+                assert_eq!(T::from_array(t.into_array()).into_array(), t.into_array());
+            }
+
+            round_trip(());
+            round_trip(-1i32);
+            round_trip((i32::MIN, i64::MIN, -3.1f32, -4.2f64));
+            round_trip((u32::MAX, u64::MAX, i8::MIN, u8::MAX, i16::MIN, u16::MAX));
+            round_trip((f32::INFINITY, f64::NEG_INFINITY, -0.0f32, -0.0f64));
+            round_trip((
+                1i32, 2i64, 3u32, 4u64, 5.5f32, 6.5f64, 7i32, 8i64, 9u32, 10u64, 11.5f32, 12.5f64,
+                13i32, 14i64, 15u32, 16u64, 17.5f32, 18.5f64, 19i32, 20i64, 21u32, 22u64, 23.5f32,
+                24.5f64, -25i32, -26i64,
+            ));
+
+            // NaN payloads are kept, as floats are stored as their bits.
+            let nan32 = f32::from_bits(0x7fa0_0001);
+            let nan64 = f64::from_bits(0xfff4_0000_0000_0001);
+            let (a, b) = <(f32, f64)>::from_array((nan32, nan64).into_array());
+            assert_eq!(a.to_bits(), nan32.to_bits());
+            assert_eq!(b.to_bits(), nan64.to_bits());
+
+            assert_eq!(
+                <(i32, i64, f32, f64)>::from_array((-1i32, -2i64, 3.1f32, 4.2f64).into_array()),
+                (-1i32, -2i64, 3.1f32, 4.2f64)
+            );
+        }
+
         #[test]
         fn test_empty_array() {
             assert_eq!(<()>::empty_array().len(), 0);
@@ -1199,6 +1759,34 @@ mod inner {
                 [Type::I32, Type::I64, Type::F32, Type::F64]
             );
         }
+
+        #[test]
+        fn test_len() {
+            assert_eq!(<()>::LEN, 0);
+            assert_eq!(<i32>::LEN, 1);
+            assert_eq!(<(i32, i64, f32)>::LEN, 3);
+            assert_eq!(<Infallible>::LEN, 0);
+
+            // `LEN` can size buffers at compile time.
+            let buffer = [0i128; <(i32, i64, f32)>::LEN];
+            assert_eq!(buffer.len(), <(i32, i64, f32)>::empty_array().len());
+        }
+
+        #[test]
+        fn test_flatten_types() {
+            assert_eq!(<()>::flatten_types(), <()>::wasm_types());
+            assert_eq!(<i32>::flatten_types(), <i32>::wasm_types());
+            assert_eq!(<f64>::flatten_types(), <f64>::wasm_types());
+            assert_eq!(<(i32, i64)>::flatten_types(), <(i32, i64)>::wasm_types());
+            assert_eq!(
+                <(i32, i64, f32, f64)>::flatten_types(),
+                <(i32, i64, f32, f64)>::wasm_types()
+            );
+            assert_eq!(
+                <(f32, f32, f32)>::flatten_types(),
+                vec![Type::F32, Type::F32, Type::F32]
+            );
+        }
     }
 
     #[allow(non_snake_case)]
@@ -1260,5 +1848,208 @@ mod inner {
             let function = unsafe { std::mem::transmute::<_, fn(usize, i32) -> i32>(f.address) };
             assert_eq!(function(0, 3), 6);
         }
+
+        #[allow(clippy::too_many_arguments)]
+        fn func_10_args(
+            a: i32,
+            b: i64,
+            c: f32,
+            d: f64,
+            e: i32,
+            f: i64,
+            g: f32,
+            h: f64,
+            i: i32,
+            j: i64,
+        ) -> f64 {
+            f64::from(a) * 1e0
+                + b as f64 * 1e1
+                + f64::from(c) * 1e2
+                + d * 1e3
+                + f64::from(e) * 1e4
+                + f as f64 * 1e5
+                + f64::from(g) * 1e6
+                + h * 1e7
+                + f64::from(i) * 1e8
+                + j as f64 * 1e9
+        }
+
+        #[test]
+        fn test_function_v128() {
+            fn func_v128_v128__v128(a: V128, b: V128) -> V128 {
+                V128::from(u128::from(a) ^ u128::from(b))
+            }
+            let function = Function::new(func_v128_v128__v128);
+            assert_eq!(
+                function.ty().clone(),
+                FunctionType::new(vec![Type::V128, Type::V128], vec![Type::V128])
+            );
+            let result = function
+                .call(V128::from(0b1100u128), V128::from(0b1010u128))
+                .unwrap();
+            assert_eq!(u128::from(result), 0b0110);
+        }
+
+        #[test]
+        fn test_function_10_args() {
+            let f = Function::new(func_10_args);
+            assert_eq!(
+                f.ty().params(),
+                [
+                    Type::I32,
+                    Type::I64,
+                    Type::F32,
+                    Type::F64,
+                    Type::I32,
+                    Type::I64,
+                    Type::F32,
+                    Type::F64,
+                    Type::I32,
+                    Type::I64,
+                ]
+            );
+            assert_eq!(f.ty().results(), [Type::F64]);
+
+            let function = unsafe {
+                std::mem::transmute::<
+                    _,
+                    fn(usize, i32, i64, f32, f64, i32, i64, f32, f64, i32, i64) -> f64,
+                >(f.address)
+            };
+            let expected = 9_876_543_210.0;
+            assert_eq!(function(0, 0, 1, 2.0, 3.0, 4, 5, 6.0, 7.0, 8, 9), expected);
+            assert_eq!(
+                f.call(0, 1, 2.0, 3.0, 4, 5, 6.0, 7.0, 8, 9).unwrap(),
+                expected
+            );
+        }
+
+        #[test]
+        fn test_function_debug() {
+            let f = Function::new(func_i32__i32);
+            let debug = format!("{:?}", f);
+            assert!(debug.contains("ty: FunctionType"), "{}", debug);
+            assert!(!debug.contains("0x"), "{}", debug);
+            assert_eq!(format!("{:?}", Function::new(func_i32__i32)), debug);
+            assert_eq!(Function::new(func_i32__i32), f);
+        }
+
+        #[test]
+        fn test_function_call() {
+            assert_eq!(Function::new(func__i32).call().unwrap(), 0);
+            assert_eq!(Function::new(func_i32_i32__i32).call(1, 2).unwrap(), 3);
+            assert_eq!(
+                Function::new(func_f32_i32__i32_f32).call(1.5, 2).unwrap(),
+                (2, 1.5)
+            );
+        }
+
+        #[test]
+        fn test_function_call_array() {
+            let mut rets = [0];
+            Function::new(func_i32__i32)
+                .call_array([21], &mut rets)
+                .unwrap();
+            assert_eq!(rets, [42]);
+
+            let mut rets = [0; 2];
+            Function::new(func_f32_i32__i32_f32)
+                .call_array([1.5f32.to_binary(), 2], &mut rets)
+                .unwrap();
+            assert_eq!(rets, [2, 1.5f32.to_binary()]);
+
+            let mut rets = [];
+            Function::new(func).call_array([], &mut rets).unwrap();
+        }
+
+        #[test]
+        fn test_function_call_array_with_env() {
+            fn func_env(_: &mut i32) {}
+            let error = Function::new(func_env).call_array([], &mut []).unwrap_err();
+            assert_eq!(
+                error.message(),
+                "the function takes an environment, call it with `call_with_env`"
+            );
+        }
+
+        #[test]
+        fn test_function_call_checked() {
+            let function = Function::new(func_f32_i32__i32_f32);
+            let ty = function.ty();
+            let mut results = [Val::I32(0), Val::F32(0.0)];
+            function
+                .call_checked(&ty, &[Val::F32(1.5), Val::I32(2)], &mut results)
+                .unwrap();
+            assert_eq!(results, [Val::I32(2), Val::F32(1.5)]);
+
+            let error = |args: &[Val], results: &mut [Val]| {
+                function
+                    .call_checked(&ty, args, results)
+                    .unwrap_err()
+                    .to_string()
+            };
+            assert_eq!(
+                error(&[Val::F32(1.5)], &mut results),
+                "expected 2 arguments, got 1"
+            );
+            assert_eq!(
+                error(&[Val::F32(1.5), Val::I32(2), Val::I32(3)], &mut results),
+                "expected 2 arguments, got 3"
+            );
+            assert_eq!(
+                error(&[Val::I32(1), Val::I32(2)], &mut results),
+                "the argument 0 must be a F32, but a I32 was given"
+            );
+            assert_eq!(
+                error(&[Val::F32(1.5), Val::I64(2)], &mut results),
+                "the argument 1 must be a I32, but a I64 was given"
+            );
+            assert_eq!(
+                error(&[Val::F32(1.5), Val::I32(2)], &mut results[..1]),
+                "expected room for 2 results, got 1"
+            );
+
+            let other_ty = FunctionType::new(vec![Type::I32], vec![]);
+            assert!(matches!(
+                function.call_checked(&other_ty, &[Val::I32(1)], &mut []),
+                Err(CallError::SignatureMismatch { .. })
+            ));
+        }
+
+        #[test]
+        fn test_function_call_with_env() {
+            fn func_env_i32__i32(env: &mut i32, a: i32) -> i32 {
+                *env += a;
+                *env
+            }
+
+            let f = Function::new(func_env_i32__i32);
+            let mut env = 10;
+            assert_eq!(unsafe { f.call_with_env(&mut env, 5) }.unwrap(), 15);
+            assert_eq!(env, 15);
+        }
+
+        #[test]
+        fn test_function_call_error() {
+            fn func_i32__result_i32(a: i32) -> Result<i32, RuntimeError> {
+                if a < 0 {
+                    Err(RuntimeError::new("negative"))
+                } else {
+                    Ok(a)
+                }
+            }
+
+            let f = Function::new(func_i32__result_i32);
+            assert_eq!(f.call(7).unwrap(), 7);
+            assert_eq!(f.call(-1).unwrap_err().message(), "negative");
+        }
+
+        #[test]
+        #[should_panic]
+        fn test_function_call_missing_env() {
+            fn func_env(_env: &mut i32) {}
+
+            let _ = Function::new(func_env).call();
+        }
     }
 }
@@ -6,7 +6,7 @@ use std::convert::TryInto;
 use std::slice;
 use std::sync::Arc;
 use wasmer_types::{Pages, ValueType};
-use wasmer_vm::{Export, ExportMemory, Memory as RuntimeMemory, MemoryError};
+use wasmer_vm::{Export, ExportMemory, LinearMemory, Memory as RuntimeMemory, MemoryError};
 
 /// A WebAssembly `memory` instance.
 ///
@@ -45,6 +45,48 @@ impl Memory {
         })
     }
 
+    /// Creates a new host `Memory` from the provided [`MemoryType`], whose
+    /// pages can also be mapped read-only, to share them with instances
+    /// through [`Memory::new_readonly_view`].
+    ///
+    /// Shareable memories are only supported on Linux.
+    pub fn new_shareable(store: &Store, ty: MemoryType) -> Result<Self, MemoryError> {
+        let style = store.tunables().memory_style(&ty);
+        let memory = LinearMemory::new_shareable(&ty, &style)?;
+
+        Ok(Self {
+            store: store.clone(),
+            memory: Arc::new(memory),
+        })
+    }
+
+    /// Creates a read-only view of the current pages of `memory`, which
+    /// must have been created with [`Memory::new_shareable`].
+    ///
+    /// Instances importing the view see the writes to `memory`, but their
+    /// stores to the view trap with [`TrapCode::ReadOnlyMemory`], and
+    /// growing the view always fails (`memory.grow` returns `-1`). The view
+    /// doesn't see the pages `memory` grows by after it's created. The host
+    /// must not write through the view either: writing to it faults.
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store};
+    /// # let store = Store::default();
+    /// let memory = Memory::new_shareable(&store, MemoryType::new(Pages(1), None, false)).unwrap();
+    /// let view = Memory::new_readonly_view(&memory).unwrap();
+    /// unsafe { memory.data_unchecked_mut()[0] = 42 };
+    /// assert_eq!(view.view::<u8>()[0].get(), 42);
+    /// assert!(view.grow(Pages(1)).is_err());
+    /// ```
+    ///
+    /// [`TrapCode::ReadOnlyMemory`]: crate::TrapCode::ReadOnlyMemory
+    pub fn new_readonly_view(memory: &Self) -> Result<Self, MemoryError> {
+        Ok(Self {
+            store: memory.store.clone(),
+            memory: Arc::from(memory.memory.readonly_view()?),
+        })
+    }
+
     /// Returns the [`MemoryType`] of the `Memory`.
     pub fn ty(&self) -> &MemoryType {
         self.memory.ty()
@@ -109,6 +151,18 @@ impl Memory {
         self.memory.grow(delta.into())
     }
 
+    /// Returns the indices of the WebAssembly pages written to since the
+    /// last call (or since the memory was created), and resets them to
+    /// clean.
+    ///
+    /// Returns `None` if the memory doesn't track its dirty pages, see
+    /// [`Tunables::track_dirty_pages`].
+    ///
+    /// [`Tunables::track_dirty_pages`]: crate::tunables::Tunables::track_dirty_pages
+    pub fn take_dirty_pages(&self) -> Option<Vec<u32>> {
+        self.memory.take_dirty_pages()
+    }
+
     /// Return a "view" of the currently accessible memory. By
     /// default, the view is unsynchronized, using regular memory
     /// accesses. You can force a memory view to use atomic accesses
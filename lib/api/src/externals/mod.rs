@@ -4,7 +4,8 @@ mod memory;
 mod table;
 
 pub use self::function::{
-    FromToNativeWasmType, Function, HostFunction, WasmTypeList, WithEnv, WithoutEnv,
+    CallError, FromToNativeWasmType, Function, HostFunction, StaticFunction, WasmTypeList, WithEnv,
+    WithoutEnv,
 };
 pub use self::global::Global;
 pub use self::memory::Memory;
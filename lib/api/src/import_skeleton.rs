@@ -0,0 +1,271 @@
+//! Skeletons of the imports required by a module, to start writing the
+//! host side of a module from.
+use crate::types::{
+    ExternType, FunctionType, GlobalType, ImportType, Mutability, TableType, ValType,
+};
+use indexmap::IndexMap;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// The imports required by a module, grouped by namespace, as returned by
+/// `Module::generate_import_skeleton`.
+///
+/// Besides listing the imports with their types, the skeleton can be
+/// turned into the Rust source of an `ImportObject` providing placeholders
+/// for all of them with `to_rust_source`.
+#[derive(Debug, Clone)]
+pub struct ImportSkeleton {
+    namespaces: IndexMap<String, Vec<ImportType>>,
+}
+
+impl ImportSkeleton {
+    /// Groups `imports` by namespace, keeping the order of the module.
+    pub(crate) fn new(imports: impl Iterator<Item = ImportType>) -> Self {
+        let mut namespaces = IndexMap::new();
+        for import in imports {
+            namespaces
+                .entry(import.module().to_string())
+                .or_insert_with(Vec::new)
+                .push(import);
+        }
+        Self { namespaces }
+    }
+
+    /// The namespaces of the imports, with the imports of each of them.
+    pub fn namespaces(&self) -> impl Iterator<Item = (&str, &[ImportType])> {
+        self.namespaces
+            .iter()
+            .map(|(namespace, imports)| (namespace.as_str(), imports.as_slice()))
+    }
+
+    /// The imports of the namespace `namespace`, if the module imports
+    /// anything from it.
+    pub fn get(&self, namespace: &str) -> Option<&[ImportType]> {
+        self.namespaces.get(namespace).map(Vec::as_slice)
+    }
+
+    /// Generates the Rust source of an `import_object` function building
+    /// an `ImportObject` with placeholders for all the imports, to be
+    /// pasted in an embedder using `wasmer::*`.
+    ///
+    /// Every imported function gets a host function with the same
+    /// signature whose body is a `todo!()`. Memories, tables and globals
+    /// are created with the imported types, and globals are initialized to
+    /// zero.
+    ///
+    /// The host functions are named after their namespace and name. Names
+    /// that aren't valid Rust identifiers, or that collide with the name of
+    /// another import, are sanitized, and the original name is noted in a
+    /// comment.
+    pub fn to_rust_source(&self) -> String {
+        let mut functions = Vec::new();
+        let mut namespaces = String::new();
+        let mut identifiers = HashSet::new();
+        for (namespace, imports) in self.namespaces() {
+            writeln!(namespaces, "        {:?} => {{", namespace).unwrap();
+            for import in imports {
+                let value = match import.ty() {
+                    ExternType::Function(ty) => {
+                        let (identifier, sanitized) =
+                            identifier(namespace, import.name(), &mut identifiers);
+                        let mut function = String::new();
+                        if sanitized {
+                            writeln!(
+                                function,
+                                "    // Imported as {:?} {:?}.",
+                                namespace,
+                                import.name()
+                            )
+                            .unwrap();
+                        }
+                        let todo = todo(&format!("{}.{}", namespace, import.name()));
+                        let value = host_function(&mut function, &identifier, ty, &todo);
+                        functions.push(function);
+                        value
+                    }
+                    ExternType::Memory(ty) => format!(
+                        "{}(store, MemoryType::new(Pages({}), {}, {})).unwrap()",
+                        if ty.shared {
+                            "Memory::new_shareable"
+                        } else {
+                            "Memory::new"
+                        },
+                        ty.minimum.0,
+                        option(ty.maximum.map(|pages| format!("Pages({})", pages.0))),
+                        ty.shared
+                    ),
+                    ExternType::Table(TableType {
+                        ty,
+                        minimum,
+                        maximum,
+                    }) => format!(
+                        "Table::new(store, TableType::new({}, {}, {}), Value::ExternRef(ExternRef::Null)).unwrap()",
+                        type_expr(*ty),
+                        minimum,
+                        option(maximum.map(|maximum| maximum.to_string()))
+                    ),
+                    ExternType::Global(GlobalType { ty, mutability }) => format!(
+                        "{}(store, {})",
+                        match mutability {
+                            Mutability::Const => "Global::new",
+                            Mutability::Var => "Global::new_mut",
+                        },
+                        zero_value(*ty)
+                    ),
+                };
+                writeln!(namespaces, "            {:?} => {},", import.name(), value).unwrap();
+            }
+            writeln!(namespaces, "        }},").unwrap();
+        }
+
+        let mut source = String::from("fn import_object(store: &Store) -> ImportObject {\n");
+        for function in functions {
+            source.push_str(&function);
+            source.push('\n');
+        }
+        source.push_str("    imports! {\n");
+        source.push_str(&namespaces);
+        source.push_str("    }\n}\n");
+        source
+    }
+}
+
+/// Writes the host function `identifier` of an imported function of type
+/// `ty`, returning the expression creating the `Function`.
+///
+/// Native functions are used, unless the signature has types they don't
+/// support.
+fn host_function(source: &mut String, identifier: &str, ty: &FunctionType, body: &str) -> String {
+    let native_types = |types: &[ValType]| -> Option<Vec<&str>> {
+        types.iter().map(|ty| native_type(*ty)).collect()
+    };
+    match (native_types(ty.params()), native_types(ty.results())) {
+        (Some(params), Some(results)) => {
+            let params = params
+                .iter()
+                .map(|ty| format!("_: {}", ty))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let results = match results.as_slice() {
+                [] => String::new(),
+                [result] => format!(" -> {}", result),
+                results => format!(" -> ({})", results.join(", ")),
+            };
+            writeln!(
+                source,
+                "    fn {}({}){} {{\n        {}\n    }}",
+                identifier, params, results, body
+            )
+            .unwrap();
+            format!("Function::new_native(store, {})", identifier)
+        }
+        _ => {
+            writeln!(
+                source,
+                "    fn {}(_: &[Val]) -> Result<Vec<Val>, RuntimeError> {{\n        {}\n    }}",
+                identifier, body
+            )
+            .unwrap();
+            format!(
+                "Function::new(store, &FunctionType::new(vec![{}], vec![{}]), {})",
+                type_list(ty.params()),
+                type_list(ty.results()),
+                identifier
+            )
+        }
+    }
+}
+
+/// A `todo!()` panicking with `message`.
+fn todo(message: &str) -> String {
+    // Braces would be taken as format arguments.
+    let message = format!("{:?}", message)
+        .replace('{', "{{")
+        .replace('}', "}}");
+    format!("todo!({})", message)
+}
+
+/// Returns the identifier of the host function of an import, and whether
+/// it had to be sanitized.
+fn identifier(namespace: &str, name: &str, identifiers: &mut HashSet<String>) -> (String, bool) {
+    let original = format!("{}_{}", namespace, name);
+    // Identifiers are kept in snake case to avoid warnings in the
+    // generated source.
+    let mut identifier = String::new();
+    for c in original.chars() {
+        if c.is_ascii_alphanumeric() {
+            identifier.push(c.to_ascii_lowercase());
+        } else if !identifier.ends_with('_') {
+            identifier.push('_');
+        }
+    }
+    let mut identifier = identifier.trim_end_matches('_').to_string();
+    if identifier.is_empty() {
+        identifier.push_str("import");
+    } else if identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    let mut sanitized = identifier != original;
+    if identifiers.contains(&identifier) {
+        let mut suffix = 2;
+        while identifiers.contains(&format!("{}_{}", identifier, suffix)) {
+            suffix += 1;
+        }
+        identifier = format!("{}_{}", identifier, suffix);
+        sanitized = true;
+    }
+    identifiers.insert(identifier.clone());
+    (identifier, sanitized)
+}
+
+/// The Rust type of the values of type `ty` in native host functions.
+fn native_type(ty: ValType) -> Option<&'static str> {
+    match ty {
+        ValType::I32 => Some("i32"),
+        ValType::I64 => Some("i64"),
+        ValType::F32 => Some("f32"),
+        ValType::F64 => Some("f64"),
+        ValType::V128 | ValType::ExternRef | ValType::FuncRef => None,
+    }
+}
+
+fn type_expr(ty: ValType) -> &'static str {
+    match ty {
+        ValType::I32 => "Type::I32",
+        ValType::I64 => "Type::I64",
+        ValType::F32 => "Type::F32",
+        ValType::F64 => "Type::F64",
+        ValType::V128 => "Type::V128",
+        ValType::ExternRef => "Type::ExternRef",
+        ValType::FuncRef => "Type::FuncRef",
+    }
+}
+
+fn type_list(types: &[ValType]) -> String {
+    types
+        .iter()
+        .map(|ty| type_expr(*ty))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The initial value of a placeholder global of type `ty`.
+fn zero_value(ty: ValType) -> &'static str {
+    match ty {
+        ValType::I32 => "Value::I32(0)",
+        ValType::I64 => "Value::I64(0)",
+        ValType::F32 => "Value::F32(0.0)",
+        ValType::F64 => "Value::F64(0.0)",
+        ValType::V128 => "Value::V128(0)",
+        ValType::ExternRef => "Value::ExternRef(ExternRef::Null)",
+        // There's no null function to initialize the global with.
+        ValType::FuncRef => "todo!(\"a function\")",
+    }
+}
+
+fn option(value: Option<String>) -> String {
+    match value {
+        Some(value) => format!("Some({})", value),
+        None => "None".to_string(),
+    }
+}
@@ -1,10 +1,11 @@
-use crate::exports::Exports;
+use crate::exports::{Exportable, Exports};
 use crate::externals::Extern;
 use crate::module::Module;
 use crate::store::Store;
 use crate::InstantiationError;
 use std::fmt;
-use wasmer_engine::Resolver;
+use wasmer_engine::{InstantiationHooks, Resolver};
+use wasmer_types::ExportIndex;
 use wasmer_vm::{InstanceHandle, VMContext};
 
 /// A WebAssembly Instance is a stateful, executable
@@ -71,25 +72,37 @@ impl Instance {
     ///  * Link errors that happen when plugging the imports into the instance
     ///  * Runtime errors that happen when running the module `start` function.
     pub fn new(module: &Module, resolver: &dyn Resolver) -> Result<Self, InstantiationError> {
-        let store = module.store();
-
         let handle = module.instantiate(resolver)?;
+        Ok(Self::from_handle(module, handle))
+    }
 
-        let exports = module
-            .exports()
-            .map(|export| {
-                let name = export.name().to_string();
-                let export = handle.lookup(&name).expect("export");
-                let extern_ = Extern::from_export(store, export);
-                (name, extern_)
-            })
-            .collect::<Exports>();
+    /// Creates a new `Instance` like [`Instance::new`], calling `hooks`
+    /// in between the steps of the instantiation.
+    ///
+    /// This lets the host set up the instance before its start function
+    /// runs, for example to write into its memory before the data
+    /// segments are applied.
+    ///
+    /// ## Errors
+    ///
+    /// The same as [`Instance::new`], and the errors of the hooks, reported
+    /// as [`InstantiationError::Start`].
+    pub fn new_with_hooks(
+        module: &Module,
+        resolver: &dyn Resolver,
+        hooks: &dyn InstantiationHooks,
+    ) -> Result<Self, InstantiationError> {
+        let handle = module.instantiate_with_hooks(resolver, hooks)?;
+        Ok(Self::from_handle(module, handle))
+    }
 
-        Ok(Self {
+    fn from_handle(module: &Module, handle: InstanceHandle) -> Self {
+        let exports = Exports::from_instance(module.store(), module.artifact(), handle.clone());
+        Self {
             handle,
             module: module.clone(),
             exports,
-        })
+        }
     }
 
     /// Gets the [`Module`] associated with this instance.
@@ -102,6 +115,89 @@ impl Instance {
         self.module.store()
     }
 
+    /// Returns an iterator over the names under which the given
+    /// [`Extern`] is exported by this instance.
+    ///
+    /// The lookup compares the underlying definitions (function body and
+    /// `VMContext` pointers, or the memory, table and global objects), so it
+    /// also works for handles that were not obtained through [`Instance::exports`],
+    /// for example a function pulled out of a table.
+    ///
+    /// ```
+    /// # use wasmer::{imports, Store, Module, Instance};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let module = Module::new(&store, r#"(module
+    ///     (func $f)
+    ///     (export "first" (func $f))
+    ///     (export "second" (func $f))
+    /// )"#)?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    /// let func = instance.exports.get_function("first")?.clone().into();
+    /// let names = instance.export_names_of(&func).collect::<Vec<_>>();
+    /// assert_eq!(names, vec!["first", "second"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn export_names_of<'a>(&'a self, extern_: &Extern) -> impl Iterator<Item = &'a str> + 'a {
+        let export = extern_.to_export();
+        let handle = &self.handle;
+        self.module
+            .info()
+            .exports
+            .iter()
+            .filter(move |(_name, index)| handle.lookup_by_declaration(index).same(&export))
+            .map(|(name, _index)| name.as_str())
+    }
+
+    /// Returns the first name under which the given [`Extern`] is exported
+    /// by this instance, or `None` if it's not exported.
+    ///
+    /// See [`Instance::export_names_of`] to get all the names.
+    pub fn export_name_of(&self, extern_: &Extern) -> Option<&str> {
+        self.export_names_of(extern_).next()
+    }
+
+    /// Returns the machine code of the function exported as `name`, at the
+    /// address it runs from.
+    ///
+    /// Returns `None` if there is no such exported function, if the function
+    /// is imported by the instance, or if the engine doesn't keep the size
+    /// of the function bodies.
+    ///
+    /// ```
+    /// # use wasmer::{imports, Store, Module, Instance};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let module = Module::new(&store, r#"(module
+    ///     (func (export "answer") (result i32) (i32.const 42))
+    /// )"#)?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    /// let code = instance.function_code("answer").unwrap();
+    /// assert!(!code.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn function_code(&self, name: &str) -> Option<&[u8]> {
+        match self.module.info().exports.get(name)? {
+            ExportIndex::Function(index) => self
+                .handle
+                .function_code(*index)
+                .filter(|code| !code.is_empty()),
+            _ => None,
+        }
+    }
+
+    /// Renders the fields of the instance's `VMContext` as a table, for
+    /// debugging a corrupted instance.
+    ///
+    /// Each row has the offset of a field, its name and its current value,
+    /// such as the base and length of the memories, the values of the
+    /// globals or the shared signature ids.
+    pub fn dump_vmctx(&self) -> String {
+        self.handle.dump_vmctx()
+    }
+
     #[doc(hidden)]
     pub fn vmctx_ptr(&self) -> *mut VMContext {
         self.handle.vmctx_ptr()
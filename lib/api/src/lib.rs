@@ -27,13 +27,16 @@
 mod exports;
 mod externals;
 mod import_object;
+mod import_skeleton;
 mod instance;
+mod linker;
+mod metadata;
 mod module;
 mod native;
 mod ptr;
 mod store;
 mod tunables;
-mod types;
+pub mod types;
 mod utils;
 
 pub mod internals {
@@ -42,7 +45,7 @@ pub mod internals {
     //! `wasmer-vm`. Please don't use any of this types directly, as
     //! they might change frequently or be removed in the future.
 
-    pub use crate::externals::{WithEnv, WithoutEnv};
+    pub use crate::externals::{CallError, StaticFunction, WithEnv, WithoutEnv};
 }
 
 pub use crate::exports::{ExportError, Exportable, Exports, ExportsIterator};
@@ -50,34 +53,46 @@ pub use crate::externals::{
     Extern, FromToNativeWasmType, Function, Global, HostFunction, Memory, Table, WasmTypeList,
 };
 pub use crate::import_object::{ImportObject, ImportObjectIterator, LikeNamespace};
+pub use crate::import_skeleton::ImportSkeleton;
 pub use crate::instance::Instance;
-pub use crate::module::Module;
+pub use crate::linker::{LinkedModule, Linker, LinkerError};
+pub use crate::metadata::{MetadataError, MAX_METADATA_VALUE_SIZE};
+pub use crate::module::{CompilationHandle, Module};
 pub use crate::native::NativeFunc;
 pub use crate::ptr::{Array, Item, WasmPtr};
-pub use crate::store::{Store, StoreObject};
-pub use crate::tunables::Tunables;
+pub use crate::store::{ImportSource, Store, StoreObject};
+pub use crate::tunables::{GrowDecision, MemoryGrowHook, TableGrowHook, Tunables};
 pub use crate::types::{
     ExportType, ExternRef, ExternType, FunctionType, GlobalType, HostInfo, HostRef, ImportType,
-    MemoryType, Mutability, TableType, Val, ValType,
+    MemoryType, Mutability, ParseFunctionTypeError, TableType, Val, ValType,
 };
 pub use crate::types::{Val as Value, ValType as Type};
 pub use crate::utils::is_wasm;
 pub use target_lexicon::{Architecture, CallingConvention, OperatingSystem, Triple, HOST};
 #[cfg(feature = "compiler")]
 pub use wasmer_compiler::{
-    wasmparser, CompilerConfig, FunctionMiddleware, FunctionMiddlewareGenerator,
+    wasmparser, CompilerConfig, FunctionMiddleware, FunctionMiddlewareGenerator, MiddlewareError,
     MiddlewareReaderState,
 };
-pub use wasmer_compiler::{CpuFeature, Features, Target};
+pub use wasmer_compiler::{
+    CompileError, CpuFeature, DebugInfoPolicy, Features, Target, TargetBuilder, TargetError,
+};
 pub use wasmer_engine::{
-    ChainableNamedResolver, DeserializeError, Engine, FrameInfo, InstantiationError, LinkError,
-    NamedResolver, NamedResolverChain, Resolver, RuntimeError, SerializeError,
+    ChainableNamedResolver, DeserializeError, Engine, EngineKind, FrameInfo, ImportError,
+    InstantiationError, InstantiationHooks, LinkError, ModuleFootprint, NamedResolver,
+    NamedResolverChain, Resolver, RuntimeError, SerializeError,
 };
 pub use wasmer_types::{
-    Atomically, Bytes, GlobalInit, LocalFunctionIndex, MemoryView, Pages, ValueType,
-    WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
+    Atomically, Bytes, ConstExpr, ConstOp, ExportIndex, FunctionIndex, GlobalInit,
+    LocalFunctionIndex, MemoryView, Pages, ValueType, V128, WASM_MAX_PAGES, WASM_MIN_PAGES,
+    WASM_PAGE_SIZE,
+};
+pub use wasmer_vm::{
+    is_trap_handler_installed, raise_user_trap, set_signal_handler_strategy, Export,
+    InstanceHandle, MemoryCreator, MemoryError, MemoryStyle, SignalHandlerStrategy, TableCreator,
+    TrapCanary, TrapCode,
 };
-pub use wasmer_vm::{raise_user_trap, Export, MemoryError};
+pub use wasmer_vm::libcalls::LibCall;
 #[cfg(feature = "wat")]
 pub use wat::parse_bytes as wat2wasm;
 
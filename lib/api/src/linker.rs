@@ -0,0 +1,353 @@
+//! The linker instantiates named modules on demand, resolving their imports
+//! against host definitions and the exports of the other modules.
+use crate::exports::Exportable;
+use crate::externals::{Extern, Function};
+use crate::import_object::ImportObject;
+use crate::instance::Instance;
+use crate::module::Module;
+use crate::store::Store;
+use crate::types::{ExternType, FunctionType};
+use crate::{InstantiationError, RuntimeError};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use wasmer_engine::{ImportError, LinkError, NamedResolver};
+use wasmer_vm::Export;
+
+/// An error while linking modules with a [`Linker`].
+#[derive(Error, Debug)]
+pub enum LinkerError {
+    /// The modules import each other's exports in a cycle, given as the
+    /// names of the modules in the order they import each other.
+    #[error("definition cycle: {}", .0.join(" → "))]
+    Cycle(Vec<String>),
+    /// No module is registered with this name.
+    #[error("unknown module `{0}`")]
+    UnknownModule(String),
+    /// No lazy function is waiting to be fulfilled with this name.
+    #[error("no lazy function `{0}`.`{1}` to fulfill")]
+    NotLazy(String, String),
+    /// The function doesn't have the type of the lazy function it fulfills.
+    #[error("lazy function `{module}`.`{name}` expects type {expected}, found {found}")]
+    IncompatibleType {
+        /// The module name of the lazy function.
+        module: String,
+        /// The name of the lazy function.
+        name: String,
+        /// The type of the lazy function.
+        expected: FunctionType,
+        /// The type of the function fulfilling it.
+        found: FunctionType,
+    },
+    /// A module failed to instantiate.
+    #[error(transparent)]
+    Instantiation(#[from] InstantiationError),
+}
+
+/// A lazy function, calling the function it gets fulfilled with.
+struct LazyFunction {
+    ty: FunctionType,
+    target: Arc<Mutex<Option<Function>>>,
+}
+
+/// Instantiates named modules on demand, resolving their imports against
+/// the host definitions and the exports of the other modules.
+///
+/// Modules that import each other can't be instantiated one after the other.
+/// Instead, one of the imports can be defined as a lazy function with
+/// [`Linker::define_lazy`], which traps until it's fulfilled with
+/// [`Linker::fulfill`] once the module exporting it is instantiated.
+///
+/// ```
+/// # use wasmer::{Linker, Module, Store, Type, FunctionType, Value};
+/// # fn main() -> anyhow::Result<()> {
+/// let store = Store::default();
+/// let a = Module::new(&store, r#"(module
+///     (import "b" "double" (func $double (param i32) (result i32)))
+///     (func (export "seven") (result i32) (i32.const 7))
+///     (func (export "run") (result i32) (call $double (i32.const 21)))
+/// )"#)?;
+/// let b = Module::new(&store, r#"(module
+///     (import "a" "seven" (func $seven (result i32)))
+///     (func (export "double") (param i32) (result i32)
+///         (i32.add (local.get 0) (local.get 0)))
+///     (func (export "fourteen") (result i32)
+///         (i32.add (call $seven) (call $seven)))
+/// )"#)?;
+///
+/// let mut linker = Linker::new(&store);
+/// linker.register("a", a).register("b", b);
+/// // `a` imports from `b`, which imports from `a`.
+/// linker.define_lazy("a", "seven", FunctionType::new(vec![], vec![Type::I32]));
+/// let a = linker.instantiate("a")?;
+/// linker.fulfill("a", "seven", a.exports.get_function("seven")?.clone())?;
+///
+/// let b = linker.instantiate("b")?;
+/// assert_eq!(a.exports.get_function("run")?.call(&[])?[0], Value::I32(42));
+/// assert_eq!(b.exports.get_function("fourteen")?.call(&[])?[0], Value::I32(14));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Linker {
+    store: Store,
+    definitions: HashMap<(String, String), Extern>,
+    lazy: HashMap<(String, String), LazyFunction>,
+    modules: HashMap<String, Module>,
+    instances: HashMap<String, Instance>,
+}
+
+impl Linker {
+    /// Creates a new `Linker` for the modules and definitions of `store`.
+    pub fn new(store: &Store) -> Self {
+        Self {
+            store: store.clone(),
+            definitions: HashMap::new(),
+            lazy: HashMap::new(),
+            modules: HashMap::new(),
+            instances: HashMap::new(),
+        }
+    }
+
+    /// Defines the import `name` of `module`.
+    ///
+    /// Definitions take precedence over the exports of the registered
+    /// modules.
+    pub fn define<E>(&mut self, module: &str, name: &str, definition: E) -> &mut Self
+    where
+        E: Into<Extern>,
+    {
+        let key = (module.to_string(), name.to_string());
+        self.lazy.remove(&key);
+        self.definitions.insert(key, definition.into());
+        self
+    }
+
+    /// Defines the import `name` of `module` as a lazy function of type
+    /// `ty`, which traps when called until it's fulfilled.
+    pub fn define_lazy(&mut self, module: &str, name: &str, ty: FunctionType) -> &mut Self {
+        let target: Arc<Mutex<Option<Function>>> = Arc::new(Mutex::new(None));
+        let function = {
+            let target = target.clone();
+            let (module, name) = (module.to_string(), name.to_string());
+            Function::new(&self.store, &ty, move |args| {
+                // Don't hold the lock during the call, the function may
+                // call back into this one.
+                let function = target.lock().unwrap().clone();
+                match function {
+                    Some(function) => Ok(function.call(args)?.into_vec()),
+                    None => Err(RuntimeError::new(format!(
+                        "lazy function `{}`.`{}` called before being fulfilled",
+                        module, name
+                    ))),
+                }
+            })
+        };
+        self.define(module, name, function);
+        self.lazy.insert(
+            (module.to_string(), name.to_string()),
+            LazyFunction { ty, target },
+        );
+        self
+    }
+
+    /// Fulfills the lazy function `name` of `module`, so that calling it
+    /// calls `function`.
+    pub fn fulfill(
+        &mut self,
+        module: &str,
+        name: &str,
+        function: Function,
+    ) -> Result<(), LinkerError> {
+        let key = (module.to_string(), name.to_string());
+        let lazy = self
+            .lazy
+            .get(&key)
+            .ok_or_else(|| LinkerError::NotLazy(key.0.clone(), key.1.clone()))?;
+        if function.ty() != &lazy.ty {
+            return Err(LinkerError::IncompatibleType {
+                module: key.0,
+                name: key.1,
+                expected: lazy.ty.clone(),
+                found: function.ty().clone(),
+            });
+        }
+        *lazy.target.lock().unwrap() = Some(function);
+        self.lazy.remove(&key);
+        Ok(())
+    }
+
+    /// Registers `module` under `name`, to be instantiated on demand.
+    ///
+    /// The imports of other modules from `name` resolve to the exports of
+    /// its instance.
+    pub fn register(&mut self, name: &str, module: Module) -> &mut Self {
+        self.modules.insert(name.to_string(), module);
+        self
+    }
+
+    /// Instantiates the module registered under `name`, after instantiating
+    /// the registered modules it imports from.
+    ///
+    /// Each module is instantiated once, and later calls return the same
+    /// instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LinkerError::Cycle`] if the modules import from each other
+    /// in a cycle not broken by a definition, for example a lazy function.
+    pub fn instantiate(&mut self, name: &str) -> Result<Instance, LinkerError> {
+        self.instantiate_with_path(name, &mut vec![])
+    }
+
+    /// Instantiates the module `name`, with `path` the names of the modules
+    /// being instantiated that import from it.
+    fn instantiate_with_path(
+        &mut self,
+        name: &str,
+        path: &mut Vec<String>,
+    ) -> Result<Instance, LinkerError> {
+        if let Some(instance) = self.instances.get(name) {
+            return Ok(instance.clone());
+        }
+        if let Some(start) = path.iter().position(|n| n == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(LinkerError::Cycle(cycle));
+        }
+        let module = self
+            .modules
+            .get(name)
+            .cloned()
+            .ok_or_else(|| LinkerError::UnknownModule(name.to_string()))?;
+
+        path.push(name.to_string());
+        for import in module.imports() {
+            let key = (import.module().to_string(), import.name().to_string());
+            if !self.definitions.contains_key(&key) && self.modules.contains_key(import.module()) {
+                self.instantiate_with_path(import.module(), path)?;
+            }
+        }
+        path.pop();
+
+        let instance = Instance::new(&module, &*self)?;
+        self.instances.insert(name.to_string(), instance.clone());
+        Ok(instance)
+    }
+}
+
+impl NamedResolver for Linker {
+    fn resolve_by_name(&self, module: &str, name: &str) -> Option<Export> {
+        if let Some(definition) = self
+            .definitions
+            .get(&(module.to_string(), name.to_string()))
+        {
+            return Some(definition.to_export());
+        }
+        self.instances
+            .get(module)?
+            .exports
+            .get_extern(name)
+            .map(Exportable::to_export)
+    }
+}
+
+/// Two modules linked with [`Module::link`], which instantiate together
+/// without an import object.
+#[derive(Clone)]
+pub struct LinkedModule {
+    module: Module,
+    dependency: Module,
+    /// The export of `dependency` each connected import of `module`
+    /// resolves to, by import name.
+    connections: HashMap<String, String>,
+}
+
+impl LinkedModule {
+    /// Links the imports of `module` to the exports of `dependency`, see
+    /// [`Module::link`].
+    pub(crate) fn new(
+        module: &Module,
+        dependency: &Module,
+        connections: &[(&str, &str)],
+    ) -> Result<Self, LinkError> {
+        let exports = dependency
+            .exports()
+            .map(|export| (export.name().to_string(), export.ty().clone()))
+            .collect::<HashMap<String, ExternType>>();
+        for (import_name, export_name) in connections {
+            let mut imports = module
+                .imports()
+                .filter(|import| import.name() == *import_name)
+                .peekable();
+            if imports.peek().is_none() {
+                return Err(LinkError::UnknownImport(import_name.to_string()));
+            }
+            for import in imports {
+                let export_ty = exports.get(*export_name).ok_or_else(|| {
+                    LinkError::Import(
+                        import.module().to_string(),
+                        import.name().to_string(),
+                        ImportError::UnknownImport(import.ty().clone()),
+                    )
+                })?;
+                if !export_ty.is_compatible_with(import.ty()) {
+                    return Err(LinkError::Import(
+                        import.module().to_string(),
+                        import.name().to_string(),
+                        ImportError::IncompatibleType(import.ty().clone(), export_ty.clone()),
+                    ));
+                }
+            }
+        }
+        Ok(Self {
+            module: module.clone(),
+            dependency: dependency.clone(),
+            connections: connections
+                .iter()
+                .map(|(import, export)| (import.to_string(), export.to_string()))
+                .collect(),
+        })
+    }
+
+    /// Gets the module whose imports are connected.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Gets the module whose exports the imports are connected to.
+    pub fn dependency(&self) -> &Module {
+        &self.dependency
+    }
+
+    /// Instantiates the dependency, then the module with its connected
+    /// imports resolved to the exports of the dependency instance.
+    ///
+    /// It returns the instance of the module. The dependency must not
+    /// have imports, and all the imports of the module must be connected.
+    pub fn instantiate(&self) -> Result<Instance, InstantiationError> {
+        let dependency = Instance::new(&self.dependency, &ImportObject::new())?;
+        Instance::new(
+            &self.module,
+            &ConnectionResolver {
+                dependency: &dependency,
+                connections: &self.connections,
+            },
+        )
+    }
+}
+
+/// Resolves the connected imports of a [`LinkedModule`].
+struct ConnectionResolver<'a> {
+    dependency: &'a Instance,
+    connections: &'a HashMap<String, String>,
+}
+
+impl NamedResolver for ConnectionResolver<'_> {
+    fn resolve_by_name(&self, _module: &str, name: &str) -> Option<Export> {
+        let export = self.connections.get(name)?;
+        self.dependency
+            .exports
+            .get_extern(export)
+            .map(Exportable::to_export)
+    }
+}
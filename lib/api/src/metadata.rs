@@ -0,0 +1,160 @@
+//! The metadata of a serialized [`Module`], stored in a section appended
+//! to the bytes of the engine artifact.
+//!
+//! The section is laid out as follows, all integers being little-endian:
+//!
+//! * the number of entries as a `u32`, then for each entry in the order
+//!   of the keys, the length of the key as a `u32`, the key, the length
+//!   of the value as a `u32` and the value;
+//! * the integrity hash: the BLAKE3 hash of the artifact followed by the
+//!   entries;
+//! * the length of the entries as a `u64`;
+//! * the `MAGIC_TRAILER`.
+//!
+//! Modules without metadata are serialized without the section, so the
+//! artifact of a module, and its code hash, don't depend on its
+//! metadata.
+//!
+//! [`Module`]: crate::Module
+
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+use thiserror::Error;
+use wasmer_engine::DeserializeError;
+
+/// The maximum size of a metadata value, in bytes.
+pub const MAX_METADATA_VALUE_SIZE: usize = 64 * 1024;
+
+const MAGIC_TRAILER: &[u8] = b"\0wasmer-metadata";
+
+const HASH_SIZE: usize = 32;
+
+/// The metadata of a module, by key.
+pub(crate) type Metadata = BTreeMap<String, Vec<u8>>;
+
+/// An error setting the metadata of a [`Module`].
+///
+/// [`Module`]: crate::Module
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum MetadataError {
+    /// The key already has a value.
+    #[error("the metadata key `{0}` is already set")]
+    DuplicateKey(String),
+    /// The value is larger than [`MAX_METADATA_VALUE_SIZE`].
+    #[error(
+        "the value of the metadata key `{key}` has {size} bytes, more than the maximum of {} bytes",
+        MAX_METADATA_VALUE_SIZE
+    )]
+    ValueTooLarge {
+        /// The key of the value.
+        key: String,
+        /// The size of the value, in bytes.
+        size: usize,
+    },
+}
+
+/// Sets the value of `key`, which must not have one yet.
+pub(crate) fn insert(
+    metadata: &mut Metadata,
+    key: &str,
+    value: &[u8],
+) -> Result<(), MetadataError> {
+    if value.len() > MAX_METADATA_VALUE_SIZE {
+        return Err(MetadataError::ValueTooLarge {
+            key: key.to_string(),
+            size: value.len(),
+        });
+    }
+    if metadata.contains_key(key) {
+        return Err(MetadataError::DuplicateKey(key.to_string()));
+    }
+    metadata.insert(key.to_string(), value.to_vec());
+    Ok(())
+}
+
+/// Appends the metadata section to the serialized `artifact`, if there is
+/// any metadata.
+pub(crate) fn append(artifact: &mut Vec<u8>, metadata: &Metadata) {
+    if metadata.is_empty() {
+        return;
+    }
+    let mut entries = Vec::new();
+    entries.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+    for (key, value) in metadata {
+        entries.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        entries.extend_from_slice(key.as_bytes());
+        entries.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        entries.extend_from_slice(value);
+    }
+    let integrity = integrity_hash(artifact, &entries);
+    artifact.extend_from_slice(&entries);
+    artifact.extend_from_slice(&integrity);
+    artifact.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+    artifact.extend_from_slice(MAGIC_TRAILER);
+}
+
+/// Splits serialized `bytes` into the artifact and its metadata, checking
+/// the integrity hash.
+pub(crate) fn split(bytes: &[u8]) -> Result<(&[u8], Metadata), DeserializeError> {
+    if !bytes.ends_with(MAGIC_TRAILER) {
+        return Ok((bytes, Metadata::new()));
+    }
+    let corrupted = |reason: &str| DeserializeError::CorruptedBinary(reason.to_string());
+    let rest = &bytes[..bytes.len() - MAGIC_TRAILER.len()];
+    let (rest, length) = split_suffix(rest, 8).ok_or_else(|| corrupted("truncated metadata"))?;
+    let length = u64::from_le_bytes(length.try_into().unwrap());
+    let (rest, integrity) =
+        split_suffix(rest, HASH_SIZE).ok_or_else(|| corrupted("truncated metadata"))?;
+    let (artifact, entries) = usize::try_from(length)
+        .ok()
+        .and_then(|length| split_suffix(rest, length))
+        .ok_or_else(|| corrupted("truncated metadata"))?;
+    if integrity_hash(artifact, entries)[..] != *integrity {
+        return Err(corrupted(
+            "the module doesn't match the integrity hash of its metadata",
+        ));
+    }
+    let metadata = parse_entries(entries).ok_or_else(|| corrupted("malformed metadata"))?;
+    Ok((artifact, metadata))
+}
+
+fn integrity_hash(artifact: &[u8], entries: &[u8]) -> [u8; HASH_SIZE] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(artifact);
+    hasher.update(entries);
+    *hasher.finalize().as_bytes()
+}
+
+fn split_suffix(bytes: &[u8], length: usize) -> Option<(&[u8], &[u8])> {
+    let middle = bytes.len().checked_sub(length)?;
+    Some(bytes.split_at(middle))
+}
+
+fn parse_entries(mut entries: &[u8]) -> Option<Metadata> {
+    fn take<'a>(entries: &mut &'a [u8], length: usize) -> Option<&'a [u8]> {
+        if entries.len() < length {
+            return None;
+        }
+        let (taken, rest) = entries.split_at(length);
+        *entries = rest;
+        Some(taken)
+    }
+    fn take_u32(entries: &mut &[u8]) -> Option<usize> {
+        let bytes = take(entries, 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    }
+
+    let mut metadata = Metadata::new();
+    for _ in 0..take_u32(&mut entries)? {
+        let length = take_u32(&mut entries)?;
+        let key = std::str::from_utf8(take(&mut entries, length)?).ok()?;
+        let length = take_u32(&mut entries)?;
+        let value = take(&mut entries, length)?;
+        insert(&mut metadata, key, value).ok()?;
+    }
+    if entries.is_empty() {
+        Some(metadata)
+    } else {
+        None
+    }
+}
@@ -1,15 +1,23 @@
-use crate::store::Store;
+use crate::import_skeleton::ImportSkeleton;
+use crate::linker::LinkedModule;
+use crate::metadata::{self, Metadata, MetadataError};
+use crate::store::{ImportSource, Store};
 use crate::types::{ExportType, ImportType};
 use crate::InstantiationError;
 use std::fmt;
+use std::fs;
 use std::io;
+use std::panic;
 use std::path::Path;
 use std::sync::Arc;
+use std::thread::{self, JoinHandle};
 use thiserror::Error;
-use wasmer_compiler::CompileError;
-#[cfg(feature = "wat")]
-use wasmer_compiler::WasmError;
-use wasmer_engine::{Artifact, DeserializeError, Resolver, SerializeError};
+use wasmer_compiler::{CompilationProgress, CompileError};
+use wasmer_engine::{
+    Artifact, DeserializeError, InstantiationHooks, LinkError, ModuleFootprint, Resolver,
+    SerializeError,
+};
+use wasmer_types::ExportIndex;
 use wasmer_vm::{ExportsIterator, ImportsIterator, InstanceHandle, ModuleInfo};
 
 #[derive(Error, Debug)]
@@ -34,6 +42,7 @@ pub enum IoCompileError {
 pub struct Module {
     store: Store,
     artifact: Arc<dyn Artifact>,
+    metadata: Arc<Metadata>,
 }
 
 impl Module {
@@ -99,12 +108,8 @@ impl Module {
     #[allow(unreachable_code)]
     pub fn new(store: &Store, bytes: impl AsRef<[u8]>) -> Result<Self, CompileError> {
         #[cfg(feature = "wat")]
-        let bytes = wat::parse_bytes(bytes.as_ref()).map_err(|e| {
-            CompileError::Wasm(WasmError::Generic(format!(
-                "Error when converting wat: {}",
-                e
-            )))
-        })?;
+        let bytes = wat::parse_bytes(bytes.as_ref())
+            .map_err(|e| CompileError::Validate(format!("Error when converting wat: {}", e)))?;
 
         Self::from_binary(store, bytes.as_ref())
     }
@@ -162,6 +167,40 @@ impl Module {
         Ok(Self::from_artifact(store, artifact))
     }
 
+    /// Validates and compiles a WebAssembly binary on a background thread.
+    ///
+    /// The returned [`CompilationHandle`] reports how many functions have
+    /// been compiled, can cancel the compilation, and waits for the module.
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let wasm = wat2wasm(b"(module (func (export \"f\")))")?;
+    /// let handle = Module::compile_in_background(&store, wasm.into_owned());
+    /// let (_done, _total) = handle.progress();
+    /// let module = handle.wait()?;
+    /// assert_eq!(module.exports().count(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compile_in_background(store: &Store, binary: Vec<u8>) -> CompilationHandle {
+        let progress = Arc::new(CompilationProgress::new());
+        let thread = {
+            let store = store.clone();
+            let progress = progress.clone();
+            thread::spawn(move || {
+                Self::validate(&store, &binary)?;
+                let artifact =
+                    store
+                        .engine()
+                        .compile_with_progress(&binary, store.tunables(), &progress)?;
+                Ok(Self::from_artifact(&store, artifact))
+            })
+        };
+        CompilationHandle { progress, thread }
+    }
+
     /// Serializes a module into a binary representation that the `Engine`
     /// can later process via [`Module::deserialize`].
     ///
@@ -177,7 +216,9 @@ impl Module {
     /// # }
     /// ```
     pub fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
-        self.artifact.serialize()
+        let mut bytes = self.artifact.serialize()?;
+        metadata::append(&mut bytes, &self.metadata);
+        Ok(bytes)
     }
 
     /// Serializes a module into a file that the `Engine`
@@ -195,7 +236,14 @@ impl Module {
     /// # }
     /// ```
     pub fn serialize_to_file(&self, path: impl AsRef<Path>) -> Result<(), SerializeError> {
-        self.artifact.serialize_to_file(path.as_ref())
+        let path = path.as_ref();
+        self.artifact.serialize_to_file(path)?;
+        if !self.metadata.is_empty() {
+            let mut bytes = fs::read(path)?;
+            metadata::append(&mut bytes, &self.metadata);
+            fs::write(path, bytes)?;
+        }
+        Ok(())
     }
 
     /// Deserializes a a serialized Module binary into a `Module`.
@@ -222,8 +270,9 @@ impl Module {
     /// # }
     /// ```
     pub unsafe fn deserialize(store: &Store, bytes: &[u8]) -> Result<Self, DeserializeError> {
+        let (bytes, metadata) = metadata::split(bytes)?;
         let artifact = store.engine().deserialize(bytes)?;
-        Ok(Self::from_artifact(store, artifact))
+        Ok(Self::from_artifact(store, artifact).with_metadata(metadata))
     }
 
     /// Deserializes a a serialized Module located in a `Path` into a `Module`.
@@ -247,25 +296,85 @@ impl Module {
         store: &Store,
         path: impl AsRef<Path>,
     ) -> Result<Self, DeserializeError> {
+        // The engines ignore the metadata section at the end of the file.
+        let (_, metadata) = metadata::split(&fs::read(path.as_ref())?)?;
         let artifact = store.engine().deserialize_from_file(path.as_ref())?;
-        Ok(Self::from_artifact(store, artifact))
+        Ok(Self::from_artifact(store, artifact).with_metadata(metadata))
     }
 
     fn from_artifact(store: &Store, artifact: Arc<dyn Artifact>) -> Self {
         Self {
             store: store.clone(),
             artifact,
+            metadata: Arc::new(Metadata::new()),
         }
     }
 
+    fn with_metadata(self, metadata: Metadata) -> Self {
+        Self {
+            metadata: Arc::new(metadata),
+            ..self
+        }
+    }
+
+    /// Sets the value of the metadata `key`, to be stored along with the
+    /// module when it's serialized.
+    ///
+    /// The metadata isn't part of the code of the module: it doesn't
+    /// change its [`Module::code_hash`]. Serialized modules with metadata
+    /// are checked for integrity when they are deserialized.
+    ///
+    /// It fails if `key` already has a value, or if `value` is larger
+    /// than [`MAX_METADATA_VALUE_SIZE`] bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let mut module = Module::new(&store, "(module)")?;
+    /// module.set_metadata("policy-version", b"3")?;
+    ///
+    /// let serialized = module.serialize()?;
+    /// let module = unsafe { Module::deserialize(&store, &serialized) }?;
+    /// assert_eq!(module.metadata("policy-version"), Some(&b"3"[..]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`MAX_METADATA_VALUE_SIZE`]: crate::MAX_METADATA_VALUE_SIZE
+    pub fn set_metadata(&mut self, key: &str, value: &[u8]) -> Result<(), MetadataError> {
+        metadata::insert(Arc::make_mut(&mut self.metadata), key, value)
+    }
+
+    /// Returns the value of the metadata `key`, if it has one.
+    pub fn metadata(&self, key: &str) -> Option<&[u8]> {
+        self.metadata.get(key).map(Vec::as_slice)
+    }
+
+    /// Returns an iterator over the metadata keys of the module, in
+    /// order.
+    pub fn metadata_keys(&self) -> impl Iterator<Item = &str> {
+        self.metadata.keys().map(String::as_str)
+    }
+
+    /// Returns the BLAKE3 hash of the serialized code of the module,
+    /// which doesn't depend on its metadata.
+    pub fn code_hash(&self) -> Result<[u8; 32], SerializeError> {
+        Ok(*blake3::hash(&self.artifact.serialize()?).as_bytes())
+    }
+
     pub(crate) fn instantiate(
         &self,
         resolver: &dyn Resolver,
     ) -> Result<InstanceHandle, InstantiationError> {
         unsafe {
-            let instance_handle =
-                self.artifact
-                    .instantiate(self.store.tunables(), resolver, Box::new(()))?;
+            let instance_handle = self.artifact.instantiate(
+                self.store.tunables(),
+                &self.store.with_default_namespaces(resolver),
+                Box::new(()),
+            )?;
 
             // After the instance handle is created, we need to initialize
             // the data, call the start function and so. However, if any
@@ -278,6 +387,21 @@ impl Module {
         }
     }
 
+    pub(crate) fn instantiate_with_hooks(
+        &self,
+        resolver: &dyn Resolver,
+        hooks: &dyn InstantiationHooks,
+    ) -> Result<InstanceHandle, InstantiationError> {
+        unsafe {
+            self.artifact.instantiate_with_hooks(
+                self.store.tunables(),
+                &self.store.with_default_namespaces(resolver),
+                Box::new(()),
+                hooks,
+            )
+        }
+    }
+
     /// Returns the name of the current module.
     ///
     /// This name is normally set in the WebAssembly bytecode by some
@@ -358,6 +482,79 @@ impl Module {
         self.artifact.module_ref().imports()
     }
 
+    /// Returns the imports of the Module grouped by namespace, to start
+    /// writing the host side of the module from.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module
+    ///     (import "host" "log" (func (param i32)))
+    ///     (import "host" "memory" (memory 1))
+    /// )"#;
+    /// let module = Module::new(&store, wat)?;
+    /// let skeleton = module.generate_import_skeleton();
+    /// assert_eq!(skeleton.get("host").unwrap().len(), 2);
+    /// assert!(skeleton
+    ///     .to_rust_source()
+    ///     .contains("fn host_log(_: i32) {"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn generate_import_skeleton(&self) -> ImportSkeleton {
+        ImportSkeleton::new(self.imports())
+    }
+
+    /// Returns where an instantiation with `resolver` would resolve each
+    /// import of the module from, without instantiating it: `resolver`,
+    /// the default namespaces of the store, or neither.
+    ///
+    /// The imports are in the same order as [`Module::imports`]. The types
+    /// of the resolved imports aren't checked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let mut host = Exports::new();
+    /// host.insert("answer", Function::new_native(&store, || 42));
+    /// store.register_default_namespace("host", host);
+    ///
+    /// let module = Module::new(&store, r#"(module
+    ///     (import "host" "answer" (func (result i32)))
+    ///     (import "host" "log" (func (param i32))))"#)?;
+    /// let sources = module
+    ///     .import_sources(&imports! {})
+    ///     .into_iter()
+    ///     .map(|(import, source)| (import.name().to_string(), source))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(sources, vec![
+    ///     ("answer".to_string(), ImportSource::DefaultNamespace),
+    ///     ("log".to_string(), ImportSource::Missing),
+    /// ]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn import_sources(&self, resolver: &dyn Resolver) -> Vec<(ImportType, ImportSource)> {
+        let module = self.artifact.module_ref();
+        module
+            .imports
+            .keys()
+            .zip(module.imports())
+            .map(|((module_name, field, index), import)| {
+                let source = self
+                    .store
+                    .import_source(resolver, *index, module_name, field);
+                (import, source)
+            })
+            .collect()
+    }
+
     /// Returns an iterator over the exported types in the Module.
     ///
     /// The order of the exports is guaranteed to be the same as in the
@@ -385,6 +582,95 @@ impl Module {
         self.artifact.module_ref().exports()
     }
 
+    /// Returns an iterator over the names under which the given
+    /// [`ExportIndex`] is exported.
+    ///
+    /// # Important
+    ///
+    /// One entity can be exported under several names. That's why an
+    /// iterator (rather than one element) is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module
+    ///     (func $f)
+    ///     (export "first" (func $f))
+    ///     (export "second" (func $f))
+    /// )"#;
+    /// let module = Module::new(&store, wat)?;
+    /// let index = ExportIndex::Function(FunctionIndex::from_u32(0));
+    /// let names = module.export_names_of(index).collect::<Vec<_>>();
+    /// assert_eq!(names, vec!["first", "second"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn export_names_of<'a>(&'a self, index: ExportIndex) -> impl Iterator<Item = &'a str> + 'a {
+        self.artifact.module_ref().export_names_of(index)
+    }
+
+    /// Links this module with `other`, connecting imports of this module
+    /// to exports of `other`.
+    ///
+    /// Each connection `(import, export)` resolves the imports of this
+    /// module named `import` to the export `export` of `other`. The
+    /// resulting [`LinkedModule`] instantiates both modules without an
+    /// import object.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LinkError`] if this module has no import named like a
+    /// connection, or if the connected export is missing or doesn't have
+    /// the type of the import.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let math = Module::new(&store, r#"(module
+    ///     (func (export "double") (param i32) (result i32)
+    ///         (i32.add (local.get 0) (local.get 0)))
+    /// )"#)?;
+    /// let main = Module::new(&store, r#"(module
+    ///     (import "math" "twice" (func $twice (param i32) (result i32)))
+    ///     (func (export "run") (result i32) (call $twice (i32.const 21)))
+    /// )"#)?;
+    /// let linked = main.link(&math, &[("twice", "double")])?;
+    /// let instance = linked.instantiate()?;
+    /// let run = instance.exports.get_function("run")?;
+    /// assert_eq!(run.call(&[])?[0], Value::I32(42));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn link(
+        &self,
+        other: &Self,
+        connections: &[(&str, &str)],
+    ) -> Result<LinkedModule, LinkError> {
+        LinkedModule::new(self, other, connections)
+    }
+
+    /// Returns the memory footprint of the compiled module: the sizes of
+    /// its code, read-only data, unwind and frame information and
+    /// serialized form, and its number of functions and trampolines.
+    ///
+    /// A deserialized module reports the footprint recorded when it was
+    /// compiled.
+    pub fn footprint(&self) -> ModuleFootprint {
+        self.artifact.footprint()
+    }
+
+    /// Returns the size in bytes of the executable code of the functions
+    /// of the module.
+    pub fn code_size(&self) -> usize {
+        self.artifact.code_size()
+    }
+
     /// Get the custom sections of the module given a `name`.
     ///
     /// # Important
@@ -401,6 +687,38 @@ impl Module {
         &self.store
     }
 
+    /// Returns this module bound to `store`, whose tunables and default
+    /// namespaces then apply to its instances.
+    ///
+    /// The compiled code of the module belongs to the engine of its
+    /// store, so `None` is returned if `store` has another engine.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, "(module)")?;
+    /// let other_store = Store::new(&**store.engine());
+    /// let module = module.with_store(&other_store).unwrap();
+    /// assert!(Store::same(module.store(), &other_store));
+    ///
+    /// assert!(module.with_store(&Store::default()).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_store(&self, store: &Store) -> Option<Self> {
+        if !Store::same(&self.store, store) {
+            return None;
+        }
+        Some(Self {
+            store: store.clone(),
+            artifact: self.artifact.clone(),
+            metadata: self.metadata.clone(),
+        })
+    }
+
     /// The ABI of the ModuleInfo is very unstable, we refactor it very often.
     /// This function is public because in some cases it can be useful to get some
     /// extra information from the module.
@@ -422,6 +740,50 @@ impl Module {
     }
 }
 
+/// A module being compiled on a background thread, see
+/// [`Module::compile_in_background`].
+pub struct CompilationHandle {
+    progress: Arc<CompilationProgress>,
+    thread: JoinHandle<Result<Module, CompileError>>,
+}
+
+impl CompilationHandle {
+    /// Returns the number of functions compiled so far, and the number of
+    /// functions in the module.
+    ///
+    /// The total is `0` until the module has been validated and
+    /// translated.
+    pub fn progress(&self) -> (usize, usize) {
+        self.progress.progress()
+    }
+
+    /// Cancels the compilation, so that [`CompilationHandle::wait`] returns
+    /// [`CompileError::Cancelled`] without compiling the remaining
+    /// functions.
+    ///
+    /// The functions being compiled when cancelling still finish.
+    pub fn cancel(&self) {
+        self.progress.cancel()
+    }
+
+    /// Blocks until the compilation finishes, returning the compiled
+    /// module.
+    pub fn wait(self) -> Result<Module, CompileError> {
+        match self.thread.join() {
+            Ok(result) => result,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+}
+
+impl fmt::Debug for CompilationHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CompilationHandle")
+            .field("progress", &self.progress)
+            .finish()
+    }
+}
+
 impl fmt::Debug for Module {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Module")
@@ -10,8 +10,8 @@
 use std::marker::PhantomData;
 
 use crate::externals::function::{
-    FunctionDefinition, HostFunctionDefinition, VMDynamicFunction, VMDynamicFunctionWithEnv,
-    VMDynamicFunctionWithoutEnv, WasmFunctionDefinition,
+    call_dynamic, FunctionDefinition, HostFunctionDefinition, VMDynamicFunctionWithEnv,
+    VMDynamicFunctionWithoutEnv,
 };
 use crate::{FromToNativeWasmType, Function, FunctionType, RuntimeError, Store, WasmTypeList};
 use std::panic::{catch_unwind, AssertUnwindSafe};
@@ -28,6 +28,7 @@ pub struct NativeFunc<'a, Args = (), Rets = ()> {
     address: *const VMFunctionBody,
     vmctx: *mut VMContext,
     arg_kind: VMFunctionKind,
+    noreturn: bool,
     // exported: ExportFunction,
     _phantom: PhantomData<(&'a (), Args, Rets)>,
 }
@@ -44,6 +45,7 @@ where
         address: *const VMFunctionBody,
         vmctx: *mut VMContext,
         arg_kind: VMFunctionKind,
+        noreturn: bool,
         definition: FunctionDefinition,
     ) -> Self {
         Self {
@@ -52,6 +54,7 @@ where
             address,
             vmctx,
             arg_kind,
+            noreturn,
             _phantom: PhantomData,
         }
     }
@@ -63,12 +66,13 @@ where
     Rets: WasmTypeList,
 {
     fn from(other: &NativeFunc<'a, Args, Rets>) -> Self {
-        let signature = FunctionType::new(Args::wasm_types(), Rets::wasm_types());
+        let signature = FunctionType::new(Args::flatten_types(), Rets::flatten_types());
         Self {
             address: other.address,
             vmctx: other.vmctx,
             signature,
             kind: other.arg_kind,
+            noreturn: other.noreturn,
         }
     }
 }
@@ -79,7 +83,7 @@ where
     Rets: WasmTypeList,
 {
     fn from(other: NativeFunc<'a, Args, Rets>) -> Self {
-        let signature = FunctionType::new(Args::wasm_types(), Rets::wasm_types());
+        let signature = FunctionType::new(Args::flatten_types(), Rets::flatten_types());
         Self {
             store: other.store,
             definition: other.definition,
@@ -88,11 +92,18 @@ where
                 vmctx: other.vmctx,
                 signature,
                 kind: other.arg_kind,
+                noreturn: other.noreturn,
             },
         }
     }
 }
 
+/// The maximum number of results a function call returns in registers.
+///
+/// `call` passes a result area to the functions returning more results,
+/// see `call_with_result_area`.
+const MAX_REGISTER_RESULTS: usize = 2;
+
 macro_rules! impl_native_traits {
     (  $( $x:ident ),* ) => {
         #[allow(unused_parens, non_snake_case)]
@@ -103,10 +114,14 @@ macro_rules! impl_native_traits {
         {
             /// Call the typed func and return results.
             pub fn call(&self, $( $x: $x, )* ) -> Result<Rets, RuntimeError> {
+                if Rets::LEN > MAX_REGISTER_RESULTS {
+                    let mut rets_list_array = Rets::empty_array();
+                    let area = rets_list_array.as_mut().as_mut_ptr() as *mut u8;
+                    unsafe { self.call_with_result_area( $( $x, )* area)? };
+                    return Ok(Rets::from_array(rets_list_array));
+                }
                 match self.definition {
-                    FunctionDefinition::Wasm(WasmFunctionDefinition {
-                        trampoline
-                    }) => {
+                    FunctionDefinition::Wasm(ref wasm) => {
                         // TODO: when `const fn` related features mature more, we can declare a single array
                         // of the correct size here.
                         let mut params_list = [ $( $x.to_native().to_binary() ),* ];
@@ -124,9 +139,8 @@ macro_rules! impl_native_traits {
                             rets_list.as_mut()
                         };
                         unsafe {
-                            wasmer_vm::wasmer_call_trampoline(
+                            wasm.call_trampoline(
                                 self.vmctx,
-                                trampoline,
                                 self.address,
                                 args_rets.as_mut_ptr() as *mut u8,
                             )
@@ -163,9 +177,9 @@ macro_rules! impl_native_traits {
                         match self.arg_kind {
                             VMFunctionKind::Static => {
                                 let results = catch_unwind(AssertUnwindSafe(|| unsafe {
-                                    let f = std::mem::transmute::<_, unsafe extern "C" fn( *mut VMContext, $( $x, )*) -> Rets::CStruct>(self.address);
+                                    let f = std::mem::transmute::<_, unsafe extern "C" fn( *mut VMContext, $( $x::Native, )*) -> Rets::CStruct>(self.address);
                                     // We always pass the vmctx
-                                    f( self.vmctx, $( $x, )* )
+                                    f( self.vmctx, $( $x.to_native(), )* )
                                 })).map_err(|e| RuntimeError::new(format!("{:?}", e)))?;
                                 Ok(Rets::from_c_struct(results))
                             },
@@ -174,11 +188,11 @@ macro_rules! impl_native_traits {
                                 let results = if !has_env {
                                     type VMContextWithoutEnv = VMDynamicFunctionContext<VMDynamicFunctionWithoutEnv>;
                                     let ctx = self.vmctx as *mut VMContextWithoutEnv;
-                                    unsafe { (*ctx).ctx.call(&params_list)? }
+                                    unsafe { call_dynamic(&(*ctx).ctx, &params_list)? }
                                 } else {
                                     type VMContextWithEnv = VMDynamicFunctionContext<VMDynamicFunctionWithEnv<std::ffi::c_void>>;
                                     let ctx = self.vmctx as *mut VMContextWithEnv;
-                                    unsafe { (*ctx).ctx.call(&params_list)? }
+                                    unsafe { call_dynamic(&(*ctx).ctx, &params_list)? }
                                 };
                                 let mut rets_list_array = Rets::empty_array();
                                 let mut_rets = rets_list_array.as_mut() as *mut [i128] as *mut i128;
@@ -194,6 +208,73 @@ macro_rules! impl_native_traits {
                 }
 
             }
+
+            /// Call the typed func, writing its results to the result area
+            /// `area` instead of returning them.
+            ///
+            /// The results are written in their binary form, as the
+            /// `i128` values of `Rets::Array`. WebAssembly functions write
+            /// their results to the area directly. `call` uses this for the
+            /// functions returning more results than fit in registers.
+            ///
+            /// # Safety
+            ///
+            /// `area` must be valid for writes of `Rets::LEN` `i128`
+            /// values, and aligned for them.
+            pub unsafe fn call_with_result_area(&self, $( $x: $x, )* area: *mut u8) -> Result<(), RuntimeError> {
+                let rets_list = std::slice::from_raw_parts_mut(area as *mut i128, Rets::LEN);
+                match self.definition {
+                    FunctionDefinition::Wasm(ref wasm) => {
+                        let mut params_list = [ $( $x.to_native().to_binary() ),* ];
+                        if params_list.len() > rets_list.len() {
+                            wasm.call_trampoline(
+                                self.vmctx,
+                                self.address,
+                                params_list.as_mut_ptr() as *mut u8,
+                            )?;
+                            let num_rets = rets_list.len();
+                            rets_list.copy_from_slice(&params_list[..num_rets]);
+                        } else {
+                            rets_list[..params_list.len()].copy_from_slice(&params_list);
+                            wasm.call_trampoline(
+                                self.vmctx,
+                                self.address,
+                                area,
+                            )?;
+                        }
+                    }
+                    FunctionDefinition::Host(HostFunctionDefinition {
+                        has_env
+                    }) => {
+                        match self.arg_kind {
+                            VMFunctionKind::Static => {
+                                let results = catch_unwind(AssertUnwindSafe(|| {
+                                    let f = std::mem::transmute::<_, unsafe extern "C" fn( *mut VMContext, $( $x::Native, )*) -> Rets::CStruct>(self.address);
+                                    // We always pass the vmctx
+                                    f( self.vmctx, $( $x.to_native(), )* )
+                                })).map_err(|e| RuntimeError::new(format!("{:?}", e)))?;
+                                rets_list.copy_from_slice(Rets::from_c_struct(results).into_array().as_mut());
+                            },
+                            VMFunctionKind::Dynamic => {
+                                let params_list = [ $( $x.to_native().to_value() ),* ];
+                                let results = if !has_env {
+                                    type VMContextWithoutEnv = VMDynamicFunctionContext<VMDynamicFunctionWithoutEnv>;
+                                    let ctx = self.vmctx as *mut VMContextWithoutEnv;
+                                    call_dynamic(&(*ctx).ctx, &params_list)?
+                                } else {
+                                    type VMContextWithEnv = VMDynamicFunctionContext<VMDynamicFunctionWithEnv<std::ffi::c_void>>;
+                                    let ctx = self.vmctx as *mut VMContextWithEnv;
+                                    call_dynamic(&(*ctx).ctx, &params_list)?
+                                };
+                                for (ret, slot) in results.iter().zip(rets_list.iter_mut()) {
+                                    ret.write_value_to(slot);
+                                }
+                            }
+                        }
+                    },
+                }
+                Ok(())
+            }
         }
     };
 }
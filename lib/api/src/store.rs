@@ -1,10 +1,14 @@
+use crate::exports::Exports;
+use crate::import_object::LikeNamespace;
 use crate::tunables::Tunables;
+use std::collections::HashMap;
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 #[cfg(all(feature = "compiler", feature = "engine"))]
 use wasmer_compiler::CompilerConfig;
-use wasmer_engine::Engine;
 use wasmer_engine::Tunables as BaseTunables;
+use wasmer_engine::{Engine, EngineKind, Resolver};
+use wasmer_vm::{defer_cleanup, install_trap_handler, Export};
 
 /// The store represents all global state that can be manipulated by
 /// WebAssembly programs. It consists of the runtime representation
@@ -15,11 +19,21 @@ use wasmer_engine::Tunables as BaseTunables;
 /// the Wasm bytes into a valid module artifact), in addition to the
 /// [`Tunables`] (that are used to create the memories, tables and globals).
 ///
+/// The modules, instances and externals created with a store hold a clone
+/// of it, and each compiled module holds its code, so they can be dropped
+/// in any order: dropping a `Store` (or its engine) while they are alive
+/// only drops this handle.
+///
+/// A store can also hold default import namespaces, see
+/// [`Store::register_default_namespace`], which are shared with its
+/// clones.
+///
 /// Spec: https://webassembly.github.io/spec/core/exec/runtime.html#store
 #[derive(Clone)]
 pub struct Store {
     engine: Arc<dyn Engine + Send + Sync>,
     tunables: Arc<dyn BaseTunables + Send + Sync>,
+    default_namespaces: Arc<RwLock<HashMap<String, Exports>>>,
 }
 
 impl Store {
@@ -31,6 +45,7 @@ impl Store {
         Self {
             engine: engine.cloned(),
             tunables: Arc::new(Tunables::for_target(engine.target())),
+            default_namespaces: Default::default(),
         }
     }
 
@@ -45,6 +60,7 @@ impl Store {
         Self {
             engine: engine.cloned(),
             tunables: Arc::new(tunables),
+            default_namespaces: Default::default(),
         }
     }
 
@@ -58,12 +74,124 @@ impl Store {
         &self.engine
     }
 
+    /// Returns the kind of the [`Engine`] of the store.
+    pub fn engine_kind(&self) -> EngineKind {
+        self.engine.kind()
+    }
+
+    /// Registers `namespace` under `name` for every instantiation of the
+    /// modules of this store (and of its clones), replacing the namespace
+    /// previously registered under `name`, which is returned.
+    ///
+    /// Each import is resolved by the resolver given to the instantiation
+    /// first, and only if it doesn't provide the import, by the default
+    /// namespace of the import's module name. So the imports can override
+    /// the fields of a default namespace one by one.
+    /// [`Module::import_sources`] tells where each import of a module would
+    /// be resolved from.
+    ///
+    /// ```
+    /// # use wasmer::{imports, Exports, Function, Instance, Module, Store};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let mut host = Exports::new();
+    /// host.insert("answer", Function::new_native(&store, || 42));
+    /// store.register_default_namespace("host", host);
+    ///
+    /// let module = Module::new(&store, r#"(module
+    ///     (import "host" "answer" (func (result i32))))"#)?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_default_namespace(&self, name: &str, namespace: Exports) -> Option<Exports> {
+        self.default_namespaces
+            .write()
+            .unwrap()
+            .insert(name.to_string(), namespace)
+    }
+
+    /// Removes the default namespace registered under `name`, and returns
+    /// it.
+    pub fn remove_default_namespace(&self, name: &str) -> Option<Exports> {
+        self.default_namespaces.write().unwrap().remove(name)
+    }
+
+    /// Defers `cleanup` until the host function that is running returns,
+    /// which also happens when it traps or panics.
+    ///
+    /// A trap unwinds through the host functions without running the
+    /// destructors of their local variables, so a host function that calls
+    /// back into WebAssembly while it holds a resource the guest can see,
+    /// like a lock or a borrowed region of memory, should release it here
+    /// rather than with `Drop`. The cleanups run on the thread they were
+    /// deferred on, in the reverse order they were deferred. Outside of a
+    /// host function, `cleanup` runs immediately.
+    ///
+    /// In debug builds, a [`TrapCanary`] records when a trap skips its
+    /// `Drop`, which helps find the values that should be released here,
+    /// see [`TrapCanary::take_skipped`].
+    ///
+    /// [`TrapCanary`]: crate::TrapCanary
+    /// [`TrapCanary::take_skipped`]: crate::TrapCanary::take_skipped
+    pub fn defer(&self, cleanup: impl FnOnce() + 'static) {
+        defer_cleanup(cleanup)
+    }
+
+    /// Returns `resolver`, falling back to the default namespaces of the
+    /// store for the imports it doesn't provide.
+    pub(crate) fn with_default_namespaces<'a>(
+        &'a self,
+        resolver: &'a dyn Resolver,
+    ) -> impl Resolver + 'a {
+        DefaultNamespacesResolver {
+            resolver,
+            default_namespaces: &self.default_namespaces,
+        }
+    }
+
+    /// Returns where the import `module`.`field` at `index` is resolved
+    /// from, by `resolver` or by the default namespaces of the store.
+    pub(crate) fn import_source(
+        &self,
+        resolver: &dyn Resolver,
+        index: u32,
+        module: &str,
+        field: &str,
+    ) -> ImportSource {
+        if resolver.resolve(index, module, field).is_some() {
+            ImportSource::Resolver
+        } else if default_namespace_export(&self.default_namespaces, module, field).is_some() {
+            ImportSource::DefaultNamespace
+        } else {
+            ImportSource::Missing
+        }
+    }
+
     /// Checks whether two stores are identical. A store is considered
     /// equal to another store if both have the same engine. The
     /// tunables are excluded from the logic.
     pub fn same(a: &Self, b: &Self) -> bool {
         a.engine.id() == b.engine.id()
     }
+
+    /// Installs the handlers catching the traps of WebAssembly code, for
+    /// the process.
+    ///
+    /// With the [`SignalHandlerStrategy::Manual`] strategy, see
+    /// [`set_signal_handler_strategy`], this must be called before running
+    /// any WebAssembly code. Since POSIX signal handlers are called last
+    /// installed first, it should be called after the host installs its
+    /// own handlers, which must then forward the faults they don't handle
+    /// to the previous handler. It has no effect once the handlers are
+    /// installed, or with the [`SignalHandlerStrategy::None`] strategy.
+    ///
+    /// [`set_signal_handler_strategy`]: crate::set_signal_handler_strategy
+    /// [`SignalHandlerStrategy::Manual`]: crate::SignalHandlerStrategy::Manual
+    /// [`SignalHandlerStrategy::None`]: crate::SignalHandlerStrategy::None
+    pub fn install_trap_handler(&self) {
+        install_trap_handler();
+    }
 }
 
 impl PartialEq for Store {
@@ -115,6 +243,7 @@ impl Default for Store {
         Store {
             engine: Arc::new(engine),
             tunables: Arc::new(tunables),
+            default_namespaces: Default::default(),
         }
     }
 }
@@ -125,6 +254,46 @@ impl fmt::Debug for Store {
     }
 }
 
+struct DefaultNamespacesResolver<'a> {
+    resolver: &'a dyn Resolver,
+    default_namespaces: &'a RwLock<HashMap<String, Exports>>,
+}
+
+impl Resolver for DefaultNamespacesResolver<'_> {
+    fn resolve(&self, index: u32, module: &str, field: &str) -> Option<Export> {
+        self.resolver
+            .resolve(index, module, field)
+            .or_else(|| default_namespace_export(self.default_namespaces, module, field))
+    }
+}
+
+fn default_namespace_export(
+    default_namespaces: &RwLock<HashMap<String, Exports>>,
+    module: &str,
+    field: &str,
+) -> Option<Export> {
+    default_namespaces
+        .read()
+        .unwrap()
+        .get(module)?
+        .get_namespace_export(field)
+}
+
+/// Where an instantiation resolves an import from, see
+/// [`Module::import_sources`].
+///
+/// [`Module::import_sources`]: crate::Module::import_sources
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportSource {
+    /// The resolver given to the instantiation.
+    Resolver,
+    /// The default namespace of the store registered under the module name
+    /// of the import.
+    DefaultNamespace,
+    /// Neither, so the instantiation fails with a missing import.
+    Missing,
+}
+
 /// A trait represinting any object that lives in the `Store`.
 pub trait StoreObject {
     /// Return true if the object `Store` is the same as the provided `Store`.
@@ -1,16 +1,41 @@
 use crate::{MemoryType, Pages, TableType};
-use std::cmp::min;
+use std::cmp::{max, min};
+use std::fmt;
+use std::ptr::{self, NonNull};
 use std::sync::Arc;
 use target_lexicon::{OperatingSystem, PointerWidth};
 use wasmer_compiler::Target;
 use wasmer_engine::Tunables as BaseTunables;
 use wasmer_vm::MemoryError;
-use wasmer_vm::{LinearMemory, LinearTable, Memory, MemoryStyle, Table, TableStyle};
+use wasmer_vm::{
+    LinearMemory, LinearTable, Memory, MemoryCreator, MemoryStyle, Table, TableCreator, TableStyle,
+    Trap, VMCallerCheckedAnyfunc, VMMemoryDefinition, VMTableDefinition,
+};
+
+/// Whether a memory or a table may grow, as decided by the grow hooks of
+/// the [`Tunables`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrowDecision {
+    /// Attempt to grow, which may still fail.
+    Allow,
+    /// Fail to grow, as if there wasn't enough memory left.
+    Deny,
+}
+
+/// A hook deciding whether a memory of `current` pages may grow by `delta`
+/// pages.
+pub type MemoryGrowHook = dyn Fn(Pages, Pages) -> GrowDecision + Send + Sync;
+
+/// A hook deciding whether a table of `current` elements may grow by
+/// `delta` elements.
+pub type TableGrowHook = dyn Fn(u32, u32) -> GrowDecision + Send + Sync;
 
 /// Tunable parameters for WebAssembly compilation.
 #[derive(Clone)]
 pub struct Tunables {
     /// For static heaps, the size in wasm pages of the heap protected by bounds checking.
+    ///
+    /// A bound of `0` pages disables static heaps: every memory is dynamic.
     pub static_memory_bound: Pages,
 
     /// The size in bytes of the offset guard for static heaps.
@@ -18,6 +43,67 @@ pub struct Tunables {
 
     /// The size in bytes of the offset guard for dynamic heaps.
     pub dynamic_memory_offset_guard_size: u64,
+
+    /// Whether the memories track the pages written to, which can be
+    /// retrieved with [`Memory::take_dirty_pages`].
+    ///
+    /// This is ignored when a `memory_creator` is set.
+    ///
+    /// [`Memory::take_dirty_pages`]: crate::Memory::take_dirty_pages
+    pub track_dirty_pages: bool,
+
+    /// Whether the memories returned by the `memory_creator` are zeroed
+    /// before being handed to an instance, as WebAssembly requires new
+    /// memories to be.
+    ///
+    /// This is needed when the `memory_creator` reuses the buffers of
+    /// dropped memories, which would otherwise leak their contents to the
+    /// next instance. It can be disabled if the `memory_creator` always
+    /// returns zeroed memories. Memories created with the default
+    /// [`LinearMemory`] are freshly mapped, so they are never zeroed again.
+    pub zero_on_reuse: bool,
+
+    /// The size in bytes from which the pages of a data initializer are
+    /// faulted in at once before the initializer is applied, instead of
+    /// one page fault at a time while it is copied.
+    ///
+    /// This speeds up the instantiation of modules with large data
+    /// segments, but doesn't pay for small ones. `None` disables it. This
+    /// is only supported on Linux 5.14 and later, and ignored elsewhere.
+    pub data_prefault_threshold: Option<usize>,
+
+    /// Chooses the style of the memories it returns a style for, instead
+    /// of the bounds and guard sizes above. This can give a memory a
+    /// smaller reservation than the other ones, for instance.
+    ///
+    /// Shared memories must not move when they grow, so they should be
+    /// given a static style bounded by their maximum.
+    pub memory_style_override:
+        Option<Arc<dyn Fn(&MemoryType) -> Option<MemoryStyle> + Send + Sync>>,
+
+    /// The allocator used to create the memories, instead of the default
+    /// mmap-based [`LinearMemory`].
+    pub memory_creator: Option<Arc<dyn MemoryCreator>>,
+
+    /// The allocator used to create the tables, instead of the default
+    /// [`LinearTable`].
+    pub table_creator: Option<Arc<dyn TableCreator>>,
+
+    /// Consulted before every growth of the memories, see
+    /// [`Tunables::with_memory_grow_hook`].
+    pub memory_grow_hook: Option<Arc<MemoryGrowHook>>,
+
+    /// Consulted before every growth of the tables, see
+    /// [`Tunables::with_table_grow_hook`].
+    pub table_grow_hook: Option<Arc<TableGrowHook>>,
+
+    /// The number of pages the memories can't exceed, see
+    /// [`Tunables::with_memory_limit`].
+    pub memory_limit: Option<Pages>,
+
+    /// The number of elements the tables can't exceed, see
+    /// [`Tunables::with_table_limit`].
+    pub table_limit: Option<u32>,
 }
 
 impl Tunables {
@@ -27,8 +113,10 @@ impl Tunables {
         let pointer_width: PointerWidth = triple.pointer_width().unwrap();
         let (mut static_memory_bound, mut static_memory_offset_guard_size): (Pages, u64) =
             match pointer_width {
-                PointerWidth::U16 => (0x400.into(), 0x1000),
-                PointerWidth::U32 => (0x4000.into(), 0x1_0000),
+                // There isn't enough address space to reserve static
+                // memories on 16 and 32-bit hosts, so all the memories
+                // are dynamic there.
+                PointerWidth::U16 | PointerWidth::U32 => (0.into(), 0),
                 // Static Memory Bound:
                 //   Allocating 4 GiB of address space let us avoid the
                 //   need for explicit bounds checks.
@@ -53,19 +141,155 @@ impl Tunables {
             static_memory_bound,
             static_memory_offset_guard_size,
             dynamic_memory_offset_guard_size,
+            track_dirty_pages: false,
+            zero_on_reuse: true,
+            data_prefault_threshold: Some(0x10_0000),
+            memory_style_override: None,
+            memory_creator: None,
+            table_creator: None,
+            memory_grow_hook: None,
+            table_grow_hook: None,
+            memory_limit: None,
+            table_limit: None,
         }
     }
+
+    /// Use `style` to choose the style of the memories, falling back to the
+    /// bounds and guard sizes of the tunables for the memories it returns
+    /// `None` for.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{MemoryStyle, Pages, Store, Tunables};
+    /// # let engine = Store::default().engine().clone();
+    /// // Reserve 16 MiB for the small memories, rather than 4 GiB.
+    /// let tunables = Tunables::for_target(engine.target()).with_memory_style(|memory| {
+    ///     match memory.maximum {
+    ///         Some(maximum) if maximum <= Pages(256) => Some(MemoryStyle::Static {
+    ///             bound: Pages(256),
+    ///             offset_guard_size: 0x1_0000,
+    ///         }),
+    ///         _ => None,
+    ///     }
+    /// });
+    /// let store = Store::new_with_tunables(&*engine, tunables);
+    /// ```
+    pub fn with_memory_style(
+        mut self,
+        style: impl Fn(&MemoryType) -> Option<MemoryStyle> + Send + Sync + 'static,
+    ) -> Self {
+        self.memory_style_override = Some(Arc::new(style));
+        self
+    }
+
+    /// Use a custom [`MemoryCreator`] to create the memories.
+    pub fn with_memory_creator(mut self, memory_creator: impl MemoryCreator + 'static) -> Self {
+        self.memory_creator = Some(Arc::new(memory_creator));
+        self
+    }
+
+    /// Use a custom [`TableCreator`] to create the tables.
+    pub fn with_table_creator(mut self, table_creator: impl TableCreator + 'static) -> Self {
+        self.table_creator = Some(Arc::new(table_creator));
+        self
+    }
+
+    /// Call `hook` with the current size and the delta, in pages, before
+    /// a memory grows, whether it's grown by `memory.grow` in an instance
+    /// defining or importing it, or by the host. When the hook denies it,
+    /// the growth fails, and `memory.grow` returns -1.
+    ///
+    /// This injects failures to test how modules behave when they run out
+    /// of memory, without exhausting the memory of the host.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{GrowDecision, Pages, Store, Tunables};
+    /// # let engine = Store::default().engine().clone();
+    /// // Let the memories grow up to 16 pages.
+    /// let tunables = Tunables::for_target(engine.target()).with_memory_grow_hook(
+    ///     |current, delta| {
+    ///         if current.0 + delta.0 <= 16 {
+    ///             GrowDecision::Allow
+    ///         } else {
+    ///             GrowDecision::Deny
+    ///         }
+    ///     },
+    /// );
+    /// let store = Store::new_with_tunables(&*engine, tunables);
+    /// ```
+    pub fn with_memory_grow_hook(
+        mut self,
+        hook: impl Fn(Pages, Pages) -> GrowDecision + Send + Sync + 'static,
+    ) -> Self {
+        self.memory_grow_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Call `hook` with the current size and the delta, in elements,
+    /// before a table grows. When the hook denies it, the growth fails.
+    ///
+    /// The compilers don't support `table.grow` yet, so only the growths
+    /// from the host reach the hook.
+    pub fn with_table_grow_hook(
+        mut self,
+        hook: impl Fn(u32, u32) -> GrowDecision + Send + Sync + 'static,
+    ) -> Self {
+        self.table_grow_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Limit the memories to `limit` pages.
+    ///
+    /// Creating a memory whose minimum exceeds the limit fails with
+    /// [`MemoryError::MinimumExceedsLimit`], which makes the instantiation
+    /// of a module defining it fail with a [`LinkError::Memory`]. The
+    /// memories can't grow beyond the limit either, whatever their
+    /// maximum.
+    ///
+    /// [`LinkError::Memory`]: crate::LinkError::Memory
+    pub fn with_memory_limit(mut self, limit: Pages) -> Self {
+        self.memory_limit = Some(limit);
+        self
+    }
+
+    /// Limit the tables to `limit` elements.
+    ///
+    /// Creating a table whose minimum exceeds the limit fails, as does
+    /// the instantiation of a module defining it. The tables can't grow
+    /// beyond the limit either, whatever their maximum.
+    pub fn with_table_limit(mut self, limit: u32) -> Self {
+        self.table_limit = Some(limit);
+        self
+    }
 }
 
 impl BaseTunables for Tunables {
     /// Get a `MemoryStyle` for the provided `MemoryType`
     fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        if let Some(style) = self
+            .memory_style_override
+            .as_ref()
+            .and_then(|style| style(memory))
+        {
+            return style;
+        }
+
         // A heap with a maximum that doesn't exceed the static memory bound specified by the
         // tunables make it static.
         //
         // If the module doesn't declare an explicit maximum treat it as 4GiB.
         let maximum = memory.maximum.unwrap_or_else(Pages::max_value);
-        if maximum <= self.static_memory_bound {
+        if memory.shared {
+            // Shared memories can't move when they grow, so they reserve
+            // their maximum up front, even beyond the static memory bound.
+            MemoryStyle::Static {
+                bound: max(maximum, self.static_memory_bound),
+                offset_guard_size: self.static_memory_offset_guard_size,
+            }
+        } else if self.static_memory_bound > Pages(0) && maximum <= self.static_memory_bound {
             MemoryStyle::Static {
                 bound: self.static_memory_bound,
                 offset_guard_size: self.static_memory_offset_guard_size,
@@ -77,6 +301,11 @@ impl BaseTunables for Tunables {
         }
     }
 
+    /// Get the size from which the data initializers are prefaulted.
+    fn data_prefault_threshold(&self) -> Option<usize> {
+        self.data_prefault_threshold
+    }
+
     /// Get a [`TableStyle`] for the provided [`TableType`].
     fn table_style(&self, _table: &TableType) -> TableStyle {
         TableStyle::CallerChecksSignature
@@ -88,11 +317,196 @@ impl BaseTunables for Tunables {
         ty: &MemoryType,
         style: &MemoryStyle,
     ) -> Result<Arc<dyn Memory>, MemoryError> {
-        Ok(Arc::new(LinearMemory::new(&ty, &style)?))
+        if let Some(limit) = self.memory_limit {
+            if ty.minimum > limit {
+                return Err(MemoryError::MinimumExceedsLimit {
+                    declared: ty.minimum,
+                    limit,
+                });
+            }
+        }
+        let memory = self.create_unhooked_memory(ty, style)?;
+        if self.memory_grow_hook.is_none() && self.memory_limit.is_none() {
+            return Ok(memory);
+        }
+        Ok(Arc::new(HookedMemory {
+            memory,
+            hook: self.memory_grow_hook.clone(),
+            limit: self.memory_limit,
+        }))
     }
 
     /// Create a table given a [`TableType`] and a [`TableStyle`].
     fn create_table(&self, ty: &TableType, style: &TableStyle) -> Result<Arc<dyn Table>, String> {
-        Ok(Arc::new(LinearTable::new(&ty, &style)?))
+        if let Some(limit) = self.table_limit {
+            if ty.minimum > limit {
+                return Err(format!(
+                    "The minimum of the table ({} elements) exceeds the limit ({} elements)",
+                    ty.minimum, limit
+                ));
+            }
+        }
+        let table: Arc<dyn Table> = match &self.table_creator {
+            Some(creator) => creator.new_table(&ty, &style)?.into(),
+            None => Arc::new(LinearTable::new(&ty, &style)?),
+        };
+        if self.table_grow_hook.is_none() && self.table_limit.is_none() {
+            return Ok(table);
+        }
+        Ok(Arc::new(HookedTable {
+            table,
+            hook: self.table_grow_hook.clone(),
+            limit: self.table_limit,
+        }))
+    }
+}
+
+impl Tunables {
+    /// Creates a memory with the `memory_creator`, or a [`LinearMemory`],
+    /// before it's given the `memory_grow_hook`.
+    fn create_unhooked_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        match &self.memory_creator {
+            Some(creator) => {
+                let memory = creator.new_memory(&ty, &style)?;
+                if self.zero_on_reuse {
+                    // Safety: the definition of the memory is valid and
+                    // nothing else can access its `current_length` bytes
+                    // yet.
+                    unsafe {
+                        let definition = memory.vmmemory().as_ptr();
+                        let length = (*definition).current_length as usize;
+                        ptr::write_bytes((*definition).base, 0, length);
+                    }
+                }
+                Ok(memory.into())
+            }
+            // Shared memories are backed by a shareable mapping, like the
+            // memories created by the host.
+            #[cfg(target_os = "linux")]
+            None if ty.shared => Ok(Arc::new(LinearMemory::new_shareable(&ty, &style)?)),
+            None if self.track_dirty_pages => Ok(Arc::new(
+                LinearMemory::new_with_dirty_page_tracking(&ty, &style)?,
+            )),
+            None => Ok(Arc::new(LinearMemory::new(&ty, &style)?)),
+        }
+    }
+}
+
+/// A memory whose growth is first checked against a limit, then
+/// submitted to a hook.
+struct HookedMemory {
+    memory: Arc<dyn Memory>,
+    hook: Option<Arc<MemoryGrowHook>>,
+    limit: Option<Pages>,
+}
+
+impl fmt::Debug for HookedMemory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.memory, f)
+    }
+}
+
+impl Memory for HookedMemory {
+    fn ty(&self) -> &MemoryType {
+        self.memory.ty()
+    }
+
+    fn style(&self) -> &MemoryStyle {
+        self.memory.style()
+    }
+
+    fn size(&self) -> Pages {
+        self.memory.size()
+    }
+
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let current = self.memory.size();
+        let within_limit = self.limit.map_or(true, |limit| {
+            u64::from(current.0) + u64::from(delta.0) <= u64::from(limit.0)
+        });
+        let decision = match &self.hook {
+            _ if !within_limit => GrowDecision::Deny,
+            Some(hook) => hook(current, delta),
+            None => GrowDecision::Allow,
+        };
+        match decision {
+            GrowDecision::Allow => self.memory.grow(delta),
+            GrowDecision::Deny => Err(MemoryError::CouldNotGrow {
+                current,
+                attempted_delta: delta,
+            }),
+        }
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        self.memory.vmmemory()
+    }
+
+    fn take_dirty_pages(&self) -> Option<Vec<u32>> {
+        self.memory.take_dirty_pages()
+    }
+
+    fn readonly_view(&self) -> Result<Box<dyn Memory>, MemoryError> {
+        self.memory.readonly_view()
+    }
+}
+
+/// A table whose growth is first checked against a limit, then
+/// submitted to a hook.
+struct HookedTable {
+    table: Arc<dyn Table>,
+    hook: Option<Arc<TableGrowHook>>,
+    limit: Option<u32>,
+}
+
+impl fmt::Debug for HookedTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.table, f)
+    }
+}
+
+impl Table for HookedTable {
+    fn style(&self) -> &TableStyle {
+        self.table.style()
+    }
+
+    fn ty(&self) -> &TableType {
+        self.table.ty()
+    }
+
+    fn size(&self) -> u32 {
+        self.table.size()
+    }
+
+    fn grow(&self, delta: u32) -> Option<u32> {
+        let current = self.table.size();
+        let within_limit = self.limit.map_or(true, |limit| {
+            u64::from(current) + u64::from(delta) <= u64::from(limit)
+        });
+        let decision = match &self.hook {
+            _ if !within_limit => GrowDecision::Deny,
+            Some(hook) => hook(current, delta),
+            None => GrowDecision::Allow,
+        };
+        match decision {
+            GrowDecision::Allow => self.table.grow(delta),
+            GrowDecision::Deny => None,
+        }
+    }
+
+    fn get(&self, index: u32) -> Option<VMCallerCheckedAnyfunc> {
+        self.table.get(index)
+    }
+
+    fn set(&self, index: u32, func: VMCallerCheckedAnyfunc) -> Result<(), Trap> {
+        self.table.set(index, func)
+    }
+
+    fn vmtable(&self) -> NonNull<VMTableDefinition> {
+        self.table.vmtable()
     }
 }
@@ -1,11 +1,34 @@
+//! The WebAssembly types used by the Wasmer API.
+//!
+//! This module is the stable facade over the internal `wasmer-types`
+//! crate: embedders should import types, units and indexes from here (or
+//! from the crate root) rather than depending on `wasmer-types` directly.
+//! Everything re-exported here follows the semver guarantees of the
+//! `wasmer` crate, even if the underlying crate changes.
+//!
+//! ```
+//! use wasmer::types::{EntityRef, FunctionIndex, FunctionType, Pages, ValType};
+//!
+//! let ty = FunctionType::new(vec![ValType::I32], vec![ValType::I64]);
+//! assert_eq!(ty.params(), &[ValType::I32]);
+//! assert_eq!(Pages(1).bytes().0, 65536);
+//! assert_eq!(FunctionIndex::new(3).index(), 3);
+//! ```
+
 use crate::externals::Function;
 use crate::store::{Store, StoreObject};
 use crate::RuntimeError;
 use std::ptr;
+pub use wasmer_types::entity::EntityRef;
 use wasmer_types::Value;
+pub use wasmer_types::{
+    Bytes, ConstExpr, ConstOp, ExportIndex, FunctionIndex, GlobalIndex, GlobalInit, ImportIndex,
+    LocalFunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex, Pages,
+    TableIndex, V128, WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
+};
 pub use wasmer_types::{
     ExportType, ExternRef, ExternType, FunctionType, GlobalType, HostInfo, HostRef, ImportType,
-    MemoryType, Mutability, TableType, Type as ValType,
+    MemoryType, Mutability, ParseFunctionTypeError, TableType, Type as ValType,
 };
 
 /// WebAssembly computations manipulate values of basic value types:
@@ -35,7 +58,7 @@ impl From<Function> for Val {
 
 /// It provides useful functions for converting back and forth
 /// from [`Val`] into `FuncRef`.
-pub trait ValFuncRef {
+pub(crate) trait ValFuncRef {
     fn into_checked_anyfunc(
         &self,
         store: &Store,
@@ -78,6 +101,8 @@ impl ValFuncRef for Val {
             // are converted to use the trampolines with static signatures).
             kind: wasmer_vm::VMFunctionKind::Static,
             vmctx: item.vmctx,
+            // The tables don't keep whether their functions return.
+            noreturn: false,
         };
         let f = Function::from_export(store, export);
         Self::FuncRef(f)
@@ -58,6 +58,27 @@ fn global_set() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn v128_global_initializer() -> Result<()> {
+    let mut features = Features::default();
+    features.simd(true);
+    let engine = JIT::new(&Cranelift::default()).features(features).engine();
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module
+    (global (export "lanes") v128 (v128.const i32x4 1 2 3 4)))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let lanes = instance.exports.get_global("lanes")?;
+    assert_eq!(lanes.ty().ty, Type::V128);
+    assert_eq!(
+        lanes.get().unwrap_v128(),
+        0x0000_0004_0000_0003_0000_0002_0000_0001
+    );
+    Ok(())
+}
+
 #[test]
 fn table_new() -> Result<()> {
     let store = Store::default();
@@ -134,6 +155,160 @@ fn table_copy() -> Result<()> {
     Ok(())
 }
 
+fn funcref(value: Option<Value>) -> Function {
+    match value {
+        Some(Value::FuncRef(function)) => function,
+        _ => panic!("the table element should be a function"),
+    }
+}
+
+/// A module whose exported `get` function returns its instance's `state`.
+const STATE_WAT: &str = r#"(module
+    (global $state (export "state") (mut i32) (i32.const 0))
+    (func (export "get") (result i32) (global.get $state))
+)"#;
+
+/// A module calling the functions of its exported table.
+const CALLER_WAT: &str = r#"(module
+    (type $get (func (result i32)))
+    (table (export "table") 2 funcref)
+    (func (export "call") (param i32) (result i32)
+        (call_indirect (type $get) (local.get 0)))
+)"#;
+
+#[test]
+fn table_function_identity() -> Result<()> {
+    let store = Store::default();
+    let instance = Instance::new(&Module::new(&store, STATE_WAT)?, &imports! {})?;
+    let get = instance.exports.get_function("get")?;
+    let caller = Instance::new(&Module::new(&store, CALLER_WAT)?, &imports! {})?;
+    let table = caller.exports.get_table("table")?;
+
+    // The same function installed twice gives equal functions back.
+    table.set(0, Value::FuncRef(get.clone()))?;
+    table.set(1, Value::FuncRef(get.clone()))?;
+    assert_eq!(funcref(table.get(0)), funcref(table.get(1)));
+    assert_eq!(&funcref(table.get(0)), get);
+    Ok(())
+}
+
+#[test]
+fn imported_function_identity() -> Result<()> {
+    let store = Store::default();
+    let instance = Instance::new(&Module::new(&store, STATE_WAT)?, &imports! {})?;
+    let get = instance.exports.get_function("get")?;
+    let wat = r#"(module
+    (import "env" "get" (func $get (result i32)))
+    (table (export "table") 1 funcref)
+    (elem (i32.const 0) $get)
+    (export "get" (func $get))
+)"#;
+    let importer = Instance::new(
+        &Module::new(&store, wat)?,
+        &imports! {
+            "env" => {
+                "get" => get.clone(),
+            },
+        },
+    )?;
+
+    // Importing the function doesn't change its identity, whether it's
+    // re-exported or installed in a table.
+    assert_eq!(importer.exports.get_function("get")?, get);
+    assert_eq!(&funcref(importer.exports.get_table("table")?.get(0)), get);
+    Ok(())
+}
+
+#[test]
+fn functions_of_different_instances_differ() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, STATE_WAT)?;
+    let first = Instance::new(&module, &imports! {})?;
+    let second = Instance::new(&module, &imports! {})?;
+    first.exports.get_global("state")?.set(Value::I32(1))?;
+    second.exports.get_global("state")?.set(Value::I32(2))?;
+    let caller = Instance::new(&Module::new(&store, CALLER_WAT)?, &imports! {})?;
+    let table = caller.exports.get_table("table")?;
+
+    table.set(
+        0,
+        Value::FuncRef(first.exports.get_function("get")?.clone()),
+    )?;
+    table.set(
+        1,
+        Value::FuncRef(second.exports.get_function("get")?.clone()),
+    )?;
+    assert_ne!(funcref(table.get(0)), funcref(table.get(1)));
+
+    // Each function runs with the state of its own instance.
+    let call = caller.exports.get_native_function::<i32, i32>("call")?;
+    assert_eq!(call.call(0)?, 1);
+    assert_eq!(call.call(1)?, 2);
+    Ok(())
+}
+
+#[test]
+fn cloned_function_shares_env() -> Result<()> {
+    let store = Store::default();
+    fn increment(counter: &mut i32) -> i32 {
+        *counter += 1;
+        *counter
+    }
+    let function = Function::new_native_with_env(&store, 0, increment);
+    let clone = function.clone();
+    assert_eq!(clone, function);
+
+    assert_eq!(function.native::<(), i32>()?.call()?, 1);
+    assert_eq!(clone.native::<(), i32>()?.call()?, 2);
+    assert_eq!(function.native::<(), i32>()?.call()?, 3);
+    Ok(())
+}
+
+#[test]
+fn separately_created_host_functions_differ() -> Result<()> {
+    use std::collections::HashSet;
+
+    let store = Store::default();
+    fn one() -> i32 {
+        1
+    }
+    let first = Function::new_native(&store, one);
+    let second = Function::new_native(&store, one);
+    assert_ne!(first, second);
+    assert_ne!(
+        Function::new_native(&store, || 1),
+        Function::new_native(&store, || 1)
+    );
+    assert_ne!(
+        Function::new_native_with_env(&store, (), |_: &mut ()| 1),
+        Function::new_native_with_env(&store, (), |_: &mut ()| 1)
+    );
+
+    // Hashing is consistent with equality.
+    let functions: HashSet<_> = vec![first.clone(), first.clone(), second.clone()]
+        .into_iter()
+        .collect();
+    assert_eq!(functions.len(), 2);
+    assert!(functions.contains(&first));
+    assert!(functions.contains(&second));
+    Ok(())
+}
+
+#[test]
+fn function_debug_hides_addresses() -> Result<()> {
+    let store = Store::default();
+    let function = Function::new_native_with_env(&store, 1, |env: &mut i32, a: i32| a + *env);
+    let debug = format!("{:?}", function);
+    assert!(debug.contains("ty: FunctionType"), "{}", debug);
+    assert!(!debug.contains("0x"), "{}", debug);
+
+    // The identifier is stable, and specific to the function.
+    assert_eq!(format!("{:?}", function.clone()), debug);
+    let other = Function::new_native_with_env(&store, 1, |env: &mut i32, a: i32| a + *env);
+    assert_ne!(format!("{:?}", other), debug);
+    Ok(())
+}
+
 #[test]
 fn memory_new() -> Result<()> {
     let store = Store::default();
@@ -177,6 +352,58 @@ fn memory_grow() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn memory_readonly_view() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+    (import "env" "memory" (memory 1))
+    (func (export "load") (param i32) (result i32)
+        (i32.load (local.get 0)))
+    (func (export "store") (param i32 i32)
+        (i32.store (local.get 0) (local.get 1)))
+    (func (export "grow") (param i32) (result i32)
+        (memory.grow (local.get 0)))
+)"#,
+    )?;
+    let memory = Memory::new_shareable(&store, MemoryType::new(Pages(1), None, false))?;
+    let view = Memory::new_readonly_view(&memory)?;
+    let writer = Instance::new(&module, &imports! { "env" => { "memory" => memory } })?;
+    let reader = Instance::new(&module, &imports! { "env" => { "memory" => view } })?;
+
+    let store_fn = writer.exports.get_function("store")?;
+    store_fn.call(&[Val::I32(16), Val::I32(1234)])?;
+    let load = reader.exports.get_function("load")?;
+    assert_eq!(load.call(&[Val::I32(16)])?.to_vec(), vec![Val::I32(1234)]);
+
+    let error = reader
+        .exports
+        .get_function("store")?
+        .call(&[Val::I32(16), Val::I32(5678)])
+        .unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::ReadOnlyMemory));
+    assert_eq!(error.message(), "write to read-only memory");
+    assert_eq!(load.call(&[Val::I32(16)])?.to_vec(), vec![Val::I32(1234)]);
+
+    let grow = reader.exports.get_function("grow")?;
+    assert_eq!(grow.call(&[Val::I32(1)])?.to_vec(), vec![Val::I32(-1)]);
+
+    // Out of bounds accesses to the view are still reported as such.
+    let error = load.call(&[Val::I32(65536)]).unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+
+    // Only shareable memories can be viewed read-only.
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), None, false))?;
+    assert_eq!(
+        Memory::new_readonly_view(&memory).unwrap_err(),
+        MemoryError::NotShareable
+    );
+
+    Ok(())
+}
+
 #[test]
 fn function_new() -> Result<()> {
     let store = Store::default();
@@ -202,6 +429,45 @@ fn function_new() -> Result<()> {
         function.ty().clone(),
         FunctionType::new(vec![], vec![Type::I32, Type::I64, Type::F32, Type::F64])
     );
+    let function = Function::new_native(
+        &store,
+        |a: i32, b: i64, c: f32, d: f64, e: i32, f: i64, g: f32, h: f64, i: i32, j: i64| {
+            f64::from(a)
+                + b as f64
+                + f64::from(c)
+                + d
+                + f64::from(e)
+                + f as f64
+                + f64::from(g)
+                + h
+                + f64::from(i)
+                + j as f64
+        },
+    );
+    assert_eq!(
+        function.ty().clone(),
+        FunctionType::new(
+            vec![
+                Type::I32,
+                Type::I64,
+                Type::F32,
+                Type::F64,
+                Type::I32,
+                Type::I64,
+                Type::F32,
+                Type::F64,
+                Type::I32,
+                Type::I64
+            ],
+            vec![Type::F64]
+        )
+    );
+    let native_function: NativeFunc<(i32, i64, f32, f64, i32, i64, f32, f64, i32, i64), f64> =
+        function.native()?;
+    assert_eq!(
+        native_function.call(1, 2, 3.0, 4.0, 5, 6, 7.0, 8.0, 9, 10)?,
+        55.0
+    );
     Ok(())
 }
 
@@ -246,6 +512,57 @@ fn function_new_env() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn function_new_noreturn() -> Result<()> {
+    #[derive(Debug)]
+    struct Exit(i32);
+
+    impl std::fmt::Display for Exit {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "exit({})", self.0)
+        }
+    }
+
+    impl std::error::Error for Exit {}
+
+    fn exit(code: i32) {
+        RuntimeError::raise(Box::new(Exit(code)));
+    }
+
+    let store = Store::default();
+    let function = Function::new_noreturn(&store, exit);
+    assert!(function.is_noreturn());
+    assert_eq!(
+        function.ty().clone(),
+        FunctionType::new(vec![Type::I32], vec![])
+    );
+    assert!(!Function::new_native(&store, exit).is_noreturn());
+
+    let wat = r#"(module
+    (import "env" "exit" (func $exit (param i32)))
+    (export "exit" (func $exit))
+    (func (export "run") (result i32)
+        (call $exit (i32.const 3))
+        (i32.const 1))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "exit" => function,
+            },
+        },
+    )?;
+    // The marker is kept through the import resolution.
+    assert!(instance.exports.get_function("exit")?.is_noreturn());
+    assert!(!instance.exports.get_function("run")?.is_noreturn());
+    let run = instance.exports.get_native_function::<(), i32>("run")?;
+    let error = run.call().unwrap_err();
+    assert_eq!(error.downcast::<Exit>().unwrap().0, 3);
+    Ok(())
+}
+
 #[test]
 fn function_new_dynamic() -> Result<()> {
     let store = Store::default();
@@ -317,6 +634,101 @@ fn function_new_dynamic_env() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn function_new_with_signature() -> Result<()> {
+    let store = Store::default();
+    let function = Function::new_with_signature(&store, "(i32, i64) -> f64", |values| {
+        Ok(vec![Value::F64(
+            values[0].unwrap_i32() as f64 + values[1].unwrap_i64() as f64,
+        )])
+    })?;
+    assert_eq!(
+        function.ty().clone(),
+        FunctionType::new(vec![Type::I32, Type::I64], vec![Type::F64])
+    );
+
+    let wat = r#"(module
+    (import "env" "add" (func $add (param i32 i64) (result f64)))
+    (func (export "run") (result f64)
+        (call $add (i32.const 2) (i64.const 40)))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "add" => function,
+            },
+        },
+    )?;
+    let run = instance.exports.get_function("run")?;
+    assert_eq!(run.call(&[])?.to_vec(), vec![Value::F64(42.0)]);
+
+    let error = Function::new_with_signature(&store, "(i32, i46) -> f64", |_| unimplemented!())
+        .err()
+        .unwrap();
+    assert_eq!(error.position, 6);
+    assert_eq!(error.to_string(), "unknown type `i46` at position 6");
+    assert_eq!(
+        Function::new_with_signature(&store, "(i32) f64", |_| unimplemented!())
+            .err()
+            .unwrap()
+            .to_string(),
+        "expected `->` at position 6"
+    );
+    Ok(())
+}
+
+#[test]
+fn function_new_with_results() -> Result<()> {
+    let store = Store::default();
+    let function_type = "(i32, i32) -> (i32, i64)".parse::<FunctionType>()?;
+    let function = Function::new_with_results(&store, &function_type, |args, results| {
+        assert_eq!(results, &[Value::I32(0), Value::I64(0)]);
+        let sum = args[0].unwrap_i32() + args[1].unwrap_i32();
+        results[0] = Value::I32(sum);
+        results[1] = Value::I64(sum as i64 * 2);
+        Ok(())
+    });
+    assert_eq!(function.ty().clone(), function_type);
+    let native = function.native::<(i32, i32), (i32, i64)>()?;
+    assert_eq!(native.call(3, 4)?, (7, 14));
+
+    let wat = r#"(module
+    (import "env" "f" (func $f (param i32 i32) (result i32 i64)))
+    (func (export "run") (result i64)
+        (call $f (i32.const 20) (i32.const 1))
+        (drop)
+        (i64.extend_i32_s))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "f" => function,
+            },
+        },
+    )?;
+    let run = instance.exports.get_function("run")?;
+    assert_eq!(run.call(&[])?.to_vec(), vec![Value::I64(21)]);
+
+    // Writing a result of the wrong type is an error.
+    let function = Function::new_with_results(&store, &function_type, |_args, results| {
+        results[1] = Value::F32(1.0);
+        Ok(())
+    });
+    let error = function
+        .native::<(i32, i32), (i32, i64)>()?
+        .call(1, 2)
+        .err()
+        .unwrap();
+    assert!(error
+        .message()
+        .starts_with("Dynamic function returned wrong signature"));
+    Ok(())
+}
+
 #[test]
 fn native_function_works() -> Result<()> {
     let store = Store::default();
@@ -354,3 +766,36 @@ fn native_function_works() -> Result<()> {
     */
     Ok(())
 }
+
+#[test]
+fn static_function_calls_trapping_export() -> Result<()> {
+    use wasmer::internals::StaticFunction;
+
+    struct Env {
+        guest: Function,
+    }
+
+    fn host(env: &mut Env) -> Result<i32, RuntimeError> {
+        env.guest.call(&[]).map(|_| 0)
+    }
+
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+    (memory 1)
+    (func (export "load") (result i32)
+        (i32.load (i32.const 0x7fff_fff0))))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let mut env = Env {
+        guest: instance.exports.get_function("load")?.clone(),
+    };
+
+    // The out-of-bounds access faults in the guest, under the call of the
+    // host function, which has no instance.
+    let function = StaticFunction::new(host);
+    let error = unsafe { function.call_with_env(&mut env) }.unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+    Ok(())
+}
@@ -0,0 +1,45 @@
+fn import_object(store: &Store) -> ImportObject {
+    fn env_log(_: i32, _: i64) -> f32 {
+        todo!("env.log")
+    }
+
+    fn env_pair() -> (i32, f64) {
+        todo!("env.pair")
+    }
+
+    // Imported as "env" "a-b".
+    fn env_a_b() {
+        todo!("env.a-b")
+    }
+
+    // Imported as "env" "a_b".
+    fn env_a_b_2() {
+        todo!("env.a_b")
+    }
+
+    // Imported as "env" "{weird}".
+    fn env_weird() {
+        todo!("env.{{weird}}")
+    }
+
+    // Imported as "wasi snapshot" "fd_write".
+    fn wasi_snapshot_fd_write(_: i32, _: i32, _: i32, _: i32) -> i32 {
+        todo!("wasi snapshot.fd_write")
+    }
+
+    imports! {
+        "env" => {
+            "log" => Function::new_native(store, env_log),
+            "pair" => Function::new_native(store, env_pair),
+            "a-b" => Function::new_native(store, env_a_b),
+            "a_b" => Function::new_native(store, env_a_b_2),
+            "memory" => Memory::new(store, MemoryType::new(Pages(1), Some(Pages(2)), false)).unwrap(),
+            "table" => Table::new(store, TableType::new(Type::FuncRef, 2, None), Value::ExternRef(ExternRef::Null)).unwrap(),
+            "counter" => Global::new_mut(store, Value::I64(0)),
+            "{weird}" => Function::new_native(store, env_weird),
+        },
+        "wasi snapshot" => {
+            "fd_write" => Function::new_native(store, wasi_snapshot_fd_write),
+        },
+    }
+}
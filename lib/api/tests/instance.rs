@@ -0,0 +1,389 @@
+use anyhow::Result;
+use wasmer::*;
+
+#[test]
+fn export_names_of_doubly_exported_function() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+    (func $f nop)
+    (memory $m 1)
+    (export "first" (func $f))
+    (export "memory" (memory $m))
+    (export "second" (func $f))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let func: Extern = instance.exports.get_function("second")?.clone().into();
+    assert_eq!(
+        instance.export_names_of(&func).collect::<Vec<_>>(),
+        vec!["first", "second"]
+    );
+    assert_eq!(instance.export_name_of(&func), Some("first"));
+
+    let memory: Extern = instance.exports.get_memory("memory")?.clone().into();
+    assert_eq!(instance.export_name_of(&memory), Some("memory"));
+
+    Ok(())
+}
+
+#[test]
+fn export_name_of_host_function() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, r#"(module (func (export "f") nop))"#)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let host: Extern = Function::new_native(&store, || {}).into();
+    assert_eq!(instance.export_name_of(&host), None);
+    assert_eq!(instance.export_names_of(&host).count(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn function_code_of_exports() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+    (import "host" "f" (func $imported))
+    (func $local (result i32) (i32.const 42))
+    (memory $m 1)
+    (export "imported" (func $imported))
+    (export "local" (func $local))
+    (export "memory" (memory $m))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let imports = imports! {
+        "host" => {
+            "f" => Function::new_native(&store, || {}),
+        },
+    };
+    let instance = Instance::new(&module, &imports)?;
+
+    let code = instance.function_code("local").unwrap();
+    assert!(!code.is_empty());
+    #[cfg(target_arch = "x86_64")]
+    {
+        // Functions start by saving the frame pointer (`push rbp`, maybe
+        // with a REX prefix), adjusting the stack with a REX.W instruction,
+        // or with an `endbr64`.
+        assert!([0x55, 0x40, 0x48, 0xf3].contains(&code[0]), "{:x?}", code);
+    }
+
+    assert!(instance.function_code("imported").is_none());
+    assert!(instance.function_code("memory").is_none());
+    assert!(instance.function_code("missing").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn dump_vmctx_of_memory_and_global() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+    (memory (export "memory") 2)
+    (global (export "counter") (mut i64) (i64.const 1234567))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let dump = instance.dump_vmctx();
+    let row = |field: &str| -> String {
+        dump.lines()
+            .find(|line| line.split_whitespace().nth(1) == Some(field))
+            .unwrap_or_else(|| panic!("no `{}` row in:\n{}", field, dump))
+            .to_string()
+    };
+    assert!(dump.starts_with("offset"), "{}", dump);
+    assert!(row("memories[0].current_length").ends_with(" 131072"));
+    let memory = instance.exports.get_memory("memory")?;
+    assert!(row("memories[0].base").ends_with(&format!(" {:p}", memory.data_ptr())));
+    assert!(row("globals[0]").ends_with("(I64: 1234567)"));
+    assert!(dump.contains("builtin_functions[0]"));
+
+    // The dump reads the live values.
+    instance
+        .exports
+        .get_global("counter")?
+        .set(Value::I64(-42))?;
+    assert!(instance.dump_vmctx().contains("(I64: -42)"));
+
+    Ok(())
+}
+
+#[test]
+fn overlapping_data_segments() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+    (global $base (import "env" "base") i32)
+    (memory (export "memory") 1)
+    (data (i32.const 2) "\01\01\01\01\01\01")
+    (data (i32.const 0) "\02\02\02\02")
+    (data (i32.const 4) "\03")
+    (data (global.get $base) "\04\04")
+    (data (i32.const 3) "\05\05\05\05\05\05\05")
+    (data (i32.const 10) "\06\06")
+    (data (i32.const 20) "")
+    (data (i32.const 16) "\07")
+)"#;
+    let module = Module::new(&store, wat)?;
+    let import_object = imports! {
+        "env" => {
+            "base" => Global::new(&store, Value::I32(2)),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    let memory = instance.exports.get_memory("memory")?;
+
+    // The segments are applied in order, as if each was copied in turn.
+    let mut expected = vec![0u8; 20];
+    for (offset, data) in [
+        (2, &[1u8; 6][..]),
+        (0, &[2; 4]),
+        (4, &[3]),
+        (2, &[4; 2]),
+        (3, &[5; 7]),
+        (10, &[6; 2]),
+        (16, &[7]),
+    ]
+    .iter()
+    {
+        expected[*offset..*offset + data.len()].copy_from_slice(data);
+    }
+    let contents = memory.view::<u8>()[..20]
+        .iter()
+        .map(|cell| cell.get())
+        .collect::<Vec<_>>();
+    assert_eq!(contents, expected);
+
+    // An out of bounds segment still fails the whole instantiation.
+    let wat = r#"(module
+    (memory (export "memory") 1)
+    (data (i32.const 0) "\01\01")
+    (data (i32.const 65535) "\02\02")
+    (data (i32.const 1) "\03")
+)"#;
+    let module = Module::new(&store, wat)?;
+    assert!(Instance::new(&module, &imports! {}).is_err());
+
+    Ok(())
+}
+
+struct SeedMemory {
+    store: Store,
+    seen_by_before_start: std::cell::Cell<Option<u8>>,
+}
+
+impl SeedMemory {
+    fn memory(&self, handle: &InstanceHandle) -> Memory {
+        match Extern::from_export(&self.store, handle.lookup("memory").unwrap()) {
+            Extern::Memory(memory) => memory,
+            _ => panic!("`memory` should be a memory"),
+        }
+    }
+}
+
+impl InstantiationHooks for SeedMemory {
+    fn after_memories(&self, handle: &InstanceHandle) -> Result<(), RuntimeError> {
+        let memory = self.memory(handle);
+        let view = memory.view::<u8>();
+        for (cell, byte) in view[0..4].iter().zip(&[1, 2, 3, 4]) {
+            cell.set(*byte);
+        }
+        Ok(())
+    }
+
+    fn before_start(&self, handle: &InstanceHandle) -> Result<(), RuntimeError> {
+        let memory = self.memory(handle);
+        let view = memory.view::<u8>();
+        self.seen_by_before_start.set(Some(view[4].get()));
+        Ok(())
+    }
+}
+
+#[test]
+fn instantiation_hooks() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+    (memory $m (export "memory") 1)
+    (global $seeded (export "seeded") (mut i32) (i32.const 0))
+    (data (i32.const 4) "\2a")
+    (func $start
+        (global.set $seeded (i32.load (i32.const 0))))
+    (start $start)
+)"#;
+    let module = Module::new(&store, wat)?;
+    let hooks = SeedMemory {
+        store: store.clone(),
+        seen_by_before_start: Default::default(),
+    };
+    let instance = Instance::new_with_hooks(&module, &imports! {}, &hooks)?;
+
+    // The start function read the bytes written by `after_memories`, and
+    // `before_start` saw the data segment.
+    assert_eq!(
+        instance.exports.get_global("seeded")?.get(),
+        Value::I32(0x04030201)
+    );
+    assert_eq!(hooks.seen_by_before_start.get(), Some(0x2a));
+
+    Ok(())
+}
+
+struct FailingHook;
+
+impl InstantiationHooks for FailingHook {
+    fn before_start(&self, _handle: &InstanceHandle) -> Result<(), RuntimeError> {
+        Err(RuntimeError::new("can't set up the instance"))
+    }
+}
+
+#[test]
+fn failing_instantiation_hook() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, "(module)")?;
+    match Instance::new_with_hooks(&module, &imports! {}, &FailingHook) {
+        Err(InstantiationError::Start(error)) => {
+            assert_eq!(error.message(), "can't set up the instance")
+        }
+        _ => panic!("the hook should fail the instantiation"),
+    }
+    Ok(())
+}
+
+#[test]
+fn reexported_imports() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+    (import "host" "f" (func $f (result i32)))
+    (import "host" "memory" (memory $m 1))
+    (import "host" "global" (global $g i32))
+    (import "host" "table" (table $t 1 anyfunc))
+    (func $local (result i32) (i32.const 1))
+    (export "f" (func $f))
+    (export "local" (func $local))
+    (export "memory" (memory $m))
+    (export "global" (global $g))
+    (export "table" (table $t))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    let global = Global::new(&store, Value::I32(7));
+    let table = Table::new(
+        &store,
+        TableType::new(ValType::FuncRef, 1, None),
+        Value::FuncRef(Function::new_native(&store, || {})),
+    )?;
+    let imports = imports! {
+        "host" => {
+            "f" => Function::new_native(&store, || 42),
+            "memory" => memory.clone(),
+            "global" => global.clone(),
+            "table" => table.clone(),
+        },
+    };
+    let instance = Instance::new(&module, &imports)?;
+
+    let f = instance.exports.get_native_function::<(), i32>("f")?;
+    assert_eq!(f.call()?, 42);
+    let local = instance.exports.get_native_function::<(), i32>("local")?;
+    assert_eq!(local.call()?, 1);
+    assert!(instance.exports.get_memory("memory")?.same(&memory));
+    assert!(instance.exports.get_global("global")?.same(&global));
+    assert!(instance.exports.get_table("table")?.same(&table));
+    assert_eq!(instance.exports.len(), 5);
+    assert!(instance.exports.contains("table"));
+    assert!(!instance.exports.contains("missing"));
+    assert_eq!(
+        instance
+            .exports
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["f", "local", "memory", "global", "table"]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn insert_into_instance_exports() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module (global (export "g") i32 (i32.const 3)))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let mut exports = instance.exports.clone();
+    exports.insert("h", Global::new(&store, Value::I32(4)));
+    assert_eq!(exports.get_global("g")?.get(), Value::I32(3));
+    assert_eq!(exports.get_global("h")?.get(), Value::I32(4));
+    assert_eq!(exports.len(), 2);
+    // The instance's own exports are left untouched.
+    assert!(!instance.exports.contains("h"));
+
+    Ok(())
+}
+
+#[test]
+fn imported_memory_init() -> Result<()> {
+    let store = Store::default();
+    let owner = Instance::new(
+        &Module::new(
+            &store,
+            r#"(module
+    (memory (export "memory") 1)
+    (func (export "grow") (result i32) (memory.grow (i32.const 1))))"#,
+        )?,
+        &imports! {},
+    )?;
+    let memory = owner.exports.get_memory("memory")?;
+    let user = Instance::new(
+        &Module::new(
+            &store,
+            r#"(module
+    (import "env" "memory" (memory 1))
+    (data $hello "hello")
+    (func (export "init") (param i32 i32 i32)
+        (memory.init $hello (local.get 0) (local.get 1) (local.get 2)))
+    (func (export "drop") (data.drop $hello)))"#,
+        )?,
+        &imports! {
+            "env" => {
+                "memory" => memory.clone(),
+            },
+        },
+    )?;
+    let init = user
+        .exports
+        .get_native_function::<(i32, i32, i32), ()>("init")?;
+    let read = |offset: usize, len: usize| {
+        memory.view::<u8>()[offset..offset + len]
+            .iter()
+            .map(|cell| cell.get())
+            .collect::<Vec<_>>()
+    };
+
+    init.call(100, 1, 3)?;
+    assert_eq!(read(99, 5), b"\0ell\0");
+
+    // The destination is checked against the current size of the memory.
+    let page = WASM_PAGE_SIZE as i32;
+    assert!(init.call(page - 2, 0, 5).is_err());
+    owner
+        .exports
+        .get_native_function::<(), i32>("grow")?
+        .call()?;
+    init.call(page - 2, 0, 5)?;
+    assert_eq!(read(page as usize - 2, 5), b"hello");
+    assert!(init.call(2 * page - 2, 0, 5).is_err());
+
+    // The source is checked against the data segment.
+    assert!(init.call(0, 3, 3).is_err());
+    init.call(0, 5, 0)?;
+
+    user.exports.get_native_function::<(), ()>("drop")?.call()?;
+    assert!(init.call(0, 0, 1).is_err());
+    init.call(0, 0, 0)?;
+    Ok(())
+}
@@ -0,0 +1,73 @@
+#![cfg(target_arch = "x86_64")]
+
+use anyhow::Result;
+use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use wasmer::*;
+
+static CEIL_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn counting_ceil(x: f32) -> f32 {
+    CEIL_CALLS.fetch_add(1, SeqCst);
+    x.ceil()
+}
+
+#[test]
+fn override_libcall() -> Result<()> {
+    // Without SSE4.1, the rounding instructions are compiled to libcalls.
+    let target = Target::new(Triple::host(), CpuFeature::SSE2 | CpuFeature::SSE3);
+    let engine = JIT::new(&Cranelift::default())
+        .target(target)
+        .libcall_resolver(|libcall| match libcall {
+            LibCall::CeilF32 => Some(counting_ceil as usize),
+            _ => None,
+        })
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module
+    (func (export "ceil") (param f32) (result f32)
+        (f32.ceil (local.get 0)))
+    (func (export "floor") (param f32) (result f32)
+        (f32.floor (local.get 0))))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let ceil = instance.exports.get_native_function::<f32, f32>("ceil")?;
+    let floor = instance.exports.get_native_function::<f32, f32>("floor")?;
+
+    assert_eq!(ceil.call(1.5)?, 2.0);
+    assert_eq!(ceil.call(-1.5)?, -1.0);
+    assert_eq!(CEIL_CALLS.load(SeqCst), 2);
+    // The other libcalls resolve to the built-in functions.
+    assert_eq!(floor.call(1.5)?, 1.0);
+    assert_eq!(CEIL_CALLS.load(SeqCst), 2);
+    Ok(())
+}
+
+#[test]
+fn null_libcall() -> Result<()> {
+    let target = Target::new(Triple::host(), CpuFeature::SSE2 | CpuFeature::SSE3);
+    let engine = JIT::new(&Cranelift::default())
+        .target(target)
+        .libcall_resolver(|_| Some(0))
+        .engine();
+    let store = Store::new(&engine);
+    let error = Module::new(
+        &store,
+        r#"(module
+    (func (export "ceil") (param f32) (result f32)
+        (f32.ceil (local.get 0))))"#,
+    )
+    .unwrap_err();
+    assert!(
+        matches!(error, CompileError::UnsupportedTarget(_)),
+        "unexpected error: {}",
+        error
+    );
+    assert!(
+        error.to_string().contains("CeilF32"),
+        "unexpected error: {}",
+        error
+    );
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use anyhow::Result;
+use wasmer::*;
+
+fn mutually_importing_modules(store: &Store) -> Result<(Module, Module)> {
+    let a = Module::new(
+        store,
+        r#"(module
+    (import "b" "inc" (func $inc (param i32) (result i32)))
+    (func (export "dec") (param i32) (result i32)
+        (i32.sub (local.get 0) (i32.const 1)))
+    (func (export "inc_twice") (param i32) (result i32)
+        (call $inc (call $inc (local.get 0))))
+)"#,
+    )?;
+    let b = Module::new(
+        store,
+        r#"(module
+    (import "a" "dec" (func $dec (param i32) (result i32)))
+    (func (export "inc") (param i32) (result i32)
+        (i32.add (local.get 0) (i32.const 1)))
+    (func (export "dec_twice") (param i32) (result i32)
+        (call $dec (call $dec (local.get 0))))
+)"#,
+    )?;
+    Ok((a, b))
+}
+
+#[test]
+fn definition_cycle() -> Result<()> {
+    let store = Store::default();
+    let (a, b) = mutually_importing_modules(&store)?;
+    let mut linker = Linker::new(&store);
+    linker.register("a", a).register("b", b);
+
+    let error = linker.instantiate("a").unwrap_err();
+    assert!(matches!(&error, LinkerError::Cycle(cycle) if cycle == &["a", "b", "a"]));
+    assert_eq!(error.to_string(), "definition cycle: a → b → a");
+
+    let error = linker.instantiate("c").unwrap_err();
+    assert!(matches!(error, LinkerError::UnknownModule(name) if name == "c"));
+
+    Ok(())
+}
+
+#[test]
+fn lazy_function_breaks_cycle() -> Result<()> {
+    let store = Store::default();
+    let (a, b) = mutually_importing_modules(&store)?;
+    let mut linker = Linker::new(&store);
+    let ty = FunctionType::new(vec![Type::I32], vec![Type::I32]);
+    linker
+        .register("a", a)
+        .register("b", b)
+        .define_lazy("a", "dec", ty);
+
+    let a = linker.instantiate("a")?;
+    let b = linker.instantiate("b")?;
+    let inc_twice = a.exports.get_function("inc_twice")?;
+    let dec_twice = b.exports.get_function("dec_twice")?;
+    assert_eq!(inc_twice.call(&[Val::I32(1)])?.to_vec(), vec![Val::I32(3)]);
+
+    // The lazy function traps until it's fulfilled.
+    let error = dec_twice.call(&[Val::I32(1)]).unwrap_err();
+    assert_eq!(
+        error.message(),
+        "lazy function `a`.`dec` called before being fulfilled"
+    );
+
+    assert!(matches!(
+        linker.fulfill("a", "dec", Function::new_native(&store, || {})),
+        Err(LinkerError::IncompatibleType { .. })
+    ));
+    let dec = a.exports.get_function("dec")?.clone();
+    linker.fulfill("a", "dec", dec.clone())?;
+    assert_eq!(dec_twice.call(&[Val::I32(5)])?.to_vec(), vec![Val::I32(3)]);
+    assert!(matches!(
+        linker.fulfill("a", "dec", dec),
+        Err(LinkerError::NotLazy(..))
+    ));
+
+    Ok(())
+}
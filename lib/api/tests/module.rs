@@ -1,4 +1,6 @@
 use anyhow::Result;
+use std::thread;
+use std::time::{Duration, Instant};
 use wasmer::*;
 
 #[test]
@@ -157,6 +159,32 @@ fn exports() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn export_names_of() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+    (func $f nop)
+    (func $g nop)
+    (export "first" (func $f))
+    (export "other" (func $g))
+    (export "second" (func $f))
+)"#;
+    let module = Module::new(&store, wat)?;
+    assert_eq!(
+        module
+            .export_names_of(ExportIndex::Function(FunctionIndex::from_u32(0)))
+            .collect::<Vec<_>>(),
+        vec!["first", "second"]
+    );
+    assert_eq!(
+        module
+            .export_names_of(ExportIndex::Function(FunctionIndex::from_u32(2)))
+            .count(),
+        0
+    );
+    Ok(())
+}
+
 #[test]
 fn calling_host_functions_with_negative_values_works() -> Result<()> {
     let store = Store::default();
@@ -246,3 +274,466 @@ fn calling_host_functions_with_negative_values_works() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn module_requires_disabled_features() -> Result<()> {
+    let mut features = Features::default();
+    features.simd(false);
+    let engine = JIT::new(&Cranelift::default()).features(features).engine();
+    let store = Store::new(&engine);
+
+    let wat = r#"(module
+    (func (export "splat") (param i32) (result i32)
+        (i32x4.extract_lane 0 (i32x4.splat (local.get 0))))
+)"#;
+    let error = Module::new(&store, wat).unwrap_err();
+    match &error {
+        CompileError::UnsupportedFeature {
+            feature,
+            message,
+            function,
+            offset,
+        } => {
+            assert_eq!(feature, "SIMD");
+            assert!(
+                message.contains("SIMD support is not enabled"),
+                "{}",
+                message
+            );
+            assert_eq!(*function, Some(FunctionIndex::from_u32(0)));
+            assert!(offset.is_some());
+        }
+        e => panic!("unexpected error: {}", e),
+    }
+    assert!(error.is_engine_limitation());
+    assert!(!error.is_user_error());
+
+    // Invalid modules still report the validation error.
+    let wat = r#"(module (func (result i32) (i64.const 0)))"#;
+    let error = Module::new(&store, wat).unwrap_err();
+    assert!(matches!(error, CompileError::Validate(_)));
+
+    Ok(())
+}
+
+#[test]
+fn shared_memories_require_threads() -> Result<()> {
+    let wat = r#"(module
+    (memory (export "memory") 1 1 shared)
+    (func (export "store") (param i32 i32)
+        (i32.store (local.get 0) (local.get 1)))
+    (func (export "load") (param i32) (result i32)
+        (i32.load (local.get 0)))
+)"#;
+
+    let mut features = Features::default();
+    features.threads(true);
+    let engine = JIT::new(&Cranelift::default()).features(features).engine();
+    let store = Store::new(&engine);
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let memory = instance.exports.get_memory("memory")?;
+    assert!(memory.ty().shared);
+    let store_fn: NativeFunc<(i32, i32), ()> = instance.exports.get_native_function("store")?;
+    let load_fn: NativeFunc<i32, i32> = instance.exports.get_native_function("load")?;
+    store_fn.call(8, 42)?;
+    assert_eq!(load_fn.call(8)?, 42);
+    assert!(memory.grow(1).is_err());
+
+    let mut features = Features::default();
+    features.threads(false);
+    let engine = JIT::new(&Cranelift::default()).features(features).engine();
+    let store = Store::new(&engine);
+    assert!(Module::new(&store, wat).is_err());
+    Ok(())
+}
+
+#[test]
+fn compile_error_categories() -> Result<()> {
+    let store = Store::default();
+
+    // An invalid binary is a user error.
+    let error = Module::new(&store, b"\0asm\x01\0\0\0\xff").unwrap_err();
+    assert!(matches!(error, CompileError::Validate(_)), "{}", error);
+    assert!(error.is_user_error());
+    assert!(!error.is_engine_limitation());
+
+    // Too many locals exceeds a limit of the implementation.
+    let wat = r#"(module (func (local i32) (local i64) (local f32)))"#
+        .replace("(local i32)", &"(local i32) ".repeat(50001));
+    let error = Module::new(&store, wat).unwrap_err();
+    assert!(matches!(error, CompileError::ResourceLimit(_)), "{}", error);
+    assert!(!error.is_user_error());
+    assert!(error.is_engine_limitation());
+
+    // Cranelift can't compile to a target it doesn't support.
+    let triple: Triple = "mips-unknown-linux-gnu".parse().unwrap();
+    let target = Target::new(triple, CpuFeature::set());
+    let engine = JIT::new(&Cranelift::default()).target(target).engine();
+    let error = Module::new(&Store::new(&engine), "(module (func))").unwrap_err();
+    assert!(
+        matches!(error, CompileError::UnsupportedTarget(_)),
+        "{}",
+        error
+    );
+    assert!(error.is_engine_limitation());
+
+    // A failure of the compiler is neither.
+    let engine = JIT::new(&FailingCompilerConfig).engine();
+    let error = Module::new(&Store::new(&engine), "(module (func))").unwrap_err();
+    assert!(matches!(error, CompileError::Codegen(_)), "{}", error);
+    assert!(!error.is_user_error());
+    assert!(!error.is_engine_limitation());
+
+    Ok(())
+}
+
+/// A compiler configuration whose compiler always fails to generate code.
+struct FailingCompilerConfig;
+
+struct FailingCompiler;
+
+impl CompilerConfig for FailingCompilerConfig {
+    fn compiler(&self) -> Box<dyn wasmer_compiler::Compiler + Send> {
+        Box::new(FailingCompiler)
+    }
+
+    fn push_middleware(&mut self, _: std::sync::Arc<dyn FunctionMiddlewareGenerator>) {}
+}
+
+impl wasmer_compiler::Compiler for FailingCompiler {
+    fn compile_module<'data, 'module>(
+        &self,
+        _: &Target,
+        _: &'module wasmer_compiler::CompileModuleInfo,
+        _: &'module wasmer_compiler::ModuleTranslationState,
+        _: wasmer_types::entity::PrimaryMap<
+            wasmer_types::LocalFunctionIndex,
+            wasmer_compiler::FunctionBodyData<'data>,
+        >,
+        _: &wasmer_compiler::CompilationProgress,
+    ) -> Result<wasmer_compiler::Compilation, CompileError> {
+        Err(CompileError::Codegen(
+            "the code generation failed".to_string(),
+        ))
+    }
+}
+
+/// A module with `count` exported functions, each summing its argument a
+/// few times.
+fn many_functions(count: usize) -> Vec<u8> {
+    let mut wat = String::from("(module\n");
+    for i in 0..count {
+        wat.push_str(&format!(
+            "(func (export \"f{}\") (param i32) (result i32) {}(local.get 0){})\n",
+            i,
+            "(i32.add (local.get 0) ".repeat(16),
+            ")".repeat(16)
+        ));
+    }
+    wat.push(')');
+    wat2wasm(wat.as_bytes()).unwrap().into_owned()
+}
+
+#[test]
+fn compile_in_background_reports_progress() -> Result<()> {
+    let store = Store::default();
+    let handle = Module::compile_in_background(&store, many_functions(2000));
+
+    let start = Instant::now();
+    let mut last = (0, 0);
+    loop {
+        let (done, total) = handle.progress();
+        assert!(
+            done >= last.0,
+            "progress went from {:?} to {:?}",
+            last,
+            (done, total)
+        );
+        assert!(total == 0 || total == 2000);
+        assert!(done <= 2000);
+        last = (done, total);
+        if done == 2000 {
+            break;
+        }
+        assert!(
+            start.elapsed() < Duration::from_secs(120),
+            "stuck at {:?}",
+            last
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let module = handle.wait()?;
+    assert_eq!(module.exports().count(), 2000);
+    Ok(())
+}
+
+#[test]
+fn compile_in_background_cancel() -> Result<()> {
+    let store = Store::default();
+    let wasm = many_functions(2000);
+    let handle = Module::compile_in_background(&store, wasm.clone());
+    while handle.progress().0 == 0 {
+        thread::yield_now();
+    }
+
+    let start = Instant::now();
+    handle.cancel();
+    match handle.wait() {
+        Err(CompileError::Cancelled) => {}
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("the compilation wasn't cancelled"),
+    }
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    // The engine still compiles other modules.
+    let module = Module::compile_in_background(&store, wasm).wait()?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let f = instance.exports.get_native_function::<i32, i32>("f1999")?;
+    assert_eq!(f.call(1)?, 17);
+    Ok(())
+}
+
+#[test]
+fn link_modules() -> Result<()> {
+    let store = Store::default();
+    let math = Module::new(
+        &store,
+        r#"(module
+            (func (export "add") (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1)))
+            (func (export "seven") (result i32) (i32.const 7))
+        )"#,
+    )?;
+    let main = Module::new(
+        &store,
+        r#"(module
+            (import "math" "sum" (func $sum (param i32 i32) (result i32)))
+            (func (export "run") (param i32) (result i32)
+                (call $sum (local.get 0) (i32.const 35)))
+        )"#,
+    )?;
+
+    let linked = main.link(&math, &[("sum", "add")])?;
+    let instance = linked.instantiate()?;
+    let run = instance.exports.get_native_function::<i32, i32>("run")?;
+    assert_eq!(run.call(7)?, 42);
+
+    match main.link(&math, &[("product", "add")]) {
+        Err(LinkError::UnknownImport(name)) => assert_eq!(name, "product"),
+        _ => panic!("linking an unknown import should fail"),
+    }
+    match main.link(&math, &[("sum", "mul")]) {
+        Err(LinkError::Import(module, name, ImportError::UnknownImport(_))) => {
+            assert_eq!((module.as_str(), name.as_str()), ("math", "sum"))
+        }
+        _ => panic!("linking to an unknown export should fail"),
+    }
+    match main.link(&math, &[("sum", "seven")]) {
+        Err(LinkError::Import(_, _, ImportError::IncompatibleType(_, _))) => {}
+        _ => panic!("linking to an export of another type should fail"),
+    }
+    Ok(())
+}
+
+#[test]
+fn module_footprint() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "host" "log" (func $log (param i32)))
+            (func (export "div") (param i32 i32) (result i32)
+                (i32.div_s (local.get 0) (local.get 1)))
+            (func (export "run") (param i32)
+                (block
+                    (block
+                        (br_table 0 1 (local.get 0)))
+                    (call $log (i32.const 1)))
+                (call $log (i32.const 2)))
+        )"#,
+    )?;
+
+    let footprint = module.footprint();
+    let finished_code_bytes: usize = module
+        .artifact()
+        .finished_functions()
+        .values()
+        .map(|function| unsafe { (&***function).len() })
+        .sum();
+    assert_eq!(footprint.code_bytes, finished_code_bytes);
+    assert_eq!(module.code_size(), finished_code_bytes);
+    assert!(footprint.code_bytes > 0);
+    assert!(footprint.frame_info_bytes > 0);
+    assert_eq!(footprint.functions, 2);
+    // Two signatures and one imported function.
+    assert_eq!(footprint.trampolines, 3);
+
+    let serialized = module.serialize()?;
+    assert_eq!(footprint.serialized_bytes, serialized.len());
+    let deserialized = unsafe { Module::deserialize(&store, &serialized)? };
+    assert_eq!(deserialized.footprint(), footprint);
+    Ok(())
+}
+
+#[test]
+fn custom_sections_with_the_same_name() -> Result<()> {
+    let store = Store::default();
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    for (name, payload) in &[
+        ("meta", &b"first"[..]),
+        ("other", &b"x"[..]),
+        ("meta", &b"second"[..]),
+    ] {
+        wasm.push(0x00);
+        wasm.push((1 + name.len() + payload.len()) as u8);
+        wasm.push(name.len() as u8);
+        wasm.extend(name.as_bytes());
+        wasm.extend(payload.iter());
+    }
+
+    let module = Module::new(&store, &wasm)?;
+    let sections = |module: &Module| {
+        module
+            .custom_sections("meta")
+            .map(|section| section.to_vec())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        sections(&module),
+        vec![b"first".to_vec(), b"second".to_vec()]
+    );
+    assert_eq!(module.custom_sections("other").count(), 1);
+    assert_eq!(module.custom_sections("missing").count(), 0);
+
+    let serialized = module.serialize()?;
+    let deserialized = unsafe { Module::deserialize(&store, &serialized)? };
+    assert_eq!(sections(&deserialized), sections(&module));
+    Ok(())
+}
+
+#[test]
+fn module_metadata() -> Result<()> {
+    let store = Store::default();
+    let mut module = Module::new(&store, "(module (func (export \"run\")))")?;
+    module.set_metadata("source-sha", b"0123abcd")?;
+    module.set_metadata("tenant", b"acme")?;
+    assert_eq!(
+        module.set_metadata("tenant", b"other"),
+        Err(MetadataError::DuplicateKey("tenant".to_string()))
+    );
+    assert_eq!(
+        module.set_metadata("big", &vec![0; MAX_METADATA_VALUE_SIZE + 1]),
+        Err(MetadataError::ValueTooLarge {
+            key: "big".to_string(),
+            size: MAX_METADATA_VALUE_SIZE + 1,
+        })
+    );
+
+    let deserialized = unsafe { Module::deserialize(&store, &module.serialize()?)? };
+    assert_eq!(
+        deserialized.metadata_keys().collect::<Vec<_>>(),
+        vec!["source-sha", "tenant"]
+    );
+    assert_eq!(deserialized.metadata("source-sha"), Some(&b"0123abcd"[..]));
+    assert_eq!(deserialized.metadata("tenant"), Some(&b"acme"[..]));
+    assert_eq!(deserialized.metadata("big"), None);
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module");
+    module.serialize_to_file(&path)?;
+    let deserialized = unsafe { Module::deserialize_from_file(&store, &path)? };
+    assert_eq!(deserialized.metadata("tenant"), Some(&b"acme"[..]));
+    Ok(())
+}
+
+#[test]
+fn module_metadata_does_not_change_code_hash() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, "(module (func (export \"run\")))")?;
+    let mut first = module.clone();
+    first.set_metadata("tenant", b"first")?;
+    let mut second = module.clone();
+    second.set_metadata("tenant", b"second")?;
+    assert_eq!(module.metadata("tenant"), None);
+
+    let first = unsafe { Module::deserialize(&store, &first.serialize()?)? };
+    let second = unsafe { Module::deserialize(&store, &second.serialize()?)? };
+    assert_eq!(first.code_hash()?, module.code_hash()?);
+    assert_eq!(second.code_hash()?, module.code_hash()?);
+    assert_ne!(first.serialize()?, second.serialize()?);
+    Ok(())
+}
+
+#[test]
+fn module_metadata_tampering_is_detected() -> Result<()> {
+    let store = Store::default();
+    let mut module = Module::new(&store, "(module)")?;
+    module.set_metadata("policy-version", b"3")?;
+    let mut serialized = module.serialize()?;
+
+    // The value is followed by the integrity hash, the length of the
+    // entries and the 16 bytes of the trailer.
+    let value = serialized.len() - 16 - 8 - 32 - 1;
+    assert_eq!(serialized[value], b'3');
+    serialized[value] = b'4';
+    match unsafe { Module::deserialize(&store, &serialized) } {
+        Err(DeserializeError::CorruptedBinary(message)) => assert_eq!(
+            message,
+            "the module doesn't match the integrity hash of its metadata"
+        ),
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+    Ok(())
+}
+
+const IMPORT_SKELETON_WAT: &str = r#"(module
+    (import "env" "log" (func (param i32 i64) (result f32)))
+    (import "env" "pair" (func (result i32 f64)))
+    (import "env" "a-b" (func))
+    (import "env" "a_b" (func))
+    (import "env" "memory" (memory 1 2))
+    (import "env" "table" (table 2 funcref))
+    (import "env" "counter" (global (mut i64)))
+    (import "wasi snapshot" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+    (import "env" "{weird}" (func))
+)"#;
+
+include!("fixtures/import_skeleton.rs");
+
+#[test]
+fn import_skeleton() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, IMPORT_SKELETON_WAT)?;
+    let skeleton = module.generate_import_skeleton();
+
+    let namespaces = skeleton
+        .namespaces()
+        .map(|(namespace, imports)| (namespace, imports.len()))
+        .collect::<Vec<_>>();
+    assert_eq!(namespaces, vec![("env", 8), ("wasi snapshot", 1)]);
+    let env = skeleton.get("env").unwrap();
+    assert_eq!(env[0].name(), "log");
+    assert_eq!(
+        env[0].ty(),
+        &ExternType::Function(FunctionType::new(
+            vec![Type::I32, Type::I64],
+            vec![Type::F32]
+        ))
+    );
+    assert_eq!(
+        env[4].ty(),
+        &ExternType::Memory(MemoryType::new(1, Some(2), false))
+    );
+    assert!(skeleton.get("host").is_none());
+
+    // The fixture is the generated source, compiled in this test.
+    assert_eq!(
+        skeleton.to_rust_source(),
+        include_str!("fixtures/import_skeleton.rs")
+    );
+    let instance = Instance::new(&module, &import_object(&store))?;
+    assert!(instance.exports.is_empty());
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! The trap handlers are installed once per process, so this file holds a
+//! single test, run in its own process.
+#![cfg(unix)]
+
+use anyhow::Result;
+use wasmer::*;
+
+fn trap(store: &Store) -> Result<RuntimeError> {
+    let module = Module::new(store, r#"(module (func (export "trap") unreachable))"#)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let trap = instance.exports.get_function("trap")?;
+    Ok(trap.call(&[]).unwrap_err())
+}
+
+#[test]
+fn signal_handler_strategies() -> Result<()> {
+    let store = Store::default();
+
+    // The handlers are neither installed automatically with the `Manual`
+    // strategy, nor explicitly with the `None` one.
+    set_signal_handler_strategy(SignalHandlerStrategy::Manual);
+    let module = Module::new(&store, "(module)")?;
+    Instance::new(&module, &imports! {})?;
+    assert!(!is_trap_handler_installed());
+    set_signal_handler_strategy(SignalHandlerStrategy::None);
+    store.install_trap_handler();
+    assert!(!is_trap_handler_installed());
+
+    set_signal_handler_strategy(SignalHandlerStrategy::Manual);
+    store.install_trap_handler();
+    assert!(is_trap_handler_installed());
+    assert_eq!(trap(&store)?.message(), "unreachable");
+
+    // Installing them again has no effect.
+    set_signal_handler_strategy(SignalHandlerStrategy::Auto);
+    store.install_trap_handler();
+    assert_eq!(trap(&store)?.message(), "unreachable");
+
+    Ok(())
+}
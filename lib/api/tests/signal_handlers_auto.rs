@@ -0,0 +1,21 @@
+//! The trap handlers are installed once per process, so this file holds a
+//! single test, run in its own process.
+#![cfg(unix)]
+
+use anyhow::Result;
+use wasmer::*;
+
+#[test]
+fn signal_handler_auto_strategy() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, r#"(module (func (export "trap") unreachable))"#)?;
+    assert!(!is_trap_handler_installed());
+
+    // Creating the instance installs the handlers.
+    let instance = Instance::new(&module, &imports! {})?;
+    assert!(is_trap_handler_installed());
+    let trap = instance.exports.get_function("trap")?;
+    assert_eq!(trap.call(&[]).unwrap_err().message(), "unreachable");
+
+    Ok(())
+}
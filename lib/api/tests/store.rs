@@ -0,0 +1,166 @@
+use anyhow::Result;
+use wasmer::*;
+
+#[test]
+fn store_engine_kind() {
+    let engine = JIT::new(&Cranelift::default()).engine();
+    let store = Store::new(&engine);
+    assert_eq!(store.engine_kind(), EngineKind::Jit);
+    assert_eq!(store.engine().kind(), EngineKind::Jit);
+}
+
+#[test]
+fn default_namespaces() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+    (import "host" "answer" (func $answer (result i32)))
+    (import "host" "offset" (global $offset i32))
+    (func (export "run") (result i32)
+        (i32.add (call $answer) (global.get $offset))))"#,
+    )?;
+    let mut host = Exports::new();
+    host.insert("answer", Function::new_native(&store, || 40));
+    host.insert("offset", Global::new(&store, Val::I32(2)));
+    assert!(store.register_default_namespace("host", host).is_none());
+    let sources = |imports: &ImportObject| {
+        module
+            .import_sources(imports)
+            .into_iter()
+            .map(|(import, source)| (import.name().to_string(), source))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        sources(&imports! {}),
+        vec![
+            ("answer".to_string(), ImportSource::DefaultNamespace),
+            ("offset".to_string(), ImportSource::DefaultNamespace),
+        ]
+    );
+
+    // The default namespace provides the imports, even to the clones of
+    // the store.
+    let instance = Instance::new(&module, &imports! {})?;
+    let run = instance.exports.get_native_function::<(), i32>("run")?;
+    assert_eq!(run.call()?, 42);
+    let clone = store.clone();
+    let module_of_clone =
+        Module::new(&clone, "(module (import \"host\" \"offset\" (global i32)))")?;
+    Instance::new(&module_of_clone, &imports! {})?;
+
+    // The imports take precedence, field by field.
+    let imports = imports! {
+        "host" => {
+            "answer" => Function::new_native(&store, || 10),
+        },
+    };
+    assert_eq!(
+        sources(&imports),
+        vec![
+            ("answer".to_string(), ImportSource::Resolver),
+            ("offset".to_string(), ImportSource::DefaultNamespace),
+        ]
+    );
+    let instance = Instance::new(&module, &imports)?;
+    let run = instance.exports.get_native_function::<(), i32>("run")?;
+    assert_eq!(run.call()?, 12);
+
+    assert!(store.remove_default_namespace("host").is_some());
+    assert_eq!(
+        sources(&imports! {}),
+        vec![
+            ("answer".to_string(), ImportSource::Missing),
+            ("offset".to_string(), ImportSource::Missing),
+        ]
+    );
+    match Instance::new(&module, &imports! {}) {
+        Err(InstantiationError::Link(LinkError::Import(module, field, _))) => {
+            assert_eq!((module.as_str(), field.as_str()), ("host", "answer"));
+        }
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("the imports shouldn't resolve"),
+    }
+    Ok(())
+}
+
+#[test]
+fn deferred_cleanups_run_once() -> Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+    use std::sync::Arc;
+
+    struct Env {
+        store: Store,
+        guest: Function,
+        cleanups: Arc<AtomicUsize>,
+        raise: bool,
+    }
+
+    fn host(env: &mut Env, trap: i32) -> Result<(), RuntimeError> {
+        let cleanups = env.cleanups.clone();
+        env.store.defer(move || {
+            cleanups.fetch_add(1, SeqCst);
+        });
+        let _canary = TrapCanary::new("host");
+        match env.guest.call(&[Val::I32(trap)]) {
+            Ok(_) => Ok(()),
+            // Unwinds through this frame without running its destructors.
+            Err(error) if env.raise => unsafe { raise_user_trap(Box::new(error)) },
+            Err(error) => Err(error),
+        }
+    }
+
+    let store = Store::default();
+    let guest = Module::new(
+        &store,
+        r#"(module
+    (func (export "guest") (param i32)
+        (if (local.get 0) (then unreachable))))"#,
+    )?;
+    let guest = Instance::new(&guest, &imports! {})?;
+    let module = Module::new(
+        &store,
+        r#"(module
+    (import "host" "call" (func $call (param i32)))
+    (func (export "run") (param i32)
+        (call $call (local.get 0))))"#,
+    )?;
+
+    for &raise in &[false, true] {
+        let cleanups = Arc::new(AtomicUsize::new(0));
+        let env = Env {
+            store: store.clone(),
+            guest: guest.exports.get_function("guest")?.clone(),
+            cleanups: cleanups.clone(),
+            raise,
+        };
+        let imports = imports! {
+            "host" => {
+                "call" => Function::new_native_with_env(&store, env, host),
+            },
+        };
+        let instance = Instance::new(&module, &imports)?;
+        let run = instance.exports.get_native_function::<i32, ()>("run")?;
+
+        run.call(0)?;
+        assert_eq!(cleanups.load(SeqCst), 1);
+        assert!(run.call(1).is_err());
+        assert_eq!(cleanups.load(SeqCst), 2);
+        // Raising the trap skips the `Drop` of the canary.
+        let skipped = if raise && cfg!(debug_assertions) {
+            vec!["host"]
+        } else {
+            vec![]
+        };
+        assert_eq!(TrapCanary::take_skipped(), skipped);
+    }
+
+    // Outside of a host function, the cleanup runs right away.
+    let cleanups = Arc::new(AtomicUsize::new(0));
+    let counter = cleanups.clone();
+    store.defer(move || {
+        counter.fetch_add(1, SeqCst);
+    });
+    assert_eq!(cleanups.load(SeqCst), 1);
+    Ok(())
+}
@@ -0,0 +1,536 @@
+use anyhow::Result;
+use std::cell::UnsafeCell;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use wasmer::*;
+use wasmer_vm::{
+    LinearTable, Memory as RuntimeMemory, MemoryStyle, Table as RuntimeTable, TableStyle, Trap,
+    VMCallerCheckedAnyfunc, VMMemoryDefinition, VMTableDefinition,
+};
+
+/// A memory backed by a `Vec`, allocated up front for its maximum size.
+///
+/// The `Vec` is returned as is to the pool of its creator when the memory
+/// is dropped.
+#[derive(Debug)]
+struct VecMemory {
+    ty: MemoryType,
+    style: MemoryStyle,
+    data: Mutex<Vec<u8>>,
+    definition: Box<UnsafeCell<VMMemoryDefinition>>,
+    pool: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl Drop for VecMemory {
+    fn drop(&mut self) {
+        let data = std::mem::take(&mut *self.data.lock().unwrap());
+        self.pool.lock().unwrap().push(data);
+    }
+}
+
+unsafe impl Send for VecMemory {}
+unsafe impl Sync for VecMemory {}
+
+impl RuntimeMemory for VecMemory {
+    fn ty(&self) -> &MemoryType {
+        &self.ty
+    }
+
+    fn style(&self) -> &MemoryStyle {
+        &self.style
+    }
+
+    fn size(&self) -> Pages {
+        Bytes(self.data.lock().unwrap().len()).into()
+    }
+
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let mut data = self.data.lock().unwrap();
+        let previous: Pages = Bytes(data.len()).into();
+        let new_pages = previous + delta;
+        if new_pages > self.ty.maximum.unwrap() {
+            return Err(MemoryError::CouldNotGrow {
+                current: previous,
+                attempted_delta: delta,
+            });
+        }
+        // The capacity was reserved up front, so the base doesn't move.
+        data.resize(Bytes::from(new_pages).0, 0);
+        unsafe {
+            (*self.definition.get()).current_length = data.len() as u32;
+        }
+        Ok(previous)
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        unsafe { NonNull::new_unchecked(self.definition.get()) }
+    }
+}
+
+#[derive(Default)]
+struct VecMemoryCreator {
+    created: Arc<AtomicUsize>,
+    pool: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl MemoryCreator for VecMemoryCreator {
+    fn new_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Box<dyn RuntimeMemory>, MemoryError> {
+        let maximum = ty.maximum.ok_or_else(|| MemoryError::InvalidMemory {
+            reason: "a maximum size is required".to_string(),
+        })?;
+        let capacity = Bytes::from(maximum).0;
+        let mut pool = self.pool.lock().unwrap();
+        let mut data = match pool.iter().position(|data| data.capacity() >= capacity) {
+            // Reuse the buffer of a dropped memory, without clearing it.
+            Some(index) => pool.swap_remove(index),
+            None => Vec::with_capacity(capacity),
+        };
+        data.resize(Bytes::from(ty.minimum).0, 0);
+        let definition = Box::new(UnsafeCell::new(VMMemoryDefinition {
+            base: data.as_mut_ptr(),
+            current_length: data.len() as u32,
+        }));
+        self.created.fetch_add(1, Ordering::SeqCst);
+        Ok(Box::new(VecMemory {
+            ty: ty.clone(),
+            style: style.clone(),
+            data: Mutex::new(data),
+            definition,
+            pool: self.pool.clone(),
+        }))
+    }
+}
+
+#[test]
+fn custom_memory_creator() -> Result<()> {
+    let engine = Store::default().engine().clone();
+    let mut tunables = Tunables::for_target(engine.target());
+    // The memory can't be reserved up front, so make it dynamic and bounds
+    // checked without relying on guard pages.
+    tunables.static_memory_bound = Pages(0);
+    tunables.dynamic_memory_offset_guard_size = 0;
+    let creator = VecMemoryCreator::default();
+    let created = creator.created.clone();
+    let store = Store::new_with_tunables(&*engine, tunables.with_memory_creator(creator));
+
+    let wat = r#"(module
+    (memory (export "memory") 1 2)
+    (func (export "store") (param i32 i32)
+        (i32.store (local.get 0) (local.get 1)))
+    (func (export "load") (param i32) (result i32)
+        (i32.load (local.get 0)))
+    (func (export "grow") (param i32) (result i32)
+        (memory.grow (local.get 0)))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+
+    let store_fn: NativeFunc<(i32, i32), ()> = instance.exports.get_native_function("store")?;
+    let load_fn: NativeFunc<i32, i32> = instance.exports.get_native_function("load")?;
+    let grow_fn: NativeFunc<i32, i32> = instance.exports.get_native_function("grow")?;
+
+    store_fn.call(65532, 42)?;
+    assert_eq!(load_fn.call(65532)?, 42);
+    assert!(load_fn.call(65536).is_err());
+
+    assert_eq!(grow_fn.call(1)?, 1);
+    store_fn.call(65536, 7)?;
+    assert_eq!(load_fn.call(65536)?, 7);
+    assert_eq!(grow_fn.call(1)?, -1);
+
+    let memory = instance.exports.get_memory("memory")?;
+    assert_eq!(memory.size(), Pages(2));
+    assert_eq!(memory.view::<u8>()[65532].get(), 42);
+
+    // Memories created from the host also use the memory creator.
+    Memory::new(&store, MemoryType::new(1, Some(1), false))?;
+    assert_eq!(created.load(Ordering::SeqCst), 2);
+
+    Ok(())
+}
+
+#[test]
+fn memory_creator_zero_on_reuse() -> Result<()> {
+    let engine = Store::default().engine().clone();
+    let mut tunables = Tunables::for_target(engine.target());
+    tunables.static_memory_bound = Pages(0);
+    tunables.dynamic_memory_offset_guard_size = 0;
+    let creator = VecMemoryCreator::default();
+    let pool = creator.pool.clone();
+    let tunables = tunables.with_memory_creator(creator);
+
+    let wat = r#"(module
+    (memory 1 1)
+    (func (export "load") (param i32) (result i32)
+        (i32.load8_u (local.get 0)))
+)"#;
+    // Dirties a memory and drops it, which returns its buffer to the pool,
+    // then returns the value loaded by a new instance. Instances never free
+    // their memories, so the dirty memory is created from the host.
+    let reuse = |store: &Store| -> Result<i32> {
+        let memory = Memory::new(store, MemoryType::new(1, Some(1), false))?;
+        memory.view::<u8>()[100].set(42);
+        drop(memory);
+        assert_eq!(pool.lock().unwrap().len(), 1);
+
+        let module = Module::new(store, wat)?;
+        let instance = Instance::new(&module, &imports! {})?;
+        assert!(pool.lock().unwrap().is_empty());
+        let load_fn: NativeFunc<i32, i32> = instance.exports.get_native_function("load")?;
+        Ok(load_fn.call(100)?)
+    };
+
+    let store = Store::new_with_tunables(&*engine, tunables.clone());
+    assert_eq!(reuse(&store)?, 0);
+
+    // Without zeroing, the bytes of the previous instance leak through.
+    let mut tunables = tunables;
+    tunables.zero_on_reuse = false;
+    let store = Store::new_with_tunables(&*engine, tunables);
+    assert_eq!(reuse(&store)?, 42);
+
+    Ok(())
+}
+
+#[test]
+fn dirty_page_tracking() -> Result<()> {
+    let engine = Store::default().engine().clone();
+    let mut tunables = Tunables::for_target(engine.target());
+    tunables.track_dirty_pages = true;
+    let store = Store::new_with_tunables(&*engine, tunables);
+
+    let wat = r#"(module
+    (memory (export "memory") 8)
+    (func (export "store") (param i32 i32)
+        (i32.store (local.get 0) (local.get 1)))
+    (func (export "load") (param i32) (result i32)
+        (i32.load (local.get 0)))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let memory = instance.exports.get_memory("memory")?;
+    let store_fn: NativeFunc<(i32, i32), ()> = instance.exports.get_native_function("store")?;
+    let load_fn: NativeFunc<i32, i32> = instance.exports.get_native_function("load")?;
+
+    assert_eq!(memory.take_dirty_pages(), Some(vec![]));
+
+    store_fn.call(16, 1)?;
+    store_fn.call(7 * 65536 + 100, 2)?;
+    store_fn.call(20, 3)?;
+    assert_eq!(memory.take_dirty_pages(), Some(vec![0, 7]));
+
+    // Reading doesn't dirty the pages.
+    assert_eq!(load_fn.call(16)?, 1);
+    assert_eq!(load_fn.call(7 * 65536 + 100)?, 2);
+    assert_eq!(memory.view::<u8>()[20].get(), 3);
+    assert_eq!(memory.take_dirty_pages(), Some(vec![]));
+
+    // Writes from the host are tracked too.
+    memory.view::<u8>()[3 * 65536].set(4);
+    assert_eq!(memory.take_dirty_pages(), Some(vec![3]));
+
+    // Out of bounds accesses still trap.
+    let e = store_fn.call(8 * 65536, 5).unwrap_err();
+    assert!(e.message().contains("out of bounds"), "{}", e.message());
+    assert_eq!(memory.take_dirty_pages(), Some(vec![]));
+
+    // Grown memories keep tracking the pages written to.
+    memory.grow(1)?;
+    store_fn.call(8 * 65536, 5)?;
+    store_fn.call(0, 6)?;
+    assert_eq!(memory.take_dirty_pages(), Some(vec![0, 8]));
+
+    Ok(())
+}
+
+#[test]
+fn dirty_page_tracking_disabled() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    assert_eq!(memory.take_dirty_pages(), None);
+    Ok(())
+}
+
+/// A table counting the elements set through it.
+#[derive(Debug)]
+struct CountingTable {
+    table: LinearTable,
+    sets: Arc<AtomicUsize>,
+}
+
+impl RuntimeTable for CountingTable {
+    fn style(&self) -> &TableStyle {
+        self.table.style()
+    }
+
+    fn ty(&self) -> &TableType {
+        self.table.ty()
+    }
+
+    fn size(&self) -> u32 {
+        self.table.size()
+    }
+
+    fn grow(&self, delta: u32) -> Option<u32> {
+        self.table.grow(delta)
+    }
+
+    fn get(&self, index: u32) -> Option<VMCallerCheckedAnyfunc> {
+        self.table.get(index)
+    }
+
+    fn set(&self, index: u32, func: VMCallerCheckedAnyfunc) -> Result<(), Trap> {
+        self.sets.fetch_add(1, Ordering::SeqCst);
+        self.table.set(index, func)
+    }
+
+    fn vmtable(&self) -> NonNull<VMTableDefinition> {
+        self.table.vmtable()
+    }
+}
+
+#[derive(Default)]
+struct CountingTableCreator {
+    created: Arc<AtomicUsize>,
+    sets: Arc<AtomicUsize>,
+}
+
+impl TableCreator for CountingTableCreator {
+    fn new_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Box<dyn RuntimeTable>, String> {
+        self.created.fetch_add(1, Ordering::SeqCst);
+        Ok(Box::new(CountingTable {
+            table: LinearTable::new(ty, style)?,
+            sets: self.sets.clone(),
+        }))
+    }
+}
+
+#[test]
+fn custom_table_creator() -> Result<()> {
+    let engine = Store::default().engine().clone();
+    let creator = CountingTableCreator::default();
+    let created = creator.created.clone();
+    let sets = creator.sets.clone();
+    let tunables = Tunables::for_target(engine.target()).with_table_creator(creator);
+    let store = Store::new_with_tunables(&*engine, tunables);
+
+    let wat = r#"(module
+    (type $t (func (result i32)))
+    (table (export "table") 2 funcref)
+    (func $one (result i32) (i32.const 1))
+    (elem (i32.const 0) $one)
+    (func (export "call") (param i32) (result i32)
+        (call_indirect (type $t) (local.get 0)))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+    assert_eq!(sets.load(Ordering::SeqCst), 1);
+
+    let call: NativeFunc<i32, i32> = instance.exports.get_native_function("call")?;
+    assert_eq!(call.call(0)?, 1);
+    assert!(call.call(1).is_err());
+
+    let table = instance.exports.get_table("table")?;
+    let two = Function::new_native(&store, || 2i32);
+    table.set(1, Val::FuncRef(two))?;
+    assert_eq!(sets.load(Ordering::SeqCst), 2);
+    assert!(matches!(table.get(1), Some(Val::FuncRef(_))));
+    assert_eq!(call.call(1)?, 2);
+
+    Ok(())
+}
+
+#[test]
+fn no_static_memories_on_32_bit_hosts() -> Result<()> {
+    use wasmer_engine::Tunables as BaseTunables;
+
+    let memory = MemoryType::new(Pages(1), Some(Pages(1)), false);
+    for (triple, is_static) in &[
+        ("x86_64-unknown-linux-gnu", true),
+        ("i686-unknown-linux-gnu", false),
+        ("armv7-unknown-linux-gnueabihf", false),
+    ] {
+        let target = Target::new(triple.parse::<Triple>().unwrap(), Default::default());
+        let tunables = Tunables::for_target(&target);
+        let style = tunables.memory_style(&memory);
+        assert_eq!(
+            matches!(style, MemoryStyle::Static { .. }),
+            *is_static,
+            "{}: {:?}",
+            triple,
+            style
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn memory_style_override() -> Result<()> {
+    use wasmer_engine::Tunables as BaseTunables;
+
+    let engine = Store::default().engine().clone();
+    let small_style = MemoryStyle::Static {
+        bound: Pages(16),
+        offset_guard_size: 0x1_0000,
+    };
+    let override_style = small_style.clone();
+    let tunables = Tunables::for_target(engine.target()).with_memory_style(move |memory| {
+        match memory.maximum {
+            Some(maximum) if maximum <= Pages(16) => Some(override_style.clone()),
+            _ => None,
+        }
+    });
+
+    let small = MemoryType::new(Pages(1), Some(Pages(16)), false);
+    let large = MemoryType::new(Pages(1), None, false);
+    assert_eq!(tunables.memory_style(&small), small_style);
+    let default_tunables = Tunables::for_target(engine.target());
+    assert_eq!(
+        tunables.memory_style(&large),
+        default_tunables.memory_style(&large)
+    );
+
+    let store = Store::new_with_tunables(&*engine, tunables);
+    let wat = r#"(module
+    (memory (export "memory") 1 16)
+    (func (export "store") (param i32 i32)
+        (i32.store (local.get 0) (local.get 1)))
+    (func (export "load") (param i32) (result i32)
+        (i32.load (local.get 0)))
+)"#;
+    let module = Module::new(&store, wat)?;
+    assert_eq!(
+        module
+            .artifact()
+            .memory_styles()
+            .values()
+            .collect::<Vec<_>>(),
+        vec![&small_style]
+    );
+
+    let instance = Instance::new(&module, &imports! {})?;
+    let memory = instance.exports.get_memory("memory")?;
+    let store_fn: NativeFunc<(i32, i32), ()> = instance.exports.get_native_function("store")?;
+    let load_fn: NativeFunc<i32, i32> = instance.exports.get_native_function("load")?;
+    store_fn.call(100, 42)?;
+    assert_eq!(load_fn.call(100)?, 42);
+    assert!(store_fn.call(65536, 1).is_err());
+    memory.grow(15)?;
+    store_fn.call(15 * 65536, 7)?;
+    assert_eq!(load_fn.call(15 * 65536)?, 7);
+    assert!(memory.grow(1).is_err());
+    Ok(())
+}
+
+#[test]
+fn prefaulted_data_initializers() -> Result<()> {
+    let engine = Store::default().engine().clone();
+    let wat = r#"(module
+    (import "env" "base" (global $base i32))
+    (memory (export "memory") 2)
+    (data (i32.const 0) "small")
+    (data (global.get $base) "large initializer"))"#;
+
+    for &threshold in &[None, Some(0), Some(8)] {
+        let mut tunables = Tunables::for_target(engine.target());
+        tunables.data_prefault_threshold = threshold;
+        let store = Store::new_with_tunables(&*engine, tunables);
+        let module = Module::new(&store, wat)?;
+
+        let imports = imports! {
+            "env" => {
+                "base" => Global::new(&store, Value::I32(0x1_0000 - 4)),
+            },
+        };
+        let instance = Instance::new(&module, &imports)?;
+        let memory = instance.exports.get_memory("memory")?;
+        let data = unsafe { memory.data_unchecked() };
+        assert_eq!(&data[..5], b"small");
+        assert_eq!(&data[0x1_0000 - 4..0x1_0000 + 13], b"large initializer");
+
+        // No base can move an initializer past the end of the memory.
+        for &base in &[0x2_0000 - 4, -1] {
+            let imports = imports! {
+                "env" => {
+                    "base" => Global::new(&store, Value::I32(base)),
+                },
+            };
+            assert!(Instance::new(&module, &imports).is_err());
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn memory_limit() -> Result<()> {
+    let engine = Store::default().engine().clone();
+    let tunables = Tunables::for_target(engine.target()).with_memory_limit(Pages(4));
+    let store = Store::new_with_tunables(&*engine, tunables);
+
+    // A memory exactly at the limit, which can't grow beyond it.
+    let module = Module::new(&store, r#"(module (memory (export "memory") 4 8))"#)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let memory = instance.exports.get_memory("memory")?;
+    assert!(memory.grow(Pages(1)).is_err());
+    assert_eq!(memory.size(), Pages(4));
+
+    // One page over the limit.
+    let module = Module::new(&store, "(module (memory 5))")?;
+    match Instance::new(&module, &imports! {}) {
+        Err(InstantiationError::Link(LinkError::Memory(MemoryError::MinimumExceedsLimit {
+            declared,
+            limit,
+        }))) => {
+            assert_eq!(declared, Pages(5));
+            assert_eq!(limit, Pages(4));
+        }
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+    match Memory::new(&store, MemoryType::new(Pages(5), None, false)) {
+        Err(error) => assert_eq!(
+            error.to_string(),
+            "The minimum of the memory (5 pages) exceeds the limit (4 pages)"
+        ),
+        Ok(_) => panic!("the memory exceeds the limit"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn table_limit() -> Result<()> {
+    let engine = Store::default().engine().clone();
+    let tunables = Tunables::for_target(engine.target()).with_table_limit(4);
+    let store = Store::new_with_tunables(&*engine, tunables);
+
+    // A table exactly at the limit, which can't grow beyond it.
+    let module = Module::new(&store, r#"(module (table (export "table") 4 funcref))"#)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let table = instance.exports.get_table("table")?;
+    let f = Function::new_native(&store, || {});
+    assert!(table.grow(1, Val::FuncRef(f)).is_err());
+    assert_eq!(table.size(), 4);
+
+    // One element over the limit.
+    let module = Module::new(&store, "(module (table 5 funcref))")?;
+    match Instance::new(&module, &imports! {}) {
+        Err(InstantiationError::Link(LinkError::Resource(message))) => assert_eq!(
+            message,
+            "The minimum of the table (5 elements) exceeds the limit (4 elements)"
+        ),
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+
+    Ok(())
+}
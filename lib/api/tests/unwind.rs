@@ -0,0 +1,77 @@
+use anyhow::Result;
+use std::panic::{self, AssertUnwindSafe};
+use wasmer::*;
+
+fn call_host(store: &Store, host: Function) -> Result<Instance> {
+    let module = Module::new(
+        store,
+        r#"(module
+    (import "host" "call" (func $call))
+    (func (export "run")
+        (call $call)))"#,
+    )?;
+    let imports = imports! {
+        "host" => {
+            "call" => host,
+        },
+    };
+    Ok(Instance::new(&module, &imports)?)
+}
+
+#[test]
+fn host_panics_unwind_through_wasm() -> Result<()> {
+    fn host() {
+        panic!("host panic");
+    }
+
+    let store = Store::default();
+    let host = Function::new_native(&store, host);
+    let instance = call_host(&store, host)?;
+    let run = instance.exports.get_native_function::<(), ()>("run")?;
+
+    let panic = panic::catch_unwind(AssertUnwindSafe(|| run.call())).unwrap_err();
+    assert_eq!(panic.downcast_ref::<&str>(), Some(&"host panic"));
+
+    // The state of the thread is restored, so the next calls panic too.
+    assert!(panic::catch_unwind(AssertUnwindSafe(|| run.call())).is_err());
+    Ok(())
+}
+
+// The unwind information of the wasm functions is registered with the
+// system unwinder, which walks through their frames to the host ones.
+#[cfg(all(unix, target_arch = "x86_64"))]
+#[test]
+fn backtraces_cross_wasm_frames() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    #[inline(never)]
+    fn host_calling_wasm(run: &NativeFunc<(), ()>) {
+        run.call().unwrap();
+    }
+
+    let store = Store::default();
+    let symbols = Arc::new(Mutex::new(Vec::new()));
+    let captured = symbols.clone();
+    let host = Function::new_native(&store, move || {
+        let backtrace = backtrace::Backtrace::new();
+        *captured.lock().unwrap() = backtrace
+            .frames()
+            .iter()
+            .flat_map(|frame| frame.symbols())
+            .filter_map(|symbol| symbol.name().map(|name| name.to_string()))
+            .collect::<Vec<_>>();
+    });
+    let instance = call_host(&store, host)?;
+    let run = instance.exports.get_native_function::<(), ()>("run")?;
+    host_calling_wasm(&run);
+
+    let symbols = symbols.lock().unwrap();
+    assert!(
+        symbols
+            .iter()
+            .any(|symbol| symbol.contains("host_calling_wasm")),
+        "the backtrace stops at the wasm frames: {:#?}",
+        symbols
+    );
+    Ok(())
+}
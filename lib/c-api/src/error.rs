@@ -1,9 +1,11 @@
 //! Read runtime errors.
 
 use libc::{c_char, c_int};
+use std::any::Any;
 use std::cell::RefCell;
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr::{self, NonNull};
 use std::slice;
 
@@ -17,6 +19,33 @@ pub fn update_last_error<E: Error + 'static>(err: E) {
     });
 }
 
+/// Runs `f`, catching a panic instead of letting it unwind across the FFI
+/// boundary: the panic message becomes the last error, and `None` is
+/// returned.
+pub(crate) fn catch_panic<T, F: FnOnce() -> Option<T>>(f: F) -> Option<T> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            update_last_error(CApiError {
+                msg: panic_message(&*payload),
+            });
+            None
+        }
+    }
+}
+
+/// Describes a panic from its payload.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "unknown panic"
+    };
+    format!("panicked: {}", message)
+}
+
 /// Retrieve the most recent error, clearing it in the process.
 pub(crate) fn take_last_error() -> Option<Box<dyn Error>> {
     LAST_ERROR.with(|prev| prev.borrow_mut().take())
@@ -115,3 +144,70 @@ impl Display for CApiError {
 }
 
 impl Error for CApiError {}
+
+impl From<&str> for CApiError {
+    fn from(msg: &str) -> Self {
+        Self {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "jit", feature = "compiler", feature = "wat"))]
+mod tests {
+    use super::*;
+    use crate::wasm_c_api::engine::wasm_engine_new;
+    use crate::wasm_c_api::externals::{wasm_extern_t, wasm_extern_vec_t};
+    use crate::wasm_c_api::instance::wasm_instance_new;
+    use crate::wasm_c_api::module::wasm_module_new;
+    use crate::wasm_c_api::store::wasm_store_new;
+    use crate::wasm_c_api::types::wasm_byte_vec_t;
+
+    /// Reads the last error through the C API.
+    fn last_error_message() -> String {
+        let length = wasmer_last_error_length();
+        assert!(length > 0, "no error was reported");
+        let mut buffer = vec![0u8; length as usize];
+        let written = unsafe {
+            wasmer_last_error_message(NonNull::new(buffer.as_mut_ptr() as *mut c_char), length)
+        };
+        assert_eq!(written, length);
+        buffer.pop();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn invalid_module() {
+        let engine = wasm_engine_new();
+        let store = unsafe { wasm_store_new(Some(NonNull::from(&*engine))) }.unwrap();
+        let bytes: wasm_byte_vec_t = b"not a wasm module".to_vec().into();
+
+        assert!(unsafe { wasm_module_new(&store, &bytes) }.is_none());
+        assert!(last_error_message().contains("magic header"));
+        // Reading the error clears it.
+        assert_eq!(wasmer_last_error_length(), 0);
+    }
+
+    #[test]
+    fn missing_import() {
+        let engine = wasm_engine_new();
+        let store = unsafe { wasm_store_new(Some(NonNull::from(&*engine))) }.unwrap();
+        let wasm = wasmer::wat2wasm(br#"(module (import "env" "f" (func)))"#).unwrap();
+        let bytes: wasm_byte_vec_t = wasm.into_owned().into();
+        let module = unsafe { wasm_module_new(&store, &bytes) }.unwrap();
+        let imports: wasm_extern_vec_t = Vec::<Box<wasm_extern_t>>::new().into();
+
+        let instance =
+            unsafe { wasm_instance_new(&store, &module, &imports, std::ptr::null_mut()) };
+        assert!(instance.is_none());
+        let message = last_error_message();
+        assert!(message.contains("\"env\""), "{}", message);
+        assert!(message.contains("\"f\""), "{}", message);
+    }
+
+    #[test]
+    fn caught_panic() {
+        assert_eq!(catch_panic::<(), _>(|| panic!("oops")), None);
+        assert_eq!(last_error_message(), "panicked: oops");
+    }
+}
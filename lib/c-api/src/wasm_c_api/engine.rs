@@ -1,3 +1,4 @@
+use crate::error::{update_last_error, CApiError};
 use cfg_if::cfg_if;
 use std::sync::Arc;
 use wasmer::Engine;
@@ -168,7 +169,6 @@ pub unsafe extern "C" fn wasm_engine_delete(_wasm_engine_address: Option<Box<was
 pub extern "C" fn wasm_engine_new_with_config(
     config: Box<wasm_config_t>,
 ) -> Option<Box<wasm_engine_t>> {
-    // TODO: return useful error messages in failure branches
     cfg_if! {
         if #[cfg(feature = "compiler")] {
             #[allow(unused_mut)]
@@ -178,7 +178,7 @@ pub extern "C" fn wasm_engine_new_with_config(
                         if #[cfg(feature = "cranelift")] {
                             Box::new(wasmer_compiler_cranelift::Cranelift::default())
                         } else {
-                            return None;
+                            return feature_not_enabled("cranelift");
                         }
                     }
                 },
@@ -187,7 +187,7 @@ pub extern "C" fn wasm_engine_new_with_config(
                         if #[cfg(feature = "llvm")] {
                             Box::new(wasmer_compiler_llvm::LLVM::default())
                         } else {
-                            return None;
+                            return feature_not_enabled("llvm");
                         }
                     }
                 },
@@ -196,7 +196,7 @@ pub extern "C" fn wasm_engine_new_with_config(
                         if #[cfg(feature = "singlepass")] {
                             Box::new(wasmer_compiler_singlepass::Singlepass::default())
                         } else {
-                            return None;
+                            return feature_not_enabled("singlepass");
                         }
                     }
                 },
@@ -208,7 +208,7 @@ pub extern "C" fn wasm_engine_new_with_config(
                         if #[cfg(feature = "jit")] {
                             Arc::new(JIT::new(&*compiler_config).engine())
                         } else {
-                            return None;
+                            return feature_not_enabled("jit");
                         }
                     }
                 },
@@ -217,7 +217,7 @@ pub extern "C" fn wasm_engine_new_with_config(
                         if #[cfg(feature = "native")] {
                             Arc::new(Native::new(&mut *compiler_config).engine())
                         } else {
-                            return None;
+                            return feature_not_enabled("native");
                         }
                     }
                 },
@@ -228,7 +228,7 @@ pub extern "C" fn wasm_engine_new_with_config(
                         if #[cfg(feature = "object-file")] {
                             Arc::new(ObjectFile::headless().engine())
                         } else {
-                            return None;
+                            return feature_not_enabled("object-file");
                         }
                     }
                 },
@@ -241,7 +241,7 @@ pub extern "C" fn wasm_engine_new_with_config(
                         if #[cfg(feature = "jit")] {
                             Arc::new(JIT::headless().engine())
                         } else {
-                            return None;
+                            return feature_not_enabled("jit");
                         }
                     }
                 },
@@ -250,7 +250,7 @@ pub extern "C" fn wasm_engine_new_with_config(
                         if #[cfg(feature = "native")] {
                             Arc::new(Native::headless().engine())
                         } else {
-                            return None;
+                            return feature_not_enabled("native");
                         }
                     }
                 },
@@ -259,7 +259,7 @@ pub extern "C" fn wasm_engine_new_with_config(
                         if #[cfg(feature = "object-file")] {
                             Arc::new(ObjectFile::headless().engine())
                         } else {
-                            return None;
+                            return feature_not_enabled("object-file");
                         }
                     }
                 },
@@ -268,3 +268,13 @@ pub extern "C" fn wasm_engine_new_with_config(
         }
     }
 }
+
+/// Reports that the C API was built without `feature`, for a failure branch
+/// of `wasm_engine_new_with_config`.
+#[allow(dead_code)]
+fn feature_not_enabled<T>(feature: &str) -> Option<T> {
+    update_last_error(CApiError {
+        msg: format!("the `{}` feature isn't enabled in this build", feature),
+    });
+    None
+}
@@ -2,8 +2,10 @@ use super::super::store::wasm_store_t;
 use super::super::trap::wasm_trap_t;
 use super::super::types::{wasm_functype_t, wasm_valkind_enum};
 use super::super::value::{wasm_val_inner, wasm_val_t, wasm_val_vec_t};
+use crate::error::{panic_message, update_last_error};
 use std::convert::TryInto;
 use std::ffi::c_void;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::Arc;
 use wasmer::{Function, Instance, RuntimeError, Val};
 
@@ -141,30 +143,36 @@ pub unsafe extern "C" fn wasm_func_call(
     args: &wasm_val_vec_t,
     results: &mut wasm_val_vec_t,
 ) -> Option<Box<wasm_trap_t>> {
-    let params = args
-        .into_slice()
-        .map(|slice| {
-            slice
-                .into_iter()
-                .map(TryInto::try_into)
-                .collect::<Result<Vec<Val>, _>>()
-                .expect("Argument conversion failed")
-        })
-        .unwrap_or_default();
-
-    match func.inner.call(&params) {
-        Ok(wasm_results) => {
-            *results = wasm_results
-                .into_iter()
-                .map(TryInto::try_into)
-                .collect::<Result<Vec<wasm_val_t>, _>>()
-                .expect("Argument conversion failed")
-                .into();
+    let call = panic::catch_unwind(AssertUnwindSafe(|| {
+        let params = args
+            .into_slice()
+            .map(|slice| {
+                slice
+                    .iter()
+                    .map(TryInto::try_into)
+                    .collect::<Result<Vec<Val>, _>>()
+            })
+            .unwrap_or_else(|| Ok(vec![]))
+            .map_err(|e| RuntimeError::new(format!("Argument conversion failed: {}", e)))?;
+
+        func.inner
+            .call(&params)?
+            .iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<wasm_val_t>, _>>()
+            .map_err(|e| RuntimeError::new(format!("Result conversion failed: {}", e)))
+    }));
 
-            None
+    let error = match call {
+        Ok(Ok(wasm_results)) => {
+            *results = wasm_results.into();
+            return None;
         }
-        Err(e) => Some(Box::new(e.into())),
-    }
+        Ok(Err(error)) => error,
+        Err(payload) => RuntimeError::new(panic_message(&*payload)),
+    };
+    update_last_error(error.clone());
+    Some(Box::new(error.into()))
 }
 
 #[no_mangle]
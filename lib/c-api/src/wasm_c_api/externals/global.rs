@@ -1,6 +1,7 @@
 use super::super::store::wasm_store_t;
 use super::super::types::wasm_globaltype_t;
 use super::super::value::wasm_val_t;
+use crate::error::CApiError;
 use std::convert::TryInto;
 use wasmer::{Global, Val};
 
@@ -17,7 +18,7 @@ pub unsafe extern "C" fn wasm_global_new(
     val: &wasm_val_t,
 ) -> Option<Box<wasm_global_t>> {
     let gt = gt.as_globaltype();
-    let wasm_val = val.try_into().ok()?;
+    let wasm_val: Val = c_try!(val.try_into().map_err(CApiError::from));
     let store = &store.inner;
     let global = if gt.mutability.is_mutable() {
         Global::new_mut(store, wasm_val)
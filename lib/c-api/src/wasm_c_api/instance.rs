@@ -2,6 +2,7 @@ use super::externals::{wasm_extern_t, wasm_extern_vec_t};
 use super::module::wasm_module_t;
 use super::store::wasm_store_t;
 use super::trap::wasm_trap_t;
+use crate::error::catch_panic;
 use crate::ordered_resolver::OrderedResolver;
 use std::mem;
 use std::sync::Arc;
@@ -32,8 +33,10 @@ pub unsafe extern "C" fn wasm_instance_new(
         .cloned()
         .collect();
 
-    let instance = Arc::new(c_try!(Instance::new(wasm_module, &resolver)));
-    Some(Box::new(wasm_instance_t { inner: instance }))
+    catch_panic(|| {
+        let instance = Arc::new(c_try!(Instance::new(wasm_module, &resolver)));
+        Some(Box::new(wasm_instance_t { inner: instance }))
+    })
 }
 
 #[no_mangle]
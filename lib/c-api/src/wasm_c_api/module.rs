@@ -3,7 +3,7 @@ use super::types::{
     wasm_byte_vec_t, wasm_exporttype_t, wasm_exporttype_vec_t, wasm_importtype_t,
     wasm_importtype_vec_t,
 };
-use crate::error::update_last_error;
+use crate::error::{catch_panic, update_last_error, CApiError};
 use std::ptr::NonNull;
 use std::slice;
 use std::sync::Arc;
@@ -20,12 +20,19 @@ pub unsafe extern "C" fn wasm_module_new(
     bytes: &wasm_byte_vec_t,
 ) -> Option<Box<wasm_module_t>> {
     // TODO: review lifetime of byte slice
-    let wasm_byte_slice: &[u8] = slice::from_raw_parts_mut(bytes.data, bytes.size);
-    let module = c_try!(Module::from_binary(&store.inner, wasm_byte_slice));
+    let wasm_byte_slice: &[u8] = c_try!(
+        bytes.into_slice(),
+        CApiError {
+            msg: "the module bytes are null".to_string(),
+        }
+    );
+    catch_panic(|| {
+        let module = c_try!(Module::from_binary(&store.inner, wasm_byte_slice));
 
-    Some(Box::new(wasm_module_t {
-        inner: Arc::new(module),
-    }))
+        Some(Box::new(wasm_module_t {
+            inner: Arc::new(module),
+        }))
+    })
 }
 
 #[no_mangle]
@@ -85,12 +92,12 @@ pub unsafe extern "C" fn wasm_module_deserialize(
 ) -> Option<NonNull<wasm_module_t>> {
     // TODO: read config from store and use that to decide which compiler to use
 
-    let byte_slice = if bytes.is_null() || (&*bytes).into_slice().is_none() {
-        // TODO: error handling here
-        return None;
-    } else {
-        (&*bytes).into_slice().unwrap()
-    };
+    let byte_slice = c_try!(
+        bytes.as_ref().and_then(|bytes| bytes.into_slice()),
+        CApiError {
+            msg: "the serialized module bytes are null".to_string(),
+        }
+    );
 
     let module = c_try!(Module::deserialize(&store.inner, byte_slice));
 
@@ -110,8 +117,116 @@ pub unsafe extern "C" fn wasm_module_serialize(
         Ok(byte_vec) => byte_vec,
         Err(err) => {
             crate::error::update_last_error(err);
+            // `out` is owned by the caller, who must be able to delete it.
+            *out_ptr = Vec::<u8>::new().into();
             return;
         }
     };
     *out_ptr = byte_vec.into();
 }
+
+/// A module that can be sent to another thread, see `wasm_module_share`.
+#[allow(non_camel_case_types)]
+pub struct wasm_shared_module_t {
+    inner: Arc<Module>,
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn wasm_shared_module_delete(_module: Option<Box<wasm_shared_module_t>>) {}
+
+/// Shares `module` with other threads, where `wasm_module_obtain` returns
+/// it for their stores.
+#[no_mangle]
+pub extern "C" fn wasm_module_share(module: &wasm_module_t) -> Box<wasm_shared_module_t> {
+    Box::new(wasm_shared_module_t {
+        inner: module.inner.clone(),
+    })
+}
+
+/// Returns the shared `module` for `store`, which must have the engine the
+/// module was compiled with.
+#[no_mangle]
+pub extern "C" fn wasm_module_obtain(
+    store: &wasm_store_t,
+    module: &wasm_shared_module_t,
+) -> Option<Box<wasm_module_t>> {
+    let module = c_try!(
+        module.inner.with_store(&store.inner),
+        CApiError {
+            msg: "the module was compiled with the engine of another store".to_string(),
+        }
+    );
+
+    Some(Box::new(wasm_module_t {
+        inner: Arc::new(module),
+    }))
+}
+
+#[cfg(all(test, feature = "wat"))]
+mod tests {
+    use super::super::engine::wasm_engine_new;
+    use super::super::store::wasm_store_new;
+    use super::*;
+    use std::ptr::NonNull;
+    use std::thread;
+    use wasmer::{imports, wat2wasm, Instance, Store};
+
+    fn answer_module(store: &wasm_store_t) -> Box<wasm_module_t> {
+        let wasm = wat2wasm(br#"(module (func (export "answer") (result i32) (i32.const 42)))"#)
+            .unwrap()
+            .into_owned();
+        unsafe { wasm_module_new(store, &wasm.into()) }.unwrap()
+    }
+
+    fn call_answer(module: &wasm_module_t) -> i32 {
+        let instance = Instance::new(&module.inner, &imports! {}).unwrap();
+        let answer = instance
+            .exports
+            .get_native_function::<(), i32>("answer")
+            .unwrap();
+        answer.call().unwrap()
+    }
+
+    #[test]
+    fn share_module_across_threads() {
+        let mut engine = wasm_engine_new();
+        let store = unsafe { wasm_store_new(Some(NonNull::from(&mut *engine))) }.unwrap();
+        let shared = wasm_module_share(&answer_module(&store));
+
+        let thread_store = store.inner.clone();
+        let answer = thread::spawn(move || {
+            let store = wasm_store_t {
+                inner: Store::new(&**thread_store.engine()),
+            };
+            let module = wasm_module_obtain(&store, &shared).unwrap();
+            assert!(Store::same(module.inner.store(), &store.inner));
+            call_answer(&module)
+        })
+        .join()
+        .unwrap();
+        assert_eq!(answer, 42);
+
+        // The module can't be obtained for the store of another engine.
+        let mut other_engine = wasm_engine_new();
+        let other_store =
+            unsafe { wasm_store_new(Some(NonNull::from(&mut *other_engine))) }.unwrap();
+        let shared = wasm_module_share(&answer_module(&store));
+        assert!(wasm_module_obtain(&other_store, &shared).is_none());
+    }
+
+    #[test]
+    fn serialize_and_deserialize_in_another_engine() {
+        let mut engine = wasm_engine_new();
+        let store = unsafe { wasm_store_new(Some(NonNull::from(&mut *engine))) }.unwrap();
+        let mut serialized: wasm_byte_vec_t = Vec::<u8>::new().into();
+        unsafe { wasm_module_serialize(&answer_module(&store), &mut serialized) };
+
+        let mut other_engine = wasm_engine_new();
+        let other_store =
+            unsafe { wasm_store_new(Some(NonNull::from(&mut *other_engine))) }.unwrap();
+        let module = unsafe { wasm_module_deserialize(&other_store, &serialized) }.unwrap();
+        let module = unsafe { Box::from_raw(module.as_ptr()) };
+        assert!(Store::same(module.inner.store(), &other_store.inner));
+        assert_eq!(call_answer(&module), 42);
+    }
+}
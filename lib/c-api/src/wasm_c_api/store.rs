@@ -1,6 +1,7 @@
-use super::engine::wasm_engine_t;
+use super::engine::{wasm_engine_t, wasmer_engine_t};
+use crate::error::CApiError;
 use std::ptr::NonNull;
-use wasmer::Store;
+use wasmer::{EngineKind, Store};
 
 /// Opaque wrapper around `Store`
 #[allow(non_camel_case_types)]
@@ -12,7 +13,12 @@ pub struct wasm_store_t {
 pub unsafe extern "C" fn wasm_store_new(
     wasm_engine_ptr: Option<NonNull<wasm_engine_t>>,
 ) -> Option<Box<wasm_store_t>> {
-    let wasm_engine_ptr = wasm_engine_ptr?;
+    let wasm_engine_ptr = c_try!(
+        wasm_engine_ptr,
+        CApiError {
+            msg: "the engine is null".to_string(),
+        }
+    );
     let wasm_engine = wasm_engine_ptr.as_ref();
     let store = Store::new(&*wasm_engine.inner);
 
@@ -21,3 +27,13 @@ pub unsafe extern "C" fn wasm_store_new(
 
 #[no_mangle]
 pub unsafe extern "C" fn wasm_store_delete(_store: Option<Box<wasm_store_t>>) {}
+
+/// Returns the kind of the engine of the store.
+#[no_mangle]
+pub extern "C" fn wasm_store_engine_kind(store: &wasm_store_t) -> wasmer_engine_t {
+    match store.inner.engine_kind() {
+        EngineKind::Native => wasmer_engine_t::NATIVE,
+        EngineKind::ObjectFile => wasmer_engine_t::OBJECT_FILE,
+        _ => wasmer_engine_t::JIT,
+    }
+}
@@ -1,5 +1,6 @@
 use super::store::wasm_store_t;
 use super::types::{wasm_byte_vec_t, wasm_frame_t, wasm_frame_vec_t, wasm_message_t};
+use crate::error::CApiError;
 use wasmer::RuntimeError;
 
 // opaque type which is a `RuntimeError`
@@ -19,7 +20,12 @@ pub unsafe extern "C" fn wasm_trap_new(
     _store: &mut wasm_store_t,
     message: &wasm_message_t,
 ) -> Option<Box<wasm_trap_t>> {
-    let message_bytes: &[u8] = message.into_slice()?;
+    let message_bytes: &[u8] = c_try!(
+        message.into_slice(),
+        CApiError {
+            msg: "the trap message is null".to_string(),
+        }
+    );
     let message_str = c_try!(std::str::from_utf8(message_bytes));
     let runtime_error = RuntimeError::new(message_str);
     let trap = runtime_error.into();
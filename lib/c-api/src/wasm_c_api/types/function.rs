@@ -1,5 +1,8 @@
 use super::{wasm_externtype_t, wasm_valtype_t, wasm_valtype_vec_t};
+use crate::error::CApiError;
+use std::ffi::CStr;
 use std::mem;
+use std::os::raw::c_char;
 use std::ptr::NonNull;
 use wasmer::{ExternType, FunctionType, ValType};
 
@@ -65,6 +68,28 @@ unsafe fn wasm_functype_new_inner(
     ))))
 }
 
+/// Creates a function type from a signature string such as
+/// `(i32, i64) -> f64`. This is wasmer-specific.
+///
+/// In case of failure, `wasm_functype_new_from_string` returns `NULL`,
+/// and the error message, which gives the position of the error in the
+/// signature, can be read with `wasmer_last_error_message`.
+#[no_mangle]
+pub unsafe extern "C" fn wasm_functype_new_from_string(
+    signature: *const c_char,
+) -> Option<Box<wasm_functype_t>> {
+    let signature = c_try!(
+        NonNull::new(signature as *mut c_char),
+        CApiError {
+            msg: "the signature is null".to_string(),
+        }
+    );
+    let signature = c_try!(CStr::from_ptr(signature.as_ptr()).to_str());
+    let function_type: FunctionType = c_try!(signature.parse());
+
+    Some(Box::new(wasm_functype_t::new(function_type)))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn wasm_functype_delete(_ft: Option<Box<wasm_functype_t>>) {}
 
@@ -1,6 +1,7 @@
 use super::{
     wasm_externtype_t, wasm_mutability_enum, wasm_mutability_t, wasm_valtype_delete, wasm_valtype_t,
 };
+use crate::error::CApiError;
 use std::convert::TryInto;
 use wasmer::{ExternType, GlobalType};
 
@@ -41,7 +42,7 @@ unsafe fn wasm_globaltype_new_inner(
     valtype: Box<wasm_valtype_t>,
     mutability: wasm_mutability_t,
 ) -> Option<Box<wasm_globaltype_t>> {
-    let me: wasm_mutability_enum = mutability.try_into().ok()?;
+    let me: wasm_mutability_enum = c_try!(mutability.try_into().map_err(CApiError::from));
     let gd = Box::new(wasm_globaltype_t {
         extern_: wasm_externtype_t {
             inner: ExternType::Global(GlobalType::new((*valtype).into(), me.into())),
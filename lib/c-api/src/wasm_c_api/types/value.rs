@@ -1,4 +1,5 @@
 use super::super::value::wasm_valkind_t;
+use crate::error::CApiError;
 use std::convert::TryInto;
 use wasmer::ValType;
 
@@ -74,7 +75,7 @@ impl From<ValType> for wasm_valtype_t {
 
 #[no_mangle]
 pub extern "C" fn wasm_valtype_new(kind: wasm_valkind_t) -> Option<Box<wasm_valtype_t>> {
-    let kind_enum = kind.try_into().ok()?;
+    let kind_enum: wasm_valkind_enum = c_try!(kind.try_into().map_err(CApiError::from));
     let valtype = wasm_valtype_t { valkind: kind_enum };
     Some(Box::new(valtype))
 }
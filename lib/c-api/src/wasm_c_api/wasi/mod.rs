@@ -176,10 +176,12 @@ pub extern "C" fn wasi_env_delete(_state: Option<Box<wasi_env_t>>) {}
 
 #[no_mangle]
 pub extern "C" fn wasi_env_set_instance(env: &mut wasi_env_t, instance: &wasm_instance_t) -> bool {
-    let memory = if let Ok(memory) = instance.inner.exports.get_memory("memory") {
-        memory
-    } else {
-        return false;
+    let memory = match instance.inner.exports.get_memory("memory") {
+        Ok(memory) => memory,
+        Err(e) => {
+            update_last_error(e);
+            return false;
+        }
     };
     env.inner.set_memory(memory.clone());
 
@@ -210,6 +212,9 @@ pub unsafe extern "C" fn wasi_env_read_stdout(
             return -1;
         }
     } else {
+        update_last_error(CApiError {
+            msg: "could not find a file handle for `stdout`".to_string(),
+        });
         return -1;
     };
     read_inner(stdout, inner_buffer)
@@ -1,4 +1,5 @@
 use super::types::wasm_byte_vec_t;
+use crate::error::CApiError;
 
 /// Parses in-memory bytes as either the WAT format, or a binary Wasm
 /// module. This is wasmer-specific.
@@ -7,7 +8,12 @@ use super::types::wasm_byte_vec_t;
 #[cfg(feature = "wat")]
 #[no_mangle]
 pub unsafe extern "C" fn wat2wasm(wat: &wasm_byte_vec_t) -> Option<Box<wasm_byte_vec_t>> {
-    let wat: &[u8] = wat.into_slice()?;
+    let wat: &[u8] = c_try!(
+        wat.into_slice(),
+        CApiError {
+            msg: "the WAT bytes are null".to_string(),
+        }
+    );
     let result: wasm_byte_vec_t = c_try!(wasmer::wat2wasm(wat)).into_owned().into();
 
     Some(Box::new(result))
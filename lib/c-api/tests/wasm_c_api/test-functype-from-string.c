@@ -0,0 +1,56 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "wasmer_wasm.h"
+
+#define own
+
+int main(int argc, const char* argv[]) {
+  // Parsing a signature.
+  printf("Parsing a signature...\n");
+  own wasm_functype_t* functype = wasm_functype_new_from_string("(i32, i64) -> f64");
+
+  if (!functype) {
+    printf("> Error parsing the signature!\n");
+    return 1;
+  }
+
+  const wasm_valtype_vec_t* params = wasm_functype_params(functype);
+  const wasm_valtype_vec_t* results = wasm_functype_results(functype);
+
+  if (params->size != 2 ||
+      wasm_valtype_kind(params->data[0]) != WASM_I32 ||
+      wasm_valtype_kind(params->data[1]) != WASM_I64 ||
+      results->size != 1 ||
+      wasm_valtype_kind(results->data[0]) != WASM_F64) {
+    printf("The function type is incorrect!\n");
+    return 1;
+  }
+
+  wasm_functype_delete(functype);
+
+  // Parsing a malformed signature.
+  printf("Parsing a malformed signature...\n");
+  functype = wasm_functype_new_from_string("(i32, i33) -> f64");
+
+  if (functype) {
+    printf("> The malformed signature was parsed!\n");
+    return 1;
+  }
+
+  int error_length = wasmer_last_error_length();
+  char* error_message = malloc(error_length);
+  wasmer_last_error_message(error_message, error_length);
+
+  if (strcmp(error_message, "unknown type `i33` at position 6") != 0) {
+    printf("The error message is incorrect: `%s`\n", error_message);
+    return 1;
+  }
+
+  free(error_message);
+
+  // All done.
+  printf("Done.\n");
+  return 0;
+}
@@ -162,12 +162,27 @@ void wasm_config_set_compiler(wasm_config_t *config, wasmer_compiler_t compiler)
 
 void wasm_config_set_engine(wasm_config_t *config, wasmer_engine_t engine);
 
+/**
+ * Creates a function type from a signature string such as
+ * `(i32, i64) -> f64`. This is wasmer-specific.
+ *
+ * In case of failure, `wasm_functype_new_from_string` returns `NULL`,
+ * and the error message, which gives the position of the error in the
+ * signature, can be read with `wasmer_last_error_message`.
+ */
+wasm_functype_t *wasm_functype_new_from_string(const char *signature);
+
 void *wasm_instance_get_vmctx_ptr(const wasm_instance_t *instance);
 
 void wasm_module_name(const wasm_module_t *module, wasm_name_t *out);
 
 bool wasm_module_set_name(wasm_module_t *module, const wasm_name_t *name);
 
+/**
+ * Returns the kind of the engine of the store.
+ */
+wasmer_engine_t wasm_store_engine_kind(const wasm_store_t *store);
+
 /**
  * Gets the length in bytes of the last error if any.
  *
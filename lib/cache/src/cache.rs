@@ -4,7 +4,35 @@
 
 use crate::hash::Hash;
 use std::error::Error;
-use wasmer::{Module, Store};
+use std::io;
+use thiserror::Error;
+use wasmer::{CompileError, Module, Store};
+
+/// The counters of the operations of a [`Cache`], to export to a metrics
+/// system.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheMetrics {
+    /// The number of modules loaded from the cache.
+    pub hits: u64,
+    /// The number of modules that were not in the cache.
+    pub misses: u64,
+    /// The number of modules removed to keep the cache under its maximum
+    /// size.
+    pub evictions: u64,
+    /// The number of modules in the cache that failed to load.
+    pub load_failures: u64,
+}
+
+/// The error of [`Cache::load_or_compile`].
+#[derive(Error, Debug)]
+pub enum LoadOrCompileError<E: Error + 'static> {
+    /// The module failed to compile.
+    #[error(transparent)]
+    Compile(#[from] CompileError),
+    /// The compiled module failed to be stored in the cache.
+    #[error(transparent)]
+    Store(E),
+}
 
 /// A generic cache for storing and loading compiled wasm modules.
 pub trait Cache {
@@ -21,4 +49,61 @@ pub trait Cache {
 
     /// Store a [`Module`] into the cache with the given [`Hash`].
     fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError>;
+
+    /// Removes the module stored with the given [`Hash`], returning
+    /// whether there was one.
+    ///
+    /// The default implementation removes nothing, and returns `false`.
+    fn remove(&mut self, _key: Hash) -> io::Result<bool> {
+        Ok(false)
+    }
+
+    /// Removes all the modules of the cache.
+    ///
+    /// The default implementation removes nothing.
+    fn clear(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The size of the modules in the cache, in bytes.
+    ///
+    /// The default implementation returns 0, for the caches that don't
+    /// track it.
+    fn size_bytes(&self) -> u64 {
+        0
+    }
+
+    /// The counters of the operations of the cache so far.
+    ///
+    /// The default implementation returns zeroed counters, for the caches
+    /// that don't count their operations.
+    fn metrics(&self) -> CacheMetrics {
+        CacheMetrics::default()
+    }
+
+    /// Loads the module stored with the given [`Hash`] or, if it can't be
+    /// loaded, compiles `wasm` and stores the resulting module.
+    ///
+    /// The cache records the outcome of the call as a hit, a miss or a load
+    /// failure in its [`CacheMetrics`].
+    ///
+    /// # Safety
+    /// This function is unsafe as the cache store could be tampered with.
+    unsafe fn load_or_compile(
+        &mut self,
+        store: &Store,
+        key: Hash,
+        wasm: &[u8],
+    ) -> Result<Module, LoadOrCompileError<Self::SerializeError>>
+    where
+        Self::SerializeError: 'static,
+    {
+        if let Ok(module) = self.load(store, key) {
+            return Ok(module);
+        }
+        let module = Module::new(store, wasm)?;
+        self.store(key, &module)
+            .map_err(LoadOrCompileError::Store)?;
+        Ok(module)
+    }
 }
@@ -1,15 +1,31 @@
-use crate::cache::Cache;
+use crate::cache::{Cache, CacheMetrics};
 use crate::hash::Hash;
-use std::fs::{create_dir_all, File};
+use std::collections::HashMap;
+use std::fs::{self, create_dir_all, File};
 use std::io::{self, Write};
-use std::path::PathBuf;
-use wasmer::{DeserializeError, Module, SerializeError, Store};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use wasmer::{DeserializeError, Module, SerializeError, Store, VERSION};
+
+/// The first line of the metadata file of a cached module.
+const METADATA_HEADER: &str = "wasmer-cache";
+
+/// The extension of the metadata file of a cached module.
+const METADATA_EXTENSION: &str = "meta";
 
 /// Representation of a directory that contains compiled wasm artifacts.
 ///
 /// The `FileSystemCache` type implements the [`Cache`] trait, which allows it to be used
 /// generically when some sort of cache is required.
 ///
+/// Each module is stored in its own file, next to a metadata file recording
+/// the version of Wasmer that stored it, and the size and hash of the
+/// module, see `validate`. The cache can be kept under a maximum size by
+/// removing the least recently used modules, see `set_max_size`.
+///
 /// # Usage
 ///
 /// ```
@@ -33,17 +49,138 @@ use wasmer::{DeserializeError, Module, SerializeError, Store};
 pub struct FileSystemCache {
     path: PathBuf,
     ext: Option<String>,
+    max_size: Option<u64>,
+    index: Mutex<Index>,
+    counters: Counters,
+}
+
+/// The modules of the cache, with their size and last use.
+#[derive(Default)]
+struct Index {
+    entries: HashMap<Hash, Entry>,
+    /// Incremented on every use of a module.
+    clock: u64,
+}
+
+struct Entry {
+    size: u64,
+    last_use: u64,
+}
+
+impl Index {
+    /// Records a use of the module stored with `key`.
+    fn touch(&mut self, key: Hash, size: u64) {
+        self.clock += 1;
+        self.entries.insert(
+            key,
+            Entry {
+                size,
+                last_use: self.clock,
+            },
+        );
+    }
+
+    fn size_bytes(&self) -> u64 {
+        self.entries.values().map(|entry| entry.size).sum()
+    }
+
+    /// Returns the least recently used module, other than `keep`.
+    fn least_recently_used(&self, keep: Option<Hash>) -> Option<Hash> {
+        self.entries
+            .iter()
+            .filter(|(&key, _)| Some(key) != keep)
+            .min_by_key(|(_, entry)| entry.last_use)
+            .map(|(&key, _)| key)
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    load_failures: AtomicU64,
+}
+
+/// The contents of the metadata file of a cached module.
+struct Metadata {
+    version: String,
+    size: u64,
+    hash: Hash,
+}
+
+impl Metadata {
+    fn new(module: &[u8]) -> Self {
+        Self {
+            version: VERSION.to_string(),
+            size: module.len() as u64,
+            hash: Hash::generate(module),
+        }
+    }
+
+    fn read(path: &Path) -> Result<Self, DeserializeError> {
+        let contents = fs::read_to_string(path)?;
+        let corrupted = || {
+            DeserializeError::CorruptedBinary(format!("invalid metadata file: {}", path.display()))
+        };
+        let mut lines = contents.lines();
+        if lines.next() != Some(METADATA_HEADER) {
+            return Err(corrupted());
+        }
+        let version = lines.next().ok_or_else(corrupted)?.to_string();
+        let size = lines
+            .next()
+            .and_then(|size| size.parse().ok())
+            .ok_or_else(corrupted)?;
+        let hash = lines
+            .next()
+            .and_then(|hash| Hash::from_str(hash).ok())
+            .ok_or_else(corrupted)?;
+        Ok(Self {
+            version,
+            size,
+            hash,
+        })
+    }
+
+    fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(
+            path,
+            format!(
+                "{}\n{}\n{}\n{}\n",
+                METADATA_HEADER,
+                self.version,
+                self.size,
+                self.hash.to_string()
+            ),
+        )
+    }
+
+    /// Checks that the module was stored by this version of Wasmer.
+    fn check_version(&self) -> Result<(), DeserializeError> {
+        if self.version == VERSION {
+            Ok(())
+        } else {
+            Err(DeserializeError::Incompatible(format!(
+                "the module was cached by Wasmer {}, not {}",
+                self.version, VERSION
+            )))
+        }
+    }
 }
 
 impl FileSystemCache {
     /// Construct a new `FileSystemCache` around the specified directory.
+    ///
+    /// The modules already in the directory are considered used in the
+    /// order they were stored.
     pub fn new<P: Into<PathBuf>>(path: P) -> io::Result<Self> {
         let path: PathBuf = path.into();
         if path.exists() {
             let metadata = path.metadata()?;
             if metadata.is_dir() {
                 if !metadata.permissions().readonly() {
-                    Ok(Self { path, ext: None })
+                    Self::open(path)
                 } else {
                     // This directory is readonly.
                     Err(io::Error::new(
@@ -64,7 +201,7 @@ impl FileSystemCache {
         } else {
             // Create the directory and any parent directories if they don't yet exist.
             create_dir_all(&path)?;
-            Ok(Self { path, ext: None })
+            Self::open(path)
         }
     }
 
@@ -75,34 +212,201 @@ impl FileSystemCache {
     pub fn set_cache_extension(&mut self, ext: Option<impl ToString>) {
         self.ext = ext.map(|ext| ext.to_string());
     }
-}
 
-impl Cache for FileSystemCache {
-    type DeserializeError = DeserializeError;
-    type SerializeError = SerializeError;
+    /// Set the maximum size of the modules in the cache, in bytes.
+    ///
+    /// Whenever the cache grows beyond it, the least recently stored or
+    /// loaded modules are removed, except the module just stored.
+    pub fn set_max_size(&mut self, max_size: Option<u64>) -> io::Result<()> {
+        self.max_size = max_size;
+        self.evict(None)
+    }
 
-    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
-        let filename = if let Some(ref ext) = self.ext {
-            format!("{}.{}", key.to_string(), ext)
-        } else {
-            key.to_string()
+    /// Removes the modules that can't be loaded: those whose metadata file
+    /// is missing or corrupted, that were stored by another version of
+    /// Wasmer, or whose contents don't match their metadata. It returns the
+    /// number of removed modules.
+    ///
+    /// This is useful after upgrading Wasmer.
+    pub fn validate(&mut self) -> io::Result<usize> {
+        let mut removed = 0;
+        for (key, _, _) in self.scan()? {
+            if self.check(key).is_err() {
+                self.remove(key)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn open(path: PathBuf) -> io::Result<Self> {
+        let mut cache = Self {
+            path,
+            ext: None,
+            max_size: None,
+            index: Mutex::default(),
+            counters: Counters::default(),
         };
-        let path = self.path.join(filename);
-        Module::deserialize_from_file(&store, path)
+        let mut entries = cache.scan()?;
+        entries.sort_by_key(|&(_, _, modified)| modified);
+        let index = cache.index.get_mut().unwrap();
+        for (key, size, _) in entries {
+            index.touch(key, size);
+        }
+        Ok(cache)
     }
 
-    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+    /// Lists the modules in the directory, with their size and modification
+    /// time.
+    fn scan(&self) -> io::Result<Vec<(Hash, u64, SystemTime)>> {
+        let mut entries = Vec::new();
+        for file in fs::read_dir(&self.path)? {
+            let file = file?;
+            let name = file.file_name();
+            let mut parts = match name.to_str() {
+                Some(name) => name.splitn(2, '.'),
+                None => continue,
+            };
+            let key = match parts.next().map(Hash::from_str) {
+                Some(Ok(key)) => key,
+                _ => continue,
+            };
+            if parts.next() == Some(METADATA_EXTENSION) {
+                continue;
+            }
+            let metadata = file.metadata()?;
+            entries.push((key, metadata.len(), metadata.modified()?));
+        }
+        Ok(entries)
+    }
+
+    fn module_path(&self, key: Hash) -> PathBuf {
         let filename = if let Some(ref ext) = self.ext {
             format!("{}.{}", key.to_string(), ext)
         } else {
             key.to_string()
         };
-        let path = self.path.join(filename);
-        let mut file = File::create(path)?;
+        self.path.join(filename)
+    }
+
+    fn metadata_path(&self, key: Hash) -> PathBuf {
+        self.path
+            .join(format!("{}.{}", key.to_string(), METADATA_EXTENSION))
+    }
+
+    /// Checks that a module matches its metadata.
+    fn check(&self, key: Hash) -> Result<(), DeserializeError> {
+        let metadata = Metadata::read(&self.metadata_path(key))?;
+        metadata.check_version()?;
+        let path = self.module_path(key);
+        let module = fs::read(&path)?;
+        if module.len() as u64 != metadata.size || Hash::generate(&module) != metadata.hash {
+            return Err(DeserializeError::CorruptedBinary(format!(
+                "the module doesn't match its metadata: {}",
+                path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Removes the least recently used modules, other than `keep`, until
+    /// the cache fits in its maximum size.
+    fn evict(&mut self, keep: Option<Hash>) -> io::Result<()> {
+        let max_size = match self.max_size {
+            Some(max_size) => max_size,
+            None => return Ok(()),
+        };
+        loop {
+            let index = self.index.get_mut().unwrap();
+            if index.size_bytes() <= max_size {
+                return Ok(());
+            }
+            match index.least_recently_used(keep) {
+                Some(key) => {
+                    self.remove(key)?;
+                    self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+                None => return Ok(()),
+            }
+        }
+    }
+}
 
+impl Cache for FileSystemCache {
+    type DeserializeError = DeserializeError;
+    type SerializeError = SerializeError;
+
+    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+        let path = self.module_path(key);
+        if !path.exists() {
+            self.counters.misses.fetch_add(1, Ordering::Relaxed);
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no module cached at {}", path.display()),
+            )
+            .into());
+        }
+        let result = Metadata::read(&self.metadata_path(key)).and_then(|metadata| {
+            metadata.check_version()?;
+            let module = Module::deserialize_from_file(&store, path)?;
+            Ok((module, metadata.size))
+        });
+        match result {
+            Ok((module, size)) => {
+                self.counters.hits.fetch_add(1, Ordering::Relaxed);
+                self.index.lock().unwrap().touch(key, size);
+                Ok(module)
+            }
+            Err(e) => {
+                self.counters.load_failures.fetch_add(1, Ordering::Relaxed);
+                Err(e)
+            }
+        }
+    }
+
+    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
         let buffer = module.serialize()?;
+        let mut file = File::create(self.module_path(key))?;
         file.write_all(&buffer)?;
+        let metadata = Metadata::new(&buffer);
+        metadata.write(&self.metadata_path(key))?;
 
+        self.index.get_mut().unwrap().touch(key, metadata.size);
+        self.evict(Some(key))?;
         Ok(())
     }
+
+    fn remove(&mut self, key: Hash) -> io::Result<bool> {
+        self.index.get_mut().unwrap().entries.remove(&key);
+        let removed = match fs::remove_file(self.module_path(key)) {
+            Ok(()) => true,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+            Err(e) => return Err(e),
+        };
+        match fs::remove_file(self.metadata_path(key)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(removed),
+        }
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        for (key, _, _) in self.scan()? {
+            self.remove(key)?;
+        }
+        self.index.get_mut().unwrap().entries.clear();
+        Ok(())
+    }
+
+    fn size_bytes(&self) -> u64 {
+        self.index.lock().unwrap().size_bytes()
+    }
+
+    fn metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            evictions: self.counters.evictions.load(Ordering::Relaxed),
+            load_failures: self.counters.load_failures.load(Ordering::Relaxed),
+        }
+    }
 }
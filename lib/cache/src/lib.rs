@@ -23,7 +23,7 @@ mod cache;
 mod filesystem;
 mod hash;
 
-pub use crate::cache::Cache;
+pub use crate::cache::{Cache, CacheMetrics, LoadOrCompileError};
 pub use crate::filesystem::FileSystemCache;
 pub use crate::hash::Hash;
 
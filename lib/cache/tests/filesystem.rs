@@ -0,0 +1,198 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use wasmer::{Module, Store};
+use wasmer_cache::{Cache, CacheMetrics, FileSystemCache, Hash};
+
+/// Compiles a module returning `value`, whose serialized size doesn't
+/// depend on `value`.
+fn module(store: &Store, value: u8) -> Result<(Hash, Module)> {
+    let wat = format!(
+        r#"(module (func (export "get") (result i32) (i32.const {})))"#,
+        value % 64
+    );
+    Ok((
+        Hash::generate(wat.as_bytes()),
+        Module::new(store, wat.as_bytes())?,
+    ))
+}
+
+fn directory_size(path: &Path) -> Result<u64> {
+    let mut size = 0;
+    for file in fs::read_dir(path)? {
+        let file = file?;
+        if file.path().extension().map_or(true, |ext| ext != "meta") {
+            size += file.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+#[test]
+fn load_and_store() -> Result<()> {
+    let dir = TempDir::new()?;
+    let store = Store::default();
+    let mut cache = FileSystemCache::new(dir.path())?;
+    let (key, module) = module(&store, 1)?;
+
+    assert!(unsafe { cache.load(&store, key) }.is_err());
+    cache.store(key, &module)?;
+    let loaded = unsafe { cache.load(&store, key) }?;
+    assert_eq!(loaded.serialize()?, module.serialize()?);
+    assert_eq!(cache.size_bytes(), module.serialize()?.len() as u64);
+    assert_eq!(
+        cache.metrics(),
+        CacheMetrics {
+            hits: 1,
+            misses: 1,
+            ..CacheMetrics::default()
+        }
+    );
+
+    // Another cache on the same directory finds the module.
+    let reopened = FileSystemCache::new(dir.path())?;
+    assert_eq!(reopened.size_bytes(), cache.size_bytes());
+    assert!(unsafe { reopened.load(&store, key) }.is_ok());
+    Ok(())
+}
+
+#[test]
+fn remove_and_clear() -> Result<()> {
+    let dir = TempDir::new()?;
+    let store = Store::default();
+    let mut cache = FileSystemCache::new(dir.path())?;
+    let (first, first_module) = module(&store, 1)?;
+    let (second, second_module) = module(&store, 2)?;
+    cache.store(first, &first_module)?;
+    cache.store(second, &second_module)?;
+
+    assert!(cache.remove(first)?);
+    assert!(!cache.remove(first)?);
+    assert!(unsafe { cache.load(&store, first) }.is_err());
+    assert_eq!(cache.size_bytes(), second_module.serialize()?.len() as u64);
+
+    cache.clear()?;
+    assert_eq!(cache.size_bytes(), 0);
+    assert_eq!(fs::read_dir(dir.path())?.count(), 0);
+    Ok(())
+}
+
+#[test]
+fn validate_removes_invalid_modules() -> Result<()> {
+    let dir = TempDir::new()?;
+    let store = Store::default();
+    let mut cache = FileSystemCache::new(dir.path())?;
+    let modules = (0..4)
+        .map(|value| module(&store, value))
+        .collect::<Result<Vec<_>>>()?;
+    for (key, module) in &modules {
+        cache.store(*key, module)?;
+    }
+
+    // Corrupt the contents of a module, and pretend another one was stored
+    // by another version of Wasmer.
+    let corrupted = modules[1].0;
+    let path = dir.path().join(corrupted.to_string());
+    let mut contents = fs::read(&path)?;
+    let middle = contents.len() / 2;
+    contents[middle] ^= 0xff;
+    fs::write(&path, contents)?;
+    let outdated = modules[2].0;
+    let path = dir.path().join(format!("{}.meta", outdated.to_string()));
+    let metadata = fs::read_to_string(&path)?.replacen(wasmer::VERSION, "0.17.0", 1);
+    fs::write(&path, metadata)?;
+
+    // Loading an outdated module fails.
+    assert!(unsafe { cache.load(&store, outdated) }.is_err());
+    assert_eq!(cache.metrics().load_failures, 1);
+
+    assert_eq!(cache.validate()?, 2);
+    assert!(!dir.path().join(corrupted.to_string()).exists());
+    assert!(!dir.path().join(outdated.to_string()).exists());
+    assert_eq!(cache.validate()?, 0);
+    for &key in &[modules[0].0, modules[3].0] {
+        assert!(unsafe { cache.load(&store, key) }.is_ok());
+    }
+    assert_eq!(cache.size_bytes(), directory_size(dir.path())?);
+    Ok(())
+}
+
+#[test]
+fn evict_least_recently_used() -> Result<()> {
+    let dir = TempDir::new()?;
+    let store = Store::default();
+    let mut cache = FileSystemCache::new(dir.path())?;
+    let modules = (0..4)
+        .map(|value| module(&store, value))
+        .collect::<Result<Vec<_>>>()?;
+    let size = modules[0].1.serialize()?.len() as u64;
+    for (_, module) in &modules {
+        assert_eq!(module.serialize()?.len() as u64, size);
+    }
+    let exists = |index: usize| dir.path().join(modules[index].0.to_string()).exists();
+
+    // The cache fits two modules.
+    cache.set_max_size(Some(2 * size))?;
+    cache.store(modules[0].0, &modules[0].1)?;
+    cache.store(modules[1].0, &modules[1].1)?;
+    assert_eq!(cache.metrics().evictions, 0);
+
+    cache.store(modules[2].0, &modules[2].1)?;
+    assert!(!exists(0));
+    assert_eq!(cache.metrics().evictions, 1);
+
+    // Loading a module makes it the most recently used.
+    unsafe { cache.load(&store, modules[1].0) }?;
+    cache.store(modules[3].0, &modules[3].1)?;
+    assert!(exists(1));
+    assert!(!exists(2));
+    assert!(exists(3));
+    assert_eq!(cache.metrics().evictions, 2);
+    assert_eq!(cache.size_bytes(), 2 * size);
+    assert_eq!(cache.size_bytes(), directory_size(dir.path())?);
+
+    // Shrinking the cache evicts the least recently used module.
+    cache.set_max_size(Some(size))?;
+    assert!(!exists(1));
+    assert!(exists(3));
+    assert_eq!(cache.metrics().evictions, 3);
+    assert_eq!(cache.size_bytes(), directory_size(dir.path())?);
+    Ok(())
+}
+
+#[test]
+fn load_or_compile() -> Result<()> {
+    let dir = TempDir::new()?;
+    let store = Store::default();
+    let mut cache = FileSystemCache::new(dir.path())?;
+    let wasm = br#"(module (func (export "get") (result i32) (i32.const 1)))"#;
+    let key = Hash::generate(wasm);
+
+    unsafe { cache.load_or_compile(&store, key, wasm) }?;
+    assert_eq!(
+        cache.metrics(),
+        CacheMetrics {
+            misses: 1,
+            ..CacheMetrics::default()
+        }
+    );
+    unsafe { cache.load_or_compile(&store, key, wasm) }?;
+    assert_eq!(cache.metrics().hits, 1);
+
+    // A module that fails to load is compiled and stored again.
+    fs::write(dir.path().join(key.to_string()), b"corrupted")?;
+    unsafe { cache.load_or_compile(&store, key, wasm) }?;
+    assert_eq!(cache.metrics().load_failures, 1);
+    unsafe { cache.load_or_compile(&store, key, wasm) }?;
+    assert_eq!(
+        cache.metrics(),
+        CacheMetrics {
+            hits: 2,
+            misses: 1,
+            evictions: 0,
+            load_failures: 1,
+        }
+    );
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::io;
+use wasmer::{DeserializeError, Module, SerializeError, Store};
+use wasmer_cache::{Cache, CacheMetrics, Hash};
+
+/// A cache keeping the serialized modules in memory, which only implements
+/// the required methods of `Cache`.
+#[derive(Default)]
+struct MemoryCache {
+    modules: HashMap<Hash, Vec<u8>>,
+}
+
+impl Cache for MemoryCache {
+    type DeserializeError = DeserializeError;
+    type SerializeError = SerializeError;
+
+    unsafe fn load(&self, store: &Store, key: Hash) -> Result<Module, Self::DeserializeError> {
+        match self.modules.get(&key) {
+            Some(bytes) => Module::deserialize(store, bytes),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "no module cached").into()),
+        }
+    }
+
+    fn store(&mut self, key: Hash, module: &Module) -> Result<(), Self::SerializeError> {
+        self.modules.insert(key, module.serialize()?);
+        Ok(())
+    }
+}
+
+#[test]
+fn default_methods() -> Result<()> {
+    let store = Store::default();
+    let mut cache = MemoryCache::default();
+    let wasm = br#"(module (func (export "get") (result i32) (i32.const 1)))"#;
+    let key = Hash::generate(wasm);
+
+    unsafe { cache.load_or_compile(&store, key, wasm) }?;
+    assert!(unsafe { cache.load(&store, key) }.is_ok());
+
+    assert!(!cache.remove(key)?);
+    cache.clear()?;
+    assert_eq!(cache.size_bytes(), 0);
+    assert_eq!(cache.metrics(), CacheMetrics::default());
+    // The defaults leave the modules in place.
+    assert!(unsafe { cache.load(&store, key) }.is_ok());
+    Ok(())
+}
@@ -102,6 +102,7 @@ impl Compile {
             "✔ File compiled successfully to `{}`.",
             self.output.display(),
         );
+        super::inspect::print_footprint(&module.footprint());
 
         #[cfg(feature = "object-file")]
         if engine_type == EngineType::ObjectFile {
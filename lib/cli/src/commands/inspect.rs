@@ -35,6 +35,8 @@ impl Inspect {
             }
         );
         println!("Size: {}", ByteSize(module_contents.len() as _));
+        println!("Debug info: {}", module.info().debug_info_policy);
+        print_footprint(&module.footprint());
         println!("Imports:");
         println!("  Functions:");
         for f in module.imports().functions() {
@@ -72,3 +74,27 @@ impl Inspect {
         Ok(())
     }
 }
+
+/// Prints the footprint of a compiled module.
+pub(crate) fn print_footprint(footprint: &ModuleFootprint) {
+    println!("Footprint:");
+    println!("  Functions: {}", footprint.functions);
+    println!("  Trampolines: {}", footprint.trampolines);
+    println!("  Code: {}", ByteSize(footprint.code_bytes as _));
+    println!(
+        "  Read-only data: {}",
+        ByteSize(footprint.rodata_bytes as _)
+    );
+    println!(
+        "  Unwind info: {}",
+        ByteSize(footprint.unwind_info_bytes as _)
+    );
+    println!(
+        "  Frame info: {}",
+        ByteSize(footprint.frame_info_bytes as _)
+    );
+    println!(
+        "  Serialized: {}",
+        ByteSize(footprint.serialized_bytes as _)
+    );
+}
@@ -29,6 +29,10 @@ pub struct WasmFeatures {
     #[structopt(long = "enable-bulk-memory")]
     pub bulk_memory: bool,
 
+    /// Enable support for the extended constant expressions proposal.
+    #[structopt(long = "enable-extended-const")]
+    pub extended_const: bool,
+
     /// Enable support for all pre-standard proposals.
     #[structopt(long = "enable-all")]
     pub all: bool,
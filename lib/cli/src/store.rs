@@ -113,6 +113,9 @@ impl CompilerOptions {
         if self.features.reference_types || self.features.all {
             features.reference_types(true);
         }
+        if self.features.extended_const || self.features.all {
+            features.extended_const(true);
+        }
         Ok(features)
     }
 
@@ -200,10 +203,10 @@ impl CompilerOptions {
                 use std::fmt;
                 use std::fs::File;
                 use std::io::Write;
+                use wasmer::types::EntityRef;
                 use wasmer_compiler_llvm::{
                     CompiledKind, InkwellMemoryBuffer, InkwellModule, LLVMCallbacks, LLVM,
                 };
-                use wasmer_types::entity::EntityRef;
                 let mut config = LLVM::new();
                 struct Callbacks {
                     debug_dir: PathBuf,
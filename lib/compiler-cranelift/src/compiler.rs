@@ -10,23 +10,25 @@ use crate::trampoline::{
     make_trampoline_dynamic_function, make_trampoline_function_call, FunctionBuilderContext,
 };
 use crate::translator::{
-    compiled_function_unwind_info, signature_to_cranelift_ir, transform_jump_table,
-    CraneliftUnwindInfo, FuncTranslator,
+    compiled_function_unwind_info, jump_table_entries, signature_to_cranelift_ir,
+    transform_jump_table, CraneliftUnwindInfo, FuncTranslator,
 };
 use cranelift_codegen::ir;
 use cranelift_codegen::print_errors::pretty_error;
-use cranelift_codegen::{binemit, Context};
+use cranelift_codegen::{binemit, CodegenError, Context};
 #[cfg(feature = "unwind")]
 use gimli::write::{Address, EhFrame, FrameTable};
 use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 use wasmer_compiler::CompileError;
 use wasmer_compiler::{CallingConvention, ModuleTranslationState, Target};
 use wasmer_compiler::{
-    Compilation, CompileModuleInfo, CompiledFunction, CompiledFunctionFrameInfo,
-    CompiledFunctionUnwindInfo, Compiler, Dwarf, FunctionBody, FunctionBodyData, SectionIndex,
+    Compilation, CompilationProgress, CompileModuleInfo, CompiledFunction,
+    CompiledFunctionFrameInfo, CompiledFunctionUnwindInfo, Compiler, DebugInfoPolicy, Dwarf,
+    FunctionBody, FunctionBodyData, SectionIndex,
 };
 use wasmer_types::entity::{EntityRef, PrimaryMap};
-use wasmer_types::{FunctionIndex, LocalFunctionIndex, SignatureIndex};
+use wasmer_types::{FunctionIndex, LocalFunctionIndex, MemoryIndex, SignatureIndex};
+use wasmer_vm::MemoryStyle;
 
 /// A compiler that compiles a WebAssembly module with Cranelift, translating the Wasm to Cranelift IR,
 /// optimizing it and then translating to assembly.
@@ -49,6 +51,14 @@ impl CraneliftCompiler {
 }
 
 impl Compiler for CraneliftCompiler {
+    fn prune_dead_functions(&self) -> bool {
+        self.config.enable_dead_function_pruning
+    }
+
+    fn debug_info_policy(&self) -> DebugInfoPolicy {
+        self.config.debug_info_policy
+    }
+
     /// Compile the module using Cranelift, producing a compilation result with
     /// associated relocations.
     fn compile_module(
@@ -57,10 +67,26 @@ impl Compiler for CraneliftCompiler {
         compile_info: &CompileModuleInfo,
         module_translation: &ModuleTranslationState,
         function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+        progress: &CompilationProgress,
     ) -> Result<Compilation, CompileError> {
-        let isa = self.config().isa(target);
+        let isa = self.config().try_isa(target)?;
         let frontend_config = isa.frontend_config();
-        let memory_styles = &compile_info.memory_styles;
+        // Without bounds check elimination, every memory is compiled as a
+        // dynamic heap without guard pages, checking each access against
+        // the current length of the memory.
+        let checked_memory_styles;
+        let memory_styles = if self.config.disable_bounds_check_elimination {
+            checked_memory_styles = compile_info
+                .memory_styles
+                .values()
+                .map(|_| MemoryStyle::Dynamic {
+                    offset_guard_size: 0,
+                })
+                .collect::<PrimaryMap<MemoryIndex, _>>();
+            &checked_memory_styles
+        } else {
+            &compile_info.memory_styles
+        };
         let table_styles = &compile_info.table_styles;
         let module = &compile_info.module;
         let signatures = module
@@ -69,7 +95,7 @@ impl Compiler for CraneliftCompiler {
             .map(|(_sig_index, func_type)| signature_to_cranelift_ir(func_type, frontend_config))
             .collect::<PrimaryMap<SignatureIndex, ir::Signature>>();
 
-        // Generate the frametable
+        // Collect the FDEs of the frametable
         #[cfg(feature = "unwind")]
         let dwarf_frametable = if function_body_inputs.is_empty() {
             // If we have no function body inputs, we don't need to
@@ -77,15 +103,11 @@ impl Compiler for CraneliftCompiler {
             // FDEs will cause some issues in Linux.
             None
         } else {
-            use std::sync::{Arc, Mutex};
+            use std::sync::Mutex;
             match target.triple().default_calling_convention() {
                 Ok(CallingConvention::SystemV) => {
                     match isa.create_systemv_cie() {
-                        Some(cie) => {
-                            let mut dwarf_frametable = FrameTable::default();
-                            let cie_id = dwarf_frametable.add_cie(cie);
-                            Some((Arc::new(Mutex::new(dwarf_frametable)), cie_id))
-                        }
+                        Some(cie) => Some((Mutex::new(Vec::new()), cie)),
                         // Even though we are in a SystemV system, Cranelift doesn't support it
                         None => None,
                     }
@@ -94,11 +116,15 @@ impl Compiler for CraneliftCompiler {
             }
         };
 
-        let functions = function_body_inputs
-            .into_iter()
-            .collect::<Vec<(LocalFunctionIndex, &FunctionBodyData<'_>)>>()
-            .par_iter()
-            .map_init(FuncTranslator::new, |func_translator, (i, input)| {
+        let parallel = self.config.enable_parallel_compilation;
+        let functions = map_init(
+            parallel,
+            &function_body_inputs
+                .into_iter()
+                .collect::<Vec<(LocalFunctionIndex, &FunctionBodyData<'_>)>>(),
+            FuncTranslator::new,
+            |func_translator, (i, input)| {
+                progress.check_cancelled()?;
                 let func_index = module.func_index(*i);
                 let mut context = Context::new();
                 let mut func_env = FuncEnvironment::new(
@@ -110,13 +136,16 @@ impl Compiler for CraneliftCompiler {
                 );
                 context.func.name = get_function_name(func_index);
                 context.func.signature = signatures[module.functions[func_index]].clone();
+                if self.config.enable_explicit_stack_checks {
+                    func_env.enable_explicit_stack_checks();
+                }
                 // if generate_debug_info {
                 //     context.func.collect_debug_info();
                 // }
 
                 func_translator.translate(
                     module_translation,
-                    input.data,
+                    &input.data,
                     input.module_offset,
                     &mut context.func,
                     &mut func_env,
@@ -136,28 +165,34 @@ impl Compiler for CraneliftCompiler {
                         &mut trap_sink,
                         &mut stackmap_sink,
                     )
-                    .map_err(|error| {
-                        CompileError::Codegen(pretty_error(&context.func, Some(&*isa), error))
+                    .map_err(|error| match error {
+                        CodegenError::ImplLimitExceeded => {
+                            CompileError::ResourceLimit(error.to_string())
+                        }
+                        error => {
+                            CompileError::Codegen(pretty_error(&context.func, Some(&*isa), error))
+                        }
                     })?;
 
                 let unwind_info = match compiled_function_unwind_info(&*isa, &context)? {
                     #[cfg(feature = "unwind")]
                     CraneliftUnwindInfo::FDE(fde) => {
-                        if let Some((dwarf_frametable, cie_id)) = &dwarf_frametable {
-                            dwarf_frametable
-                                .lock()
-                                .expect("Can't write into DWARF frametable")
-                                .add_fde(
-                                    *cie_id,
-                                    fde.to_fde(Address::Symbol {
-                                        // The symbol is the kind of relocation.
-                                        // "0" is used for functions
-                                        symbol: WriterRelocate::FUNCTION_SYMBOL,
-                                        // We use the addend as a way to specify the
-                                        // function index
-                                        addend: i.index() as _,
-                                    }),
-                                );
+                        if let Some((fdes, _cie_id)) = &dwarf_frametable {
+                            // The FDEs are added to the frametable once all
+                            // the functions are compiled, in function order,
+                            // so the section doesn't depend on which thread
+                            // finishes first.
+                            fdes.lock().expect("Can't write into DWARF FDEs").push((
+                                *i,
+                                fde.to_fde(Address::Symbol {
+                                    // The symbol is the kind of relocation.
+                                    // "0" is used for functions
+                                    symbol: WriterRelocate::FUNCTION_SYMBOL,
+                                    // We use the addend as a way to specify the
+                                    // function index
+                                    addend: i.index() as _,
+                                }),
+                            ));
                             // The unwind information is inserted into the dwarf section
                             Some(CompiledFunctionUnwindInfo::Dwarf)
                         } else {
@@ -170,35 +205,43 @@ impl Compiler for CraneliftCompiler {
                 let address_map = get_function_address_map(&context, input, code_buf.len(), &*isa);
 
                 // We transform the Cranelift JumpTable's into compiler JumpTables
+                let func_jt_entries = jump_table_entries(&context.func);
                 let func_jt_offsets = transform_jump_table(context.func.jt_offsets);
 
-                Ok(CompiledFunction {
+                progress.functions_compiled(1);
+                let mut function = CompiledFunction {
                     body: FunctionBody {
                         body: code_buf,
                         unwind_info,
                     },
                     jt_offsets: func_jt_offsets,
+                    jt_entries: func_jt_entries,
                     relocations: reloc_sink.func_relocs,
                     frame_info: CompiledFunctionFrameInfo {
                         address_map,
                         traps: trap_sink.traps,
                     },
-                })
-            })
-            .collect::<Result<Vec<_>, CompileError>>()?
-            .into_iter()
-            .collect::<PrimaryMap<LocalFunctionIndex, _>>();
+                };
+                function.sort_by_offset();
+                Ok(function)
+            },
+        )?
+        .into_iter()
+        .collect::<PrimaryMap<LocalFunctionIndex, _>>();
 
         #[cfg(feature = "unwind")]
         let (custom_sections, dwarf) = {
             let mut custom_sections = PrimaryMap::new();
-            let dwarf = if let Some((dwarf_frametable, _cie_id)) = dwarf_frametable {
+            let dwarf = if let Some((fdes, cie)) = dwarf_frametable {
+                let mut fdes = fdes.into_inner().unwrap();
+                fdes.sort_by_key(|(index, _)| *index);
+                let mut dwarf_frametable = FrameTable::default();
+                let cie_id = dwarf_frametable.add_cie(cie);
+                for (_, fde) in fdes {
+                    dwarf_frametable.add_fde(cie_id, fde);
+                }
                 let mut eh_frame = EhFrame(WriterRelocate::new(target.triple().endianness().ok()));
-                dwarf_frametable
-                    .lock()
-                    .unwrap()
-                    .write_eh_frame(&mut eh_frame)
-                    .unwrap();
+                dwarf_frametable.write_eh_frame(&mut eh_frame).unwrap();
 
                 let eh_frame_section = eh_frame.0.into_section();
                 custom_sections.push(eh_frame_section);
@@ -212,31 +255,28 @@ impl Compiler for CraneliftCompiler {
         let (custom_sections, dwarf) = (PrimaryMap::new(), None);
 
         // function call trampolines (only for local functions, by signature)
-        let function_call_trampolines = module
-            .signatures
-            .values()
-            .collect::<Vec<_>>()
-            .par_iter()
-            .map_init(FunctionBuilderContext::new, |mut cx, sig| {
-                make_trampoline_function_call(&*isa, &mut cx, sig)
-            })
-            .collect::<Result<Vec<FunctionBody>, CompileError>>()?
-            .into_iter()
-            .collect::<PrimaryMap<SignatureIndex, FunctionBody>>();
+        let function_call_trampolines = map_init(
+            parallel,
+            &module.signatures.values().collect::<Vec<_>>(),
+            FunctionBuilderContext::new,
+            |mut cx, sig| make_trampoline_function_call(&*isa, &mut cx, sig),
+        )?
+        .into_iter()
+        .collect::<PrimaryMap<SignatureIndex, FunctionBody>>();
 
         use wasmer_vm::VMOffsets;
         let offsets = VMOffsets::new_for_trampolines(frontend_config.pointer_bytes());
         // dynamic function trampolines (only for imported functions)
-        let dynamic_function_trampolines = module
-            .imported_function_types()
-            .collect::<Vec<_>>()
-            .par_iter()
-            .map_init(FunctionBuilderContext::new, |mut cx, func_type| {
-                make_trampoline_dynamic_function(&*isa, &offsets, &mut cx, &func_type)
-            })
-            .collect::<Result<Vec<_>, CompileError>>()?
-            .into_iter()
-            .collect::<PrimaryMap<FunctionIndex, FunctionBody>>();
+        let dynamic_function_trampolines = map_init(
+            parallel,
+            &module.imported_function_types().collect::<Vec<_>>(),
+            FunctionBuilderContext::new,
+            |mut cx, func_type| {
+                make_trampoline_dynamic_function(&*isa, &offsets, &mut cx, func_type)
+            },
+        )?
+        .into_iter()
+        .collect::<PrimaryMap<FunctionIndex, FunctionBody>>();
 
         Ok(Compilation::new(
             functions,
@@ -247,3 +287,24 @@ impl Compiler for CraneliftCompiler {
         ))
     }
 }
+
+/// Maps `items` with `map_op` as `ParallelIterator::map_init`, on the
+/// threads of the current rayon pool if `parallel`, and on the calling
+/// thread otherwise.
+fn map_init<T, S, R>(
+    parallel: bool,
+    items: &[T],
+    init: impl Fn() -> S + Sync + Send,
+    map_op: impl Fn(&mut S, &T) -> Result<R, CompileError> + Sync + Send,
+) -> Result<Vec<R>, CompileError>
+where
+    T: Sync,
+    R: Send,
+{
+    if parallel {
+        items.par_iter().map_init(init, map_op).collect()
+    } else {
+        let mut state = init();
+        items.iter().map(|item| map_op(&mut state, item)).collect()
+    }
+}
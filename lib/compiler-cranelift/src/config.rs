@@ -3,7 +3,8 @@ use cranelift_codegen::isa::{lookup, TargetIsa};
 use cranelift_codegen::settings::{self, Configurable};
 use std::sync::Arc;
 use wasmer_compiler::{
-    Architecture, Compiler, CompilerConfig, CpuFeature, FunctionMiddlewareGenerator, Target,
+    Architecture, CompileError, Compiler, CompilerConfig, CpuFeature, DebugInfoPolicy,
+    FunctionMiddlewareGenerator, Target,
 };
 
 // Runtime Environment
@@ -33,6 +34,12 @@ pub struct Cranelift {
     enable_verifier: bool,
     enable_simd: bool,
     enable_pic: bool,
+    pub(crate) enable_explicit_stack_checks: bool,
+    pub(crate) enable_dead_function_pruning: bool,
+    pub(crate) debug_info_policy: DebugInfoPolicy,
+    pub(crate) disable_bounds_check_elimination: bool,
+    pub(crate) enable_parallel_compilation: bool,
+    allow_missing_sse2: bool,
     opt_level: OptLevel,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn FunctionMiddlewareGenerator>>,
@@ -47,6 +54,12 @@ impl Cranelift {
             enable_verifier: false,
             opt_level: OptLevel::Speed,
             enable_pic: false,
+            enable_explicit_stack_checks: false,
+            enable_dead_function_pruning: false,
+            debug_info_policy: DebugInfoPolicy::KeepAll,
+            disable_bounds_check_elimination: false,
+            enable_parallel_compilation: true,
+            allow_missing_sse2: false,
             enable_simd: true,
             middlewares: vec![],
         }
@@ -55,77 +68,141 @@ impl Cranelift {
     /// Enable NaN canonicalization.
     ///
     /// NaN canonicalization is useful when trying to run WebAssembly
-    /// deterministically across different architectures.
+    /// deterministically across different architectures: the NaNs produced
+    /// by the floating-point arithmetic are replaced by the canonical NaN,
+    /// with the sign bit clear and only the most significant bit of the
+    /// payload set.
     pub fn canonicalize_nans(&mut self, enable: bool) -> &mut Self {
         self.enable_nan_canonicalization = enable;
         self
     }
 
+    /// Verify the IR of every function before generating its code, which
+    /// is disabled by default.
+    ///
+    /// This is the same as [`CompilerConfig::enable_verifier`], but can
+    /// also disable the verifier, and be chained.
+    pub fn verifier(&mut self, enable: bool) -> &mut Self {
+        self.enable_verifier = enable;
+        self
+    }
+
     /// Enable SIMD support.
     pub fn enable_simd(&mut self, enable: bool) -> &mut Self {
         self.enable_simd = enable;
         self
     }
 
+    /// Compile the functions of a module in parallel, which is the default.
+    ///
+    /// The compilation is the same either way: disabling this only
+    /// compiles the functions one after the other on the calling thread.
+    pub fn parallel_compilation(&mut self, enable: bool) -> &mut Self {
+        self.enable_parallel_compilation = enable;
+        self
+    }
+
+    /// Make [`Cranelift::try_isa`] fail with `CompileError::UnsupportedTarget`
+    /// for an x86_64 target without SSE2, which Cranelift requires, instead
+    /// of panicking, which is the default.
+    ///
+    /// This lets experiments on unusual targets recover from the error.
+    pub fn allow_missing_sse2(&mut self, allow: bool) -> &mut Self {
+        self.allow_missing_sse2 = allow;
+        self
+    }
+
     /// The optimization levels when optimizing the IR.
     pub fn opt_level(&mut self, opt_level: OptLevel) -> &mut Self {
         self.opt_level = opt_level;
         self
     }
 
-    /// Generates the ISA for the provided target
+    /// Generates the ISA for the provided target.
+    ///
+    /// # Panics
+    ///
+    /// Panics if Cranelift doesn't support the target, see
+    /// [`Cranelift::try_isa`].
     pub fn isa(&self, target: &Target) -> Box<dyn TargetIsa> {
-        let mut builder =
-            lookup(target.triple().clone()).expect("construct Cranelift ISA for triple");
+        self.try_isa(target)
+            .expect("construct Cranelift ISA for triple")
+    }
+
+    /// Generates the ISA for the provided target, failing with
+    /// `CompileError::UnsupportedTarget` if Cranelift doesn't support it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the target is x86_64 without SSE2, unless
+    /// [`Cranelift::allow_missing_sse2`] is set.
+    pub fn try_isa(&self, target: &Target) -> Result<Box<dyn TargetIsa>, CompileError> {
+        let mut builder = lookup(target.triple().clone())
+            .map_err(|e| CompileError::UnsupportedTarget(format!("{}: {}", target.triple(), e)))?;
         // Cpu Features
         let cpu_features = target.cpu_features();
-        if target.triple().architecture == Architecture::X86_64
-            && !cpu_features.contains(CpuFeature::SSE2)
-        {
-            panic!("x86 support requires SSE2");
-        }
-        if cpu_features.contains(CpuFeature::SSE3) {
-            builder.enable("has_sse3").expect("should be valid flag");
-        }
-        if cpu_features.contains(CpuFeature::SSSE3) {
-            builder.enable("has_ssse3").expect("should be valid flag");
-        }
-        if cpu_features.contains(CpuFeature::SSE41) {
-            builder.enable("has_sse41").expect("should be valid flag");
-        }
-        if cpu_features.contains(CpuFeature::SSE42) {
-            builder.enable("has_sse42").expect("should be valid flag");
-        }
-        if cpu_features.contains(CpuFeature::POPCNT) {
-            builder.enable("has_popcnt").expect("should be valid flag");
-        }
-        if cpu_features.contains(CpuFeature::AVX) {
-            builder.enable("has_avx").expect("should be valid flag");
-        }
-        if cpu_features.contains(CpuFeature::BMI1) {
-            builder.enable("has_bmi1").expect("should be valid flag");
-        }
-        if cpu_features.contains(CpuFeature::BMI2) {
-            builder.enable("has_bmi2").expect("should be valid flag");
-        }
-        if cpu_features.contains(CpuFeature::AVX2) {
-            builder.enable("has_avx2").expect("should be valid flag");
-        }
-        if cpu_features.contains(CpuFeature::AVX512DQ) {
-            builder
-                .enable("has_avx512dq")
-                .expect("should be valid flag");
-        }
-        if cpu_features.contains(CpuFeature::AVX512VL) {
-            builder
-                .enable("has_avx512vl")
-                .expect("should be valid flag");
-        }
-        if cpu_features.contains(CpuFeature::LZCNT) {
-            builder.enable("has_lzcnt").expect("should be valid flag");
+        match target.triple().architecture {
+            Architecture::X86_64 | Architecture::I386 | Architecture::I586 | Architecture::I686 => {
+                // The 32-bit targets without SSE2 are left to Cranelift.
+                if target.triple().architecture == Architecture::X86_64
+                    && !cpu_features.contains(CpuFeature::SSE2)
+                {
+                    if self.allow_missing_sse2 {
+                        return Err(CompileError::UnsupportedTarget(format!(
+                            "{}: x86 support requires SSE2",
+                            target.triple()
+                        )));
+                    }
+                    panic!("x86 support requires SSE2");
+                }
+                if cpu_features.contains(CpuFeature::SSE3) {
+                    builder.enable("has_sse3").expect("should be valid flag");
+                }
+                if cpu_features.contains(CpuFeature::SSSE3) {
+                    builder.enable("has_ssse3").expect("should be valid flag");
+                }
+                if cpu_features.contains(CpuFeature::SSE41) {
+                    builder.enable("has_sse41").expect("should be valid flag");
+                }
+                if cpu_features.contains(CpuFeature::SSE42) {
+                    builder.enable("has_sse42").expect("should be valid flag");
+                }
+                if cpu_features.contains(CpuFeature::POPCNT) {
+                    builder.enable("has_popcnt").expect("should be valid flag");
+                }
+                if cpu_features.contains(CpuFeature::AVX) {
+                    builder.enable("has_avx").expect("should be valid flag");
+                }
+                if cpu_features.contains(CpuFeature::BMI1) {
+                    builder.enable("has_bmi1").expect("should be valid flag");
+                }
+                if cpu_features.contains(CpuFeature::BMI2) {
+                    builder.enable("has_bmi2").expect("should be valid flag");
+                }
+                if cpu_features.contains(CpuFeature::AVX2) {
+                    builder.enable("has_avx2").expect("should be valid flag");
+                }
+                if cpu_features.contains(CpuFeature::AVX512DQ) {
+                    builder
+                        .enable("has_avx512dq")
+                        .expect("should be valid flag");
+                }
+                if cpu_features.contains(CpuFeature::AVX512VL) {
+                    builder
+                        .enable("has_avx512vl")
+                        .expect("should be valid flag");
+                }
+                if cpu_features.contains(CpuFeature::LZCNT) {
+                    builder.enable("has_lzcnt").expect("should be valid flag");
+                }
+            }
+            // NEON is part of the base AArch64 architecture, which Cranelift
+            // always generates code for, so it has no flag to enable.
+            Architecture::Aarch64(_) => {}
+            _ => {}
         }
 
-        builder.finish(self.flags())
+        Ok(builder.finish(self.flags()))
     }
 
     /// Generates the flags for the compiler
@@ -133,7 +210,10 @@ impl Cranelift {
         let mut flags = settings::builder();
 
         // There are two possible traps for division, and this way
-        // we get the proper one if code traps.
+        // we get the proper one if code traps: dividing by zero traps with
+        // `IntegerDivisionByZero`, while `INT_MIN / -1` traps with
+        // `IntegerOverflow` (and `INT_MIN % -1` doesn't trap, it's `0`)
+        // instead of all of them being reported by the hardware `idiv` trap.
         flags
             .enable("avoid_div_traps")
             .expect("should be valid flag");
@@ -193,6 +273,22 @@ impl CompilerConfig for Cranelift {
         self.enable_verifier = true;
     }
 
+    fn enable_explicit_stack_checks(&mut self) {
+        self.enable_explicit_stack_checks = true;
+    }
+
+    fn enable_dead_function_pruning(&mut self) {
+        self.enable_dead_function_pruning = true;
+    }
+
+    fn set_debug_info_policy(&mut self, policy: DebugInfoPolicy) {
+        self.debug_info_policy = policy;
+    }
+
+    fn disable_bounds_check_elimination(&mut self) {
+        self.disable_bounds_check_elimination = true;
+    }
+
     /// Transform it into the compiler
     fn compiler(&self) -> Box<dyn Compiler + Send> {
         Box::new(CraneliftCompiler::new(&self))
@@ -209,3 +305,42 @@ impl Default for Cranelift {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use wasmer_compiler::Triple;
+
+    #[test]
+    fn aarch64_isa() {
+        let triple = Triple::from_str("aarch64-apple-darwin").unwrap();
+        let target = Target::new(triple, CpuFeature::NEON.into());
+        let isa = Cranelift::new().isa(&target);
+        assert_eq!(isa.triple().architecture, target.triple().architecture);
+    }
+
+    #[test]
+    #[should_panic(expected = "x86 support requires SSE2")]
+    fn x86_isa_requires_sse2() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let target = Target::new(triple, CpuFeature::SSE3.into());
+        let _ = Cranelift::new().isa(&target);
+    }
+
+    #[test]
+    fn x86_isa_allowing_missing_sse2() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let target = Target::new(triple, CpuFeature::SSE3.into());
+        let result = Cranelift::new().allow_missing_sse2(true).try_isa(&target);
+        assert!(matches!(result, Err(CompileError::UnsupportedTarget(_))));
+    }
+
+    #[test]
+    fn x86_32_isa_without_sse2() {
+        let triple = Triple::from_str("i686-unknown-linux-gnu").unwrap();
+        let target = Target::new(triple, CpuFeature::SSE3.into());
+        let isa = Cranelift::new().isa(&target);
+        assert_eq!(isa.triple().architecture, target.triple().architecture);
+    }
+}
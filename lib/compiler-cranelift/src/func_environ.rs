@@ -89,6 +89,9 @@ pub struct FuncEnvironment<'module_environment> {
 
     /// The table styles
     table_styles: &'module_environment PrimaryMap<TableIndex, TableStyle>,
+
+    /// Whether to check the stack limit at the entry of the function.
+    explicit_stack_checks: bool,
 }
 
 impl<'module_environment> FuncEnvironment<'module_environment> {
@@ -116,9 +119,16 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             offsets: VMOffsets::new(target_config.pointer_bytes(), module),
             memory_styles,
             table_styles,
+            explicit_stack_checks: false,
         }
     }
 
+    /// Check the stack limit at the entry of the function, trapping with
+    /// `StackOverflow` when the stack pointer is at or below it.
+    pub fn enable_explicit_stack_checks(&mut self) {
+        self.explicit_stack_checks = true;
+    }
+
     fn pointer_type(&self) -> ir::Type {
         self.target_config.pointer_type()
     }
@@ -417,9 +427,22 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
     fn get_memory_init_func(
         &mut self,
         func: &mut Function,
-    ) -> (ir::SigRef, VMBuiltinFunctionIndex) {
+        memory_index: MemoryIndex,
+    ) -> (ir::SigRef, usize, VMBuiltinFunctionIndex) {
         let sig = self.get_memory_init_sig(func);
-        (sig, VMBuiltinFunctionIndex::get_memory_init_index())
+        if let Some(local_memory_index) = self.module.local_memory_index(memory_index) {
+            (
+                sig,
+                local_memory_index.index(),
+                VMBuiltinFunctionIndex::get_memory_init_index(),
+            )
+        } else {
+            (
+                sig,
+                memory_index.index(),
+                VMBuiltinFunctionIndex::get_imported_memory_init_index(),
+            )
+        }
     }
 
     fn get_data_drop_sig(&mut self, func: &mut Function) -> ir::SigRef {
@@ -479,6 +502,35 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         index >= 1
     }
 
+    fn translate_function_entry(&mut self, mut pos: FuncCursor) -> WasmResult<()> {
+        if !self.explicit_stack_checks {
+            return Ok(());
+        }
+        // The limit belongs to the thread running the function, so it's
+        // asked to the runtime rather than read from the vmctx.
+        let func_sig = pos.func.import_signature(Signature {
+            params: vec![AbiParam::special(
+                self.pointer_type(),
+                ArgumentPurpose::VMContext,
+            )],
+            returns: vec![AbiParam::new(self.pointer_type())],
+            call_conv: self.target_config.default_call_conv,
+        });
+        let (vmctx, func_addr) = self.translate_load_builtin_function_address(
+            &mut pos,
+            VMBuiltinFunctionIndex::get_stack_limit_index(),
+        );
+        let call_inst = pos.ins().call_indirect(func_sig, func_addr, &[vmctx]);
+        let limit = *pos.func.dfg.inst_results(call_inst).first().unwrap();
+        let flags = pos.ins().ifcmp_sp(limit);
+        pos.ins().trapif(
+            IntCC::UnsignedGreaterThanOrEqual,
+            flags,
+            ir::TrapCode::StackOverflow,
+        );
+        Ok(())
+    }
+
     fn make_table(&mut self, func: &mut ir::Function, index: TableIndex) -> WasmResult<ir::Table> {
         let pointer_type = self.pointer_type();
 
@@ -954,9 +1006,10 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         src: ir::Value,
         len: ir::Value,
     ) -> WasmResult<()> {
-        let (func_sig, func_idx) = self.get_memory_init_func(&mut pos.func);
+        let (func_sig, memory_index, func_idx) =
+            self.get_memory_init_func(&mut pos.func, memory_index);
 
-        let memory_index_arg = pos.ins().iconst(I32, memory_index.index() as i64);
+        let memory_index_arg = pos.ins().iconst(I32, memory_index as i64);
         let seg_index_arg = pos.ins().iconst(I32, seg_index as i64);
 
         let (vmctx, func_addr) = self.translate_load_builtin_function_address(&mut pos, func_idx);
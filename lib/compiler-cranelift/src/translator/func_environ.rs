@@ -364,6 +364,15 @@ pub trait FuncEnvironment: TargetEnvironment {
         val: ir::Value,
     ) -> WasmResult<()>;
 
+    /// Emit code at the beginning of every wasm function, once its locals
+    /// are declared.
+    ///
+    /// This can be used to insert explicit stack checks.
+    fn translate_function_entry(&mut self, _pos: FuncCursor) -> WasmResult<()> {
+        // By default, don't emit anything.
+        Ok(())
+    }
+
     /// Emit code at the beginning of every wasm loop.
     ///
     /// This can be used to insert explicit interrupt or safepoint checking at
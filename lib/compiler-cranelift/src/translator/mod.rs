@@ -11,7 +11,7 @@ pub use self::func_environ::{FuncEnvironment, GlobalVariable, ReturnMode, Target
 pub use self::func_state::FuncTranslationState;
 pub use self::func_translator::FuncTranslator;
 pub use self::translation_utils::{
-    get_vmctx_value_label, irlibcall_to_libcall, irreloc_to_relocationkind,
+    get_vmctx_value_label, irlibcall_to_libcall, irreloc_to_relocationkind, jump_table_entries,
     signature_to_cranelift_ir, transform_jump_table, type_to_irtype,
 };
 pub(crate) use self::unwind::{compiled_function_unwind_info, CraneliftUnwindInfo};
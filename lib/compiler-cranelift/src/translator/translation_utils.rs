@@ -9,7 +9,7 @@ use cranelift_codegen::isa::TargetFrontendConfig;
 use cranelift_frontend::FunctionBuilder;
 use wasmer_compiler::wasm_unsupported;
 use wasmer_compiler::wasmparser;
-use wasmer_compiler::{JumpTable, RelocationKind};
+use wasmer_compiler::{JumpTable, JumpTableData, RelocationKind};
 use wasmer_compiler::{WasmError, WasmResult};
 use wasmer_types::entity::{EntityRef, SecondaryMap};
 use wasmer_types::{FunctionType, Type};
@@ -88,6 +88,7 @@ pub fn irreloc_to_relocationkind(reloc: Reloc) -> RelocationKind {
         Reloc::X86PCRelRodata4 => RelocationKind::X86PCRelRodata4,
         Reloc::X86CallPCRel4 => RelocationKind::X86CallPCRel4,
         Reloc::X86CallPLTRel4 => RelocationKind::X86CallPLTRel4,
+        Reloc::Arm64Call => RelocationKind::Arm64Call,
         _ => panic!("The relocation {} is not yet supported.", reloc),
     }
 }
@@ -146,6 +147,19 @@ pub fn get_vmctx_value_label() -> ir::ValueLabel {
     ir::ValueLabel::from_u32(VMCTX_LABEL)
 }
 
+/// Gets the code offsets of the entries of the jump tables of a compiled
+/// Cranelift function
+pub fn jump_table_entries(func: &ir::Function) -> SecondaryMap<JumpTable, JumpTableData> {
+    let mut func_jt_entries = SecondaryMap::with_capacity(func.jump_tables.len());
+
+    for (key, data) in func.jump_tables.iter() {
+        let new_key = JumpTable::new(key.index());
+        func_jt_entries[new_key] =
+            JumpTableData::new(data.iter().map(|block| func.offsets[*block]).collect());
+    }
+    func_jt_entries
+}
+
 /// Transforms Cranelift JumpTable's into runtime JumpTables
 pub fn transform_jump_table(
     jt_offsets: SecondaryMap<ir::JumpTable, u32>,
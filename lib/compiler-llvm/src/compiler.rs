@@ -9,9 +9,9 @@ use inkwell::targets::FileType;
 use inkwell::DLLStorageClass;
 use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 use wasmer_compiler::{
-    Compilation, CompileError, CompileModuleInfo, Compiler, CustomSection, CustomSectionProtection,
-    Dwarf, FunctionBodyData, ModuleTranslationState, RelocationTarget, SectionBody, SectionIndex,
-    Symbol, SymbolRegistry, Target,
+    Compilation, CompilationProgress, CompileError, CompileModuleInfo, Compiler, CustomSection,
+    CustomSectionProtection, DebugInfoPolicy, Dwarf, FunctionBodyData, ModuleTranslationState,
+    RelocationTarget, SectionBody, SectionIndex, Symbol, SymbolRegistry, Target,
 };
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{FunctionIndex, LocalFunctionIndex, SignatureIndex};
@@ -191,9 +191,7 @@ impl LLVMCompiler {
         metadata_gv.set_linkage(Linkage::DLLExport);
         metadata_gv.set_dll_storage_class(DLLStorageClass::Export);
 
-        if self.config().enable_verifier {
-            merged_module.verify().unwrap();
-        }
+        self.config().verify(&merged_module)?;
 
         let memory_buffer = target_machine
             .write_to_memory_buffer(&merged_module, FileType::Object)
@@ -207,6 +205,14 @@ impl LLVMCompiler {
 }
 
 impl Compiler for LLVMCompiler {
+    fn prune_dead_functions(&self) -> bool {
+        self.config.enable_dead_function_pruning
+    }
+
+    fn debug_info_policy(&self) -> DebugInfoPolicy {
+        self.config.debug_info_policy
+    }
+
     fn experimental_native_compile_module<'data, 'module>(
         &self,
         target: &Target,
@@ -236,6 +242,7 @@ impl Compiler for LLVMCompiler {
         compile_info: &'module CompileModuleInfo,
         module_translation: &ModuleTranslationState,
         function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'data>>,
+        progress: &CompilationProgress,
     ) -> Result<Compilation, CompileError> {
         //let data = Arc::new(Mutex::new(0));
         let memory_styles = &compile_info.memory_styles;
@@ -257,9 +264,10 @@ impl Compiler for LLVMCompiler {
                     FuncTranslator::new(target_machine)
                 },
                 |func_translator, (i, input)| {
+                    progress.check_cancelled()?;
                     // TODO: remove (to serialize)
                     //let _data = data.lock().unwrap();
-                    func_translator.translate(
+                    let function = func_translator.translate(
                         &module,
                         module_translation,
                         i,
@@ -268,7 +276,9 @@ impl Compiler for LLVMCompiler {
                         memory_styles,
                         &table_styles,
                         &mut ShortNames {},
-                    )
+                    )?;
+                    progress.functions_compiled(1);
+                    Ok(function)
                 },
             )
             .collect::<Result<Vec<_>, CompileError>>()?
@@ -8,7 +8,10 @@ use itertools::Itertools;
 use std::fmt::Debug;
 use std::sync::Arc;
 use target_lexicon::Architecture;
-use wasmer_compiler::{Compiler, CompilerConfig, FunctionMiddlewareGenerator, Target, Triple};
+use wasmer_compiler::{
+    CompileError, Compiler, CompilerConfig, DebugInfoPolicy, FunctionMiddlewareGenerator, Target,
+    Triple,
+};
 use wasmer_types::{FunctionType, LocalFunctionIndex};
 
 /// The InkWell ModuleInfo type
@@ -41,8 +44,14 @@ pub trait LLVMCallbacks: Debug + Send + Sync {
 pub struct LLVM {
     pub(crate) enable_nan_canonicalization: bool,
     pub(crate) enable_verifier: bool,
+    pub(crate) conservative_globals: bool,
     pub(crate) opt_level: OptimizationLevel,
     is_pic: bool,
+    reloc_mode: Option<RelocMode>,
+    code_model: Option<CodeModel>,
+    pub(crate) enable_dead_function_pruning: bool,
+    pub(crate) debug_info_policy: DebugInfoPolicy,
+    pub(crate) disable_bounds_check_elimination: bool,
     pub(crate) callbacks: Option<Arc<dyn LLVMCallbacks>>,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn FunctionMiddlewareGenerator>>,
@@ -55,8 +64,14 @@ impl LLVM {
         Self {
             enable_nan_canonicalization: false,
             enable_verifier: false,
+            conservative_globals: false,
             opt_level: OptimizationLevel::Aggressive,
             is_pic: false,
+            reloc_mode: None,
+            code_model: None,
+            enable_dead_function_pruning: false,
+            debug_info_policy: DebugInfoPolicy::KeepAll,
+            disable_bounds_check_elimination: false,
             callbacks: None,
             middlewares: vec![],
         }
@@ -65,12 +80,35 @@ impl LLVM {
     /// Enable NaN canonicalization.
     ///
     /// NaN canonicalization is useful when trying to run WebAssembly
-    /// deterministically across different architectures.
+    /// deterministically across different architectures: every NaN
+    /// produced by a floating point operation is replaced with the
+    /// canonical quiet NaN before it can be observed.
     pub fn canonicalize_nans(&mut self, enable: bool) -> &mut Self {
         self.enable_nan_canonicalization = enable;
         self
     }
 
+    /// Verify the generated LLVM IR.
+    ///
+    /// Each function and trampoline is verified before it is optimized,
+    /// and a malformed one fails the compilation with a
+    /// `CompileError::Codegen` holding the verifier message.
+    pub fn verifier(&mut self, enable: bool) -> &mut Self {
+        self.enable_verifier = enable;
+        self
+    }
+
+    /// Reload imported immutable globals on every `global.get`.
+    ///
+    /// By default the value of an immutable global is loaded once from the
+    /// instance's `VMContext` at function entry and reused. When enabled,
+    /// imported immutable globals are treated like mutable ones and a fresh
+    /// load is emitted for every read instead.
+    pub fn conservative_globals(&mut self, enable: bool) -> &mut Self {
+        self.conservative_globals = enable;
+        self
+    }
+
     /// The optimization levels when optimizing the IR.
     pub fn opt_level(&mut self, opt_level: OptimizationLevel) -> &mut Self {
         self.opt_level = opt_level;
@@ -84,7 +122,28 @@ impl LLVM {
         self
     }
 
-    fn reloc_mode(&self) -> RelocMode {
+    /// The relocation model of the generated code.
+    ///
+    /// By default the code is position independent when PIC is enabled,
+    /// and static otherwise.
+    pub fn reloc_mode(&mut self, reloc_mode: RelocMode) -> &mut Self {
+        self.reloc_mode = Some(reloc_mode);
+        self
+    }
+
+    /// The code model of the generated code.
+    ///
+    /// By default the small code model is used when PIC is enabled, and
+    /// the large one otherwise.
+    pub fn code_model(&mut self, code_model: CodeModel) -> &mut Self {
+        self.code_model = Some(code_model);
+        self
+    }
+
+    fn target_reloc_mode(&self) -> RelocMode {
+        if let Some(reloc_mode) = self.reloc_mode {
+            return reloc_mode;
+        }
         if self.is_pic {
             RelocMode::PIC
         } else {
@@ -92,7 +151,10 @@ impl LLVM {
         }
     }
 
-    fn code_model(&self) -> CodeModel {
+    fn target_code_model(&self) -> CodeModel {
+        if let Some(code_model) = self.code_model {
+            return code_model;
+        }
         // We normally use the large code model, but when targeting shared
         // objects, we are required to use PIC. If we use PIC anyways, we lose
         // any benefit from large code model and there's some cost on all
@@ -186,11 +248,21 @@ impl LLVM {
                 "generic",
                 &llvm_cpu_features,
                 self.opt_level,
-                self.reloc_mode(),
-                self.code_model(),
+                self.target_reloc_mode(),
+                self.target_code_model(),
             )
             .unwrap()
     }
+
+    /// Verifies `module`, if the verifier is enabled.
+    pub(crate) fn verify(&self, module: &InkwellModule) -> Result<(), CompileError> {
+        if self.enable_verifier {
+            module
+                .verify()
+                .map_err(|message| CompileError::Codegen(message.to_string()))?;
+        }
+        Ok(())
+    }
 }
 
 impl CompilerConfig for LLVM {
@@ -206,6 +278,18 @@ impl CompilerConfig for LLVM {
         self.enable_verifier = true;
     }
 
+    fn enable_dead_function_pruning(&mut self) {
+        self.enable_dead_function_pruning = true;
+    }
+
+    fn set_debug_info_policy(&mut self, policy: DebugInfoPolicy) {
+        self.debug_info_policy = policy;
+    }
+
+    fn disable_bounds_check_elimination(&mut self) {
+        self.disable_bounds_check_elimination = true;
+    }
+
     /// Transform it into the compiler.
     fn compiler(&self) -> Box<dyn Compiler + Send> {
         Box::new(LLVMCompiler::new(&self))
@@ -222,3 +306,27 @@ impl Default for LLVM {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reloc_mode_and_code_model() {
+        let mut config = LLVM::new();
+        assert_eq!(config.target_reloc_mode(), RelocMode::Static);
+        assert_eq!(config.target_code_model(), CodeModel::Large);
+        config.enable_pic();
+        assert_eq!(config.target_reloc_mode(), RelocMode::PIC);
+        assert_eq!(config.target_code_model(), CodeModel::Small);
+
+        let mut config = LLVM::new();
+        config
+            .reloc_mode(RelocMode::PIC)
+            .code_model(CodeModel::Small);
+        assert_eq!(config.target_reloc_mode(), RelocMode::PIC);
+        assert_eq!(config.target_code_model(), CodeModel::Small);
+        // The target machine is created with them.
+        config.target_machine(&Target::default());
+    }
+}
@@ -22,3 +22,4 @@ mod translator;
 
 pub use crate::compiler::LLVMCompiler;
 pub use crate::config::{CompiledKind, InkwellMemoryBuffer, InkwellModule, LLVMCallbacks, LLVM};
+pub use inkwell::targets::{CodeModel, RelocMode};
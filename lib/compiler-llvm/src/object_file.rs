@@ -172,6 +172,9 @@ where
                 // make the whole match per-arch.
                 goblin::elf::reloc::R_X86_64_64 => RelocationKind::Abs8,
                 goblin::elf::reloc::R_X86_64_PC64 => RelocationKind::X86PCRel8,
+                goblin::elf::reloc::R_AARCH64_CALL26 | goblin::elf::reloc::R_AARCH64_JUMP26 => {
+                    RelocationKind::Arm64Call
+                }
                 goblin::elf::reloc::R_X86_64_GOT64 => {
                     return Err(CompileError::Codegen(
                         "unimplemented PIC relocation R_X86_64_GOT64".into(),
@@ -289,18 +292,22 @@ where
         body_len: function_body.body.len(),
     };
 
-    Ok(CompiledFunction {
-        compiled_function: wasmer_compiler::CompiledFunction {
-            body: function_body,
-            jt_offsets: SecondaryMap::new(),
-            relocations: relocations
-                .remove_entry(&root_section_index)
-                .map_or(vec![], |(_, v)| v),
-            frame_info: CompiledFunctionFrameInfo {
-                address_map,
-                traps: vec![],
-            },
+    let mut compiled_function = wasmer_compiler::CompiledFunction {
+        body: function_body,
+        jt_offsets: SecondaryMap::new(),
+        jt_entries: SecondaryMap::new(),
+        relocations: relocations
+            .remove_entry(&root_section_index)
+            .map_or(vec![], |(_, v)| v),
+        frame_info: CompiledFunctionFrameInfo {
+            address_map,
+            traps: vec![],
         },
+    };
+    compiled_function.sort_by_offset();
+
+    Ok(CompiledFunction {
+        compiled_function,
         custom_sections,
         eh_frame_section_indices,
     })
@@ -78,11 +78,9 @@ impl FuncTrampoline {
             callbacks.preopt_ir(&function, &module);
         }
 
-        let pass_manager = PassManager::create(());
+        config.verify(&module)?;
 
-        if config.enable_verifier {
-            pass_manager.add_verifier_pass();
-        }
+        let pass_manager = PassManager::create(());
 
         pass_manager.add_early_cse_pass();
 
@@ -200,11 +198,9 @@ impl FuncTrampoline {
             callbacks.preopt_ir(&function, &module);
         }
 
-        let pass_manager = PassManager::create(());
+        config.verify(&module)?;
 
-        if config.enable_verifier {
-            pass_manager.add_verifier_pass();
-        }
+        let pass_manager = PassManager::create(());
 
         pass_manager.add_early_cse_pass();
 
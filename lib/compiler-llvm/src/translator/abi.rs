@@ -575,3 +575,43 @@ pub fn pack_values_for_register_return<'ctx>(
         _ => unreachable!("called to perform register return on struct return or void function"),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use wasmer_compiler::{Target, Triple};
+
+    #[test]
+    fn is_sret_agrees_with_result_area_required() {
+        let types = [Type::I32, Type::I64, Type::F32, Type::F64, Type::V128];
+        for triple in &["x86_64-unknown-linux-gnu", "aarch64-unknown-linux-gnu"] {
+            let target = Target::new(Triple::from_str(triple).unwrap(), Default::default());
+            // Every list of up to five results.
+            let mut results_lists = vec![vec![]];
+            for len in 1..=5 {
+                for previous in results_lists
+                    .clone()
+                    .into_iter()
+                    .filter(|r| r.len() == len - 1)
+                {
+                    for &ty in &types {
+                        let mut results = previous.clone();
+                        results.push(ty);
+                        results_lists.push(results);
+                    }
+                }
+            }
+            for results in results_lists {
+                let func_sig = FuncSig::new(vec![], results);
+                assert_eq!(
+                    is_sret(&func_sig).unwrap(),
+                    target.result_area_required(&func_sig),
+                    "{} on {}",
+                    func_sig,
+                    triple
+                );
+            }
+        }
+    }
+}
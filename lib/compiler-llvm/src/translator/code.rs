@@ -31,8 +31,7 @@ use wasmer_compiler::{
 };
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::{
-    FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex, SignatureIndex,
-    TableIndex, Type,
+    FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex, TableIndex, Type,
 };
 use wasmer_vm::{MemoryStyle, ModuleInfo, TableStyle};
 
@@ -78,6 +77,21 @@ impl FuncTranslator {
         _table_styles: &PrimaryMap<TableIndex, TableStyle>,
         symbol_registry: &dyn SymbolRegistry,
     ) -> Result<Module, CompileError> {
+        // Without bounds check elimination, every memory is accessed as a
+        // dynamic memory without guard pages.
+        let checked_memory_styles;
+        let memory_styles = if config.disable_bounds_check_elimination {
+            checked_memory_styles = memory_styles
+                .values()
+                .map(|_| MemoryStyle::Dynamic {
+                    offset_guard_size: 0,
+                })
+                .collect::<PrimaryMap<MemoryIndex, _>>();
+            &checked_memory_styles
+        } else {
+            memory_styles
+        };
+
         // The function type, used for the callbacks.
         let function = CompiledKind::Local(*local_func_index);
         let func_index = wasm_module.func_index(*local_func_index);
@@ -139,7 +153,7 @@ impl FuncTranslator {
         builder.position_at_end(start_of_code);
 
         let mut reader = MiddlewareBinaryReader::new_with_offset(
-            function_body.data,
+            &function_body.data,
             function_body.module_offset,
         );
         reader.set_middleware_chain(
@@ -203,10 +217,17 @@ impl FuncTranslator {
             state,
             function: func,
             locals: params_locals,
-            ctx: CtxType::new(wasm_module, &func, &cache_builder),
+            ctx: CtxType::new(
+                wasm_module,
+                &func,
+                &cache_builder,
+                config.conservative_globals,
+            ),
             unreachable_depth: 0,
             memory_styles,
             _table_styles,
+            disable_bounds_check_elimination: config.disable_bounds_check_elimination,
+            enable_nan_canonicalization: config.enable_nan_canonicalization,
             module: &module,
             module_translation,
             wasm_module,
@@ -221,7 +242,7 @@ impl FuncTranslator {
 
         while fcg.state.has_control_frames() {
             let pos = reader.current_position() as u32;
-            let op = reader.read_operator().map_err(to_wasm_error)?;
+            let op = reader.read_operator()?;
             fcg.translate_operator(op, pos)?;
         }
 
@@ -231,11 +252,9 @@ impl FuncTranslator {
             callbacks.preopt_ir(&function, &module);
         }
 
-        let pass_manager = PassManager::create(());
+        config.verify(&module)?;
 
-        if config.enable_verifier {
-            pass_manager.add_verifier_pass();
-        }
+        let pass_manager = PassManager::create(());
 
         pass_manager.add_type_based_alias_analysis_pass();
         pass_manager.add_ipsccp_pass();
@@ -636,7 +655,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         let trap_code = self.builder.build_select(
             is_nan,
             self.intrinsics.trap_bad_conversion_to_integer,
-            self.intrinsics.trap_illegal_arithmetic,
+            self.intrinsics.trap_integer_overflow,
             "",
         );
         self.builder
@@ -645,6 +664,8 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         self.builder.position_at_end(continue_block);
     }
 
+    // Traps with `IntegerDivisionByZero` if `right` is zero, or with
+    // `IntegerOverflow` if the signed division `left / right` overflows.
     fn trap_if_zero_or_overflow(&self, left: IntValue, right: IntValue) {
         let int_type = left.get_type();
 
@@ -709,7 +730,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         let trap_code = self.builder.build_select(
             divisor_is_zero,
             self.intrinsics.trap_integer_division_by_zero,
-            self.intrinsics.trap_illegal_arithmetic,
+            self.intrinsics.trap_integer_overflow,
             "",
         );
         self.builder
@@ -718,6 +739,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         self.builder.position_at_end(shouldnt_trap_block);
     }
 
+    // Traps with `IntegerDivisionByZero` if `value` is zero.
     fn trap_if_zero(&self, value: IntValue) {
         let int_type = value.get_type();
         let should_trap = self.builder.build_int_compare(
@@ -766,12 +788,12 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         info: ExtraInfo,
         int_vec_ty: VectorType<'ctx>,
     ) -> (VectorValue<'ctx>, ExtraInfo) {
-        let (value, info) = if info.has_pending_f32_nan() {
+        let (value, info) = if self.enable_nan_canonicalization && info.has_pending_f32_nan() {
             let value = self
                 .builder
                 .build_bitcast(value, self.intrinsics.f32x4_ty, "");
             (self.canonicalize_nans(value), info.strip_pending())
-        } else if info.has_pending_f64_nan() {
+        } else if self.enable_nan_canonicalization && info.has_pending_f64_nan() {
             let value = self
                 .builder
                 .build_bitcast(value, self.intrinsics.f64x2_ty, "");
@@ -826,7 +848,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         value: BasicValueEnum<'ctx>,
         info: ExtraInfo,
     ) -> (VectorValue<'ctx>, ExtraInfo) {
-        let (value, info) = if info.has_pending_f64_nan() {
+        let (value, info) = if self.enable_nan_canonicalization && info.has_pending_f64_nan() {
             let value = self
                 .builder
                 .build_bitcast(value, self.intrinsics.f64x2_ty, "");
@@ -849,7 +871,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         value: BasicValueEnum<'ctx>,
         info: ExtraInfo,
     ) -> (VectorValue<'ctx>, ExtraInfo) {
-        let (value, info) = if info.has_pending_f32_nan() {
+        let (value, info) = if self.enable_nan_canonicalization && info.has_pending_f32_nan() {
             let value = self
                 .builder
                 .build_bitcast(value, self.intrinsics.f32x4_ty, "");
@@ -870,6 +892,10 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         value: BasicValueEnum<'ctx>,
         info: ExtraInfo,
     ) -> BasicValueEnum<'ctx> {
+        if !self.enable_nan_canonicalization {
+            return value;
+        }
+
         if info.has_pending_f32_nan() {
             if value.get_type().is_vector_type()
                 || value.get_type() == self.intrinsics.i128_ty.as_basic_type_enum()
@@ -1008,44 +1034,45 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                     // Bounds check it.
                     let minimum = self.wasm_module.memories[memory_index].minimum;
                     let value_size_v = intrinsics.i64_ty.const_int(value_size as u64, false);
-                    let ptr_in_bounds = if offset.is_const() {
-                        // When the offset is constant, if it's below the minimum
-                        // memory size, we've statically shown that it's safe.
-                        let load_offset_end = offset.const_add(value_size_v);
-                        let ptr_in_bounds = load_offset_end.const_int_compare(
-                            IntPredicate::ULE,
-                            intrinsics.i64_ty.const_int(minimum.bytes().0 as u64, false),
-                        );
-                        if ptr_in_bounds.get_zero_extended_constant() == Some(1) {
-                            Some(ptr_in_bounds)
+                    let ptr_in_bounds =
+                        if offset.is_const() && !self.disable_bounds_check_elimination {
+                            // When the offset is constant, if it's below the minimum
+                            // memory size, we've statically shown that it's safe.
+                            let load_offset_end = offset.const_add(value_size_v);
+                            let ptr_in_bounds = load_offset_end.const_int_compare(
+                                IntPredicate::ULE,
+                                intrinsics.i64_ty.const_int(minimum.bytes().0 as u64, false),
+                            );
+                            if ptr_in_bounds.get_zero_extended_constant() == Some(1) {
+                                Some(ptr_in_bounds)
+                            } else {
+                                None
+                            }
                         } else {
                             None
                         }
-                    } else {
-                        None
-                    }
-                    .unwrap_or_else(|| {
-                        let load_offset_end = builder.build_int_add(offset, value_size_v, "");
-
-                        let current_length = builder
-                            .build_load(ptr_to_current_length, "")
-                            .into_int_value();
-                        tbaa_label(
-                            self.module,
-                            self.intrinsics,
-                            format!("memory {} length", memory_index.as_u32()),
-                            current_length.as_instruction_value().unwrap(),
-                        );
-                        let current_length =
-                            builder.build_int_z_extend(current_length, intrinsics.i64_ty, "");
-
-                        builder.build_int_compare(
-                            IntPredicate::ULE,
-                            load_offset_end,
-                            current_length,
-                            "",
-                        )
-                    });
+                        .unwrap_or_else(|| {
+                            let load_offset_end = builder.build_int_add(offset, value_size_v, "");
+
+                            let current_length = builder
+                                .build_load(ptr_to_current_length, "")
+                                .into_int_value();
+                            tbaa_label(
+                                self.module,
+                                self.intrinsics,
+                                format!("memory {} length", memory_index.as_u32()),
+                                current_length.as_instruction_value().unwrap(),
+                            );
+                            let current_length =
+                                builder.build_int_z_extend(current_length, intrinsics.i64_ty, "");
+
+                            builder.build_int_compare(
+                                IntPredicate::ULE,
+                                load_offset_end,
+                                current_length,
+                                "",
+                            )
+                        });
                     if !ptr_in_bounds.is_constant_int()
                         || ptr_in_bounds.get_zero_extended_constant().unwrap() != 1
                     {
@@ -1306,6 +1333,8 @@ pub struct LLVMFunctionCodeGenerator<'ctx, 'a> {
     unreachable_depth: usize,
     memory_styles: &'a PrimaryMap<MemoryIndex, MemoryStyle>,
     _table_styles: &'a PrimaryMap<TableIndex, TableStyle>,
+    disable_bounds_check_elimination: bool,
+    enable_nan_canonicalization: bool,
 
     // This is support for stackmaps:
     /*
@@ -2191,7 +2220,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
                     .for_each(|ret| self.state.push1(*ret));
             }
             Operator::CallIndirect { index, table_index } => {
-                let sigindex = SignatureIndex::from_u32(index);
+                let sigindex = self.module_translation.signature_index(index);
                 let func_type = &self.wasm_module.signatures[sigindex];
                 let expected_dynamic_sigindex =
                     self.ctx
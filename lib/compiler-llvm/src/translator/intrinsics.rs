@@ -161,7 +161,7 @@ pub struct Intrinsics<'ctx> {
     pub trap_call_indirect_null: BasicValueEnum<'ctx>,
     pub trap_call_indirect_sig: BasicValueEnum<'ctx>,
     pub trap_memory_oob: BasicValueEnum<'ctx>,
-    pub trap_illegal_arithmetic: BasicValueEnum<'ctx>,
+    pub trap_integer_overflow: BasicValueEnum<'ctx>,
     pub trap_integer_division_by_zero: BasicValueEnum<'ctx>,
     pub trap_bad_conversion_to_integer: BasicValueEnum<'ctx>,
     pub trap_unaligned_atomic: BasicValueEnum<'ctx>,
@@ -421,7 +421,7 @@ impl<'ctx> Intrinsics<'ctx> {
             trap_memory_oob: i32_ty
                 .const_int(TrapCode::HeapAccessOutOfBounds as _, false)
                 .as_basic_value_enum(),
-            trap_illegal_arithmetic: i32_ty
+            trap_integer_overflow: i32_ty
                 .const_int(TrapCode::IntegerOverflow as _, false)
                 .as_basic_value_enum(),
             trap_integer_division_by_zero: i32_ty
@@ -546,6 +546,7 @@ pub struct CtxType<'ctx, 'a> {
     cached_memory_size: HashMap<MemoryIndex, PointerValue<'ctx>>,
 
     offsets: VMOffsets,
+    conservative_globals: bool,
 }
 
 impl<'ctx, 'a> CtxType<'ctx, 'a> {
@@ -553,6 +554,7 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
         wasm_module: &'a WasmerCompilerModule,
         func_value: &FunctionValue<'ctx>,
         cache_builder: &'a Builder<'ctx>,
+        conservative_globals: bool,
     ) -> CtxType<'ctx, 'a> {
         CtxType {
             ctx_ptr_value: abi::get_vmctx_ptr_param(func_value),
@@ -570,6 +572,7 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
 
             // TODO: pointer width
             offsets: VMOffsets::new(8, &wasm_module),
+            conservative_globals,
         }
     }
 
@@ -840,6 +843,7 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
             &self.cache_builder,
             &self.offsets,
         );
+        let conservative_globals = self.conservative_globals;
         Ok(match cached_globals.entry(index) {
             Entry::Occupied(entry) => entry.into_mut(),
             Entry::Vacant(entry) => {
@@ -883,8 +887,11 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
                     )
                     .into_pointer_value();
 
+                // Imported globals are only known at instantiation time, so
+                // in conservative mode they are always read through the pointer.
+                let is_imported = wasm_module.is_imported_global(index);
                 entry.insert(match global_mutability {
-                    Mutability::Const => {
+                    Mutability::Const if !(conservative_globals && is_imported) => {
                         let value = cache_builder.build_load(global_ptr, "");
                         tbaa_label(
                             module,
@@ -894,7 +901,7 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
                         );
                         GlobalCache::Const { value }
                     }
-                    Mutability::Var => GlobalCache::Mut {
+                    _ => GlobalCache::Mut {
                         ptr_to_value: global_ptr,
                     },
                 })
@@ -8,16 +8,15 @@ use wasmer_compiler::wasmparser::{
 };
 use wasmer_compiler::{
     CompiledFunction, CompiledFunctionFrameInfo, CustomSection, CustomSectionProtection,
-    FunctionBody, Relocation, RelocationKind, RelocationTarget, SectionBody, SectionIndex,
-    TrapInformation,
+    FunctionBody, ModuleTranslationState, Relocation, RelocationKind, RelocationTarget,
+    SectionBody, SectionIndex, TrapInformation,
 };
 use wasmer_types::{
     entity::{EntityRef, PrimaryMap, SecondaryMap},
     FunctionType,
 };
 use wasmer_types::{
-    FunctionIndex, GlobalIndex, LocalFunctionIndex, LocalMemoryIndex, MemoryIndex, SignatureIndex,
-    TableIndex, Type,
+    FunctionIndex, GlobalIndex, LocalFunctionIndex, LocalMemoryIndex, MemoryIndex, TableIndex, Type,
 };
 use wasmer_vm::{MemoryStyle, ModuleInfo, TableStyle, TrapCode, VMBuiltinFunctionIndex, VMOffsets};
 
@@ -27,6 +26,9 @@ pub struct FuncGen<'a> {
     /// Static module information.
     module: &'a ModuleInfo,
 
+    /// The signatures of the types of the module.
+    module_translation: &'a ModuleTranslationState,
+
     /// ModuleInfo compilation config.
     config: &'a Singlepass,
 
@@ -88,6 +90,7 @@ struct SpecialLabelSet {
     table_access_oob: DynamicLabel,
     indirect_call_null: DynamicLabel,
     bad_signature: DynamicLabel,
+    stack_overflow: DynamicLabel,
 }
 
 /// A trap table for a `RunnableModuleInfo`.
@@ -1227,7 +1230,7 @@ impl<'a> FuncGen<'a> {
         let need_check = match self.memory_styles[MemoryIndex::new(0)] {
             MemoryStyle::Static { .. } => false,
             MemoryStyle::Dynamic { .. } => true,
-        };
+        } || self.config.disable_bounds_check_elimination;
         let tmp_addr = self.machine.acquire_temp_gpr().unwrap();
 
         // Reusing `tmp_addr` for temporary indirection here, since it's not used before the last reference to `{base,bound}_loc`.
@@ -1738,7 +1741,45 @@ impl<'a> FuncGen<'a> {
         self.machine.state.register_values
             [X64Register::GPR(Machine::get_vmctx_reg()).to_index().0] = MachineValue::Vmctx;
 
-        // TODO: Explicit stack check is not supported for now.
+        // Explicit stack check: trap if the stack pointer went below the
+        // limit of the current thread, which the runtime returns. A limit of
+        // zero disables the check.
+        //
+        // The parameters are already in their local locations, which are
+        // callee-saved registers or stack slots, so the call doesn't need
+        // to save any register.
+        if self.config.enable_stack_check {
+            let misaligned = self.machine.get_stack_offset() % 16 != 0;
+            if misaligned {
+                self.assembler
+                    .emit_sub(Size::S64, Location::Imm32(8), Location::GPR(GPR::RSP));
+            }
+            self.assembler.emit_mov(
+                Size::S64,
+                Location::Memory(
+                    Machine::get_vmctx_reg(),
+                    self.vmoffsets
+                        .vmctx_builtin_function(VMBuiltinFunctionIndex::get_stack_limit_index())
+                        as i32,
+                ),
+                Location::GPR(GPR::RAX),
+            );
+            self.assembler.emit_mov(
+                Size::S64,
+                Location::GPR(Machine::get_vmctx_reg()),
+                Machine::get_param_location(0),
+            );
+            self.assembler.emit_call_location(Location::GPR(GPR::RAX));
+            if misaligned {
+                self.assembler
+                    .emit_add(Size::S64, Location::Imm32(8), Location::GPR(GPR::RSP));
+            }
+            self.assembler
+                .emit_cmp(Size::S64, Location::GPR(GPR::RAX), Location::GPR(GPR::RSP));
+            self.assembler
+                .emit_jmp(Condition::BelowEqual, self.special_labels.stack_overflow);
+        }
+
         let diff = self.machine.state.diff(&new_machine_state());
         let state_diff_id = self.fsm.diffs.len();
         self.fsm.diffs.push(diff);
@@ -1774,6 +1815,7 @@ impl<'a> FuncGen<'a> {
 
     pub fn new(
         module: &'a ModuleInfo,
+        module_translation: &'a ModuleTranslationState,
         config: &'a Singlepass,
         vmoffsets: &'a VMOffsets,
         memory_styles: &'a PrimaryMap<MemoryIndex, MemoryStyle>,
@@ -1808,10 +1850,12 @@ impl<'a> FuncGen<'a> {
             table_access_oob: assembler.get_label(),
             indirect_call_null: assembler.get_label(),
             bad_signature: assembler.get_label(),
+            stack_overflow: assembler.get_label(),
         };
 
         let mut fg = FuncGen {
             module,
+            module_translation,
             config,
             vmoffsets,
             memory_styles,
@@ -5214,7 +5258,7 @@ impl<'a> FuncGen<'a> {
                     });
                 }
                 let table_index = TableIndex::new(table_index as _);
-                let index = SignatureIndex::new(index as usize);
+                let index = self.module_translation.signature_index(index);
                 let sig = self.module.signatures.get(index).unwrap();
                 let param_types: SmallVec<[WpType; 8]> =
                     sig.params().iter().cloned().map(type_to_wp_type).collect();
@@ -8163,15 +8207,21 @@ impl<'a> FuncGen<'a> {
         self.mark_address_with_trap_code(TrapCode::BadSignature);
         self.assembler.emit_ud2();
 
+        self.assembler
+            .emit_label(self.special_labels.stack_overflow);
+        self.mark_address_with_trap_code(TrapCode::StackOverflow);
+        self.assembler.emit_ud2();
+
         // Notify the assembler backend to generate necessary code at end of function.
         self.assembler.finalize_function();
-        CompiledFunction {
+        let mut function = CompiledFunction {
             body: FunctionBody {
                 body: self.assembler.finalize().unwrap().to_vec(),
                 unwind_info: None,
             },
             relocations: self.relocations,
             jt_offsets: SecondaryMap::new(),
+            jt_entries: SecondaryMap::new(),
             frame_info: CompiledFunctionFrameInfo {
                 traps: self
                     .trap_table
@@ -8185,7 +8235,9 @@ impl<'a> FuncGen<'a> {
                     .collect(),
                 ..Default::default()
             },
-        }
+        };
+        function.sort_by_offset();
+        function
     }
 }
 
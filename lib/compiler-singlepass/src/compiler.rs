@@ -11,12 +11,15 @@ use rayon::prelude::{IntoParallelIterator, IntoParallelRefIterator, ParallelIter
 use std::sync::Arc;
 use wasmer_compiler::wasmparser::BinaryReaderError;
 use wasmer_compiler::TrapInformation;
-use wasmer_compiler::{Compilation, CompileError, CompiledFunction, Compiler, SectionIndex};
 use wasmer_compiler::{
-    CompileModuleInfo, CompilerConfig, GenerateMiddlewareChain, MiddlewareBinaryReader,
-    ModuleTranslationState, Target,
+    Architecture, CompileModuleInfo, CompilerConfig, GenerateMiddlewareChain,
+    MiddlewareBinaryReader, ModuleTranslationState, Target,
 };
-use wasmer_compiler::{FunctionBody, FunctionBodyData};
+use wasmer_compiler::{
+    Compilation, CompilationProgress, CompileError, CompiledFunction, Compiler, DebugInfoPolicy,
+    SectionIndex,
+};
+use wasmer_compiler::{FunctionBody, FunctionBodyData, WasmError};
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{FunctionIndex, FunctionType, LocalFunctionIndex, MemoryIndex, TableIndex};
 use wasmer_vm::{ModuleInfo, TrapCode, VMOffsets};
@@ -42,17 +45,38 @@ impl SinglepassCompiler {
 }
 
 impl Compiler for SinglepassCompiler {
+    fn max_function_results(&self) -> Option<usize> {
+        self.config.max_function_results()
+    }
+
+    fn prune_dead_functions(&self) -> bool {
+        self.config.enable_dead_function_pruning
+    }
+
+    fn debug_info_policy(&self) -> DebugInfoPolicy {
+        self.config.debug_info_policy
+    }
+
     /// Compile the module using Singlepass, producing a compilation result with
     /// associated relocations.
     fn compile_module(
         &self,
-        _target: &Target,
+        target: &Target,
         compile_info: &CompileModuleInfo,
-        _module_translation: &ModuleTranslationState,
+        module_translation: &ModuleTranslationState,
         function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+        progress: &CompilationProgress,
     ) -> Result<Compilation, CompileError> {
+        if target.triple().architecture != Architecture::X86_64 {
+            return Err(CompileError::UnsupportedTarget(target.triple().to_string()));
+        }
         if compile_info.features.multi_value {
-            return Err(CompileError::UnsupportedFeature("multivalue".to_string()));
+            return Err(CompileError::UnsupportedFeature {
+                feature: "multi value".to_string(),
+                message: "not supported by Singlepass".to_string(),
+                function: None,
+                offset: None,
+            });
         }
         let vmoffsets = VMOffsets::new(8, &compile_info.module);
         let memory_styles = &compile_info.memory_styles;
@@ -73,9 +97,10 @@ impl Compiler for SinglepassCompiler {
             .collect::<Vec<(LocalFunctionIndex, &FunctionBodyData<'_>)>>()
             .par_iter()
             .map(|(i, input)| {
+                progress.check_cancelled()?;
                 let middleware_chain = self.config.middlewares.generate_middleware_chain(*i);
                 let mut reader =
-                    MiddlewareBinaryReader::new_with_offset(input.data, input.module_offset);
+                    MiddlewareBinaryReader::new_with_offset(&input.data, input.module_offset);
                 reader.set_middleware_chain(middleware_chain);
 
                 // This local list excludes arguments.
@@ -93,6 +118,7 @@ impl Compiler for SinglepassCompiler {
 
                 let mut generator = FuncGen::new(
                     module,
+                    module_translation,
                     &self.config,
                     &vmoffsets,
                     &memory_styles,
@@ -107,7 +133,9 @@ impl Compiler for SinglepassCompiler {
                     generator.feed_operator(op).map_err(to_compile_error)?;
                 }
 
-                Ok(generator.finalize())
+                let function = generator.finalize();
+                progress.functions_compiled(1);
+                Ok(function)
             })
             .collect::<Result<Vec<CompiledFunction>, CompileError>>()?
             .into_iter()
@@ -153,6 +181,12 @@ impl ToCompileError for BinaryReaderError {
     }
 }
 
+impl ToCompileError for WasmError {
+    fn to_compile_error(self) -> CompileError {
+        self.into()
+    }
+}
+
 impl ToCompileError for CodegenError {
     fn to_compile_error(self) -> CompileError {
         CompileError::Codegen(self.message)
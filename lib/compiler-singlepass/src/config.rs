@@ -3,13 +3,18 @@
 
 use crate::compiler::SinglepassCompiler;
 use std::sync::Arc;
-use wasmer_compiler::{Compiler, CompilerConfig, CpuFeature, FunctionMiddlewareGenerator, Target};
+use wasmer_compiler::{
+    Compiler, CompilerConfig, CpuFeature, DebugInfoPolicy, FunctionMiddlewareGenerator, Target,
+};
 use wasmer_types::Features;
 
 #[derive(Debug, Clone)]
 pub struct Singlepass {
     pub(crate) enable_nan_canonicalization: bool,
     pub(crate) enable_stack_check: bool,
+    pub(crate) enable_dead_function_pruning: bool,
+    pub(crate) debug_info_policy: DebugInfoPolicy,
+    pub(crate) disable_bounds_check_elimination: bool,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn FunctionMiddlewareGenerator>>,
 }
@@ -21,6 +26,9 @@ impl Singlepass {
         Self {
             enable_nan_canonicalization: true,
             enable_stack_check: false,
+            enable_dead_function_pruning: false,
+            debug_info_policy: DebugInfoPolicy::KeepAll,
+            disable_bounds_check_elimination: false,
             middlewares: vec![],
         }
     }
@@ -53,6 +61,27 @@ impl CompilerConfig for Singlepass {
         // PIC code.
     }
 
+    fn enable_explicit_stack_checks(&mut self) {
+        self.enable_stack_check = true;
+    }
+
+    fn enable_dead_function_pruning(&mut self) {
+        self.enable_dead_function_pruning = true;
+    }
+
+    fn set_debug_info_policy(&mut self, policy: DebugInfoPolicy) {
+        self.debug_info_policy = policy;
+    }
+
+    fn disable_bounds_check_elimination(&mut self) {
+        self.disable_bounds_check_elimination = true;
+    }
+
+    /// Singlepass doesn't support multi-value returns.
+    fn max_function_results(&self) -> Option<usize> {
+        Some(1)
+    }
+
     /// Transform it into the compiler
     fn compiler(&self) -> Box<dyn Compiler + Send> {
         Box::new(SinglepassCompiler::new(&self))
@@ -1,19 +1,33 @@
 //! This module mainly outputs the `Compiler` trait that custom
 //! compilers will need to implement.
 
-use crate::error::CompileError;
-use crate::function::Compilation;
+use crate::error::{CompileError, WasmError, WasmResult};
+use crate::function::{Compilation, CompiledFunction};
+use crate::lib::std::borrow::Cow;
 use crate::lib::std::boxed::Box;
+use crate::lib::std::string::String;
 use crate::lib::std::sync::Arc;
-use crate::module::CompileModuleInfo;
+use crate::lib::std::vec::Vec;
+use crate::module::{CompileModuleInfo, CompiledModule};
+use crate::progress::CompilationProgress;
 use crate::target::Target;
-use crate::translator::FunctionMiddlewareGenerator;
+use crate::translator::{
+    lower_extended_const_exprs, to_wasm_error, write_leb128, FunctionMiddlewareGenerator,
+};
 use crate::FunctionBodyData;
+use crate::ModuleEnvironment;
 use crate::ModuleTranslationState;
 use crate::SectionIndex;
-use wasmer_types::entity::PrimaryMap;
-use wasmer_types::{Features, FunctionIndex, LocalFunctionIndex, SignatureIndex};
-use wasmparser::{validate, OperatorValidatorConfig, ValidatingParserConfig};
+use wasmer_types::entity::{EntityRef, PrimaryMap};
+use wasmer_types::{
+    merge_data_initializers, DebugInfoPolicy, Features, FunctionIndex, FunctionType,
+    LocalFunctionIndex, MemoryType, OwnedDataInitializer, SignatureIndex, TableType, Type,
+};
+use wasmer_vm::{MemoryStyle, TableStyle};
+use wasmparser::{
+    ImportSectionEntryType, ModuleReader, OperatorValidatorConfig, SectionCode,
+    ValidatingParserConfig,
+};
 
 /// The compiler configuration options.
 pub trait CompilerConfig {
@@ -36,6 +50,57 @@ pub trait CompilerConfig {
         // in case they create an IR that they can verify.
     }
 
+    /// Enable explicit stack checks.
+    ///
+    /// For compilers capable of doing so, this emits a comparison against
+    /// the stack limit of the current thread in every function prologue,
+    /// trapping with `TrapCode::StackOverflow` when it's exceeded instead of
+    /// relying on the guard page to catch the overflow.
+    ///
+    /// Cranelift and Singlepass support this, LLVM ignores it.
+    fn enable_explicit_stack_checks(&mut self) {
+        // By default we do nothing, each backend will need to customize this
+        // in case they can emit stack checks.
+    }
+
+    /// Disable bounds check elimination.
+    ///
+    /// For compilers capable of doing so, this emits an explicit bounds
+    /// check on every memory access, instead of relying on the guard pages
+    /// of static memories or on accesses proven in bounds. The code is
+    /// slower, but an out-of-bounds access is reported at the access that
+    /// caused it, which makes debugging easier.
+    fn disable_bounds_check_elimination(&mut self) {
+        // By default we do nothing, each backend will need to customize this
+        // in case they elide bounds checks.
+    }
+
+    /// Enable dead function pruning.
+    ///
+    /// The functions that can't be reached from the exports, the start
+    /// function, the element segments or the global initializers are
+    /// removed before compilation, and the remaining functions are
+    /// renumbered, which makes the compiled module smaller. The function
+    /// indices of the compiled module then differ from the original ones,
+    /// in the frames of the traps for instance.
+    fn enable_dead_function_pruning(&mut self) {
+        // By default we do nothing, each backend will need to customize this
+        // in case they can prune the modules they compile.
+    }
+
+    /// Sets the debug information of the modules that is kept when they
+    /// are compiled, see [`DebugInfoPolicy`]. All of it is kept by default.
+    fn set_debug_info_policy(&mut self, _policy: DebugInfoPolicy) {
+        // By default we do nothing, each backend will need to customize this
+        // in case they can strip the modules they compile.
+    }
+
+    /// The maximum number of results of the functions the compiler can
+    /// compile, or `None` if there is no limit.
+    fn max_function_results(&self) -> Option<usize> {
+        None
+    }
+
     /// Gets the custom compiler config
     fn compiler(&self) -> Box<dyn Compiler + Send>;
 
@@ -48,8 +113,205 @@ pub trait CompilerConfig {
     fn push_middleware(&mut self, middleware: Arc<dyn FunctionMiddlewareGenerator>);
 }
 
+fn validating_config(features: &Features) -> ValidatingParserConfig {
+    ValidatingParserConfig {
+        operator_config: OperatorValidatorConfig {
+            enable_threads: features.threads,
+            enable_reference_types: features.reference_types,
+            enable_bulk_memory: features.bulk_memory,
+            enable_tail_call: false,
+            enable_simd: features.simd,
+            enable_multi_value: features.multi_value,
+        },
+    }
+}
+
+/// Validates a WebAssembly module with the given features, without
+/// translating or compiling it.
+///
+/// `wasmparser` doesn't know the extended constant expressions proposal,
+/// so when it's enabled the extended expressions are checked and replaced
+/// by constants before validating the module.
+pub fn validate(data: &[u8], features: &Features) -> WasmResult<()> {
+    let data = if features.extended_const {
+        lower_extended_const_exprs(data)?
+    } else {
+        Cow::Borrowed(data)
+    };
+    wasmparser::validate(&data, Some(validating_config(features))).map_err(to_wasm_error)
+}
+
+/// The messages of the `wasmparser` errors raised when one of its
+/// implementation limits is exceeded by an otherwise valid module.
+const IMPLEMENTATION_LIMIT_MESSAGES: &[&str] = &[
+    "local_count is out of bounds",
+    "locals_total is out of bounds",
+    "locals exceed maximum",
+    "function params size is out of bound",
+    "function returns size is out of bound",
+    "br_table size is out of bound",
+    "string size in out of bounds",
+    "num_elements is out of bounds",
+    "function count is out of bounds",
+    "functions count out of bounds",
+    "types count is out of bounds",
+    "globals count out of bounds",
+];
+
+/// Converts a validation error to a `CompileError`, telling the exceeded
+/// implementation limits apart from the invalid modules.
+fn validation_error(error: WasmError) -> CompileError {
+    match error {
+        WasmError::InvalidWebAssembly { message, offset }
+            if IMPLEMENTATION_LIMIT_MESSAGES.contains(&message.as_str()) =>
+        {
+            CompileError::ResourceLimit(format!("{} (at offset {})", message, offset))
+        }
+        error => error.into(),
+    }
+}
+
+/// Finds the function whose body contains `offset`.
+fn function_at_offset(data: &[u8], offset: usize) -> Option<FunctionIndex> {
+    let mut reader = ModuleReader::new(data).ok()?;
+    let mut imported_functions = 0;
+    while !reader.eof() {
+        let section = reader.read().ok()?;
+        match section.code {
+            SectionCode::Import => {
+                for import in section.get_import_section_reader().ok()? {
+                    if let ImportSectionEntryType::Function(_) = import.ok()?.ty {
+                        imported_functions += 1;
+                    }
+                }
+            }
+            SectionCode::Code => {
+                for (index, body) in section
+                    .get_code_section_reader()
+                    .ok()?
+                    .into_iter()
+                    .enumerate()
+                {
+                    let range = body.ok()?.range();
+                    if range.start <= offset && offset < range.end {
+                        return Some(FunctionIndex::new(imported_functions + index));
+                    }
+                }
+                return None;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Encodes a module defining a single function, see
+/// `Compiler::compile_function`.
+fn single_function_module(ty: &FunctionType, body: &[u8], locals: &[(u32, Type)]) -> Vec<u8> {
+    fn type_byte(ty: Type) -> u8 {
+        match ty {
+            Type::I32 => 0x7f,
+            Type::I64 => 0x7e,
+            Type::F32 => 0x7d,
+            Type::F64 => 0x7c,
+            Type::V128 => 0x7b,
+            Type::FuncRef => 0x70,
+            Type::ExternRef => 0x6f,
+        }
+    }
+    fn write_types(out: &mut Vec<u8>, types: &[Type]) {
+        write_leb128(out, types.len());
+        out.extend(types.iter().map(|&ty| type_byte(ty)));
+    }
+    fn write_section(out: &mut Vec<u8>, id: u8, contents: &[u8]) {
+        out.push(id);
+        write_leb128(out, contents.len());
+        out.extend_from_slice(contents);
+    }
+
+    let mut data = b"\0asm\x01\0\0\0".to_vec();
+
+    // The type section, with the type of the function.
+    let mut types = Vec::new();
+    write_leb128(&mut types, 1);
+    types.push(0x60);
+    write_types(&mut types, ty.params());
+    write_types(&mut types, ty.results());
+    write_section(&mut data, 1, &types);
+
+    // The function section, with the index of its type.
+    write_section(&mut data, 3, &[1, 0]);
+
+    // The code section, with its locals and its body.
+    let mut function = Vec::new();
+    write_leb128(&mut function, locals.len());
+    for &(count, ty) in locals {
+        write_leb128(&mut function, count as usize);
+        function.push(type_byte(ty));
+    }
+    function.extend_from_slice(body);
+    let mut code = Vec::new();
+    write_leb128(&mut code, 1);
+    write_leb128(&mut code, function.len());
+    code.extend(function);
+    write_section(&mut data, 10, &code);
+
+    data
+}
+
+/// Detects the features required by a WebAssembly module.
+///
+/// A feature is required if the module doesn't validate without it.
+pub fn detect_features(data: &[u8]) -> Result<Features, CompileError> {
+    let mut all = Features::new();
+    all.threads(true)
+        .reference_types(true)
+        .simd(true)
+        .bulk_memory(true)
+        .multi_value(true)
+        .extended_const(true);
+    validate(data, &all).map_err(validation_error)?;
+
+    let requires = |disable: fn(&mut Features)| {
+        let mut features = all.clone();
+        disable(&mut features);
+        validate(data, &features).is_err()
+    };
+    Ok(Features {
+        threads: requires(|f| f.threads = false),
+        reference_types: requires(|f| f.reference_types = false),
+        simd: requires(|f| f.simd = false),
+        bulk_memory: requires(|f| f.bulk_memory = false),
+        multi_value: requires(|f| f.multi_value = false),
+        extended_const: requires(|f| f.extended_const = false),
+    })
+}
+
 /// An implementation of a Compiler from parsed WebAssembly module to Compiled native code.
 pub trait Compiler {
+    /// The maximum number of results of the functions the compiler can
+    /// compile, or `None` if there is no limit.
+    ///
+    /// Translating a module with a function returning more results fails
+    /// with [`WasmError::TooManyResults`].
+    ///
+    /// [`WasmError::TooManyResults`]: crate::WasmError::TooManyResults
+    fn max_function_results(&self) -> Option<usize> {
+        None
+    }
+
+    /// Whether the dead functions of a module are removed before compiling
+    /// it, see `CompilerConfig::enable_dead_function_pruning`.
+    fn prune_dead_functions(&self) -> bool {
+        false
+    }
+
+    /// The debug information of the modules that is kept when they are
+    /// compiled, see `CompilerConfig::set_debug_info_policy`.
+    fn debug_info_policy(&self) -> DebugInfoPolicy {
+        DebugInfoPolicy::KeepAll
+    }
+
     /// Validates a module.
     ///
     /// It returns the a succesful Result in case is valid, `CompileError` in case is not.
@@ -58,21 +320,128 @@ pub trait Compiler {
         features: &Features,
         data: &'data [u8],
     ) -> Result<(), CompileError> {
-        let config = ValidatingParserConfig {
-            operator_config: OperatorValidatorConfig {
-                enable_threads: features.threads,
-                enable_reference_types: features.reference_types,
-                enable_bulk_memory: features.bulk_memory,
-                enable_tail_call: false,
-                enable_simd: features.simd,
-                enable_multi_value: features.multi_value,
-            },
+        validate(data, features).map_err(|e| {
+            // Report the features the module needs that aren't enabled,
+            // along with the first instruction that uses them.
+            let required = match detect_features(data) {
+                Ok(required) if !features.is_superset_of(&required) => required,
+                _ => return validation_error(e),
+            };
+            match e {
+                WasmError::InvalidWebAssembly { message, offset } => {
+                    CompileError::UnsupportedFeature {
+                        feature: features.missing_features(&required).join(", "),
+                        message,
+                        function: function_at_offset(data, offset),
+                        offset: Some(offset),
+                    }
+                }
+                e => validation_error(e),
+            }
+        })
+    }
+
+    /// Translates and compiles a WebAssembly module.
+    ///
+    /// This is the entry point for engines: the returned
+    /// [`CompiledModule`] has everything needed to load the module. The
+    /// memories and tables are compiled with the styles returned by
+    /// `memory_style` and `table_style` respectively.
+    ///
+    /// The compiled functions are counted in `progress`, and cancelling it
+    /// stops the compilation with [`CompileError::Cancelled`].
+    fn compile_wasm(
+        &self,
+        target: &Target,
+        data: &[u8],
+        features: &Features,
+        memory_style: &dyn Fn(&MemoryType) -> MemoryStyle,
+        table_style: &dyn Fn(&TableType) -> TableStyle,
+        progress: &CompilationProgress,
+    ) -> Result<CompiledModule, CompileError> {
+        progress.check_cancelled()?;
+        let mut translation = ModuleEnvironment::new()
+            .with_max_function_results(self.max_function_results())
+            .with_threads(features.threads)
+            .with_debug_info_policy(self.debug_info_policy())
+            .translate(data)?;
+        if self.prune_dead_functions() {
+            translation.prune_dead_functions()?;
+        }
+        progress.set_functions_total(translation.function_body_inputs.len());
+        let compile_info = CompileModuleInfo {
+            memory_styles: translation
+                .module
+                .memories
+                .values()
+                .map(memory_style)
+                .collect(),
+            table_styles: translation
+                .module
+                .tables
+                .values()
+                .map(table_style)
+                .collect(),
+            module: Arc::new(translation.module),
+            features: features.clone(),
         };
-        validate(data, Some(config)).map_err(|e| CompileError::Validate(format!("{}", e)))
+        let compilation = self.compile_module(
+            target,
+            &compile_info,
+            translation.module_translation.as_ref().unwrap(),
+            translation.function_body_inputs,
+            progress,
+        )?;
+        let data_initializers = merge_data_initializers(
+            &translation
+                .data_initializers
+                .iter()
+                .map(OwnedDataInitializer::new)
+                .collect::<Vec<_>>(),
+        );
+        Ok(CompiledModule {
+            compile_info,
+            compilation,
+            data_initializers,
+        })
+    }
+
+    /// Compiles a single function for the host, from its type, its
+    /// locals, as `(count, type)` groups, and its body, the code that
+    /// follows the declaration of its locals.
+    ///
+    /// This is meant for testing compilers on a function in isolation:
+    /// the function is validated and compiled as the only item of a
+    /// module, without imports, memories, tables or globals, with the
+    /// features it requires enabled.
+    fn compile_function(
+        &self,
+        ty: &FunctionType,
+        body: &[u8],
+        locals: &[(u32, Type)],
+    ) -> Result<CompiledFunction, CompileError> {
+        let data = single_function_module(ty, body, locals);
+        let features = detect_features(&data)?;
+        let target = Target::default();
+        let compiled = self.compile_wasm(
+            &target,
+            &data,
+            &features,
+            &|_| unreachable!("the module has no memory"),
+            &|_| unreachable!("the module has no table"),
+            &CompilationProgress::new(),
+        )?;
+        Ok(compiled.compilation.get(LocalFunctionIndex::new(0)).clone())
     }
 
     /// Compiles a parsed module.
     ///
+    /// This is implemented by each compiler, engines should use
+    /// [`Compiler::compile_wasm`] instead.
+    ///
+    /// Each compiled function is counted in `progress`, and no new function
+    /// should be compiled once it's cancelled.
+    ///
     /// It returns the [`Compilation`] or a [`CompileError`].
     fn compile_module<'data, 'module>(
         &self,
@@ -81,6 +450,7 @@ pub trait Compiler {
         module_translation: &ModuleTranslationState,
         // The list of function bodies
         function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'data>>,
+        progress: &CompilationProgress,
     ) -> Result<Compilation, CompileError>;
 
     /// Compiles a module into a native object file.
@@ -126,4 +496,10 @@ pub trait SymbolRegistry: Send + Sync {
     ///
     /// This function is the inverse of [`SymbolRegistry::symbol_to_name`]
     fn name_to_symbol(&self, name: &str) -> Option<Symbol>;
+
+    /// Given a local function it returns its name in the Wasm module, if
+    /// it has one, to name its debugging symbol in the object file.
+    fn local_function_name(&self, _index: LocalFunctionIndex) -> Option<String> {
+        None
+    }
 }
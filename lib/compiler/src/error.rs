@@ -1,6 +1,10 @@
-use crate::lib::std::string::String;
+use crate::lib::std::string::{String, ToString};
+use crate::target::{Architecture, CpuFeature};
+#[cfg(feature = "core")]
+use alloc::format;
 #[cfg(feature = "std")]
 use thiserror::Error;
+use wasmer_types::FunctionIndex;
 
 // Compilation Errors
 //
@@ -16,25 +20,153 @@ use thiserror::Error;
 #[derive(Debug)]
 #[cfg_attr(feature = "std", derive(Error))]
 pub enum CompileError {
-    /// A Wasm translation error occured.
-    #[cfg_attr(feature = "std", error("WebAssembly translation error: {0}"))]
-    Wasm(#[cfg_attr(feature = "std", from)] WasmError),
+    /// The module is not valid WebAssembly.
+    #[cfg_attr(feature = "std", error("Validation error: {0}"))]
+    Validate(String),
+
+    /// The module uses a feature that is disabled, or that the compiler
+    /// doesn't support yet.
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "Unsupported feature {feature}: {message}{}",
+            location(.function, .offset)
+        )
+    )]
+    UnsupportedFeature {
+        /// The unsupported feature.
+        feature: String,
+        /// Why the use of the feature failed.
+        message: String,
+        /// The function using the feature, if known.
+        function: Option<FunctionIndex>,
+        /// The bytecode offset where the feature is used, if known.
+        offset: Option<usize>,
+    },
+
+    /// The compiler doesn't support the target.
+    #[cfg_attr(feature = "std", error("Unsupported target: {0}"))]
+    UnsupportedTarget(String),
 
-    /// A compilation error occured.
+    /// An implementation limit was exceeded, or there weren't enough
+    /// resources to compile the module.
+    #[cfg_attr(feature = "std", error("Insufficient resources: {0}"))]
+    ResourceLimit(String),
+
+    /// The compiler failed to generate the code of a valid module, which
+    /// is a bug of the compiler.
     #[cfg_attr(feature = "std", error("Compilation error: {0}"))]
     Codegen(String),
 
-    /// The module did not pass validation.
-    #[cfg_attr(feature = "std", error("Validation error: {0}"))]
-    Validate(String),
+    /// A middleware rejected the module.
+    #[cfg_attr(feature = "std", error("{0}"))]
+    Middleware(MiddlewareError),
+
+    /// The compilation was cancelled through its [`CompilationProgress`].
+    ///
+    /// [`CompilationProgress`]: crate::CompilationProgress
+    #[cfg_attr(feature = "std", error("Compilation was cancelled"))]
+    Cancelled,
+}
 
-    /// The compiler doesn't support a Wasm feature
-    #[cfg_attr(feature = "std", error("Feature {0} is not yet supported"))]
-    UnsupportedFeature(String),
+impl CompileError {
+    /// Whether the error is caused by the module, which is invalid or
+    /// rejected by a middleware.
+    pub fn is_user_error(&self) -> bool {
+        match self {
+            Self::Validate(_) | Self::Middleware(_) => true,
+            _ => false,
+        }
+    }
 
-    /// Insufficient resources available for execution.
-    #[cfg_attr(feature = "std", error("Insufficient resources: {0}"))]
-    Resource(String),
+    /// Whether the error is caused by a limitation of the compiler or of
+    /// the engine, rather than by the module.
+    ///
+    /// A compiler bug (`Codegen`) is neither a user error nor an engine
+    /// limitation.
+    pub fn is_engine_limitation(&self) -> bool {
+        match self {
+            Self::UnsupportedFeature { .. }
+            | Self::UnsupportedTarget(_)
+            | Self::ResourceLimit(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Formats where an unsupported feature is used.
+#[cfg(feature = "std")]
+fn location(function: &Option<FunctionIndex>, offset: &Option<usize>) -> String {
+    match (function, offset) {
+        (Some(function), Some(offset)) => {
+            format!(" (in function {} at offset {})", function.as_u32(), offset)
+        }
+        (Some(function), None) => format!(" (in function {})", function.as_u32()),
+        (None, Some(offset)) => format!(" (at offset {})", offset),
+        (None, None) => String::new(),
+    }
+}
+
+impl From<WasmError> for CompileError {
+    fn from(error: WasmError) -> Self {
+        match error {
+            WasmError::InvalidWebAssembly { message, offset } => {
+                Self::Validate(format!("{} (at offset {})", message, offset))
+            }
+            WasmError::Unsupported(feature) => Self::UnsupportedFeature {
+                feature,
+                message: "not supported by the compiler".to_string(),
+                function: None,
+                offset: None,
+            },
+            WasmError::ImplLimitExceeded => {
+                Self::ResourceLimit("Implementation limit exceeded".to_string())
+            }
+            WasmError::TooManyResults { func, count, limit } => Self::UnsupportedFeature {
+                feature: "multi value".to_string(),
+                message: format!(
+                    "the function returns {} results, but the compiler supports at most {}",
+                    count, limit
+                ),
+                function: Some(func),
+                offset: None,
+            },
+            WasmError::Middleware(error) => Self::Middleware(error),
+            WasmError::Generic(message) => Self::Codegen(message),
+        }
+    }
+}
+
+/// An error raised by a middleware to reject a function.
+#[derive(Debug)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("Error in middleware {name}: {message}"))]
+pub struct MiddlewareError {
+    /// The name of the middleware.
+    pub name: String,
+    /// The error message.
+    pub message: String,
+}
+
+impl MiddlewareError {
+    /// Creates a new `MiddlewareError`.
+    pub fn new<A: Into<String>, B: Into<String>>(name: A, message: B) -> Self {
+        Self {
+            name: name.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// A type error found by [`ModuleTranslationState::typecheck_function`].
+///
+/// [`ModuleTranslationState::typecheck_function`]: crate::ModuleTranslationState::typecheck_function
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Error))]
+#[cfg_attr(feature = "std", error("Type error: {message}"))]
+pub struct TypeError {
+    /// The error message.
+    pub message: String,
 }
 
 /// A WebAssembly translation error.
@@ -69,6 +201,29 @@ pub enum WasmError {
     #[cfg_attr(feature = "std", error("Implementation limit exceeded"))]
     ImplLimitExceeded,
 
+    /// A function returns more results than the compiler supports.
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "Function {} returns {} results, but the compiler supports at most {}",
+            .func.as_u32(),
+            .count,
+            .limit
+        )
+    )]
+    TooManyResults {
+        /// The index of the function.
+        func: FunctionIndex,
+        /// The number of results of the function.
+        count: usize,
+        /// The maximum number of results the compiler supports.
+        limit: usize,
+    },
+
+    /// A middleware rejected a function.
+    #[cfg_attr(feature = "std", error("{0}"))]
+    Middleware(MiddlewareError),
+
     /// A generic error.
     #[cfg_attr(feature = "std", error("{0}"))]
     Generic(String),
@@ -84,5 +239,22 @@ pub enum ParseCpuFeatureError {
     Missing(String),
 }
 
+/// The error that can happen while building a [`Target`].
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Error))]
+pub enum TargetError {
+    /// A CPU feature is not available on the architecture of the triple.
+    #[cfg_attr(
+        feature = "std",
+        error("CpuFeature {} is not available on {}", feature.to_string(), architecture)
+    )]
+    UnsupportedCpuFeature {
+        /// The requested CPU feature.
+        feature: CpuFeature,
+        /// The architecture of the triple.
+        architecture: Architecture,
+    },
+}
+
 /// A convenient alias for a `Result` that uses `WasmError` as the error type.
 pub type WasmResult<T> = Result<T, WasmError>;
@@ -8,10 +8,14 @@
 //! * `jit`: to generate a JIT
 //! * `obj`: to generate a native object
 
+use crate::lib::std::fmt;
 use crate::lib::std::vec::Vec;
 use crate::section::{CustomSection, SectionIndex};
 use crate::trap::TrapInformation;
-use crate::{CompiledFunctionUnwindInfo, FunctionAddressMap, JumpTableOffsets, Relocation};
+use crate::{
+    CodeOffset, CompiledFunctionUnwindInfo, FunctionAddressMap, JumpTable, JumpTableEntries,
+    JumpTableOffsets, Relocation,
+};
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
 use wasmer_types::entity::PrimaryMap;
@@ -50,22 +54,61 @@ pub struct FunctionBody {
 /// This structure only have the compiled information data
 /// (function bytecode body, relocations, traps, jump tables
 /// and unwind information).
+///
+/// The relocations are sorted by offset and the traps by code offset,
+/// so that compiling the same function always gives the same result,
+/// see [`CompiledFunction::sort_by_offset`].
 #[cfg_attr(feature = "enable-serde", derive(Deserialize, Serialize))]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CompiledFunction {
     /// The function body.
     pub body: FunctionBody,
 
-    /// The relocations (in the body)
+    /// The relocations (in the body), in ascending offset order.
     pub relocations: Vec<Relocation>,
 
-    /// The jump tables offsets (in the body).
+    /// The jump tables offsets (in the body), indexed by jump table.
     pub jt_offsets: JumpTableOffsets,
 
+    /// The entries of the jump tables, indexed by jump table. Only the
+    /// compilers emitting jump tables in the body record them.
+    pub jt_entries: JumpTableEntries,
+
     /// The frame information.
     pub frame_info: CompiledFunctionFrameInfo,
 }
 
+impl CompiledFunction {
+    /// Sorts the relocations by offset and the traps by code offset.
+    ///
+    /// The sort is stable, so relocations or traps at the same offset
+    /// keep the order in which the compiler emitted them.
+    pub fn sort_by_offset(&mut self) {
+        self.relocations.sort_by_key(|reloc| reloc.offset);
+        self.frame_info.traps.sort_by_key(|trap| trap.code_offset);
+    }
+
+    /// Returns the jump tables of the function with the code offsets of
+    /// their entries, see `JumpTableData::entries`.
+    pub fn jump_tables(&self) -> impl Iterator<Item = (JumpTable, &[CodeOffset])> {
+        self.jt_entries
+            .iter()
+            .map(|(jump_table, data)| (jump_table, data.entries()))
+    }
+
+    /// Returns whether the relocations and the traps are sorted by offset.
+    fn is_sorted_by_offset(&self) -> bool {
+        self.relocations
+            .windows(2)
+            .all(|pair| pair[0].offset <= pair[1].offset)
+            && self
+                .frame_info
+                .traps
+                .windows(2)
+                .all(|pair| pair[0].code_offset <= pair[1].code_offset)
+    }
+}
+
 /// The compiled functions map (index in the Wasm -> function)
 pub type Functions = PrimaryMap<LocalFunctionIndex, CompiledFunction>;
 
@@ -144,6 +187,13 @@ pub struct Compilation {
 
 impl Compilation {
     /// Creates a compilation artifact from a contiguous function buffer and a set of ranges
+    ///
+    /// The relocations and traps of each function must be sorted by
+    /// offset, see [`CompiledFunction::sort_by_offset`]. Together with the
+    /// functions being indexed by `LocalFunctionIndex`, this makes the
+    /// compilation independent of the order in which the functions were
+    /// compiled, so it serializes to the same bytes however many threads
+    /// were used.
     pub fn new(
         functions: Functions,
         custom_sections: CustomSections,
@@ -151,6 +201,12 @@ impl Compilation {
         dynamic_function_trampolines: PrimaryMap<FunctionIndex, FunctionBody>,
         debug: Option<Dwarf>,
     ) -> Self {
+        debug_assert!(
+            functions
+                .values()
+                .all(CompiledFunction::is_sorted_by_offset),
+            "the relocations and traps of the compiled functions must be sorted by offset"
+        );
         Self {
             functions,
             custom_sections,
@@ -207,6 +263,14 @@ impl Compilation {
             .collect::<PrimaryMap<LocalFunctionIndex, _>>()
     }
 
+    /// Gets functions address maps.
+    pub fn get_address_maps(&self) -> PrimaryMap<LocalFunctionIndex, FunctionAddressMap> {
+        self.functions
+            .iter()
+            .map(|(_, func)| func.frame_info.address_map.clone())
+            .collect::<PrimaryMap<LocalFunctionIndex, _>>()
+    }
+
     /// Gets function call trampolines.
     pub fn get_function_call_trampolines(&self) -> PrimaryMap<SignatureIndex, FunctionBody> {
         self.function_call_trampolines.clone()
@@ -234,6 +298,53 @@ impl Compilation {
     pub fn get_debug(&self) -> Option<Dwarf> {
         self.debug.clone()
     }
+
+    /// Returns statistics about the compiled functions.
+    pub fn stats(&self) -> CompilationStats {
+        let mut stats = CompilationStats {
+            functions: self.functions.len(),
+            ..Default::default()
+        };
+        for (_, function) in self.functions.iter() {
+            stats.code_bytes += function.body.body.len();
+            stats.relocations += function.relocations.len();
+            stats.traps += function.frame_info.traps.len();
+        }
+        if stats.functions > 0 {
+            stats.average_function_size = stats.code_bytes / stats.functions;
+        }
+        stats
+    }
+}
+
+/// Statistics about the functions of a [`Compilation`], see
+/// [`Compilation::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CompilationStats {
+    /// The number of compiled functions.
+    pub functions: usize,
+    /// The total size in bytes of the function bodies.
+    pub code_bytes: usize,
+    /// The total number of relocations in the function bodies.
+    pub relocations: usize,
+    /// The total number of trap sites in the function bodies.
+    pub traps: usize,
+    /// The average size in bytes of a function body, rounded down.
+    pub average_function_size: usize,
+}
+
+impl fmt::Display for CompilationStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} functions, {} bytes of code (average {} bytes per function), {} relocations, {} traps",
+            self.functions,
+            self.code_bytes,
+            self.average_function_size,
+            self.relocations,
+            self.traps
+        )
+    }
 }
 
 impl<'a> IntoIterator for &'a Compilation {
@@ -5,6 +5,7 @@
 //! Source: https://en.wikipedia.org/wiki/Branch_table
 
 use super::CodeOffset;
+use crate::lib::std::vec::Vec;
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
 use wasmer_types::entity::{entity_impl, SecondaryMap};
@@ -34,3 +35,38 @@ impl JumpTable {
 
 /// Code offsets for Jump Tables.
 pub type JumpTableOffsets = SecondaryMap<JumpTable, CodeOffset>;
+
+/// The contents of a jump table of a compiled function.
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct JumpTableData {
+    entries: Vec<CodeOffset>,
+}
+
+impl JumpTableData {
+    /// Creates the jump table with the given entries.
+    pub fn new(entries: Vec<CodeOffset>) -> Self {
+        Self { entries }
+    }
+
+    /// The code offsets, in the function body, of the targets of the jump
+    /// table, in the order of its entries.
+    ///
+    /// The default target of the branch isn't part of the table.
+    pub fn entries(&self) -> &[CodeOffset] {
+        &self.entries
+    }
+
+    /// The number of entries of the jump table.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the jump table has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// The contents of the jump tables of a compiled function.
+pub type JumpTableEntries = SecondaryMap<JumpTable, JumpTableData>;
@@ -56,6 +56,7 @@ mod error;
 mod function;
 mod jump_table;
 mod module;
+mod progress;
 mod relocation;
 mod target;
 mod trap;
@@ -68,31 +69,38 @@ mod sourceloc;
 
 pub use crate::address_map::{FunctionAddressMap, InstructionAddressMap};
 #[cfg(feature = "translator")]
-pub use crate::compiler::{Compiler, CompilerConfig, Symbol, SymbolRegistry};
-pub use crate::error::{CompileError, ParseCpuFeatureError, WasmError, WasmResult};
+pub use crate::compiler::{
+    detect_features, validate, Compiler, CompilerConfig, Symbol, SymbolRegistry,
+};
+pub use crate::error::{
+    CompileError, MiddlewareError, ParseCpuFeatureError, TargetError, TypeError, WasmError,
+    WasmResult,
+};
 pub use crate::function::{
-    Compilation, CompiledFunction, CompiledFunctionFrameInfo, CustomSections, Dwarf, FunctionBody,
-    Functions,
+    Compilation, CompilationStats, CompiledFunction, CompiledFunctionFrameInfo, CustomSections,
+    Dwarf, FunctionBody, Functions,
 };
-pub use crate::jump_table::{JumpTable, JumpTableOffsets};
-pub use crate::module::CompileModuleInfo;
+pub use crate::jump_table::{JumpTable, JumpTableData, JumpTableEntries, JumpTableOffsets};
+pub use crate::module::{CompileModuleInfo, CompiledModule};
+pub use crate::progress::CompilationProgress;
 pub use crate::relocation::{Relocation, RelocationKind, RelocationTarget, Relocations};
 pub use crate::section::{CustomSection, CustomSectionProtection, SectionBody, SectionIndex};
 pub use crate::sourceloc::SourceLoc;
 pub use crate::target::{
     Architecture, BinaryFormat, CallingConvention, CpuFeature, Endianness, OperatingSystem,
-    PointerWidth, Target, Triple,
+    PointerWidth, Target, TargetBuilder, Triple,
 };
 #[cfg(feature = "translator")]
 pub use crate::translator::{
-    to_wasm_error, translate_module, wptype_to_type, FunctionBodyData, FunctionMiddleware,
-    FunctionMiddlewareGenerator, GenerateMiddlewareChain, MiddlewareBinaryReader,
-    MiddlewareReaderState, ModuleEnvironment, ModuleInfoTranslation, ModuleTranslationState,
+    to_wasm_error, translate_module, wptype_to_type, CallGraph, FunctionBodyData,
+    FunctionMiddleware, FunctionMiddlewareGenerator, GenerateMiddlewareChain,
+    MiddlewareBinaryReader, MiddlewareReaderState, ModuleEnvironment, ModuleInfoTranslation,
+    ModuleTranslationState, OwnedFunctionBodyData, StreamingTranslator,
 };
 pub use crate::trap::TrapInformation;
 pub use crate::unwind::CompiledFunctionUnwindInfo;
 
-pub use wasmer_types::Features;
+pub use wasmer_types::{DebugInfoPolicy, Features};
 
 #[cfg(feature = "translator")]
 /// wasmparser is exported as a module to slim compiler dependencies
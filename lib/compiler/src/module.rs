@@ -1,8 +1,10 @@
+use crate::function::Compilation;
+use crate::lib::std::boxed::Box;
 use crate::lib::std::sync::Arc;
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
 use wasmer_types::entity::PrimaryMap;
-use wasmer_types::{Features, MemoryIndex, TableIndex};
+use wasmer_types::{Features, MemoryIndex, OwnedDataInitializer, TableIndex};
 use wasmer_vm::{MemoryStyle, ModuleInfo, TableStyle};
 
 /// The required info for compiling a module.
@@ -25,3 +27,86 @@ pub struct CompileModuleInfo {
     /// The table plans used for compiling.
     pub table_styles: PrimaryMap<TableIndex, TableStyle>,
 }
+
+/// A WebAssembly module compiled by a [`Compiler`], ready to be loaded
+/// by an engine.
+///
+/// This is the contract between the compilers and the engines: it holds
+/// everything an engine needs to load and instantiate the module (the
+/// module information, the features and styles it was compiled with, the
+/// function bodies with their relocations and frame information, the
+/// trampolines, the custom sections and the data initializers), so
+/// engines don't need to access the translator.
+///
+/// # Stability
+///
+/// New fields may be added to this struct, and the serialized form is
+/// only guaranteed to be readable by the same version of this crate.
+///
+/// # Example
+///
+/// An engine only needs the compiled module to load the code. For
+/// example, listing the size of each function body:
+///
+/// ```
+/// use std::sync::Arc;
+/// use wasmer_compiler::{
+///     Compilation, CompileModuleInfo, CompiledFunction, CompiledFunctionFrameInfo,
+///     CompiledModule, Features, FunctionBody,
+/// };
+/// use wasmer_types::entity::{PrimaryMap, SecondaryMap};
+/// use wasmer_vm::ModuleInfo;
+///
+/// /// A fake engine, listing the size of the function bodies.
+/// fn function_body_sizes(module: &CompiledModule) -> Vec<usize> {
+///     module
+///         .compilation
+///         .get_function_bodies()
+///         .values()
+///         .map(|function_body| function_body.body.len())
+///         .collect()
+/// }
+///
+/// let mut functions = PrimaryMap::new();
+/// for body in &[vec![0xc3], vec![0x90, 0xc3]] {
+///     functions.push(CompiledFunction {
+///         body: FunctionBody {
+///             body: body.clone(),
+///             unwind_info: None,
+///         },
+///         relocations: vec![],
+///         jt_offsets: SecondaryMap::new(),
+///         jt_entries: SecondaryMap::new(),
+///         frame_info: CompiledFunctionFrameInfo::default(),
+///     });
+/// }
+/// let module = CompiledModule {
+///     compile_info: CompileModuleInfo {
+///         features: Features::default(),
+///         module: Arc::new(ModuleInfo::new()),
+///         memory_styles: PrimaryMap::new(),
+///         table_styles: PrimaryMap::new(),
+///     },
+///     compilation: Compilation::new(
+///         functions,
+///         PrimaryMap::new(),
+///         PrimaryMap::new(),
+///         PrimaryMap::new(),
+///         None,
+///     ),
+///     data_initializers: Box::new([]),
+/// };
+/// assert_eq!(function_body_sizes(&module), vec![1, 2]);
+/// ```
+///
+/// [`Compiler`]: crate::Compiler
+#[derive(Debug)]
+#[cfg_attr(feature = "enable-serde", derive(Deserialize, Serialize))]
+pub struct CompiledModule {
+    /// The information the module was compiled with.
+    pub compile_info: CompileModuleInfo,
+    /// The compiled functions, trampolines and custom sections.
+    pub compilation: Compilation,
+    /// The data initializers of the module.
+    pub data_initializers: Box<[OwnedDataInitializer]>,
+}
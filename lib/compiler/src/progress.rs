@@ -0,0 +1,66 @@
+//! Progress reporting and cancellation of a module compilation.
+
+use crate::error::CompileError;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// The progress of a module compilation, shared between the compiler and
+/// whoever is waiting for it.
+///
+/// The compiler counts the functions it has compiled, and stops compiling
+/// new functions once the compilation is cancelled. Functions that are
+/// being compiled when the compilation is cancelled still finish.
+#[derive(Debug, Default)]
+pub struct CompilationProgress {
+    functions_done: AtomicUsize,
+    functions_total: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl CompilationProgress {
+    /// Creates the progress of a compilation that hasn't started yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of functions compiled so far, and the number of
+    /// functions to compile.
+    ///
+    /// The total is `0` until the module is translated.
+    pub fn progress(&self) -> (usize, usize) {
+        (
+            self.functions_done.load(Ordering::SeqCst),
+            self.functions_total.load(Ordering::SeqCst),
+        )
+    }
+
+    /// Cancels the compilation, which then fails with
+    /// [`CompileError::Cancelled`].
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the compilation has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns [`CompileError::Cancelled`] if the compilation has been
+    /// cancelled, to be checked before compiling each function.
+    pub fn check_cancelled(&self) -> Result<(), CompileError> {
+        if self.is_cancelled() {
+            Err(CompileError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets the number of functions to compile.
+    pub fn set_functions_total(&self, total: usize) {
+        self.functions_total.store(total, Ordering::SeqCst);
+    }
+
+    /// Records that `count` more functions have been compiled.
+    pub fn functions_compiled(&self, count: usize) {
+        self.functions_done.fetch_add(count, Ordering::SeqCst);
+    }
+}
@@ -37,15 +37,15 @@ pub enum RelocationKind {
     X86CallPCRel4,
     /// x86 call to PLT-relative 4-byte
     X86CallPLTRel4,
+    /// Arm64 call target, encoded in the 26-bit immediate of a `b` or `bl`
+    /// instruction as the number of instructions to the target.
+    Arm64Call,
     // /// x86 GOT PC-relative 4-byte
     // X86GOTPCRel4,
 
     // /// Arm32 call target
     // Arm32Call,
 
-    // /// Arm64 call target
-    // Arm64Call,
-
     // /// RISC-V call target
     // RiscvCall,
 
@@ -68,8 +68,9 @@ impl fmt::Display for RelocationKind {
             Self::X86PCRelRodata4 => write!(f, "PCRelRodata4"),
             Self::X86CallPCRel4 => write!(f, "CallPCRel4"),
             Self::X86CallPLTRel4 => write!(f, "CallPLTRel4"),
+            Self::Arm64Call => write!(f, "Call"),
             // Self::X86GOTPCRel4 => write!(f, "GOTPCRel4"),
-            // Self::Arm32Call | Self::Arm64Call | Self::RiscvCall => write!(f, "Call"),
+            // Self::Arm32Call | Self::RiscvCall => write!(f, "Call"),
 
             // Self::ElfX86_64TlsGd => write!(f, "ElfX86_64TlsGd"),
             // Self::MachOX86_64Tlv => write!(f, "MachOX86_64Tlv"),
@@ -120,13 +121,21 @@ impl Relocation {
                     .unwrap();
                 (reloc_address, reloc_abs)
             }
+            RelocationKind::Abs4 => {
+                let reloc_address = start + self.offset as usize;
+                let reloc_addend = self.addend as isize;
+                let reloc_abs = (target_func_address as u32)
+                    .checked_add(reloc_addend as u32)
+                    .unwrap();
+                (reloc_address, reloc_abs as u64)
+            }
             RelocationKind::X86PCRel4 => {
                 let reloc_address = start + self.offset as usize;
                 let reloc_addend = self.addend as isize;
+                // The addend is usually negative, so the addition wraps.
                 let reloc_delta_u32 = (target_func_address as u32)
                     .wrapping_sub(reloc_address as u32)
-                    .checked_add(reloc_addend as u32)
-                    .unwrap();
+                    .wrapping_add(reloc_addend as u32);
                 (reloc_address, reloc_delta_u32 as u64)
             }
             RelocationKind::X86PCRel8 => {
@@ -146,6 +155,14 @@ impl Relocation {
                     .wrapping_add(reloc_addend as u32);
                 (reloc_address, reloc_delta_u32 as u64)
             }
+            RelocationKind::Arm64Call => {
+                let reloc_address = start + self.offset as usize;
+                let reloc_addend = self.addend as isize;
+                let reloc_delta = target_func_address
+                    .wrapping_sub(reloc_address as u64)
+                    .wrapping_add(reloc_addend as u64);
+                (reloc_address, reloc_delta)
+            }
             // RelocationKind::X86PCRelRodata4 => {
             //     (start, target_func_address)
             // }
@@ -1,5 +1,5 @@
 //! Target configuration
-use crate::error::ParseCpuFeatureError;
+use crate::error::{ParseCpuFeatureError, TargetError};
 use crate::lib::std::str::FromStr;
 use crate::lib::std::string::{String, ToString};
 use enumset::{EnumSet, EnumSetType};
@@ -7,6 +7,7 @@ pub use target_lexicon::{
     Architecture, BinaryFormat, CallingConvention, Endianness, OperatingSystem, PointerWidth,
     Triple,
 };
+use wasmer_types::{FunctionType, Type};
 
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 use raw_cpuid::CpuId;
@@ -41,6 +42,7 @@ pub enum CpuFeature {
     AVX512VL,
     LZCNT,
     // ARM features
+    NEON,
     // Risc-V features
 }
 
@@ -110,6 +112,35 @@ impl CpuFeature {
         // We default to an empty hash set
         EnumSet::new()
     }
+
+    /// Whether the feature is available on the given architecture.
+    pub fn is_available_on(self, architecture: Architecture) -> bool {
+        match self {
+            Self::SSE2
+            | Self::SSE3
+            | Self::SSSE3
+            | Self::SSE41
+            | Self::SSE42
+            | Self::POPCNT
+            | Self::AVX
+            | Self::BMI1
+            | Self::BMI2
+            | Self::AVX2
+            | Self::AVX512DQ
+            | Self::AVX512VL
+            | Self::LZCNT => match architecture {
+                Architecture::I386
+                | Architecture::I586
+                | Architecture::I686
+                | Architecture::X86_64 => true,
+                _ => false,
+            },
+            Self::NEON => match architecture {
+                Architecture::Arm(_) | Architecture::Aarch64(_) => true,
+                _ => false,
+            },
+        }
+    }
 }
 
 // This options should map exactly the GCC options indicated
@@ -136,6 +167,7 @@ impl FromStr for CpuFeature {
             "avx512dq" => Ok(Self::AVX512DQ),
             "avx512vl" => Ok(Self::AVX512VL),
             "lzcnt" => Ok(Self::LZCNT),
+            "neon" => Ok(Self::NEON),
             _ => Err(ParseCpuFeatureError::Missing(s.to_string())),
         }
     }
@@ -157,6 +189,7 @@ impl ToString for CpuFeature {
             Self::AVX512DQ => "avx512dq",
             Self::AVX512VL => "avx512vl",
             Self::LZCNT => "lzcnt",
+            Self::NEON => "neon",
         }
         .to_string()
     }
@@ -171,6 +204,14 @@ pub struct Target {
 }
 
 impl Target {
+    /// Creates a builder of a target for the given triple.
+    ///
+    /// Unlike `Target::new`, the builder checks that the CPU features
+    /// are available on the architecture of the triple.
+    pub fn builder(triple: Triple) -> TargetBuilder {
+        TargetBuilder::new(triple)
+    }
+
     /// Creates a new target given a triple
     pub fn new(triple: Triple, cpu_features: EnumSet<CpuFeature>) -> Self {
         Self {
@@ -188,6 +229,36 @@ impl Target {
     pub fn cpu_features(&self) -> &EnumSet<CpuFeature> {
         &self.cpu_features
     }
+
+    /// Whether the results of the functions of type `ty` are written to
+    /// a result area the caller passes a pointer to, rather than returned
+    /// in registers.
+    ///
+    /// A single result is always returned in a register. On 64-bit
+    /// targets, the results are laid out like the fields of a C struct,
+    /// which is returned in two 64-bit registers if it takes at most 16
+    /// bytes: up to four `i32`s and `f32`s, or two `i64`s and `f64`s, for
+    /// instance. Elsewhere, several results always need a result area.
+    pub fn result_area_required(&self, ty: &FunctionType) -> bool {
+        let results = ty.results();
+        if results.len() <= 1 {
+            return false;
+        }
+        if self.triple.pointer_width() != Ok(PointerWidth::U64) {
+            return true;
+        }
+        let mut size = 0;
+        for ty in results {
+            let result_size = match ty {
+                Type::I32 | Type::F32 => 4,
+                Type::I64 | Type::F64 | Type::ExternRef | Type::FuncRef => 8,
+                Type::V128 => 16,
+            };
+            // Each result is aligned to its size.
+            size = (size + result_size - 1) / result_size * result_size + result_size;
+        }
+        size > 16
+    }
 }
 
 /// The default for the Target will use the HOST as the triple
@@ -199,3 +270,132 @@ impl Default for Target {
         }
     }
 }
+
+/// The builder of a [`Target`], validating the CPU features against
+/// the architecture of the triple.
+#[derive(Clone, Debug)]
+pub struct TargetBuilder {
+    triple: Triple,
+    cpu_features: EnumSet<CpuFeature>,
+}
+
+impl TargetBuilder {
+    /// Creates a new builder for the given triple, without any CPU
+    /// feature.
+    pub fn new(triple: Triple) -> Self {
+        Self {
+            triple,
+            cpu_features: CpuFeature::set(),
+        }
+    }
+
+    /// Adds a CPU feature to the target.
+    pub fn cpu_feature(mut self, cpu_feature: CpuFeature) -> Self {
+        self.cpu_features |= cpu_feature;
+        self
+    }
+
+    /// Adds a set of CPU features to the target.
+    pub fn cpu_features(mut self, cpu_features: EnumSet<CpuFeature>) -> Self {
+        self.cpu_features |= cpu_features;
+        self
+    }
+
+    /// Builds the target, failing if a CPU feature is not available
+    /// on the architecture of the triple.
+    pub fn build(self) -> Result<Target, TargetError> {
+        let architecture = self.triple.architecture;
+        if let Some(feature) = self
+            .cpu_features
+            .iter()
+            .find(|feature| !feature.is_available_on(architecture))
+        {
+            return Err(TargetError::UnsupportedCpuFeature {
+                feature,
+                architecture,
+            });
+        }
+        Ok(Target::new(self.triple, self.cpu_features))
+    }
+}
+
+/// The default for the TargetBuilder will use the HOST as the triple
+/// and its CPU features
+impl Default for TargetBuilder {
+    fn default() -> Self {
+        Self {
+            triple: Triple::host(),
+            cpu_features: CpuFeature::for_host(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_x86_64_with_avx() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let target = TargetBuilder::new(triple.clone())
+            .cpu_feature(CpuFeature::SSE2)
+            .cpu_feature(CpuFeature::AVX)
+            .build()
+            .unwrap();
+        assert_eq!(target.triple(), &triple);
+        assert_eq!(*target.cpu_features(), CpuFeature::SSE2 | CpuFeature::AVX);
+    }
+
+    #[test]
+    fn build_aarch64_with_sse() {
+        let triple = Triple::from_str("aarch64-unknown-linux-gnu").unwrap();
+        let error = Target::builder(triple.clone())
+            .cpu_feature(CpuFeature::NEON)
+            .cpu_feature(CpuFeature::SSE2)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            TargetError::UnsupportedCpuFeature {
+                feature: CpuFeature::SSE2,
+                architecture: triple.architecture,
+            }
+        );
+    }
+
+    #[test]
+    fn build_x86_64_with_neon() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        assert!(TargetBuilder::new(triple)
+            .cpu_feature(CpuFeature::NEON)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn result_area_required_on_x86_64() {
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let target = Target::new(triple, EnumSet::empty());
+        let required = |results: &[Type]| {
+            target.result_area_required(&FunctionType::new(vec![], results.to_vec()))
+        };
+
+        // The results fitting in `rax` and `rdx`, or `xmm0` and `xmm1`.
+        assert!(!required(&[]));
+        assert!(!required(&[Type::V128]));
+        assert!(!required(&[Type::I64, Type::F64]));
+        assert!(!required(&[Type::I32, Type::I32, Type::I64]));
+        assert!(!required(&[Type::F32, Type::F32, Type::F32, Type::F32]));
+
+        assert!(required(&[Type::I64, Type::I64, Type::I32]));
+        assert!(required(&[Type::I32, Type::I64, Type::I32]));
+        assert!(required(&[Type::I32; 5]));
+        assert!(required(&[Type::V128, Type::I32]));
+    }
+
+    #[test]
+    fn build_host() {
+        let target = TargetBuilder::default().build().unwrap();
+        assert_eq!(target, Target::default());
+    }
+}
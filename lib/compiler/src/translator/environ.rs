@@ -1,34 +1,95 @@
 // This file contains code from external sources.
 // Attributions: https://github.com/wasmerio/wasmer/blob/master/ATTRIBUTIONS.md
 
+use super::error::to_wasm_error;
+use super::leb128::write_padded_leb128;
 use super::module::translate_module;
+use super::sections::wptype_to_type;
 use super::state::ModuleTranslationState;
-use crate::lib::std::borrow::ToOwned;
+use super::streaming::StreamingTranslator;
+use crate::lib::std::borrow::{Cow, ToOwned};
+use crate::lib::std::collections::{HashMap, HashSet};
 use crate::lib::std::string::ToString;
 use crate::lib::std::{boxed::Box, string::String, vec::Vec};
 use crate::{WasmError, WasmResult};
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
 use std::convert::{TryFrom, TryInto};
+use std::hash::BuildHasher;
+use std::mem;
 use std::sync::Arc;
-use wasmer_types::entity::PrimaryMap;
+use wasmer_types::entity::packed_option::ReservedValue;
+use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::FunctionType;
 use wasmer_types::{
-    CustomSectionIndex, DataIndex, DataInitializer, DataInitializerLocation, ElemIndex,
-    ExportIndex, FunctionIndex, GlobalIndex, GlobalInit, GlobalType, ImportIndex,
-    LocalFunctionIndex, MemoryIndex, MemoryType, SignatureIndex, TableIndex, TableInitializer,
-    TableType,
+    ConstExpr, CustomSectionIndex, DataIndex, DataInitializer, DataInitializerLocation,
+    DebugInfoPolicy, ElemIndex, ExportIndex, FunctionIndex, GlobalIndex, GlobalInit, GlobalType,
+    ImportIndex, LocalFunctionIndex, MemoryIndex, MemoryType, SignatureIndex, TableIndex,
+    TableInitializer, TableType, Type,
 };
 use wasmer_vm::ModuleInfo;
+use wasmparser::{BinaryReader, Operator};
 
 /// Contains function data: bytecode and its offset in the module.
 #[derive(Hash)]
 pub struct FunctionBodyData<'a> {
     /// Function body bytecode.
-    pub data: &'a [u8],
+    ///
+    /// It's borrowed from the module, unless it was rewritten after the
+    /// translation or the module was translated as it was received.
+    pub data: Cow<'a, [u8]>,
 
     /// Body offset relative to the module file.
     pub module_offset: usize,
 }
 
+/// As `FunctionBodyData` but owning the bytecode rather than borrowing it,
+/// so that it can outlive the module, or be serialized.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct OwnedFunctionBodyData {
+    /// Function body bytecode.
+    #[cfg_attr(feature = "enable-serde", serde(with = "serde_bytes"))]
+    pub data: Vec<u8>,
+
+    /// Body offset relative to the module file.
+    pub module_offset: usize,
+}
+
+impl OwnedFunctionBodyData {
+    /// Borrows the bytecode as a `FunctionBodyData`.
+    pub fn to_borrowed(&self) -> FunctionBodyData<'_> {
+        FunctionBodyData {
+            data: Cow::Borrowed(&self.data),
+            module_offset: self.module_offset,
+        }
+    }
+}
+
+impl From<&FunctionBodyData<'_>> for OwnedFunctionBodyData {
+    fn from(borrowed: &FunctionBodyData<'_>) -> Self {
+        Self {
+            data: borrowed.data.to_vec(),
+            module_offset: borrowed.module_offset,
+        }
+    }
+}
+
+/// The static call graph of a module, over its defined functions.
+///
+/// See [`ModuleInfoTranslation::call_graph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallGraph {
+    /// The functions each defined function calls with `call`, sorted.
+    pub direct: PrimaryMap<LocalFunctionIndex, Vec<FunctionIndex>>,
+
+    /// The functions each defined function may call with `call_indirect`,
+    /// sorted: the functions of the element segments and the referenced
+    /// functions that have the signature of one of its indirect calls.
+    pub indirect: PrimaryMap<LocalFunctionIndex, Vec<FunctionIndex>>,
+}
+
 /// The result of translating via `ModuleEnvironment`. Function bodies are not
 /// yet translated, and data initializers have not yet been copied out of the
 /// original buffer.
@@ -45,29 +106,411 @@ pub struct ModuleInfoTranslation<'data> {
 
     /// The decoded Wasm types for the module.
     pub module_translation: Option<ModuleTranslationState>,
+
+    /// The local declarations of each function body.
+    function_locals: PrimaryMap<LocalFunctionIndex, Vec<(u32, Type)>>,
+}
+
+impl<'data> ModuleInfoTranslation<'data> {
+    /// Returns the local declarations of the defined function `index`:
+    /// the number of locals of each declaration and their type, in order.
+    /// The parameters aren't included.
+    pub fn function_locals(&self, index: LocalFunctionIndex) -> Option<&[(u32, Type)]> {
+        self.function_locals.get(index).map(Vec::as_slice)
+    }
+
+    /// Returns the functions that can be called: the ones reachable through
+    /// calls and function references from the exported functions, the start
+    /// function, the functions of the element segments and the function
+    /// references of the global initializers.
+    ///
+    /// Imported functions are included when they are reachable.
+    pub fn reachable_functions(&self) -> WasmResult<HashSet<FunctionIndex>> {
+        let module = &self.module;
+        let mut pending: Vec<FunctionIndex> = module
+            .exports
+            .values()
+            .filter_map(|export| match export {
+                ExportIndex::Function(index) => Some(*index),
+                _ => None,
+            })
+            .chain(module.start_function)
+            .chain(
+                module
+                    .table_initializers
+                    .iter()
+                    .flat_map(|init| init.elements.iter().cloned()),
+            )
+            .chain(
+                module
+                    .passive_elements
+                    .values()
+                    .flat_map(|elements| elements.iter().cloned()),
+            )
+            .chain(
+                module
+                    .global_initializers
+                    .values()
+                    .filter_map(|init| match init {
+                        GlobalInit::RefFunc(index) => Some(*index),
+                        _ => None,
+                    }),
+            )
+            // The null elements of the element segments are reserved values.
+            .filter(|index| *index != FunctionIndex::reserved_value())
+            .collect();
+
+        let mut reachable = HashSet::new();
+        while let Some(index) = pending.pop() {
+            if !reachable.insert(index) {
+                continue;
+            }
+            let local_index = match module.local_func_index(index) {
+                Some(local_index) => local_index,
+                None => continue,
+            };
+            let body = &self.function_body_inputs[local_index];
+            let mut reader = BinaryReader::new_with_offset(&body.data, body.module_offset);
+            let mut locals_total = 0;
+            for _ in 0..reader.read_local_count().map_err(to_wasm_error)? {
+                reader
+                    .read_local_decl(&mut locals_total)
+                    .map_err(to_wasm_error)?;
+            }
+            while !reader.eof() {
+                match reader.read_operator().map_err(to_wasm_error)? {
+                    Operator::Call { function_index } | Operator::RefFunc { function_index } => {
+                        let callee = FunctionIndex::from_u32(function_index);
+                        if !reachable.contains(&callee) {
+                            pending.push(callee);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(reachable)
+    }
+
+    /// Returns the call graph of the defined functions.
+    ///
+    /// The candidates of the indirect calls are the functions that can be
+    /// stored in a table: the functions of the element segments and the
+    /// functions referenced by `ref.func` or by the global initializers.
+    ///
+    /// Fails if the module hasn't been translated entirely.
+    pub fn call_graph(&self) -> WasmResult<CallGraph> {
+        let module = &self.module;
+        let state = self.module_translation.as_ref().ok_or_else(|| {
+            WasmError::Generic("the call graph needs a translated module".to_string())
+        })?;
+        let mut direct = PrimaryMap::with_capacity(self.function_body_inputs.len());
+        let mut indirect_signatures = PrimaryMap::with_capacity(self.function_body_inputs.len());
+        let mut candidates: Vec<FunctionIndex> = module
+            .table_initializers
+            .iter()
+            .flat_map(|init| init.elements.iter().cloned())
+            .chain(
+                module
+                    .passive_elements
+                    .values()
+                    .flat_map(|elements| elements.iter().cloned()),
+            )
+            .chain(
+                module
+                    .global_initializers
+                    .values()
+                    .filter_map(|init| match init {
+                        GlobalInit::RefFunc(index) => Some(*index),
+                        _ => None,
+                    }),
+            )
+            .collect();
+
+        for body in self.function_body_inputs.values() {
+            let mut calls = Vec::new();
+            let mut signatures = Vec::new();
+            let mut reader = BinaryReader::new_with_offset(&body.data, body.module_offset);
+            let mut locals_total = 0;
+            for _ in 0..reader.read_local_count().map_err(to_wasm_error)? {
+                reader
+                    .read_local_decl(&mut locals_total)
+                    .map_err(to_wasm_error)?;
+            }
+            while !reader.eof() {
+                match reader.read_operator().map_err(to_wasm_error)? {
+                    Operator::Call { function_index } => {
+                        calls.push(FunctionIndex::from_u32(function_index))
+                    }
+                    Operator::CallIndirect { index, .. } => {
+                        signatures.push(state.signature_index(index))
+                    }
+                    Operator::RefFunc { function_index } => {
+                        candidates.push(FunctionIndex::from_u32(function_index))
+                    }
+                    _ => {}
+                }
+            }
+            calls.sort();
+            calls.dedup();
+            direct.push(calls);
+            indirect_signatures.push(signatures);
+        }
+
+        // The null elements of the element segments are reserved values.
+        candidates.retain(|index| *index != FunctionIndex::reserved_value());
+        candidates.sort();
+        candidates.dedup();
+        let indirect = indirect_signatures
+            .values()
+            .map(|signatures| {
+                candidates
+                    .iter()
+                    .filter(|index| signatures.contains(&module.functions[**index]))
+                    .cloned()
+                    .collect()
+            })
+            .collect();
+        Ok(CallGraph { direct, indirect })
+    }
+
+    /// Returns the defined functions that aren't reachable (see
+    /// `reachable_functions`), in order.
+    pub fn dead_functions(&self) -> WasmResult<Vec<LocalFunctionIndex>> {
+        let reachable = self.reachable_functions()?;
+        Ok(self
+            .function_body_inputs
+            .keys()
+            .filter(|index| !reachable.contains(&self.module.func_index(*index)))
+            .collect())
+    }
+
+    /// Removes the dead functions (see `dead_functions`), and renumbers the
+    /// remaining ones.
+    ///
+    /// The exports, the start function, the element segments, the global
+    /// initializers, the function and local names and the calls and function
+    /// references in the function bodies are updated to the new indices.
+    /// The rewritten function bodies keep their size, so that the source
+    /// locations in them stay valid.
+    pub fn prune_dead_functions(&mut self) -> WasmResult<()> {
+        let dead = self.dead_functions()?;
+        if dead.is_empty() {
+            return Ok(());
+        }
+
+        // The new index of every function, or `None` for the dead ones.
+        let module = &mut self.module;
+        let mut renumbering: PrimaryMap<FunctionIndex, Option<FunctionIndex>> =
+            PrimaryMap::with_capacity(module.functions.len());
+        let mut functions = PrimaryMap::with_capacity(module.functions.len() - dead.len());
+        let mut dead = dead.into_iter().peekable();
+        for (index, signature) in module.functions.iter() {
+            let local_index = module.local_func_index(index);
+            if local_index.is_some() && dead.peek() == local_index.as_ref() {
+                dead.next();
+                renumbering.push(None);
+            } else {
+                renumbering.push(Some(functions.push(*signature)));
+            }
+        }
+        let renumber = |index: FunctionIndex| {
+            renumbering[index].expect("reachable functions only refer to reachable functions")
+        };
+
+        module.functions = functions;
+        for export in module.exports.values_mut() {
+            if let ExportIndex::Function(index) = export {
+                *index = renumber(*index);
+            }
+        }
+        module.start_function = module.start_function.map(renumber);
+        // The null elements of the element segments are reserved values,
+        // and stay unchanged.
+        let renumber_element = |index: &mut FunctionIndex| {
+            if *index != FunctionIndex::reserved_value() {
+                *index = renumber(*index);
+            }
+        };
+        for init in module.table_initializers.iter_mut() {
+            init.elements.iter_mut().for_each(renumber_element);
+        }
+        for elements in module.passive_elements.values_mut() {
+            elements.iter_mut().for_each(renumber_element);
+        }
+        for init in module.global_initializers.values_mut() {
+            if let GlobalInit::RefFunc(index) = init {
+                *index = renumber(*index);
+            }
+        }
+        module.function_names = module
+            .function_names
+            .drain()
+            .filter_map(|(index, name)| Some((renumbering[index]?, name)))
+            .collect();
+        module.local_names = module
+            .local_names
+            .drain()
+            .filter_map(|((index, local), name)| Some(((renumbering[index]?, local), name)))
+            .collect();
+
+        let num_imported_functions = module.num_imported_functions;
+        let mut function_body_inputs = PrimaryMap::with_capacity(module.functions.len());
+        let mut function_locals = PrimaryMap::with_capacity(module.functions.len());
+        for (local_index, body) in self.function_body_inputs.iter_mut() {
+            let index = FunctionIndex::new(num_imported_functions + local_index.index());
+            if renumbering[index].is_some() {
+                renumber_function_body(body, &renumber)?;
+                function_body_inputs.push(FunctionBodyData {
+                    data: mem::replace(&mut body.data, Cow::Borrowed(&[])),
+                    module_offset: body.module_offset,
+                });
+                function_locals.push(mem::take(&mut self.function_locals[local_index]));
+            }
+        }
+        self.function_body_inputs = function_body_inputs;
+        self.function_locals = function_locals;
+        Ok(())
+    }
+}
+
+/// Rewrites the function indices of the calls and function references in
+/// `body` with `renumber`.
+///
+/// The new indices are encoded with as many bytes as the old ones, padding
+/// the LEB128 encoding if needed, so that the size of the body and the
+/// offsets of its instructions don't change.
+fn renumber_function_body(
+    body: &mut FunctionBodyData<'_>,
+    renumber: &dyn Fn(FunctionIndex) -> FunctionIndex,
+) -> WasmResult<()> {
+    let mut rewrites = Vec::new();
+    {
+        let mut reader = BinaryReader::new_with_offset(&body.data, body.module_offset);
+        let mut locals_total = 0;
+        for _ in 0..reader.read_local_count().map_err(to_wasm_error)? {
+            reader
+                .read_local_decl(&mut locals_total)
+                .map_err(to_wasm_error)?;
+        }
+        while !reader.eof() {
+            // The index follows the one-byte opcode of both instructions.
+            let start = reader.current_position() + 1;
+            match reader.read_operator().map_err(to_wasm_error)? {
+                Operator::Call { function_index } | Operator::RefFunc { function_index } => {
+                    let old = FunctionIndex::from_u32(function_index);
+                    let new = renumber(old);
+                    if new != old {
+                        rewrites.push((start..reader.current_position(), new.as_u32()));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if !rewrites.is_empty() {
+        let data = body.data.to_mut();
+        for (range, value) in rewrites {
+            write_padded_leb128(&mut data[range], value);
+        }
+    }
+    Ok(())
 }
 
 /// Object containing the standalone environment information.
-pub struct ModuleEnvironment<'data> {
+///
+/// `S` hashes the signatures to find the identical ones. The default
+/// hasher resists collision attacks; a faster one can be used with
+/// [`ModuleEnvironment::with_hasher`] for trusted modules.
+pub struct ModuleEnvironment<'data, S = RandomState> {
     /// The result to be filled in.
     pub result: ModuleInfoTranslation<'data>,
     imports: u32,
+    /// The number of bytes of the module from the start of the section
+    /// being translated.
+    bytes_remaining: usize,
+    /// The maximum number of results of a function, if any.
+    max_function_results: Option<usize>,
+    /// The index of each distinct signature declared so far.
+    signature_indices: HashMap<FunctionType, SignatureIndex, S>,
+    /// Whether the threads proposal is enabled, which allows shared
+    /// memories.
+    threads: bool,
 }
 
 impl<'data> ModuleEnvironment<'data> {
     /// Allocates the environment data structures.
     pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<S: BuildHasher> ModuleEnvironment<'static, S> {
+    /// Translate a wasm module received in chunks, which are pushed to the
+    /// returned translator. This produces the same `ModuleInfoTranslation`
+    /// as translating the whole module at once, except that it owns the
+    /// function bodies and the data segments.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer_compiler::{ModuleEnvironment, WasmResult};
+    /// # fn main() -> WasmResult<()> {
+    /// let mut translator = ModuleEnvironment::new().translate_streaming();
+    /// for chunk in [&b"\0asm"[..], &b"\x01\0\0\0"[..]].iter() {
+    ///     translator.push(chunk)?;
+    /// }
+    /// let translation = translator.finish()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn translate_streaming(self) -> StreamingTranslator<S> {
+        StreamingTranslator::new(self)
+    }
+}
+
+impl<'data, S: BuildHasher> ModuleEnvironment<'data, S> {
+    /// Allocates the environment data structures, hashing the signatures
+    /// with `hash_builder`.
+    pub fn with_hasher(hash_builder: S) -> Self {
         Self {
             result: ModuleInfoTranslation {
                 module: ModuleInfo::new(),
                 function_body_inputs: PrimaryMap::new(),
                 data_initializers: Vec::new(),
                 module_translation: None,
+                function_locals: PrimaryMap::new(),
             },
             imports: 0,
+            bytes_remaining: usize::MAX,
+            max_function_results: None,
+            signature_indices: HashMap::with_hasher(hash_builder),
+            threads: false,
         }
     }
 
+    /// Rejects the functions returning more than `limit` results, if
+    /// any, with [`WasmError::TooManyResults`].
+    pub fn with_max_function_results(mut self, limit: Option<usize>) -> Self {
+        self.max_function_results = limit;
+        self
+    }
+
+    /// Accepts the shared memories of the threads proposal, which are
+    /// rejected with [`WasmError::Unsupported`] otherwise.
+    pub fn with_threads(mut self, enable: bool) -> Self {
+        self.threads = enable;
+        self
+    }
+
+    /// Keeps the debug information of the module allowed by `policy`,
+    /// which is recorded in the translated module.
+    pub fn with_debug_info_policy(mut self, policy: DebugInfoPolicy) -> Self {
+        self.result.module.debug_info_policy = policy;
+        self
+    }
+
     /// Translate a wasm module using this environment. This consumes the
     /// `ModuleEnvironment` and produces a `ModuleInfoTranslation`.
     pub fn translate(mut self, data: &'data [u8]) -> WasmResult<ModuleInfoTranslation<'data>> {
@@ -77,6 +520,21 @@ impl<'data> ModuleEnvironment<'data> {
         Ok(self.result)
     }
 
+    pub(crate) fn set_bytes_remaining(&mut self, bytes_remaining: usize) {
+        self.bytes_remaining = bytes_remaining;
+    }
+
+    /// Returns how many of the `num` entries declared by a section to
+    /// reserve memory for.
+    ///
+    /// The counts come from the module, so we don't trust them: every entry
+    /// takes at least a byte, so the rest of the module can't define more
+    /// entries than it has bytes. Past that, the entries are allocated as
+    /// they are defined.
+    pub(crate) fn reservation(&self, num: u32) -> usize {
+        usize::try_from(num).unwrap().min(self.bytes_remaining)
+    }
+
     pub(crate) fn declare_export(&mut self, export: ExportIndex, name: &str) -> WasmResult<()> {
         self.result
             .module
@@ -99,17 +557,38 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_signatures(&mut self, num: u32) -> WasmResult<()> {
-        self.result
-            .module
-            .signatures
-            .reserve_exact(usize::try_from(num).unwrap());
+        let num = self.reservation(num);
+        self.result.module.signatures.reserve_exact(num);
+        Ok(())
+    }
+
+    /// Checks that the function about to be declared with the signature
+    /// `sig_index` doesn't return too many results.
+    fn check_function_results(&self, sig_index: SignatureIndex) -> WasmResult<()> {
+        let limit = match self.max_function_results {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let count = self.result.module.signatures[sig_index].results().len();
+        if count > limit {
+            return Err(WasmError::TooManyResults {
+                func: FunctionIndex::from_u32(self.result.module.functions.len() as _),
+                count,
+                limit,
+            });
+        }
         Ok(())
     }
 
-    pub(crate) fn declare_signature(&mut self, sig: FunctionType) -> WasmResult<()> {
-        // TODO: Deduplicate signatures.
-        self.result.module.signatures.push(sig);
-        Ok(())
+    /// Declares the signature `sig`, returning the index of the identical
+    /// signature declared before, if any.
+    pub(crate) fn declare_signature(&mut self, sig: FunctionType) -> WasmResult<SignatureIndex> {
+        if let Some(&sig_index) = self.signature_indices.get(&sig) {
+            return Ok(sig_index);
+        }
+        let sig_index = self.result.module.signatures.push(sig.clone());
+        self.signature_indices.insert(sig, sig_index);
+        Ok(sig_index)
     }
 
     pub(crate) fn declare_func_import(
@@ -123,6 +602,7 @@ impl<'data> ModuleEnvironment<'data> {
             self.result.module.num_imported_functions,
             "Imported functions must be declared first"
         );
+        self.check_function_results(sig_index)?;
         self.declare_import(
             ImportIndex::Function(FunctionIndex::from_u32(
                 self.result.module.num_imported_functions as _,
@@ -166,6 +646,7 @@ impl<'data> ModuleEnvironment<'data> {
         module: &str,
         field: &str,
     ) -> WasmResult<()> {
+        self.check_shared_memory(&memory)?;
         debug_assert_eq!(
             self.result.module.memories.len(),
             self.result.module.num_imported_memories,
@@ -213,26 +694,21 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_func_types(&mut self, num: u32) -> WasmResult<()> {
-        self.result
-            .module
-            .functions
-            .reserve_exact(usize::try_from(num).unwrap());
-        self.result
-            .function_body_inputs
-            .reserve_exact(usize::try_from(num).unwrap());
+        let num = self.reservation(num);
+        self.result.module.functions.reserve_exact(num);
+        self.result.function_body_inputs.reserve_exact(num);
         Ok(())
     }
 
     pub(crate) fn declare_func_type(&mut self, sig_index: SignatureIndex) -> WasmResult<()> {
+        self.check_function_results(sig_index)?;
         self.result.module.functions.push(sig_index);
         Ok(())
     }
 
     pub(crate) fn reserve_tables(&mut self, num: u32) -> WasmResult<()> {
-        self.result
-            .module
-            .tables
-            .reserve_exact(usize::try_from(num).unwrap());
+        let num = self.reservation(num);
+        self.result.module.tables.reserve_exact(num);
         Ok(())
     }
 
@@ -242,28 +718,39 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_memories(&mut self, num: u32) -> WasmResult<()> {
-        self.result
-            .module
-            .memories
-            .reserve_exact(usize::try_from(num).unwrap());
+        let num = self.reservation(num);
+        self.result.module.memories.reserve_exact(num);
         Ok(())
     }
 
     pub(crate) fn declare_memory(&mut self, memory: MemoryType) -> WasmResult<()> {
-        if memory.shared {
+        self.check_shared_memory(&memory)?;
+        self.result.module.memories.push(memory);
+        Ok(())
+    }
+
+    /// Shared memories can't be moved when they grow, so their maximum
+    /// size must be known to reserve their address space up front.
+    fn check_shared_memory(&self, memory: &MemoryType) -> WasmResult<()> {
+        if !memory.shared {
+            return Ok(());
+        }
+        if !self.threads {
             return Err(WasmError::Unsupported(
-                "shared memories are not supported yet".to_owned(),
+                "shared memories require the threads feature".to_owned(),
+            ));
+        }
+        if memory.maximum.is_none() {
+            return Err(WasmError::Unsupported(
+                "shared memories must declare a maximum size".to_owned(),
             ));
         }
-        self.result.module.memories.push(memory);
         Ok(())
     }
 
     pub(crate) fn reserve_globals(&mut self, num: u32) -> WasmResult<()> {
-        self.result
-            .module
-            .globals
-            .reserve_exact(usize::try_from(num).unwrap());
+        let num = self.reservation(num);
+        self.result.module.globals.reserve_exact(num);
         Ok(())
     }
 
@@ -278,10 +765,8 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_exports(&mut self, num: u32) -> WasmResult<()> {
-        self.result
-            .module
-            .exports
-            .reserve(usize::try_from(num).unwrap());
+        let num = self.reservation(num);
+        self.result.module.exports.reserve(num);
         Ok(())
     }
 
@@ -324,17 +809,15 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_table_initializers(&mut self, num: u32) -> WasmResult<()> {
-        self.result
-            .module
-            .table_initializers
-            .reserve_exact(usize::try_from(num).unwrap());
+        let num = self.reservation(num);
+        self.result.module.table_initializers.reserve_exact(num);
         Ok(())
     }
 
     pub(crate) fn declare_table_initializers(
         &mut self,
         table_index: TableIndex,
-        base: Option<GlobalIndex>,
+        base: Option<ConstExpr>,
         offset: usize,
         elements: Box<[FunctionIndex]>,
     ) -> WasmResult<()> {
@@ -371,9 +854,21 @@ impl<'data> ModuleEnvironment<'data> {
     pub(crate) fn define_function_body(
         &mut self,
         _module_translation: &ModuleTranslationState,
-        body_bytes: &'data [u8],
+        body_bytes: Cow<'data, [u8]>,
         body_offset: usize,
     ) -> WasmResult<()> {
+        let mut reader = BinaryReader::new_with_offset(&body_bytes, body_offset);
+        let mut locals_total = 0;
+        let count = reader.read_local_count().map_err(to_wasm_error)?;
+        // Every declaration takes at least two bytes of the body.
+        let mut locals = Vec::with_capacity((count as usize).min(body_bytes.len() / 2));
+        for _ in 0..count {
+            let (count, ty) = reader
+                .read_local_decl(&mut locals_total)
+                .map_err(to_wasm_error)?;
+            locals.push((count, wptype_to_type(ty)?));
+        }
+        self.result.function_locals.push(locals);
         self.result.function_body_inputs.push(FunctionBodyData {
             data: body_bytes,
             module_offset: body_offset,
@@ -382,18 +877,17 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_data_initializers(&mut self, num: u32) -> WasmResult<()> {
-        self.result
-            .data_initializers
-            .reserve_exact(usize::try_from(num).unwrap());
+        let num = self.reservation(num);
+        self.result.data_initializers.reserve_exact(num);
         Ok(())
     }
 
     pub(crate) fn declare_data_initialization(
         &mut self,
         memory_index: MemoryIndex,
-        base: Option<GlobalIndex>,
+        base: Option<ConstExpr>,
         offset: usize,
-        data: &'data [u8],
+        data: Cow<'data, [u8]>,
     ) -> WasmResult<()> {
         self.result.data_initializers.push(DataInitializer {
             location: DataInitializerLocation {
@@ -407,14 +901,15 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     pub(crate) fn reserve_passive_data(&mut self, count: u32) -> WasmResult<()> {
-        self.result.module.passive_data.reserve(count as usize);
+        let count = self.reservation(count);
+        self.result.module.passive_data.reserve(count);
         Ok(())
     }
 
     pub(crate) fn declare_passive_data(
         &mut self,
         data_index: DataIndex,
-        data: &'data [u8],
+        data: &[u8],
     ) -> WasmResult<()> {
         let old = self
             .result
@@ -428,7 +923,7 @@ impl<'data> ModuleEnvironment<'data> {
         Ok(())
     }
 
-    pub(crate) fn declare_module_name(&mut self, name: &'data str) -> WasmResult<()> {
+    pub(crate) fn declare_module_name(&mut self, name: &str) -> WasmResult<()> {
         self.result.module.name = Some(name.to_string());
         Ok(())
     }
@@ -436,7 +931,7 @@ impl<'data> ModuleEnvironment<'data> {
     pub(crate) fn declare_function_name(
         &mut self,
         func_index: FunctionIndex,
-        name: &'data str,
+        name: &str,
     ) -> WasmResult<()> {
         self.result
             .module
@@ -445,6 +940,19 @@ impl<'data> ModuleEnvironment<'data> {
         Ok(())
     }
 
+    pub(crate) fn declare_local_name(
+        &mut self,
+        func_index: FunctionIndex,
+        local_index: u32,
+        name: &str,
+    ) -> WasmResult<()> {
+        self.result
+            .module
+            .local_names
+            .insert((func_index, local_index), name.to_string());
+        Ok(())
+    }
+
     /// Provides the number of imports up front. By default this does nothing, but
     /// implementations can use this to preallocate memory if desired.
     pub(crate) fn reserve_imports(&mut self, _num: u32) -> WasmResult<()> {
@@ -457,7 +965,15 @@ impl<'data> ModuleEnvironment<'data> {
     }
 
     /// Indicates that a custom section has been found in the wasm file
-    pub(crate) fn custom_section(&mut self, name: &'data str, data: &'data [u8]) -> WasmResult<()> {
+    pub(crate) fn custom_section(&mut self, name: &str, data: &[u8]) -> WasmResult<()> {
+        if !self
+            .result
+            .module
+            .debug_info_policy
+            .keeps_custom_section(name)
+        {
+            return Ok(());
+        }
         let custom_section = CustomSectionIndex::from_u32(
             self.result
                 .module
@@ -469,7 +985,9 @@ impl<'data> ModuleEnvironment<'data> {
         self.result
             .module
             .custom_sections
-            .insert(String::from(name), custom_section);
+            .entry(String::from(name))
+            .or_default()
+            .push(custom_section);
         self.result
             .module
             .custom_sections_data
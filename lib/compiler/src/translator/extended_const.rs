@@ -0,0 +1,165 @@
+//! Validation support for the extended constant expressions proposal,
+//! which `wasmparser` doesn't validate yet.
+
+use super::error::to_wasm_error;
+use super::leb128::write_leb128;
+use crate::{WasmError, WasmResult};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::vec::Vec;
+use wasmparser::{
+    DataKind, ElementKind, ImportSectionEntryType, InitExpr, ModuleReader, Operator, SectionCode,
+    Type,
+};
+
+/// Replaces the extended constant expressions of a module by constants of
+/// the same type, so that `wasmparser` can validate the rest of the module.
+///
+/// The replaced expressions are type checked here. The offsets of the
+/// errors found when validating the lowered module may be off from the
+/// offsets in the original module.
+pub(crate) fn lower_extended_const_exprs(data: &[u8]) -> WasmResult<Cow<[u8]>> {
+    let mut reader = ModuleReader::new(data).map_err(to_wasm_error)?;
+    // The types of the globals declared so far.
+    let mut globals = Vec::new();
+    let mut lowered = Vec::new();
+    let mut copied = 0;
+    while !reader.eof() {
+        let section_start = reader.current_position();
+        let section = reader.read().map_err(to_wasm_error)?;
+        let mut replacements = Vec::new();
+        match section.code {
+            SectionCode::Import => {
+                for import in section.get_import_section_reader().map_err(to_wasm_error)? {
+                    if let ImportSectionEntryType::Global(ty) = import.map_err(to_wasm_error)?.ty {
+                        globals.push(ty.content_type);
+                    }
+                }
+            }
+            SectionCode::Global => {
+                for global in section.get_global_section_reader().map_err(to_wasm_error)? {
+                    let global = global.map_err(to_wasm_error)?;
+                    replacements.extend(lower_init_expr(
+                        &global.init_expr,
+                        &globals,
+                        global.ty.content_type,
+                    )?);
+                    globals.push(global.ty.content_type);
+                }
+            }
+            SectionCode::Element => {
+                for element in section
+                    .get_element_section_reader()
+                    .map_err(to_wasm_error)?
+                {
+                    if let ElementKind::Active { init_expr, .. } =
+                        element.map_err(to_wasm_error)?.kind
+                    {
+                        replacements.extend(lower_init_expr(&init_expr, &globals, Type::I32)?);
+                    }
+                }
+            }
+            SectionCode::Data => {
+                for data in section.get_data_section_reader().map_err(to_wasm_error)? {
+                    if let DataKind::Active { init_expr, .. } = data.map_err(to_wasm_error)?.kind {
+                        replacements.extend(lower_init_expr(&init_expr, &globals, Type::I32)?);
+                    }
+                }
+            }
+            _ => {}
+        }
+        if replacements.is_empty() {
+            continue;
+        }
+
+        let range = section.range();
+        let mut payload = Vec::with_capacity(range.end - range.start);
+        let mut position = range.start;
+        for (expr, placeholder) in replacements {
+            payload.extend_from_slice(&data[position..expr.start]);
+            payload.extend_from_slice(placeholder);
+            position = expr.end;
+        }
+        payload.extend_from_slice(&data[position..range.end]);
+
+        // Copy the module up to this section and its id, then the section
+        // with its new size.
+        lowered.extend_from_slice(&data[copied..=section_start]);
+        write_leb128(&mut lowered, payload.len());
+        lowered.extend_from_slice(&payload);
+        copied = range.end;
+    }
+    if copied == 0 {
+        return Ok(Cow::Borrowed(data));
+    }
+    lowered.extend_from_slice(&data[copied..]);
+    Ok(Cow::Owned(lowered))
+}
+
+/// Type checks an init expression of type `ty` with more than one
+/// operator, returning its range and the constant replacing it.
+///
+/// Init expressions of one operator are left to `wasmparser`.
+fn lower_init_expr(
+    init_expr: &InitExpr,
+    globals: &[Type],
+    ty: Type,
+) -> WasmResult<Option<(Range<usize>, &'static [u8])>> {
+    let mut reader = init_expr.get_binary_reader();
+    let start = reader.original_position();
+    let mut operators = Vec::new();
+    loop {
+        let offset = reader.original_position();
+        match reader.read_operator().map_err(to_wasm_error)? {
+            Operator::End => break,
+            operator => operators.push((operator, offset)),
+        }
+    }
+    if operators.len() <= 1 {
+        return Ok(None);
+    }
+
+    let mut stack = Vec::new();
+    for (operator, offset) in operators {
+        let error = |message: &str| WasmError::InvalidWebAssembly {
+            message: message.into(),
+            offset,
+        };
+        let (operand, result) = match operator {
+            Operator::I32Const { .. } => (None, Type::I32),
+            Operator::I64Const { .. } => (None, Type::I64),
+            Operator::GlobalGet { global_index } => {
+                let ty = globals
+                    .get(global_index as usize)
+                    .ok_or_else(|| error("unknown global: init_expr global index out of bounds"))?;
+                (None, *ty)
+            }
+            Operator::I32Add | Operator::I32Sub | Operator::I32Mul => (Some(Type::I32), Type::I32),
+            Operator::I64Add | Operator::I64Sub | Operator::I64Mul => (Some(Type::I64), Type::I64),
+            _ => {
+                return Err(error(
+                    "constant expression required: invalid init_expr operator",
+                ))
+            }
+        };
+        if let Some(operand) = operand {
+            let rhs = stack.pop();
+            let lhs = stack.pop();
+            if lhs != Some(operand) || rhs != Some(operand) {
+                return Err(error("type mismatch: invalid init_expr operand"));
+            }
+        }
+        stack.push(result);
+    }
+    let placeholder: &'static [u8] = match (stack.as_slice(), ty) {
+        ([Type::I32], Type::I32) => &[0x41, 0x00, 0x0b],
+        ([Type::I64], Type::I64) => &[0x42, 0x00, 0x0b],
+        _ => {
+            return Err(WasmError::InvalidWebAssembly {
+                message: "type mismatch: invalid init_expr type".into(),
+                offset: start,
+            })
+        }
+    };
+    Ok(Some((start..reader.original_position(), placeholder)))
+}
@@ -0,0 +1,29 @@
+//! Encoding of the unsigned LEB128 integers of the binary format.
+
+use crate::lib::std::vec::Vec;
+
+/// Appends `value` to `bytes` as an unsigned LEB128.
+pub(crate) fn write_leb128(bytes: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Writes `value` as an unsigned LEB128 filling all of `bytes`, padding
+/// the encoding with continuation bytes if needed.
+///
+/// `value` must fit in `bytes`.
+pub(crate) fn write_padded_leb128(bytes: &mut [u8], mut value: u32) {
+    let last = bytes.len() - 1;
+    for (position, byte) in bytes.iter_mut().enumerate() {
+        let continuation = if position == last { 0 } else { 0x80 };
+        *byte = (value & 0x7f) as u8 | continuation;
+        value >>= 7;
+    }
+}
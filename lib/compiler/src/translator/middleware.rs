@@ -1,6 +1,8 @@
 //! The middleware parses the function binary bytecodes and transform them
 //! with the chosen functions.
 
+use super::error::to_wasm_error;
+use crate::error::{MiddlewareError, WasmError, WasmResult};
 use smallvec::SmallVec;
 use std::collections::VecDeque;
 use std::fmt::Debug;
@@ -17,11 +19,14 @@ pub trait FunctionMiddlewareGenerator: Debug + Send + Sync {
 /// A function middleware specialized for a single function.
 pub trait FunctionMiddleware: Debug {
     /// Processes the given event, module info and sink.
+    ///
+    /// Returning an error rejects the function, and the compilation fails
+    /// with `CompileError::Middleware`.
     fn feed<'a>(
         &mut self,
         operator: Operator<'a>,
         state: &mut MiddlewareReaderState<'a>,
-    ) -> WpResult<()> {
+    ) -> Result<(), MiddlewareError> {
         state.push_operator(operator);
         Ok(())
     }
@@ -104,10 +109,10 @@ impl<'a> MiddlewareBinaryReader<'a> {
     }
 
     /// Reads the next available `Operator`.
-    pub fn read_operator(&mut self) -> WpResult<Operator<'a>> {
+    pub fn read_operator(&mut self) -> WasmResult<Operator<'a>> {
         // Try to fill the `self.pending_operations` buffer, until it is non-empty.
         while self.state.pending_operations.is_empty() {
-            let raw_op = self.state.inner.read_operator()?;
+            let raw_op = self.state.inner.read_operator().map_err(to_wasm_error)?;
 
             // Fill the initial raw operator into pending buffer.
             self.state.pending_operations.push_back(raw_op);
@@ -120,7 +125,9 @@ impl<'a> MiddlewareBinaryReader<'a> {
 
                 // ...and feed them into the current stage.
                 for pending_op in pending {
-                    stage.feed(pending_op, &mut self.state)?;
+                    stage
+                        .feed(pending_op, &mut self.state)
+                        .map_err(WasmError::Middleware)?;
                 }
             }
         }
@@ -6,15 +6,23 @@
 //!
 //! [cranelift-wasm]: https://crates.io/crates/cranelift-wasm/
 mod environ;
+mod extended_const;
+mod leb128;
 mod middleware;
 mod module;
 mod state;
+mod streaming;
+mod typecheck;
 #[macro_use]
 mod error;
 mod sections;
 
-pub use self::environ::{FunctionBodyData, ModuleEnvironment, ModuleInfoTranslation};
+pub use self::environ::{
+    CallGraph, FunctionBodyData, ModuleEnvironment, ModuleInfoTranslation, OwnedFunctionBodyData,
+};
 pub use self::error::to_wasm_error;
+pub(crate) use self::extended_const::lower_extended_const_exprs;
+pub(crate) use self::leb128::{write_leb128, write_padded_leb128};
 pub use self::middleware::{
     FunctionMiddleware, FunctionMiddlewareGenerator, GenerateMiddlewareChain,
     MiddlewareBinaryReader, MiddlewareReaderState,
@@ -22,3 +30,4 @@ pub use self::middleware::{
 pub use self::module::translate_module;
 pub use self::sections::wptype_to_type;
 pub use self::state::ModuleTranslationState;
+pub use self::streaming::StreamingTranslator;
@@ -11,86 +11,111 @@ use super::sections::{
     parse_name_section, parse_start_section, parse_table_section, parse_type_section,
 };
 use super::state::ModuleTranslationState;
+use crate::lib::std::borrow::Cow;
 use crate::WasmResult;
+use std::hash::BuildHasher;
 use wasmparser::{CustomSectionContent, ModuleReader, SectionContent};
 
 /// Translate a sequence of bytes forming a valid Wasm binary into a
 /// parsed ModuleInfo `ModuleTranslationState`.
-pub fn translate_module<'data>(
+pub fn translate_module<'data, S: BuildHasher>(
     data: &'data [u8],
-    environ: &mut ModuleEnvironment<'data>,
+    environ: &mut ModuleEnvironment<'data, S>,
 ) -> WasmResult<ModuleTranslationState> {
     let mut reader = ModuleReader::new(data).map_err(to_wasm_error)?;
     let mut module_translation_state = ModuleTranslationState::new();
 
     while !reader.eof() {
         let section = reader.read().map_err(to_wasm_error)?;
-        match section.content().map_err(to_wasm_error)? {
-            SectionContent::Type(types) => {
-                parse_type_section(types, &mut module_translation_state, environ)?;
-            }
+        environ.set_bytes_remaining(data.len() - section.range().start);
+        translate_section(
+            section.content().map_err(to_wasm_error)?,
+            &mut module_translation_state,
+            environ,
+            Cow::Borrowed,
+        )?;
+    }
 
-            SectionContent::Import(imports) => {
-                parse_import_section(imports, environ)?;
-            }
+    Ok(module_translation_state)
+}
 
-            SectionContent::Function(functions) => {
-                parse_function_section(functions, environ)?;
-            }
+/// Translate the `content` of a section of a module.
+///
+/// The function bodies and the data segments are kept with `keep`, which
+/// borrows them from the module or copies them.
+pub(crate) fn translate_section<'a, 'data, S: BuildHasher>(
+    content: SectionContent<'a>,
+    module_translation_state: &mut ModuleTranslationState,
+    environ: &mut ModuleEnvironment<'data, S>,
+    keep: fn(&'a [u8]) -> Cow<'data, [u8]>,
+) -> WasmResult<()> {
+    match content {
+        SectionContent::Type(types) => {
+            parse_type_section(types, module_translation_state, environ)?;
+        }
 
-            SectionContent::Table(tables) => {
-                parse_table_section(tables, environ)?;
-            }
+        SectionContent::Import(imports) => {
+            parse_import_section(imports, module_translation_state, environ)?;
+        }
 
-            SectionContent::Memory(memories) => {
-                parse_memory_section(memories, environ)?;
-            }
+        SectionContent::Function(functions) => {
+            parse_function_section(functions, module_translation_state, environ)?;
+        }
 
-            SectionContent::Global(globals) => {
-                parse_global_section(globals, environ)?;
-            }
+        SectionContent::Table(tables) => {
+            parse_table_section(tables, environ)?;
+        }
 
-            SectionContent::Export(exports) => {
-                parse_export_section(exports, environ)?;
-            }
+        SectionContent::Memory(memories) => {
+            parse_memory_section(memories, environ)?;
+        }
 
-            SectionContent::Start(start) => {
-                parse_start_section(start, environ)?;
-            }
+        SectionContent::Global(globals) => {
+            parse_global_section(globals, environ)?;
+        }
 
-            SectionContent::Element(elements) => {
-                parse_element_section(elements, environ)?;
-            }
+        SectionContent::Export(exports) => {
+            parse_export_section(exports, environ)?;
+        }
 
-            SectionContent::Code(code) => {
-                parse_code_section(code, &module_translation_state, environ)?;
-            }
+        SectionContent::Start(start) => {
+            parse_start_section(start, environ)?;
+        }
 
-            SectionContent::Data(data) => {
-                parse_data_section(data, environ)?;
-            }
+        SectionContent::Element(elements) => {
+            parse_element_section(elements, environ)?;
+        }
 
-            SectionContent::DataCount(count) => {
-                environ.reserve_passive_data(count)?;
-            }
+        SectionContent::Code(code) => {
+            parse_code_section(code, module_translation_state, environ, keep)?;
+        }
 
-            SectionContent::Custom {
-                name,
-                binary,
-                content,
-            } => match content {
-                Some(CustomSectionContent::Name(names)) => {
+        SectionContent::Data(data) => {
+            parse_data_section(data, environ, keep)?;
+        }
+
+        SectionContent::DataCount(count) => {
+            environ.reserve_passive_data(count)?;
+        }
+
+        SectionContent::Custom {
+            name,
+            binary,
+            content,
+        } => match content {
+            Some(CustomSectionContent::Name(names)) => {
+                if environ.result.module.debug_info_policy.keeps_names() {
                     parse_name_section(names, environ)?;
                 }
-                _ => {
-                    let mut reader = binary.clone();
-                    let len = reader.bytes_remaining();
-                    let payload = reader.read_bytes(len).map_err(to_wasm_error)?;
-                    environ.custom_section(name, payload)?;
-                }
-            },
-        }
+            }
+            _ => {
+                let mut reader = binary.clone();
+                let len = reader.bytes_remaining();
+                let payload = reader.read_bytes(len).map_err(to_wasm_error)?;
+                environ.custom_section(name, payload)?;
+            }
+        },
     }
 
-    Ok(module_translation_state)
+    Ok(())
 }
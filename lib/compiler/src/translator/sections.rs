@@ -15,23 +15,24 @@ use super::error::to_wasm_error;
 use super::state::ModuleTranslationState;
 use crate::wasm_unsupported;
 use crate::{WasmError, WasmResult};
-use core::convert::TryFrom;
+use std::borrow::Cow;
 use std::boxed::Box;
 use std::collections::HashMap;
+use std::hash::BuildHasher;
 use std::vec::Vec;
 use wasmer_types::entity::packed_option::ReservedValue;
 use wasmer_types::entity::EntityRef;
 use wasmer_types::{
-    DataIndex, ElemIndex, FunctionIndex, FunctionType, GlobalIndex, GlobalInit, GlobalType,
-    MemoryIndex, MemoryType, Pages, SignatureIndex, TableIndex, TableType, Type, V128,
+    ConstExpr, ConstOp, DataIndex, ElemIndex, FunctionIndex, FunctionType, GlobalIndex, GlobalInit,
+    GlobalType, MemoryIndex, MemoryType, Pages, TableIndex, TableType, Type, V128, WASM_MAX_PAGES,
 };
 use wasmparser::{
     self, CodeSectionReader, Data, DataKind, DataSectionReader, Element, ElementItem, ElementItems,
     ElementKind, ElementSectionReader, Export, ExportSectionReader, ExternalKind,
-    FuncType as WPFunctionType, FunctionSectionReader, GlobalSectionReader,
-    GlobalType as WPGlobalType, ImportSectionEntryType, ImportSectionReader, MemorySectionReader,
-    MemoryType as WPMemoryType, NameSectionReader, Naming, NamingReader, Operator,
-    TableSectionReader, TypeSectionReader,
+    FuncType as WPFunctionType, FunctionLocalReader, FunctionSectionReader, GlobalSectionReader,
+    GlobalType as WPGlobalType, ImportSectionEntryType, ImportSectionReader, InitExpr,
+    MemorySectionReader, MemoryType as WPMemoryType, NameSectionReader, Naming, NamingReader,
+    Operator, TableSectionReader, TypeSectionReader,
 };
 
 /// Helper function translating wasmparser types to Wasm Type.
@@ -52,10 +53,10 @@ pub fn wptype_to_type(ty: wasmparser::Type) -> WasmResult<Type> {
 }
 
 /// Parses the Type section of the wasm module.
-pub fn parse_type_section(
+pub fn parse_type_section<S: BuildHasher>(
     types: TypeSectionReader,
     module_translation_state: &mut ModuleTranslationState,
-    environ: &mut ModuleEnvironment,
+    environ: &mut ModuleEnvironment<'_, S>,
 ) -> WasmResult<()> {
     let count = types.get_count();
     environ.reserve_signatures(count)?;
@@ -77,28 +78,32 @@ pub fn parse_type_section(
             })
             .collect();
         let sig = FunctionType::new(sig_params, sig_returns);
-        environ.declare_signature(sig)?;
+        let sig_index = environ.declare_signature(sig)?;
+        module_translation_state.signature_indices.push(sig_index);
         module_translation_state.wasm_types.push((params, returns));
     }
     Ok(())
 }
 
 /// Parses the Import section of the wasm module.
-pub fn parse_import_section<'data>(
-    imports: ImportSectionReader<'data>,
-    environ: &mut ModuleEnvironment<'data>,
+pub fn parse_import_section<S: BuildHasher>(
+    imports: ImportSectionReader,
+    module_translation_state: &ModuleTranslationState,
+    environ: &mut ModuleEnvironment<'_, S>,
 ) -> WasmResult<()> {
+    let mut imports = imports;
     environ.reserve_imports(imports.get_count())?;
 
-    for entry in imports {
-        let import = entry.map_err(to_wasm_error)?;
+    for _ in 0..imports.get_count() {
+        let offset = imports.original_position();
+        let import = imports.read().map_err(to_wasm_error)?;
         let module_name = import.module;
         let field_name = import.field;
 
         match import.ty {
             ImportSectionEntryType::Function(sig) => {
                 environ.declare_func_import(
-                    SignatureIndex::from_u32(sig),
+                    module_translation_state.signature_index(sig),
                     module_name,
                     field_name,
                 )?;
@@ -107,15 +112,13 @@ pub fn parse_import_section<'data>(
                 limits: ref memlimits,
                 shared,
             }) => {
-                environ.declare_memory_import(
-                    MemoryType {
-                        minimum: Pages(memlimits.initial),
-                        maximum: memlimits.maximum.map(Pages),
-                        shared,
-                    },
-                    module_name,
-                    field_name,
-                )?;
+                let memory = MemoryType {
+                    minimum: Pages(memlimits.initial),
+                    maximum: memlimits.maximum.map(Pages),
+                    shared,
+                };
+                check_memory_limits(&memory, offset)?;
+                environ.declare_memory_import(memory, module_name, field_name)?;
             }
             ImportSectionEntryType::Global(ref ty) => {
                 environ.declare_global_import(
@@ -128,6 +131,7 @@ pub fn parse_import_section<'data>(
                 )?;
             }
             ImportSectionEntryType::Table(ref tab) => {
+                check_limits(tab.limits.initial, tab.limits.maximum, offset)?;
                 environ.declare_table_import(
                     TableType {
                         ty: wptype_to_type(tab.element_type).unwrap(),
@@ -146,9 +150,10 @@ pub fn parse_import_section<'data>(
 }
 
 /// Parses the Function section of the wasm module.
-pub fn parse_function_section(
+pub fn parse_function_section<S: BuildHasher>(
     functions: FunctionSectionReader,
-    environ: &mut ModuleEnvironment,
+    module_translation_state: &ModuleTranslationState,
+    environ: &mut ModuleEnvironment<'_, S>,
 ) -> WasmResult<()> {
     let num_functions = functions.get_count();
     if num_functions == std::u32::MAX {
@@ -160,21 +165,24 @@ pub fn parse_function_section(
 
     for entry in functions {
         let sigindex = entry.map_err(to_wasm_error)?;
-        environ.declare_func_type(SignatureIndex::from_u32(sigindex))?;
+        environ.declare_func_type(module_translation_state.signature_index(sigindex))?;
     }
 
     Ok(())
 }
 
 /// Parses the Table section of the wasm module.
-pub fn parse_table_section(
+pub fn parse_table_section<S: BuildHasher>(
     tables: TableSectionReader,
-    environ: &mut ModuleEnvironment,
+    environ: &mut ModuleEnvironment<'_, S>,
 ) -> WasmResult<()> {
+    let mut tables = tables;
     environ.reserve_tables(tables.get_count())?;
 
-    for entry in tables {
-        let table = entry.map_err(to_wasm_error)?;
+    for _ in 0..tables.get_count() {
+        let offset = tables.original_position();
+        let table = tables.read().map_err(to_wasm_error)?;
+        check_limits(table.limits.initial, table.limits.maximum, offset)?;
         environ.declare_table(TableType {
             ty: wptype_to_type(table.element_type).unwrap(),
             minimum: table.limits.initial,
@@ -186,28 +194,140 @@ pub fn parse_table_section(
 }
 
 /// Parses the Memory section of the wasm module.
-pub fn parse_memory_section(
+pub fn parse_memory_section<S: BuildHasher>(
     memories: MemorySectionReader,
-    environ: &mut ModuleEnvironment,
+    environ: &mut ModuleEnvironment<'_, S>,
 ) -> WasmResult<()> {
+    let mut memories = memories;
     environ.reserve_memories(memories.get_count())?;
 
-    for entry in memories {
-        let memory = entry.map_err(to_wasm_error)?;
-        environ.declare_memory(MemoryType {
+    for _ in 0..memories.get_count() {
+        let offset = memories.original_position();
+        let memory = memories.read().map_err(to_wasm_error)?;
+        let memory = MemoryType {
             minimum: Pages(memory.limits.initial),
             maximum: memory.limits.maximum.map(Pages),
             shared: memory.shared,
-        })?;
+        };
+        check_memory_limits(&memory, offset)?;
+        environ.declare_memory(memory)?;
     }
 
     Ok(())
 }
 
+/// Checks that the minimum of a memory or table at `offset` doesn't
+/// exceed its maximum.
+fn check_limits(minimum: u32, maximum: Option<u32>, offset: usize) -> WasmResult<()> {
+    match maximum {
+        Some(maximum) if minimum > maximum => Err(WasmError::InvalidWebAssembly {
+            message: "size minimum must not be greater than maximum".to_string(),
+            offset,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Checks the limits of the memory at `offset`, which can't exceed 4 GiB.
+///
+/// The messages are the ones of the validation.
+fn check_memory_limits(memory: &MemoryType, offset: usize) -> WasmResult<()> {
+    check_limits(
+        memory.minimum.0,
+        memory.maximum.map(|maximum| maximum.0),
+        offset,
+    )?;
+    let too_large = |pages: Pages| pages.0 > WASM_MAX_PAGES;
+    if too_large(memory.minimum) || memory.maximum.map_or(false, too_large) {
+        return Err(WasmError::InvalidWebAssembly {
+            message: "memory size must be at most 65536 pages (4GiB)".to_string(),
+            offset,
+        });
+    }
+    Ok(())
+}
+
+/// Reads the operators of an init expression, without its final `end`.
+fn read_init_expr<'data>(init_expr: &InitExpr<'data>) -> WasmResult<Vec<Operator<'data>>> {
+    let mut reader = init_expr.get_binary_reader();
+    let mut operators = Vec::new();
+    loop {
+        match reader.read_operator().map_err(to_wasm_error)? {
+            Operator::End => return Ok(operators),
+            operator => operators.push(operator),
+        }
+    }
+}
+
+/// Translates the operators of an init expression of the extended
+/// constant expressions proposal.
+fn translate_const_expr(
+    init_expr: &InitExpr,
+    operators: &[Operator],
+    section: &str,
+) -> WasmResult<ConstExpr> {
+    let mut ops = Vec::with_capacity(operators.len());
+    for operator in operators {
+        ops.push(match *operator {
+            Operator::I32Const { value } => ConstOp::I32Const(value),
+            Operator::I64Const { value } => ConstOp::I64Const(value),
+            Operator::GlobalGet { global_index } => {
+                ConstOp::GlobalGet(GlobalIndex::from_u32(global_index))
+            }
+            Operator::I32Add => ConstOp::I32Add,
+            Operator::I32Sub => ConstOp::I32Sub,
+            Operator::I32Mul => ConstOp::I32Mul,
+            Operator::I64Add => ConstOp::I64Add,
+            Operator::I64Sub => ConstOp::I64Sub,
+            Operator::I64Mul => ConstOp::I64Mul,
+            ref s => {
+                return Err(wasm_unsupported!(
+                    "unsupported init expr in {} section: {:?}",
+                    section,
+                    s
+                ))
+            }
+        });
+    }
+    ConstExpr::new(ops).ok_or_else(|| WasmError::InvalidWebAssembly {
+        message: format!("type mismatch: invalid init expr in {} section", section),
+        offset: init_expr.get_binary_reader().original_position(),
+    })
+}
+
+/// Reads the offset of an active element or data segment, as the base
+/// expression reading globals (if any) and the constant offset added to it.
+fn read_segment_offset(
+    init_expr: &InitExpr,
+    section: &str,
+) -> WasmResult<(Option<ConstExpr>, usize)> {
+    Ok(match read_init_expr(init_expr)?.as_slice() {
+        [Operator::I32Const { value }] => (None, *value as u32 as usize),
+        [Operator::GlobalGet { global_index }] => (
+            Some(ConstExpr::global_get(GlobalIndex::from_u32(*global_index))),
+            0,
+        ),
+        [s] => {
+            return Err(wasm_unsupported!(
+                "unsupported init expr in {} section: {:?}",
+                section,
+                s
+            ))
+        }
+        operators => {
+            let expr = translate_const_expr(init_expr, operators, section)?;
+            match expr.constant() {
+                Some(value) => (None, value as u32 as usize),
+                None => (Some(expr), 0),
+            }
+        }
+    })
+}
+
 /// Parses the Global section of the wasm module.
-pub fn parse_global_section(
+pub fn parse_global_section<S: BuildHasher>(
     globals: GlobalSectionReader,
-    environ: &mut ModuleEnvironment,
+    environ: &mut ModuleEnvironment<'_, S>,
 ) -> WasmResult<()> {
     environ.reserve_globals(globals.get_count())?;
 
@@ -219,27 +339,36 @@ pub fn parse_global_section(
             },
             init_expr,
         } = entry.map_err(to_wasm_error)?;
-        let mut init_expr_reader = init_expr.get_binary_reader();
-        let initializer = match init_expr_reader.read_operator().map_err(to_wasm_error)? {
-            Operator::I32Const { value } => GlobalInit::I32Const(value),
-            Operator::I64Const { value } => GlobalInit::I64Const(value),
-            Operator::F32Const { value } => GlobalInit::F32Const(f32::from_bits(value.bits())),
-            Operator::F64Const { value } => GlobalInit::F64Const(f64::from_bits(value.bits())),
-            Operator::V128Const { value } => {
-                GlobalInit::V128Const(V128::from(value.bytes().to_vec().as_slice()))
-            }
-            Operator::RefNull { ty: _ } => GlobalInit::RefNullConst,
-            Operator::RefFunc { function_index } => {
-                GlobalInit::RefFunc(FunctionIndex::from_u32(function_index))
-            }
-            Operator::GlobalGet { global_index } => {
-                GlobalInit::GetGlobal(GlobalIndex::from_u32(global_index))
-            }
-            ref s => {
-                return Err(wasm_unsupported!(
-                    "unsupported init expr in global section: {:?}",
-                    s
-                ));
+        let initializer = match read_init_expr(&init_expr)?.as_slice() {
+            [op] => match *op {
+                Operator::I32Const { value } => GlobalInit::I32Const(value),
+                Operator::I64Const { value } => GlobalInit::I64Const(value),
+                Operator::F32Const { value } => GlobalInit::F32Const(f32::from_bits(value.bits())),
+                Operator::F64Const { value } => GlobalInit::F64Const(f64::from_bits(value.bits())),
+                Operator::V128Const { value } => {
+                    GlobalInit::V128Const(V128::from(value.bytes().to_vec().as_slice()))
+                }
+                Operator::RefNull { ty: _ } => GlobalInit::RefNullConst,
+                Operator::RefFunc { function_index } => {
+                    GlobalInit::RefFunc(FunctionIndex::from_u32(function_index))
+                }
+                Operator::GlobalGet { global_index } => {
+                    GlobalInit::GetGlobal(GlobalIndex::from_u32(global_index))
+                }
+                ref s => {
+                    return Err(wasm_unsupported!(
+                        "unsupported init expr in global section: {:?}",
+                        s
+                    ));
+                }
+            },
+            operators => {
+                let expr = translate_const_expr(&init_expr, operators, "global")?;
+                match (expr.constant(), content_type) {
+                    (Some(value), wasmparser::Type::I32) => GlobalInit::I32Const(value as i32),
+                    (Some(value), _) => GlobalInit::I64Const(value),
+                    (None, _) => GlobalInit::Expr(expr),
+                }
             }
         };
         let global = GlobalType {
@@ -253,9 +382,9 @@ pub fn parse_global_section(
 }
 
 /// Parses the Export section of the wasm module.
-pub fn parse_export_section<'data>(
-    exports: ExportSectionReader<'data>,
-    environ: &mut ModuleEnvironment<'data>,
+pub fn parse_export_section<S: BuildHasher>(
+    exports: ExportSectionReader,
+    environ: &mut ModuleEnvironment<'_, S>,
 ) -> WasmResult<()> {
     environ.reserve_exports(exports.get_count())?;
 
@@ -289,14 +418,20 @@ pub fn parse_export_section<'data>(
 }
 
 /// Parses the Start section of the wasm module.
-pub fn parse_start_section(index: u32, environ: &mut ModuleEnvironment) -> WasmResult<()> {
+pub fn parse_start_section<S: BuildHasher>(
+    index: u32,
+    environ: &mut ModuleEnvironment<'_, S>,
+) -> WasmResult<()> {
     environ.declare_start_function(FunctionIndex::from_u32(index))?;
     Ok(())
 }
 
-fn read_elems(items: &ElementItems) -> WasmResult<Box<[FunctionIndex]>> {
+fn read_elems<S: BuildHasher>(
+    items: &ElementItems,
+    environ: &ModuleEnvironment<'_, S>,
+) -> WasmResult<Box<[FunctionIndex]>> {
     let items_reader = items.get_items_reader().map_err(to_wasm_error)?;
-    let mut elems = Vec::with_capacity(usize::try_from(items_reader.get_count()).unwrap());
+    let mut elems = Vec::with_capacity(environ.reservation(items_reader.get_count()));
     for item in items_reader {
         let elem = match item.map_err(to_wasm_error)? {
             ElementItem::Null(_ty) => FunctionIndex::reserved_value(),
@@ -308,9 +443,9 @@ fn read_elems(items: &ElementItems) -> WasmResult<Box<[FunctionIndex]>> {
 }
 
 /// Parses the Element section of the wasm module.
-pub fn parse_element_section<'data>(
+pub fn parse_element_section<'data, S: BuildHasher>(
     elements: ElementSectionReader<'data>,
-    environ: &mut ModuleEnvironment,
+    environ: &mut ModuleEnvironment<'_, S>,
 ) -> WasmResult<()> {
     environ.reserve_table_initializers(elements.get_count())?;
 
@@ -322,26 +457,13 @@ pub fn parse_element_section<'data>(
                 ty
             ));
         }
-        let segments = read_elems(&items)?;
+        let segments = read_elems(&items, environ)?;
         match kind {
             ElementKind::Active {
                 table_index,
                 init_expr,
             } => {
-                let mut init_expr_reader = init_expr.get_binary_reader();
-                let (base, offset) =
-                    match init_expr_reader.read_operator().map_err(to_wasm_error)? {
-                        Operator::I32Const { value } => (None, value as u32 as usize),
-                        Operator::GlobalGet { global_index } => {
-                            (Some(GlobalIndex::from_u32(global_index)), 0)
-                        }
-                        ref s => {
-                            return Err(wasm_unsupported!(
-                                "unsupported init expr in element section: {:?}",
-                                s
-                            ));
-                        }
-                    };
+                let (base, offset) = read_segment_offset(&init_expr, "element")?;
                 environ.declare_table_initializers(
                     TableIndex::from_u32(table_index),
                     base,
@@ -360,10 +482,14 @@ pub fn parse_element_section<'data>(
 }
 
 /// Parses the Code section of the wasm module.
-pub fn parse_code_section<'data>(
-    code: CodeSectionReader<'data>,
+///
+/// The function bodies are kept with `keep`, which borrows them from the
+/// module or copies them.
+pub fn parse_code_section<'a, 'data, S: BuildHasher>(
+    code: CodeSectionReader<'a>,
     module_translation_state: &ModuleTranslationState,
-    environ: &mut ModuleEnvironment<'data>,
+    environ: &mut ModuleEnvironment<'data, S>,
+    keep: fn(&'a [u8]) -> Cow<'data, [u8]>,
 ) -> WasmResult<()> {
     for body in code {
         let mut reader = body.map_err(to_wasm_error)?.get_binary_reader();
@@ -371,7 +497,7 @@ pub fn parse_code_section<'data>(
         let offset = reader.original_position();
         environ.define_function_body(
             module_translation_state,
-            reader.read_bytes(size).map_err(to_wasm_error)?,
+            keep(reader.read_bytes(size).map_err(to_wasm_error)?),
             offset,
         )?;
     }
@@ -379,9 +505,13 @@ pub fn parse_code_section<'data>(
 }
 
 /// Parses the Data section of the wasm module.
-pub fn parse_data_section<'data>(
-    data: DataSectionReader<'data>,
-    environ: &mut ModuleEnvironment<'data>,
+///
+/// The data of the active segments is kept with `keep`, which borrows it
+/// from the module or copies it.
+pub fn parse_data_section<'a, 'data, S: BuildHasher>(
+    data: DataSectionReader<'a>,
+    environ: &mut ModuleEnvironment<'data, S>,
+    keep: fn(&'a [u8]) -> Cow<'data, [u8]>,
 ) -> WasmResult<()> {
     environ.reserve_data_initializers(data.get_count())?;
 
@@ -392,25 +522,12 @@ pub fn parse_data_section<'data>(
                 memory_index,
                 init_expr,
             } => {
-                let mut init_expr_reader = init_expr.get_binary_reader();
-                let (base, offset) =
-                    match init_expr_reader.read_operator().map_err(to_wasm_error)? {
-                        Operator::I32Const { value } => (None, value as u32 as usize),
-                        Operator::GlobalGet { global_index } => {
-                            (Some(GlobalIndex::from_u32(global_index)), 0)
-                        }
-                        ref s => {
-                            return Err(wasm_unsupported!(
-                                "unsupported init expr in data section: {:?}",
-                                s
-                            ))
-                        }
-                    };
+                let (base, offset) = read_segment_offset(&init_expr, "data")?;
                 environ.declare_data_initialization(
                     MemoryIndex::from_u32(memory_index),
                     base,
                     offset,
-                    data,
+                    keep(data),
                 )?;
             }
             DataKind::Passive => {
@@ -424,9 +541,9 @@ pub fn parse_data_section<'data>(
 }
 
 /// Parses the Name section of the wasm module.
-pub fn parse_name_section<'data>(
-    mut names: NameSectionReader<'data>,
-    environ: &mut ModuleEnvironment<'data>,
+pub fn parse_name_section<S: BuildHasher>(
+    mut names: NameSectionReader,
+    environ: &mut ModuleEnvironment<'_, S>,
 ) -> WasmResult<()> {
     while let Ok(subsection) = names.read() {
         match subsection {
@@ -446,7 +563,17 @@ pub fn parse_name_section<'data>(
                     environ.declare_module_name(name)?;
                 }
             }
-            wasmparser::Name::Local(_) => {}
+            wasmparser::Name::Local(local) => {
+                if let Some(local_names) = local
+                    .get_function_local_reader()
+                    .ok()
+                    .and_then(parse_local_name_subsection)
+                {
+                    for ((func_index, local_index), name) in local_names {
+                        environ.declare_local_name(func_index, local_index, name)?;
+                    }
+                }
+            }
         };
     }
     Ok(())
@@ -475,3 +602,23 @@ fn parse_function_name_subsection(
     }
     Some(function_names)
 }
+
+fn parse_local_name_subsection(
+    mut reader: FunctionLocalReader<'_>,
+) -> Option<HashMap<(FunctionIndex, u32), &str>> {
+    let mut local_names = HashMap::new();
+    for _ in 0..reader.get_count() {
+        let function_locals = reader.read().ok()?;
+        let func_index = FunctionIndex::from_u32(function_locals.func_index);
+        let mut naming_reader = function_locals.get_map().ok()?;
+        for _ in 0..naming_reader.get_count() {
+            let Naming { index, name } = naming_reader.read().ok()?;
+            if local_names.insert((func_index, index), name).is_some() {
+                // Like the function names, the local names of a function
+                // should be unique.
+                return None;
+            }
+        }
+    }
+    Some(local_names)
+}
@@ -3,6 +3,7 @@
 
 use crate::{wasm_unsupported, WasmResult};
 use std::boxed::Box;
+use std::vec::Vec;
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::SignatureIndex;
 
@@ -23,6 +24,12 @@ pub struct ModuleTranslationState {
     /// This is used for translating multi-value Wasm blocks inside functions,
     /// which are encoded to refer to their type signature via index.
     pub(crate) wasm_types: WasmTypes,
+
+    /// The index of the signature of each type of the Wasm module, in
+    /// `ModuleInfo::signatures`.
+    ///
+    /// The identical types of the module share a signature.
+    pub(crate) signature_indices: Vec<SignatureIndex>,
 }
 
 impl ModuleTranslationState {
@@ -30,9 +37,16 @@ impl ModuleTranslationState {
     pub fn new() -> Self {
         Self {
             wasm_types: PrimaryMap::new(),
+            signature_indices: Vec::new(),
         }
     }
 
+    /// Get the index of the signature of the Wasm type `type_index`, as
+    /// used by the imports, the functions and `call_indirect`.
+    pub fn signature_index(&self, type_index: u32) -> SignatureIndex {
+        self.signature_indices[type_index as usize]
+    }
+
     /// Get the parameter and result types for the given Wasm blocktype.
     pub fn blocktype_params_results(
         &self,
@@ -0,0 +1,216 @@
+//! Translation of a module received in chunks, like a module downloaded
+//! over the network.
+
+use super::environ::{ModuleEnvironment, ModuleInfoTranslation};
+use super::error::to_wasm_error;
+use super::module::translate_section;
+use super::state::ModuleTranslationState;
+use crate::lib::std::borrow::Cow;
+use crate::lib::std::string::ToString;
+use crate::lib::std::vec::Vec;
+use crate::{WasmError, WasmResult};
+use std::hash::BuildHasher;
+use wasmparser::{
+    BinaryReader, CodeSectionReader, CustomSectionContent, DataSectionReader, ElementSectionReader,
+    ExportSectionReader, FunctionSectionReader, GlobalSectionReader, ImportSectionReader,
+    MemorySectionReader, NameSectionReader, SectionContent, TableSectionReader, TypeSectionReader,
+};
+
+/// The magic number and the version of the supported binary format.
+const HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+/// Translates a module pushed in chunks with [`push`].
+///
+/// Each section is translated as soon as it has been received, so only
+/// the section being received is buffered, and a malformed section fails
+/// the `push` completing it. The function bodies and the data segments
+/// are copied out of the sections, so the translation owns all of its
+/// data.
+///
+/// Once `push` fails, the module can't be translated anymore.
+///
+/// [`push`]: StreamingTranslator::push
+pub struct StreamingTranslator<S> {
+    environ: ModuleEnvironment<'static, S>,
+    module_translation: ModuleTranslationState,
+    /// The bytes received since the last complete section, or since the
+    /// start of the module until the header is complete.
+    pending: Vec<u8>,
+    /// The offset in the module of the first pending byte.
+    offset: usize,
+}
+
+impl<S: BuildHasher> StreamingTranslator<S> {
+    pub(crate) fn new(environ: ModuleEnvironment<'static, S>) -> Self {
+        Self {
+            environ,
+            module_translation: ModuleTranslationState::new(),
+            pending: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Appends `bytes` to the module, and translates the sections they
+    /// complete.
+    pub fn push(&mut self, bytes: &[u8]) -> WasmResult<()> {
+        self.pending.extend_from_slice(bytes);
+        if self.offset == 0 {
+            let received = self.pending.len().min(HEADER.len());
+            if self.pending[..received] != HEADER[..received] {
+                return Err(invalid("expected the magic number and version 1", 0));
+            }
+            if received < HEADER.len() {
+                return Ok(());
+            }
+            self.pending.drain(..HEADER.len());
+            self.offset = HEADER.len();
+        }
+
+        let mut start = 0;
+        while let Some((id, payload_start, end)) =
+            section_at(&self.pending[start..], self.offset + start)?
+        {
+            let payload = &self.pending[start + payload_start..start + end];
+            let content = section_content(id, payload, self.offset + start + payload_start)?;
+            self.environ.set_bytes_remaining(payload.len());
+            translate_section(
+                content,
+                &mut self.module_translation,
+                &mut self.environ,
+                copy,
+            )?;
+            start += end;
+        }
+        self.pending.drain(..start);
+        self.offset += start;
+        Ok(())
+    }
+
+    /// Returns the translation of the module, which must have been
+    /// received entirely.
+    pub fn finish(self) -> WasmResult<ModuleInfoTranslation<'static>> {
+        if self.offset == 0 || !self.pending.is_empty() {
+            return Err(invalid("unexpected end of the module", self.offset));
+        }
+        let mut result = self.environ.result;
+        result.module_translation = Some(self.module_translation);
+        Ok(result)
+    }
+}
+
+/// Copies the function bodies and the data segments out of the section
+/// they are received in.
+fn copy(bytes: &[u8]) -> Cow<'static, [u8]> {
+    Cow::Owned(bytes.to_vec())
+}
+
+/// Returns the id of the section starting `bytes`, found at `offset` in
+/// the module, and the start of its payload and its end in `bytes`, once
+/// it has been received entirely.
+fn section_at(bytes: &[u8], offset: usize) -> WasmResult<Option<(u8, usize, usize)>> {
+    let id = match bytes.first() {
+        Some(&id) if id > 12 => return Err(invalid("invalid section code", offset)),
+        Some(&id) => id,
+        None => return Ok(None),
+    };
+    // The size of the payload follows the id.
+    let mut position = 1;
+    let mut size = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = match bytes.get(position) {
+            Some(&byte) => byte,
+            None => return Ok(None),
+        };
+        position += 1;
+        if shift == 28 && byte > 0x0f {
+            return Err(invalid("invalid section size", offset + position - 1));
+        }
+        size |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            let end = position + size as usize;
+            return Ok(if end <= bytes.len() {
+                Some((id, position, end))
+            } else {
+                None
+            });
+        }
+    }
+    Err(invalid("invalid section size", offset + position - 1))
+}
+
+/// Reads the section `id` with the `payload` found at `offset` in the
+/// module, as `wasmparser::ModuleReader` does for a complete module.
+fn section_content(id: u8, payload: &[u8], offset: usize) -> WasmResult<SectionContent<'_>> {
+    Ok(match id {
+        0 => {
+            let mut reader = BinaryReader::new_with_offset(payload, offset);
+            let name = reader.read_string().map_err(to_wasm_error)?;
+            let start = reader.current_position();
+            let offset = reader.original_position();
+            let content = if name == "name" {
+                NameSectionReader::new(&payload[start..], offset)
+                    .ok()
+                    .map(CustomSectionContent::Name)
+            } else {
+                None
+            };
+            SectionContent::Custom {
+                name,
+                binary: BinaryReader::new_with_offset(&payload[start..], offset),
+                content,
+            }
+        }
+        1 => SectionContent::Type(TypeSectionReader::new(payload, offset).map_err(to_wasm_error)?),
+        2 => SectionContent::Import(
+            ImportSectionReader::new(payload, offset).map_err(to_wasm_error)?,
+        ),
+        3 => SectionContent::Function(
+            FunctionSectionReader::new(payload, offset).map_err(to_wasm_error)?,
+        ),
+        4 => {
+            SectionContent::Table(TableSectionReader::new(payload, offset).map_err(to_wasm_error)?)
+        }
+        5 => SectionContent::Memory(
+            MemorySectionReader::new(payload, offset).map_err(to_wasm_error)?,
+        ),
+        6 => SectionContent::Global(
+            GlobalSectionReader::new(payload, offset).map_err(to_wasm_error)?,
+        ),
+        7 => SectionContent::Export(
+            ExportSectionReader::new(payload, offset).map_err(to_wasm_error)?,
+        ),
+        8 => SectionContent::Start(read_index(
+            payload,
+            offset,
+            "unexpected content in the start section",
+        )?),
+        9 => SectionContent::Element(
+            ElementSectionReader::new(payload, offset).map_err(to_wasm_error)?,
+        ),
+        10 => SectionContent::Code(CodeSectionReader::new(payload, offset).map_err(to_wasm_error)?),
+        11 => SectionContent::Data(DataSectionReader::new(payload, offset).map_err(to_wasm_error)?),
+        12 => SectionContent::DataCount(read_index(
+            payload,
+            offset,
+            "unexpected content in the data count section",
+        )?),
+        _ => unreachable!("the section code is checked by `section_at`"),
+    })
+}
+
+/// Reads the index or count making up the whole `payload` of a section.
+fn read_index(payload: &[u8], offset: usize, trailing_error: &str) -> WasmResult<u32> {
+    let mut reader = BinaryReader::new_with_offset(payload, offset);
+    let index = reader.read_var_u32().map_err(to_wasm_error)?;
+    if !reader.eof() {
+        return Err(invalid(trailing_error, reader.original_position()));
+    }
+    Ok(index)
+}
+
+fn invalid(message: &str, offset: usize) -> WasmError {
+    WasmError::InvalidWebAssembly {
+        message: message.to_string(),
+        offset,
+    }
+}
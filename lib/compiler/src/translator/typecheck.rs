@@ -0,0 +1,224 @@
+//! Type checking of a single function body against the types of a
+//! module, without translating it.
+
+use super::state::ModuleTranslationState;
+use crate::error::TypeError;
+use crate::lib::std::collections::HashSet;
+use crate::lib::std::string::ToString;
+use crate::lib::std::vec::Vec;
+use wasmer_types::entity::EntityRef;
+use wasmer_types::{ExportIndex, Features, FunctionType, GlobalInit, Type};
+use wasmer_vm::ModuleInfo;
+use wasmparser::{
+    FuncType, GlobalType, MemoryType, OperatorValidatorConfig, ResizableLimits, TableType,
+    WasmModuleResources,
+};
+
+/// The index the checked function is validated as, which no other
+/// function of the module can have.
+const CHECKED_FUNCTION: u32 = u32::max_value();
+
+impl ModuleTranslationState {
+    /// Type checks the function `body` with the signature `ty`, as it is
+    /// done before translating it, in the translated `module`.
+    ///
+    /// The operators run through the type stack of the validator, and the
+    /// first mismatch is returned with its byte offset in `body`. The
+    /// operators can use the types, functions, tables, memories, globals
+    /// and element segments of `module`. It doesn't record the active data
+    /// segments, so every data segment index is accepted.
+    ///
+    /// The proposals are enabled as in `features`.
+    pub fn typecheck_function(
+        &self,
+        module: &ModuleInfo,
+        features: &Features,
+        body: &[u8],
+        ty: &FunctionType,
+    ) -> Result<(), (usize, TypeError)> {
+        let resources = Resources::new(self, module, ty);
+        let config = OperatorValidatorConfig {
+            enable_threads: features.threads,
+            enable_reference_types: features.reference_types,
+            enable_bulk_memory: features.bulk_memory,
+            enable_tail_call: false,
+            enable_simd: features.simd,
+            enable_multi_value: features.multi_value,
+        };
+        wasmparser::validate_function_body(body, 0, CHECKED_FUNCTION, &resources, Some(config))
+            .map_err(|error| {
+                (
+                    error.offset(),
+                    TypeError {
+                        message: error.message().to_string(),
+                    },
+                )
+            })
+    }
+}
+
+fn type_to_wptype(ty: Type) -> wasmparser::Type {
+    match ty {
+        Type::I32 => wasmparser::Type::I32,
+        Type::I64 => wasmparser::Type::I64,
+        Type::F32 => wasmparser::Type::F32,
+        Type::F64 => wasmparser::Type::F64,
+        Type::V128 => wasmparser::Type::V128,
+        Type::ExternRef => wasmparser::Type::ExternRef,
+        Type::FuncRef => wasmparser::Type::FuncRef,
+    }
+}
+
+fn func_type(ty: &FunctionType) -> FuncType {
+    FuncType {
+        params: ty.params().iter().map(|ty| type_to_wptype(*ty)).collect(),
+        returns: ty.results().iter().map(|ty| type_to_wptype(*ty)).collect(),
+    }
+}
+
+/// The module seen by the validator.
+///
+/// Its types are the types of the module, followed by its signatures,
+/// which its functions refer to, and by the type of the checked function.
+struct Resources {
+    types: Vec<FuncType>,
+    /// The index in `types` of the signature of each function.
+    function_types: Vec<u32>,
+    tables: Vec<TableType>,
+    memories: Vec<MemoryType>,
+    globals: Vec<GlobalType>,
+    element_count: u32,
+    /// The functions referenced outside of the function bodies.
+    referenced_functions: HashSet<u32>,
+}
+
+impl Resources {
+    fn new(state: &ModuleTranslationState, module: &ModuleInfo, ty: &FunctionType) -> Self {
+        let mut types = state
+            .wasm_types
+            .values()
+            .map(|(params, returns)| FuncType {
+                params: params.clone(),
+                returns: returns.clone(),
+            })
+            .collect::<Vec<_>>();
+        let signatures_start = types.len() as u32;
+        types.extend(module.signatures.values().map(func_type));
+        types.push(func_type(ty));
+        let function_types = module
+            .functions
+            .values()
+            .map(|signature| signatures_start + signature.index() as u32)
+            .collect();
+        let tables = module
+            .tables
+            .values()
+            .map(|table| TableType {
+                element_type: type_to_wptype(table.ty),
+                limits: ResizableLimits {
+                    initial: table.minimum,
+                    maximum: table.maximum,
+                },
+            })
+            .collect();
+        let memories = module
+            .memories
+            .values()
+            .map(|memory| MemoryType {
+                limits: ResizableLimits {
+                    initial: memory.minimum.0,
+                    maximum: memory.maximum.map(|maximum| maximum.0),
+                },
+                shared: memory.shared,
+            })
+            .collect();
+        let globals = module
+            .globals
+            .values()
+            .map(|global| GlobalType {
+                content_type: type_to_wptype(global.ty),
+                mutable: global.mutability.is_mutable(),
+            })
+            .collect();
+
+        let mut referenced_functions = HashSet::new();
+        for initializer in &module.table_initializers {
+            referenced_functions.extend(initializer.elements.iter().map(|f| f.index() as u32));
+        }
+        for elements in module.passive_elements.values() {
+            referenced_functions.extend(elements.iter().map(|f| f.index() as u32));
+        }
+        for export in module.exports.values() {
+            if let ExportIndex::Function(function) = export {
+                referenced_functions.insert(function.index() as u32);
+            }
+        }
+        for init in module.global_initializers.values() {
+            if let GlobalInit::RefFunc(function) = init {
+                referenced_functions.insert(function.index() as u32);
+            }
+        }
+
+        Self {
+            types,
+            function_types,
+            tables,
+            memories,
+            globals,
+            element_count: (module.table_initializers.len() + module.passive_elements.len()) as u32,
+            referenced_functions,
+        }
+    }
+}
+
+impl WasmModuleResources for Resources {
+    type FuncType = FuncType;
+    type TableType = TableType;
+    type MemoryType = MemoryType;
+    type GlobalType = GlobalType;
+
+    fn type_at(&self, at: u32) -> Option<&FuncType> {
+        self.types.get(at as usize)
+    }
+
+    fn table_at(&self, at: u32) -> Option<&TableType> {
+        self.tables.get(at as usize)
+    }
+
+    fn memory_at(&self, at: u32) -> Option<&MemoryType> {
+        self.memories.get(at as usize)
+    }
+
+    fn global_at(&self, at: u32) -> Option<&GlobalType> {
+        self.globals.get(at as usize)
+    }
+
+    fn func_type_id_at(&self, at: u32) -> Option<u32> {
+        if at == CHECKED_FUNCTION {
+            Some(self.types.len() as u32 - 1)
+        } else {
+            self.function_types.get(at as usize).copied()
+        }
+    }
+
+    fn element_type_at(&self, at: u32) -> Option<wasmparser::Type> {
+        // The element segments only hold functions.
+        if at < self.element_count {
+            Some(wasmparser::Type::FuncRef)
+        } else {
+            None
+        }
+    }
+
+    fn element_count(&self) -> u32 {
+        self.element_count
+    }
+
+    fn data_count(&self) -> u32 {
+        u32::max_value()
+    }
+
+    fn is_function_referenced(&self, idx: u32) -> bool {
+        self.referenced_functions.contains(&idx)
+    }
+}
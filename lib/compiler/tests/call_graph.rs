@@ -0,0 +1,85 @@
+#![cfg(feature = "translator")]
+
+mod utils;
+
+use utils::{code, module, name, vector};
+use wasmer_compiler::{ModuleEnvironment, WasmError};
+use wasmer_types::entity::PrimaryMap;
+use wasmer_types::{FunctionIndex, LocalFunctionIndex};
+
+fn functions(indices: &[u32]) -> Vec<FunctionIndex> {
+    indices
+        .iter()
+        .cloned()
+        .map(FunctionIndex::from_u32)
+        .collect()
+}
+
+#[test]
+fn call_graph() {
+    let data = module(&[
+        // Types `() -> ()` and `(i32) -> ()`.
+        (0x01, vector(2, &[0x60, 0x00, 0x00, 0x60, 0x01, 0x7f, 0x00])),
+        // An imported function of type 0, then five functions of types 0,
+        // 0, 1, 0 and 1.
+        (
+            0x02,
+            vector(1, &[name("e"), name("f"), vec![0x00, 0x00]].concat()),
+        ),
+        (0x03, vector(5, &[0x00, 0x00, 0x01, 0x00, 0x01])),
+        // A table with the functions 3, 4 and 2 as its elements.
+        (0x04, vector(1, &[0x70, 0x00, 0x03])),
+        (
+            0x09,
+            vector(1, &[0x00, 0x41, 0x00, 0x0b, 0x03, 0x03, 0x04, 0x02]),
+        ),
+        // Function 1 calls function 2 twice, the import and a function of
+        // type 0 indirectly. Function 2 calls function 1, and function 3
+        // calls a function of type 1 indirectly.
+        (
+            0x0a,
+            code(&[
+                &[
+                    0x00, 0x10, 0x02, 0x10, 0x02, 0x10, 0x00, 0x41, 0x00, 0x11, 0x00, 0x00, 0x0b,
+                ],
+                &[0x00, 0x10, 0x01, 0x0b],
+                &[0x00, 0x41, 0x00, 0x41, 0x00, 0x11, 0x01, 0x00, 0x0b],
+                &[0x00, 0x0b],
+                &[0x00, 0x0b],
+            ]),
+        ),
+    ]);
+
+    let translation = ModuleEnvironment::new().translate(&data).unwrap();
+    let call_graph = translation.call_graph().unwrap();
+    let direct: PrimaryMap<LocalFunctionIndex, _> = vec![
+        functions(&[0, 2]),
+        functions(&[1]),
+        functions(&[]),
+        functions(&[]),
+        functions(&[]),
+    ]
+    .into_iter()
+    .collect();
+    let indirect: PrimaryMap<LocalFunctionIndex, _> = vec![
+        functions(&[2, 4]),
+        functions(&[]),
+        functions(&[3]),
+        functions(&[]),
+        functions(&[]),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(call_graph.direct, direct);
+    assert_eq!(call_graph.indirect, indirect);
+}
+
+#[test]
+fn call_graph_of_untranslated_module() {
+    let mut translation = ModuleEnvironment::new().translate(&module(&[])).unwrap();
+    translation.module_translation = None;
+    match translation.call_graph() {
+        Err(WasmError::Generic(_)) => {}
+        _ => panic!("a call graph was built without the translation state"),
+    }
+}
@@ -0,0 +1,141 @@
+use wasmer_compiler::{
+    Compilation, CompilationStats, CompiledFunction, CompiledFunctionFrameInfo, FunctionAddressMap,
+    FunctionBody, InstructionAddressMap, Relocation, RelocationKind, RelocationTarget, SourceLoc,
+    TrapInformation,
+};
+use wasmer_types::entity::{PrimaryMap, SecondaryMap};
+use wasmer_types::LocalFunctionIndex;
+use wasmer_vm::TrapCode;
+
+fn function(len: usize, relocations: usize, traps: usize) -> CompiledFunction {
+    CompiledFunction {
+        body: FunctionBody {
+            body: vec![0; len],
+            unwind_info: None,
+        },
+        relocations: (0..relocations)
+            .map(|i| Relocation {
+                kind: RelocationKind::X86CallPCRel4,
+                reloc_target: RelocationTarget::LocalFunc(LocalFunctionIndex::from_u32(0)),
+                offset: i as u32,
+                addend: -4,
+            })
+            .collect(),
+        jt_offsets: SecondaryMap::new(),
+        jt_entries: SecondaryMap::new(),
+        frame_info: CompiledFunctionFrameInfo {
+            traps: (0..traps)
+                .map(|i| TrapInformation {
+                    code_offset: i as u32,
+                    source_loc: SourceLoc::default(),
+                    trap_code: TrapCode::UnreachableCodeReached,
+                })
+                .collect(),
+            ..Default::default()
+        },
+    }
+}
+
+/// The address map of a function of `body_len` bytes starting at `start`
+/// in the module, with `instructions` as `(srcloc, code_offset, code_len)`.
+fn address_map(
+    start: u32,
+    body_len: usize,
+    instructions: &[(u32, usize, usize)],
+) -> FunctionAddressMap {
+    FunctionAddressMap {
+        instructions: instructions
+            .iter()
+            .map(|&(srcloc, code_offset, code_len)| InstructionAddressMap {
+                srcloc: SourceLoc::new(srcloc),
+                code_offset,
+                code_len,
+            })
+            .collect(),
+        start_srcloc: SourceLoc::new(start),
+        end_srcloc: SourceLoc::new(instructions.last().map_or(start, |i| i.0 + 1)),
+        body_offset: 0,
+        body_len,
+    }
+}
+
+/// A compilation of two functions, the first one with an address map.
+fn two_functions() -> Compilation {
+    let mut first = function(10, 2, 1);
+    first.frame_info.address_map = address_map(30, 10, &[(32, 0, 4), (33, 4, 6)]);
+    let mut functions = PrimaryMap::new();
+    functions.push(first);
+    functions.push(function(25, 1, 3));
+    Compilation::new(
+        functions,
+        PrimaryMap::new(),
+        PrimaryMap::new(),
+        PrimaryMap::new(),
+        None,
+    )
+}
+
+#[test]
+fn stats_of_two_functions() {
+    let stats = two_functions().stats();
+    assert_eq!(
+        stats,
+        CompilationStats {
+            functions: 2,
+            code_bytes: 35,
+            relocations: 3,
+            traps: 4,
+            average_function_size: 17,
+        }
+    );
+    assert_eq!(
+        stats.to_string(),
+        "2 functions, 35 bytes of code (average 17 bytes per function), 3 relocations, 4 traps"
+    );
+}
+
+#[test]
+fn stats_of_no_functions() {
+    let compilation = Compilation::new(
+        PrimaryMap::new(),
+        PrimaryMap::new(),
+        PrimaryMap::new(),
+        PrimaryMap::new(),
+        None,
+    );
+    assert_eq!(compilation.stats(), CompilationStats::default());
+}
+
+#[test]
+fn address_maps() {
+    let address_maps = two_functions().get_address_maps();
+    assert_eq!(address_maps.len(), 2);
+    let first = &address_maps[LocalFunctionIndex::from_u32(0)];
+    assert_eq!(first.start_srcloc, SourceLoc::new(30));
+    assert_eq!(first.end_srcloc, SourceLoc::new(34));
+    assert_eq!(first.body_len, 10);
+    assert_eq!(
+        first
+            .instructions
+            .iter()
+            .map(|i| (i.srcloc.bits(), i.code_offset, i.code_len))
+            .collect::<Vec<_>>(),
+        vec![(32, 0, 4), (33, 4, 6)]
+    );
+    assert!(address_maps[LocalFunctionIndex::from_u32(1)]
+        .instructions
+        .is_empty());
+}
+
+#[test]
+#[cfg(feature = "enable-serde")]
+fn serialization_round_trip() {
+    let compilation = two_functions();
+    let serialized = bincode::serialize(&compilation).unwrap();
+    let deserialized: Compilation = bincode::deserialize(&serialized).unwrap();
+    assert_eq!(deserialized, compilation);
+    assert_eq!(
+        deserialized.get_address_maps(),
+        compilation.get_address_maps()
+    );
+}
@@ -0,0 +1,211 @@
+#![cfg(feature = "translator")]
+
+mod utils;
+
+use utils::{code, module, name, vector};
+use wasmer_compiler::ModuleEnvironment;
+use wasmer_types::entity::packed_option::ReservedValue;
+use wasmer_types::{ElemIndex, ExportIndex, FunctionIndex, LocalFunctionIndex};
+
+#[test]
+fn unused_function_is_dead() {
+    let data = module(&[
+        // Type `() -> ()`, and four functions of that type.
+        (0x01, vector(1, &[0x60, 0x00, 0x00])),
+        (0x03, vector(4, &[0x00, 0x00, 0x00, 0x00])),
+        // A table, with function 3 as its only element.
+        (0x04, vector(1, &[0x70, 0x00, 0x01])),
+        // Function 0 is exported as `run`.
+        (0x07, vector(1, &[name("run"), vec![0x00, 0x00]].concat())),
+        (0x09, vector(1, &[0x00, 0x41, 0x00, 0x0b, 0x01, 0x03])),
+        // Functions 0 and 2 call function 1, function 2 is never used.
+        (
+            0x0a,
+            code(&[
+                &[0x00, 0x10, 0x01, 0x0b],
+                &[0x00, 0x0b],
+                &[0x00, 0x10, 0x01, 0x0b],
+                &[0x00, 0x0b],
+            ]),
+        ),
+    ]);
+
+    let translation = ModuleEnvironment::new().translate(&data).unwrap();
+    let mut reachable = translation
+        .reachable_functions()
+        .unwrap()
+        .into_iter()
+        .collect::<Vec<_>>();
+    reachable.sort();
+    assert_eq!(
+        reachable,
+        vec![
+            FunctionIndex::from_u32(0),
+            FunctionIndex::from_u32(1),
+            FunctionIndex::from_u32(3)
+        ]
+    );
+    assert_eq!(
+        translation.dead_functions().unwrap(),
+        vec![LocalFunctionIndex::from_u32(2)]
+    );
+}
+
+#[test]
+fn null_elements_are_skipped() {
+    let data = module(&[
+        // Type `() -> ()`, and three functions of that type.
+        (0x01, vector(1, &[0x60, 0x00, 0x00])),
+        (0x03, vector(3, &[0x00, 0x00, 0x00])),
+        // A table of two elements.
+        (0x04, vector(1, &[0x70, 0x00, 0x02])),
+        // Function 0 is exported as `run`.
+        (0x07, vector(1, &[name("run"), vec![0x00, 0x00]].concat())),
+        // An active segment with `(ref.func 1) (ref.null func)`, and a
+        // passive segment with `(ref.null func)`.
+        (
+            0x09,
+            vector(
+                2,
+                &[
+                    &[0x04, 0x41, 0x00, 0x0b, 0x02][..],
+                    &[0xd2, 0x01, 0x0b, 0xd0, 0x70, 0x0b],
+                    &[0x05, 0x70, 0x01, 0xd0, 0x70, 0x0b],
+                ]
+                .concat(),
+            ),
+        ),
+        (0x0a, code(&[&[0x00, 0x0b], &[0x00, 0x0b], &[0x00, 0x0b]])),
+    ]);
+
+    let translation = ModuleEnvironment::new().translate(&data).unwrap();
+    let mut reachable = translation
+        .reachable_functions()
+        .unwrap()
+        .into_iter()
+        .collect::<Vec<_>>();
+    reachable.sort();
+    assert_eq!(
+        reachable,
+        vec![FunctionIndex::from_u32(0), FunctionIndex::from_u32(1)]
+    );
+    assert_eq!(
+        translation.dead_functions().unwrap(),
+        vec![LocalFunctionIndex::from_u32(2)]
+    );
+}
+
+#[test]
+fn prune_dead_functions() {
+    let data = module(&[
+        // Type `() -> ()`, an imported function and five functions of that
+        // type.
+        (0x01, vector(1, &[0x60, 0x00, 0x00])),
+        (
+            0x02,
+            vector(1, &[name("e"), name("f"), vec![0x00, 0x00]].concat()),
+        ),
+        (0x03, vector(5, &[0x00, 0x00, 0x00, 0x00, 0x00])),
+        // A table, with function 5 as its only element.
+        (0x04, vector(1, &[0x70, 0x00, 0x01])),
+        // Function 3 is exported as `run`.
+        (0x07, vector(1, &[name("run"), vec![0x00, 0x03]].concat())),
+        (0x09, vector(1, &[0x00, 0x41, 0x00, 0x0b, 0x01, 0x05])),
+        // Functions 1 and 2 are dead, function 3 calls the imported function
+        // 0 and function 4, function 4 calls function 1 through a padded
+        // index.
+        (
+            0x0a,
+            code(&[
+                &[0x00, 0x0b],
+                &[0x00, 0x10, 0x01, 0x0b],
+                &[0x00, 0x10, 0x00, 0x10, 0x04, 0x0b],
+                &[0x00, 0x10, 0x85, 0x00, 0x0b],
+                &[0x00, 0x0b],
+            ]),
+        ),
+    ]);
+
+    let mut translation = ModuleEnvironment::new().translate(&data).unwrap();
+    assert_eq!(
+        translation.dead_functions().unwrap(),
+        vec![
+            LocalFunctionIndex::from_u32(0),
+            LocalFunctionIndex::from_u32(1)
+        ]
+    );
+    translation.prune_dead_functions().unwrap();
+
+    let module = &translation.module;
+    assert_eq!(module.functions.len(), 4);
+    assert_eq!(translation.function_body_inputs.len(), 3);
+    assert_eq!(
+        module.exports.get("run"),
+        Some(&ExportIndex::Function(FunctionIndex::from_u32(1)))
+    );
+    assert_eq!(
+        &*module.table_initializers[0].elements,
+        &[FunctionIndex::from_u32(3)]
+    );
+    // The calls are renumbered in place.
+    let bodies = translation
+        .function_body_inputs
+        .values()
+        .map(|body| &*body.data)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        bodies,
+        vec![
+            &[0x00, 0x10, 0x00, 0x10, 0x02, 0x0b][..],
+            &[0x00, 0x10, 0x83, 0x00, 0x0b],
+            &[0x00, 0x0b],
+        ]
+    );
+    assert!(translation.dead_functions().unwrap().is_empty());
+}
+
+#[test]
+fn prune_dead_functions_with_null_elements() {
+    let data = module(&[
+        // Type `() -> ()`, and three functions of that type.
+        (0x01, vector(1, &[0x60, 0x00, 0x00])),
+        (0x03, vector(3, &[0x00, 0x00, 0x00])),
+        // A table of two elements.
+        (0x04, vector(1, &[0x70, 0x00, 0x02])),
+        // Function 1 is exported as `run`, function 0 is dead.
+        (0x07, vector(1, &[name("run"), vec![0x00, 0x01]].concat())),
+        // An active segment with `(ref.null func) (ref.func 2)`, and a
+        // passive segment with `(ref.null func)`.
+        (
+            0x09,
+            vector(
+                2,
+                &[
+                    &[0x04, 0x41, 0x00, 0x0b, 0x02][..],
+                    &[0xd0, 0x70, 0x0b, 0xd2, 0x02, 0x0b],
+                    &[0x05, 0x70, 0x01, 0xd0, 0x70, 0x0b],
+                ]
+                .concat(),
+            ),
+        ),
+        (0x0a, code(&[&[0x00, 0x0b], &[0x00, 0x0b], &[0x00, 0x0b]])),
+    ]);
+
+    let mut translation = ModuleEnvironment::new().translate(&data).unwrap();
+    translation.prune_dead_functions().unwrap();
+
+    let module = &translation.module;
+    assert_eq!(module.functions.len(), 2);
+    assert_eq!(
+        module.exports.get("run"),
+        Some(&ExportIndex::Function(FunctionIndex::from_u32(0)))
+    );
+    assert_eq!(
+        &*module.table_initializers[0].elements,
+        &[FunctionIndex::reserved_value(), FunctionIndex::from_u32(1)]
+    );
+    assert_eq!(
+        &*module.passive_elements[&ElemIndex::from_u32(1)],
+        &[FunctionIndex::reserved_value()]
+    );
+}
@@ -0,0 +1,37 @@
+#![cfg(all(feature = "translator", feature = "enable-serde"))]
+
+mod utils;
+
+use utils::{code, module, vector};
+use wasmer_compiler::{ModuleEnvironment, OwnedFunctionBodyData};
+
+/// A module with two functions, `nop`-ing once and not at all.
+fn nop_module() -> Vec<u8> {
+    module(&[
+        (0x01, vector(1, &[0x60, 0x00, 0x00])),
+        (0x03, vector(2, &[0x00, 0x00])),
+        (0x0a, code(&[&[0x00, 0x0b], &[0x00, 0x01, 0x0b]])),
+    ])
+}
+
+#[test]
+fn owned_function_bodies_round_trip() {
+    let data = nop_module();
+    let translation = ModuleEnvironment::new().translate(&data).unwrap();
+    assert_eq!(translation.function_body_inputs.len(), 2);
+    for body in translation.function_body_inputs.values() {
+        let owned = OwnedFunctionBodyData::from(body);
+        assert_eq!(
+            owned.data,
+            &data[owned.module_offset..owned.module_offset + owned.data.len()]
+        );
+
+        let serialized = bincode::serialize(&owned).unwrap();
+        let deserialized: OwnedFunctionBodyData = bincode::deserialize(&serialized).unwrap();
+        assert_eq!(deserialized, owned);
+
+        let borrowed = deserialized.to_borrowed();
+        assert_eq!(borrowed.data, body.data);
+        assert_eq!(borrowed.module_offset, body.module_offset);
+    }
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "translator")]
+
+mod utils;
+
+use utils::{code, module, vector};
+use wasmer_compiler::ModuleEnvironment;
+use wasmer_types::{LocalFunctionIndex, Type};
+
+#[test]
+fn function_locals() {
+    let data = module(&[
+        // `(i32) -> ()`
+        (0x01, vector(1, &[0x60, 0x01, 0x7f, 0x00])),
+        (0x03, vector(2, &[0x00, 0x00])),
+        (
+            0x0a,
+            code(&[
+                // 3 i32, 2 f64 and 1 i32.
+                &[0x03, 0x03, 0x7f, 0x02, 0x7c, 0x01, 0x7f, 0x0b],
+                // No locals.
+                &[0x00, 0x0b],
+            ]),
+        ),
+    ]);
+
+    let translation = ModuleEnvironment::new().translate(&data).unwrap();
+    assert_eq!(
+        translation.function_locals(LocalFunctionIndex::from_u32(0)),
+        Some(&[(3, Type::I32), (2, Type::F64), (1, Type::I32)][..])
+    );
+    assert_eq!(
+        translation.function_locals(LocalFunctionIndex::from_u32(1)),
+        Some(&[][..])
+    );
+    assert_eq!(
+        translation.function_locals(LocalFunctionIndex::from_u32(2)),
+        None
+    );
+}
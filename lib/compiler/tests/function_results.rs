@@ -0,0 +1,81 @@
+#![cfg(feature = "translator")]
+
+mod utils;
+
+use utils::{code, module, name, vector};
+use wasmer_compiler::{ModuleEnvironment, WasmError};
+use wasmer_types::FunctionIndex;
+
+/// Types: `() -> (i32, i32)` and `() -> i32`.
+fn type_section() -> (u8, Vec<u8>) {
+    (
+        0x01,
+        vector(2, &[0x60, 0x00, 0x02, 0x7f, 0x7f, 0x60, 0x00, 0x01, 0x7f]),
+    )
+}
+
+#[test]
+fn defined_function_with_too_many_results() {
+    let data = module(&[
+        type_section(),
+        // Functions 0 and 1, returning one and two results.
+        (0x03, vector(2, &[0x01, 0x00])),
+        (
+            0x0a,
+            code(&[
+                &[0x00, 0x41, 0x00, 0x0b],
+                &[0x00, 0x41, 0x00, 0x41, 0x00, 0x0b],
+            ]),
+        ),
+    ]);
+
+    let error = ModuleEnvironment::new()
+        .with_max_function_results(Some(1))
+        .translate(&data)
+        .err()
+        .unwrap();
+    match error {
+        WasmError::TooManyResults { func, count, limit } => {
+            assert_eq!(func, FunctionIndex::from_u32(1));
+            assert_eq!(count, 2);
+            assert_eq!(limit, 1);
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+    assert_eq!(
+        error_message(&data, 1),
+        "Function 1 returns 2 results, but the compiler supports at most 1"
+    );
+
+    assert!(ModuleEnvironment::new()
+        .with_max_function_results(Some(2))
+        .translate(&data)
+        .is_ok());
+    assert!(ModuleEnvironment::new().translate(&data).is_ok());
+}
+
+#[test]
+fn imported_function_with_too_many_results() {
+    let data = module(&[
+        type_section(),
+        // `(import "m" "f" (func (type 0)))`
+        (
+            0x02,
+            vector(1, &[name("m"), name("f"), vec![0x00, 0x00]].concat()),
+        ),
+    ]);
+
+    assert_eq!(
+        error_message(&data, 1),
+        "Function 0 returns 2 results, but the compiler supports at most 1"
+    );
+}
+
+fn error_message(data: &[u8], limit: usize) -> String {
+    ModuleEnvironment::new()
+        .with_max_function_results(Some(limit))
+        .translate(data)
+        .err()
+        .unwrap()
+        .to_string()
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "translator")]
+
+mod utils;
+
+use utils::{leb128, module, vector};
+use wasmer_compiler::{ModuleEnvironment, WasmError};
+
+/// A module with a single section `id`, holding one entry made of
+/// `prefix` followed by the limits `minimum` and `maximum`.
+fn limits_module(id: u8, prefix: &[u8], minimum: u32, maximum: Option<u32>) -> Vec<u8> {
+    let mut entry = prefix.to_vec();
+    entry.push(if maximum.is_some() { 0x01 } else { 0x00 });
+    leb128(&mut entry, minimum as usize);
+    if let Some(maximum) = maximum {
+        leb128(&mut entry, maximum as usize);
+    }
+    module(&[(id, vector(1, &entry))])
+}
+
+fn memory(minimum: u32, maximum: Option<u32>) -> Vec<u8> {
+    limits_module(0x05, &[], minimum, maximum)
+}
+
+/// An import of the memory `env.memory`.
+fn memory_import(minimum: u32, maximum: Option<u32>) -> Vec<u8> {
+    limits_module(
+        0x02,
+        &[
+            0x03, b'e', b'n', b'v', 0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02,
+        ],
+        minimum,
+        maximum,
+    )
+}
+
+fn table(minimum: u32, maximum: Option<u32>) -> Vec<u8> {
+    limits_module(0x04, &[0x70], minimum, maximum)
+}
+
+fn translation_error(module: &[u8]) -> Option<String> {
+    match ModuleEnvironment::new().translate(module) {
+        Ok(_) => None,
+        Err(WasmError::InvalidWebAssembly { message, .. }) => Some(message),
+        Err(error) => panic!("unexpected error: {}", error),
+    }
+}
+
+#[test]
+fn memory_limits() {
+    const TOO_LARGE: &str = "memory size must be at most 65536 pages (4GiB)";
+    const MINIMUM_OVER_MAXIMUM: &str = "size minimum must not be greater than maximum";
+    let modules: [fn(u32, Option<u32>) -> Vec<u8>; 2] = [memory, memory_import];
+    for memory in &modules {
+        assert_eq!(translation_error(&memory(65536, None)), None);
+        assert_eq!(translation_error(&memory(65536, Some(65536))), None);
+        assert_eq!(
+            translation_error(&memory(65537, None)).as_deref(),
+            Some(TOO_LARGE)
+        );
+        assert_eq!(
+            translation_error(&memory(0, Some(65537))).as_deref(),
+            Some(TOO_LARGE)
+        );
+        assert_eq!(translation_error(&memory(2, Some(2))), None);
+        assert_eq!(
+            translation_error(&memory(3, Some(2))).as_deref(),
+            Some(MINIMUM_OVER_MAXIMUM)
+        );
+    }
+}
+
+#[test]
+fn table_limits() {
+    assert_eq!(translation_error(&table(u32::max_value(), None)), None);
+    assert_eq!(translation_error(&table(2, Some(2))), None);
+    assert_eq!(
+        translation_error(&table(3, Some(2))).as_deref(),
+        Some("size minimum must not be greater than maximum")
+    );
+}
@@ -0,0 +1,44 @@
+#![cfg(feature = "translator")]
+
+mod utils;
+
+use utils::{code, module, name, section, vector};
+use wasmer_compiler::ModuleEnvironment;
+use wasmer_types::FunctionIndex;
+
+/// A function `() -> ()` with an `i32` local, without a name section.
+fn unnamed_module() -> Vec<u8> {
+    module(&[
+        (0x01, vector(1, &[0x60, 0x00, 0x00])),
+        (0x03, vector(1, &[0x00])),
+        (0x0a, code(&[&[0x01, 0x01, 0x7f, 0x0b]])),
+    ])
+}
+
+#[test]
+fn local_names() {
+    let mut data = unnamed_module();
+    // A name section naming the function `f` and its local `x`.
+    let function_names = vector(1, &[vec![0x00], name("f")].concat());
+    let local_names = vector(1, &[vec![0x00, 0x01, 0x00], name("x")].concat());
+    let mut names = name("name");
+    section(&mut names, 0x01, &function_names);
+    section(&mut names, 0x02, &local_names);
+    section(&mut data, 0x00, &names);
+
+    let translation = ModuleEnvironment::new().translate(&data).unwrap();
+    let module = &translation.module;
+    let func_index = FunctionIndex::from_u32(0);
+    assert_eq!(module.function_names[&func_index], "f");
+    assert_eq!(module.local_names.len(), 1);
+    assert_eq!(module.local_names[&(func_index, 0)], "x");
+}
+
+#[test]
+fn no_name_section() {
+    let translation = ModuleEnvironment::new()
+        .translate(&unnamed_module())
+        .unwrap();
+    assert!(translation.module.function_names.is_empty());
+    assert!(translation.module.local_names.is_empty());
+}
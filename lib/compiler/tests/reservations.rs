@@ -0,0 +1,124 @@
+//! Modules declaring huge numbers of entries must not make the translator
+//! allocate memory for them before the entries are defined.
+#![cfg(feature = "translator")]
+
+mod utils;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use utils::{leb128, module, vector};
+use wasmer_compiler::ModuleEnvironment;
+
+/// Keeps track of the allocated bytes, and their peak.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Whether allocations are being measured, as the tests can't measure them
+/// concurrently.
+static MEASURING: AtomicBool = AtomicBool::new(false);
+
+struct MeasuringGuard;
+
+impl MeasuringGuard {
+    fn lock() -> Self {
+        while MEASURING
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            std::thread::yield_now();
+        }
+        Self
+    }
+}
+
+impl Drop for MeasuringGuard {
+    fn drop(&mut self) {
+        MEASURING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Returns the peak number of bytes allocated while running `f`.
+fn peak_allocation(f: impl FnOnce()) -> usize {
+    let _guard = MeasuringGuard::lock();
+    let start = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(start, Ordering::SeqCst);
+    f();
+    PEAK.load(Ordering::SeqCst) - start
+}
+
+#[test]
+fn huge_declared_counts() {
+    const HUGE: usize = 100_000_000;
+    let modules = vec![
+        ("types", module(&[(1, vector(HUGE, &[]))])),
+        ("functions", module(&[(3, vector(HUGE, &[]))])),
+        ("tables", module(&[(4, vector(HUGE, &[]))])),
+        ("memories", module(&[(5, vector(HUGE, &[]))])),
+        ("globals", module(&[(6, vector(HUGE, &[]))])),
+        ("exports", module(&[(7, vector(HUGE, &[]))])),
+        ("elements", module(&[(9, vector(HUGE, &[]))])),
+        // A passive element segment with a huge number of items.
+        ("element items", {
+            let mut segment = vec![0x01, 0x00];
+            leb128(&mut segment, HUGE);
+            module(&[(9, vector(1, &segment))])
+        }),
+        ("data", module(&[(11, vector(HUGE, &[]))])),
+        ("data count", {
+            let mut count = vec![];
+            leb128(&mut count, HUGE);
+            module(&[(12, count)])
+        }),
+    ];
+
+    for (name, data) in modules {
+        let peak = peak_allocation(|| {
+            let _ = ModuleEnvironment::new().translate(&data);
+        });
+        assert!(
+            peak < 1024 * 1024,
+            "translating a module declaring huge {} allocated {} bytes",
+            name,
+            peak
+        );
+    }
+}
+
+#[test]
+fn large_module() {
+    const FUNCTIONS: usize = 100_000;
+    // One `() -> ()` type, and empty function bodies.
+    let types = vector(1, &[0x60, 0x00, 0x00]);
+    let functions = vector(FUNCTIONS, &vec![0x00; FUNCTIONS]);
+    let code = vector(FUNCTIONS, &[0x02, 0x00, 0x0b].repeat(FUNCTIONS));
+    let data = module(&[(1, types), (3, functions), (10, code)]);
+
+    let peak = peak_allocation(|| {
+        let translation = ModuleEnvironment::new().translate(&data).unwrap();
+        assert_eq!(translation.module.functions.len(), FUNCTIONS);
+        assert_eq!(translation.function_body_inputs.len(), FUNCTIONS);
+    });
+    // The function entries are still reserved up front.
+    assert!(peak < 64 * 1024 * 1024, "allocated {} bytes", peak);
+}
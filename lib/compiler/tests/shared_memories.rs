@@ -0,0 +1,53 @@
+#![cfg(feature = "translator")]
+
+mod utils;
+
+use utils::{module, vector};
+use wasmer_compiler::{ModuleEnvironment, WasmError};
+use wasmer_types::{MemoryIndex, Pages};
+
+#[test]
+fn shared_memory_with_maximum() {
+    // `(memory 1 1 shared)`
+    let data = module(&[(0x05, vector(1, &[0x03, 0x01, 0x01]))]);
+
+    let translation = ModuleEnvironment::new()
+        .with_threads(true)
+        .translate(&data)
+        .unwrap();
+    let memory = &translation.module.memories[MemoryIndex::from_u32(0)];
+    assert!(memory.shared);
+    assert_eq!(memory.minimum, Pages(1));
+    assert_eq!(memory.maximum, Some(Pages(1)));
+}
+
+#[test]
+fn shared_memory_without_maximum() {
+    // `(memory 1 shared)`, which the threads proposal doesn't allow.
+    let data = module(&[(0x05, vector(1, &[0x02, 0x01]))]);
+
+    match ModuleEnvironment::new()
+        .with_threads(true)
+        .translate(&data)
+        .err()
+        .unwrap()
+    {
+        WasmError::Unsupported(message) => {
+            assert_eq!(message, "shared memories must declare a maximum size")
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+}
+
+#[test]
+fn shared_memory_without_threads() {
+    // `(memory 1 1 shared)`
+    let data = module(&[(0x05, vector(1, &[0x03, 0x01, 0x01]))]);
+
+    match ModuleEnvironment::new().translate(&data).err().unwrap() {
+        WasmError::Unsupported(message) => {
+            assert_eq!(message, "shared memories require the threads feature")
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+}
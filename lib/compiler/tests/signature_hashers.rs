@@ -0,0 +1,74 @@
+#![cfg(feature = "translator")]
+
+mod utils;
+
+use rustc_hash::FxHasher;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, BuildHasherDefault};
+use utils::{leb128, module, vector};
+use wasmer_compiler::ModuleEnvironment;
+use wasmer_types::{FunctionType, SignatureIndex};
+
+/// A module declaring every signature with up to five parameters twice,
+/// and a function of each of the declared types.
+fn many_signatures() -> Vec<u8> {
+    let mut params = vec![vec![]];
+    for len in 1..=5 {
+        for previous in params.clone().into_iter().filter(|p| p.len() == len - 1) {
+            for &ty in &[0x7f, 0x7e, 0x7d, 0x7c] {
+                let mut next = previous.clone();
+                next.push(ty);
+                params.push(next);
+            }
+        }
+    }
+
+    let count = params.len() * 2;
+    let mut types = Vec::new();
+    for params in params.iter().chain(params.iter()) {
+        types.push(0x60);
+        leb128(&mut types, params.len());
+        types.extend(params);
+        types.push(0x00);
+    }
+    let mut functions = Vec::new();
+    let mut code = Vec::new();
+    for index in 0..count {
+        leb128(&mut functions, index);
+        code.extend(&[0x02, 0x00, 0x0b]);
+    }
+
+    module(&[
+        (0x01, vector(count, &types)),
+        (0x03, vector(count, &functions)),
+        (0x0a, vector(count, &code)),
+    ])
+}
+
+fn deduplicate<S: BuildHasher>(
+    hash_builder: S,
+    data: &[u8],
+) -> (Vec<FunctionType>, Vec<SignatureIndex>) {
+    let translation = ModuleEnvironment::with_hasher(hash_builder)
+        .translate(data)
+        .unwrap();
+    let module = translation.module;
+    (
+        module.signatures.values().cloned().collect(),
+        module.functions.values().cloned().collect(),
+    )
+}
+
+#[test]
+fn hashers_deduplicate_identically() {
+    let data = many_signatures();
+    let (signatures, functions) = deduplicate(RandomState::new(), &data);
+    // Each signature is declared twice, but kept once.
+    assert_eq!(signatures.len(), functions.len() / 2);
+    assert_eq!(functions[..signatures.len()], functions[signatures.len()..]);
+
+    assert_eq!(
+        deduplicate(BuildHasherDefault::<FxHasher>::default(), &data),
+        (signatures, functions)
+    );
+}
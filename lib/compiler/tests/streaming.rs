@@ -0,0 +1,169 @@
+#![cfg(feature = "translator")]
+
+mod utils;
+
+use utils::{code, module, name, vector};
+use wasmer_compiler::{ModuleEnvironment, ModuleInfoTranslation, WasmError};
+use wasmer_types::LocalFunctionIndex;
+
+/// A module with an imported function, three functions, a memory with a
+/// data segment, and exports.
+fn streamed_module() -> Vec<u8> {
+    let exports = [
+        name("run"),
+        vec![0x00, 0x01],
+        name("increment"),
+        vec![0x00, 0x02],
+        name("memory"),
+        vec![0x02, 0x00],
+    ]
+    .concat();
+    module(&[
+        // `() -> ()` and `(i32) -> i32`
+        (
+            0x01,
+            vector(2, &[0x60, 0x00, 0x00, 0x60, 0x01, 0x7f, 0x01, 0x7f]),
+        ),
+        (
+            0x02,
+            vector(
+                1,
+                &[name("env"), name("callback"), vec![0x00, 0x00]].concat(),
+            ),
+        ),
+        (0x03, vector(3, &[0x00, 0x01, 0x01])),
+        (0x05, vector(1, &[0x00, 0x01])),
+        (0x07, vector(3, &exports)),
+        (
+            0x0a,
+            code(&[
+                &[0x00, 0x10, 0x00, 0x0b],
+                &[0x00, 0x20, 0x00, 0x41, 0x01, 0x6a, 0x0b],
+                &[0x01, 0x01, 0x7f, 0x20, 0x00, 0x0b],
+            ]),
+        ),
+        (
+            0x0b,
+            vector(
+                1,
+                &[vec![0x00, 0x41, 0x10, 0x0b], name("hello streaming!")].concat(),
+            ),
+        ),
+    ])
+}
+
+fn assert_same_translation(a: &ModuleInfoTranslation, b: &ModuleInfoTranslation) {
+    assert_eq!(
+        a.module.imports.iter().collect::<Vec<_>>(),
+        b.module.imports.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        a.module.exports.iter().collect::<Vec<_>>(),
+        b.module.exports.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        a.module.signatures.values().collect::<Vec<_>>(),
+        b.module.signatures.values().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        a.module.functions.values().collect::<Vec<_>>(),
+        b.module.functions.values().collect::<Vec<_>>()
+    );
+    assert_eq!(a.function_body_inputs.len(), b.function_body_inputs.len());
+    for (index, body) in a.function_body_inputs.iter() {
+        let other = &b.function_body_inputs[index];
+        assert_eq!(body.data, other.data);
+        assert_eq!(body.module_offset, other.module_offset);
+        assert_eq!(a.function_locals(index), b.function_locals(index));
+    }
+    assert_eq!(
+        a.data_initializers
+            .iter()
+            .map(|init| (init.location.offset, &init.data))
+            .collect::<Vec<_>>(),
+        b.data_initializers
+            .iter()
+            .map(|init| (init.location.offset, &init.data))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn streaming_translation() {
+    let data = streamed_module();
+    let translation = ModuleEnvironment::new().translate(&data).unwrap();
+    assert_eq!(translation.module.functions.len(), 4);
+    assert_eq!(
+        translation.function_locals(LocalFunctionIndex::from_u32(2)),
+        Some(&[(1, wasmer_types::Type::I32)][..])
+    );
+
+    let mut translator = ModuleEnvironment::new().translate_streaming();
+    for chunk in data.chunks(64) {
+        translator.push(chunk).unwrap();
+    }
+    let streamed = translator.finish().unwrap();
+    assert_same_translation(&translation, &streamed);
+}
+
+#[test]
+fn streaming_byte_by_byte() {
+    let data = streamed_module();
+    let translation = ModuleEnvironment::new().translate(&data).unwrap();
+    let mut translator = ModuleEnvironment::new().translate_streaming();
+    for chunk in data.chunks(1) {
+        translator.push(chunk).unwrap();
+    }
+    assert_same_translation(&translation, &translator.finish().unwrap());
+}
+
+#[test]
+fn streamed_translation_owns_its_data() {
+    let mut translator = ModuleEnvironment::new().translate_streaming();
+    {
+        let data = streamed_module();
+        for chunk in data.chunks(64) {
+            translator.push(chunk).unwrap();
+        }
+    }
+    let streamed = translator.finish().unwrap();
+    let translation = ModuleEnvironment::new()
+        .translate(&streamed_module())
+        .unwrap();
+    assert_same_translation(&translation, &streamed);
+}
+
+#[test]
+fn malformed_section_fails_on_push() {
+    // The last type index of the function section doesn't end.
+    let mut data = streamed_module();
+    let position = data
+        .windows(6)
+        .position(|window| window == [0x03, 0x04, 0x03, 0x00, 0x01, 0x01])
+        .unwrap();
+    data[position + 5] = 0x81;
+
+    let mut translator = ModuleEnvironment::new().translate_streaming();
+    translator.push(&data[..position]).unwrap();
+    assert!(translator.push(&data[position..position + 6]).is_err());
+}
+
+#[test]
+fn truncated_stream() {
+    let data = streamed_module();
+    let mut translator = ModuleEnvironment::new().translate_streaming();
+    translator.push(&data[..data.len() - 1]).unwrap();
+    match translator.finish() {
+        Err(WasmError::InvalidWebAssembly { .. }) => {}
+        _ => panic!("a truncated module was translated"),
+    }
+}
+
+#[test]
+fn invalid_header() {
+    let mut translator = ModuleEnvironment::new().translate_streaming();
+    match translator.push(b"\0wasm") {
+        Err(WasmError::InvalidWebAssembly { offset: 0, .. }) => {}
+        _ => panic!("an invalid header was accepted"),
+    }
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "translator")]
+
+use wasmer_compiler::ModuleTranslationState;
+use wasmer_types::{Features, FunctionType, GlobalType, MemoryType, Mutability, Type};
+use wasmer_vm::ModuleInfo;
+
+#[test]
+fn typecheck_valid_function() {
+    let state = ModuleTranslationState::new();
+    let ty = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+    // (local.get 0) (local.get 1) (i32.add)
+    let body = [0x00, 0x20, 0x00, 0x20, 0x01, 0x6a, 0x0b];
+    assert_eq!(
+        state.typecheck_function(&ModuleInfo::new(), &Features::default(), &body, &ty),
+        Ok(())
+    );
+}
+
+#[test]
+fn typecheck_stack_mismatch() {
+    let state = ModuleTranslationState::new();
+    let module = ModuleInfo::new();
+    let features = Features::default();
+    let ty = FunctionType::new(vec![], vec![Type::I32]);
+    // (i32.const 1) (i64.const 2) (i32.add)
+    let body = [0x00, 0x41, 0x01, 0x42, 0x02, 0x6a, 0x0b];
+    let (offset, error) = state
+        .typecheck_function(&module, &features, &body, &ty)
+        .unwrap_err();
+    // The offset of the `i32.add`.
+    assert_eq!(offset, 5);
+    assert!(error.message.contains("mismatch"), "{}", error);
+
+    // The results of the function are checked too.
+    let body = [0x00, 0x42, 0x01, 0x0b];
+    let (offset, _) = state
+        .typecheck_function(&module, &features, &body, &ty)
+        .unwrap_err();
+    assert_eq!(offset, 3);
+}
+
+#[test]
+fn typecheck_with_module_resources() {
+    let state = ModuleTranslationState::new();
+    let mut module = ModuleInfo::new();
+    module
+        .globals
+        .push(GlobalType::new(Type::I64, Mutability::Const));
+    module.memories.push(MemoryType::new(1, None, false));
+    let ty = FunctionType::new(vec![], vec![Type::I64]);
+
+    // (global.get 0) (i64.load (i32.const 0)) (i64.add)
+    let body = [0x00, 0x23, 0x00, 0x41, 0x00, 0x29, 0x03, 0x00, 0x7c, 0x0b];
+    assert_eq!(
+        state.typecheck_function(&module, &Features::default(), &body, &ty),
+        Ok(())
+    );
+
+    // (global.get 0) (i32.load (i32.const 0)) (i64.add)
+    let body = [0x00, 0x23, 0x00, 0x41, 0x00, 0x28, 0x02, 0x00, 0x7c, 0x0b];
+    let (offset, _) = state
+        .typecheck_function(&module, &Features::default(), &body, &ty)
+        .unwrap_err();
+    assert_eq!(offset, 8);
+
+    // The global doesn't exist in an empty module.
+    let body = [0x00, 0x23, 0x00, 0x0b];
+    assert!(state
+        .typecheck_function(&ModuleInfo::new(), &Features::default(), &body, &ty)
+        .is_err());
+}
+
+#[test]
+fn typecheck_with_features() {
+    let state = ModuleTranslationState::new();
+    let mut module = ModuleInfo::new();
+    module.memories.push(MemoryType::new(1, None, false));
+    let ty = FunctionType::new(vec![], vec![]);
+    // (memory.fill (i32.const 0) (i32.const 0) (i32.const 0))
+    let body = [
+        0x00, 0x41, 0x00, 0x41, 0x00, 0x41, 0x00, 0xfc, 0x0b, 0x00, 0x0b,
+    ];
+
+    let mut features = Features::default();
+    features.bulk_memory(true);
+    assert_eq!(
+        state.typecheck_function(&module, &features, &body, &ty),
+        Ok(())
+    );
+    features.bulk_memory(false);
+    let (offset, _) = state
+        .typecheck_function(&module, &features, &body, &ty)
+        .unwrap_err();
+    assert_eq!(offset, 7);
+}
@@ -0,0 +1,63 @@
+//! Helpers to write WebAssembly binaries by hand, for the modules that
+//! the text format can't express, or only with a huge text.
+#![allow(dead_code)]
+
+/// The magic number and the version starting a module.
+pub const HEADER: &[u8] = b"\0asm\x01\0\0\0";
+
+/// Appends `value` to `bytes` as an unsigned LEB128.
+pub fn leb128(bytes: &mut Vec<u8>, mut value: usize) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Appends the section `id` with `contents` to `bytes`.
+pub fn section(bytes: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    bytes.push(id);
+    leb128(bytes, contents.len());
+    bytes.extend_from_slice(contents);
+}
+
+/// Builds the contents of a section declaring `count` entries, followed by
+/// `entries`.
+pub fn vector(count: usize, entries: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![];
+    leb128(&mut bytes, count);
+    bytes.extend_from_slice(entries);
+    bytes
+}
+
+/// Builds a module with the given sections, as `(id, contents)`.
+pub fn module(sections: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = HEADER.to_vec();
+    for (id, contents) in sections {
+        section(&mut bytes, *id, contents);
+    }
+    bytes
+}
+
+/// Encodes `name`, prefixed by its length.
+pub fn name(name: &str) -> Vec<u8> {
+    let mut bytes = vec![];
+    leb128(&mut bytes, name.len());
+    bytes.extend_from_slice(name.as_bytes());
+    bytes
+}
+
+/// Builds the contents of a code section with the function `bodies`, each
+/// made of its locals and its instructions.
+pub fn code(bodies: &[&[u8]]) -> Vec<u8> {
+    let mut entries = vec![];
+    for body in bodies {
+        leb128(&mut entries, body.len());
+        entries.extend_from_slice(body);
+    }
+    vector(bodies.len(), &entries)
+}
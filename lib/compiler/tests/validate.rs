@@ -0,0 +1,48 @@
+#![cfg(feature = "translator")]
+
+mod utils;
+
+use utils::{code, module, vector, HEADER};
+use wasmer_compiler::{validate, WasmError};
+use wasmer_types::Features;
+
+/// A function `(v128) -> ()`, which needs the SIMD proposal.
+fn simd_module() -> Vec<u8> {
+    module(&[
+        (0x01, vector(1, &[0x60, 0x01, 0x7b, 0x00])),
+        (0x03, vector(1, &[0x00])),
+        (0x0a, code(&[&[0x00, 0x0b]])),
+    ])
+}
+
+#[test]
+fn valid_module() {
+    let mut features = Features::new();
+    features.simd(true);
+    assert!(validate(&simd_module(), &features).is_ok());
+    assert!(validate(HEADER, &Features::new()).is_ok());
+}
+
+#[test]
+fn disabled_feature() {
+    let mut features = Features::new();
+    features.simd(false);
+    match validate(&simd_module(), &features) {
+        Err(WasmError::InvalidWebAssembly { .. }) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn truncated_module() {
+    let mut features = Features::new();
+    features.simd(true);
+    let data = simd_module();
+    // Truncated in the header, and in the body of the function.
+    for &len in &[4, data.len() - 1] {
+        match validate(&data[..len], &features) {
+            Err(WasmError::InvalidWebAssembly { .. }) => {}
+            result => panic!("unexpected result for {} bytes: {:?}", len, result),
+        }
+    }
+}
@@ -2,33 +2,121 @@
 //! done as separate steps.
 
 use crate::engine::{JITEngine, JITEngineInner};
-use crate::link::link_module;
+use crate::link::link_module_with_entries;
 #[cfg(feature = "compiler")]
 use crate::serialize::SerializableCompilation;
 use crate::serialize::SerializableModule;
+use crate::CodeMemory;
+use std::borrow::Cow;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use wasmer_compiler::{CompileError, Features, Triple};
 #[cfg(feature = "compiler")]
-use wasmer_compiler::{CompileModuleInfo, ModuleEnvironment};
+use wasmer_compiler::{
+    CompilationProgress, CompiledModule, CompilerConfig, FunctionBodyData, ModuleEnvironment,
+};
+use wasmer_compiler::{
+    CompileError, CustomSection, CustomSectionProtection, Features, SectionBody, Triple,
+};
+use wasmer_engine::{
+    register_frame_info, Artifact, DeserializeError, GlobalFrameInfoRegistration, LinkError,
+    ModuleFootprint, SerializeError,
+};
+#[cfg(feature = "compiler")]
 use wasmer_engine::{
-    register_frame_info, Artifact, DeserializeError, GlobalFrameInfoRegistration, SerializeError,
+    register_function_frame_info, Engine, SerializableFunctionFrameInfo, Tunables,
 };
 #[cfg(feature = "compiler")]
-use wasmer_engine::{Engine, SerializableFunctionFrameInfo, Tunables};
+use wasmer_types::entity::EntityRef;
 use wasmer_types::entity::{BoxedSlice, PrimaryMap};
 use wasmer_types::{
     FunctionIndex, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer, SignatureIndex,
     TableIndex,
 };
-use wasmer_vm::{FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMSharedSignatureIndex};
+#[cfg(feature = "compiler")]
+use wasmer_types::{MemoryType, Pages};
+use wasmer_vm::{
+    ExportResolution, FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMFunctionBody,
+    VMSharedSignatureIndex,
+};
 
 /// A compiled wasm module, ready to be instantiated.
 pub struct JITArtifact {
     serializable: SerializableModule,
     finished_functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
+    /// The entries the functions are called through, if they can be
+    /// recompiled.
+    function_entries: Option<BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>>,
     finished_dynamic_function_trampolines: BoxedSlice<FunctionIndex, FunctionBodyPtr>,
     signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
+    export_resolutions: Vec<ExportResolution>,
     frame_info_registration: Mutex<Option<GlobalFrameInfoRegistration>>,
+    /// Keeps the functions mapped while the artifact is alive, whether or
+    /// not the engine that compiled it is.
+    _code_memory: Arc<CodeMemory>,
+    #[cfg(feature = "compiler")]
+    recompilation: Option<Recompilation>,
+}
+
+/// What a `JITArtifact` compiled by a recompilable engine keeps to
+/// recompile its functions.
+#[cfg(feature = "compiler")]
+struct Recompilation {
+    engine: JITEngine,
+    wasm: Vec<u8>,
+    /// Whether the dead functions were pruned when compiling the artifact,
+    /// which renumbers its functions.
+    prune_dead_functions: bool,
+    /// The address of the code the entry of each function jumps to.
+    entry_slots: Box<[AtomicUsize]>,
+    /// The frame info registrations of the recompiled functions.
+    frame_info_registrations: Mutex<Vec<GlobalFrameInfoRegistration>>,
+}
+
+/// The body of the functions compiled along with a recompiled function.
+#[cfg(feature = "compiler")]
+const STUB_FUNCTION_BODY: &[u8] = &[
+    0x00, // no locals
+    0x00, // unreachable
+    0x0b, // end
+];
+
+/// The length of the entry of a function that can be recompiled.
+const ENTRY_LEN: usize = 16;
+
+/// Returns the entry of a function that can be recompiled, jumping to the
+/// address held by `slot`.
+#[cfg(target_arch = "x86_64")]
+fn entry(slot: &AtomicUsize) -> Result<[u8; ENTRY_LEN], CompileError> {
+    let mut entry = [
+        0x49, 0xbb, 0, 0, 0, 0, 0, 0, 0, 0, // movabs r11, slot
+        0x41, 0xff, 0x23, // jmp qword ptr [r11]
+        0xcc, 0xcc, 0xcc, // int3
+    ];
+    entry[2..10].copy_from_slice(&(slot as *const AtomicUsize as u64).to_le_bytes());
+    Ok(entry)
+}
+
+/// Returns the entry of a function that can be recompiled, jumping to the
+/// address held by `slot`.
+#[cfg(not(target_arch = "x86_64"))]
+fn entry(_slot: &AtomicUsize) -> Result<[u8; ENTRY_LEN], CompileError> {
+    Err(CompileError::UnsupportedTarget(
+        "Recompiling functions is only supported on x86_64".to_string(),
+    ))
+}
+
+/// Returns the error to compile or deserialize a module with, when its
+/// code can't be linked.
+///
+/// The relocations and the libcalls are resolved for the host, so the
+/// code can only fail to link because the target isn't supported, or
+/// because it's too large to be reached by its relocations.
+fn link_error(error: LinkError) -> CompileError {
+    match error {
+        LinkError::RelocationOutOfRange { .. } => CompileError::ResourceLimit(error.to_string()),
+        error => CompileError::UnsupportedTarget(error.to_string()),
+    }
 }
 
 impl JITArtifact {
@@ -40,57 +128,47 @@ impl JITArtifact {
     }
 
     /// Compile a data buffer into a `JITArtifact`, which may then be instantiated.
+    ///
+    /// The compiled functions are counted in `progress`, which can cancel
+    /// the compilation.
     #[cfg(feature = "compiler")]
     pub fn new(
         jit: &JITEngine,
         data: &[u8],
         tunables: &dyn Tunables,
+        progress: &CompilationProgress,
     ) -> Result<Self, CompileError> {
-        let environ = ModuleEnvironment::new();
         let mut inner_jit = jit.inner_mut();
         let features = inner_jit.features();
-
-        let translation = environ.translate(data).map_err(CompileError::Wasm)?;
-
-        let memory_styles: PrimaryMap<MemoryIndex, MemoryStyle> = translation
-            .module
-            .memories
-            .values()
-            .map(|memory_type| tunables.memory_style(memory_type))
-            .collect();
-        let table_styles: PrimaryMap<TableIndex, TableStyle> = translation
-            .module
-            .tables
-            .values()
-            .map(|table_type| tunables.table_style(table_type))
-            .collect();
-
-        let compile_info = CompileModuleInfo {
-            module: Arc::new(translation.module),
-            features: features.clone(),
-            memory_styles,
-            table_styles,
-        };
-
         let compiler = inner_jit.compiler()?;
+        let prune_dead_functions = compiler.prune_dead_functions();
+        let unchecked = inner_jit.unchecked();
+        // The memories of unchecked artifacts are only protected by their
+        // guard pages.
+        let memory_style = |memory_type: &MemoryType| match tunables.memory_style(memory_type) {
+            MemoryStyle::Dynamic { offset_guard_size } if unchecked => MemoryStyle::Static {
+                bound: Pages::max_value(),
+                offset_guard_size,
+            },
+            style => style,
+        };
 
         // Compile the Module
-        let compilation = compiler.compile_module(
+        let CompiledModule {
+            compile_info,
+            compilation,
+            data_initializers,
+        } = compiler.compile_wasm(
             &jit.target(),
-            &compile_info,
-            translation.module_translation.as_ref().unwrap(),
-            translation.function_body_inputs,
+            data,
+            features,
+            &memory_style,
+            &|table_type| tunables.table_style(table_type),
+            progress,
         )?;
         let function_call_trampolines = compilation.get_function_call_trampolines();
         let dynamic_function_trampolines = compilation.get_dynamic_function_trampolines();
 
-        let data_initializers = translation
-            .data_initializers
-            .iter()
-            .map(OwnedDataInitializer::new)
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
-
         let frame_infos = compilation
             .get_frame_info()
             .values()
@@ -108,12 +186,187 @@ impl JITArtifact {
             custom_section_relocations: compilation.get_custom_section_relocations(),
             debug: compilation.get_debug(),
         };
-        let serializable = SerializableModule {
+        let mut serializable = SerializableModule {
             compilation: serializable_compilation,
             compile_info,
             data_initializers,
+            footprint: ModuleFootprint::from_compilation(&compilation),
+            unchecked,
         };
-        Self::from_parts(&mut inner_jit, serializable)
+        // The footprint has a fixed size, so recording the serialized size
+        // doesn't change it.
+        serializable.footprint.serialized_bytes = Self::MAGIC_HEADER.len()
+            + bincode::serialized_size(&serializable)
+                .map_err(|e| CompileError::Codegen(format!("{:?}", e)))? as usize;
+        if !inner_jit.recompilable() {
+            return Self::from_parts(&mut inner_jit, serializable);
+        }
+        let entry_slots = serializable
+            .compilation
+            .function_bodies
+            .keys()
+            .map(|_| AtomicUsize::new(0))
+            .collect::<Box<[_]>>();
+        let mut artifact =
+            Self::from_parts_with_entries(&mut inner_jit, serializable, Some(&entry_slots))?;
+        artifact.recompilation = Some(Recompilation {
+            engine: jit.clone(),
+            wasm: data.to_vec(),
+            prune_dead_functions,
+            entry_slots,
+            frame_info_registrations: Mutex::new(Vec::new()),
+        });
+        Ok(artifact)
+    }
+
+    /// Recompiles the function `index` with the compiler of `config`, and
+    /// makes it run the new code from now on.
+    ///
+    /// Only this function is compiled, the other ones are replaced by stubs
+    /// in the compiled module. The functions are called through an entry
+    /// jumping to the address of their code held in a slot, and the slot of
+    /// the function is set to the new code once it's ready, with an atomic
+    /// store. The calls starting afterwards, including the ones of existing
+    /// instances and of the functions calling it directly, run the new
+    /// code, and the calls in progress finish running the previous code,
+    /// which stays allocated. The new code has no DWARF unwind information,
+    /// so backtraces stop at its frames.
+    ///
+    /// It fails if the artifact wasn't compiled by a recompilable engine,
+    /// see [`JIT::recompilable`], which keeps the WebAssembly module.
+    ///
+    /// [`JIT::recompilable`]: crate::JIT::recompilable
+    #[cfg(feature = "compiler")]
+    pub fn recompile_function(
+        &self,
+        index: LocalFunctionIndex,
+        config: &dyn CompilerConfig,
+    ) -> Result<(), CompileError> {
+        let recompilation = self.recompilation.as_ref().ok_or_else(|| {
+            CompileError::Codegen(
+                "Only the artifacts of a recompilable engine can recompile their functions"
+                    .to_string(),
+            )
+        })?;
+        let function_entries = self
+            .function_entries
+            .as_ref()
+            .expect("the functions of recompilable artifacts have entries");
+        if self.finished_functions.get(index).is_none() {
+            return Err(CompileError::Codegen(format!(
+                "No function to recompile at {}",
+                index.index()
+            )));
+        }
+
+        let compiler = config.compiler();
+        let mut translation = ModuleEnvironment::new()
+            .with_max_function_results(compiler.max_function_results())
+            .with_threads(self.serializable.compile_info.features.threads)
+            .translate(&recompilation.wasm)?;
+        if recompilation.prune_dead_functions {
+            translation.prune_dead_functions()?;
+        }
+        let mut function_body_inputs = translation.function_body_inputs;
+        for (i, input) in function_body_inputs.iter_mut() {
+            if i != index {
+                *input = FunctionBodyData {
+                    data: Cow::Borrowed(STUB_FUNCTION_BODY),
+                    module_offset: input.module_offset,
+                };
+            }
+        }
+        let compile_info = &self.serializable.compile_info;
+        let compilation = compiler.compile_module(
+            &recompilation.engine.target(),
+            compile_info,
+            translation.module_translation.as_ref().unwrap(),
+            function_body_inputs,
+            &CompilationProgress::new(),
+        )?;
+        let function = compilation.get(index);
+
+        // The unwind information of the stubs would shadow the one of the
+        // previous code of the functions, so it's left out.
+        let eh_frame = compilation.get_debug().map(|debug| debug.eh_frame);
+        let custom_sections = compilation
+            .get_custom_sections()
+            .iter()
+            .map(|(section_index, section)| {
+                if Some(section_index) == eh_frame {
+                    CustomSection {
+                        protection: section.protection.clone(),
+                        bytes: SectionBody::new_with_vec(Vec::new()),
+                        relocations: Vec::new(),
+                    }
+                } else {
+                    section.clone()
+                }
+            })
+            .collect::<PrimaryMap<_, _>>();
+        let section_relocations = custom_sections
+            .values()
+            .map(|section| section.relocations.clone())
+            .collect::<PrimaryMap<_, _>>();
+
+        let mut inner_jit = recompilation.engine.inner_mut();
+        let mut functions = PrimaryMap::<LocalFunctionIndex, _>::new();
+        functions.push(function.body.clone());
+        let (allocated_functions, _, _, allocated_sections) = inner_jit.allocate(
+            &compile_info.module,
+            &functions,
+            &PrimaryMap::new(),
+            &PrimaryMap::new(),
+            &custom_sections,
+        )?;
+        let body = allocated_functions[LocalFunctionIndex::new(0)];
+
+        // Only the new code is linked, and it calls the other functions
+        // through their entries.
+        let functions = self
+            .finished_functions
+            .iter()
+            .map(|(i, finished)| if i == index { body } else { *finished })
+            .collect::<PrimaryMap<_, _>>();
+        let relocations = functions
+            .keys()
+            .map(|i| {
+                if i == index {
+                    function.relocations.clone()
+                } else {
+                    Vec::new()
+                }
+            })
+            .collect::<PrimaryMap<_, _>>();
+        let libcall_resolver = inner_jit.libcall_resolver();
+        link_module_with_entries(
+            &compile_info.module,
+            &functions,
+            function_entries,
+            &compilation.get_jt_offsets(),
+            relocations,
+            &allocated_sections,
+            &section_relocations,
+            libcall_resolver.as_deref(),
+        )
+        .map_err(link_error)?;
+        inner_jit.publish_compiled_code();
+        inner_jit.publish_eh_frame(None)?;
+        drop(inner_jit);
+
+        recompilation
+            .frame_info_registrations
+            .lock()
+            .unwrap()
+            .push(register_function_frame_info(
+                compile_info.module.clone(),
+                index,
+                body,
+                function.frame_info.clone(),
+            ));
+        recompilation.entry_slots[index.index()]
+            .store(unsafe { (**body).as_ptr() } as usize, Ordering::SeqCst);
+        Ok(())
     }
 
     /// Compile a data buffer into a `JITArtifact`, which may then be instantiated.
@@ -139,6 +392,13 @@ impl JITArtifact {
 
         let serializable: SerializableModule = bincode::deserialize(inner_bytes)
             .map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))?;
+        if serializable.unchecked != jit.inner().unchecked() {
+            return Err(DeserializeError::Incompatible(if serializable.unchecked {
+                "The artifact runs unchecked, but the engine isn't".to_string()
+            } else {
+                "The engine runs unchecked, but the artifact isn't".to_string()
+            }));
+        }
 
         Self::from_parts(&mut jit.inner_mut(), serializable).map_err(DeserializeError::Compiler)
     }
@@ -148,6 +408,34 @@ impl JITArtifact {
         inner_jit: &mut JITEngineInner,
         serializable: SerializableModule,
     ) -> Result<Self, CompileError> {
+        Self::from_parts_with_entries(inner_jit, serializable, None)
+    }
+
+    /// Construct a `JITArtifact` from component parts, whose functions are
+    /// called through entries jumping to the address in their slot of
+    /// `entry_slots`, if any, which is set to their code.
+    fn from_parts_with_entries(
+        inner_jit: &mut JITEngineInner,
+        serializable: SerializableModule,
+        entry_slots: Option<&[AtomicUsize]>,
+    ) -> Result<Self, CompileError> {
+        // The entries are allocated after the custom sections, in the same
+        // executable memory as the functions.
+        let mut custom_sections = Cow::Borrowed(&serializable.compilation.custom_sections);
+        let entries_section = match entry_slots {
+            Some(entry_slots) => {
+                let mut entries = Vec::with_capacity(entry_slots.len() * ENTRY_LEN);
+                for slot in entry_slots {
+                    entries.extend_from_slice(&entry(slot)?);
+                }
+                Some(custom_sections.to_mut().push(CustomSection {
+                    protection: CustomSectionProtection::ReadExecute,
+                    bytes: SectionBody::new_with_vec(entries),
+                    relocations: Vec::new(),
+                }))
+            }
+            None => None,
+        };
         let (
             finished_functions,
             _finished_function_call_trampolines,
@@ -158,17 +446,37 @@ impl JITArtifact {
             &serializable.compilation.function_bodies,
             &serializable.compilation.function_call_trampolines,
             &serializable.compilation.dynamic_function_trampolines,
-            &serializable.compilation.custom_sections,
+            &custom_sections,
         )?;
+        let function_entries = match (entry_slots, entries_section) {
+            (Some(entry_slots), Some(entries_section)) => {
+                let entries = *custom_sections[entries_section] as *mut VMFunctionBody;
+                Some(
+                    finished_functions
+                        .iter()
+                        .map(|(index, body)| {
+                            let slot = &entry_slots[index.index()];
+                            slot.store(unsafe { (***body).as_ptr() } as usize, Ordering::SeqCst);
+                            let entry = unsafe { entries.add(index.index() * ENTRY_LEN) };
+                            FunctionBodyPtr(ptr::slice_from_raw_parts_mut(entry, ENTRY_LEN))
+                        })
+                        .collect::<PrimaryMap<_, _>>(),
+                )
+            }
+            _ => None,
+        };
 
-        link_module(
+        link_module_with_entries(
             &serializable.compile_info.module,
             &finished_functions,
+            function_entries.as_ref().unwrap_or(&finished_functions),
             &serializable.compilation.function_jt_offsets,
             serializable.compilation.function_relocations.clone(),
             &custom_sections,
             &serializable.compilation.custom_section_relocations,
-        );
+            inner_jit.libcall_resolver().as_deref(),
+        )
+        .map_err(link_error)?;
 
         // Compute indices into the shared signature table.
         let signatures = {
@@ -201,16 +509,23 @@ impl JITArtifact {
         inner_jit.publish_eh_frame(eh_frame)?;
 
         let finished_functions = finished_functions.into_boxed_slice();
+        let function_entries = function_entries.map(PrimaryMap::into_boxed_slice);
         let finished_dynamic_function_trampolines =
             finished_dynamic_function_trampolines.into_boxed_slice();
         let signatures = signatures.into_boxed_slice();
 
+        let export_resolutions = serializable.compile_info.module.export_resolutions();
         Ok(Self {
             serializable,
             finished_functions,
+            function_entries,
             finished_dynamic_function_trampolines,
             signatures,
+            export_resolutions,
             frame_info_registration: Mutex::new(None),
+            _code_memory: inner_jit.last_code_memory(),
+            #[cfg(feature = "compiler")]
+            recompilation: None,
         })
     }
 
@@ -254,6 +569,10 @@ impl Artifact for JITArtifact {
         &self.serializable.compile_info.features
     }
 
+    fn unchecked(&self) -> bool {
+        self.serializable.unchecked
+    }
+
     fn data_initializers(&self) -> &[OwnedDataInitializer] {
         &*self.serializable.data_initializers
     }
@@ -267,7 +586,9 @@ impl Artifact for JITArtifact {
     }
 
     fn finished_functions(&self) -> &BoxedSlice<LocalFunctionIndex, FunctionBodyPtr> {
-        &self.finished_functions
+        self.function_entries
+            .as_ref()
+            .unwrap_or(&self.finished_functions)
     }
 
     // TODO: return *const instead of *mut
@@ -279,6 +600,14 @@ impl Artifact for JITArtifact {
         &self.signatures
     }
 
+    fn export_resolutions(&self) -> &[ExportResolution] {
+        &self.export_resolutions
+    }
+
+    fn footprint(&self) -> ModuleFootprint {
+        self.serializable.footprint
+    }
+
     fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
         // let mut s = flexbuffers::FlexbufferSerializer::new();
         // self.serializable.serialize(&mut s).map_err(|e| SerializeError::Generic(format!("{:?}", e)));
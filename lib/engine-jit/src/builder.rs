@@ -1,5 +1,7 @@
-use crate::JITEngine;
+use crate::{JITEngine, LibCallResolver};
+use std::sync::Arc;
 use wasmer_compiler::{CompilerConfig, Features, Target};
+use wasmer_vm::libcalls::LibCall;
 
 /// The JIT builder
 pub struct JIT<'a> {
@@ -7,6 +9,10 @@ pub struct JIT<'a> {
     compiler_config: Option<&'a dyn CompilerConfig>,
     target: Option<Target>,
     features: Option<Features>,
+    align_functions: Option<usize>,
+    unchecked: bool,
+    recompilable: bool,
+    libcall_resolver: Option<Arc<LibCallResolver>>,
 }
 
 impl<'a> JIT<'a> {
@@ -16,6 +22,10 @@ impl<'a> JIT<'a> {
             compiler_config: Some(compiler_config),
             target: None,
             features: None,
+            align_functions: None,
+            unchecked: false,
+            recompilable: false,
+            libcall_resolver: None,
         }
     }
 
@@ -25,6 +35,10 @@ impl<'a> JIT<'a> {
             compiler_config: None,
             target: None,
             features: None,
+            align_functions: None,
+            unchecked: false,
+            recompilable: false,
+            libcall_resolver: None,
         }
     }
 
@@ -40,11 +54,103 @@ impl<'a> JIT<'a> {
         self
     }
 
+    /// Set the alignment of the start of each function in executable
+    /// memory, 16 bytes by default.
+    ///
+    /// The padding between functions is filled with trap instructions. The
+    /// alignment must be a power of two, no larger than the page size.
+    pub fn align_functions(mut self, align_functions: usize) -> Self {
+        assert!(
+            align_functions.is_power_of_two() && align_functions <= region::page::size(),
+            "invalid function alignment: {}",
+            align_functions
+        );
+        self.align_functions = Some(align_functions);
+        self
+    }
+
+    /// Make the engine run its artifacts unchecked, for trusted modules
+    /// that are known never to trap, e.g. because they were verified
+    /// offline.
+    ///
+    /// Unchecked artifacts skip what protects the host from the faults of
+    /// WebAssembly code:
+    ///
+    /// - their memories are compiled with the static style, without bounds
+    ///   checks: only the guard pages protect the host from out of bounds
+    ///   accesses, and reserving them requires a 64-bit host;
+    /// - instantiating them doesn't install the signal handlers, so that
+    ///   they're only installed if a checked artifact is instantiated;
+    /// - calling their functions doesn't set up the catching of traps.
+    ///
+    /// Serialized artifacts record whether they run unchecked, and only an
+    /// engine running in the same mode deserializes them.
+    ///
+    /// # Safety
+    ///
+    /// Any misbehavior of the code of the artifacts is undefined behavior:
+    /// the traps of WebAssembly code, including out of bounds accesses and
+    /// stack overflows, and the errors and panics of the host functions it
+    /// calls.
+    pub unsafe fn unchecked(mut self) -> Self {
+        self.unchecked = true;
+        self
+    }
+
+    /// Make the artifacts compiled by the engine able to recompile their
+    /// functions, see [`JITArtifact::recompile_function`].
+    ///
+    /// Their functions are then called through a jump to their current
+    /// code, and they keep a copy of the WebAssembly module to recompile
+    /// its functions from. Deserialized artifacts can't recompile their
+    /// functions.
+    ///
+    /// Compiling fails with [`CompileError::UnsupportedTarget`] on other
+    /// architectures than x86-64.
+    ///
+    /// [`JITArtifact::recompile_function`]: crate::JITArtifact::recompile_function
+    /// [`CompileError::UnsupportedTarget`]: wasmer_compiler::CompileError::UnsupportedTarget
+    pub fn recompilable(mut self) -> Self {
+        self.recompilable = true;
+        self
+    }
+
+    /// Resolve the libcalls called by the compiled code with `resolver`,
+    /// which returns the address of the function to call instead of the
+    /// built-in one, or `None` to keep it.
+    ///
+    /// A replacement must have the signature and the calling convention
+    /// of the built-in function named by [`LibCall::to_function_name`],
+    /// defined in [`wasmer_vm::libcalls`]:
+    ///
+    /// - `CeilF32`, `FloorF32`, `NearestF32` and `TruncF32` are
+    ///   `extern "C" fn(f32) -> f32`, and their `F64` counterparts
+    ///   `extern "C" fn(f64) -> f64`;
+    /// - `RaiseTrap` is `unsafe extern "C" fn(TrapCode) -> !`, and must
+    ///   raise the trap with [`wasmer_vm::raise_lib_trap`];
+    /// - `Probestack` isn't a function of the C calling convention: it
+    ///   takes the size of the frame in `rax` on x86-64 and must preserve
+    ///   every register.
+    ///
+    /// Linking fails with [`LinkError::InvalidLibCall`] if a libcall is
+    /// resolved to a null address, or, in debug builds, to an address that
+    /// isn't aligned for a function.
+    ///
+    /// [`LibCall::to_function_name`]: wasmer_vm::libcalls::LibCall::to_function_name
+    /// [`LinkError::InvalidLibCall`]: wasmer_engine::LinkError::InvalidLibCall
+    pub fn libcall_resolver(
+        mut self,
+        resolver: impl Fn(LibCall) -> Option<usize> + Send + Sync + 'static,
+    ) -> Self {
+        self.libcall_resolver = Some(Arc::new(resolver));
+        self
+    }
+
     /// Build the `JITEngine` for this configuration
     #[cfg(feature = "compiler")]
     pub fn engine(self) -> JITEngine {
         let target = self.target.unwrap_or_default();
-        if let Some(compiler_config) = self.compiler_config {
+        let engine = if let Some(compiler_config) = self.compiler_config {
             let features = self
                 .features
                 .unwrap_or_else(|| compiler_config.default_features_for_target(&target));
@@ -52,12 +158,30 @@ impl<'a> JIT<'a> {
             JITEngine::new(compiler, target, features)
         } else {
             JITEngine::headless()
+        };
+        if let Some(align_functions) = self.align_functions {
+            engine.inner_mut().set_align_functions(align_functions);
         }
+        engine.inner_mut().set_unchecked(self.unchecked);
+        engine.inner_mut().set_recompilable(self.recompilable);
+        if let Some(libcall_resolver) = self.libcall_resolver {
+            engine.inner_mut().set_libcall_resolver(libcall_resolver);
+        }
+        engine
     }
 
     /// Build the `JITEngine` for this configuration
     #[cfg(not(feature = "compiler"))]
     pub fn engine(self) -> JITEngine {
-        JITEngine::headless()
+        let engine = JITEngine::headless();
+        if let Some(align_functions) = self.align_functions {
+            engine.inner_mut().set_align_functions(align_functions);
+        }
+        engine.inner_mut().set_unchecked(self.unchecked);
+        engine.inner_mut().set_recompilable(self.recompilable);
+        if let Some(libcall_resolver) = self.libcall_resolver {
+            engine.inner_mut().set_libcall_resolver(libcall_resolver);
+        }
+        engine
     }
 }
@@ -3,15 +3,25 @@
 
 //! Memory management for executable code.
 use crate::unwind::UnwindRegistry;
+use std::ptr;
 use wasmer_compiler::{CompiledFunctionUnwindInfo, CustomSection, FunctionBody};
-use wasmer_vm::{Mmap, VMFunctionBody};
+use wasmer_vm::{CodeRegistration, FunctionBodyPtr, Mmap, VMFunctionBody};
 
 /// The optimal alignment for functions.
 ///
 /// On x86-64, this is 16 since it's what the optimizations assume.
 /// When we add support for other architectures, we should also figure out their
 /// optimal alignment values.
-const ARCH_FUNCTION_ALIGNMENT: usize = 16;
+pub(crate) const ARCH_FUNCTION_ALIGNMENT: usize = 16;
+
+/// The bytes filling the padding between functions, so that jumping into
+/// it traps.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+const TRAP_FILL: &[u8] = &[0xcc]; // int3
+#[cfg(target_arch = "aarch64")]
+const TRAP_FILL: &[u8] = &[0x00, 0x00, 0x20, 0xd4]; // brk #0
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+const TRAP_FILL: &[u8] = &[0x00];
 
 /// The optimal alignment for data.
 ///
@@ -22,18 +32,47 @@ pub struct CodeMemory {
     unwind_registry: UnwindRegistry,
     mmap: Mmap,
     start_of_nonexecutable_pages: usize,
+    align_functions: usize,
+    function_offsets: Vec<usize>,
+    /// Lets the signal handler turn the faults of the code into traps,
+    /// once it's published.
+    code_registration: Option<CodeRegistration>,
 }
 
 impl CodeMemory {
     /// Create a new `CodeMemory` instance.
     pub fn new() -> Self {
+        Self::with_function_alignment(ARCH_FUNCTION_ALIGNMENT)
+    }
+
+    /// Create a new `CodeMemory` instance, aligning the start of each
+    /// function to `align_functions` bytes.
+    ///
+    /// The padding between functions is filled with trap instructions.
+    /// `align_functions` must be a power of two, no larger than the page
+    /// size.
+    pub fn with_function_alignment(align_functions: usize) -> Self {
+        assert!(
+            align_functions.is_power_of_two() && align_functions <= region::page::size(),
+            "invalid function alignment: {}",
+            align_functions
+        );
         Self {
             unwind_registry: UnwindRegistry::new(),
             mmap: Mmap::new(),
             start_of_nonexecutable_pages: 0,
+            align_functions,
+            function_offsets: vec![],
+            code_registration: None,
         }
     }
 
+    /// The offsets of the allocated functions from the start of the
+    /// allocation, in the order they were given to [`CodeMemory::allocate`].
+    pub fn function_offsets(&self) -> &[usize] {
+        &self.function_offsets
+    }
+
     /// Mutably get the UnwindRegistry.
     pub fn unwind_registry_mut(&mut self) -> &mut UnwindRegistry {
         &mut self.unwind_registry
@@ -62,15 +101,17 @@ impl CodeMemory {
         // - data section body size
         // -- padding between data sections
 
-        let total_len = round_up(
+        let functions_len = round_up(
             functions.iter().fold(0, |acc, func| {
-                round_up(
-                    acc + Self::function_allocation_size(func),
-                    ARCH_FUNCTION_ALIGNMENT,
-                )
-            }) + executable_sections.iter().fold(0, |acc, exec| {
-                round_up(acc + exec.bytes.len(), ARCH_FUNCTION_ALIGNMENT)
+                round_up(acc, self.align_functions) + Self::function_allocation_size(func)
             }),
+            ARCH_FUNCTION_ALIGNMENT,
+        );
+        let total_len = round_up(
+            functions_len
+                + executable_sections.iter().fold(0, |acc, exec| {
+                    round_up(acc + exec.bytes.len(), ARCH_FUNCTION_ALIGNMENT)
+                }),
             page_size,
         ) + data_sections.iter().fold(0, |acc, data| {
             round_up(acc + data.bytes.len(), DATA_SECTION_ALIGNMENT)
@@ -83,21 +124,27 @@ impl CodeMemory {
         // 3. Determine where the pointers to each function, executable section
         // or data section are. Copy the functions. Collect the addresses of each and return them.
 
-        let mut bytes = 0;
         let mut buf = self.mmap.as_mut_slice();
+        for (i, byte) in buf[..functions_len].iter_mut().enumerate() {
+            *byte = TRAP_FILL[i % TRAP_FILL.len()];
+        }
+        self.function_offsets.clear();
+        let mut bytes = 0;
         for func in functions {
-            let len = round_up(
-                Self::function_allocation_size(func),
-                ARCH_FUNCTION_ALIGNMENT,
-            );
-            let (func_buf, next_buf) = buf.split_at_mut(len);
+            let padding = round_up(bytes, self.align_functions) - bytes;
+            let len = Self::function_allocation_size(func);
+            let (func_buf, next_buf) = buf[padding..].split_at_mut(len);
             buf = next_buf;
+            bytes += padding;
+            self.function_offsets.push(bytes);
             bytes += len;
 
             let vmfunc = Self::copy_function(&mut self.unwind_registry, func, func_buf);
-            assert_eq!(vmfunc.as_ptr() as usize % ARCH_FUNCTION_ALIGNMENT, 0);
+            assert_eq!(vmfunc.as_ptr() as usize % self.align_functions, 0);
             function_result.push(vmfunc);
         }
+        buf = buf.split_at_mut(functions_len - bytes).1;
+        bytes = functions_len;
         for section in executable_sections {
             let section = &section.bytes;
             assert_eq!(buf.as_mut_ptr() as usize % ARCH_FUNCTION_ALIGNMENT, 0);
@@ -149,6 +196,13 @@ impl CodeMemory {
             )
         }
         .expect("unable to make memory readonly and executable");
+        if self.code_registration.is_none() {
+            let code = ptr::slice_from_raw_parts_mut(
+                self.mmap.as_mut_ptr() as *mut VMFunctionBody,
+                self.start_of_nonexecutable_pages,
+            );
+            self.code_registration = Some(CodeRegistration::new(vec![FunctionBodyPtr(code)]));
+        }
     }
 
     /// Calculates the allocation size of the given compiled function.
@@ -172,8 +226,6 @@ impl CodeMemory {
         func: &FunctionBody,
         buf: &'a mut [u8],
     ) -> &'a mut [VMFunctionBody] {
-        assert_eq!(buf.as_ptr() as usize % ARCH_FUNCTION_ALIGNMENT, 0);
-
         let func_len = func.body.len();
 
         let (body, remainder) = buf.split_at_mut(func_len);
@@ -215,9 +267,41 @@ fn round_up(size: usize, multiple: usize) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use super::CodeMemory;
+    use super::{CodeMemory, TRAP_FILL};
+    use wasmer_compiler::FunctionBody;
+
     fn _assert() {
         fn _assert_send_sync<T: Send + Sync>() {}
         _assert_send_sync::<CodeMemory>();
     }
+
+    #[test]
+    fn functions_are_aligned_and_padded_with_traps() {
+        let functions = [1, 64, 65, 7]
+            .iter()
+            .map(|&len| FunctionBody {
+                body: vec![0x90; len],
+                unwind_info: None,
+            })
+            .collect::<Vec<_>>();
+        let function_refs = functions.iter().collect::<Vec<_>>();
+
+        let mut code_memory = CodeMemory::with_function_alignment(64);
+        let (allocated, _, _) = code_memory.allocate(&function_refs, &[], &[]).unwrap();
+        for (function, body) in functions.iter().zip(allocated) {
+            assert_eq!(body.as_ptr() as usize % 64, 0);
+            assert_eq!(body.len(), function.body.len());
+        }
+        assert_eq!(code_memory.function_offsets(), &[0, 64, 128, 256]);
+
+        let memory = code_memory.mmap.as_slice();
+        let mut end = 0;
+        for (function, &offset) in functions.iter().zip(code_memory.function_offsets()) {
+            for i in end..offset {
+                assert_eq!(memory[i], TRAP_FILL[i % TRAP_FILL.len()]);
+            }
+            end = offset + function.body.len();
+            assert!(memory[offset..end].iter().all(|&byte| byte == 0x90));
+        }
+    }
 }
@@ -1,14 +1,17 @@
 //! JIT compilation.
 
+use crate::code_memory::ARCH_FUNCTION_ALIGNMENT;
+use crate::link::LibCallResolver;
 use crate::{CodeMemory, JITArtifact};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::Compiler;
 use wasmer_compiler::{
-    CompileError, CustomSection, CustomSectionProtection, FunctionBody, SectionIndex, Target,
+    CompilationProgress, CompileError, CustomSection, CustomSectionProtection, FunctionBody,
+    SectionIndex, Target,
 };
-use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, Tunables};
+use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, EngineKind, Tunables};
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::Features;
 use wasmer_types::{FunctionIndex, FunctionType, LocalFunctionIndex, SignatureIndex};
@@ -36,6 +39,10 @@ impl JITEngine {
                 function_call_trampolines: HashMap::new(),
                 code_memory: vec![],
                 signatures: SignatureRegistry::new(),
+                align_functions: ARCH_FUNCTION_ALIGNMENT,
+                unchecked: false,
+                recompilable: false,
+                libcall_resolver: None,
                 features,
             })),
             target: Arc::new(target),
@@ -64,6 +71,10 @@ impl JITEngine {
                 function_call_trampolines: HashMap::new(),
                 code_memory: vec![],
                 signatures: SignatureRegistry::new(),
+                align_functions: ARCH_FUNCTION_ALIGNMENT,
+                unchecked: false,
+                recompilable: false,
+                libcall_resolver: None,
                 features: Features::default(),
             })),
             target: Arc::new(Target::default()),
@@ -109,21 +120,34 @@ impl Engine for JITEngine {
     }
 
     /// Compile a WebAssembly binary
-    #[cfg(feature = "compiler")]
     fn compile(
         &self,
         binary: &[u8],
         tunables: &dyn Tunables,
     ) -> Result<Arc<dyn Artifact>, CompileError> {
-        Ok(Arc::new(JITArtifact::new(&self, binary, tunables)?))
+        self.compile_with_progress(binary, tunables, &CompilationProgress::new())
     }
 
-    /// Compile a WebAssembly binary
+    /// Compile a WebAssembly binary, reporting the progress
+    #[cfg(feature = "compiler")]
+    fn compile_with_progress(
+        &self,
+        binary: &[u8],
+        tunables: &dyn Tunables,
+        progress: &CompilationProgress,
+    ) -> Result<Arc<dyn Artifact>, CompileError> {
+        Ok(Arc::new(JITArtifact::new(
+            &self, binary, tunables, progress,
+        )?))
+    }
+
+    /// Compile a WebAssembly binary, reporting the progress
     #[cfg(not(feature = "compiler"))]
-    fn compile(
+    fn compile_with_progress(
         &self,
         _binary: &[u8],
         _tunables: &dyn Tunables,
+        _progress: &CompilationProgress,
     ) -> Result<Arc<dyn Artifact>, CompileError> {
         Err(CompileError::Codegen(
             "The JITEngine is operating in headless mode, so it can not compile Modules."
@@ -136,10 +160,18 @@ impl Engine for JITEngine {
         Ok(Arc::new(JITArtifact::deserialize(&self, &bytes)?))
     }
 
+    fn unchecked(&self) -> bool {
+        self.inner().unchecked()
+    }
+
     fn id(&self) -> &EngineId {
         &self.engine_id
     }
 
+    fn kind(&self) -> EngineKind {
+        EngineKind::Jit
+    }
+
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }
@@ -156,10 +188,24 @@ pub struct JITEngineInner {
     features: Features,
     /// The code memory is responsible of publishing the compiled
     /// functions to memory.
-    code_memory: Vec<CodeMemory>,
+    ///
+    /// Each artifact shares the code memory its functions are allocated
+    /// in, so that they stay mapped as long as the artifact is alive, even
+    /// if the engine is dropped first.
+    code_memory: Vec<Arc<CodeMemory>>,
     /// The signature registry is used mainly to operate with trampolines
     /// performantly.
     signatures: SignatureRegistry,
+    /// The alignment of the start of each function in code memory.
+    align_functions: usize,
+    /// Whether the artifacts run unchecked, see `JIT::unchecked`.
+    unchecked: bool,
+    /// Whether the artifacts can recompile their functions, see
+    /// `JIT::recompilable`.
+    recompilable: bool,
+    /// Resolves the libcalls before the built-in ones, see
+    /// `JIT::libcall_resolver`.
+    libcall_resolver: Option<Arc<LibCallResolver>>,
 }
 
 impl JITEngineInner {
@@ -192,6 +238,45 @@ impl JITEngineInner {
         &self.features
     }
 
+    /// Sets the alignment of the start of each function allocated from now
+    /// on, see [`CodeMemory::with_function_alignment`].
+    pub(crate) fn set_align_functions(&mut self, align_functions: usize) {
+        self.align_functions = align_functions;
+    }
+
+    /// Makes the artifacts run unchecked, see `JIT::unchecked`.
+    pub(crate) fn set_unchecked(&mut self, unchecked: bool) {
+        self.unchecked = unchecked;
+    }
+
+    /// Whether the artifacts run unchecked.
+    pub(crate) fn unchecked(&self) -> bool {
+        self.unchecked
+    }
+
+    /// Makes the artifacts compiled from now on able to recompile their
+    /// functions, see `JIT::recompilable`.
+    pub(crate) fn set_recompilable(&mut self, recompilable: bool) {
+        self.recompilable = recompilable;
+    }
+
+    /// Whether the artifacts compiled from now on can recompile their
+    /// functions.
+    pub(crate) fn recompilable(&self) -> bool {
+        self.recompilable
+    }
+
+    /// Resolves the libcalls of the artifacts linked from now on with
+    /// `libcall_resolver` first, see `JIT::libcall_resolver`.
+    pub(crate) fn set_libcall_resolver(&mut self, libcall_resolver: Arc<LibCallResolver>) {
+        self.libcall_resolver = Some(libcall_resolver);
+    }
+
+    /// The resolver of the libcalls, if any.
+    pub(crate) fn libcall_resolver(&self) -> Option<Arc<LibCallResolver>> {
+        self.libcall_resolver.clone()
+    }
+
     /// Allocate compiled functions into memory
     #[allow(clippy::type_complexity)]
     pub(crate) fn allocate(
@@ -218,19 +303,21 @@ impl JITEngineInner {
         let (executable_sections, data_sections): (Vec<_>, _) = custom_sections
             .values()
             .partition(|section| section.protection == CustomSectionProtection::ReadExecute);
-        self.code_memory.push(CodeMemory::new());
+        self.code_memory
+            .push(Arc::new(CodeMemory::with_function_alignment(
+                self.align_functions,
+            )));
 
+        let code_memory = Arc::get_mut(self.code_memory.last_mut().unwrap()).unwrap();
         let (mut allocated_functions, allocated_executable_sections, allocated_data_sections) =
-            self.code_memory
-                .last_mut()
-                .unwrap()
+            code_memory
                 .allocate(
                     function_bodies.as_slice(),
                     executable_sections.as_slice(),
                     data_sections.as_slice(),
                 )
                 .map_err(|message| {
-                    CompileError::Resource(format!(
+                    CompileError::ResourceLimit(format!(
                         "failed to allocate memory for functions: {}",
                         message
                     ))
@@ -287,20 +374,34 @@ impl JITEngineInner {
         ))
     }
 
+    /// The code memory of the last allocation, which isn't shared with an
+    /// artifact until it's published.
+    fn last_code_memory_mut(&mut self) -> &mut CodeMemory {
+        Arc::get_mut(self.code_memory.last_mut().unwrap())
+            .expect("The code memory of an artifact can't be modified")
+    }
+
+    /// The code memory of the last allocation, to be kept alive by the
+    /// artifact its functions belong to.
+    pub(crate) fn last_code_memory(&self) -> Arc<CodeMemory> {
+        self.code_memory.last().unwrap().clone()
+    }
+
     /// Make memory containing compiled code executable.
     pub(crate) fn publish_compiled_code(&mut self) {
-        self.code_memory.last_mut().unwrap().publish();
+        self.last_code_memory_mut().publish();
     }
 
     /// Register DWARF-type exception handling information associated with the code.
     pub(crate) fn publish_eh_frame(&mut self, eh_frame: Option<&[u8]>) -> Result<(), CompileError> {
-        self.code_memory
-            .last_mut()
-            .unwrap()
+        self.last_code_memory_mut()
             .unwind_registry_mut()
             .publish(eh_frame)
             .map_err(|e| {
-                CompileError::Resource(format!("Error while publishing the unwind code: {}", e))
+                CompileError::ResourceLimit(format!(
+                    "Error while publishing the unwind code: {}",
+                    e
+                ))
             })?;
         Ok(())
     }
@@ -36,7 +36,7 @@ pub use crate::artifact::JITArtifact;
 pub use crate::builder::JIT;
 pub use crate::code_memory::CodeMemory;
 pub use crate::engine::JITEngine;
-pub use crate::link::link_module;
+pub use crate::link::{link_module, LibCallResolver};
 
 /// Version number of this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
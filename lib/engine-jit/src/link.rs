@@ -2,27 +2,60 @@
 
 use std::ptr::write_unaligned;
 use wasmer_compiler::{
-    JumpTable, JumpTableOffsets, Relocation, RelocationKind, RelocationTarget, Relocations,
-    SectionIndex,
+    Architecture, JumpTable, JumpTableOffsets, Relocation, RelocationKind, RelocationTarget,
+    Relocations, SectionIndex,
 };
+use wasmer_engine::LinkError;
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::LocalFunctionIndex;
+use wasmer_vm::libcalls::LibCall;
 use wasmer_vm::ModuleInfo;
 use wasmer_vm::{FunctionBodyPtr, SectionBodyPtr, VMFunctionBody};
 
+/// Resolves a libcall to the address of the function the compiled code
+/// calls for it, or `None` for the built-in one, see
+/// [`JIT::libcall_resolver`].
+///
+/// [`JIT::libcall_resolver`]: crate::JIT::libcall_resolver
+pub type LibCallResolver = dyn Fn(LibCall) -> Option<usize> + Send + Sync;
+
+/// Returns the address of the function implementing `libcall`.
+fn resolve_libcall(
+    libcall: LibCall,
+    libcall_resolver: Option<&LibCallResolver>,
+) -> Result<usize, LinkError> {
+    let address = match libcall_resolver.and_then(|resolve| resolve(libcall)) {
+        Some(address) => address,
+        None => return Ok(libcall.function_pointer()),
+    };
+    if address == 0 {
+        return Err(LinkError::InvalidLibCall { libcall, address });
+    }
+    // The instructions, and so the functions, are 4-byte aligned on AArch64.
+    #[cfg(all(debug_assertions, target_arch = "aarch64"))]
+    {
+        if address % 4 != 0 {
+            return Err(LinkError::InvalidLibCall { libcall, address });
+        }
+    }
+    Ok(address)
+}
+
 fn apply_relocation(
     body: usize,
     r: &Relocation,
     allocated_functions: &PrimaryMap<LocalFunctionIndex, FunctionBodyPtr>,
+    function_entries: &PrimaryMap<LocalFunctionIndex, FunctionBodyPtr>,
     jt_offsets: &PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
     allocated_sections: &PrimaryMap<SectionIndex, SectionBodyPtr>,
-) {
+    libcall_resolver: Option<&LibCallResolver>,
+) -> Result<(), LinkError> {
     let target_func_address: usize = match r.reloc_target {
         RelocationTarget::LocalFunc(index) => {
-            let fatptr: *const [VMFunctionBody] = allocated_functions[index].0;
+            let fatptr: *const [VMFunctionBody] = function_entries[index].0;
             fatptr as *const VMFunctionBody as usize
         }
-        RelocationTarget::LibCall(libcall) => libcall.function_pointer(),
+        RelocationTarget::LibCall(libcall) => resolve_libcall(libcall, libcall_resolver)?,
         RelocationTarget::CustomSection(custom_section) => {
             *allocated_sections[custom_section] as usize
         }
@@ -43,6 +76,11 @@ fn apply_relocation(
             write_unaligned(reloc_address as *mut u64, reloc_delta);
         },
         #[cfg(target_pointer_width = "32")]
+        RelocationKind::Abs4 => unsafe {
+            let (reloc_address, reloc_abs) = r.for_address(body, target_func_address as u64);
+            write_unaligned(reloc_address as *mut u32, reloc_abs as _);
+        },
+        #[cfg(target_pointer_width = "32")]
         RelocationKind::X86PCRel4 => unsafe {
             let (reloc_address, reloc_delta) = r.for_address(body, target_func_address as u64);
             write_unaligned(reloc_address as *mut u32, reloc_delta as _);
@@ -57,35 +95,270 @@ fn apply_relocation(
             let (reloc_address, reloc_delta) = r.for_address(body, target_func_address as u64);
             write_unaligned(reloc_address as *mut u32, reloc_delta as _);
         },
+        #[cfg(target_pointer_width = "64")]
+        RelocationKind::Arm64Call => unsafe {
+            let (reloc_address, reloc_delta) = r.for_address(body, target_func_address as u64);
+            let instruction = (reloc_address as *const u32).read_unaligned();
+            let instruction = arm64_call(instruction, reloc_delta as i64)?;
+            write_unaligned(reloc_address as *mut u32, instruction);
+        },
         RelocationKind::X86PCRelRodata4 => {}
-        kind => panic!(
-            "Relocation kind unsupported in the current architecture {}",
-            kind
-        ),
+        kind => {
+            return Err(LinkError::UnsupportedRelocation {
+                kind,
+                target_arch: Architecture::host(),
+            })
+        }
+    }
+    Ok(())
+}
+
+/// Patches the 26-bit immediate of the `b` or `bl` `instruction` to
+/// branch `delta` bytes away, which must be a multiple of 4 within
+/// ±128 MiB.
+#[cfg(target_pointer_width = "64")]
+fn arm64_call(instruction: u32, delta: i64) -> Result<u32, LinkError> {
+    const RANGE: i64 = 1 << 27;
+    if delta % 4 != 0 || delta < -RANGE || delta >= RANGE {
+        return Err(LinkError::RelocationOutOfRange {
+            kind: RelocationKind::Arm64Call,
+            distance: delta,
+        });
     }
+    let immediate = ((delta >> 2) as u32) & 0x03ff_ffff;
+    Ok((instruction & 0xfc00_0000) | immediate)
 }
 
 /// Links a module, patching the allocated functions with the
 /// required relocations and jump tables.
+///
+/// The libcalls are resolved with `libcall_resolver` first, if any.
+///
+/// Returns a [`LinkError::UnsupportedRelocation`] if a relocation can't be
+/// applied on the host architecture, and a [`LinkError::InvalidLibCall`]
+/// if `libcall_resolver` resolves a libcall to an invalid address.
 pub fn link_module(
+    module: &ModuleInfo,
+    allocated_functions: &PrimaryMap<LocalFunctionIndex, FunctionBodyPtr>,
+    jt_offsets: &PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
+    function_relocations: Relocations,
+    allocated_sections: &PrimaryMap<SectionIndex, SectionBodyPtr>,
+    section_relocations: &PrimaryMap<SectionIndex, Vec<Relocation>>,
+    libcall_resolver: Option<&LibCallResolver>,
+) -> Result<(), LinkError> {
+    link_module_with_entries(
+        module,
+        allocated_functions,
+        allocated_functions,
+        jt_offsets,
+        function_relocations,
+        allocated_sections,
+        section_relocations,
+        libcall_resolver,
+    )
+}
+
+/// Links a module as `link_module`, except that the calls to the functions
+/// go to their `function_entries` rather than to their code.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn link_module_with_entries(
     _module: &ModuleInfo,
     allocated_functions: &PrimaryMap<LocalFunctionIndex, FunctionBodyPtr>,
+    function_entries: &PrimaryMap<LocalFunctionIndex, FunctionBodyPtr>,
     jt_offsets: &PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
     function_relocations: Relocations,
     allocated_sections: &PrimaryMap<SectionIndex, SectionBodyPtr>,
     section_relocations: &PrimaryMap<SectionIndex, Vec<Relocation>>,
-) {
+    libcall_resolver: Option<&LibCallResolver>,
+) -> Result<(), LinkError> {
     for (i, section_relocs) in section_relocations.iter() {
         let body = *allocated_sections[i] as usize;
         for r in section_relocs {
-            apply_relocation(body, r, allocated_functions, jt_offsets, allocated_sections);
+            apply_relocation(
+                body,
+                r,
+                allocated_functions,
+                function_entries,
+                jt_offsets,
+                allocated_sections,
+                libcall_resolver,
+            )?;
         }
     }
     for (i, function_relocs) in function_relocations.into_iter() {
         let fatptr: *const [VMFunctionBody] = allocated_functions[i].0;
         let body = fatptr as *const VMFunctionBody as usize;
         for r in function_relocs {
-            apply_relocation(body, r, allocated_functions, jt_offsets, allocated_sections);
+            apply_relocation(
+                body,
+                r,
+                allocated_functions,
+                function_entries,
+                jt_offsets,
+                allocated_sections,
+                libcall_resolver,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_relocation, LibCallResolver};
+    use std::ptr;
+    use wasmer_compiler::{Architecture, Relocation, RelocationKind, RelocationTarget};
+    use wasmer_engine::LinkError;
+    use wasmer_types::entity::{EntityRef, PrimaryMap};
+    use wasmer_types::LocalFunctionIndex;
+    use wasmer_vm::libcalls::LibCall;
+    use wasmer_vm::{FunctionBodyPtr, VMFunctionBody};
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn unsupported_relocation() {
+        let mut body = [0u8; 8];
+        for &kind in &[RelocationKind::X86PCRel4, RelocationKind::X86CallPLTRel4] {
+            let relocation = Relocation {
+                kind,
+                reloc_target: RelocationTarget::LibCall(LibCall::CeilF32),
+                offset: 0,
+                addend: 0,
+            };
+            let error = apply_relocation(
+                body.as_mut_ptr() as usize,
+                &relocation,
+                &PrimaryMap::new(),
+                &PrimaryMap::new(),
+                &PrimaryMap::new(),
+                &PrimaryMap::new(),
+                None,
+            )
+            .unwrap_err();
+            match error {
+                LinkError::UnsupportedRelocation {
+                    kind: error_kind,
+                    target_arch,
+                } => {
+                    assert_eq!(error_kind, kind);
+                    assert_eq!(target_arch, Architecture::X86_64);
+                }
+                error => panic!("unexpected error: {}", error),
+            }
+            assert_eq!(body, [0; 8]);
+        }
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn arm64_call() {
+        // `bl 0` four times.
+        let mut body = [0x9400_0000u32; 4];
+        let start = body.as_mut_ptr() as usize;
+        let functions: PrimaryMap<LocalFunctionIndex, _> = (0..4)
+            .map(|i| {
+                let address = (start + 4 * i) as *mut VMFunctionBody;
+                FunctionBodyPtr(ptr::slice_from_raw_parts_mut(address, 4))
+            })
+            .collect();
+        // The libcalls are resolved 128 MiB after the second instruction,
+        // just out of its range.
+        let far = |_: LibCall| Some(start + 4 + (1 << 27));
+        let link = |offset: u32, reloc_target: RelocationTarget, addend: i64| {
+            let relocation = Relocation {
+                kind: RelocationKind::Arm64Call,
+                reloc_target,
+                offset,
+                addend,
+            };
+            apply_relocation(
+                start,
+                &relocation,
+                &functions,
+                &functions,
+                &PrimaryMap::new(),
+                &PrimaryMap::new(),
+                Some(&far),
+            )
+        };
+        let function = |index| RelocationTarget::LocalFunc(LocalFunctionIndex::new(index));
+
+        // Two instructions forward, then three backward.
+        link(0, function(2), 0).unwrap();
+        link(12, function(0), 0).unwrap();
+        let bytes: Vec<u8> = body
+            .iter()
+            .flat_map(|word| word.to_le_bytes().to_vec())
+            .collect();
+        assert_eq!(
+            bytes,
+            [
+                0x02, 0x00, 0x00, 0x94, // bl +8
+                0x00, 0x00, 0x00, 0x94, // bl 0
+                0x00, 0x00, 0x00, 0x94, // bl 0
+                0xfd, 0xff, 0xff, 0x97, // bl -12
+            ]
+        );
+
+        // Out of range, then misaligned.
+        for &(target, addend, distance) in &[
+            (RelocationTarget::LibCall(LibCall::CeilF32), 0, 1 << 27),
+            (function(2), 2, 6),
+        ] {
+            match link(4, target, addend) {
+                Err(LinkError::RelocationOutOfRange {
+                    kind: RelocationKind::Arm64Call,
+                    distance: error_distance,
+                }) => assert_eq!(error_distance, distance),
+                result => panic!("unexpected result: {:?}", result),
+            }
+            assert_eq!(body[1], 0x9400_0000);
+        }
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn resolve_libcalls() {
+        extern "C" fn ceil(x: f32) -> f32 {
+            x.ceil()
+        }
+
+        fn link(libcall: LibCall, resolver: &LibCallResolver) -> Result<u64, LinkError> {
+            let mut body = [0u64; 1];
+            let relocation = Relocation {
+                kind: RelocationKind::Abs8,
+                reloc_target: RelocationTarget::LibCall(libcall),
+                offset: 0,
+                addend: 0,
+            };
+            apply_relocation(
+                body.as_mut_ptr() as usize,
+                &relocation,
+                &PrimaryMap::new(),
+                &PrimaryMap::new(),
+                &PrimaryMap::new(),
+                &PrimaryMap::new(),
+                Some(resolver),
+            )?;
+            Ok(body[0])
+        }
+
+        let resolver = |libcall| match libcall {
+            LibCall::CeilF32 => Some(ceil as usize),
+            LibCall::TruncF32 => Some(0),
+            _ => None,
+        };
+        assert_eq!(link(LibCall::CeilF32, &resolver).unwrap(), ceil as u64);
+        assert_eq!(
+            link(LibCall::FloorF32, &resolver).unwrap(),
+            LibCall::FloorF32.function_pointer() as u64
+        );
+        match link(LibCall::TruncF32, &resolver) {
+            Err(LinkError::InvalidLibCall {
+                libcall: LibCall::TruncF32,
+                address: 0,
+            }) => {}
+            result => panic!("unexpected result: {:?}", result),
         }
     }
 }
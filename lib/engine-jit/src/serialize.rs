@@ -3,7 +3,7 @@ use wasmer_compiler::{
     CompileModuleInfo, CustomSection, Dwarf, FunctionBody, JumpTableOffsets, Relocation,
     SectionIndex,
 };
-use wasmer_engine::SerializableFunctionFrameInfo;
+use wasmer_engine::{ModuleFootprint, SerializableFunctionFrameInfo};
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::{FunctionIndex, LocalFunctionIndex, OwnedDataInitializer, SignatureIndex};
 
@@ -42,4 +42,8 @@ pub struct SerializableModule {
     pub compilation: SerializableCompilation,
     pub compile_info: CompileModuleInfo,
     pub data_initializers: Box<[OwnedDataInitializer]>,
+    // The footprint, recorded when compiling the module
+    pub footprint: ModuleFootprint,
+    // Whether the module was compiled to run unchecked
+    pub unchecked: bool,
 }
@@ -14,13 +14,15 @@ use std::sync::Arc;
 use tempfile::NamedTempFile;
 #[cfg(feature = "compiler")]
 use tracing::trace;
-use wasmer_compiler::{CompileError, Features, OperatingSystem, Symbol, SymbolRegistry, Triple};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::{
-    CompileModuleInfo, FunctionBodyData, ModuleEnvironment, ModuleTranslationState,
+    CompilationProgress, CompileModuleInfo, DebugInfoPolicy, FunctionBodyData, ModuleEnvironment,
+    ModuleTranslationState,
 };
+use wasmer_compiler::{CompileError, Features, OperatingSystem, Symbol, SymbolRegistry, Triple};
 use wasmer_engine::{
-    Artifact, DeserializeError, InstantiationError, LinkError, RuntimeError, SerializeError,
+    Artifact, DeserializeError, InstantiationError, LinkError, ModuleFootprint, RuntimeError,
+    SerializeError,
 };
 #[cfg(feature = "compiler")]
 use wasmer_engine::{Engine, Tunables};
@@ -28,14 +30,14 @@ use wasmer_engine::{Engine, Tunables};
 use wasmer_object::{emit_compilation, emit_data, get_object_for_target};
 use wasmer_types::entity::{BoxedSlice, PrimaryMap};
 #[cfg(feature = "compiler")]
-use wasmer_types::DataInitializer;
+use wasmer_types::{merge_data_initializers, DataInitializer};
 use wasmer_types::{
     FunctionIndex, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer, SignatureIndex,
     TableIndex,
 };
 use wasmer_vm::{
-    FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMFunctionBody, VMSharedSignatureIndex,
-    VMTrampoline,
+    CodeRegistration, ExportResolution, FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle,
+    VMFunctionBody, VMSharedSignatureIndex, VMTrampoline,
 };
 
 /// A compiled wasm module, ready to be instantiated.
@@ -47,6 +49,11 @@ pub struct NativeArtifact {
     finished_functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
     finished_dynamic_function_trampolines: BoxedSlice<FunctionIndex, FunctionBodyPtr>,
     signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
+    export_resolutions: Vec<ExportResolution>,
+    /// Lets the signal handler turn the faults of the loaded functions
+    /// into traps. Their lengths aren't known yet, so the extent of their
+    /// code isn't either.
+    _code_registration: Option<CodeRegistration>,
 }
 
 fn to_compile_error(err: impl Error) -> CompileError {
@@ -96,12 +103,27 @@ impl NativeArtifact {
         }
     }
 
+    /// Serializes the metadata, prefixed with its length.
+    #[cfg(feature = "compiler")]
+    fn serialize_metadata(metadata: &ModuleMetadata) -> Result<Vec<u8>, CompileError> {
+        let serialized_data = bincode::serialize(metadata).map_err(to_compile_error)?;
+        let mut metadata_binary = vec![0; 10];
+        let mut writable = &mut metadata_binary[..];
+        leb128::write::unsigned(&mut writable, serialized_data.len() as u64)
+            .expect("Should write number");
+        metadata_binary.extend(serialized_data);
+        Ok(metadata_binary)
+    }
+
     #[cfg(feature = "compiler")]
     /// Generate a compilation
     fn generate_metadata<'data>(
         data: &'data [u8],
         features: &Features,
         tunables: &dyn Tunables,
+        max_function_results: Option<usize>,
+        prune_dead_functions: bool,
+        debug_info_policy: DebugInfoPolicy,
     ) -> Result<
         (
             CompileModuleInfo,
@@ -111,8 +133,14 @@ impl NativeArtifact {
         ),
         CompileError,
     > {
-        let environ = ModuleEnvironment::new();
-        let translation = environ.translate(data).map_err(CompileError::Wasm)?;
+        let environ = ModuleEnvironment::new()
+            .with_max_function_results(max_function_results)
+            .with_threads(features.threads)
+            .with_debug_info_policy(debug_info_policy);
+        let mut translation = environ.translate(data)?;
+        if prune_dead_functions {
+            translation.prune_dead_functions()?;
+        }
         let memory_styles: PrimaryMap<MemoryIndex, MemoryStyle> = translation
             .module
             .memories
@@ -151,13 +179,21 @@ impl NativeArtifact {
         let target = engine.target();
         let compiler = engine_inner.compiler()?;
         let (compile_info, function_body_inputs, data_initializers, module_translation) =
-            Self::generate_metadata(data, engine_inner.features(), tunables)?;
-
-        let data_initializers = data_initializers
-            .iter()
-            .map(OwnedDataInitializer::new)
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
+            Self::generate_metadata(
+                data,
+                engine_inner.features(),
+                tunables,
+                compiler.max_function_results(),
+                compiler.prune_dead_functions(),
+                compiler.debug_info_policy(),
+            )?;
+
+        let data_initializers = merge_data_initializers(
+            &data_initializers
+                .iter()
+                .map(OwnedDataInitializer::new)
+                .collect::<Vec<_>>(),
+        );
 
         let target_triple = target.triple();
 
@@ -179,19 +215,15 @@ impl NativeArtifact {
             .map(|_function_body| 0u64)
             .collect::<PrimaryMap<LocalFunctionIndex, u64>>();
 
-        let metadata = ModuleMetadata {
+        let mut metadata = ModuleMetadata {
             compile_info,
             prefix: engine_inner.get_prefix(&data),
             data_initializers,
             function_body_lengths,
+            footprint: None,
         };
 
-        let serialized_data = bincode::serialize(&metadata).map_err(to_compile_error)?;
-        let mut metadata_binary = vec![0; 10];
-        let mut writable = &mut metadata_binary[..];
-        leb128::write::unsigned(&mut writable, serialized_data.len() as u64)
-            .expect("Should write number");
-        metadata_binary.extend(serialized_data);
+        let metadata_binary = Self::serialize_metadata(&metadata)?;
 
         let maybe_obj_bytes = compiler.experimental_native_compile_module(
             &target,
@@ -222,10 +254,17 @@ impl NativeArtifact {
                     &metadata.compile_info,
                     module_translation.as_ref().unwrap(),
                     function_body_inputs,
+                    &CompilationProgress::new(),
                 )?;
+                // The footprint is only known once the module is compiled.
+                metadata.footprint = Some(ModuleFootprint::from_compilation(&compilation));
                 let mut obj = get_object_for_target(&target_triple).map_err(to_compile_error)?;
-                emit_data(&mut obj, WASMER_METADATA_SYMBOL, &metadata_binary)
-                    .map_err(to_compile_error)?;
+                emit_data(
+                    &mut obj,
+                    WASMER_METADATA_SYMBOL,
+                    &Self::serialize_metadata(&metadata)?,
+                )
+                .map_err(to_compile_error)?;
                 emit_compilation(&mut obj, compilation, &metadata, &target_triple)
                     .map_err(to_compile_error)?;
                 let file = tempfile::Builder::new()
@@ -327,6 +366,14 @@ impl NativeArtifact {
         let finished_dynamic_function_trampolines: PrimaryMap<FunctionIndex, FunctionBodyPtr> =
             PrimaryMap::new();
         let signatures: PrimaryMap<SignatureIndex, VMSharedSignatureIndex> = PrimaryMap::new();
+        // The lengths of the functions aren't known, but the library only
+        // holds the code of the module.
+        let code_registration = match finished_functions.values().next() {
+            Some(function) => CodeRegistration::library(*function),
+            None => CodeRegistration::new(vec![]),
+        };
+
+        let export_resolutions = metadata.compile_info.module.export_resolutions();
         Ok(Self {
             sharedobject_path,
             metadata,
@@ -335,6 +382,8 @@ impl NativeArtifact {
             finished_dynamic_function_trampolines: finished_dynamic_function_trampolines
                 .into_boxed_slice(),
             signatures: signatures.into_boxed_slice(),
+            export_resolutions,
+            _code_registration: None,
         })
     }
 
@@ -428,6 +477,14 @@ impl NativeArtifact {
                 .collect::<PrimaryMap<_, _>>()
         };
 
+        // The lengths of the functions aren't known, but the library only
+        // holds the code of the module.
+        let code_registration = match finished_functions.values().next() {
+            Some(function) => CodeRegistration::library(*function),
+            None => CodeRegistration::new(vec![]),
+        };
+
+        let export_resolutions = metadata.compile_info.module.export_resolutions();
         Ok(Self {
             sharedobject_path,
             metadata,
@@ -436,6 +493,8 @@ impl NativeArtifact {
             finished_dynamic_function_trampolines: finished_dynamic_function_trampolines
                 .into_boxed_slice(),
             signatures: signatures.into_boxed_slice(),
+            export_resolutions,
+            _code_registration: Some(code_registration),
         })
     }
 
@@ -579,6 +638,10 @@ impl Artifact for NativeArtifact {
         &self.signatures
     }
 
+    fn export_resolutions(&self) -> &[ExportResolution] {
+        &self.export_resolutions
+    }
+
     fn preinstantiate(&self) -> Result<(), InstantiationError> {
         if self.library.is_none() {
             return Err(InstantiationError::Link(LinkError::Trap(
@@ -588,6 +651,25 @@ impl Artifact for NativeArtifact {
         Ok(())
     }
 
+    fn footprint(&self) -> ModuleFootprint {
+        // The shared object can't record its own size. Only the number of
+        // functions and trampolines of a module compiled straight to an
+        // object file by the compiler is known.
+        let serialized_bytes = std::fs::metadata(&self.sharedobject_path)
+            .map(|metadata| metadata.len() as usize)
+            .unwrap_or_default();
+        let footprint = self.metadata.footprint.unwrap_or_else(|| ModuleFootprint {
+            functions: self.metadata.function_body_lengths.len(),
+            trampolines: self.signatures().len()
+                + self.finished_dynamic_function_trampolines().len(),
+            ..Default::default()
+        });
+        ModuleFootprint {
+            serialized_bytes,
+            ..footprint
+        }
+    }
+
     /// Serialize a NativeArtifact
     fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
         Ok(std::fs::read(&self.sharedobject_path)?)
@@ -8,7 +8,7 @@ use std::sync::Mutex;
 use wasmer_compiler::{CompileError, Target};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::{Compiler, Triple};
-use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, Tunables};
+use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, EngineKind, Tunables};
 #[cfg(feature = "compiler")]
 use wasmer_types::Features;
 use wasmer_types::FunctionType;
@@ -190,6 +190,10 @@ impl Engine for NativeEngine {
         &self.engine_id
     }
 
+    fn kind(&self) -> EngineKind {
+        EngineKind::Native
+    }
+
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }
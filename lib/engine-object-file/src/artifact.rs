@@ -7,12 +7,15 @@ use std::collections::BTreeMap;
 use std::error::Error;
 use std::mem;
 use std::sync::Arc;
-use wasmer_compiler::{CompileError, Features, OperatingSystem, SymbolRegistry, Triple};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::{
-    CompileModuleInfo, FunctionBodyData, ModuleEnvironment, ModuleTranslationState,
+    CompilationProgress, CompileModuleInfo, DebugInfoPolicy, FunctionBodyData, ModuleEnvironment,
+    ModuleTranslationState,
+};
+use wasmer_compiler::{CompileError, Features, OperatingSystem, SymbolRegistry, Triple};
+use wasmer_engine::{
+    Artifact, DeserializeError, InstantiationError, ModuleFootprint, SerializeError,
 };
-use wasmer_engine::{Artifact, DeserializeError, InstantiationError, SerializeError};
 #[cfg(feature = "compiler")]
 use wasmer_engine::{Engine, Tunables};
 #[cfg(feature = "compiler")]
@@ -20,13 +23,14 @@ use wasmer_object::{emit_compilation, emit_data, get_object_for_target};
 use wasmer_types::entity::EntityRef;
 use wasmer_types::entity::{BoxedSlice, PrimaryMap};
 #[cfg(feature = "compiler")]
-use wasmer_types::DataInitializer;
+use wasmer_types::{merge_data_initializers, DataInitializer};
 use wasmer_types::{
     FunctionIndex, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer, SignatureIndex,
     TableIndex,
 };
 use wasmer_vm::{
-    FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMSharedSignatureIndex, VMTrampoline,
+    CodeRegistration, ExportResolution, FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle,
+    VMSharedSignatureIndex, VMTrampoline,
 };
 
 /// A compiled wasm module, ready to be instantiated.
@@ -36,8 +40,13 @@ pub struct ObjectFileArtifact {
     finished_functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
     finished_dynamic_function_trampolines: BoxedSlice<FunctionIndex, FunctionBodyPtr>,
     signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
+    export_resolutions: Vec<ExportResolution>,
     /// Length of the serialized metadata
     metadata_length: usize,
+    /// Lets the signal handler turn the faults of the loaded functions
+    /// into traps. Their lengths aren't known yet, so the extent of their
+    /// code isn't either.
+    _code_registration: Option<CodeRegistration>,
 }
 
 fn to_compile_error(err: impl Error) -> CompileError {
@@ -87,12 +96,27 @@ impl ObjectFileArtifact {
         }
     }
 
+    /// Serializes the metadata, prefixed with its length.
+    #[cfg(feature = "compiler")]
+    fn serialize_metadata(metadata: &ModuleMetadata) -> Result<Vec<u8>, CompileError> {
+        let serialized_data = bincode::serialize(metadata).map_err(to_compile_error)?;
+        let mut metadata_binary = vec![0; 10];
+        let mut writable = &mut metadata_binary[..];
+        leb128::write::unsigned(&mut writable, serialized_data.len() as u64)
+            .expect("Should write number");
+        metadata_binary.extend(serialized_data);
+        Ok(metadata_binary)
+    }
+
     #[cfg(feature = "compiler")]
     /// Generate a compilation
     fn generate_metadata<'data>(
         data: &'data [u8],
         features: &Features,
         tunables: &dyn Tunables,
+        max_function_results: Option<usize>,
+        prune_dead_functions: bool,
+        debug_info_policy: DebugInfoPolicy,
     ) -> Result<
         (
             CompileModuleInfo,
@@ -102,8 +126,14 @@ impl ObjectFileArtifact {
         ),
         CompileError,
     > {
-        let environ = ModuleEnvironment::new();
-        let translation = environ.translate(data).map_err(CompileError::Wasm)?;
+        let environ = ModuleEnvironment::new()
+            .with_max_function_results(max_function_results)
+            .with_threads(features.threads)
+            .with_debug_info_policy(debug_info_policy);
+        let mut translation = environ.translate(data)?;
+        if prune_dead_functions {
+            translation.prune_dead_functions()?;
+        }
         let memory_styles: PrimaryMap<MemoryIndex, MemoryStyle> = translation
             .module
             .memories
@@ -143,13 +173,21 @@ impl ObjectFileArtifact {
         let target = engine.target();
         let compiler = engine_inner.compiler()?;
         let (compile_info, function_body_inputs, data_initializers, module_translation) =
-            Self::generate_metadata(data, engine_inner.features(), tunables)?;
+            Self::generate_metadata(
+                data,
+                engine_inner.features(),
+                tunables,
+                compiler.max_function_results(),
+                compiler.prune_dead_functions(),
+                compiler.debug_info_policy(),
+            )?;
 
-        let data_initializers = data_initializers
-            .iter()
-            .map(OwnedDataInitializer::new)
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
+        let data_initializers = merge_data_initializers(
+            &data_initializers
+                .iter()
+                .map(OwnedDataInitializer::new)
+                .collect::<Vec<_>>(),
+        );
 
         let target_triple = target.triple();
 
@@ -161,11 +199,12 @@ impl ObjectFileArtifact {
             .map(|_function_body| 0u64)
             .collect::<PrimaryMap<LocalFunctionIndex, u64>>();
 
-        let metadata = ModuleMetadata {
+        let mut metadata = ModuleMetadata {
             compile_info,
             prefix: engine_inner.get_prefix(&data),
             data_initializers,
             function_body_lengths,
+            footprint: None,
         };
 
         /*
@@ -185,13 +224,8 @@ impl ObjectFileArtifact {
         - SignatureIndex -> VMSharedSignatureindextureIndex // signatures
          */
 
-        let serialized_data = bincode::serialize(&metadata).map_err(to_compile_error)?;
-        let mut metadata_binary = vec![0; 10];
-        let mut writable = &mut metadata_binary[..];
-        leb128::write::unsigned(&mut writable, serialized_data.len() as u64)
-            .expect("Should write number");
-        metadata_binary.extend(serialized_data);
-        let metadata_length = metadata_binary.len();
+        let mut metadata_binary = Self::serialize_metadata(&metadata)?;
+        let mut metadata_length = metadata_binary.len();
 
         let maybe_obj_bytes = compiler.experimental_native_compile_module(
             &target,
@@ -210,16 +244,18 @@ impl ObjectFileArtifact {
                 &metadata.compile_info,
                 module_translation.as_ref().unwrap(),
                 function_body_inputs,
+                &CompilationProgress::new(),
             )?;
-            // there's an ordering issue, but we can update function_body_lengths here.
-            /*
-            // We construct the function body lengths
-            let function_body_lengths = compilation
-            .get_function_bodies()
-            .values()
-            .map(|function_body| function_body.body.len() as u64)
-            .collect::<PrimaryMap<LocalFunctionIndex, u64>>();
-             */
+            // The function body lengths and the footprint are only known
+            // once the module is compiled.
+            metadata.function_body_lengths = compilation
+                .get_function_bodies()
+                .values()
+                .map(|function_body| function_body.body.len() as u64)
+                .collect::<PrimaryMap<LocalFunctionIndex, u64>>();
+            metadata.footprint = Some(ModuleFootprint::from_compilation(&compilation));
+            metadata_binary = Self::serialize_metadata(&metadata)?;
+            metadata_length = metadata_binary.len();
             let mut obj = get_object_for_target(&target_triple).map_err(to_compile_error)?;
             emit_data(&mut obj, WASMER_METADATA_SYMBOL, &metadata_binary)
                 .map_err(to_compile_error)?;
@@ -258,6 +294,7 @@ impl ObjectFileArtifact {
             .map(|sig| signature_registry.register(sig))
             .collect::<PrimaryMap<_, _>>();
 
+        let export_resolutions = metadata.compile_info.module.export_resolutions();
         Ok(Self {
             metadata,
             module_bytes,
@@ -265,7 +302,9 @@ impl ObjectFileArtifact {
             finished_dynamic_function_trampolines: finished_dynamic_function_trampolines
                 .into_boxed_slice(),
             signatures: signatures.into_boxed_slice(),
+            export_resolutions,
             metadata_length,
+            _code_registration: None,
         })
     }
 
@@ -322,8 +361,8 @@ impl ObjectFileArtifact {
             byte_buffer[0..WORD_SIZE]
                 .clone_from_slice(&bytes[cur_offset..(cur_offset + WORD_SIZE)]);
             sp.ptr = usize::from_ne_bytes(byte_buffer);
+            sp.len = metadata.function_body_lengths[LocalFunctionIndex::new(i)] as usize;
             cur_offset += WORD_SIZE;
-            // TODO: we can read  back the length here if we serialize it. This will improve debug output.
 
             let fp = FunctionBodyPtr(mem::transmute(sp));
             finished_functions.push(fp);
@@ -373,6 +412,19 @@ impl ObjectFileArtifact {
             finished_dynamic_function_trampolines.push(fp);
         }
 
+        // The lengths of the functions are only known if the module wasn't
+        // compiled to an object file directly by the compiler.
+        let code_registration = if metadata
+            .function_body_lengths
+            .values()
+            .all(|length| *length > 0)
+        {
+            CodeRegistration::new(finished_functions.values().cloned())
+        } else {
+            CodeRegistration::unknown()
+        };
+
+        let export_resolutions = metadata.compile_info.module.export_resolutions();
         Ok(Self {
             metadata,
             module_bytes: bytes.to_owned(),
@@ -380,7 +432,9 @@ impl ObjectFileArtifact {
             finished_dynamic_function_trampolines: finished_dynamic_function_trampolines
                 .into_boxed_slice(),
             signatures: signatures.into_boxed_slice(),
+            export_resolutions,
             metadata_length: 0,
+            _code_registration: Some(code_registration),
         })
     }
 
@@ -440,10 +494,30 @@ impl Artifact for ObjectFileArtifact {
         &self.signatures
     }
 
+    fn export_resolutions(&self) -> &[ExportResolution] {
+        &self.export_resolutions
+    }
+
     fn preinstantiate(&self) -> Result<(), InstantiationError> {
         Ok(())
     }
 
+    fn footprint(&self) -> ModuleFootprint {
+        // The object file can't record its own size. Only the number of
+        // functions and trampolines of a module compiled straight to an
+        // object file by the compiler is known.
+        let footprint = self.metadata.footprint.unwrap_or_else(|| ModuleFootprint {
+            functions: self.metadata.function_body_lengths.len(),
+            trampolines: self.signatures().len()
+                + self.finished_dynamic_function_trampolines().len(),
+            ..Default::default()
+        });
+        ModuleFootprint {
+            serialized_bytes: self.module_bytes.len(),
+            ..footprint
+        }
+    }
+
     /// Serialize a ObjectFileArtifact
     fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
         Ok(self.module_bytes.clone())
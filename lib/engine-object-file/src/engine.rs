@@ -6,7 +6,7 @@ use std::sync::{Arc, Mutex};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::Compiler;
 use wasmer_compiler::{CompileError, Target};
-use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, Tunables};
+use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, EngineKind, Tunables};
 #[cfg(feature = "compiler")]
 use wasmer_types::Features;
 use wasmer_types::FunctionType;
@@ -167,6 +167,10 @@ impl Engine for ObjectFileEngine {
         &self.engine_id
     }
 
+    fn kind(&self) -> EngineKind {
+        EngineKind::ObjectFile
+    }
+
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use wasmer_compiler::{CompileModuleInfo, SectionIndex, Symbol, SymbolRegistry};
+use wasmer_engine::ModuleFootprint;
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{FunctionIndex, LocalFunctionIndex, OwnedDataInitializer, SignatureIndex};
 
@@ -11,6 +12,9 @@ pub struct ModuleMetadata {
     pub data_initializers: Box<[OwnedDataInitializer]>,
     // The function body lengths (used to find function by address)
     pub function_body_lengths: PrimaryMap<LocalFunctionIndex, u64>,
+    // The footprint, recorded when the module is compiled by the engine,
+    // or `None` when the compiler compiles it straight to an object file
+    pub footprint: Option<ModuleFootprint>,
 }
 
 impl SymbolRegistry for ModuleMetadata {
@@ -63,4 +67,12 @@ impl SymbolRegistry for ModuleMetadata {
             None
         }
     }
+
+    fn local_function_name(&self, index: LocalFunctionIndex) -> Option<String> {
+        let module = &self.compile_info.module;
+        module
+            .function_names
+            .get(&module.func_index(index))
+            .cloned()
+    }
 }
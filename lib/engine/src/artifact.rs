@@ -1,7 +1,9 @@
 use crate::{
-    resolve_imports, InstantiationError, Resolver, RuntimeError, SerializeError, Tunables,
+    resolve_imports, InstantiationError, ModuleFootprint, Resolver, RuntimeError, SerializeError,
+    Tunables,
 };
 use std::any::Any;
+use std::borrow::Cow;
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
@@ -12,7 +14,8 @@ use wasmer_types::{
     SignatureIndex, TableIndex,
 };
 use wasmer_vm::{
-    FunctionBodyPtr, InstanceHandle, MemoryStyle, ModuleInfo, TableStyle, VMSharedSignatureIndex,
+    init_traps, ExportResolution, FunctionBodyPtr, InstanceHandle, MemoryStyle, ModuleInfo,
+    TableStyle, VMSharedSignatureIndex,
 };
 
 /// An `Artifact` is the product that the `Engine`
@@ -41,6 +44,15 @@ pub trait Artifact: Send + Sync + Upcastable {
     /// Returns the features for this Artifact
     fn features(&self) -> &Features;
 
+    /// Whether this `Artifact` was compiled to run unchecked, see
+    /// `Engine::unchecked`.
+    ///
+    /// The signal handlers aren't installed to instantiate unchecked
+    /// artifacts.
+    fn unchecked(&self) -> bool {
+        false
+    }
+
     /// Returns the memory styles associated with this `Artifact`.
     fn memory_styles(&self) -> &PrimaryMap<MemoryIndex, MemoryStyle>;
 
@@ -61,6 +73,10 @@ pub trait Artifact: Send + Sync + Upcastable {
     /// Returns the associated VM signatures for this `Artifact`.
     fn signatures(&self) -> &BoxedSlice<SignatureIndex, VMSharedSignatureIndex>;
 
+    /// Returns how to resolve each export of the instances, in the order of
+    /// the exports of the module, see `ModuleInfo::export_resolutions`.
+    fn export_resolutions(&self) -> &[ExportResolution];
+
     /// Serializes an artifact into bytes
     fn serialize(&self) -> Result<Vec<u8>, SerializeError>;
 
@@ -71,6 +87,30 @@ pub trait Artifact: Send + Sync + Upcastable {
         Ok(())
     }
 
+    /// Returns the memory footprint of this `Artifact`.
+    ///
+    /// By default, only the number of functions and trampolines and the
+    /// size of the code of the finished functions are known. Engines
+    /// recording the footprint when building the artifact override this.
+    fn footprint(&self) -> ModuleFootprint {
+        ModuleFootprint {
+            code_bytes: self
+                .finished_functions()
+                .values()
+                .map(|function| unsafe { (&***function).len() })
+                .sum(),
+            functions: self.finished_functions().len(),
+            trampolines: self.signatures().len()
+                + self.finished_dynamic_function_trampolines().len(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the size in bytes of the executable code of the functions.
+    fn code_size(&self) -> usize {
+        self.footprint().code_bytes
+    }
+
     /// Do preinstantiation logic that is executed before instantiating
     fn preinstantiate(&self) -> Result<(), InstantiationError> {
         Ok(())
@@ -113,6 +153,11 @@ pub trait Artifact: Send + Sync + Upcastable {
 
         self.register_frame_info();
 
+        // Ensure that our signal handlers are ready for action.
+        if !self.unchecked() {
+            init_traps();
+        }
+
         InstanceHandle::new(
             module,
             self.finished_functions().clone(),
@@ -121,6 +166,7 @@ pub trait Artifact: Send + Sync + Upcastable {
             finished_globals,
             imports,
             self.signatures().clone(),
+            tunables.data_prefault_threshold(),
             host_state,
         )
         .map_err(|trap| InstantiationError::Start(RuntimeError::from_trap(trap)))
@@ -135,18 +181,75 @@ pub trait Artifact: Send + Sync + Upcastable {
         &self,
         handle: &InstanceHandle,
     ) -> Result<(), InstantiationError> {
-        let data_initializers = self
-            .data_initializers()
-            .iter()
-            .map(|init| DataInitializer {
-                location: init.location.clone(),
-                data: &*init.data,
-            })
-            .collect::<Vec<_>>();
+        let data_initializers = borrow_data_initializers(self.data_initializers());
         handle
             .finish_instantiation(&data_initializers)
             .map_err(|trap| InstantiationError::Start(RuntimeError::from_trap(trap)))
     }
+
+    /// Creates an `Instance` from this `Artifact` and finishes its
+    /// instantiation, calling `hooks` in between the steps.
+    ///
+    /// The returned handle is the one `hooks` are called with. If a hook
+    /// fails, the instantiation stops with an
+    /// [`InstantiationError::Start`] error.
+    ///
+    /// # Safety
+    ///
+    /// See [`InstanceHandle::new`] and
+    /// [`InstanceHandle::finish_instantiation`].
+    unsafe fn instantiate_with_hooks(
+        &self,
+        tunables: &dyn Tunables,
+        resolver: &dyn Resolver,
+        host_state: Box<dyn Any>,
+        hooks: &dyn InstantiationHooks,
+    ) -> Result<InstanceHandle, InstantiationError> {
+        let handle = self.instantiate(tunables, resolver, host_state)?;
+        hooks
+            .after_memories(&handle)
+            .map_err(InstantiationError::Start)?;
+        let data_initializers = borrow_data_initializers(self.data_initializers());
+        handle
+            .initialize(&data_initializers)
+            .map_err(|trap| InstantiationError::Start(RuntimeError::from_trap(trap)))?;
+        hooks
+            .before_start(&handle)
+            .map_err(InstantiationError::Start)?;
+        handle
+            .invoke_start_function()
+            .map_err(|trap| InstantiationError::Start(RuntimeError::from_trap(trap)))?;
+        Ok(handle)
+    }
+}
+
+/// Callbacks run by `Artifact::instantiate_with_hooks` between the steps of
+/// the instantiation, to set up the instance from the host before its start
+/// function runs.
+pub trait InstantiationHooks {
+    /// Called once the memories, tables and globals of the instance are
+    /// created and its imports resolved, before the element and data
+    /// segments are applied.
+    fn after_memories(&self, _handle: &InstanceHandle) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+
+    /// Called once the element and data segments are applied, right
+    /// before the start function runs.
+    fn before_start(&self, _handle: &InstanceHandle) -> Result<(), RuntimeError> {
+        Ok(())
+    }
+}
+
+/// Borrows `data_initializers` to initialize an instance with.
+fn borrow_data_initializers(data_initializers: &[OwnedDataInitializer]) -> Vec<DataInitializer> {
+    data_initializers
+        .iter()
+        .map(|init| DataInitializer {
+            location: init.location.clone(),
+            data: Cow::Borrowed(&*init.data),
+        })
+        .collect()
 }
 
 // Implementation of `Upcastable` taken from https://users.rust-lang.org/t/why-does-downcasting-not-work-for-subtraits/33286/7 .
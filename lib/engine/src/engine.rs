@@ -5,7 +5,7 @@ use crate::{Artifact, DeserializeError};
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
 use std::sync::Arc;
-use wasmer_compiler::{CompileError, Target};
+use wasmer_compiler::{CompilationProgress, CompileError, Target};
 use wasmer_types::FunctionType;
 use wasmer_vm::{VMSharedSignatureIndex, VMTrampoline};
 
@@ -38,6 +38,21 @@ pub trait Engine {
         tunables: &dyn Tunables,
     ) -> Result<Arc<dyn Artifact>, CompileError>;
 
+    /// Compile a WebAssembly binary, counting the compiled functions in
+    /// `progress`, which can cancel the compilation.
+    ///
+    /// Engines that don't report their progress only check whether the
+    /// compilation is cancelled before starting it.
+    fn compile_with_progress(
+        &self,
+        binary: &[u8],
+        tunables: &dyn Tunables,
+        progress: &CompilationProgress,
+    ) -> Result<Arc<dyn Artifact>, CompileError> {
+        progress.check_cancelled()?;
+        self.compile(binary, tunables)
+    }
+
     /// Deserializes a WebAssembly module
     ///
     /// # Safety
@@ -58,6 +73,13 @@ pub trait Engine {
         self.deserialize(&bytes)
     }
 
+    /// Whether the artifacts of this engine run unchecked: without the
+    /// signal handlers and trap catching protecting the host from their
+    /// faults, see the `unchecked` option of the JIT engine.
+    fn unchecked(&self) -> bool {
+        false
+    }
+
     /// A unique identifier for this object.
     ///
     /// This exists to allow us to compare two Engines for equality. Otherwise,
@@ -65,10 +87,29 @@ pub trait Engine {
     /// of trait representation.
     fn id(&self) -> &EngineId;
 
+    /// The kind of the engine, which tells where its artifacts can be
+    /// deserialized.
+    fn kind(&self) -> EngineKind;
+
     /// Clone the engine
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync>;
 }
 
+/// The kind of an [`Engine`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EngineKind {
+    /// The engine compiles the code in memory. Its artifacts are
+    /// deserialized into memory on a compatible host.
+    Jit,
+    /// The engine compiles the code into shared objects, which are
+    /// loaded by the system dynamic loader.
+    Native,
+    /// The engine compiles the code into object files, to link
+    /// statically into an executable.
+    ObjectFile,
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(transparent)]
 /// A unique identifier for an Engine.
@@ -2,8 +2,10 @@
 use crate::trap::RuntimeError;
 use std::io;
 use thiserror::Error;
-use wasmer_compiler::CompileError;
+use wasmer_compiler::{Architecture, CompileError, RelocationKind};
 use wasmer_types::ExternType;
+use wasmer_vm::libcalls::LibCall;
+use wasmer_vm::MemoryError;
 
 /// The Serialize error can occur when serializing a
 /// compiled Module into a binary.
@@ -76,6 +78,45 @@ pub enum LinkError {
     /// Insufficient resources available for linking.
     #[error("Insufficient resources: {0}")]
     Resource(String),
+
+    /// A memory of the module couldn't be created.
+    #[error("Failed to create memory: {0}")]
+    Memory(#[source] MemoryError),
+
+    /// A module was linked to another one through an import it doesn't
+    /// have.
+    #[error("The module has no import named {0:?}")]
+    UnknownImport(String),
+
+    /// The compiled code has a relocation that can't be applied on the
+    /// target architecture.
+    #[error("Relocation kind {kind} is unsupported on {target_arch}")]
+    UnsupportedRelocation {
+        /// The kind of the relocation.
+        kind: RelocationKind,
+        /// The architecture of the host running the code.
+        target_arch: Architecture,
+    },
+
+    /// A relocation can't encode the distance to its target, which is
+    /// out of its range or misaligned.
+    #[error("Relocation kind {kind} can't reach a target {distance} bytes away")]
+    RelocationOutOfRange {
+        /// The kind of the relocation.
+        kind: RelocationKind,
+        /// The distance from the relocation to its target.
+        distance: i64,
+    },
+
+    /// A libcall was resolved to an address that can't be the one of a
+    /// function.
+    #[error("Libcall {libcall} was resolved to the invalid address {address:#x}")]
+    InvalidLibCall {
+        /// The libcall.
+        libcall: LibCall,
+        /// The address it was resolved to.
+        address: usize,
+    },
 }
 
 /// An error while instantiating a module.
@@ -0,0 +1,77 @@
+//! The memory footprint of a compiled module.
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use wasmer_compiler::{Compilation, CompiledFunctionUnwindInfo, CustomSectionProtection};
+
+/// The memory footprint of a compiled module, see `Artifact::footprint`.
+///
+/// The sizes are computed when the artifact is built and recorded in its
+/// serialized form, so a deserialized artifact reports the same numbers.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ModuleFootprint {
+    /// The size in bytes of the executable code of the functions.
+    pub code_bytes: usize,
+    /// The size in bytes of the read-only data, such as the constants
+    /// and jump tables emitted in custom sections.
+    pub rodata_bytes: usize,
+    /// The size in bytes of the unwind information.
+    pub unwind_info_bytes: usize,
+    /// The size in bytes of the frame information of the functions, used
+    /// to symbolicate traps, in its serialized form.
+    pub frame_info_bytes: usize,
+    /// The size in bytes of the serialized artifact.
+    pub serialized_bytes: usize,
+    /// The number of compiled functions.
+    pub functions: usize,
+    /// The number of function call and dynamic function trampolines.
+    pub trampolines: usize,
+}
+
+impl ModuleFootprint {
+    /// Computes the footprint of `compilation`.
+    ///
+    /// The serialized size depends on the engine and is left to zero.
+    pub fn from_compilation(compilation: &Compilation) -> Self {
+        let eh_frame = compilation.get_debug().map(|debug| debug.eh_frame);
+        let mut footprint = Self {
+            functions: compilation.len(),
+            trampolines: compilation.get_function_call_trampolines().len()
+                + compilation.get_dynamic_function_trampolines().len(),
+            ..Default::default()
+        };
+        for function in compilation {
+            footprint.code_bytes += function.body.body.len();
+            if let Some(CompiledFunctionUnwindInfo::WindowsX64(info)) = &function.body.unwind_info {
+                footprint.unwind_info_bytes += info.len();
+            }
+            footprint.frame_info_bytes += bincode::serialized_size(&function.frame_info)
+                .expect("Can't serialize the info")
+                as usize;
+        }
+        for (index, section) in compilation.get_custom_sections().iter() {
+            if Some(index) == eh_frame {
+                footprint.unwind_info_bytes += section.bytes.len();
+            } else if section.protection == CustomSectionProtection::Read {
+                footprint.rodata_bytes += section.bytes.len();
+            }
+        }
+        footprint
+    }
+}
+
+impl fmt::Display for ModuleFootprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} functions, {} trampolines, {} bytes of code, {} bytes of read-only data, \
+             {} bytes of unwind info, {} bytes of frame info, {} bytes serialized",
+            self.functions,
+            self.trampolines,
+            self.code_bytes,
+            self.rodata_bytes,
+            self.unwind_info_bytes,
+            self.frame_info_bytes,
+            self.serialized_bytes
+        )
+    }
+}
@@ -23,16 +23,18 @@
 mod artifact;
 mod engine;
 mod error;
+mod footprint;
 mod resolver;
 mod serialize;
 mod trap;
 mod tunables;
 
-pub use crate::artifact::Artifact;
-pub use crate::engine::{Engine, EngineId};
+pub use crate::artifact::{Artifact, InstantiationHooks};
+pub use crate::engine::{Engine, EngineId, EngineKind};
 pub use crate::error::{
     DeserializeError, ImportError, InstantiationError, LinkError, SerializeError,
 };
+pub use crate::footprint::ModuleFootprint;
 pub use crate::resolver::{
     resolve_imports, ChainableNamedResolver, NamedResolver, NamedResolverChain, NullResolver,
     Resolver,
@@ -128,6 +128,7 @@ pub fn resolve_imports(
     let mut table_imports = PrimaryMap::with_capacity(module.num_imported_tables);
     let mut memory_imports = PrimaryMap::with_capacity(module.num_imported_memories);
     let mut global_imports = PrimaryMap::with_capacity(module.num_imported_globals);
+    let mut noreturn_functions = PrimaryMap::with_capacity(module.num_imported_functions);
 
     for ((module_name, field, import_idx), import_index) in module.imports.iter() {
         let resolved = resolver.resolve(*import_idx, module_name, field);
@@ -169,6 +170,7 @@ pub fn resolve_imports(
                     body: address,
                     vmctx: f.vmctx,
                 });
+                noreturn_functions.push(f.noreturn);
             }
             Export::Table(ref t) => {
                 table_imports.push(VMTableImport {
@@ -225,6 +227,7 @@ pub fn resolve_imports(
         table_imports,
         memory_imports,
         global_imports,
+        noreturn_functions,
     ))
 }
 
@@ -94,11 +94,16 @@ impl RuntimeError {
                 } else {
                     info
                 };
-                let code = info
-                    .lookup_trap_info(pc)
-                    .map_or(signal_trap.unwrap_or(TrapCode::StackOverflow), |info| {
-                        info.trap_code
-                    });
+                let code = match signal_trap {
+                    // The compiled code can't tell stores to read-only
+                    // memories from out of bounds accesses.
+                    Some(TrapCode::ReadOnlyMemory) => TrapCode::ReadOnlyMemory,
+                    _ => info
+                        .lookup_trap_info(pc)
+                        .map_or(signal_trap.unwrap_or(TrapCode::StackOverflow), |info| {
+                            info.trap_code
+                        }),
+                };
                 Self::new_with_trace(info, Some(pc), RuntimeErrorSource::Trap(code), backtrace)
             }
             // A trap triggered manually from the Wasmer runtime
@@ -186,6 +191,15 @@ impl RuntimeError {
         format!("{}", self.inner.source)
     }
 
+    /// Returns the [`TrapCode`] of the trap that caused this error, if it's
+    /// caused by a trap rather than by the host.
+    pub fn to_trap(&self) -> Option<TrapCode> {
+        match self.inner.source {
+            RuntimeErrorSource::Trap(code) => Some(code),
+            _ => None,
+        }
+    }
+
     /// Returns a list of function frames in WebAssembly code that led to this
     /// trap happening.
     pub fn trace(&self) -> &[FrameInfo] {
@@ -16,7 +16,7 @@ use std::collections::BTreeMap;
 use std::sync::{Arc, RwLock};
 use wasmer_compiler::{CompiledFunctionFrameInfo, SourceLoc, TrapInformation};
 use wasmer_types::entity::{BoxedSlice, EntityRef, PrimaryMap};
-use wasmer_types::LocalFunctionIndex;
+use wasmer_types::{ExportIndex, LocalFunctionIndex};
 use wasmer_vm::{FunctionBodyPtr, ModuleInfo};
 
 lazy_static::lazy_static! {
@@ -158,7 +158,18 @@ impl GlobalFrameInfo {
         Some(FrameInfo {
             module_name: module.module.name(),
             func_index: func_index.index() as u32,
-            function_name: module.module.function_names.get(&func_index).cloned(),
+            function_name: module
+                .module
+                .function_names
+                .get(&func_index)
+                .map(|name| name.as_str())
+                .or_else(|| {
+                    module
+                        .module
+                        .export_names_of(ExportIndex::Function(func_index))
+                        .next()
+                })
+                .map(ToString::to_string),
             instr,
             func_start: instr_map.start_srcloc,
         })
@@ -250,7 +261,54 @@ pub fn register(
     if functions.is_empty() {
         return None;
     }
+    Some(insert_module_frame_info(
+        min,
+        max,
+        functions,
+        module,
+        frame_infos,
+    ))
+}
+
+/// Registers the frame information of the function `local_index` of
+/// `module`, allocated apart from the other functions of the module, for
+/// example when it's recompiled.
+///
+/// The returned object, when dropped, unregisters it.
+pub fn register_function(
+    module: Arc<ModuleInfo>,
+    local_index: LocalFunctionIndex,
+    body: FunctionBodyPtr,
+    frame_info: CompiledFunctionFrameInfo,
+) -> GlobalFrameInfoRegistration {
+    let (start, end) = unsafe {
+        let ptr = (**body).as_ptr();
+        let len = (**body).len();
+        (ptr as usize, ptr as usize + len)
+    };
+    let mut functions = BTreeMap::new();
+    functions.insert(end, FunctionInfo { start, local_index });
+    // The frame infos are indexed by function, only the one of
+    // `local_index` is used.
+    let mut frame_infos = PrimaryMap::with_capacity(local_index.index() + 1);
+    while frame_infos.len() < local_index.index() {
+        frame_infos.push(SerializableFunctionFrameInfo::Processed(
+            CompiledFunctionFrameInfo::default(),
+        ));
+    }
+    frame_infos.push(SerializableFunctionFrameInfo::Processed(frame_info));
+    insert_module_frame_info(start, end, functions, module, frame_infos)
+}
 
+/// Inserts the frame information of functions spanning `min..max` in the
+/// global map.
+fn insert_module_frame_info(
+    min: usize,
+    max: usize,
+    functions: BTreeMap<usize, FunctionInfo>,
+    module: Arc<ModuleInfo>,
+    frame_infos: PrimaryMap<LocalFunctionIndex, SerializableFunctionFrameInfo>,
+) -> GlobalFrameInfoRegistration {
     let mut info = FRAME_INFO.write().unwrap();
     // First up assert that our chunk of jit functions doesn't collide with
     // any other known chunks of jit functions...
@@ -272,7 +330,7 @@ pub fn register(
         },
     );
     assert!(prev.is_none());
-    Some(GlobalFrameInfoRegistration { key: max })
+    GlobalFrameInfoRegistration { key: max }
 }
 
 /// Description of a frame in a backtrace for a [`Trap`].
@@ -28,6 +28,13 @@ pub trait Tunables {
     /// Create a memory given a memory type
     fn create_table(&self, ty: &TableType, style: &TableStyle) -> Result<Arc<dyn Table>, String>;
 
+    /// The size in bytes from which the pages written by a data
+    /// initializer are faulted in at once before it is applied, or `None`
+    /// to let every page fault on its first write.
+    fn data_prefault_threshold(&self) -> Option<usize> {
+        None
+    }
+
     /// Create a global with an unset value.
     fn create_global(&self, ty: GlobalType) -> Result<Arc<Global>, String> {
         Ok(Arc::new(Global::new(ty)))
@@ -46,10 +53,7 @@ pub trait Tunables {
             let mi = MemoryIndex::new(index);
             let ty = &module.memories[mi];
             let style = &memory_styles[mi];
-            memories.push(
-                self.create_memory(ty, style)
-                    .map_err(|e| LinkError::Resource(format!("Failed to create memory: {}", e)))?,
-            );
+            memories.push(self.create_memory(ty, style).map_err(LinkError::Memory)?);
         }
         Ok(memories)
     }
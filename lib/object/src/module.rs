@@ -5,6 +5,8 @@ use wasmer_compiler::{
     Architecture, BinaryFormat, Compilation, CustomSectionProtection, Endianness, RelocationTarget,
     Symbol, SymbolRegistry, Triple,
 };
+use wasmer_types::entity::EntityRef;
+use wasmer_types::LocalFunctionIndex;
 
 /// Create an object for a given target `Triple`.
 ///
@@ -148,6 +150,7 @@ pub fn emit_compilation(
     }
 
     // Add functions
+    let mut function_offsets = Vec::with_capacity(function_bodies.len());
     for (function_local_index, function) in function_bodies.into_iter() {
         let function_name =
             symbol_registry.symbol_to_name(Symbol::LocalFunction(function_local_index));
@@ -163,7 +166,8 @@ pub fn emit_compilation(
         });
 
         let section_id = obj.section_id(StandardSection::Text);
-        obj.add_symbol_data(symbol_id, section_id, &function.body, 1);
+        let offset = obj.add_symbol_data(symbol_id, section_id, &function.body, 1);
+        function_offsets.push((function_local_index, offset, function.body.len()));
     }
 
     // Add function call trampolines
@@ -306,5 +310,121 @@ pub fn emit_compilation(
         }
     }
 
+    // Add the debugging symbols of the functions last, so that they can't
+    // shadow the symbols looked up by name above.
+    let section_id = obj.section_id(StandardSection::Text);
+    for (function_local_index, offset, size) in function_offsets {
+        obj.add_symbol(ObjSymbol {
+            name: function_debug_name(symbol_registry, function_local_index).into_bytes(),
+            value: offset,
+            size: size as u64,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Compilation,
+            weak: false,
+            section: SymbolSection::Section(section_id),
+            flags: SymbolFlags::None,
+        });
+    }
+
     Ok(())
 }
+
+/// Returns the name of the local symbol aliasing a function, to make the
+/// output of debuggers and profilers readable: the function's name in the
+/// Wasm module, demangled if it's a mangled Rust symbol, or else
+/// `wasm_function_{index}`.
+///
+/// Other mangled names, like C++ ones, are kept as they are, since
+/// debuggers and profilers demangle them.
+fn function_debug_name(symbol_registry: &impl SymbolRegistry, index: LocalFunctionIndex) -> String {
+    match symbol_registry.local_function_name(index) {
+        Some(name) => match rustc_demangle::try_demangle(&name) {
+            // Leave out the hash of the legacy mangling scheme.
+            Ok(demangled) => format!("{:#}", demangled),
+            Err(_) => name,
+        },
+        None => format!("wasm_function_{}", index.index()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer_compiler::{CompiledFunction, FunctionBody};
+    use wasmer_types::entity::{PrimaryMap, SecondaryMap};
+
+    struct Registry;
+
+    impl SymbolRegistry for Registry {
+        fn symbol_to_name(&self, symbol: Symbol) -> String {
+            match symbol {
+                Symbol::LocalFunction(index) => format!("wasmer_function_{}", index.index()),
+                Symbol::Section(index) => format!("wasmer_section_{}", index.index()),
+                Symbol::FunctionCallTrampoline(index) => {
+                    format!("wasmer_trampoline_function_call_{}", index.index())
+                }
+                Symbol::DynamicFunctionTrampoline(index) => {
+                    format!("wasmer_trampoline_dynamic_function_{}", index.index())
+                }
+            }
+        }
+
+        fn name_to_symbol(&self, name: &str) -> Option<Symbol> {
+            let index = name.strip_prefix("wasmer_function_")?.parse().ok()?;
+            Some(Symbol::LocalFunction(LocalFunctionIndex::from_u32(index)))
+        }
+
+        fn local_function_name(&self, index: LocalFunctionIndex) -> Option<String> {
+            match index.index() {
+                0 => Some("add".to_string()),
+                1 => Some("_ZN4core3fmt5write17h0123456789abcdefE".to_string()),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn function_debug_symbols() {
+        let triple = Triple::host();
+        let mut obj = match get_object_for_target(&triple) {
+            Ok(obj) if triple.architecture == Architecture::X86_64 => obj,
+            _ => return,
+        };
+        let mut functions = PrimaryMap::new();
+        for len in &[3, 5, 7] {
+            functions.push(CompiledFunction {
+                body: FunctionBody {
+                    body: vec![0xc3; *len],
+                    unwind_info: None,
+                },
+                relocations: vec![],
+                jt_offsets: SecondaryMap::new(),
+                jt_entries: SecondaryMap::new(),
+                frame_info: Default::default(),
+            });
+        }
+        let compilation = Compilation::new(
+            functions,
+            PrimaryMap::new(),
+            PrimaryMap::new(),
+            PrimaryMap::new(),
+            None,
+        );
+        emit_compilation(&mut obj, compilation, &Registry, &triple).unwrap();
+
+        for (index, name) in ["add", "core::fmt::write", "wasm_function_2"]
+            .iter()
+            .enumerate()
+        {
+            let linkage_name = format!("wasmer_function_{}", index);
+            let linkage_id = obj.symbol_id(linkage_name.as_bytes()).unwrap();
+            let debug_id = obj.symbol_id(name.as_bytes()).unwrap();
+            let (linkage, debug) = (obj.symbol(linkage_id), obj.symbol(debug_id));
+            assert_eq!(debug.scope, SymbolScope::Compilation);
+            assert_eq!(debug.kind, SymbolKind::Text);
+            assert_eq!(debug.section, linkage.section);
+            assert_eq!(debug.value, linkage.value);
+            assert_eq!(debug.size, [3, 5, 7][index]);
+        }
+    }
+}
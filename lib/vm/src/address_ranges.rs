@@ -0,0 +1,199 @@
+//! A table of address ranges that the signal handler can look up.
+//!
+//! The signal handler may interrupt a thread anywhere, including while it
+//! holds a lock, so it must not take any. The table is a fixed array of
+//! slots made of atomics, allocated on the first insertion.
+//! Each slot has a sequence number which is odd while the slot is written:
+//! a lookup skips the slots being written and the ones written while they
+//! were read. It may then miss a range being added or removed, which can't
+//! be in use yet, or anymore.
+
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+/// The number of ranges a table can hold.
+const CAPACITY: usize = 4096;
+
+/// A slot of the table, holding a range or none.
+#[derive(Default)]
+struct Slot {
+    /// Whether the slot is taken by a range.
+    used: AtomicBool,
+    /// The number of writes of the slot, started or done: it's odd while
+    /// the slot is being written.
+    sequence: AtomicUsize,
+    start: AtomicUsize,
+    end: AtomicUsize,
+    data: AtomicUsize,
+}
+
+impl Slot {
+    fn write(&self, start: usize, end: usize, data: usize) {
+        self.sequence.fetch_add(1, Ordering::SeqCst);
+        self.start.store(start, Ordering::SeqCst);
+        self.end.store(end, Ordering::SeqCst);
+        self.data.store(data, Ordering::SeqCst);
+        self.sequence.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns the range of the slot and its data, or `None` if the slot
+    /// is being written.
+    fn read(&self) -> Option<(usize, usize, usize)> {
+        let sequence = self.sequence.load(Ordering::SeqCst);
+        if sequence % 2 == 1 {
+            return None;
+        }
+        let range = (
+            self.start.load(Ordering::SeqCst),
+            self.end.load(Ordering::SeqCst),
+            self.data.load(Ordering::SeqCst),
+        );
+        if self.sequence.load(Ordering::SeqCst) != sequence {
+            return None;
+        }
+        Some(range)
+    }
+}
+
+/// Frees the `slots` allocated by `AddressRanges::allocated_slots`.
+unsafe fn free_slots(slots: *mut Slot) {
+    drop(Box::from_raw(
+        slice::from_raw_parts_mut(slots, CAPACITY) as *mut [Slot]
+    ));
+}
+
+/// A fixed-size table of address ranges, each with a word of data.
+pub(crate) struct AddressRanges {
+    /// The `CAPACITY` slots, or null until the first insertion.
+    slots: AtomicPtr<Slot>,
+}
+
+impl AddressRanges {
+    /// Creates an empty table.
+    pub(crate) const fn new() -> Self {
+        Self {
+            slots: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    fn slots(&self) -> Option<&[Slot]> {
+        let slots = self.slots.load(Ordering::SeqCst);
+        if slots.is_null() {
+            None
+        } else {
+            Some(unsafe { slice::from_raw_parts(slots, CAPACITY) })
+        }
+    }
+
+    fn allocated_slots(&self) -> &[Slot] {
+        if let Some(slots) = self.slots() {
+            return slots;
+        }
+        let slots = (0..CAPACITY)
+            .map(|_| Slot::default())
+            .collect::<Box<[Slot]>>();
+        let slots = Box::into_raw(slots) as *mut Slot;
+        if self
+            .slots
+            .compare_exchange(ptr::null_mut(), slots, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            // Another thread allocated the slots first.
+            unsafe { free_slots(slots) };
+        }
+        self.slots().unwrap()
+    }
+
+    /// Adds the range `start..end` with `data`.
+    ///
+    /// Returns the index to remove the range with, or `None` if the table
+    /// is full.
+    pub(crate) fn insert(&self, start: usize, end: usize, data: usize) -> Option<usize> {
+        let slots = self.allocated_slots();
+        let index = slots.iter().position(|slot| {
+            slot.used
+                .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+        })?;
+        slots[index].write(start, end, data);
+        Some(index)
+    }
+
+    /// Removes the range added at `index`.
+    pub(crate) fn remove(&self, index: usize) {
+        let slot = &self.slots().expect("no range was added")[index];
+        slot.write(0, 0, 0);
+        slot.used.store(false, Ordering::SeqCst);
+    }
+
+    /// Returns the start and the data of a range containing `address`.
+    ///
+    /// This is async-signal-safe.
+    pub(crate) fn lookup(&self, address: usize) -> Option<(usize, usize)> {
+        self.slots()?.iter().find_map(|slot| match slot.read() {
+            Some((start, end, data)) if start <= address && address < end => Some((start, data)),
+            _ => None,
+        })
+    }
+}
+
+impl Drop for AddressRanges {
+    fn drop(&mut self) {
+        let slots = *self.slots.get_mut();
+        if !slots.is_null() {
+            unsafe { free_slots(slots) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_lookup_remove() {
+        let ranges = AddressRanges::new();
+        assert_eq!(ranges.lookup(0), None);
+
+        let first = ranges.insert(0x1000, 0x2000, 1).unwrap();
+        let second = ranges.insert(0x3000, 0x3010, 2).unwrap();
+        assert_eq!(ranges.lookup(0xfff), None);
+        assert_eq!(ranges.lookup(0x1000), Some((0x1000, 1)));
+        assert_eq!(ranges.lookup(0x1fff), Some((0x1000, 1)));
+        assert_eq!(ranges.lookup(0x2000), None);
+        assert_eq!(ranges.lookup(0x3008), Some((0x3000, 2)));
+
+        ranges.remove(first);
+        assert_eq!(ranges.lookup(0x1000), None);
+        assert_eq!(ranges.lookup(0x3008), Some((0x3000, 2)));
+
+        // The slot of a removed range is reused.
+        assert_eq!(ranges.insert(0x5000, 0x6000, 3), Some(first));
+        ranges.remove(second);
+        assert_eq!(ranges.lookup(0x3008), None);
+    }
+
+    #[test]
+    fn full_table() {
+        let ranges = AddressRanges::new();
+        for i in 0..CAPACITY {
+            assert_eq!(ranges.insert(i * 16, i * 16 + 16, i), Some(i));
+        }
+        assert_eq!(ranges.insert(0x10_0000, 0x10_0010, 0), None);
+        ranges.remove(7);
+        assert_eq!(ranges.insert(0x10_0000, 0x10_0010, 0), Some(7));
+        assert_eq!(ranges.lookup(0x10_0000), Some((0x10_0000, 0)));
+    }
+
+    #[test]
+    fn slot_being_written_is_skipped() {
+        let ranges = AddressRanges::new();
+        let index = ranges.insert(0x1000, 0x2000, 1).unwrap();
+        let slot = &ranges.slots().unwrap()[index];
+        slot.sequence.fetch_add(1, Ordering::SeqCst);
+        assert_eq!(ranges.lookup(0x1000), None);
+        slot.sequence.fetch_add(1, Ordering::SeqCst);
+        assert_eq!(ranges.lookup(0x1000), Some((0x1000, 1)));
+    }
+}
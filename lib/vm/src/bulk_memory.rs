@@ -0,0 +1,62 @@
+//! Helpers shared by the bulk memory builtins (`memory.copy`,
+//! `memory.fill` and `memory.init`).
+
+use crate::trap::TrapCode;
+
+/// Check that the range `[offset, offset + len)` fits in a memory of
+/// `memory_len` bytes.
+///
+/// A zero-length range is valid as long as `offset` is not past the end of
+/// the memory, so `offset == memory_len` with `len == 0` succeeds.
+///
+/// # Errors
+///
+/// Returns [`TrapCode::HeapAccessOutOfBounds`] if the range doesn't fit,
+/// including when `offset + len` overflows.
+pub fn check_bounds(len: u32, offset: u32, memory_len: u32) -> Result<(), TrapCode> {
+    match offset.checked_add(len) {
+        Some(end) if end <= memory_len => Ok(()),
+        _ => Err(TrapCode::HeapAccessOutOfBounds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_fit() {
+        assert_eq!(check_bounds(16, 0, 16), Ok(()));
+        assert_eq!(check_bounds(4, 12, 16), Ok(()));
+    }
+
+    #[test]
+    fn off_by_one() {
+        assert_eq!(
+            check_bounds(17, 0, 16),
+            Err(TrapCode::HeapAccessOutOfBounds)
+        );
+        assert_eq!(
+            check_bounds(4, 13, 16),
+            Err(TrapCode::HeapAccessOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(
+            check_bounds(u32::MAX, 1, u32::MAX),
+            Err(TrapCode::HeapAccessOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn zero_length() {
+        assert_eq!(check_bounds(0, 16, 16), Ok(()));
+        assert_eq!(check_bounds(0, 0, 0), Ok(()));
+        assert_eq!(
+            check_bounds(0, 17, 16),
+            Err(TrapCode::HeapAccessOutOfBounds)
+        );
+    }
+}
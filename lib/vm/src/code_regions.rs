@@ -0,0 +1,194 @@
+//! The address ranges of the compiled WebAssembly code.
+//!
+//! The signal handler only turns the faults of WebAssembly code into traps:
+//! a fault of a host function called from WebAssembly is a bug of the host,
+//! and it's forwarded to the previous handler to crash as it would without
+//! Wasmer. Engines register the code of their functions with a
+//! [`CodeRegistration`] for as long as it may run.
+
+use crate::address_ranges::AddressRanges;
+use crate::FunctionBodyPtr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The ranges of the registered code, looked up by the signal handler.
+static CODE_RANGES: AddressRanges = AddressRanges::new();
+
+/// The number of registrations of code whose extent isn't known, or which
+/// didn't fit in `CODE_RANGES`.
+static UNKNOWN_CODE: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether `pc` may be in registered WebAssembly code.
+///
+/// While code whose extent isn't known is registered, any `pc` may be.
+///
+/// This is async-signal-safe.
+pub(crate) fn is_wasm_code(pc: usize) -> bool {
+    UNKNOWN_CODE.load(Ordering::SeqCst) > 0 || CODE_RANGES.lookup(pc).is_some()
+}
+
+/// The registration of compiled WebAssembly code, letting the signal
+/// handler turn its faults into traps until it's dropped.
+#[derive(Debug)]
+pub struct CodeRegistration {
+    /// The indices of the registered ranges in `CODE_RANGES`.
+    indices: Vec<usize>,
+    /// Whether code whose extent isn't known is registered.
+    unknown: bool,
+}
+
+impl CodeRegistration {
+    /// Registers the code of `functions`.
+    ///
+    /// Empty functions are left out, as no instruction of them can fault.
+    pub fn new(functions: impl IntoIterator<Item = FunctionBodyPtr>) -> Self {
+        Self::from_ranges(functions.into_iter().map(|function| unsafe {
+            let start = (**function).as_ptr() as usize;
+            (start, start + (**function).len())
+        }))
+    }
+
+    /// Registers the executable segments of the shared library holding
+    /// `function`, for engines loading the code of modules as libraries.
+    ///
+    /// The code is registered as unknown on the platforms where the segments
+    /// of the loaded libraries can't be listed.
+    pub fn library(function: FunctionBodyPtr) -> Self {
+        let address = unsafe { (**function).as_ptr() as usize };
+        match library_code_ranges(address) {
+            Some(ranges) => Self::from_ranges(ranges),
+            None => Self::unknown(),
+        }
+    }
+
+    /// Registers code whose extent isn't known, for engines that don't
+    /// know the length of their functions.
+    ///
+    /// Until the registration is dropped, the faults of any code running
+    /// WebAssembly are turned into traps, as if the host functions couldn't
+    /// fault.
+    pub fn unknown() -> Self {
+        UNKNOWN_CODE.fetch_add(1, Ordering::SeqCst);
+        Self {
+            indices: vec![],
+            unknown: true,
+        }
+    }
+
+    fn from_ranges(ranges: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        let mut registration = Self {
+            indices: vec![],
+            unknown: false,
+        };
+        for (start, end) in ranges.into_iter().filter(|(start, end)| start < end) {
+            match CODE_RANGES.insert(start, end, 0) {
+                Some(index) => registration.indices.push(index),
+                None if !registration.unknown => {
+                    // Too much code is registered: fall back to treating
+                    // any code as WebAssembly while this one is.
+                    UNKNOWN_CODE.fetch_add(1, Ordering::SeqCst);
+                    registration.unknown = true;
+                }
+                None => {}
+            }
+        }
+        registration
+    }
+}
+
+impl Drop for CodeRegistration {
+    fn drop(&mut self) {
+        for index in &self.indices {
+            CODE_RANGES.remove(*index);
+        }
+        if self.unknown {
+            UNKNOWN_CODE.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Returns the executable segments of the loaded object holding `address`.
+#[cfg(target_os = "linux")]
+fn library_code_ranges(address: usize) -> Option<Vec<(usize, usize)>> {
+    struct Search {
+        address: usize,
+        ranges: Option<Vec<(usize, usize)>>,
+    }
+
+    unsafe extern "C" fn callback(
+        info: *mut libc::dl_phdr_info,
+        _size: libc::size_t,
+        data: *mut libc::c_void,
+    ) -> libc::c_int {
+        let search = &mut *(data as *mut Search);
+        let info = &*info;
+        let headers = std::slice::from_raw_parts(info.dlpi_phdr, info.dlpi_phnum as usize);
+        let ranges = headers
+            .iter()
+            .filter(|header| header.p_type == libc::PT_LOAD && header.p_flags & libc::PF_X != 0)
+            .map(|header| {
+                let start = info.dlpi_addr as usize + header.p_vaddr as usize;
+                (start, start + header.p_memsz as usize)
+            })
+            .collect::<Vec<_>>();
+        if ranges
+            .iter()
+            .any(|(start, end)| *start <= search.address && search.address < *end)
+        {
+            search.ranges = Some(ranges);
+            return 1;
+        }
+        0
+    }
+
+    let mut search = Search {
+        address,
+        ranges: None,
+    };
+    unsafe {
+        libc::dl_iterate_phdr(
+            Some(callback),
+            &mut search as *mut Search as *mut libc::c_void,
+        );
+    }
+    search.ranges
+}
+
+#[cfg(not(target_os = "linux"))]
+fn library_code_ranges(_address: usize) -> Option<Vec<(usize, usize)>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VMFunctionBody;
+
+    fn function(code: &mut [u8]) -> FunctionBodyPtr {
+        FunctionBodyPtr(code as *mut [u8] as *mut [VMFunctionBody])
+    }
+
+    #[test]
+    fn registered_code() {
+        let mut code = [0u8; 16];
+        let start = code.as_ptr() as usize;
+        let registration = CodeRegistration::new(vec![function(&mut code[4..8])]);
+        assert!(!is_wasm_code(start + 3));
+        assert!(is_wasm_code(start + 4));
+        assert!(is_wasm_code(start + 7));
+        assert!(!is_wasm_code(start + 8));
+        drop(registration);
+        assert!(!is_wasm_code(start + 4));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn registered_library() {
+        let address = registered_library as fn() as usize;
+        let code = unsafe { std::slice::from_raw_parts_mut(address as *mut u8, 0) };
+        let registration = CodeRegistration::library(function(code));
+        assert!(!registration.unknown);
+        assert!(is_wasm_code(address));
+        drop(registration);
+        assert!(!is_wasm_code(address));
+    }
+}
@@ -0,0 +1,253 @@
+//! Tracking of the WebAssembly pages written to in a linear memory.
+//!
+//! On Unix the accessible pages of a tracked memory are write-protected, and
+//! the first write to each page is caught by the signal handler, which
+//! records the page as dirty and makes it writable again before resuming the
+//! faulting instruction. Everywhere else (or if write-protecting the memory
+//! fails) the pages are hashed, and compared against the hashes taken the
+//! last time the dirty pages were collected.
+
+use crate::address_ranges::AddressRanges;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use wasmer_types::{Pages, WASM_PAGE_SIZE};
+
+/// The accessible memory of the write-protected memories, with the address
+/// of their `ProtectedRegion`, looked up by the signal handler.
+static PROTECTED_REGIONS: AddressRanges = AddressRanges::new();
+
+/// A write-protected linear memory.
+#[derive(Debug)]
+pub(crate) struct ProtectedRegion {
+    /// The base address of the memory.
+    base: AtomicUsize,
+    /// The accessible size of the memory in bytes.
+    len: AtomicUsize,
+    /// The index of the memory in `PROTECTED_REGIONS`.
+    index: AtomicUsize,
+    /// The number of write faults of each wasm page since the page was last
+    /// collected, so that it's dirty if it's not zero.
+    writes: Box<[AtomicUsize]>,
+}
+
+impl ProtectedRegion {
+    /// Write-protects the pages of the accessible memory that are not dirty.
+    fn protect_clean_pages(&self) -> Result<(), String> {
+        let base = self.base.load(Ordering::SeqCst);
+        let pages = self.len.load(Ordering::SeqCst) / WASM_PAGE_SIZE;
+        for page in 0..pages {
+            if self.writes[page].load(Ordering::SeqCst) == 0 {
+                protect_page(base, page, false).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the accessible memory to `PROTECTED_REGIONS`, returning its
+    /// index, or `None` if there are too many protected memories.
+    fn register(&self) -> Option<usize> {
+        let base = self.base.load(Ordering::SeqCst);
+        let len = self.len.load(Ordering::SeqCst);
+        PROTECTED_REGIONS.insert(base, base + len, self as *const Self as usize)
+    }
+}
+
+/// Makes the given wasm `page` of the memory at `base` read-only, or
+/// writable again.
+///
+/// This is async-signal-safe.
+#[cfg(unix)]
+fn protect_page(base: usize, page: usize, writable: bool) -> io::Result<()> {
+    let addr = (base + page * WASM_PAGE_SIZE) as *mut libc::c_void;
+    let protection = if writable {
+        libc::PROT_READ | libc::PROT_WRITE
+    } else {
+        libc::PROT_READ
+    };
+    if unsafe { libc::mprotect(addr, WASM_PAGE_SIZE, protection) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn protect_page(_base: usize, _page: usize, _writable: bool) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "write-protecting memory is not supported on this platform",
+    ))
+}
+
+/// Records a write fault at `addr` if it hit a clean page of a tracked
+/// memory, making the page writable again.
+///
+/// Returns `true` if the fault was handled, so the faulting instruction can
+/// be resumed; otherwise the fault is not caused by the dirty page tracking
+/// (for example, an out of bounds access) and must be handled as usual.
+///
+/// This is async-signal-safe.
+pub(crate) fn handle_write_fault(addr: usize) -> bool {
+    let (base, region) = match PROTECTED_REGIONS.lookup(addr) {
+        Some(found) => found,
+        None => return false,
+    };
+    // The region is removed from the table before it's dropped.
+    let region = unsafe { &*(region as *const ProtectedRegion) };
+    let page = (addr - base) / WASM_PAGE_SIZE;
+    // The page is made writable before the write is counted: if a
+    // concurrent `take` resets the count in between, it write-protected the
+    // page again, so the write faults again and is counted then.
+    if protect_page(base, page, true).is_err() {
+        return false;
+    }
+    region.writes[page].fetch_add(1, Ordering::SeqCst);
+    true
+}
+
+/// The dirty pages tracking of a linear memory.
+#[derive(Debug)]
+pub(crate) enum DirtyPages {
+    /// The pages are write-protected, and marked dirty on the first write.
+    Protected(Box<ProtectedRegion>),
+    /// The hashes of the pages the last time the dirty pages were collected.
+    Hashed(Mutex<Vec<u64>>),
+}
+
+impl DirtyPages {
+    /// Starts tracking the memory at `base`, with `len` accessible bytes and
+    /// a maximum size of `maximum`.
+    ///
+    /// # Safety
+    /// `base` must point to the `len` accessible bytes of the memory, which
+    /// must stay valid until the tracker is dropped or `remap`ped.
+    pub(crate) unsafe fn new(base: *mut u8, len: usize, maximum: Pages) -> Self {
+        let region = Box::new(ProtectedRegion {
+            base: AtomicUsize::new(base as usize),
+            len: AtomicUsize::new(len),
+            index: AtomicUsize::new(0),
+            writes: (0..maximum.0).map(|_| AtomicUsize::new(0)).collect(),
+        });
+        // The memory is registered before it's protected, so that the
+        // signal handler knows about all the faults of its protected pages.
+        if let Some(index) = region.register() {
+            region.index.store(index, Ordering::SeqCst);
+            if region.protect_clean_pages().is_ok() {
+                return Self::Protected(region);
+            }
+            // Make sure no page is left write-protected.
+            for page in 0..len / WASM_PAGE_SIZE {
+                let _ = protect_page(base as usize, page, true);
+            }
+            PROTECTED_REGIONS.remove(index);
+        }
+        let memory = std::slice::from_raw_parts(base, len);
+        Self::Hashed(Mutex::new(hash_pages(memory)))
+    }
+
+    /// Updates the tracked memory after it has grown, and maybe moved, to
+    /// `base` with `len` accessible bytes.
+    ///
+    /// # Safety
+    /// The same as `new`.
+    pub(crate) unsafe fn remap(&self, base: *mut u8, len: usize) -> Result<(), String> {
+        if let Self::Protected(region) = self {
+            region.base.store(base as usize, Ordering::SeqCst);
+            region.len.store(len, Ordering::SeqCst);
+            // The new memory is registered before the previous one is
+            // removed, so that the accessible memory is always registered.
+            let index = region
+                .register()
+                .ok_or_else(|| "too many memories are tracking their dirty pages".to_string())?;
+            PROTECTED_REGIONS.remove(region.index.swap(index, Ordering::SeqCst));
+            region.protect_clean_pages()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the indices of the wasm pages written to since the last call,
+    /// given the current contents of the memory, and resets them to clean.
+    pub(crate) fn take(&self, memory: &[u8]) -> Vec<u32> {
+        match self {
+            Self::Protected(region) => {
+                let base = region.base.load(Ordering::SeqCst);
+                let mut pages = vec![];
+                for page in 0..memory.len() / WASM_PAGE_SIZE {
+                    let writes = region.writes[page].load(Ordering::SeqCst);
+                    if writes == 0 {
+                        continue;
+                    }
+                    // The page is write-protected before it's marked clean:
+                    // if a write faults in between, the count changed and
+                    // the page stays dirty, to be collected again.
+                    protect_page(base, page, false).expect("failed to write-protect a memory page");
+                    let _ = region.writes[page].compare_exchange(
+                        writes,
+                        0,
+                        Ordering::SeqCst,
+                        Ordering::SeqCst,
+                    );
+                    pages.push(page as u32);
+                }
+                pages
+            }
+            Self::Hashed(hashes) => {
+                let mut hashes = hashes.lock().unwrap();
+                let zeroed_page_hash = hash_page(&vec![0; WASM_PAGE_SIZE]);
+                let new_hashes = hash_pages(memory);
+                let pages = new_hashes
+                    .iter()
+                    .enumerate()
+                    .filter(|(page, hash)| {
+                        **hash != *hashes.get(*page).unwrap_or(&zeroed_page_hash)
+                    })
+                    .map(|(page, _)| page as u32)
+                    .collect();
+                *hashes = new_hashes;
+                pages
+            }
+        }
+    }
+}
+
+impl Drop for DirtyPages {
+    fn drop(&mut self) {
+        if let Self::Protected(region) = self {
+            PROTECTED_REGIONS.remove(region.index.load(Ordering::SeqCst));
+        }
+    }
+}
+
+fn hash_page(page: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(page);
+    hasher.finish()
+}
+
+fn hash_pages(memory: &[u8]) -> Vec<u64> {
+    memory.chunks(WASM_PAGE_SIZE).map(hash_page).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashed_pages() {
+        let mut memory = vec![0u8; 3 * WASM_PAGE_SIZE];
+        let tracker = DirtyPages::Hashed(Mutex::new(hash_pages(&memory)));
+        assert!(tracker.take(&memory).is_empty());
+
+        memory[10] = 1;
+        memory[2 * WASM_PAGE_SIZE + 5] = 1;
+        assert_eq!(tracker.take(&memory), vec![0, 2]);
+        assert!(tracker.take(&memory).is_empty());
+
+        // Grown pages are only dirty once written to.
+        memory.resize(5 * WASM_PAGE_SIZE, 0);
+        memory[4 * WASM_PAGE_SIZE] = 1;
+        assert_eq!(tracker.take(&memory), vec![4]);
+    }
+}
@@ -24,6 +24,19 @@ pub enum Export {
     Global(ExportGlobal),
 }
 
+impl Export {
+    /// Returns whether or not the two `Export`s refer to the same entity.
+    pub fn same(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Function(a), Self::Function(b)) => a.address == b.address && a.vmctx == b.vmctx,
+            (Self::Table(a), Self::Table(b)) => a.same(b),
+            (Self::Memory(a), Self::Memory(b)) => a.same(b),
+            (Self::Global(a), Self::Global(b)) => a.same(b),
+            _ => false,
+        }
+    }
+}
+
 /// A function export value.
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExportFunction {
@@ -35,6 +48,9 @@ pub struct ExportFunction {
     pub signature: FunctionType,
     /// The function kind (it defines how it's the signature that provided `address` have)
     pub kind: VMFunctionKind,
+    /// Whether the function never returns, because it always traps or
+    /// exits the process.
+    pub noreturn: bool,
 }
 
 /// # Safety
@@ -19,6 +19,10 @@ pub struct Imports {
 
     /// Resolved addresses for imported globals.
     pub globals: BoxedSlice<GlobalIndex, VMGlobalImport>,
+
+    /// Whether each imported function never returns, see
+    /// `ExportFunction::noreturn`.
+    pub noreturn_functions: BoxedSlice<FunctionIndex, bool>,
 }
 
 impl Imports {
@@ -28,12 +32,14 @@ impl Imports {
         table_imports: PrimaryMap<TableIndex, VMTableImport>,
         memory_imports: PrimaryMap<MemoryIndex, VMMemoryImport>,
         global_imports: PrimaryMap<GlobalIndex, VMGlobalImport>,
+        noreturn_functions: PrimaryMap<FunctionIndex, bool>,
     ) -> Self {
         Self {
             functions: function_imports.into_boxed_slice(),
             tables: table_imports.into_boxed_slice(),
             memories: memory_imports.into_boxed_slice(),
             globals: global_imports.into_boxed_slice(),
+            noreturn_functions: noreturn_functions.into_boxed_slice(),
         }
     }
 
@@ -44,6 +50,7 @@ impl Imports {
             tables: PrimaryMap::new().into_boxed_slice(),
             memories: PrimaryMap::new().into_boxed_slice(),
             globals: PrimaryMap::new().into_boxed_slice(),
+            noreturn_functions: PrimaryMap::new().into_boxed_slice(),
         }
     }
 }
@@ -4,19 +4,21 @@
 //! An `Instance` contains all the runtime state used by execution of a
 //! wasm module (except its callstack and register state). An
 //! `InstanceHandle` is a reference-counting handle for an `Instance`.
+use crate::bulk_memory::check_bounds;
 use crate::export::Export;
 use crate::global::Global;
 use crate::imports::Imports;
 use crate::memory::{Memory, MemoryError};
+use crate::mmap::prefault;
 use crate::table::Table;
-use crate::trap::{catch_traps, init_traps, Trap, TrapCode};
+use crate::trap::{catch_traps, Trap, TrapCode};
 use crate::vmcontext::{
-    VMBuiltinFunctionsArray, VMCallerCheckedAnyfunc, VMContext, VMFunctionBody, VMFunctionImport,
-    VMFunctionKind, VMGlobalDefinition, VMGlobalImport, VMMemoryDefinition, VMMemoryImport,
-    VMSharedSignatureIndex, VMTableDefinition, VMTableImport,
+    VMBuiltinFunctionIndex, VMBuiltinFunctionsArray, VMCallerCheckedAnyfunc, VMContext,
+    VMFunctionBody, VMFunctionImport, VMFunctionKind, VMGlobalDefinition, VMGlobalImport,
+    VMMemoryDefinition, VMMemoryImport, VMSharedSignatureIndex, VMTableDefinition, VMTableImport,
 };
 use crate::{ExportFunction, ExportGlobal, ExportMemory, ExportTable};
-use crate::{FunctionBodyPtr, ModuleInfo, VMOffsets};
+use crate::{ExportResolution, FunctionBodyPtr, ModuleInfo, VMOffsets};
 use memoffset::offset_of;
 use more_asserts::assert_lt;
 use std::alloc::{self, Layout};
@@ -29,9 +31,9 @@ use std::sync::Arc;
 use std::{mem, ptr, slice};
 use wasmer_types::entity::{packed_option::ReservedValue, BoxedSlice, EntityRef, PrimaryMap};
 use wasmer_types::{
-    DataIndex, DataInitializer, ElemIndex, ExportIndex, FunctionIndex, GlobalIndex, GlobalInit,
-    LocalFunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex, Pages,
-    SignatureIndex, TableIndex, TableInitializer,
+    ConstExpr, DataIndex, DataInitializer, ElemIndex, ExportIndex, FunctionIndex, GlobalIndex,
+    GlobalInit, LocalFunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex,
+    MemoryIndex, Pages, SignatureIndex, TableIndex, TableInitializer, Type,
 };
 
 cfg_if::cfg_if! {
@@ -85,6 +87,11 @@ pub(crate) struct Instance {
     /// Pointers to functions in executable memory.
     functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
 
+    /// The resolved imports. The `vmctx` only holds bitwise copies of
+    /// them, so they are kept here to keep the imported entities alive for
+    /// as long as the instance.
+    imports: Imports,
+
     /// Passive elements in this instantiation. As `elem.drop`s happen, these
     /// entries get removed. A missing entry is considered equivalent to an
     /// empty slice.
@@ -94,6 +101,10 @@ pub(crate) struct Instance {
     /// get removed. A missing entry is considered equivalent to an empty slice.
     passive_data: RefCell<HashMap<DataIndex, Arc<[u8]>>>,
 
+    /// The size in bytes from which the pages of a data initializer are
+    /// faulted in before it is applied, see `Tunables`.
+    data_prefault_threshold: Option<usize>,
+
     /// Hosts can store arbitrary per-instance information here.
     host_state: Box<dyn Any>,
 
@@ -286,60 +297,209 @@ impl Instance {
         Some(self.lookup_by_declaration(&export))
     }
 
+    /// Return the machine code of the function `index`, at the address it
+    /// runs from, or `None` if the function is imported.
+    pub fn function_code(&self, index: FunctionIndex) -> Option<&[u8]> {
+        let def_index = self.module.local_func_index(index)?;
+        // `VMFunctionBody` is a single byte, and the function bodies
+        // outlive the instance.
+        Some(unsafe { &*(self.functions[def_index].0 as *const [u8]) })
+    }
+
+    /// Render the fields of the `VMContext`, read at their offsets, as a
+    /// table with one `offset  field  value` row per field.
+    pub fn dump_vmctx(&self) -> String {
+        let offsets = &self.offsets;
+        let mut rows: Vec<(u32, String, String)> = Vec::new();
+        unsafe {
+            for i in 0..offsets.num_signature_ids {
+                let offset = offsets.vmctx_vmshared_signature_id(SignatureIndex::from_u32(i));
+                let id = *self.vmctx_plus_offset::<VMSharedSignatureIndex>(offset);
+                rows.push((offset, format!("signature_ids[{}]", i), format!("{:?}", id)));
+            }
+            for i in 0..offsets.num_imported_functions {
+                let index = FunctionIndex::from_u32(i);
+                let offset = offsets.vmctx_vmfunction_import_body(index);
+                let body = *self.vmctx_plus_offset::<*const VMFunctionBody>(offset);
+                rows.push((
+                    offset,
+                    format!("imported_functions[{}].body", i),
+                    format!("{:p}", body),
+                ));
+                let offset = offsets.vmctx_vmfunction_import_vmctx(index);
+                let vmctx = *self.vmctx_plus_offset::<*mut VMContext>(offset);
+                rows.push((
+                    offset,
+                    format!("imported_functions[{}].vmctx", i),
+                    format!("{:p}", vmctx),
+                ));
+            }
+            for i in 0..offsets.num_imported_tables {
+                let offset = offsets.vmctx_vmtable_import_definition(TableIndex::from_u32(i));
+                let definition = *self.vmctx_plus_offset::<*mut VMTableDefinition>(offset);
+                rows.push((
+                    offset,
+                    format!("imported_tables[{}].definition", i),
+                    format!("{:p}", definition),
+                ));
+            }
+            for i in 0..offsets.num_imported_memories {
+                let offset = offsets.vmctx_vmmemory_import_definition(MemoryIndex::from_u32(i));
+                let definition = *self.vmctx_plus_offset::<*mut VMMemoryDefinition>(offset);
+                rows.push((
+                    offset,
+                    format!("imported_memories[{}].definition", i),
+                    format!("{:p}", definition),
+                ));
+            }
+            for i in 0..offsets.num_imported_globals {
+                let offset = offsets.vmctx_vmglobal_import_definition(GlobalIndex::from_u32(i));
+                let definition = *self.vmctx_plus_offset::<*mut VMGlobalDefinition>(offset);
+                rows.push((
+                    offset,
+                    format!("imported_globals[{}].definition", i),
+                    format!("{:p}", definition),
+                ));
+            }
+            for i in 0..offsets.num_local_tables {
+                let index = LocalTableIndex::from_u32(i);
+                let offset = offsets.vmctx_vmtable_definition_base(index);
+                let base = *self.vmctx_plus_offset::<*mut u8>(offset);
+                rows.push((offset, format!("tables[{}].base", i), format!("{:p}", base)));
+                let offset = offsets.vmctx_vmtable_definition_current_elements(index);
+                let current_elements = *self.vmctx_plus_offset::<u32>(offset);
+                rows.push((
+                    offset,
+                    format!("tables[{}].current_elements", i),
+                    current_elements.to_string(),
+                ));
+            }
+            for i in 0..offsets.num_local_memories {
+                let index = LocalMemoryIndex::from_u32(i);
+                let offset = offsets.vmctx_vmmemory_definition_base(index);
+                let base = *self.vmctx_plus_offset::<*mut u8>(offset);
+                rows.push((
+                    offset,
+                    format!("memories[{}].base", i),
+                    format!("{:p}", base),
+                ));
+                let offset = offsets.vmctx_vmmemory_definition_current_length(index);
+                let current_length = *self.vmctx_plus_offset::<u32>(offset);
+                rows.push((
+                    offset,
+                    format!("memories[{}].current_length", i),
+                    current_length.to_string(),
+                ));
+            }
+            for i in 0..offsets.num_local_globals {
+                let index = LocalGlobalIndex::from_u32(i);
+                let offset = offsets.vmctx_vmglobal_definition(index);
+                let definition = *self.vmctx_plus_offset::<*const VMGlobalDefinition>(offset);
+                let ty = self.module.globals[self.module.global_index(index)].ty;
+                let value = match ty {
+                    Type::I32 => (*definition).as_i32().to_string(),
+                    Type::I64 => (*definition).as_i64().to_string(),
+                    Type::F32 => (*definition).as_f32().to_string(),
+                    Type::F64 => (*definition).as_f64().to_string(),
+                    Type::V128 => format!("{:#x}", (*definition).as_u128()),
+                    Type::ExternRef | Type::FuncRef => format!("{:#x}", (*definition).as_u64()),
+                };
+                rows.push((
+                    offset,
+                    format!("globals[{}]", i),
+                    format!("{:p} ({}: {})", definition, ty, value),
+                ));
+            }
+            let begin = offsets.vmctx_builtin_functions_begin();
+            for i in 0..VMBuiltinFunctionIndex::builtin_functions_total_number() {
+                let offset = begin + i * u32::from(offsets.pointer_size);
+                let function = *self.vmctx_plus_offset::<*const VMFunctionBody>(offset);
+                rows.push((
+                    offset,
+                    format!("builtin_functions[{}]", i),
+                    format!("{:p}", function),
+                ));
+            }
+        }
+
+        let width = rows
+            .iter()
+            .map(|(_, field, _)| field.len())
+            .max()
+            .unwrap_or(0);
+        let mut dump = format!(
+            "{:<8}  {:<width$}  value\n",
+            "offset",
+            "field",
+            width = width
+        );
+        for (offset, field, value) in rows {
+            dump.push_str(&format!(
+                "{:<#8x}  {:<width$}  {}\n",
+                offset,
+                field,
+                value,
+                width = width
+            ));
+        }
+        dump
+    }
+
     /// Lookup an export with the given export declaration.
     pub fn lookup_by_declaration(&self, export: &ExportIndex) -> Export {
-        match export {
-            ExportIndex::Function(index) => {
-                let sig_index = &self.module.functions[*index];
-                let (address, vmctx) = if let Some(def_index) = self.module.local_func_index(*index)
-                {
-                    (self.functions[def_index].0 as *const _, self.vmctx_ptr())
-                } else {
-                    let import = self.imported_function(*index);
-                    (import.body, import.vmctx)
-                };
-                let signature = self.module.signatures[*sig_index].clone();
+        self.resolve_export(&self.module.export_resolution(export))
+    }
+
+    /// Resolves an export precomputed with `ModuleInfo::export_resolution`.
+    pub fn resolve_export(&self, resolution: &ExportResolution) -> Export {
+        match *resolution {
+            ExportResolution::LocalFunction(index, signature) => ExportFunction {
+                address: self.functions[index].0 as *const _,
+                // Any function received is already static at this point as:
+                // 1. All locally defined functions in the Wasm have a static signature.
+                // 2. All the imported functions are already static (because
+                //    they point to the trampolines rather than the dynamic addresses).
+                kind: VMFunctionKind::Static,
+                signature: self.module.signatures[signature].clone(),
+                vmctx: self.vmctx_ptr(),
+                noreturn: false,
+            }
+            .into(),
+            ExportResolution::ImportedFunction(index, signature) => {
+                let import = self.imported_function(index);
                 ExportFunction {
-                    address,
-                    // Any function received is already static at this point as:
-                    // 1. All locally defined functions in the Wasm have a static signature.
-                    // 2. All the imported functions are already static (because
-                    //    they point to the trampolines rather than the dynamic addresses).
+                    address: import.body,
                     kind: VMFunctionKind::Static,
-                    signature,
-                    vmctx,
+                    signature: self.module.signatures[signature].clone(),
+                    vmctx: import.vmctx,
+                    noreturn: self.imports.noreturn_functions[index],
                 }
                 .into()
             }
-            ExportIndex::Table(index) => {
-                let from = if let Some(def_index) = self.module.local_table_index(*index) {
-                    self.tables[def_index].clone()
-                } else {
-                    let import = self.imported_table(*index);
-                    import.from.clone()
-                };
-                ExportTable { from }.into()
+            ExportResolution::LocalTable(index) => ExportTable {
+                from: self.tables[index].clone(),
             }
-            ExportIndex::Memory(index) => {
-                let from = if let Some(def_index) = self.module.local_memory_index(*index) {
-                    self.memories[def_index].clone()
-                } else {
-                    let import = self.imported_memory(*index);
-                    import.from.clone()
-                };
-                ExportMemory { from }.into()
+            .into(),
+            ExportResolution::ImportedTable(index) => ExportTable {
+                from: self.imported_table(index).from.clone(),
             }
-            ExportIndex::Global(index) => {
-                let from = {
-                    if let Some(def_index) = self.module.local_global_index(*index) {
-                        self.globals[def_index].clone()
-                    } else {
-                        let import = self.imported_global(*index);
-                        import.from.clone()
-                    }
-                };
-                ExportGlobal { from }.into()
+            .into(),
+            ExportResolution::LocalMemory(index) => ExportMemory {
+                from: self.memories[index].clone(),
+            }
+            .into(),
+            ExportResolution::ImportedMemory(index) => ExportMemory {
+                from: self.imported_memory(index).from.clone(),
             }
+            .into(),
+            ExportResolution::LocalGlobal(index) => ExportGlobal {
+                from: self.globals[index].clone(),
+            }
+            .into(),
+            ExportResolution::ImportedGlobal(index) => ExportGlobal {
+                from: self.imported_global(index).from.clone(),
+            }
+            .into(),
         }
     }
 
@@ -688,24 +848,56 @@ impl Instance {
         unsafe { memory.memory_fill(dst, val, len) }
     }
 
-    /// Performs the `memory.init` operation.
+    /// Performs the `memory.init` operation on a locally defined memory.
     ///
     /// # Errors
     ///
-    /// Returns a `Trap` error if the destination range is out of this module's
+    /// Returns a `Trap` error if the destination range is out of the
     /// memory's bounds or if the source range is outside the data segment's
     /// bounds.
-    pub(crate) fn memory_init(
+    pub(crate) fn local_memory_init(
+        &self,
+        memory_index: LocalMemoryIndex,
+        data_index: DataIndex,
+        dst: u32,
+        src: u32,
+        len: u32,
+    ) -> Result<(), Trap> {
+        let memory = self.memory(memory_index);
+        self.memory_init(&memory, data_index, dst, src, len)
+    }
+
+    /// Performs the `memory.init` operation on an imported memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `Trap` error if the destination range is out of the
+    /// current bounds of the memory, which may have been grown by another
+    /// instance, or if the source range is outside the data segment's
+    /// bounds.
+    pub(crate) fn imported_memory_init(
         &self,
         memory_index: MemoryIndex,
         data_index: DataIndex,
         dst: u32,
         src: u32,
         len: u32,
+    ) -> Result<(), Trap> {
+        let import = self.imported_memory(memory_index);
+        let memory = unsafe { *import.definition.as_ref() };
+        self.memory_init(&memory, data_index, dst, src, len)
+    }
+
+    fn memory_init(
+        &self,
+        memory: &VMMemoryDefinition,
+        data_index: DataIndex,
+        dst: u32,
+        src: u32,
+        len: u32,
     ) -> Result<(), Trap> {
         // https://webassembly.github.io/bulk-memory-operations/core/exec/instructions.html#exec-memory-init
 
-        let memory = self.get_memory(memory_index);
         let passive_data = self.passive_data.borrow();
         let data = passive_data
             .get(&data_index)
@@ -714,12 +906,10 @@ impl Instance {
         if src
             .checked_add(len)
             .map_or(true, |n| n as usize > data.len())
-            || dst
-                .checked_add(len)
-                .map_or(true, |m| m > memory.current_length)
         {
             return Err(Trap::new_from_runtime(TrapCode::HeapAccessOutOfBounds));
         }
+        check_bounds(len, dst, memory.current_length).map_err(Trap::new_from_runtime)?;
 
         let src_slice = &data[src as usize..(src + len) as usize];
 
@@ -787,6 +977,9 @@ impl InstanceHandle {
     /// internally if you'd like to do so. If possible it's recommended to use
     /// the `wasmer` crate API rather than this type since that is vetted for
     /// safety.
+    ///
+    /// Unless the code of the instance runs unchecked, the signal handlers
+    /// must have been installed first, see `init_traps`.
     #[allow(clippy::too_many_arguments)]
     pub unsafe fn new(
         module: Arc<ModuleInfo>,
@@ -796,6 +989,7 @@ impl InstanceHandle {
         finished_globals: BoxedSlice<LocalGlobalIndex, Arc<Global>>,
         imports: Imports,
         vmshared_signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
+        data_prefault_threshold: Option<usize>,
         host_state: Box<dyn Any>,
     ) -> Result<Self, Trap> {
         // TODO: investigate `vmctx_tables` and `vmctx_memories`: both of these
@@ -837,8 +1031,10 @@ impl InstanceHandle {
                 tables: finished_tables,
                 globals: finished_globals,
                 functions: finished_functions,
+                imports,
                 passive_elements: Default::default(),
                 passive_data,
+                data_prefault_threshold,
                 host_state,
                 signal_handler: Cell::new(None),
                 vmctx: VMContext {},
@@ -862,24 +1058,24 @@ impl InstanceHandle {
             vmshared_signatures.len(),
         );
         ptr::copy(
-            imports.functions.values().as_slice().as_ptr(),
+            instance.imports.functions.values().as_slice().as_ptr(),
             instance.imported_functions_ptr() as *mut VMFunctionImport,
-            imports.functions.len(),
+            instance.imports.functions.len(),
         );
         ptr::copy(
-            imports.tables.values().as_slice().as_ptr(),
+            instance.imports.tables.values().as_slice().as_ptr(),
             instance.imported_tables_ptr() as *mut VMTableImport,
-            imports.tables.len(),
+            instance.imports.tables.len(),
         );
         ptr::copy(
-            imports.memories.values().as_slice().as_ptr(),
+            instance.imports.memories.values().as_slice().as_ptr(),
             instance.imported_memories_ptr() as *mut VMMemoryImport,
-            imports.memories.len(),
+            instance.imports.memories.len(),
         );
         ptr::copy(
-            imports.globals.values().as_slice().as_ptr(),
+            instance.imports.globals.values().as_slice().as_ptr(),
             instance.imported_globals_ptr() as *mut VMGlobalImport,
-            imports.globals.len(),
+            instance.imports.globals.len(),
         );
         ptr::copy(
             vmctx_tables.values().as_slice().as_ptr(),
@@ -901,9 +1097,6 @@ impl InstanceHandle {
             VMBuiltinFunctionsArray::initialized(),
         );
 
-        // Ensure that our signal handlers are ready for action.
-        init_traps();
-
         // Perform infallible initialization in this constructor, while fallible
         // initialization is deferred to the `initialize` method.
         initialize_passive_elements(instance);
@@ -921,19 +1114,39 @@ impl InstanceHandle {
         &self,
         data_initializers: &[DataInitializer<'_>],
     ) -> Result<(), Trap> {
+        self.initialize(data_initializers)?;
+
+        // The WebAssembly spec specifies that the start function is
+        // invoked automatically at instantiation time.
+        self.invoke_start_function()
+    }
+
+    /// Applies the element and data segments of the instance, the first
+    /// step of `finish_instantiation`.
+    ///
+    /// # Safety
+    ///
+    /// Only safe to call immediately after instantiation.
+    pub unsafe fn initialize(&self, data_initializers: &[DataInitializer<'_>]) -> Result<(), Trap> {
         check_table_init_bounds(self.instance())?;
         check_memory_init_bounds(self.instance(), data_initializers)?;
 
         // Apply the initializers.
         initialize_tables(self.instance())?;
         initialize_memories(self.instance(), data_initializers)?;
-
-        // The WebAssembly spec specifies that the start function is
-        // invoked automatically at instantiation time.
-        self.instance().invoke_start_function()?;
         Ok(())
     }
 
+    /// Invokes the start function of the instance, if it has one, the last
+    /// step of `finish_instantiation`.
+    ///
+    /// # Safety
+    ///
+    /// Only safe to call once, after `initialize`.
+    pub unsafe fn invoke_start_function(&self) -> Result<(), Trap> {
+        self.instance().invoke_start_function()
+    }
+
     /// Create a new `InstanceHandle` pointing at the instance
     /// pointed to by the given `VMContext` pointer.
     ///
@@ -978,6 +1191,25 @@ impl InstanceHandle {
         self.instance().lookup_by_declaration(export)
     }
 
+    /// Resolves an export precomputed with `ModuleInfo::export_resolution`.
+    pub fn resolve_export(&self, resolution: &ExportResolution) -> Export {
+        self.instance().resolve_export(resolution)
+    }
+
+    /// Return the machine code of the function `index`, at the address it
+    /// runs from, or `None` if the function is imported.
+    pub fn function_code(&self, index: FunctionIndex) -> Option<&[u8]> {
+        self.instance().function_code(index)
+    }
+
+    /// Render the fields of the `VMContext` as a table, for debugging.
+    ///
+    /// The fields are read at the offsets computed by `VMOffsets`, so a
+    /// corrupted `VMContext` shows up as unexpected values.
+    pub fn dump_vmctx(&self) -> String {
+        self.instance().dump_vmctx()
+    }
+
     /// Return an iterator over the exports of this instance.
     ///
     /// Specifically, it provides access to the key-value pairs, where the keys
@@ -1105,19 +1337,30 @@ fn check_table_init_bounds(instance: &Instance) -> Result<(), Trap> {
     Ok(())
 }
 
+/// Evaluate a constant expression reading the globals of an instance.
+fn eval_const_expr(expr: &ConstExpr, instance: &Instance) -> i64 {
+    expr.eval(|index| unsafe {
+        let global = if let Some(def_index) = instance.module.local_global_index(index) {
+            instance.global(def_index)
+        } else {
+            instance.imported_global(index).definition.as_ref().clone()
+        };
+        match instance.module.globals[index].ty {
+            Type::I64 => *global.as_i64(),
+            _ => i64::from(*global.as_i32()),
+        }
+    })
+}
+
 /// Compute the offset for a memory data initializer.
+///
+/// The offset saturates, so that no base global value can wrap it around
+/// to the start of the memory.
 fn get_memory_init_start(init: &DataInitializer<'_>, instance: &Instance) -> usize {
     let mut start = init.location.offset;
 
-    if let Some(base) = init.location.base {
-        let val = unsafe {
-            if let Some(def_index) = instance.module.local_global_index(base) {
-                *instance.global(def_index).as_u32()
-            } else {
-                *instance.imported_global(base).definition.as_ref().as_u32()
-            }
-        };
-        start += usize::try_from(val).unwrap();
+    if let Some(base) = &init.location.base {
+        start = start.saturating_add(eval_const_expr(base, instance) as u32 as usize);
     }
 
     start
@@ -1149,7 +1392,8 @@ fn check_memory_init_bounds(
         let start = get_memory_init_start(init, instance);
         unsafe {
             let mem_slice = get_memory_slice(init, instance);
-            if mem_slice.get_mut(start..start + init.data.len()).is_none() {
+            let end = start.checked_add(init.data.len());
+            if end.map_or(true, |end| end > mem_slice.len()) {
                 return Err(Trap::new_from_runtime(TrapCode::HeapSetterOutOfBounds));
             }
         }
@@ -1162,15 +1406,8 @@ fn check_memory_init_bounds(
 fn get_table_init_start(init: &TableInitializer, instance: &Instance) -> usize {
     let mut start = init.offset;
 
-    if let Some(base) = init.base {
-        let val = unsafe {
-            if let Some(def_index) = instance.module.local_global_index(base) {
-                *instance.global(def_index).as_u32()
-            } else {
-                *instance.imported_global(base).definition.as_ref().as_u32()
-            }
-        };
-        start += usize::try_from(val).unwrap();
+    if let Some(base) = &init.base {
+        start += eval_const_expr(base, instance) as u32 as usize;
     }
 
     start
@@ -1248,8 +1485,17 @@ fn initialize_memories(
         unsafe {
             let mem_slice = get_memory_slice(init, instance);
             let end = start + init.data.len();
+            // The bounds were checked above, but the bytes past the length
+            // are the guard region, which must never be written to.
+            assert!(end <= mem_slice.len());
             let to_init = &mut mem_slice[start..end];
-            to_init.copy_from_slice(init.data);
+            if instance
+                .data_prefault_threshold
+                .map_or(false, |threshold| to_init.len() >= threshold)
+            {
+                prefault(to_init.as_mut_ptr(), to_init.len());
+            }
+            to_init.copy_from_slice(&init.data);
         }
     }
 
@@ -1276,6 +1522,13 @@ fn initialize_globals(instance: &Instance) {
                         };
                     *to = from;
                 }
+                GlobalInit::Expr(expr) => {
+                    let value = eval_const_expr(expr, instance);
+                    match module.globals[module.global_index(index)].ty {
+                        Type::I64 => *(*to).as_i64_mut() = value,
+                        _ => *(*to).as_i32_mut() = value as i32,
+                    }
+                }
                 GlobalInit::RefNullConst | GlobalInit::RefFunc(_) => unimplemented!(),
             }
         }
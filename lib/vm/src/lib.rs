@@ -20,6 +20,9 @@
     )
 )]
 
+mod address_ranges;
+mod code_regions;
+mod dirty_pages;
 mod export;
 mod global;
 mod imports;
@@ -28,24 +31,28 @@ mod memory;
 mod mmap;
 mod module;
 mod probestack;
+mod readonly_memory;
 mod sig_registry;
 mod table;
 mod trap;
 mod vmcontext;
 mod vmoffsets;
 
+pub mod bulk_memory;
 pub mod libcalls;
 
+pub use crate::code_regions::CodeRegistration;
 pub use crate::export::*;
 pub use crate::global::*;
 pub use crate::imports::Imports;
 pub use crate::instance::InstanceHandle;
-pub use crate::memory::{LinearMemory, Memory, MemoryError, MemoryStyle};
+pub use crate::memory::{LinearMemory, Memory, MemoryCreator, MemoryError, MemoryStyle};
 pub use crate::mmap::Mmap;
-pub use crate::module::{ExportsIterator, ImportsIterator, ModuleInfo};
+pub use crate::module::{ExportResolution, ExportsIterator, ImportsIterator, ModuleInfo};
 pub use crate::probestack::PROBESTACK;
+pub use crate::readonly_memory::ReadOnlyMemory;
 pub use crate::sig_registry::SignatureRegistry;
-pub use crate::table::{LinearTable, Table, TableStyle};
+pub use crate::table::{LinearTable, Table, TableCreator, TableStyle};
 pub use crate::trap::*;
 pub use crate::vmcontext::{
     VMBuiltinFunctionIndex, VMCallerCheckedAnyfunc, VMContext, VMDynamicFunctionContext,
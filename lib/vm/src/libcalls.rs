@@ -36,7 +36,7 @@
 //!   ```
 
 use crate::probestack::PROBESTACK;
-use crate::trap::{raise_lib_trap, Trap, TrapCode};
+use crate::trap::{current_stack_limit, raise_lib_trap, Trap, TrapCode};
 use crate::vmcontext::VMContext;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -346,7 +346,7 @@ pub unsafe extern "C" fn wasmer_imported_memory_fill(
     }
 }
 
-/// Implementation of `memory.init`.
+/// Implementation of `memory.init` for locally defined memories.
 ///
 /// # Safety
 ///
@@ -358,12 +358,36 @@ pub unsafe extern "C" fn wasmer_memory_init(
     dst: u32,
     src: u32,
     len: u32,
+) {
+    let result = {
+        let memory_index = LocalMemoryIndex::from_u32(memory_index);
+        let data_index = DataIndex::from_u32(data_index);
+        let instance = (&*vmctx).instance();
+        instance.local_memory_init(memory_index, data_index, dst, src, len)
+    };
+    if let Err(trap) = result {
+        raise_lib_trap(trap);
+    }
+}
+
+/// Implementation of `memory.init` for imported memories.
+///
+/// # Safety
+///
+/// `vmctx` must be valid and not null.
+pub unsafe extern "C" fn wasmer_imported_memory_init(
+    vmctx: *mut VMContext,
+    memory_index: u32,
+    data_index: u32,
+    dst: u32,
+    src: u32,
+    len: u32,
 ) {
     let result = {
         let memory_index = MemoryIndex::from_u32(memory_index);
         let data_index = DataIndex::from_u32(data_index);
         let instance = (&*vmctx).instance();
-        instance.memory_init(memory_index, data_index, dst, src, len)
+        instance.imported_memory_init(memory_index, data_index, dst, src, len)
     };
     if let Err(trap) = result {
         raise_lib_trap(trap);
@@ -392,6 +416,19 @@ pub unsafe extern "C" fn wasmer_raise_trap(trap_code: TrapCode) -> ! {
     raise_lib_trap(trap)
 }
 
+/// Implementation of the explicit stack checks: returns the lowest address
+/// the stack pointer may reach in the current call into WebAssembly.
+///
+/// The limit is kept per thread, as the same instance may be running on
+/// several threads at once. A limit of `0` disables the checks.
+///
+/// # Safety
+///
+/// The `vmctx` is not used, this is always safe to call.
+pub unsafe extern "C" fn wasmer_stack_limit(_vmctx: *mut VMContext) -> usize {
+    current_stack_limit()
+}
+
 /// Probestack check
 ///
 /// # Safety
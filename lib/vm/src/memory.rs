@@ -5,7 +5,10 @@
 //!
 //! `LinearMemory` is to WebAssembly linear memories what `Table` is to WebAssembly tables.
 
+use crate::dirty_pages::DirtyPages;
 use crate::mmap::Mmap;
+use crate::readonly_memory::ReadOnlyMemory;
+use crate::trap::init_traps;
 use crate::vmcontext::VMMemoryDefinition;
 use more_asserts::assert_ge;
 use serde::{Deserialize, Serialize};
@@ -16,7 +19,7 @@ use std::fmt;
 use std::ptr::NonNull;
 use std::sync::Mutex;
 use thiserror::Error;
-use wasmer_types::{Bytes, MemoryType, Pages};
+use wasmer_types::{Bytes, MemoryType, Pages, WASM_PAGE_SIZE};
 
 /// Error type describing things that can go wrong when operating on Wasm Memories.
 #[derive(Error, Debug, Clone, PartialEq, Hash)]
@@ -55,6 +58,18 @@ pub enum MemoryError {
         /// The number of pages requested as the maximum amount of memory.
         max_allowed: Pages,
     },
+    /// The minimum of the memory exceeds the limit set by the embedder.
+    #[error("The minimum of the memory ({} pages) exceeds the limit ({} pages)", declared.0, limit.0)]
+    MinimumExceedsLimit {
+        /// The minimum declared by the memory type.
+        declared: Pages,
+        /// The limit set by the embedder.
+        limit: Pages,
+    },
+    /// The memory isn't backed by shareable pages, so it can't be viewed
+    /// read-only.
+    #[error("The memory can't be viewed read-only, as it isn't shareable")]
+    NotShareable,
     /// A user defined error value, used for error cases not listed above.
     #[error("A user-defined error occurred: {0}")]
     Generic(String),
@@ -113,11 +128,52 @@ pub trait Memory: fmt::Debug + Send + Sync {
     ///
     /// The pointer returned in [`VMMemoryDefinition`] must be valid for the lifetime of this memory.
     fn vmmemory(&self) -> NonNull<VMMemoryDefinition>;
+
+    /// Returns the indices of the wasm pages written to since the last
+    /// call (or since the memory was created), and resets them to clean.
+    ///
+    /// Returns `None` if this memory doesn't track its dirty pages.
+    fn take_dirty_pages(&self) -> Option<Vec<u32>> {
+        None
+    }
+
+    /// Returns a view of this memory's current pages, mapped read-only at
+    /// another address, to be imported by instances that may read the
+    /// memory but not write to it.
+    ///
+    /// Returns [`MemoryError::NotShareable`] if this memory's pages can't be
+    /// mapped more than once.
+    fn readonly_view(&self) -> Result<Box<dyn Memory>, MemoryError> {
+        Err(MemoryError::NotShareable)
+    }
+}
+
+/// A custom allocator for the linear memories of WebAssembly modules.
+///
+/// This can be used by embedders that can't (or don't want to) use the
+/// default mmap-based [`LinearMemory`], for example to place memories in
+/// an arena they manage.
+pub trait MemoryCreator: Send + Sync {
+    /// Create a new memory given a memory type and a memory style.
+    ///
+    /// The returned memory must uphold the guarantees of the given
+    /// `style` that compiled code relies on: a `Static` memory must have
+    /// `bound` plus `offset_guard_size` bytes of address space reserved,
+    /// and the base address of a `Static` memory must never change.
+    fn new_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Box<dyn Memory>, MemoryError>;
 }
 
 /// A linear memory instance.
 #[derive(Debug)]
 pub struct LinearMemory {
+    // The dirty pages tracking, if enabled. It's declared before `mmap` so
+    // it stops tracking the memory before it's unmapped.
+    dirty_pages: Option<DirtyPages>,
+
     // The underlying allocation.
     mmap: Mutex<WasmMmap>,
 
@@ -156,6 +212,45 @@ struct WasmMmap {
 impl LinearMemory {
     /// Create a new linear memory instance with specified minimum and maximum number of wasm pages.
     pub fn new(memory: &MemoryType, style: &MemoryStyle) -> Result<Self, MemoryError> {
+        Self::new_internal(memory, style, false, false)
+    }
+
+    /// Create a new linear memory instance whose pages are backed by a
+    /// shared mapping, so that they can be mapped again read-only with
+    /// [`Memory::readonly_view`].
+    ///
+    /// Shareable memories are only supported on Linux.
+    pub fn new_shareable(memory: &MemoryType, style: &MemoryStyle) -> Result<Self, MemoryError> {
+        // Writes to read-only views are reported by our signal handlers.
+        init_traps();
+        Self::new_internal(memory, style, false, true)
+    }
+
+    /// Create a new linear memory instance which tracks the wasm pages
+    /// written to, retrieved with [`Memory::take_dirty_pages`].
+    ///
+    /// Where supported, the pages are write-protected and the first write
+    /// to each one is recorded by the signal handler, so it's cheap to
+    /// collect them. Note that system calls writing to the memory fail
+    /// instead of faulting on write-protected pages, so the host must write
+    /// to the memory itself before passing it to them. On other platforms
+    /// the pages are hashed, and compared on every collection.
+    pub fn new_with_dirty_page_tracking(
+        memory: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Self, MemoryError> {
+        // Writes to tracked memories rely on our signal handlers, even if
+        // they don't happen in WebAssembly code.
+        init_traps();
+        Self::new_internal(memory, style, true, false)
+    }
+
+    fn new_internal(
+        memory: &MemoryType,
+        style: &MemoryStyle,
+        track_dirty_pages: bool,
+        shareable: bool,
+    ) -> Result<Self, MemoryError> {
         if memory.minimum > Pages::max_value() {
             return Err(MemoryError::MinimumMemoryTooLarge {
                 min_requested: memory.minimum,
@@ -180,7 +275,16 @@ impl LinearMemory {
             }
         }
 
-        let offset_guard_bytes = style.offset_guard_size() as usize;
+        let offset_guard_bytes: usize =
+            style
+                .offset_guard_size()
+                .try_into()
+                .map_err(|_| MemoryError::InvalidMemory {
+                    reason: format!(
+                        "the offset guard ({} bytes) doesn't fit in the address space",
+                        style.offset_guard_size()
+                    ),
+                })?;
 
         // If we have an offset guard, or if we're doing the static memory
         // allocation strategy, we need signal handlers to catch out of bounds
@@ -198,19 +302,40 @@ impl LinearMemory {
                 *bound
             }
         };
-        let minimum_bytes = minimum_pages.bytes().0;
-        let request_bytes = minimum_bytes.checked_add(offset_guard_bytes).unwrap();
+        // The whole reservation must fit in the address space, which rules
+        // out static memories of 4 GiB on 32-bit hosts.
+        let request_bytes = (u64::from(minimum_pages.0) * WASM_PAGE_SIZE as u64)
+            .checked_add(style.offset_guard_size())
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| MemoryError::InvalidMemory {
+                reason: format!(
+                    "reserving {} pages and a {} bytes offset guard exceeds the address space",
+                    minimum_pages.0,
+                    style.offset_guard_size()
+                ),
+            })?;
         let mapped_pages = memory.minimum;
         let mapped_bytes = mapped_pages.bytes();
 
+        let alloc = if shareable {
+            Mmap::accessible_reserved_shared(mapped_bytes.0, request_bytes)
+        } else {
+            Mmap::accessible_reserved(mapped_bytes.0, request_bytes)
+        };
         let mut mmap = WasmMmap {
-            alloc: Mmap::accessible_reserved(mapped_bytes.0, request_bytes)
-                .map_err(MemoryError::Region)?,
+            alloc: alloc.map_err(MemoryError::Region)?,
             size: memory.minimum,
         };
 
         let base_ptr = mmap.alloc.as_mut_ptr();
+        let dirty_pages = if track_dirty_pages {
+            let maximum = memory.maximum.unwrap_or_else(Pages::max_value);
+            Some(unsafe { DirtyPages::new(base_ptr, mapped_bytes.0, maximum) })
+        } else {
+            None
+        };
         Ok(Self {
+            dirty_pages,
             mmap: Mutex::new(mmap),
             maximum: memory.maximum,
             offset_guard_size: offset_guard_bytes,
@@ -301,11 +426,21 @@ impl Memory for LinearMemory {
                         attempted_delta: Bytes(guard_bytes).into(),
                     })?;
 
-            let mut new_mmap =
-                Mmap::accessible_reserved(new_bytes, request_bytes).map_err(MemoryError::Region)?;
-
-            let copy_len = mmap.alloc.len() - self.offset_guard_size;
-            new_mmap.as_mut_slice()[..copy_len].copy_from_slice(&mmap.alloc.as_slice()[..copy_len]);
+            let new_mmap = if mmap.alloc.is_shared() {
+                // The new mapping maps the same pages, so there is nothing
+                // to copy.
+                mmap.alloc
+                    .resize_shared(new_bytes, request_bytes)
+                    .map_err(MemoryError::Region)?
+            } else {
+                let mut new_mmap = Mmap::accessible_reserved(new_bytes, request_bytes)
+                    .map_err(MemoryError::Region)?;
+
+                let copy_len = mmap.alloc.len() - self.offset_guard_size;
+                new_mmap.as_mut_slice()[..copy_len]
+                    .copy_from_slice(&mmap.alloc.as_slice()[..copy_len]);
+                new_mmap
+            };
 
             mmap.alloc = new_mmap;
         } else if delta_bytes > 0 {
@@ -322,6 +457,10 @@ impl Memory for LinearMemory {
             md.current_length = new_pages.bytes().0.try_into().unwrap();
             md.base = mmap.alloc.as_mut_ptr() as _;
         }
+        if let Some(dirty_pages) = &self.dirty_pages {
+            unsafe { dirty_pages.remap(mmap.alloc.as_mut_ptr(), new_bytes) }
+                .map_err(MemoryError::Region)?;
+        }
 
         Ok(prev_pages)
     }
@@ -333,4 +472,31 @@ impl Memory for LinearMemory {
             as *const VMMemoryDefinition as *mut VMMemoryDefinition;
         unsafe { NonNull::new_unchecked(ptr) }
     }
+
+    /// Returns the indices of the wasm pages written to since the last call.
+    fn take_dirty_pages(&self) -> Option<Vec<u32>> {
+        let dirty_pages = self.dirty_pages.as_ref()?;
+        let mmap = self.mmap.lock().unwrap();
+        let len = mmap.size.bytes().0;
+        Some(dirty_pages.take(&mmap.alloc.as_slice()[..len]))
+    }
+
+    /// Returns a read-only view of the memory's current pages, if it's
+    /// shareable.
+    fn readonly_view(&self) -> Result<Box<dyn Memory>, MemoryError> {
+        let mmap = self.mmap.lock().unwrap();
+        if !mmap.alloc.is_shared() {
+            return Err(MemoryError::NotShareable);
+        }
+        let view = mmap
+            .alloc
+            .readonly_view(mmap.size.bytes().0)
+            .map_err(MemoryError::Region)?;
+        Ok(Box::new(ReadOnlyMemory::new(
+            view,
+            mmap.size,
+            &self.memory,
+            &self.style,
+        )?))
+    }
 }
@@ -6,6 +6,7 @@
 
 use more_asserts::assert_le;
 use more_asserts::assert_lt;
+use std::fs::File;
 use std::io;
 use std::ptr;
 use std::slice;
@@ -25,6 +26,9 @@ pub struct Mmap {
     // the coordination all happens at the OS layer.
     ptr: usize,
     len: usize,
+    // The file backing the accessible memory of a shared mapping, see
+    // `Mmap::accessible_reserved_shared`.
+    file: Option<File>,
 }
 
 impl Mmap {
@@ -37,6 +41,7 @@ impl Mmap {
         Self {
             ptr: empty.as_ptr() as usize,
             len: 0,
+            file: None,
         }
     }
 
@@ -85,6 +90,7 @@ impl Mmap {
             Self {
                 ptr: ptr as usize,
                 len: mapping_size,
+                file: None,
             }
         } else {
             // Reserve the mapping size.
@@ -105,6 +111,7 @@ impl Mmap {
             let mut result = Self {
                 ptr: ptr as usize,
                 len: mapping_size,
+                file: None,
             };
 
             if accessible_size != 0 {
@@ -155,6 +162,7 @@ impl Mmap {
             Self {
                 ptr: ptr as usize,
                 len: mapping_size,
+                file: None,
             }
         } else {
             // Reserve the mapping size.
@@ -167,6 +175,7 @@ impl Mmap {
             let mut result = Self {
                 ptr: ptr as usize,
                 len: mapping_size,
+                file: None,
             };
 
             if accessible_size != 0 {
@@ -178,6 +187,187 @@ impl Mmap {
         })
     }
 
+    /// Create a new `Mmap` like [`Mmap::accessible_reserved`], whose accessible memory is
+    /// backed by an anonymous shared file, so that it can also be mapped read-only at
+    /// another address with [`Mmap::readonly_view`].
+    #[cfg(target_os = "linux")]
+    pub fn accessible_reserved_shared(
+        accessible_size: usize,
+        mapping_size: usize,
+    ) -> Result<Self, String> {
+        use std::os::unix::io::FromRawFd;
+
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_memfd_create,
+                b"wasmer-memory\0".as_ptr(),
+                libc::MFD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().to_string());
+        }
+        let file = unsafe { File::from_raw_fd(fd as libc::c_int) };
+        file.set_len(accessible_size as u64)
+            .map_err(|e| e.to_string())?;
+        let mut result = Self::map_file(&file, accessible_size, mapping_size, true)?;
+        result.file = Some(file);
+        Ok(result)
+    }
+
+    /// Create a new `Mmap` like [`Mmap::accessible_reserved`], whose accessible memory is
+    /// backed by an anonymous shared file.
+    ///
+    /// Shared mappings are only supported on Linux.
+    #[cfg(not(target_os = "linux"))]
+    pub fn accessible_reserved_shared(
+        _accessible_size: usize,
+        _mapping_size: usize,
+    ) -> Result<Self, String> {
+        Err("shared memory mappings are not supported on this platform".to_string())
+    }
+
+    /// Map the first `accessible_size` bytes of this shared mapping read-only at another
+    /// address, within a reserved mapping of the same size as this one.
+    ///
+    /// The two mappings share their pages, so writes through this mapping are visible
+    /// through the returned one, while writes through the returned one fault.
+    #[cfg(target_os = "linux")]
+    pub fn readonly_view(&self, accessible_size: usize) -> Result<Self, String> {
+        let file = self.shared_file()?;
+        Self::map_file(file, accessible_size, self.len, false)
+    }
+
+    /// Map the first `accessible_size` bytes of this shared mapping read-only at another
+    /// address.
+    ///
+    /// Shared mappings are only supported on Linux.
+    #[cfg(not(target_os = "linux"))]
+    pub fn readonly_view(&self, _accessible_size: usize) -> Result<Self, String> {
+        Err("shared memory mappings are not supported on this platform".to_string())
+    }
+
+    /// Create a new shared mapping of `mapping_size` bytes, whose first `accessible_size`
+    /// bytes map the same pages as this one, to grow a shared mapping that can't grow in
+    /// place.
+    #[cfg(target_os = "linux")]
+    pub fn resize_shared(
+        &self,
+        accessible_size: usize,
+        mapping_size: usize,
+    ) -> Result<Self, String> {
+        let file = self.shared_file()?;
+        file.set_len(accessible_size as u64)
+            .map_err(|e| e.to_string())?;
+        let mut result = Self::map_file(file, accessible_size, mapping_size, true)?;
+        result.file = Some(file.try_clone().map_err(|e| e.to_string())?);
+        Ok(result)
+    }
+
+    /// Create a new shared mapping of `mapping_size` bytes, whose first `accessible_size`
+    /// bytes map the same pages as this one.
+    ///
+    /// Shared mappings are only supported on Linux.
+    #[cfg(not(target_os = "linux"))]
+    pub fn resize_shared(
+        &self,
+        _accessible_size: usize,
+        _mapping_size: usize,
+    ) -> Result<Self, String> {
+        Err("shared memory mappings are not supported on this platform".to_string())
+    }
+
+    /// Return whether the accessible memory is backed by a shared file.
+    pub fn is_shared(&self) -> bool {
+        self.file.is_some()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn shared_file(&self) -> Result<&File, String> {
+        self.file
+            .as_ref()
+            .ok_or_else(|| "the memory is not a shared mapping".to_string())
+    }
+
+    /// Reserve `mapping_size` bytes, and map the first `accessible_size` bytes of `file`
+    /// at their start, writable or read-only.
+    #[cfg(target_os = "linux")]
+    fn map_file(
+        file: &File,
+        accessible_size: usize,
+        mapping_size: usize,
+        writable: bool,
+    ) -> Result<Self, String> {
+        let page_size = region::page::size();
+        assert_le!(accessible_size, mapping_size);
+        assert_eq!(mapping_size & (page_size - 1), 0);
+        assert_eq!(accessible_size & (page_size - 1), 0);
+
+        if mapping_size == 0 {
+            return Ok(Self::new());
+        }
+
+        // Reserve the mapping size.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                mapping_size,
+                libc::PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANON,
+                -1,
+                0,
+            )
+        };
+        if ptr as isize == -1_isize {
+            return Err(io::Error::last_os_error().to_string());
+        }
+
+        let result = Self {
+            ptr: ptr as usize,
+            len: mapping_size,
+            file: None,
+        };
+
+        if accessible_size != 0 {
+            result.map_file_range(file, 0, accessible_size, writable)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Map the `len` bytes of `file` at offset `start` over the same range of the
+    /// reserved memory.
+    #[cfg(target_os = "linux")]
+    fn map_file_range(
+        &self,
+        file: &File,
+        start: usize,
+        len: usize,
+        writable: bool,
+    ) -> Result<(), String> {
+        use std::os::unix::io::AsRawFd;
+
+        let protection = if writable {
+            libc::PROT_READ | libc::PROT_WRITE
+        } else {
+            libc::PROT_READ
+        };
+        let ptr = unsafe {
+            libc::mmap(
+                (self.ptr + start) as *mut libc::c_void,
+                len,
+                protection,
+                libc::MAP_SHARED | libc::MAP_FIXED,
+                file.as_raw_fd(),
+                start as libc::off_t,
+            )
+        };
+        if ptr as isize == -1_isize {
+            return Err(io::Error::last_os_error().to_string());
+        }
+        Ok(())
+    }
+
     /// Make the memory starting at `start` and extending for `len` bytes accessible.
     /// `start` and `len` must be native page-size multiples and describe a range within
     /// `self`'s reserved memory.
@@ -189,6 +379,15 @@ impl Mmap {
         assert_lt!(len, self.len);
         assert_lt!(start, self.len - len);
 
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(file) = &self.file {
+                file.set_len((start + len) as u64)
+                    .map_err(|e| e.to_string())?;
+                return self.map_file_range(file, start, len, true);
+            }
+        }
+
         // Commit the accessible size.
         let ptr = self.ptr as *const u8;
         unsafe { region::protect(ptr.add(start), len, region::Protection::READ_WRITE) }
@@ -279,6 +478,37 @@ impl Drop for Mmap {
     }
 }
 
+/// Faults in the pages covering the `len` bytes at `start`, so that
+/// writing them doesn't take a page fault for every page.
+///
+/// This is only a hint: it does nothing where the OS can't populate pages
+/// ahead of time, or if it fails to.
+///
+/// # Safety
+///
+/// The pages covering the bytes must be mapped and writable.
+#[allow(unused_variables)]
+pub(crate) unsafe fn prefault(start: *mut u8, len: usize) {
+    #[cfg(target_os = "linux")]
+    {
+        /// `MADV_POPULATE_WRITE`, available since Linux 5.14. Older
+        /// kernels reject it with `EINVAL`.
+        const MADV_POPULATE_WRITE: libc::c_int = 23;
+
+        if len == 0 {
+            return;
+        }
+        let page_size = region::page::size();
+        let first_page = start as usize & !(page_size - 1);
+        let end = round_up_to_page_size(start as usize + len, page_size);
+        libc::madvise(
+            first_page as *mut libc::c_void,
+            end - first_page,
+            MADV_POPULATE_WRITE,
+        );
+    }
+}
+
 fn _assert() {
     fn _assert_send_sync<T: Send + Sync>() {}
     _assert_send_sync::<Mmap>();
@@ -13,10 +13,10 @@ use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
 use std::sync::Arc;
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{
-    CustomSectionIndex, DataIndex, ElemIndex, ExportIndex, ExportType, ExternType, FunctionIndex,
-    FunctionType, GlobalIndex, GlobalInit, GlobalType, ImportIndex, ImportType, LocalFunctionIndex,
-    LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex, MemoryType, SignatureIndex,
-    TableIndex, TableInitializer, TableType,
+    CustomSectionIndex, DataIndex, DebugInfoPolicy, ElemIndex, ExportIndex, ExportType, ExternType,
+    FunctionIndex, FunctionType, GlobalIndex, GlobalInit, GlobalType, ImportIndex, ImportType,
+    LocalFunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex,
+    MemoryType, SignatureIndex, TableIndex, TableInitializer, TableType,
 };
 
 #[derive(Debug)]
@@ -81,6 +81,9 @@ pub struct ModuleInfo {
     /// WebAssembly function names.
     pub function_names: HashMap<FunctionIndex, String>,
 
+    /// WebAssembly local names, by function and local index.
+    pub local_names: HashMap<(FunctionIndex, u32), String>,
+
     /// WebAssembly function signatures.
     pub signatures: PrimaryMap<SignatureIndex, FunctionType>,
 
@@ -96,12 +99,16 @@ pub struct ModuleInfo {
     /// WebAssembly global variables (imported and local).
     pub globals: PrimaryMap<GlobalIndex, GlobalType>,
 
-    /// Custom sections in the module.
-    pub custom_sections: IndexMap<String, CustomSectionIndex>,
+    /// Custom sections in the module, by name. Several sections can have
+    /// the same name, so the indices of each name are kept in order.
+    pub custom_sections: IndexMap<String, Vec<CustomSectionIndex>>,
 
     /// The data for each CustomSection in the module.
     pub custom_sections_data: PrimaryMap<CustomSectionIndex, Arc<[u8]>>,
 
+    /// The debug information kept when the module was compiled.
+    pub debug_info_policy: DebugInfoPolicy,
+
     /// Number of imported functions in the module.
     pub num_imported_functions: usize,
 
@@ -129,6 +136,7 @@ impl ModuleInfo {
             passive_data: HashMap::new(),
             global_initializers: PrimaryMap::new(),
             function_names: HashMap::new(),
+            local_names: HashMap::new(),
             signatures: PrimaryMap::new(),
             functions: PrimaryMap::new(),
             tables: PrimaryMap::new(),
@@ -140,6 +148,7 @@ impl ModuleInfo {
             num_imported_globals: 0,
             custom_sections: IndexMap::new(),
             custom_sections_data: PrimaryMap::new(),
+            debug_info_policy: DebugInfoPolicy::KeepAll,
         }
     }
 
@@ -229,13 +238,21 @@ impl ModuleInfo {
     /// Get the custom sections of the module given a `name`.
     pub fn custom_sections<'a>(&'a self, name: &'a str) -> impl Iterator<Item = Arc<[u8]>> + 'a {
         self.custom_sections
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(move |section_index| self.custom_sections_data[*section_index].clone())
+    }
+
+    /// Get the names under which the given `ExportIndex` is exported.
+    ///
+    /// One entity can be exported several times under different names,
+    /// that's why an iterator (rather than one element) is returned.
+    pub fn export_names_of<'a>(&'a self, index: ExportIndex) -> impl Iterator<Item = &'a str> + 'a {
+        self.exports
             .iter()
-            .filter_map(move |(section_name, section_index)| {
-                if name != section_name {
-                    return None;
-                }
-                Some(self.custom_sections_data[*section_index].clone())
-            })
+            .filter(move |(_name, export_index)| **export_index == index)
+            .map(|(name, _export_index)| name.as_str())
     }
 
     /// Convert a `LocalFunctionIndex` into a `FunctionIndex`.
@@ -313,6 +330,16 @@ impl ModuleInfo {
         index.index() < self.num_imported_globals
     }
 
+    /// The sum of the minimum sizes of the memories defined (not imported)
+    /// by the module, in pages.
+    pub fn total_minimum_memory_pages(&self) -> u64 {
+        self.memories
+            .values()
+            .skip(self.num_imported_memories)
+            .map(|memory| u64::from(memory.minimum.0))
+            .sum()
+    }
+
     /// Get the Module name
     pub fn name(&self) -> String {
         match self.name {
@@ -328,6 +355,66 @@ impl ModuleInfo {
             .take(self.num_imported_functions)
             .map(move |sig_index| self.signatures[*sig_index].clone())
     }
+
+    /// Precomputes how to resolve the export `export` of an instance.
+    pub fn export_resolution(&self, export: &ExportIndex) -> ExportResolution {
+        match *export {
+            ExportIndex::Function(index) => {
+                let signature = self.functions[index];
+                match self.local_func_index(index) {
+                    Some(index) => ExportResolution::LocalFunction(index, signature),
+                    None => ExportResolution::ImportedFunction(index, signature),
+                }
+            }
+            ExportIndex::Table(index) => match self.local_table_index(index) {
+                Some(index) => ExportResolution::LocalTable(index),
+                None => ExportResolution::ImportedTable(index),
+            },
+            ExportIndex::Memory(index) => match self.local_memory_index(index) {
+                Some(index) => ExportResolution::LocalMemory(index),
+                None => ExportResolution::ImportedMemory(index),
+            },
+            ExportIndex::Global(index) => match self.local_global_index(index) {
+                Some(index) => ExportResolution::LocalGlobal(index),
+                None => ExportResolution::ImportedGlobal(index),
+            },
+        }
+    }
+
+    /// Precomputes how to resolve each export of an instance, in the order
+    /// of `exports`.
+    pub fn export_resolutions(&self) -> Vec<ExportResolution> {
+        self.exports
+            .values()
+            .map(|export| self.export_resolution(export))
+            .collect()
+    }
+}
+
+/// How to resolve an export of an instance, see
+/// `InstanceHandle::resolve_export`.
+///
+/// It tells whether the exported entity is defined by the module or
+/// imported, and its index in the corresponding definitions or imports of
+/// the instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportResolution {
+    /// A function defined by the module, with its signature.
+    LocalFunction(LocalFunctionIndex, SignatureIndex),
+    /// An imported function, with its signature.
+    ImportedFunction(FunctionIndex, SignatureIndex),
+    /// A table defined by the module.
+    LocalTable(LocalTableIndex),
+    /// An imported table.
+    ImportedTable(TableIndex),
+    /// A memory defined by the module.
+    LocalMemory(LocalMemoryIndex),
+    /// An imported memory.
+    ImportedMemory(MemoryIndex),
+    /// A global defined by the module.
+    LocalGlobal(LocalGlobalIndex),
+    /// An imported global.
+    ImportedGlobal(GlobalIndex),
 }
 
 impl fmt::Display for ModuleInfo {
@@ -446,3 +533,29 @@ impl<I: Iterator<Item = ImportType> + Sized> Iterator for ImportsIterator<I> {
         self.iter.next()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer_types::Pages;
+
+    #[test]
+    fn total_minimum_memory_pages() {
+        let mut module = ModuleInfo::new();
+        assert_eq!(module.total_minimum_memory_pages(), 0);
+
+        // Imported memories don't count.
+        module.memories.push(MemoryType::new(Pages(7), None, false));
+        module.num_imported_memories = 1;
+        module
+            .memories
+            .push(MemoryType::new(Pages(3), Some(Pages(10)), false));
+        module
+            .memories
+            .push(MemoryType::new(Pages(u32::max_value()), None, false));
+        assert_eq!(
+            module.total_minimum_memory_pages(),
+            3 + u64::from(u32::max_value())
+        );
+    }
+}
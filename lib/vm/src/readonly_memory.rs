@@ -0,0 +1,128 @@
+//! Read-only views of shared linear memories.
+//!
+//! A view maps the pages of a shareable [`LinearMemory`] read-only at another
+//! address, so that an instance importing it can read what the memory's
+//! owner writes, while its own stores fault. The signal handler looks up the
+//! faulting address among the registered views to report these faults as
+//! [`TrapCode::ReadOnlyMemory`] traps.
+//!
+//! [`LinearMemory`]: crate::LinearMemory
+//! [`TrapCode::ReadOnlyMemory`]: crate::TrapCode::ReadOnlyMemory
+
+use crate::address_ranges::AddressRanges;
+use crate::memory::{Memory, MemoryError, MemoryStyle};
+use crate::mmap::Mmap;
+use crate::vmcontext::VMMemoryDefinition;
+use std::cell::UnsafeCell;
+use std::convert::TryInto;
+use std::ptr::NonNull;
+use wasmer_types::{MemoryType, Pages};
+
+/// The address ranges of the read-only views, looked up by the signal
+/// handler.
+static READONLY_REGIONS: AddressRanges = AddressRanges::new();
+
+/// Whether `addr` is in the accessible memory of a read-only view.
+///
+/// This is async-signal-safe.
+pub(crate) fn is_readonly_address(addr: usize) -> bool {
+    READONLY_REGIONS.lookup(addr).is_some()
+}
+
+/// A read-only view of a shareable linear memory.
+///
+/// The view has the size of the memory when the view was created: it
+/// doesn't see the pages the memory grows by afterwards, and it can't grow
+/// itself.
+#[derive(Debug)]
+pub struct ReadOnlyMemory {
+    // The read-only mapping of the memory's pages.
+    mmap: Mmap,
+
+    // The index of the view in `READONLY_REGIONS`.
+    index: usize,
+
+    // The size of the view in wasm pages.
+    size: Pages,
+
+    /// The WebAssembly linear memory description.
+    memory: MemoryType,
+
+    /// The implementation style of the viewed memory.
+    style: MemoryStyle,
+
+    /// The owned memory definition used by the generated code
+    vm_memory_definition: Box<UnsafeCell<VMMemoryDefinition>>,
+}
+
+/// This is correct because the memory definition is never mutated.
+unsafe impl Sync for ReadOnlyMemory {}
+
+impl ReadOnlyMemory {
+    /// Creates a view from the read-only mapping `mmap` of the `size` first
+    /// pages of a memory of type `memory` and style `style`.
+    pub(crate) fn new(
+        mmap: Mmap,
+        size: Pages,
+        memory: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Self, MemoryError> {
+        let base = mmap.as_ptr() as *mut u8;
+        let len = size.bytes().0;
+        let index = READONLY_REGIONS
+            .insert(base as usize, base as usize + len, 0)
+            .ok_or_else(|| MemoryError::Region("too many read-only views".to_string()))?;
+        Ok(Self {
+            mmap,
+            index,
+            size,
+            memory: *memory,
+            style: style.clone(),
+            vm_memory_definition: Box::new(UnsafeCell::new(VMMemoryDefinition {
+                base,
+                current_length: len.try_into().unwrap(),
+            })),
+        })
+    }
+}
+
+impl Memory for ReadOnlyMemory {
+    /// Returns the type of the viewed memory.
+    fn ty(&self) -> &MemoryType {
+        &self.memory
+    }
+
+    /// Returns the style of the viewed memory.
+    fn style(&self) -> &MemoryStyle {
+        &self.style
+    }
+
+    /// Returns the number of wasm pages of the view.
+    fn size(&self) -> Pages {
+        self.size
+    }
+
+    /// Read-only views can't grow.
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        if delta.0 == 0 {
+            return Ok(self.size);
+        }
+        Err(MemoryError::CouldNotGrow {
+            current: self.size,
+            attempted_delta: delta,
+        })
+    }
+
+    /// Return a `VMMemoryDefinition` for exposing the view to compiled wasm code.
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        let ptr = self.vm_memory_definition.as_ref() as *const UnsafeCell<VMMemoryDefinition>
+            as *const VMMemoryDefinition as *mut VMMemoryDefinition;
+        unsafe { NonNull::new_unchecked(ptr) }
+    }
+}
+
+impl Drop for ReadOnlyMemory {
+    fn drop(&mut self) {
+        READONLY_REGIONS.remove(self.index);
+    }
+}
@@ -102,6 +102,15 @@ pub trait Table: fmt::Debug + Send + Sync {
     }
 }
 
+/// A custom allocator for the tables of WebAssembly modules.
+///
+/// This can be used by embedders that want to control how the tables are
+/// backed, instead of using the default [`LinearTable`].
+pub trait TableCreator: Send + Sync {
+    /// Create a new table given a table type and a table style.
+    fn new_table(&self, ty: &TableType, style: &TableStyle) -> Result<Box<dyn Table>, String>;
+}
+
 /// A table instance.
 #[derive(Debug)]
 pub struct LinearTable {
@@ -0,0 +1,168 @@
+//! Cleanups deferred by the host functions, which run even when a trap
+//! unwinds through the host function that deferred them.
+//!
+//! Traps are raised with `longjmp`, which skips the destructors of the
+//! frames it crosses. A host function that holds a guest-visible resource
+//! across a call back into WebAssembly can't rely on `Drop` to release it,
+//! so it defers the release here instead.
+
+use std::cell::{Cell, RefCell};
+#[cfg(debug_assertions)]
+use std::mem;
+
+thread_local! {
+    static DEFERRED: RefCell<Vec<Box<dyn FnOnce()>>> = RefCell::new(Vec::new());
+    static HOST_FUNCTIONS: Cell<usize> = Cell::new(0);
+}
+
+/// Defers `cleanup` until the host function that is running on this
+/// thread returns, traps or panics.
+///
+/// Outside of a host function, `cleanup` runs immediately.
+pub fn defer_cleanup(cleanup: impl FnOnce() + 'static) {
+    if HOST_FUNCTIONS.with(|depth| depth.get()) == 0 {
+        cleanup();
+    } else {
+        DEFERRED.with(|deferred| deferred.borrow_mut().push(Box::new(cleanup)));
+    }
+}
+
+/// Runs `host_function`, then the cleanups it deferred, in the reverse order
+/// they were deferred.
+///
+/// The cleanups also run when `host_function` panics. When it traps
+/// without returning, they are run by the [`catch_traps`] the trap
+/// unwinds to.
+///
+/// [`catch_traps`]: crate::catch_traps
+pub fn with_deferred_cleanups<R>(host_function: impl FnOnce() -> R) -> R {
+    struct Scope {
+        start: usize,
+    }
+
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            HOST_FUNCTIONS.with(|depth| depth.set(depth.get() - 1));
+            run_deferred_cleanups(self.start);
+        }
+    }
+
+    HOST_FUNCTIONS.with(|depth| depth.set(depth.get() + 1));
+    let _scope = Scope {
+        start: deferred_cleanups(),
+    };
+    host_function()
+}
+
+/// The number of cleanups waiting to run on this thread.
+pub(crate) fn deferred_cleanups() -> usize {
+    DEFERRED.with(|deferred| deferred.borrow().len())
+}
+
+/// Runs the cleanups deferred after the first `start` ones, last first.
+pub(crate) fn run_deferred_cleanups(start: usize) {
+    // The cleanups are popped one at a time, since they may defer or run
+    // other cleanups themselves.
+    while let Some(cleanup) = DEFERRED.with(|deferred| {
+        let mut deferred = deferred.borrow_mut();
+        if deferred.len() > start {
+            deferred.pop()
+        } else {
+            None
+        }
+    }) {
+        cleanup();
+    }
+}
+
+/// The number of host functions running on this thread.
+pub(crate) fn host_function_depth() -> usize {
+    HOST_FUNCTIONS.with(|depth| depth.get())
+}
+
+/// Restores the host function depth to `depth` after a trap skipped the
+/// ends of the host functions it unwound through.
+pub(crate) fn reset_host_function_depth(depth: usize) {
+    HOST_FUNCTIONS.with(|current| current.set(depth));
+}
+
+/// A value that records when its `Drop` is skipped by a trap, in debug
+/// builds, see [`TrapCanary::take_skipped`].
+///
+/// Put a canary next to a value whose destructor matters in a host
+/// function, to find out whether a trap can unwind through it, in which
+/// case the value should be released with a deferred cleanup instead.
+/// Canaries have no effect in release builds.
+pub struct TrapCanary {
+    #[cfg(debug_assertions)]
+    id: usize,
+}
+
+#[cfg(debug_assertions)]
+thread_local! {
+    static CANARIES: RefCell<Vec<(usize, &'static str)>> = RefCell::new(Vec::new());
+    static NEXT_CANARY: Cell<usize> = Cell::new(0);
+    static SKIPPED_CANARIES: RefCell<Vec<&'static str>> = RefCell::new(Vec::new());
+}
+
+impl TrapCanary {
+    /// Creates a canary, reported as `name` when its `Drop` is skipped.
+    #[allow(unused_variables)]
+    pub fn new(name: &'static str) -> Self {
+        #[cfg(debug_assertions)]
+        {
+            let id = NEXT_CANARY.with(|next| {
+                let id = next.get();
+                next.set(id + 1);
+                id
+            });
+            CANARIES.with(|canaries| canaries.borrow_mut().push((id, name)));
+            Self { id }
+        }
+        #[cfg(not(debug_assertions))]
+        Self {}
+    }
+
+    /// Returns the names of the canaries of this thread whose `Drop` was
+    /// skipped by a trap since the last call, in the order they were
+    /// created.
+    ///
+    /// It's always empty in release builds.
+    pub fn take_skipped() -> Vec<&'static str> {
+        #[cfg(debug_assertions)]
+        return SKIPPED_CANARIES.with(|skipped| mem::take(&mut *skipped.borrow_mut()));
+        #[cfg(not(debug_assertions))]
+        Vec::new()
+    }
+}
+
+impl Drop for TrapCanary {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        CANARIES.with(|canaries| canaries.borrow_mut().retain(|(id, _)| *id != self.id));
+    }
+}
+
+/// The id of the next canary created on this thread.
+pub(crate) fn next_canary() -> usize {
+    #[cfg(debug_assertions)]
+    return NEXT_CANARY.with(|next| next.get());
+    #[cfg(not(debug_assertions))]
+    0
+}
+
+/// Records the live canaries created since the canary `start`, whose
+/// `Drop` was skipped by a trap, for `TrapCanary::take_skipped`.
+#[allow(unused_variables)]
+pub(crate) fn record_skipped_canaries(start: usize) {
+    #[cfg(debug_assertions)]
+    CANARIES.with(|canaries| {
+        canaries.borrow_mut().retain(|&(id, name)| {
+            if id < start {
+                return true;
+            }
+            SKIPPED_CANARIES.with(|skipped| skipped.borrow_mut().push(name));
+            false
+        })
+    });
+}
@@ -3,12 +3,18 @@
 
 //! This is the module that facilitates the usage of Traps
 //! in Wasmer Runtime
+mod deferred;
 mod trapcode;
 mod traphandlers;
 
+pub use deferred::{defer_cleanup, with_deferred_cleanups, TrapCanary};
 pub use trapcode::TrapCode;
 pub use traphandlers::{
     catch_traps, catch_traps_with_result, raise_lib_trap, raise_user_trap, wasmer_call_trampoline,
-    Trap,
+    wasmer_call_trampoline_unchecked, Trap,
+};
+pub(crate) use traphandlers::current_stack_limit;
+pub use traphandlers::{
+    init_traps, install_trap_handler, is_trap_handler_installed, resume_panic,
+    set_signal_handler_strategy, signal_handler_strategy, SignalHandlerStrategy,
 };
-pub use traphandlers::{init_traps, resume_panic};
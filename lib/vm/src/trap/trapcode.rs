@@ -70,6 +70,9 @@ pub enum TrapCode {
 
     /// A trap indicating that the runtime was unable to allocate sufficient memory.
     VMOutOfMemory = 14,
+
+    /// A store to a memory imported as a read-only view.
+    ReadOnlyMemory = 15,
     // /// A user-defined trap code.
     // User(u16),
 }
@@ -93,6 +96,7 @@ impl TrapCode {
             Self::Interrupt => "interrupt",
             Self::UnalignedAtomic => "unaligned atomic access",
             Self::VMOutOfMemory => "out of memory",
+            Self::ReadOnlyMemory => "write to read-only memory",
             // Self::User(_) => unreachable!(),
         }
     }
@@ -116,6 +120,7 @@ impl Display for TrapCode {
             Self::Interrupt => "interrupt",
             Self::UnalignedAtomic => "unalign_atom",
             Self::VMOutOfMemory => "oom",
+            Self::ReadOnlyMemory => "readonly_mem",
             // User(x) => return write!(f, "user{}", x),
         };
         f.write_str(identifier)
@@ -143,6 +148,7 @@ impl FromStr for TrapCode {
             "interrupt" => Ok(Interrupt),
             "unalign_atom" => Ok(UnalignedAtomic),
             "oom" => Ok(VMOutOfMemory),
+            "readonly_mem" => Ok(ReadOnlyMemory),
             // _ if s.starts_with("user") => s[4..].parse().map(User).map_err(|_| ()),
             _ => Err(()),
         }
@@ -154,7 +160,7 @@ mod tests {
     use super::*;
 
     // Everything but user-defined codes.
-    const CODES: [TrapCode; 14] = [
+    const CODES: [TrapCode; 15] = [
         TrapCode::StackOverflow,
         TrapCode::HeapSetterOutOfBounds,
         TrapCode::HeapAccessOutOfBounds,
@@ -169,6 +175,7 @@ mod tests {
         TrapCode::UnreachableCodeReached,
         TrapCode::Interrupt,
         TrapCode::UnalignedAtomic,
+        TrapCode::ReadOnlyMemory,
     ];
 
     #[test]
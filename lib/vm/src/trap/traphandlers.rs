@@ -4,6 +4,10 @@
 //! WebAssembly trap handling, which is built on top of the lower-level
 //! signalhandling mechanisms.
 
+use super::deferred::{
+    deferred_cleanups, host_function_depth, next_canary, record_skipped_canaries,
+    reset_host_function_depth, run_deferred_cleanups,
+};
 use super::trapcode::TrapCode;
 use crate::instance::{InstanceHandle, SignalHandler};
 use crate::vmcontext::{VMContext, VMFunctionBody, VMTrampoline};
@@ -14,8 +18,14 @@ use std::error::Error;
 use std::io;
 use std::mem;
 use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst};
 use std::sync::Once;
 
+/// The maximum amount of native stack that WebAssembly code is allowed to
+/// use when compiled with explicit stack checks, measured from the point
+/// where the host first calls into WebAssembly on the current thread.
+const MAX_WASM_STACK: usize = 1024 * 1024;
+
 extern "C" {
     fn RegisterSetjmp(
         jmp_buf: *mut *const u8,
@@ -114,12 +124,20 @@ cfg_if::cfg_if! {
             let maybe_signal_trap = match signum {
                 libc::SIGSEGV | libc::SIGBUS => {
                     let addr = (*siginfo).si_addr() as usize;
+                    // Writes to the pages of memories tracking their dirty
+                    // pages are recorded, and then the execution resumes.
+                    if crate::dirty_pages::handle_write_fault(addr) {
+                        return;
+                    }
                     let (stackaddr, stacksize) = thread_stack();
                     // The stack and its guard page covers the
                     // range [stackaddr - guard pages .. stackaddr + stacksize).
                     // We assume the guard page is 1 page, and pages are 4KiB.
                     if stackaddr - 4096 <= addr && addr < stackaddr + stacksize {
                         Some(TrapCode::StackOverflow)
+                    } else if crate::readonly_memory::is_readonly_address(addr) {
+                        // Stores to read-only views of memories.
+                        Some(TrapCode::ReadOnlyMemory)
                     } else {
                         Some(TrapCode::HeapAccessOutOfBounds)
                     }
@@ -279,24 +297,95 @@ cfg_if::cfg_if! {
     }
 }
 
+/// How the signal handlers (the vectored exception handler on Windows)
+/// catching the traps of WebAssembly code are installed, see
+/// `set_signal_handler_strategy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalHandlerStrategy {
+    /// The handlers are installed the first time an instance, or a memory
+    /// relying on them, is created. This is the default.
+    Auto,
+    /// The handlers are only installed by `install_trap_handler`, which
+    /// must be called before any WebAssembly code runs.
+    Manual,
+    /// The handlers are never installed: the host handles the signals
+    /// itself, and traps in WebAssembly code aren't caught.
+    None,
+}
+
+static SIGNAL_HANDLER_STRATEGY: AtomicUsize = AtomicUsize::new(0);
+static TRAP_HANDLER_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+impl SignalHandlerStrategy {
+    fn to_usize(self) -> usize {
+        match self {
+            Self::Auto => 0,
+            Self::Manual => 1,
+            Self::None => 2,
+        }
+    }
+
+    fn from_usize(value: usize) -> Self {
+        match value {
+            0 => Self::Auto,
+            1 => Self::Manual,
+            _ => Self::None,
+        }
+    }
+}
+
+/// Sets how the trap handlers are installed.
+///
+/// It must be set before creating the first instance, as the handlers
+/// can't be uninstalled once installed.
+pub fn set_signal_handler_strategy(strategy: SignalHandlerStrategy) {
+    SIGNAL_HANDLER_STRATEGY.store(strategy.to_usize(), SeqCst);
+}
+
+/// Returns how the trap handlers are installed.
+pub fn signal_handler_strategy() -> SignalHandlerStrategy {
+    SignalHandlerStrategy::from_usize(SIGNAL_HANDLER_STRATEGY.load(SeqCst))
+}
+
 /// This function performs the low-overhead signal handler initialization that
 /// we want to do eagerly to ensure a more-deterministic global process state.
 ///
+/// It's called whenever the handlers are needed, and only installs them with
+/// the `SignalHandlerStrategy::Auto` strategy.
+pub fn init_traps() {
+    if signal_handler_strategy() == SignalHandlerStrategy::Auto {
+        install_trap_handler();
+    }
+}
+
+/// Installs the trap handlers, unless the strategy is
+/// `SignalHandlerStrategy::None`.
+///
 /// This is especially relevant for signal handlers since handler ordering
 /// depends on installation order: the wasm signal handler must run *before*
 /// the other crash handlers and since POSIX signal handlers work LIFO, this
 /// function needs to be called at the end of the startup process, after other
-/// handlers have been installed. This function can thus be called multiple
+/// handlers have been installed. The handlers of other signals, or installed
+/// later for the same signals, must call the previous handler for the faults
+/// they don't handle themselves. This function can thus be called multiple
 /// times, having no effect after the first call.
-pub fn init_traps() {
+pub fn install_trap_handler() {
     static INIT: Once = Once::new();
-    INIT.call_once(real_init);
+    if signal_handler_strategy() != SignalHandlerStrategy::None {
+        INIT.call_once(real_init);
+    }
+}
+
+/// Returns whether the trap handlers are installed.
+pub fn is_trap_handler_installed() -> bool {
+    TRAP_HANDLER_INSTALLED.load(SeqCst)
 }
 
 fn real_init() {
     unsafe {
         platform_init();
     }
+    TRAP_HANDLER_INSTALLED.store(true, SeqCst);
 }
 
 /// Raises a user-defined trap immediately.
@@ -328,6 +417,12 @@ pub unsafe fn raise_lib_trap(trap: Trap) -> ! {
     tls::with(|info| info.unwrap().unwind_with(UnwindReason::LibTrap(trap)))
 }
 
+/// Returns the stack limit of the explicit stack checks for the current
+/// call into WebAssembly on this thread, or `0` outside of one.
+pub(crate) fn current_stack_limit() -> usize {
+    tls::with(|state| state.map_or(0, |state| state.stack_limit))
+}
+
 /// Carries a Rust panic across wasm code and resumes the panic on the other
 /// side.
 ///
@@ -441,12 +536,38 @@ pub unsafe fn wasmer_call_trampoline(
     })
 }
 
+/// Calls the trampoline like `wasmer_call_trampoline`, without catching
+/// the traps of the call, for code running unchecked.
+///
+/// # Safety
+///
+/// On top of the requirements of `wasmer_call_trampoline`, the call must
+/// not trap: a trap, including the errors and panics of the host functions
+/// it calls, is undefined behavior.
+pub unsafe fn wasmer_call_trampoline_unchecked(
+    vmctx: *mut VMContext,
+    trampoline: VMTrampoline,
+    callee: *const VMFunctionBody,
+    values_vec: *mut u8,
+) {
+    mem::transmute::<_, extern "C" fn(*mut VMContext, *const VMFunctionBody, *mut u8)>(trampoline)(
+        vmctx, callee, values_vec,
+    )
+}
+
 /// Catches any wasm traps that happen within the execution of `closure`,
 /// returning them as a `Result`.
 ///
+/// `vmctx` is the context of the instance called by `closure`, or null
+/// when `closure` calls a host function.
+///
 /// # Safety
 ///
-/// Highly unsafe since `closure` won't have any destructors run.
+/// Highly unsafe since `closure` won't have any destructors run. The
+/// cleanups deferred with [`defer_cleanup`] by the host functions a trap
+/// unwinds through are run before this returns, though.
+///
+/// [`defer_cleanup`]: crate::defer_cleanup
 pub unsafe fn catch_traps<F>(vmctx: *mut VMContext, mut closure: F) -> Result<(), Trap>
 where
     F: FnMut(),
@@ -455,7 +576,10 @@ where
     #[cfg(unix)]
     setup_unix_sigaltstack()?;
 
-    return CallThreadState::new(vmctx).with(|cx| {
+    let host_functions = host_function_depth();
+    let cleanups = deferred_cleanups();
+    let canaries = next_canary();
+    let result = CallThreadState::new(vmctx).with(|cx| {
         RegisterSetjmp(
             cx.jmp_buf.as_ptr(),
             call_closure::<F>,
@@ -463,6 +587,15 @@ where
         )
     });
 
+    // A trap unwinds past the ends of the host functions it goes through,
+    // so their deferred cleanups are run here instead.
+    reset_host_function_depth(host_functions);
+    run_deferred_cleanups(cleanups);
+    if result.is_err() {
+        record_skipped_canaries(canaries);
+    }
+    return result;
+
     extern "C" fn call_closure<F>(payload: *mut u8)
     where
         F: FnMut(),
@@ -503,6 +636,7 @@ pub struct CallThreadState {
     prev: Option<*const CallThreadState>,
     vmctx: *mut VMContext,
     handling_trap: Cell<bool>,
+    stack_limit: usize,
 }
 
 enum UnwindReason {
@@ -526,12 +660,22 @@ impl CallThreadState {
             reset_guard_page: Cell::new(false),
             prev: None,
             handling_trap: Cell::new(false),
+            stack_limit: 0,
         }
     }
 
     fn with(mut self, closure: impl FnOnce(&Self) -> i32) -> Result<(), Trap> {
         tls::with(|prev| {
             self.prev = prev.map(|p| p as *const _);
+            // All the calls into WebAssembly on this thread share the stack
+            // budget computed on the outermost one.
+            self.stack_limit = match prev {
+                Some(prev) => prev.stack_limit,
+                None => {
+                    let stack_marker = 0u8;
+                    (&stack_marker as *const u8 as usize).saturating_sub(MAX_WASM_STACK)
+                }
+            };
             let ret = tls::set(&self, || closure(&self));
             match self.unwind.replace(UnwindReason::None) {
                 UnwindReason::None => {
@@ -561,7 +705,8 @@ impl CallThreadState {
 
     fn any_instance(&self, func: impl Fn(&InstanceHandle) -> bool) -> bool {
         unsafe {
-            if func(&InstanceHandle::from_vmctx(self.vmctx)) {
+            // The calls of the host functions don't have an instance.
+            if !self.vmctx.is_null() && func(&InstanceHandle::from_vmctx(self.vmctx)) {
                 return true;
             }
             match self.prev {
@@ -629,14 +774,20 @@ impl CallThreadState {
             return 1 as *const _;
         }
 
-        // TODO: stack overflow can happen at any random time (i.e. in malloc()
-        // in memory.grow) and it's really hard to determine if the cause was
-        // stack overflow and if it happened in WebAssembly module.
-        //
-        // So, let's assume that any untrusted code called from WebAssembly
-        // doesn't trap. Then, if we have called some WebAssembly code, it
-        // means the trap is stack overflow.
-        if self.jmp_buf.get().is_null() {
+        // Stack overflow can happen at any random time (i.e. in malloc() in
+        // memory.grow), and the libcalls of bulk memory operations store to
+        // read-only memories, so these are traps wherever they happen. Any
+        // other fault is only a trap if it happened in WebAssembly code: a
+        // fault of a host function is a bug of the host, that must crash as
+        // it would without Wasmer, even if WebAssembly is on the stack.
+        let trap_anywhere = reset_guard_page
+            || matches!(
+                signal_trap,
+                Some(TrapCode::StackOverflow) | Some(TrapCode::ReadOnlyMemory)
+            );
+        if self.jmp_buf.get().is_null()
+            || !(trap_anywhere || crate::code_regions::is_wasm_code(pc as usize))
+        {
             self.handling_trap.set(false);
             return ptr::null();
         }
@@ -4,11 +4,12 @@
 //! This file declares `VMContext` and several related structs which contain
 //! fields that compiled wasm code accesses directly.
 
+use crate::bulk_memory::check_bounds;
 use crate::global::Global;
 use crate::instance::Instance;
 use crate::memory::Memory;
 use crate::table::Table;
-use crate::trap::{Trap, TrapCode};
+use crate::trap::Trap;
 use std::any::Any;
 use std::convert::TryFrom;
 use std::ptr::{self, NonNull};
@@ -299,15 +300,9 @@ impl VMMemoryDefinition {
     /// caller's responsibility to synchronize.
     pub(crate) unsafe fn memory_copy(&self, dst: u32, src: u32, len: u32) -> Result<(), Trap> {
         // https://webassembly.github.io/reference-types/core/exec/instructions.html#exec-memory-copy
-        if src
-            .checked_add(len)
-            .map_or(true, |n| n > self.current_length)
-            || dst
-                .checked_add(len)
-                .map_or(true, |m| m > self.current_length)
-        {
-            return Err(Trap::new_from_runtime(TrapCode::HeapAccessOutOfBounds));
-        }
+        check_bounds(len, src, self.current_length)
+            .and_then(|()| check_bounds(len, dst, self.current_length))
+            .map_err(Trap::new_from_runtime)?;
 
         let dst = usize::try_from(dst).unwrap();
         let src = usize::try_from(src).unwrap();
@@ -332,12 +327,7 @@ impl VMMemoryDefinition {
     /// The memory is not filled atomically and is not synchronized: it's the
     /// caller's responsibility to synchronize.
     pub(crate) unsafe fn memory_fill(&self, dst: u32, val: u32, len: u32) -> Result<(), Trap> {
-        if dst
-            .checked_add(len)
-            .map_or(true, |m| m > self.current_length)
-        {
-            return Err(Trap::new_from_runtime(TrapCode::HeapAccessOutOfBounds));
-        }
+        check_bounds(len, dst, self.current_length).map_err(Trap::new_from_runtime)?;
 
         let dst = isize::try_from(dst).unwrap();
         let val = val as u8;
@@ -823,21 +813,31 @@ impl VMBuiltinFunctionIndex {
     pub const fn get_imported_memory_fill_index() -> Self {
         Self(10)
     }
-    /// Returns an index for wasm's `memory.init` instruction.
+    /// Returns an index for wasm's `memory.init` instruction for locally
+    /// defined memories.
     pub const fn get_memory_init_index() -> Self {
         Self(11)
     }
+    /// Returns an index for wasm's `memory.init` instruction for imported
+    /// memories.
+    pub const fn get_imported_memory_init_index() -> Self {
+        Self(12)
+    }
     /// Returns an index for wasm's `data.drop` instruction.
     pub const fn get_data_drop_index() -> Self {
-        Self(12)
+        Self(13)
     }
     /// Returns an index for wasm's `raise_trap` instruction.
     pub const fn get_raise_trap_index() -> Self {
-        Self(13)
+        Self(14)
+    }
+    /// Returns an index for the stack limit of the explicit stack checks.
+    pub const fn get_stack_limit_index() -> Self {
+        Self(15)
     }
     /// Returns the total number of builtin functions.
     pub const fn builtin_functions_total_number() -> u32 {
-        14
+        16
     }
 
     /// Return the index as an u32 number.
@@ -891,10 +891,14 @@ impl VMBuiltinFunctionsArray {
             wasmer_imported_memory_fill as usize;
         ptrs[VMBuiltinFunctionIndex::get_memory_init_index().index() as usize] =
             wasmer_memory_init as usize;
+        ptrs[VMBuiltinFunctionIndex::get_imported_memory_init_index().index() as usize] =
+            wasmer_imported_memory_init as usize;
         ptrs[VMBuiltinFunctionIndex::get_data_drop_index().index() as usize] =
             wasmer_data_drop as usize;
         ptrs[VMBuiltinFunctionIndex::get_raise_trap_index().index() as usize] =
             wasmer_raise_trap as usize;
+        ptrs[VMBuiltinFunctionIndex::get_stack_limit_index().index() as usize] =
+            wasmer_stack_limit as usize;
 
         debug_assert!(ptrs.iter().cloned().all(|p| p != 0));
 
@@ -24,9 +24,12 @@ fn cast_to_u32(sz: usize) -> u32 {
     u32::try_from(sz).expect("overflow in cast from usize to u32")
 }
 
-/// Align an offset used in this module to a specific byte-width by rounding up
-const fn align(offset: u32, width: u32) -> u32 {
-    (offset + (width - 1)) / width * width
+/// Align an offset used in this module to a specific byte-width by rounding up.
+///
+/// `width` must be a power of two.
+fn align(offset: u32, width: u32) -> u32 {
+    debug_assert!(width.is_power_of_two());
+    offset.checked_add(width - 1).unwrap() & !(width - 1)
 }
 
 /// This class computes offsets to fields within [`VMContext`] and other
@@ -342,16 +345,19 @@ impl VMOffsets {
         0
     }
 
-    /// The offset of the `tables` array.
-    #[allow(clippy::erasing_op)]
+    /// The offset of the `imported_functions` array.
     pub fn vmctx_imported_functions_begin(&self) -> u32 {
-        self.vmctx_signature_ids_begin()
+        let offset = self
+            .vmctx_signature_ids_begin()
             .checked_add(
                 self.num_signature_ids
                     .checked_mul(u32::from(self.size_of_vmshared_signature_index()))
                     .unwrap(),
             )
-            .unwrap()
+            .unwrap();
+        // The signature ids are 4 bytes wide, but the rest of the fields
+        // hold pointers.
+        align(offset, u32::from(self.pointer_size))
     }
 
     /// The offset of the `tables` array.
@@ -684,7 +690,12 @@ impl TargetSharedSignatureIndex {
 
 #[cfg(test)]
 mod tests {
-    use crate::vmoffsets::align;
+    use crate::vmoffsets::{align, VMOffsets};
+    use crate::VMBuiltinFunctionIndex;
+    use wasmer_types::{
+        FunctionIndex, GlobalIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex,
+        MemoryIndex, SignatureIndex, TableIndex,
+    };
 
     #[test]
     fn alignment() {
@@ -696,4 +707,206 @@ mod tests {
         assert!(is_aligned(align(33, 16)));
         assert!(is_aligned(align(31, 16)));
     }
+
+    /// A xorshift generator, so the layout checks are reproducible.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        /// Returns a count up to `max`, biased towards small counts and the
+        /// bounds.
+        fn count(&mut self, max: u32) -> u32 {
+            match self.next() % 4 {
+                0 => 0,
+                1 => max,
+                2 => (self.next() % 8) as u32,
+                _ => (self.next() % (u64::from(max) + 1)) as u32,
+            }
+        }
+    }
+
+    #[test]
+    fn align_rounds_up_to_the_next_multiple() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for _ in 0..10_000 {
+            let width = 1 << (rng.next() % 8);
+            let offset = (rng.next() % u64::from(u32::MAX - width)) as u32;
+            let aligned = align(offset, width);
+            assert_eq!(aligned % width, 0);
+            assert!(aligned >= offset);
+            assert!(aligned - offset < width);
+        }
+        assert_eq!(align(u32::MAX - 15, 16), u32::MAX - 15);
+    }
+
+    /// The layout of a small module, to notice the changes of the
+    /// layout, which make the code compiled before incompatible.
+    #[test]
+    fn vmctx_layout() {
+        let offsets = VMOffsets {
+            pointer_size: 8,
+            num_signature_ids: 1,
+            num_imported_functions: 1,
+            num_imported_tables: 1,
+            num_imported_memories: 1,
+            num_imported_globals: 1,
+            num_local_tables: 1,
+            num_local_memories: 1,
+            num_local_globals: 1,
+        };
+        assert_eq!(VMBuiltinFunctionIndex::builtin_functions_total_number(), 16);
+        assert_eq!(offsets.vmctx_builtin_functions_begin(), 136);
+        assert_eq!(offsets.size_of_vmctx(), 264);
+    }
+
+    #[test]
+    fn vmctx_sections_dont_overlap() {
+        // Large enough to cover the module limits of the engines.
+        const MAX: u32 = 100_000;
+
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..10_000 {
+            let pointer_size = if rng.next() % 2 == 0 { 4 } else { 8 };
+            let offsets = VMOffsets {
+                pointer_size,
+                num_signature_ids: rng.count(MAX),
+                num_imported_functions: rng.count(MAX),
+                num_imported_tables: rng.count(MAX),
+                num_imported_memories: rng.count(MAX),
+                num_imported_globals: rng.count(MAX),
+                num_local_tables: rng.count(MAX),
+                num_local_memories: rng.count(MAX),
+                num_local_globals: rng.count(MAX),
+            };
+            check_layout(&offsets);
+        }
+        check_layout(&VMOffsets::new_for_trampolines(8));
+    }
+
+    fn check_layout(offsets: &VMOffsets) {
+        let pointer_size = u32::from(offsets.pointer_size);
+        // The sections in layout order: (begin, count, size of an entry).
+        let sections = [
+            (
+                offsets.vmctx_signature_ids_begin(),
+                offsets.num_signature_ids,
+                u32::from(offsets.size_of_vmshared_signature_index()),
+            ),
+            (
+                offsets.vmctx_imported_functions_begin(),
+                offsets.num_imported_functions,
+                u32::from(offsets.size_of_vmfunction_import()),
+            ),
+            (
+                offsets.vmctx_imported_tables_begin(),
+                offsets.num_imported_tables,
+                u32::from(offsets.size_of_vmtable_import()),
+            ),
+            (
+                offsets.vmctx_imported_memories_begin(),
+                offsets.num_imported_memories,
+                u32::from(offsets.size_of_vmmemory_import()),
+            ),
+            (
+                offsets.vmctx_imported_globals_begin(),
+                offsets.num_imported_globals,
+                u32::from(offsets.size_of_vmglobal_import()),
+            ),
+            (
+                offsets.vmctx_tables_begin(),
+                offsets.num_local_tables,
+                u32::from(offsets.size_of_vmtable_definition()),
+            ),
+            (
+                offsets.vmctx_memories_begin(),
+                offsets.num_local_memories,
+                u32::from(offsets.size_of_vmmemory_definition()),
+            ),
+            (
+                offsets.vmctx_globals_begin(),
+                offsets.num_local_globals,
+                u32::from(offsets.size_of_vmglobal_local()),
+            ),
+            (
+                offsets.vmctx_builtin_functions_begin(),
+                VMBuiltinFunctionIndex::builtin_functions_total_number(),
+                pointer_size,
+            ),
+        ];
+
+        let mut end = 0;
+        for (i, &(begin, count, size)) in sections.iter().enumerate() {
+            assert!(begin >= end, "section {} overlaps the previous one", i);
+            if i > 0 {
+                assert_eq!(begin % pointer_size, 0, "section {} is misaligned", i);
+            }
+            end = begin + count * size;
+        }
+        assert_eq!(offsets.vmctx_globals_begin() % 16, 0);
+        assert_eq!(offsets.size_of_vmctx(), end);
+
+        // The last entry of each section must end within it.
+        let within = |section: usize, offset: u32, size: u32| {
+            let (begin, count, entry_size) = sections[section];
+            assert!(offset >= begin);
+            assert!(offset + size <= begin + count * entry_size);
+        };
+        if let Some(i) = offsets.num_signature_ids.checked_sub(1) {
+            let offset = offsets.vmctx_vmshared_signature_id(SignatureIndex::from_u32(i));
+            within(0, offset, sections[0].2);
+        }
+        if let Some(i) = offsets.num_imported_functions.checked_sub(1) {
+            let index = FunctionIndex::from_u32(i);
+            within(1, offsets.vmctx_vmfunction_import(index), sections[1].2);
+            within(
+                1,
+                offsets.vmctx_vmfunction_import_vmctx(index),
+                pointer_size,
+            );
+        }
+        if let Some(i) = offsets.num_imported_tables.checked_sub(1) {
+            let offset = offsets.vmctx_vmtable_import(TableIndex::from_u32(i));
+            within(2, offset, sections[2].2);
+        }
+        if let Some(i) = offsets.num_imported_memories.checked_sub(1) {
+            let index = MemoryIndex::from_u32(i);
+            within(3, offsets.vmctx_vmmemory_import(index), sections[3].2);
+            within(3, offsets.vmctx_vmmemory_import_from(index), pointer_size);
+        }
+        if let Some(i) = offsets.num_imported_globals.checked_sub(1) {
+            let offset = offsets.vmctx_vmglobal_import(GlobalIndex::from_u32(i));
+            within(4, offset, sections[4].2);
+        }
+        if let Some(i) = offsets.num_local_tables.checked_sub(1) {
+            let index = LocalTableIndex::from_u32(i);
+            within(5, offsets.vmctx_vmtable_definition(index), sections[5].2);
+            within(
+                5,
+                offsets.vmctx_vmtable_definition_current_elements(index),
+                u32::from(offsets.size_of_vmtable_definition_current_elements()),
+            );
+        }
+        if let Some(i) = offsets.num_local_memories.checked_sub(1) {
+            let index = LocalMemoryIndex::from_u32(i);
+            within(6, offsets.vmctx_vmmemory_definition(index), sections[6].2);
+            within(
+                6,
+                offsets.vmctx_vmmemory_definition_current_length(index),
+                u32::from(offsets.size_of_vmmemory_definition_current_length()),
+            );
+        }
+        if let Some(i) = offsets.num_local_globals.checked_sub(1) {
+            let offset = offsets.vmctx_vmglobal_definition(LocalGlobalIndex::from_u32(i));
+            within(7, offset, sections[7].2);
+        }
+        // The raise trap builtin is the last one.
+        let offset = offsets.vmctx_builtin_function(VMBuiltinFunctionIndex::get_raise_trap_index());
+        within(8, offset, pointer_size);
+    }
 }
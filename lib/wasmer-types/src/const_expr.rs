@@ -0,0 +1,166 @@
+use crate::indexes::GlobalIndex;
+use crate::lib::std::boxed::Box;
+use crate::lib::std::vec::Vec;
+
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+/// An operator of a `ConstExpr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub enum ConstOp {
+    /// An `i32.const`.
+    I32Const(i32),
+    /// An `i64.const`.
+    I64Const(i64),
+    /// A `global.get` of an `i32` or `i64` global.
+    GlobalGet(GlobalIndex),
+    /// An `i32.add`.
+    I32Add,
+    /// An `i32.sub`.
+    I32Sub,
+    /// An `i32.mul`.
+    I32Mul,
+    /// An `i64.add`.
+    I64Add,
+    /// An `i64.sub`.
+    I64Sub,
+    /// An `i64.mul`.
+    I64Mul,
+}
+
+impl ConstOp {
+    /// The number of values the operator pops from the stack.
+    fn arity(self) -> usize {
+        match self {
+            Self::I32Const(_) | Self::I64Const(_) | Self::GlobalGet(_) => 0,
+            _ => 2,
+        }
+    }
+}
+
+/// A constant expression of the [extended constant expressions proposal],
+/// which computes an integer from constants and globals with the `add`,
+/// `sub` and `mul` operators.
+///
+/// The operators are stored in post-order, as in the WebAssembly binary.
+///
+/// [extended constant expressions proposal]: https://github.com/WebAssembly/extended-const
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub struct ConstExpr {
+    ops: Box<[ConstOp]>,
+}
+
+impl ConstExpr {
+    /// Creates an expression from its operators, or returns `None` if
+    /// they don't leave exactly one value on the stack.
+    pub fn new(ops: Vec<ConstOp>) -> Option<Self> {
+        let mut depth = 0usize;
+        for op in &ops {
+            depth = depth.checked_sub(op.arity())? + 1;
+        }
+        if depth != 1 {
+            return None;
+        }
+        Some(Self {
+            ops: ops.into_boxed_slice(),
+        })
+    }
+
+    /// Creates an expression getting the value of the global `index`.
+    pub fn global_get(index: GlobalIndex) -> Self {
+        Self {
+            ops: Box::new([ConstOp::GlobalGet(index)]),
+        }
+    }
+
+    /// The operators of the expression, in post-order.
+    pub fn ops(&self) -> &[ConstOp] {
+        &self.ops
+    }
+
+    /// Returns the value of the expression if it doesn't read any global.
+    pub fn constant(&self) -> Option<i64> {
+        if self
+            .ops
+            .iter()
+            .any(|op| matches!(op, ConstOp::GlobalGet(_)))
+        {
+            return None;
+        }
+        Some(self.eval(|_| unreachable!()))
+    }
+
+    /// Evaluates the expression, reading the globals with `global`.
+    ///
+    /// The arithmetic wraps around like the WebAssembly operators do. The
+    /// value of an `i32` expression is in the low 32 bits of the result,
+    /// and `global` may return the value of an `i32` global either sign or
+    /// zero extended.
+    pub fn eval(&self, mut global: impl FnMut(GlobalIndex) -> i64) -> i64 {
+        let mut stack = Vec::with_capacity(self.ops.len());
+        for op in self.ops.iter() {
+            let value = match *op {
+                ConstOp::I32Const(value) => i64::from(value),
+                ConstOp::I64Const(value) => value,
+                ConstOp::GlobalGet(index) => global(index),
+                op => {
+                    let rhs = stack.pop().unwrap();
+                    let lhs: i64 = stack.pop().unwrap();
+                    match op {
+                        ConstOp::I32Add => i64::from((lhs as i32).wrapping_add(rhs as i32)),
+                        ConstOp::I32Sub => i64::from((lhs as i32).wrapping_sub(rhs as i32)),
+                        ConstOp::I32Mul => i64::from((lhs as i32).wrapping_mul(rhs as i32)),
+                        ConstOp::I64Add => lhs.wrapping_add(rhs),
+                        ConstOp::I64Sub => lhs.wrapping_sub(rhs),
+                        ConstOp::I64Mul => lhs.wrapping_mul(rhs),
+                        _ => unreachable!(),
+                    }
+                }
+            };
+            stack.push(value);
+        }
+        stack.pop().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_checks_the_stack() {
+        assert!(ConstExpr::new(vec![]).is_none());
+        assert!(ConstExpr::new(vec![ConstOp::I32Const(1), ConstOp::I32Add]).is_none());
+        assert!(ConstExpr::new(vec![ConstOp::I32Const(1), ConstOp::I32Const(2)]).is_none());
+        assert!(ConstExpr::new(vec![
+            ConstOp::I32Const(1),
+            ConstOp::I32Const(2),
+            ConstOp::I32Add
+        ])
+        .is_some());
+    }
+
+    #[test]
+    fn eval_wraps() {
+        let expr = ConstExpr::new(vec![
+            ConstOp::GlobalGet(GlobalIndex::from_u32(0)),
+            ConstOp::I32Const(i32::max_value()),
+            ConstOp::I32Mul,
+            ConstOp::I32Const(16),
+            ConstOp::I32Sub,
+        ])
+        .unwrap();
+        assert_eq!(expr.constant(), None);
+        assert_eq!(expr.eval(|_| 2) as i32, -18);
+
+        let expr = ConstExpr::new(vec![
+            ConstOp::I64Const(i64::max_value()),
+            ConstOp::I64Const(1),
+            ConstOp::I64Add,
+        ])
+        .unwrap();
+        assert_eq!(expr.constant(), Some(i64::min_value()));
+    }
+}
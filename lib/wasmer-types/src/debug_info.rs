@@ -0,0 +1,75 @@
+use crate::lib::std::fmt;
+#[cfg(feature = "enable-serde")]
+use serde::{Deserialize, Serialize};
+
+/// Controls which debug information of a module is kept when it's
+/// compiled, and thus carried into its artifact.
+///
+/// The debug information is made of the name section, which names the
+/// module, its functions and their locals, and of the DWARF custom
+/// sections, whose names start with `.debug_`. Without the names of the
+/// functions, the frames of the traps only have the indices of the
+/// functions that aren't exported.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
+pub enum DebugInfoPolicy {
+    /// Keep the name section and the DWARF custom sections.
+    KeepAll,
+    /// Keep the name section, but drop the DWARF custom sections.
+    NamesOnly,
+    /// Drop the name section and the DWARF custom sections, which are
+    /// not even parsed.
+    StripAll,
+}
+
+impl DebugInfoPolicy {
+    /// Whether the name section is kept.
+    pub fn keeps_names(self) -> bool {
+        self != Self::StripAll
+    }
+
+    /// Whether the custom section `name` is kept.
+    pub fn keeps_custom_section(self, name: &str) -> bool {
+        self == Self::KeepAll || !name.starts_with(".debug_")
+    }
+}
+
+impl Default for DebugInfoPolicy {
+    fn default() -> Self {
+        Self::KeepAll
+    }
+}
+
+impl fmt::Display for DebugInfoPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::KeepAll => "keep all",
+            Self::NamesOnly => "names only",
+            Self::StripAll => "strip all",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kept_sections() {
+        assert!(DebugInfoPolicy::KeepAll.keeps_names());
+        assert!(DebugInfoPolicy::NamesOnly.keeps_names());
+        assert!(!DebugInfoPolicy::StripAll.keeps_names());
+
+        for policy in &[
+            DebugInfoPolicy::KeepAll,
+            DebugInfoPolicy::NamesOnly,
+            DebugInfoPolicy::StripAll,
+        ] {
+            assert!(policy.keeps_custom_section("producers"));
+            assert_eq!(
+                policy.keeps_custom_section(".debug_info"),
+                *policy == DebugInfoPolicy::KeepAll
+            );
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::lib::std::vec::Vec;
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
 
@@ -18,6 +19,8 @@ pub struct Features {
     pub bulk_memory: bool,
     /// Multi Value proposal should be enabled
     pub multi_value: bool,
+    /// Extended Constant Expressions proposal should be enabled
+    pub extended_const: bool,
 }
 
 impl Features {
@@ -31,6 +34,7 @@ impl Features {
             bulk_memory: true,
             // Multivalue should be on by default
             multi_value: true,
+            extended_const: false,
         }
     }
 
@@ -136,6 +140,57 @@ impl Features {
         self.multi_value = enable;
         self
     }
+
+    /// Configures whether the WebAssembly extended constant expressions
+    /// proposal will be enabled.
+    ///
+    /// The [WebAssembly extended constant expressions proposal][proposal] is
+    /// not currently fully standardized and is undergoing development.
+    /// Support for this feature can be enabled through this method for
+    /// appropriate WebAssembly modules.
+    ///
+    /// This feature gates the `add`, `sub` and `mul` integer operators in
+    /// global initializers and segment offsets.
+    ///
+    /// This is `false` by default.
+    ///
+    /// [proposal]: https://github.com/webassembly/extended-const
+    pub fn extended_const(&mut self, enable: bool) -> &mut Self {
+        self.extended_const = enable;
+        self
+    }
+
+    /// Returns whether all the features enabled in `other` are also
+    /// enabled in `self`.
+    pub fn is_superset_of(&self, other: &Self) -> bool {
+        self.missing_features(other).is_empty()
+    }
+
+    /// Returns the names of the features enabled in `required` but not
+    /// in `self`.
+    pub fn missing_features(&self, required: &Self) -> Vec<&'static str> {
+        let features = [
+            ("threads", self.threads, required.threads),
+            (
+                "reference types",
+                self.reference_types,
+                required.reference_types,
+            ),
+            ("SIMD", self.simd, required.simd),
+            ("bulk memory", self.bulk_memory, required.bulk_memory),
+            ("multi value", self.multi_value, required.multi_value),
+            (
+                "extended const",
+                self.extended_const,
+                required.extended_const,
+            ),
+        ];
+        features
+            .iter()
+            .filter(|(_, enabled, required)| *required && !*enabled)
+            .map(|(name, _, _)| *name)
+            .collect()
+    }
 }
 
 impl Default for Features {
@@ -158,6 +213,7 @@ mod test_features {
                 simd: false,
                 bulk_memory: true,
                 multi_value: true,
+                extended_const: false,
             }
         );
     }
@@ -192,6 +248,13 @@ mod test_features {
         assert!(features.multi_value);
     }
 
+    #[test]
+    fn enable_extended_const() {
+        let mut features = Features::new();
+        features.extended_const(true);
+        assert!(features.extended_const);
+    }
+
     #[test]
     fn enable_bulk_memory() {
         let mut features = Features::new();
@@ -209,4 +272,21 @@ mod test_features {
         assert!(!features.bulk_memory);
         assert!(!features.reference_types);
     }
+
+    #[test]
+    fn superset() {
+        let default = Features::default();
+        let mut simd = Features::default();
+        simd.simd(true);
+
+        assert!(default.is_superset_of(&default));
+        assert!(simd.is_superset_of(&default));
+        assert!(!default.is_superset_of(&simd));
+        assert_eq!(default.missing_features(&simd), vec!["SIMD"]);
+
+        let mut threads = Features::default();
+        threads.threads(true).multi_value(false);
+        assert_eq!(simd.missing_features(&threads), vec!["threads"]);
+        assert!(!threads.is_superset_of(&simd));
+    }
 }
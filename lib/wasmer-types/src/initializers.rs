@@ -1,5 +1,8 @@
-use crate::indexes::{FunctionIndex, GlobalIndex, MemoryIndex, TableIndex};
+use crate::const_expr::ConstExpr;
+use crate::indexes::{FunctionIndex, MemoryIndex, TableIndex};
+use crate::lib::std::borrow::Cow;
 use crate::lib::std::boxed::Box;
+use crate::lib::std::vec::Vec;
 
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
@@ -9,8 +12,8 @@ use serde::{Deserialize, Serialize};
 pub struct TableInitializer {
     /// The index of a table to initialize.
     pub table_index: TableIndex,
-    /// Optionally, a global variable giving a base index.
-    pub base: Option<GlobalIndex>,
+    /// Optionally, an expression reading globals giving a base index.
+    pub base: Option<ConstExpr>,
     /// The offset to add to the base.
     pub offset: usize,
     /// The values to write into the table elements.
@@ -25,8 +28,9 @@ pub struct DataInitializerLocation {
     /// The index of the memory to initialize.
     pub memory_index: MemoryIndex,
 
-    /// Optionally a Global variable base to initialize at.
-    pub base: Option<GlobalIndex>,
+    /// Optionally an expression reading globals giving the base to
+    /// initialize at.
+    pub base: Option<ConstExpr>,
 
     /// A constant offset to initialize at.
     pub offset: usize,
@@ -40,7 +44,10 @@ pub struct DataInitializer<'data> {
     pub location: DataInitializerLocation,
 
     /// The initialization data.
-    pub data: &'data [u8],
+    ///
+    /// It's borrowed from the module, unless the module was translated
+    /// as it was received.
+    pub data: Cow<'data, [u8]>,
 }
 
 /// As `DataInitializer` but owning the data rather than
@@ -64,3 +71,240 @@ impl OwnedDataInitializer {
         }
     }
 }
+
+/// Merges the adjacent and overlapping data initializers with a constant
+/// offset into single initializers, so that instantiation applies (and
+/// bounds checks) one run of data instead of many small segments.
+///
+/// Initializing a memory with the merged initializers gives the same
+/// contents, and traps in the same cases, as initializing it with
+/// `initializers`:
+///
+/// * The bytes of overlapping initializers are taken in their original
+///   order, so later initializers still overwrite earlier ones.
+/// * A merged run is contiguous, so it is in bounds exactly when all of its
+///   initializers are.
+/// * Initializers with a global base, whose offset isn't known before
+///   instantiation, are never merged nor moved across: the constant ones
+///   are only merged between them.
+/// * Empty initializers are kept, since they are still bounds checked.
+///
+/// The result is sorted by memory index and offset between the
+/// initializers with a global base.
+pub fn merge_data_initializers(
+    initializers: &[OwnedDataInitializer],
+) -> Box<[OwnedDataInitializer]> {
+    let mut merged = Vec::with_capacity(initializers.len());
+    let mut start = 0;
+    for (index, initializer) in initializers.iter().enumerate() {
+        if initializer.location.base.is_some() {
+            merge_constant_initializers(&initializers[start..index], &mut merged);
+            merged.push(initializer.clone());
+            start = index + 1;
+        }
+    }
+    merge_constant_initializers(&initializers[start..], &mut merged);
+    merged.into_boxed_slice()
+}
+
+/// Merges `initializers`, which all have a constant offset, into `merged`.
+fn merge_constant_initializers(
+    initializers: &[OwnedDataInitializer],
+    merged: &mut Vec<OwnedDataInitializer>,
+) {
+    let end = |initializer: &OwnedDataInitializer| {
+        initializer
+            .location
+            .offset
+            .checked_add(initializer.data.len())
+    };
+
+    // The empty initializers, and the ones ending past the address space
+    // (which always trap), don't write anything: they are kept as they are.
+    let mut sorted = Vec::with_capacity(initializers.len());
+    for (index, initializer) in initializers.iter().enumerate() {
+        if initializer.data.is_empty() || end(initializer).is_none() {
+            merged.push(initializer.clone());
+        } else {
+            sorted.push(index);
+        }
+    }
+    // The sort is stable, so overlapping initializers at the same offset
+    // stay in their original order.
+    sorted.sort_by_key(|&index| {
+        let location = &initializers[index].location;
+        (location.memory_index, location.offset)
+    });
+
+    let mut run: Vec<usize> = Vec::new();
+    let mut run_end = 0;
+    for index in sorted {
+        let initializer = &initializers[index];
+        if let Some(&first) = run.first() {
+            let location = &initializers[first].location;
+            if location.memory_index != initializer.location.memory_index
+                || initializer.location.offset > run_end
+            {
+                merged.push(merge_run(initializers, &mut run, run_end));
+            }
+        }
+        let initializer_end = end(initializer).unwrap();
+        if run.is_empty() || initializer_end > run_end {
+            run_end = initializer_end;
+        }
+        run.push(index);
+    }
+    if !run.is_empty() {
+        merged.push(merge_run(initializers, &mut run, run_end));
+    }
+}
+
+/// Merges the `run` of contiguous initializers ending at `end` into one
+/// initializer, and clears `run`.
+fn merge_run(
+    initializers: &[OwnedDataInitializer],
+    run: &mut Vec<usize>,
+    end: usize,
+) -> OwnedDataInitializer {
+    if run.len() == 1 {
+        return initializers[run.pop().unwrap()].clone();
+    }
+    let location = initializers[run[0]].location.clone();
+    let mut data = Vec::new();
+    data.resize(end - location.offset, 0);
+    // Apply the initializers in their original order.
+    run.sort_unstable();
+    for index in run.drain(..) {
+        let initializer = &initializers[index];
+        let start = initializer.location.offset - location.offset;
+        data[start..start + initializer.data.len()].copy_from_slice(&initializer.data);
+    }
+    OwnedDataInitializer {
+        location,
+        data: data.into_boxed_slice(),
+    }
+}
+
+#[cfg(test)]
+mod test_merge_data_initializers {
+    use super::*;
+    use crate::indexes::GlobalIndex;
+
+    fn initializer(
+        memory: u32,
+        base: Option<u32>,
+        offset: usize,
+        data: &[u8],
+    ) -> OwnedDataInitializer {
+        OwnedDataInitializer {
+            location: DataInitializerLocation {
+                memory_index: MemoryIndex::from_u32(memory),
+                base: base.map(|index| ConstExpr::global_get(GlobalIndex::from_u32(index))),
+                offset,
+            },
+            data: data.to_vec().into_boxed_slice(),
+        }
+    }
+
+    /// Initializes memories of `size` bytes, with `global` as the value of
+    /// every global base, like instantiation does: the bounds of all the
+    /// initializers are checked before any of them is applied.
+    fn apply(
+        initializers: &[OwnedDataInitializer],
+        size: usize,
+        global: usize,
+    ) -> Option<Vec<Vec<u8>>> {
+        let mut memories = vec![vec![0xff; size]; 2];
+        let start = |initializer: &OwnedDataInitializer| {
+            initializer.location.offset + initializer.location.base.as_ref().map_or(0, |_| global)
+        };
+        for initializer in initializers {
+            if start(initializer) + initializer.data.len() > size {
+                return None;
+            }
+        }
+        for initializer in initializers {
+            let start = start(initializer);
+            let memory = &mut memories[initializer.location.memory_index.as_u32() as usize];
+            memory[start..start + initializer.data.len()].copy_from_slice(&initializer.data);
+        }
+        Some(memories)
+    }
+
+    fn assert_equivalent(initializers: &[OwnedDataInitializer]) -> usize {
+        let merged = merge_data_initializers(initializers);
+        for size in 0..24 {
+            for global in 0..4 {
+                assert_eq!(
+                    apply(&merged, size, global),
+                    apply(initializers, size, global),
+                    "memory size {}, global base {}",
+                    size,
+                    global
+                );
+            }
+        }
+        merged.len()
+    }
+
+    #[test]
+    fn merges_adjacent_initializers() {
+        let initializers = [
+            initializer(0, None, 4, &[5, 6]),
+            initializer(0, None, 0, &[1, 2]),
+            initializer(0, None, 2, &[3, 4]),
+        ];
+        assert_eq!(assert_equivalent(&initializers), 1);
+        let merged = merge_data_initializers(&initializers);
+        assert_eq!(merged[0].location.offset, 0);
+        assert_eq!(&*merged[0].data, &[1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn later_overlapping_initializers_win() {
+        let initializers = [
+            initializer(0, None, 2, &[1, 1, 1, 1, 1, 1]),
+            initializer(0, None, 0, &[2, 2, 2, 2]),
+            initializer(0, None, 4, &[3]),
+            initializer(0, None, 2, &[4, 4]),
+            initializer(0, None, 3, &[5, 5, 5, 5, 5, 5, 5]),
+        ];
+        assert_eq!(assert_equivalent(&initializers), 1);
+        let merged = merge_data_initializers(&initializers);
+        assert_eq!(&*merged[0].data, &[2, 2, 4, 5, 5, 5, 5, 5, 5, 5]);
+    }
+
+    #[test]
+    fn keeps_gaps_and_memories_apart() {
+        let initializers = [
+            initializer(0, None, 0, &[1, 2]),
+            initializer(1, None, 2, &[3, 4]),
+            initializer(0, None, 3, &[5, 6]),
+            initializer(1, None, 0, &[7, 8]),
+        ];
+        assert_eq!(assert_equivalent(&initializers), 3);
+    }
+
+    #[test]
+    fn does_not_merge_across_global_bases() {
+        let initializers = [
+            initializer(0, None, 0, &[1, 1, 1, 1]),
+            initializer(0, Some(0), 1, &[2, 2]),
+            initializer(0, None, 2, &[3, 3, 3, 3]),
+            initializer(0, None, 4, &[4, 4]),
+            initializer(0, Some(0), 0, &[5]),
+        ];
+        assert_eq!(assert_equivalent(&initializers), 4);
+    }
+
+    #[test]
+    fn keeps_empty_and_out_of_bounds_initializers() {
+        let initializers = [
+            initializer(0, None, 0, &[1, 2, 3]),
+            initializer(0, None, 20, &[]),
+            initializer(0, None, 3, &[4]),
+            initializer(0, None, 18, &[5, 6, 7, 8, 9, 10]),
+        ];
+        assert_eq!(assert_equivalent(&initializers), 3);
+    }
+}
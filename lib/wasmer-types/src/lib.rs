@@ -3,6 +3,11 @@
 //!
 //! This crate provides common structures such as `Type` or `Value`, type indexes
 //! and native function wrappers with `Func`.
+//!
+//! This is an internal crate of Wasmer, and its API may change between
+//! releases. Embedders should use the curated re-exports in `wasmer::types`
+//! instead of depending on this crate directly. Items that are only used
+//! by the compilers and engines are hidden from the documentation.
 
 #![deny(missing_docs, unused_extern_crates)]
 #![warn(unused_import_braces)]
@@ -37,19 +42,21 @@ extern crate alloc;
 mod lib {
     #[cfg(feature = "core")]
     pub mod std {
-        pub use alloc::{borrow, boxed, format, rc, slice, string, vec};
+        pub use alloc::{borrow, boxed, format, rc, slice, str, string, vec};
         pub use core::{any, cell, fmt, hash, marker, ops, ptr, sync};
     }
 
     #[cfg(feature = "std")]
     pub mod std {
         pub use std::{
-            any, borrow, boxed, cell, fmt, format, hash, marker, ops, ptr, rc, slice, string, sync,
-            vec,
+            any, borrow, boxed, cell, fmt, format, hash, marker, ops, ptr, rc, slice, str, string,
+            sync, vec,
         };
     }
 }
 
+mod const_expr;
+mod debug_info;
 mod features;
 mod indexes;
 mod initializers;
@@ -65,14 +72,19 @@ pub mod entity {
     pub use cranelift_entity::*;
 }
 
+pub use crate::const_expr::{ConstExpr, ConstOp};
+pub use crate::debug_info::DebugInfoPolicy;
 pub use crate::features::Features;
+#[doc(hidden)]
+pub use crate::indexes::{CustomSectionIndex, DataIndex, ElemIndex, SignatureIndex};
 pub use crate::indexes::{
-    CustomSectionIndex, DataIndex, ElemIndex, ExportIndex, FunctionIndex, GlobalIndex, ImportIndex,
-    LocalFunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex,
-    SignatureIndex, TableIndex,
+    ExportIndex, FunctionIndex, GlobalIndex, ImportIndex, LocalFunctionIndex, LocalGlobalIndex,
+    LocalMemoryIndex, LocalTableIndex, MemoryIndex, TableIndex,
 };
+#[doc(hidden)]
 pub use crate::initializers::{
-    DataInitializer, DataInitializerLocation, OwnedDataInitializer, TableInitializer,
+    merge_data_initializers, DataInitializer, DataInitializerLocation, OwnedDataInitializer,
+    TableInitializer,
 };
 pub use crate::memory_view::{Atomically, MemoryView};
 pub use crate::native::{NativeWasmType, ValueType};
@@ -81,7 +93,7 @@ pub use crate::units::{Bytes, Pages, WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_S
 pub use crate::values::Value;
 pub use types::{
     ExportType, ExternType, FunctionType, GlobalInit, GlobalType, ImportType, MemoryType,
-    Mutability, TableType, Type, V128,
+    Mutability, ParseFunctionTypeError, TableType, Type, V128,
 };
 
 /// Version number of this crate.
@@ -2,7 +2,7 @@
 //! easily in Rust, thanks to its advanced typing system.
 
 use crate::lib::std::fmt;
-use crate::types::Type;
+use crate::types::{Type, V128};
 use crate::values::Value;
 
 /// `NativeWasmType` represents a Wasm type that has a direct
@@ -172,6 +172,34 @@ impl NativeWasmType for u128 {
     }
 }
 
+/// A `V128` is passed as a `u128` whose little-endian bytes are the
+/// bytes of the vector, so it fits in a single `i128` slot of the
+/// binary representation.
+impl NativeWasmType for V128 {
+    const WASM_TYPE: Type = Type::V128;
+    type Abi = u128;
+
+    #[inline]
+    fn from_abi(abi: Self::Abi) -> Self {
+        abi.into()
+    }
+
+    #[inline]
+    fn into_abi(self) -> Self::Abi {
+        self.into()
+    }
+
+    #[inline]
+    fn to_binary(self) -> i128 {
+        u128::from(self) as _
+    }
+
+    #[inline]
+    fn from_binary(bits: i128) -> Self {
+        (bits as u128).into()
+    }
+}
+
 #[cfg(test)]
 mod test_native_type {
     use super::*;
@@ -184,6 +212,7 @@ mod test_native_type {
         assert_eq!(f32::WASM_TYPE, Type::F32);
         assert_eq!(f64::WASM_TYPE, Type::F64);
         assert_eq!(u128::WASM_TYPE, Type::V128);
+        assert_eq!(V128::WASM_TYPE, Type::V128);
     }
 
     #[test]
@@ -193,6 +222,10 @@ mod test_native_type {
         assert_eq!(f32::from_binary(42f32.to_binary()), 42f32);
         assert_eq!(f64::from_binary(42f64.to_binary()), 42f64);
         assert_eq!(u128::from_binary(42u128.to_binary()), 42u128);
+        let v128 = V128::from(0x0000_0004_0000_0003_0000_0002_0000_0001u128);
+        assert_eq!(v128.as_i32x4(), [1, 2, 3, 4]);
+        assert_eq!(V128::from_binary(v128.to_binary()), v128);
+        assert_eq!(V128::from_abi(v128.into_abi()), v128);
     }
 }
 
@@ -1,7 +1,9 @@
+use crate::const_expr::ConstExpr;
 use crate::indexes::{FunctionIndex, GlobalIndex};
 use crate::lib::std::borrow::ToOwned;
 use crate::lib::std::fmt;
 use crate::lib::std::format;
+use crate::lib::std::str::FromStr;
 use crate::lib::std::string::{String, ToString};
 use crate::lib::std::vec::Vec;
 use crate::units::Pages;
@@ -56,9 +58,11 @@ impl fmt::Display for Type {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 /// The WebAssembly V128 type
+///
+/// It's formatted as four `i32` lanes, e.g. `v128(1, 2, 3, 4)`.
 pub struct V128(pub(crate) [u8; 16]);
 
 impl V128 {
@@ -80,6 +84,45 @@ impl V128 {
     pub fn as_slice(&self) -> &[u8] {
         &self.0[..]
     }
+
+    /// The value as four `i32` lanes, the first lane being the least
+    /// significant one.
+    pub fn as_i32x4(&self) -> [i32; 4] {
+        let mut lanes = [0; 4];
+        for (lane, bytes) in lanes.iter_mut().zip(self.0.chunks(4)) {
+            *lane = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+        lanes
+    }
+}
+
+impl fmt::Debug for V128 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for V128 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lanes = self.as_i32x4();
+        write!(
+            f,
+            "v128({}, {}, {}, {})",
+            lanes[0], lanes[1], lanes[2], lanes[3]
+        )
+    }
+}
+
+impl From<u128> for V128 {
+    fn from(value: u128) -> Self {
+        Self(value.to_le_bytes())
+    }
+}
+
+impl From<V128> for u128 {
+    fn from(value: V128) -> Self {
+        Self::from_le_bytes(value.0)
+    }
 }
 
 impl From<&[u8]> for V128 {
@@ -213,6 +256,23 @@ impl ExternType {
     }
 }
 
+macro_rules! from_descriptors {
+    ($($variant:ident($ty:ty))*) => ($(
+        impl From<$ty> for ExternType {
+            fn from(ty: $ty) -> Self {
+                Self::$variant(ty)
+            }
+        }
+    )*)
+}
+
+from_descriptors! {
+    Function(FunctionType)
+    Global(GlobalType)
+    Table(TableType)
+    Memory(MemoryType)
+}
+
 // TODO: `shrink_to_fit` these or change it to `Box<[Type]>` if not using
 // Cow or something else
 /// The signature of a function that is either implemented
@@ -250,6 +310,26 @@ impl FunctionType {
     pub fn results(&self) -> &[Type] {
         &self.results
     }
+
+    /// Returns whether `params` have the number and the types of the
+    /// parameters of the function.
+    pub fn check_params<T>(&self, params: &[Value<T>]) -> bool {
+        Self::check_values(&self.params, params)
+    }
+
+    /// Returns whether `results` have the number and the types of the
+    /// results of the function.
+    pub fn check_results<T>(&self, results: &[Value<T>]) -> bool {
+        Self::check_values(&self.results, results)
+    }
+
+    fn check_values<T>(types: &[Type], values: &[Value<T>]) -> bool {
+        types.len() == values.len()
+            && types
+                .iter()
+                .zip(values)
+                .all(|(ty, value)| *ty == value.ty())
+    }
 }
 
 impl fmt::Display for FunctionType {
@@ -270,6 +350,141 @@ impl fmt::Display for FunctionType {
     }
 }
 
+/// Parses a function signature such as `(i32, i64) -> f64`.
+///
+/// The parameters are always in parentheses, the results only when there
+/// isn't exactly one of them: `() -> ()` has neither parameters nor
+/// results. The types are named as in the text format (`i32`, `i64`, `f32`,
+/// `f64`, `v128`, `externref` and `funcref`), in any case.
+impl FromStr for FunctionType {
+    type Err = ParseFunctionTypeError;
+
+    fn from_str(signature: &str) -> Result<Self, Self::Err> {
+        let mut parser = SignatureParser {
+            signature,
+            position: 0,
+        };
+        let params = parser.types()?;
+        parser.expect("->")?;
+        let results = if parser.peek("(") {
+            parser.types()?
+        } else {
+            [parser.ty()?].to_vec()
+        };
+        parser.skip_whitespace();
+        if parser.position < signature.len() {
+            return Err(parser.error("expected the end of the signature".to_string()));
+        }
+        Ok(Self::new(params, results))
+    }
+}
+
+/// An error parsing a [`FunctionType`] from a signature string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseFunctionTypeError {
+    /// The byte offset in the signature where the error was found.
+    pub position: usize,
+    /// The description of the error.
+    pub message: String,
+}
+
+impl fmt::Display for ParseFunctionTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseFunctionTypeError {}
+
+/// A cursor in a function signature being parsed.
+struct SignatureParser<'a> {
+    signature: &'a str,
+    position: usize,
+}
+
+impl<'a> SignatureParser<'a> {
+    fn error(&self, message: String) -> ParseFunctionTypeError {
+        ParseFunctionTypeError {
+            position: self.position,
+            message,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.signature[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Whether the next token is `token`.
+    fn peek(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        self.signature[self.position..].starts_with(token)
+    }
+
+    /// Consumes the next token if it is `token`.
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.peek(token);
+        if found {
+            self.position += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), ParseFunctionTypeError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", token)))
+        }
+    }
+
+    /// Parses a type name.
+    fn ty(&mut self) -> Result<Type, ParseFunctionTypeError> {
+        self.skip_whitespace();
+        let rest = &self.signature[self.position..];
+        let name = &rest[..rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or_else(|| rest.len())];
+        if name.is_empty() {
+            return Err(self.error("expected a type".to_string()));
+        }
+        let ty = [
+            ("i32", Type::I32),
+            ("i64", Type::I64),
+            ("f32", Type::F32),
+            ("f64", Type::F64),
+            ("v128", Type::V128),
+            ("externref", Type::ExternRef),
+            ("funcref", Type::FuncRef),
+        ]
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(name))
+        .map(|(_, ty)| *ty)
+        .ok_or_else(|| self.error(format!("unknown type `{}`", name)))?;
+        self.position += name.len();
+        Ok(ty)
+    }
+
+    /// Parses a parenthesized list of types.
+    fn types(&mut self) -> Result<Vec<Type>, ParseFunctionTypeError> {
+        self.expect("(")?;
+        let mut types = Vec::new();
+        if self.eat(")") {
+            return Ok(types);
+        }
+        loop {
+            types.push(self.ty()?);
+            if self.eat(")") {
+                return Ok(types);
+            }
+            if !self.eat(",") {
+                return Err(self.error("expected `,` or `)`".to_string()));
+            }
+        }
+    }
+}
+
 /// Indicator of whether a global is mutable or not
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
@@ -350,7 +565,7 @@ impl fmt::Display for GlobalType {
 }
 
 /// Globals are initialized via the `const` operators or by referring to another import.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
 pub enum GlobalInit {
     /// An `i32.const`.
@@ -369,27 +584,40 @@ pub enum GlobalInit {
     RefNullConst,
     /// A `ref.func <index>`.
     RefFunc(FunctionIndex),
+    /// An extended constant expression reading other globals.
+    Expr(ConstExpr),
 }
 
 impl GlobalInit {
-    /// Get the `GlobalInit` from a given `Value`
-    pub fn from_value<T>(value: Value<T>) -> Self {
+    /// Get the `GlobalInit` from a given `Value`, if it can be a
+    /// constant initializer.
+    ///
+    /// References can't: they are initialized with `ref.null` or
+    /// `ref.func`, which don't carry a value.
+    pub fn from_value<T>(value: Value<T>) -> Option<Self> {
         match value {
-            Value::I32(i) => Self::I32Const(i),
-            Value::I64(i) => Self::I64Const(i),
-            Value::F32(f) => Self::F32Const(f),
-            Value::F64(f) => Self::F64Const(f),
-            _ => unimplemented!("GlobalInit from_value for {:?}", value),
+            Value::I32(i) => Some(Self::I32Const(i)),
+            Value::I64(i) => Some(Self::I64Const(i)),
+            Value::F32(f) => Some(Self::F32Const(f)),
+            Value::F64(f) => Some(Self::F64Const(f)),
+            Value::V128(v) => Some(Self::V128Const(V128::from(v))),
+            Value::ExternRef(_) | Value::FuncRef(_) => None,
         }
     }
-    /// Get the `Value` from the Global init value
-    pub fn to_value<T>(&self) -> Value<T> {
+
+    /// Get the `Value` from the Global init value, if it is a constant.
+    ///
+    /// The initializers that depend on the instance, like `global.get`
+    /// and `ref.func`, and `ref.null`, whose type isn't known, have no
+    /// value.
+    pub fn to_value<T>(&self) -> Option<Value<T>> {
         match self {
-            Self::I32Const(i) => Value::I32(*i),
-            Self::I64Const(i) => Value::I64(*i),
-            Self::F32Const(f) => Value::F32(*f),
-            Self::F64Const(f) => Value::F64(*f),
-            _ => unimplemented!("GlobalInit to_value for {:?}", self),
+            Self::I32Const(i) => Some(Value::I32(*i)),
+            Self::I64Const(i) => Some(Value::I64(*i)),
+            Self::F32Const(f) => Some(Value::F32(*f)),
+            Self::F64Const(f) => Some(Value::F64(*f)),
+            Self::V128Const(v) => Some(Value::V128(u128::from(*v))),
+            Self::GetGlobal(_) | Self::RefNullConst | Self::RefFunc(_) | Self::Expr(_) => None,
         }
     }
 }
@@ -560,3 +788,129 @@ impl<T> ExportType<T> {
         &self.ty
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn global_init_values() {
+        let value = 0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10u128;
+        let init = GlobalInit::from_value(Value::<()>::V128(value)).unwrap();
+        match &init {
+            GlobalInit::V128Const(v) => assert_eq!(u128::from(*v), value),
+            init => panic!("unexpected initializer: {:?}", init),
+        }
+        assert_eq!(init.to_value::<()>(), Some(Value::V128(value)));
+        assert_eq!(
+            GlobalInit::from_value(Value::<()>::I32(7))
+                .unwrap()
+                .to_value(),
+            Some(Value::<()>::I32(7))
+        );
+        assert!(GlobalInit::from_value(Value::FuncRef(())).is_none());
+        assert!(GlobalInit::GetGlobal(GlobalIndex::from_u32(0))
+            .to_value::<()>()
+            .is_none());
+        assert!(GlobalInit::RefNullConst.to_value::<()>().is_none());
+    }
+
+    #[test]
+    fn format_v128() {
+        let bytes = [
+            1, 0, 0, 0, 2, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0x80,
+        ];
+        let value = V128::from(&bytes[..]);
+        assert_eq!(value.as_i32x4(), [1, 2, -1, i32::min_value()]);
+        assert_eq!(format!("{}", value), "v128(1, 2, -1, -2147483648)");
+        assert_eq!(format!("{:?}", value), "v128(1, 2, -1, -2147483648)");
+        assert_eq!(value.bytes(), &bytes);
+    }
+
+    #[test]
+    fn check_function_values() {
+        let ty = FunctionType::new(vec![Type::I32, Type::F64], vec![Type::I64]);
+        let params: &[Value<()>] = &[Value::I32(1), Value::F64(2.0)];
+        assert!(ty.check_params(params));
+        assert!(ty.check_results::<()>(&[Value::I64(3)]));
+
+        // Length mismatch.
+        assert!(!ty.check_params::<()>(&[Value::I32(1)]));
+        assert!(!ty.check_params::<()>(&[Value::I32(1), Value::F64(2.0), Value::I32(3)]));
+        assert!(!ty.check_results::<()>(&[]));
+
+        // Type mismatch.
+        assert!(!ty.check_params::<()>(&[Value::F64(2.0), Value::I32(1)]));
+        assert!(!ty.check_results::<()>(&[Value::I32(3)]));
+
+        // Empty signature.
+        let empty = FunctionType::new(vec![], vec![]);
+        assert!(empty.check_params::<()>(&[]));
+        assert!(empty.check_results::<()>(&[]));
+        assert!(!empty.check_params::<()>(&[Value::I32(1)]));
+        assert!(!empty.check_results::<()>(&[Value::I32(1)]));
+    }
+
+    #[test]
+    fn extern_type_from_descriptors() {
+        let function = FunctionType::new(vec![Type::I32], vec![Type::I64]);
+        let ty = ExternType::from(function.clone());
+        assert_eq!(ty.func(), Some(&function));
+        assert_eq!(ty.memory(), None);
+
+        let global = GlobalType::new(Type::F32, Mutability::Var);
+        let ty: ExternType = global.into();
+        assert_eq!(ty.global(), Some(&global));
+        assert_eq!(ty.func(), None);
+
+        let table = TableType::new(Type::FuncRef, 1, Some(10));
+        let ty: ExternType = table.into();
+        assert_eq!(ty.table(), Some(&table));
+        assert_eq!(ty.global(), None);
+
+        let memory = MemoryType::new(1, Some(2), false);
+        let ty: ExternType = memory.into();
+        assert_eq!(ty.memory(), Some(&memory));
+        assert_eq!(ty.table(), None);
+    }
+
+    #[test]
+    fn parse_function_type() {
+        let ty: FunctionType = "(i32, i64) -> f64".parse().unwrap();
+        assert_eq!(
+            ty,
+            FunctionType::new(vec![Type::I32, Type::I64], vec![Type::F64])
+        );
+        let ty: FunctionType = " ( ) -> ( F32,v128 , ExternRef,funcref ) ".parse().unwrap();
+        assert_eq!(
+            ty,
+            FunctionType::new(
+                vec![],
+                vec![Type::F32, Type::V128, Type::ExternRef, Type::FuncRef]
+            )
+        );
+        let ty: FunctionType = "()->()".parse().unwrap();
+        assert_eq!(ty, FunctionType::new(vec![], vec![]));
+    }
+
+    #[test]
+    fn parse_malformed_function_type() {
+        let error = |signature: &str| signature.parse::<FunctionType>().unwrap_err().to_string();
+        assert_eq!(
+            error("(i32, i33) -> f64"),
+            "unknown type `i33` at position 6"
+        );
+        assert_eq!(error("i32 -> f64"), "expected `(` at position 0");
+        assert_eq!(
+            error("(i32 i64) -> f64"),
+            "expected `,` or `)` at position 5"
+        );
+        assert_eq!(error("(i32,) -> f64"), "expected a type at position 5");
+        assert_eq!(error("(i32) f64"), "expected `->` at position 6");
+        assert_eq!(error("(i32) ->"), "expected a type at position 8");
+        assert_eq!(
+            error("(i32) -> f64 f32"),
+            "expected the end of the signature at position 13"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::utils::get_compiler;
+use anyhow::Result;
+use wasmer::{FunctionType, Type};
+use wasmer_compiler::{CompileError, CompilerConfig};
+
+#[test]
+fn compile_add_function() -> Result<()> {
+    let ty = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+    // local.get 0, local.get 1, i32.add, end
+    let body = [0x20, 0x00, 0x20, 0x01, 0x6a, 0x0b];
+    let function = get_compiler(false)
+        .compiler()
+        .compile_function(&ty, &body, &[])?;
+
+    assert!(!function.body.body.is_empty());
+    // The traps of the prologue and the stubs shared by the traps of the
+    // body aren't attributed to any instruction.
+    assert!(function
+        .frame_info
+        .traps
+        .iter()
+        .all(|trap| trap.source_loc.is_default()));
+    Ok(())
+}
+
+#[test]
+// Singlepass records the shared trap stubs only, see `compile_add_function`.
+#[cfg_attr(feature = "test-singlepass", ignore)]
+fn compile_trapping_function() -> Result<()> {
+    let ty = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+    // local.get 0, local.get 1, i32.div_s, end
+    let body = [0x20, 0x00, 0x20, 0x01, 0x6d, 0x0b];
+    let function = get_compiler(false)
+        .compiler()
+        .compile_function(&ty, &body, &[])?;
+
+    assert!(function
+        .frame_info
+        .traps
+        .iter()
+        .any(|trap| !trap.source_loc.is_default()));
+    Ok(())
+}
+
+#[test]
+fn compile_function_with_locals() -> Result<()> {
+    let ty = FunctionType::new(vec![Type::I64], vec![Type::I64]);
+    // local.get 0, local.set 2, local.get 2, end
+    let body = [0x20, 0x00, 0x21, 0x02, 0x20, 0x02, 0x0b];
+    let function = get_compiler(false).compiler().compile_function(
+        &ty,
+        &body,
+        &[(1, Type::I32), (1, Type::I64)],
+    )?;
+
+    assert!(!function.body.body.is_empty());
+    Ok(())
+}
+
+#[test]
+fn compile_invalid_function() {
+    let ty = FunctionType::new(vec![Type::I32], vec![Type::I64]);
+    // local.get 0, end
+    let body = [0x20, 0x00, 0x0b];
+    let result = get_compiler(false)
+        .compiler()
+        .compile_function(&ty, &body, &[]);
+
+    match result {
+        Err(CompileError::Validate(_)) => {}
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+}
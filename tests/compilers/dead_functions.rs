@@ -0,0 +1,45 @@
+use crate::utils::get_store_with_dead_function_pruning;
+use anyhow::Result;
+use wasmer::*;
+
+#[test]
+fn pruned_module_still_works() -> Result<()> {
+    let store = get_store_with_dead_function_pruning();
+    let wat = r#"(module
+    (func $double (import "env" "double") (param i32) (result i32))
+    (type $unary (func (param i32) (result i32)))
+    (table 1 funcref)
+    (elem (i32.const 0) $add_one)
+    (func $dead (param i32) (result i32)
+        (call $also_dead (local.get 0)))
+    (func $also_dead (param i32) (result i32)
+        (call $dead (local.get 0)))
+    (func $add_one (param i32) (result i32)
+        (i32.add (local.get 0) (i32.const 1)))
+    (func $run (export "run") (param i32) (result i32)
+        (call $double
+            (call_indirect (type $unary) (local.get 0) (i32.const 0))))
+    (func $add_two (export "add_two") (param i32) (result i32)
+        (call $add_one (call $add_one (local.get 0))))
+)"#;
+    let module = Module::new(&store, wat)?;
+    // The imported function and the three live ones are left.
+    assert_eq!(module.info().functions.len(), 4);
+
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "double" => Function::new_native(&store, |x: i32| x * 2),
+            },
+        },
+    )?;
+    let run = instance.exports.get_native_function::<i32, i32>("run")?;
+    assert_eq!(run.call(20)?, 42);
+    let add_two = instance
+        .exports
+        .get_native_function::<i32, i32>("add_two")?;
+    assert_eq!(add_two.call(40)?, 42);
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Tests for the debug information kept in the compiled modules with each
+//! `DebugInfoPolicy`.
+
+use crate::utils::get_store_with_debug_info_policy;
+use anyhow::Result;
+use wasmer::*;
+
+const POLICIES: [DebugInfoPolicy; 3] = [
+    DebugInfoPolicy::KeepAll,
+    DebugInfoPolicy::NamesOnly,
+    DebugInfoPolicy::StripAll,
+];
+
+/// A module with a name section and the custom sections of a debug
+/// build: DWARF sections, and a `producers` section.
+fn debug_module() -> Result<Vec<u8>> {
+    let mut wasm = wat2wasm(
+        br#"(module
+    (func $trap_here unreachable)
+    (func (export "run") (call $trap_here)))"#,
+    )?
+    .into_owned();
+    for (name, size) in &[
+        (".debug_info", 2048),
+        (".debug_line", 1024),
+        ("producers", 16),
+    ] {
+        let mut section = vec![name.len() as u8];
+        section.extend_from_slice(name.as_bytes());
+        section.resize(section.len() + size, 0xab);
+        // The sizes are encoded in two bytes of LEB128.
+        wasm.push(0);
+        wasm.extend_from_slice(&[
+            0x80 | (section.len() & 0x7f) as u8,
+            (section.len() >> 7) as u8,
+        ]);
+        wasm.extend_from_slice(&section);
+    }
+    Ok(wasm)
+}
+
+#[test]
+fn artifact_sizes() -> Result<()> {
+    let wasm = debug_module()?;
+    let mut sizes = Vec::new();
+    for &policy in &POLICIES {
+        let store = get_store_with_debug_info_policy(policy);
+        let module = Module::new(&store, &wasm)?;
+        assert_eq!(module.info().debug_info_policy, policy);
+        assert_eq!(
+            module.custom_sections(".debug_info").count(),
+            (policy == DebugInfoPolicy::KeepAll) as usize
+        );
+        assert_eq!(module.custom_sections("producers").count(), 1);
+
+        let serialized = module.serialize()?;
+        let module = unsafe { Module::deserialize(&store, &serialized) }?;
+        assert_eq!(module.info().debug_info_policy, policy);
+        sizes.push(serialized.len());
+    }
+    // The DWARF sections are dropped, then the names.
+    assert!(sizes[0] > sizes[1] + 3072, "{:?}", sizes);
+    assert!(sizes[1] > sizes[2], "{:?}", sizes);
+    Ok(())
+}
+
+#[test]
+fn backtrace_names() -> Result<()> {
+    let wasm = debug_module()?;
+    for &policy in &POLICIES {
+        let store = get_store_with_debug_info_policy(policy);
+        let module = Module::new(&store, &wasm)?;
+        let instance = Instance::new(&module, &imports! {})?;
+        let run = instance.exports.get_function("run")?;
+        let error = run.call(&[]).unwrap_err();
+        let trace = error.trace();
+        assert_eq!(trace.len(), 2);
+        assert_eq!(trace[0].func_index(), 0);
+        if policy == DebugInfoPolicy::StripAll {
+            assert_eq!(trace[0].function_name(), None);
+        } else {
+            assert_eq!(trace[0].function_name(), Some("trap_here"));
+        }
+        // The exported function is named by its export either way.
+        assert_eq!(trace[1].func_index(), 1);
+        assert_eq!(trace[1].function_name(), Some("run"));
+    }
+    Ok(())
+}
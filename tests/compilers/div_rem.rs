@@ -0,0 +1,144 @@
+//! Execution tests for the integer division and remainder operators
+//! at their edge values, checking the exact result or trap reported.
+
+use crate::utils::get_store;
+use anyhow::Result;
+use wasmer::*;
+
+const DIVIDE_BY_ZERO: &str = "integer divide by zero";
+const OVERFLOW: &str = "integer overflow";
+
+fn instance() -> Result<Instance> {
+    let store = get_store(false);
+    let mut wat = String::from("(module\n");
+    for ty in &["i32", "i64"] {
+        for op in &["div_s", "div_u", "rem_s", "rem_u"] {
+            wat.push_str(&format!(
+                "(func (export \"{ty}.{op}\") (param {ty} {ty}) (result {ty}) \
+                 ({ty}.{op} (local.get 0) (local.get 1)))\n",
+                ty = ty,
+                op = op
+            ));
+        }
+    }
+    wat.push(')');
+    let module = Module::new(&store, wat)?;
+    Ok(Instance::new(&module, &imports! {})?)
+}
+
+fn check<T, F>(
+    instance: &Instance,
+    name: &str,
+    edges: &[T],
+    from_val: fn(&Val) -> Option<T>,
+    expected: F,
+) -> Result<()>
+where
+    T: Copy + std::fmt::Debug + PartialEq + Into<Val>,
+    F: Fn(T, T) -> std::result::Result<T, &'static str>,
+{
+    let f = instance.exports.get_function(name)?;
+    for &lhs in edges {
+        for &rhs in edges {
+            let actual = f
+                .call(&[lhs.into(), rhs.into()])
+                .map(|results| from_val(&results[0]).unwrap())
+                .map_err(|e| e.message());
+            let expected = expected(lhs, rhs).map_err(String::from);
+            assert_eq!(actual, expected, "{}({:?}, {:?})", name, lhs, rhs);
+        }
+    }
+    Ok(())
+}
+
+fn trap_on<T>(rhs_is_zero: bool, result: Option<T>) -> std::result::Result<T, &'static str> {
+    match result {
+        _ if rhs_is_zero => Err(DIVIDE_BY_ZERO),
+        Some(value) => Ok(value),
+        None => Err(OVERFLOW),
+    }
+}
+
+const I32_EDGES: &[i32] = &[0, 1, -1, 2, -2, 7, -7, i32::MIN, i32::MIN + 1, i32::MAX];
+const I64_EDGES: &[i64] = &[0, 1, -1, 2, -2, 7, -7, i64::MIN, i64::MIN + 1, i64::MAX];
+
+#[test]
+#[cfg_attr(feature = "test-singlepass", ignore)]
+fn i32_div_rem() -> Result<()> {
+    let instance = instance()?;
+    check(&instance, "i32.div_s", I32_EDGES, Val::i32, |a, b| {
+        trap_on(b == 0, a.checked_div(b))
+    })?;
+    check(&instance, "i32.div_u", I32_EDGES, Val::i32, |a, b| {
+        trap_on(b == 0, (a as u32).checked_div(b as u32).map(|r| r as i32))
+    })?;
+    // `rem_s` of the minimum value by -1 doesn't trap, the result is 0.
+    check(&instance, "i32.rem_s", I32_EDGES, Val::i32, |a, b| {
+        trap_on(b == 0, Some(if b == 0 { 0 } else { a.wrapping_rem(b) }))
+    })?;
+    check(&instance, "i32.rem_u", I32_EDGES, Val::i32, |a, b| {
+        trap_on(b == 0, (a as u32).checked_rem(b as u32).map(|r| r as i32))
+    })?;
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(feature = "test-singlepass", ignore)]
+fn i64_div_rem() -> Result<()> {
+    let instance = instance()?;
+    check(&instance, "i64.div_s", I64_EDGES, Val::i64, |a, b| {
+        trap_on(b == 0, a.checked_div(b))
+    })?;
+    check(&instance, "i64.div_u", I64_EDGES, Val::i64, |a, b| {
+        trap_on(b == 0, (a as u64).checked_div(b as u64).map(|r| r as i64))
+    })?;
+    // `rem_s` of the minimum value by -1 doesn't trap, the result is 0.
+    check(&instance, "i64.rem_s", I64_EDGES, Val::i64, |a, b| {
+        trap_on(b == 0, Some(if b == 0 { 0 } else { a.wrapping_rem(b) }))
+    })?;
+    check(&instance, "i64.rem_u", I64_EDGES, Val::i64, |a, b| {
+        trap_on(b == 0, (a as u64).checked_rem(b as u64).map(|r| r as i64))
+    })?;
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(feature = "test-singlepass", ignore)]
+fn div_rem_constant_operands() -> Result<()> {
+    let store = get_store(false);
+    let wat = r#"
+        (module
+            (func (export "i32.div_s") (result i32)
+                (i32.div_s (i32.const 0x80000000) (i32.const -1)))
+            (func (export "i32.rem_s") (result i32)
+                (i32.rem_s (i32.const 0x80000000) (i32.const -1)))
+            (func (export "i32.div_u") (result i32)
+                (i32.div_u (i32.const 0x80000000) (i32.const 0)))
+            (func (export "i64.div_s") (result i64)
+                (i64.div_s (i64.const 0x8000000000000000) (i64.const -1)))
+            (func (export "i64.rem_s") (result i64)
+                (i64.rem_s (i64.const 0x8000000000000000) (i64.const -1)))
+            (func (export "i64.rem_u") (result i64)
+                (i64.rem_u (i64.const 0x8000000000000000) (i64.const 0)))
+        )
+    "#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let call = |name: &str| {
+        instance
+            .exports
+            .get_function(name)
+            .unwrap()
+            .call(&[])
+            .map(|results| results[0].clone())
+            .map_err(|e| e.message())
+    };
+
+    assert_eq!(call("i32.div_s"), Err(OVERFLOW.to_string()));
+    assert_eq!(call("i32.rem_s"), Ok(Val::I32(0)));
+    assert_eq!(call("i32.div_u"), Err(DIVIDE_BY_ZERO.to_string()));
+    assert_eq!(call("i64.div_s"), Err(OVERFLOW.to_string()));
+    assert_eq!(call("i64.rem_s"), Ok(Val::I64(0)));
+    assert_eq!(call("i64.rem_u"), Err(DIVIDE_BY_ZERO.to_string()));
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! Dropping the store, the engine, the modules, the instances and the
+//! host functions in any order must be safe.
+
+use crate::utils::{get_engine, get_store};
+use anyhow::Result;
+use std::cell::RefCell;
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use std::sync::Arc;
+use std::thread;
+use wasmer::*;
+use wasmer_engine::Engine;
+
+static WAT: &str = r#"
+(module
+  (import "host" "count" (func $count))
+  (func (export "add") (param i32 i32) (result i32)
+    (call $count)
+    (i32.add (local.get 0) (local.get 1))))
+"#;
+
+fn instantiate(store: &Store, counter: &Arc<AtomicUsize>) -> Result<Instance> {
+    let module = Module::new(store, WAT)?;
+    let import_object = imports! {
+        "host" => {
+            "count" => Function::new_native_with_env(store, counter.clone(), |counter: &mut Arc<AtomicUsize>| {
+                counter.fetch_add(1, SeqCst);
+            }),
+        },
+    };
+    Ok(Instance::new(&module, &import_object)?)
+}
+
+#[test]
+fn instance_outlives_its_module_store_and_imports() -> Result<()> {
+    let counter = Arc::new(AtomicUsize::new(0));
+    // The module, the store and the import object are dropped when
+    // `instantiate` returns.
+    let instance = instantiate(&get_store(false), &counter)?;
+    let add = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("add")?;
+    assert_eq!(add.call(3, 4)?, 7);
+    assert_eq!(counter.load(SeqCst), 1);
+    Ok(())
+}
+
+#[test]
+fn exports_outlive_their_instance() -> Result<()> {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let add = {
+        let instance = instantiate(&get_store(false), &counter)?;
+        instance.exports.get_function("add")?.clone()
+    };
+    assert_eq!(
+        add.call(&[Val::I32(3), Val::I32(4)])?.to_vec(),
+        vec![Val::I32(7)]
+    );
+    assert_eq!(counter.load(SeqCst), 1);
+    Ok(())
+}
+
+#[test]
+fn engine_dropped_first() -> Result<()> {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let engine = get_engine(false);
+    let store = Store::new(&engine);
+    let instance = instantiate(&store, &counter)?;
+    drop(engine);
+    drop(store);
+    let add = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("add")?;
+    assert_eq!(add.call(3, 4)?, 7);
+    Ok(())
+}
+
+#[test]
+fn artifact_outlives_its_engine() -> Result<()> {
+    let engine = get_engine(false);
+    let tunables = Tunables::for_target(engine.target());
+    let serialized = engine
+        .compile(&wat2wasm(WAT.as_bytes())?, &tunables)?
+        .serialize()?;
+    let artifact = unsafe { engine.deserialize(&serialized)? };
+    drop(engine);
+    // The code of the functions must still be mapped.
+    for body in artifact.finished_functions().values() {
+        let body = unsafe { &***body };
+        let code = unsafe { std::slice::from_raw_parts(body.as_ptr() as *const u8, body.len()) };
+        assert!(code.iter().any(|&byte| byte != 0));
+    }
+    Ok(())
+}
+
+#[test]
+fn forgotten_handles() -> Result<()> {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let store = get_store(false);
+    let instance = instantiate(&store, &counter)?;
+    mem::forget(store);
+    mem::forget(instance.clone());
+    let add = instance.exports.get_function("add")?.clone();
+    drop(instance);
+    assert_eq!(add.native::<(i32, i32), i32>()?.call(3, 4)?, 7);
+    mem::forget(add);
+    Ok(())
+}
+
+thread_local! {
+    static INSTANCE: RefCell<Option<Instance>> = RefCell::new(None);
+}
+
+#[test]
+fn instance_in_a_thread_local_dropped_at_exit() -> Result<()> {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let thread_counter = counter.clone();
+    thread::spawn(move || -> Result<()> {
+        let instance = instantiate(&get_store(false), &thread_counter)?;
+        let add = instance
+            .exports
+            .get_native_function::<(i32, i32), i32>("add")?;
+        assert_eq!(add.call(3, 4)?, 7);
+        // The instance is dropped with the thread locals, after everything
+        // else in the thread.
+        INSTANCE.with(|slot| *slot.borrow_mut() = Some(instance));
+        Ok(())
+    })
+    .join()
+    .unwrap()?;
+    assert_eq!(counter.load(SeqCst), 1);
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use crate::utils::get_store_with_features;
+use anyhow::Result;
+use wasmer::*;
+
+fn extended_const_store() -> Store {
+    let mut features = Features::default();
+    features.extended_const(true);
+    get_store_with_features(features)
+}
+
+#[test]
+fn data_segment_offset_expression() -> Result<()> {
+    let store = extended_const_store();
+    let wat = r#"(module
+    (global $base (import "env" "base") i32)
+    (memory (export "memory") 1)
+    (data (offset (i32.add (global.get $base) (i32.const 16))) "\2a\2b")
+)"#;
+    let module = Module::new(&store, wat)?;
+    // The offset reads a global, so it's evaluated at instantiation.
+    assert!(module.info().global_initializers.is_empty());
+
+    for base in &[0, 100] {
+        let import_object = imports! {
+            "env" => {
+                "base" => Global::new(&store, Value::I32(*base)),
+            },
+        };
+        let instance = Instance::new(&module, &import_object)?;
+        let memory = instance.exports.get_memory("memory")?;
+        let view = memory.view::<u8>();
+        let start = *base as usize + 16;
+        assert_eq!(view[start - 1].get(), 0);
+        assert_eq!(view[start].get(), 0x2a);
+        assert_eq!(view[start + 1].get(), 0x2b);
+        assert_eq!(view[start + 2].get(), 0);
+    }
+    Ok(())
+}
+
+#[test]
+fn global_and_element_expressions() -> Result<()> {
+    let store = extended_const_store();
+    let wat = r#"(module
+    (global $base (import "env" "base") i64)
+    (global $small (import "env" "small") i32)
+    (global (export "scaled") i64
+        (i64.sub (i64.mul (global.get $base) (i64.const 3)) (i64.const 1)))
+    (global (export "wrapped") i32
+        (i32.add (i32.const 0x7fffffff) (i32.const 2)))
+    (table (export "table") 4 funcref)
+    (elem (offset (i32.mul (global.get $small) (i32.const 2))) $f)
+    (func $f (result i32) (i32.const 7))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let import_object = imports! {
+        "env" => {
+            "base" => Global::new(&store, Value::I64(5)),
+            "small" => Global::new(&store, Value::I32(1)),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    assert_eq!(instance.exports.get_global("scaled")?.get(), Value::I64(14));
+    assert_eq!(
+        instance.exports.get_global("wrapped")?.get(),
+        Value::I32(i32::min_value() + 1)
+    );
+    let table = instance.exports.get_table("table")?;
+    assert!(matches!(
+        table.get(1),
+        Some(Value::ExternRef(ExternRef::Null))
+    ));
+    let element = table.get(2).unwrap();
+    let f = element.unwrap_funcref();
+    assert_eq!(f.call(&[])?.to_vec(), vec![Value::I32(7)]);
+    Ok(())
+}
+
+#[test]
+fn extended_const_requires_the_feature() -> Result<()> {
+    let store = get_store_with_features(Features::default());
+    let wat = r#"(module
+    (global $base (import "env" "base") i32)
+    (memory 1)
+    (data (offset (i32.add (global.get $base) (i32.const 16))) "\2a")
+)"#;
+    match Module::new(&store, wat).unwrap_err() {
+        CompileError::UnsupportedFeature { feature, .. } => assert_eq!(feature, "extended const"),
+        e => panic!("unexpected error: {}", e),
+    }
+
+    // Extended expressions are still type checked when the feature is on.
+    let store = extended_const_store();
+    let wat = r#"(module
+    (global $base (import "env" "base") i32)
+    (memory 1)
+    (data (offset (i64.add (global.get $base) (i64.const 16))) "\2a")
+)"#;
+    assert!(Module::new(&store, wat).is_err());
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use crate::utils::get_engine;
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use wasmer::*;
+
+/// A hook allowing the first `allowed` growths, and recording them all.
+fn scripted_hook(
+    allowed: usize,
+    calls: Arc<Mutex<Vec<(u32, u32)>>>,
+) -> impl Fn(u32, u32) -> GrowDecision + Send + Sync + 'static {
+    move |current, delta| {
+        let mut calls = calls.lock().unwrap();
+        calls.push((current, delta));
+        if calls.len() <= allowed {
+            GrowDecision::Allow
+        } else {
+            GrowDecision::Deny
+        }
+    }
+}
+
+#[test]
+fn memory_grow_hook() -> Result<()> {
+    let engine = get_engine(false);
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let hook = scripted_hook(3, calls.clone());
+    let tunables = Tunables::for_target(engine.target())
+        .with_memory_grow_hook(move |current, delta| hook(current.0, delta.0));
+    let store = Store::new_with_tunables(&engine, tunables);
+
+    // Grows the memory one page at a time until it fails, returning the
+    // number of successful growths.
+    let grow_until_failure = r#"
+    (func (export "grow_until_failure") (result i32) (local $grown i32)
+        (block $failed
+            (loop $grow
+                (br_if $failed (i32.eq (memory.grow (i32.const 1)) (i32.const -1)))
+                (local.set $grown (i32.add (local.get $grown) (i32.const 1)))
+                (br $grow)))
+        (local.get $grown))"#;
+
+    // A memory defined by the instance.
+    let module = Module::new(
+        &store,
+        format!("(module (memory 1) {})", grow_until_failure),
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let grow: NativeFunc<(), i32> = instance.exports.get_native_function("grow_until_failure")?;
+    assert_eq!(grow.call()?, 3);
+    assert_eq!(*calls.lock().unwrap(), [(1, 1), (2, 1), (3, 1), (4, 1)]);
+
+    // A memory imported by the instance, which is denied to the host too.
+    calls.lock().unwrap().clear();
+    let memory = Memory::new(&store, MemoryType::new(Pages(2), None, false))?;
+    let module = Module::new(
+        &store,
+        format!(
+            r#"(module (import "env" "memory" (memory 1)) {})"#,
+            grow_until_failure
+        ),
+    )?;
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "memory" => memory.clone(),
+            },
+        },
+    )?;
+    let grow: NativeFunc<(), i32> = instance.exports.get_native_function("grow_until_failure")?;
+    assert_eq!(grow.call()?, 3);
+    assert!(memory.grow(Pages(2)).is_err());
+    assert_eq!(memory.size(), Pages(5));
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [(2, 1), (3, 1), (4, 1), (5, 1), (5, 2)]
+    );
+
+    Ok(())
+}
+
+// No compiler supports the `table.grow` instruction yet, so the tables are
+// only grown by the host.
+#[test]
+fn table_grow_hook() -> Result<()> {
+    let engine = get_engine(false);
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let tunables =
+        Tunables::for_target(engine.target()).with_table_grow_hook(scripted_hook(1, calls.clone()));
+    let store = Store::new_with_tunables(&engine, tunables);
+
+    let table_type = TableType::new(Type::FuncRef, 1, None);
+    let f = Function::new_native(&store, || {});
+    let table = Table::new(&store, table_type, Val::FuncRef(f.clone()))?;
+    assert_eq!(table.grow(2, Val::FuncRef(f.clone()))?, 1);
+    assert!(table.grow(2, Val::FuncRef(f)).is_err());
+    assert_eq!(table.size(), 3);
+    assert_eq!(*calls.lock().unwrap(), [(1, 2), (3, 2)]);
+
+    Ok(())
+}
@@ -272,3 +272,71 @@ fn static_function_that_fails() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn imported_const_global_is_read_per_instance() -> Result<()> {
+    let store = get_store(false);
+    let wat = r#"
+        (import "host" "g" (global $g i32))
+        (memory 1)
+        (data (i32.const 0) "\01\00\00\00\02\00\00\00")
+        (type $t (func (result i32)))
+        (table 2 funcref)
+        (elem (i32.const 0) $one $two)
+        (func $one (result i32) i32.const 1)
+        (func $two (result i32) i32.const 2)
+
+        (func (export "get") (result i32)
+            global.get $g
+        )
+        (func (export "load") (result i32)
+            global.get $g
+            i32.const 4
+            i32.mul
+            i32.load
+        )
+        (func (export "branch") (result i32)
+            (if (result i32) (global.get $g)
+                (then i32.const 10)
+                (else i32.const 20)
+            )
+        )
+        (func (export "call_indirect") (result i32)
+            (call_indirect (type $t) (global.get $g))
+        )
+    "#;
+
+    let module = Module::new(&store, &wat)?;
+    let instantiate = |value: i32| {
+        Instance::new(
+            &module,
+            &imports! {
+                "host" => {
+                    "g" => Global::new(&store, Value::I32(value)),
+                },
+            },
+        )
+    };
+    let first = instantiate(0)?;
+    let second = instantiate(1)?;
+
+    let call = |instance: &Instance, name: &str| -> Result<i32> {
+        let f: NativeFunc<(), i32> = instance.exports.get_native_function(name)?;
+        Ok(f.call()?)
+    };
+
+    // Interleave the calls so that nothing computed for one instance can
+    // be reused for the other.
+    for _ in 0..2 {
+        assert_eq!(call(&first, "get")?, 0);
+        assert_eq!(call(&second, "get")?, 1);
+        assert_eq!(call(&first, "load")?, 1);
+        assert_eq!(call(&second, "load")?, 2);
+        assert_eq!(call(&first, "branch")?, 20);
+        assert_eq!(call(&second, "branch")?, 10);
+        assert_eq!(call(&first, "call_indirect")?, 1);
+        assert_eq!(call(&second, "call_indirect")?, 2);
+    }
+
+    Ok(())
+}
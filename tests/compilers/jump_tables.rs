@@ -0,0 +1,49 @@
+//! Only Cranelift emits the jump tables of `br_table` in the function body.
+#![cfg(feature = "test-cranelift")]
+
+use crate::utils::get_compiler;
+use anyhow::Result;
+use wasmer::{wat2wasm, Features, Target, Tunables};
+use wasmer_compiler::{CompilationProgress, CompilerConfig};
+use wasmer_engine::Tunables as BaseTunables;
+use wasmer_types::LocalFunctionIndex;
+
+#[test]
+fn br_table_jump_table_entries() -> Result<()> {
+    let wasm = wat2wasm(
+        br#"(module
+    (func (export "select") (param i32) (result i32)
+        (block
+            (block
+                (block
+                    (block
+                        (block
+                            (br_table 0 1 2 3 4 (local.get 0)))
+                        (return (i32.const 10)))
+                    (return (i32.const 11)))
+                (return (i32.const 12)))
+            (return (i32.const 13)))
+        (i32.const 14)))"#,
+    )?;
+    let target = Target::default();
+    let tunables = Tunables::for_target(&target);
+    let compiled = get_compiler(false).compiler().compile_wasm(
+        &target,
+        &wasm,
+        &Features::default(),
+        &|memory| tunables.memory_style(memory),
+        &|table| tunables.table_style(table),
+        &CompilationProgress::new(),
+    )?;
+
+    let function = compiled.compilation.get(LocalFunctionIndex::from_u32(0));
+    let jump_tables = function.jump_tables().collect::<Vec<_>>();
+    assert_eq!(jump_tables.len(), 1);
+    let (_, entries) = jump_tables[0];
+    // The default target isn't part of the table.
+    assert_eq!(entries.len(), 4);
+    let body_len = function.body.body.len() as u32;
+    assert!(entries.iter().all(|&offset| offset < body_len));
+
+    Ok(())
+}
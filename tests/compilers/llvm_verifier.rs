@@ -0,0 +1,53 @@
+//! Checks that the LLVM IR verifier reports a malformed function as a
+//! compilation error.
+
+#![cfg(feature = "test-llvm")]
+
+use std::sync::Arc;
+use wasmer::*;
+use wasmer_compiler_llvm::{CompiledKind, InkwellMemoryBuffer, InkwellModule, LLVMCallbacks, LLVM};
+#[cfg(feature = "test-jit")]
+use wasmer_engine_jit::JIT;
+#[cfg(feature = "test-native")]
+use wasmer_engine_native::Native;
+
+/// Appends a basic block without a terminator to the local functions,
+/// which is invalid IR.
+#[derive(Debug)]
+struct BreakLocalFunctions;
+
+impl LLVMCallbacks for BreakLocalFunctions {
+    fn preopt_ir(&self, function: &CompiledKind, module: &InkwellModule) {
+        if let CompiledKind::Local(_) = function {
+            let function = module.get_first_function().unwrap();
+            module.get_context().append_basic_block(function, "broken");
+        }
+    }
+
+    fn postopt_ir(&self, _function: &CompiledKind, _module: &InkwellModule) {}
+
+    fn obj_memory_buffer(&self, _function: &CompiledKind, _memory_buffer: &InkwellMemoryBuffer) {}
+}
+
+#[test]
+fn verifier_error() {
+    let mut compiler_config = LLVM::new();
+    compiler_config
+        .verifier(true)
+        .callbacks(Some(Arc::new(BreakLocalFunctions)));
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(&compiler_config).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(&mut compiler_config).engine();
+    let store = Store::new(&engine);
+    let wat = r#"
+        (module
+          (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))))
+    "#;
+    match Module::new(&store, wat) {
+        Err(CompileError::Codegen(message)) => assert!(!message.is_empty()),
+        Err(other) => panic!("expected a codegen error, got {}", other),
+        Ok(_) => panic!("the malformed function was compiled"),
+    }
+}
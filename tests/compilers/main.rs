@@ -4,12 +4,26 @@
 //! implementation, such as: singlepass, cranelift or llvm depending
 //! on what's available on the target.
 
+mod compile_function;
+mod dead_functions;
+mod debug_info;
+mod div_rem;
+mod drop_order;
+mod extended_const;
+mod grow_hooks;
 mod imports;
+mod jump_tables;
+mod llvm_verifier;
 mod middlewares;
 mod multi_value_imports;
+mod nan_canonicalization;
 mod native_functions;
+mod parallel_compilation;
+mod recompile;
 mod serialize;
+mod signatures;
 mod traps;
+mod unchecked;
 mod utils;
 mod wasi;
 mod wast;
@@ -2,12 +2,12 @@ use crate::utils::get_store_with_middlewares;
 use anyhow::Result;
 
 use std::sync::Arc;
-use wasmer::wasmparser::{Operator, Result as WpResult};
+use wasmer::wasmparser::Operator;
 use wasmer::*;
 
 #[derive(Debug)]
-struct Add2MulGen {
-    value_off: i32,
+pub(crate) struct Add2MulGen {
+    pub(crate) value_off: i32,
 }
 
 #[derive(Debug)]
@@ -28,7 +28,7 @@ impl FunctionMiddleware for Add2Mul {
         &mut self,
         operator: Operator<'a>,
         state: &mut MiddlewareReaderState<'a>,
-    ) -> WpResult<()> {
+    ) -> Result<(), MiddlewareError> {
         match operator {
             Operator::I32Add => {
                 state.push_operator(Operator::I32Mul);
@@ -66,7 +66,7 @@ impl FunctionMiddleware for Fusion {
         &mut self,
         operator: Operator<'a>,
         state: &mut MiddlewareReaderState<'a>,
-    ) -> WpResult<()> {
+    ) -> Result<(), MiddlewareError> {
         match (operator, self.state) {
             (Operator::I32Add, 0) => {
                 self.state = 1;
@@ -157,6 +157,66 @@ fn middleware_multi_to_one() -> Result<()> {
     Ok(())
 }
 
+#[derive(Debug)]
+struct RejectFloatsGen;
+
+#[derive(Debug)]
+struct RejectFloats;
+
+impl FunctionMiddlewareGenerator for RejectFloatsGen {
+    fn generate<'a>(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        Box::new(RejectFloats)
+    }
+}
+
+impl FunctionMiddleware for RejectFloats {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        match operator {
+            Operator::F32Add | Operator::F64Add => Err(MiddlewareError::new(
+                "RejectFloats",
+                "floating point operations are not allowed",
+            )),
+            operator => {
+                state.push_operator(operator);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[test]
+fn middleware_rejects_module() -> Result<()> {
+    let store = get_store_with_middlewares(
+        vec![Arc::new(RejectFloatsGen) as Arc<dyn FunctionMiddlewareGenerator>].into_iter(),
+    );
+    let wat = r#"(module
+        (func (export "add") (param f64 f64) (result f64)
+           (f64.add (local.get 0)
+                    (local.get 1)))
+)"#;
+    match Module::new(&store, wat) {
+        Err(CompileError::Middleware(error)) => {
+            assert_eq!(error.name, "RejectFloats");
+            assert_eq!(error.message, "floating point operations are not allowed");
+        }
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("the middleware didn't reject the module"),
+    }
+
+    // Modules without floating point operations are still accepted.
+    let wat = r#"(module
+        (func (export "add") (param i32 i32) (result i32)
+           (i32.add (local.get 0)
+                    (local.get 1)))
+)"#;
+    Module::new(&store, wat)?;
+    Ok(())
+}
+
 #[test]
 fn middleware_chain_order_1() -> Result<()> {
     let store = get_store_with_middlewares(
@@ -0,0 +1,86 @@
+//! Checks the bit patterns of the NaNs produced by the floating-point
+//! arithmetic, with and without NaN canonicalization.
+
+use crate::utils::get_store;
+use anyhow::Result;
+use wasmer::*;
+
+const CANONICAL_F32: u32 = 0x7fc0_0000;
+const CANONICAL_F64: u64 = 0x7ff8_0000_0000_0000;
+
+// The payloads of signalling NaNs, so that the quiet bit differs from the
+// canonical NaN too.
+const SIGNALLING_F32: u32 = 0xffa0_0001;
+const SIGNALLING_F64: u64 = 0xfff4_0000_0000_0001;
+
+fn instance(canonicalize_nans: bool) -> Result<Instance> {
+    let store = get_store(canonicalize_nans);
+    // The NaNs go through integers so that their bits can't be changed
+    // outside of the addition.
+    let wat = r#"
+        (module
+          (func (export "f32.add") (param i32) (result i32)
+            (i32.reinterpret_f32
+              (f32.add (f32.reinterpret_i32 (local.get 0)) (f32.const 1))))
+          (func (export "f64.add") (param i64) (result i64)
+            (i64.reinterpret_f64
+              (f64.add (f64.reinterpret_i64 (local.get 0)) (f64.const 1)))))
+    "#;
+    let module = Module::new(&store, wat)?;
+    Ok(Instance::new(&module, &imports! {})?)
+}
+
+#[test]
+fn canonical_nans() -> Result<()> {
+    let instance = instance(true)?;
+    let f32_add = instance
+        .exports
+        .get_native_function::<i32, i32>("f32.add")?;
+    let f64_add = instance
+        .exports
+        .get_native_function::<i64, i64>("f64.add")?;
+    assert_eq!(f32_add.call(SIGNALLING_F32 as i32)? as u32, CANONICAL_F32);
+    assert_eq!(f64_add.call(SIGNALLING_F64 as i64)? as u64, CANONICAL_F64);
+    // The numbers are unchanged.
+    assert_eq!(f32_add.call(1f32.to_bits() as i32)? as u32, 2f32.to_bits());
+    assert_eq!(f64_add.call(1f64.to_bits() as i64)? as u64, 2f64.to_bits());
+    Ok(())
+}
+
+#[test]
+fn non_canonical_nans() -> Result<()> {
+    let instance = instance(false)?;
+    let f32_add = instance
+        .exports
+        .get_native_function::<i32, i32>("f32.add")?;
+    let f64_add = instance
+        .exports
+        .get_native_function::<i64, i64>("f64.add")?;
+    let nan = f32_add.call(SIGNALLING_F32 as i32)? as u32;
+    assert!(f32::from_bits(nan).is_nan());
+    assert_ne!(nan, CANONICAL_F32);
+    let nan = f64_add.call(SIGNALLING_F64 as i64)? as u64;
+    assert!(f64::from_bits(nan).is_nan());
+    assert_ne!(nan, CANONICAL_F64);
+    Ok(())
+}
+
+#[cfg(feature = "test-cranelift")]
+#[test]
+fn cranelift_builder_flags() {
+    use wasmer_compiler_cranelift::{Cranelift, OptLevel};
+
+    let mut config = Cranelift::new();
+    config
+        .opt_level(OptLevel::SpeedAndSize)
+        .canonicalize_nans(true)
+        .verifier(true);
+    let flags = config.flags();
+    assert!(flags.enable_nan_canonicalization());
+    assert!(flags.enable_verifier());
+
+    config.canonicalize_nans(false).verifier(false);
+    let flags = config.flags();
+    assert!(!flags.enable_nan_canonicalization());
+    assert!(!flags.enable_verifier());
+}
@@ -1,4 +1,4 @@
-use crate::utils::get_store;
+use crate::utils::{get_store, get_store_with_features};
 use anyhow::Result;
 use std::cell::RefCell;
 use std::convert::Infallible;
@@ -50,6 +50,72 @@ fn native_function_works_for_wasm() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg_attr(feature = "test-singlepass", ignore)]
+fn native_function_with_many_results() -> Result<()> {
+    type Results = (i64, i64, i64, i64, i64, i64, i64, i64);
+
+    let store = get_store(false);
+    let wat = r#"(module
+        (func (export "spread") (param i64) (result i64 i64 i64 i64 i64 i64 i64 i64)
+           (local.get 0)
+           (i64.add (local.get 0) (i64.const 1))
+           (i64.add (local.get 0) (i64.const 2))
+           (i64.add (local.get 0) (i64.const 3))
+           (i64.add (local.get 0) (i64.const 4))
+           (i64.add (local.get 0) (i64.const 5))
+           (i64.add (local.get 0) (i64.const 6))
+           (i64.add (local.get 0) (i64.const 7)))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let f: NativeFunc<i64, Results> = instance.exports.get_native_function("spread")?;
+
+    assert_eq!(f.call(10)?, (10, 11, 12, 13, 14, 15, 16, 17));
+
+    let mut area = [0i128; 8];
+    unsafe { f.call_with_result_area(20, area.as_mut_ptr() as *mut u8)? };
+    assert_eq!(area, [20, 21, 22, 23, 24, 25, 26, 27]);
+
+    fn spread(a: i64) -> Results {
+        (a, a * 2, a * 3, a * 4, a * 5, a * 6, a * 7, a * 8)
+    }
+    let f = Function::new_native(&store, spread);
+    let f: NativeFunc<i64, Results> = f.native().unwrap();
+    assert_eq!(f.call(1)?, (1, 2, 3, 4, 5, 6, 7, 8));
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(feature = "test-singlepass", ignore)]
+fn native_function_with_v128() -> Result<()> {
+    let mut features = Features::default();
+    features.simd(true);
+    let store = get_store_with_features(features);
+    let wat = r#"(module
+        (func $xor (import "env" "xor") (param v128 v128) (result v128))
+        (func (export "xor_then_add") (param v128 v128) (result v128)
+           (i32x4.add (call $xor (local.get 0) (local.get 1))
+                      (v128.const i32x4 1 2 3 4)))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let import_object = imports! {
+        "env" => {
+            "xor" => Function::new_native(&store, |a: V128, b: V128| {
+                V128::from(u128::from(a) ^ u128::from(b))
+            }),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    let f: NativeFunc<(V128, V128), V128> = instance.exports.get_native_function("xor_then_add")?;
+
+    let result = f.call(V128::from(0b1100u128), V128::from(0b1010u128))?;
+    assert_eq!(result.as_i32x4(), [0b0111, 2, 3, 4]);
+
+    Ok(())
+}
+
 #[test]
 fn static_host_function_without_env() -> anyhow::Result<()> {
     let store = get_store(false);
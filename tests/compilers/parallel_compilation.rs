@@ -0,0 +1,50 @@
+#![cfg(feature = "test-cranelift")]
+
+use anyhow::Result;
+use wasmer::{wat2wasm, Features, Target, Tunables};
+use wasmer_compiler::{CompilationProgress, CompiledModule, CompilerConfig};
+use wasmer_compiler_cranelift::Cranelift;
+use wasmer_engine::Tunables as BaseTunables;
+
+fn compile(wasm: &[u8], parallel: bool) -> Result<CompiledModule> {
+    let mut config = Cranelift::new();
+    config.parallel_compilation(parallel);
+    let target = Target::default();
+    let tunables = Tunables::for_target(&target);
+    Ok(config.compiler().compile_wasm(
+        &target,
+        wasm,
+        &Features::default(),
+        &|memory| tunables.memory_style(memory),
+        &|table| tunables.table_style(table),
+        &CompilationProgress::new(),
+    )?)
+}
+
+#[test]
+fn parallel_compilation_is_deterministic() -> Result<()> {
+    // Each function calls the previous one, has a jump table and can trap,
+    // so that they all have relocations, jump tables and traps.
+    let mut wat = String::from("(module (func $f0 (param i32) (result i32) (local.get 0))");
+    for i in 1..300 {
+        wat.push_str(&format!(
+            r#"
+    (func $f{i} (export "f{i}") (param i32) (result i32)
+        (block
+            (block
+                (br_table 0 1 (local.get 0)))
+            (return (i32.div_u (call $f{previous} (local.get 0)) (local.get 0))))
+        (i32.const {i}))"#,
+            i = i,
+            previous = i - 1
+        ));
+    }
+    wat.push(')');
+    let wasm = wat2wasm(wat.as_bytes())?;
+
+    let parallel = compile(&wasm, true)?;
+    let sequential = compile(&wasm, false)?;
+    assert_eq!(parallel.compilation.len(), 300);
+    assert_eq!(parallel.compilation, sequential.compilation);
+    Ok(())
+}
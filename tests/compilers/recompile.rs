@@ -0,0 +1,126 @@
+#![cfg(feature = "test-jit")]
+
+use crate::middlewares::Add2MulGen;
+use crate::utils::{get_compiler, get_recompilable_store, get_store};
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use wasmer::*;
+use wasmer_compiler::CompilerConfig;
+use wasmer_engine_jit::JITArtifact;
+
+static WAT: &str = r#"
+(module
+  (func $add (export "add") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1)))
+  (func (export "add_through_call") (param i32 i32) (result i32)
+    (call $add (local.get 0) (local.get 1)))
+  (func (export "div") (param i32 i32) (result i32)
+    (i32.div_s (local.get 0) (local.get 1))))
+"#;
+
+fn jit_artifact(module: &Module) -> &JITArtifact {
+    module
+        .artifact()
+        .downcast_ref::<JITArtifact>()
+        .expect("the module should be compiled by the JIT engine")
+}
+
+#[test]
+fn recompile_function() -> Result<()> {
+    let store = get_recompilable_store();
+    let module = Module::new(&store, WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let add = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("add")?;
+    let add_through_call = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("add_through_call")?;
+    assert_eq!(add.call(3, 4)?, 7);
+    assert_eq!(add_through_call.call(3, 4)?, 7);
+
+    let mut config = get_compiler(false);
+    config.push_middleware(Arc::new(Add2MulGen { value_off: 0 }));
+    jit_artifact(&module).recompile_function(LocalFunctionIndex::from_u32(0), &config)?;
+
+    // The existing instance, the direct callers and new instances run the
+    // recompiled code.
+    assert_eq!(add.call(3, 4)?, 12);
+    assert_eq!(add_through_call.call(3, 4)?, 12);
+    let instance = Instance::new(&module, &imports! {})?;
+    let add = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("add")?;
+    assert_eq!(add.call(3, 4)?, 12);
+    Ok(())
+}
+
+#[test]
+fn recompiled_function_traps() -> Result<()> {
+    let store = get_recompilable_store();
+    let module = Module::new(&store, WAT)?;
+    let config = get_compiler(false);
+    jit_artifact(&module).recompile_function(LocalFunctionIndex::from_u32(2), &config)?;
+
+    let instance = Instance::new(&module, &imports! {})?;
+    let div = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("div")?;
+    assert_eq!(div.call(12, 4)?, 3);
+    let err = div.call(1, 0).unwrap_err();
+    assert_eq!(err.message(), "integer divide by zero");
+    Ok(())
+}
+
+#[test]
+fn recompile_missing_function() -> Result<()> {
+    let store = get_recompilable_store();
+    let module = Module::new(&store, WAT)?;
+    let config = get_compiler(false);
+    let result = jit_artifact(&module).recompile_function(LocalFunctionIndex::from_u32(3), &config);
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn recompile_without_recompilable_engine() -> Result<()> {
+    let store = get_store(false);
+    let module = Module::new(&store, WAT)?;
+    let config = get_compiler(false);
+    let result = jit_artifact(&module).recompile_function(LocalFunctionIndex::from_u32(0), &config);
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn recompile_function_while_called() -> Result<()> {
+    let store = get_recompilable_store();
+    let module = Module::new(&store, WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let done = Arc::new(AtomicBool::new(false));
+    let caller = {
+        let done = done.clone();
+        thread::spawn(move || {
+            let add_through_call = instance
+                .exports
+                .get_native_function::<(i32, i32), i32>("add_through_call")
+                .unwrap();
+            let mut recompiled = false;
+            while !done.load(Ordering::SeqCst) || !recompiled {
+                let result = add_through_call.call(3, 4).unwrap();
+                // Each call runs either the previous or the new code.
+                assert!(result == 7 || result == 12, "unexpected result {}", result);
+                recompiled = result == 12;
+            }
+        })
+    };
+
+    let mut config = get_compiler(false);
+    config.push_middleware(Arc::new(Add2MulGen { value_off: 0 }));
+    jit_artifact(&module).recompile_function(LocalFunctionIndex::from_u32(0), &config)?;
+    done.store(true, Ordering::SeqCst);
+    caller.join().unwrap();
+    Ok(())
+}
@@ -1,6 +1,8 @@
-use crate::utils::{get_headless_store, get_store};
+use crate::utils::{get_compiler, get_headless_store, get_store};
 use anyhow::Result;
 use wasmer::*;
+use wasmer_compiler::{Compilation, CompilationProgress, CompilerConfig};
+use wasmer_engine::Tunables as BaseTunables;
 
 #[test]
 fn test_serialize() -> Result<()> {
@@ -75,3 +77,63 @@ fn test_deserialize() -> Result<()> {
     assert_eq!(result.to_vec(), vec![Value::I64(1500)]);
     Ok(())
 }
+
+#[test]
+fn test_serialize_is_independent_of_the_number_of_threads() -> Result<()> {
+    // Enough functions, each with calls and traps, for the threads to
+    // finish them in different orders.
+    let mut wat = String::from("(module (import \"\" \"hello\" (func $hello)) (memory 1)");
+    for i in 0..64 {
+        wat.push_str(&format!(
+            r#"(func (export "f{}") (param i32 i32) (result i32)
+                call $hello
+                local.get 0
+                i32.load offset={}
+                local.get 1
+                i32.div_s
+                call {})"#,
+            i,
+            i * 4,
+            (i + 1) % 64 * 2 + 2,
+        ));
+        wat.push_str(&format!(
+            "(func (param i32) (result i32) local.get 0 i32.const {} i32.rem_u)",
+            i + 1
+        ));
+    }
+    wat.push(')');
+
+    let wasm = wat2wasm(wat.as_bytes())?;
+
+    // The compilation is compared too, as the serialized module may not
+    // hold all of it.
+    let compile_with_threads = |threads: usize| -> Result<(Compilation, Vec<u8>)> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()?;
+        pool.install(|| {
+            let target = Target::default();
+            let tunables = Tunables::for_target(&target);
+            let compiled = get_compiler(false).compiler().compile_wasm(
+                &target,
+                &wasm,
+                &Features::default(),
+                &|memory| tunables.memory_style(memory),
+                &|table| tunables.table_style(table),
+                &CompilationProgress::new(),
+            )?;
+
+            let store = get_store(false);
+            let module = Module::new(&store, &wasm)?;
+            Ok((compiled.compilation, module.serialize()?))
+        })
+    };
+
+    let (compilation, serialized_bytes) = compile_with_threads(1)?;
+    for _ in 0..4 {
+        let (other_compilation, other_serialized_bytes) = compile_with_threads(8)?;
+        assert_eq!(other_compilation, compilation);
+        assert_eq!(other_serialized_bytes, serialized_bytes);
+    }
+    Ok(())
+}
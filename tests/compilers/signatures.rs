@@ -0,0 +1,50 @@
+use crate::utils::get_store;
+use anyhow::Result;
+use wasmer::*;
+
+#[test]
+fn duplicate_signatures_are_shared() -> Result<()> {
+    let store = get_store(false);
+    let wat = r#"(module
+    (type $unary (func (param i32) (result i32)))
+    (type $nullary (func (result i32)))
+    (type $unary_again (func (param i32) (result i32)))
+    (type $nullary_again (func (result i32)))
+    (func $double (import "env" "double") (type $unary_again))
+    (table 2 funcref)
+    (elem (i32.const 0) $add_one $double)
+    (func $add_one (type $unary) (i32.add (local.get 0) (i32.const 1)))
+    (func $forty (type $nullary_again) (i32.const 40))
+    (func (export "run") (type $nullary)
+        (call_indirect (type $unary)
+            (call_indirect (type $unary_again) (call $forty) (i32.const 0))
+            (i32.const 0)))
+    (func (export "run_import") (type $unary_again)
+        (call_indirect (type $unary) (local.get 0) (i32.const 1)))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let info = module.info();
+    assert_eq!(info.signatures.len(), 2);
+    // The import, `$add_one`, `$forty` and the exports.
+    let signatures = info.functions.values().collect::<Vec<_>>();
+    assert_eq!(signatures[0], signatures[1]);
+    assert_eq!(signatures[0], signatures[4]);
+    assert_eq!(signatures[2], signatures[3]);
+    assert_ne!(signatures[0], signatures[2]);
+
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "double" => Function::new_native(&store, |x: i32| x * 2),
+            },
+        },
+    )?;
+    let run = instance.exports.get_native_function::<(), i32>("run")?;
+    assert_eq!(run.call()?, 42);
+    let run_import = instance
+        .exports
+        .get_native_function::<i32, i32>("run_import")?;
+    assert_eq!(run_import.call(21)?, 42);
+    Ok(())
+}
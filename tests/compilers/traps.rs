@@ -1,4 +1,8 @@
-use crate::utils::get_store;
+use crate::utils::{
+    get_store, get_store_with_explicit_stack_checks, get_store_without_bounds_check_elimination,
+};
+#[cfg(unix)]
+use crate::utils::{in_child, run_in_child};
 use anyhow::Result;
 use std::panic::{self, AssertUnwindSafe};
 use wasmer::*;
@@ -71,7 +75,7 @@ fn test_trap_trace() -> Result<()> {
     assert_eq!(trace[0].function_name(), Some("hello"));
     assert_eq!(trace[1].module_name(), "hello_mod");
     assert_eq!(trace[1].func_index(), 0);
-    assert_eq!(trace[1].function_name(), None);
+    assert_eq!(trace[1].function_name(), Some("run"));
     assert!(
         e.message().contains("unreachable"),
         "wrong message: {}",
@@ -81,6 +85,86 @@ fn test_trap_trace() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg_attr(
+    any(
+        feature = "test-singlepass",
+        feature = "test-native",
+        target_arch = "aarch64",
+    ),
+    ignore
+)]
+fn test_trap_trace_prefers_name_section() -> Result<()> {
+    let store = get_store(false);
+    let wat = r#"
+        (module $hello_mod
+            (func $hello (unreachable))
+            (export "first" (func $hello))
+            (export "second" (func $hello))
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run_func = instance
+        .exports
+        .get_function("second")
+        .expect("expected function export");
+
+    let e = run_func.call(&[]).err().expect("error calling function");
+
+    let trace = e.trace();
+    assert_eq!(trace.len(), 1);
+    assert_eq!(trace[0].func_index(), 0);
+    assert_eq!(trace[0].function_name(), Some("hello"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(
+    any(
+        feature = "test-singlepass",
+        feature = "test-native",
+        target_arch = "aarch64",
+    ),
+    ignore
+)]
+fn test_trap_trace_source_location() -> Result<()> {
+    let store = get_store(false);
+    // The body of `run` is at offsets 30..34 of the binary: its size,
+    // the local declarations, `unreachable` (at 32) and `end`.
+    let wat = r#"
+        (module
+            (func (export "run") (unreachable))
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run_func = instance
+        .exports
+        .get_function("run")
+        .expect("expected function export");
+
+    let e = run_func.call(&[]).err().expect("error calling function");
+
+    let trace = e.trace();
+    assert_eq!(trace.len(), 1);
+    assert!(
+        (30..34).contains(&trace[0].module_offset()),
+        "wrong module offset: {}",
+        trace[0].module_offset()
+    );
+    assert!(
+        trace[0].func_offset() < 4,
+        "wrong function offset: {}",
+        trace[0].func_offset()
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_trap_trace_cb() -> Result<()> {
     let store = get_store(false);
@@ -162,6 +246,72 @@ fn test_trap_stack_overflow() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_trap_without_bounds_check_elimination() -> Result<()> {
+    let store = get_store_without_bounds_check_elimination();
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "load") (param i32) (result i32)
+                (i32.load (local.get 0)))
+            (func (export "load_past_end") (result i32)
+                (i32.load offset=0xfffe (i32.const 0)))
+            (func (export "grow") (result i32)
+                (memory.grow (i32.const 1)))
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let load = instance.exports.get_native_function::<i32, i32>("load")?;
+    let load_past_end = instance
+        .exports
+        .get_native_function::<(), i32>("load_past_end")?;
+    let grow = instance.exports.get_native_function::<(), i32>("grow")?;
+
+    assert_eq!(load.call(0xfffc)?, 0);
+    let e = load_past_end.call().err().expect("error calling function");
+    assert!(e.message().contains("out of bounds memory access"));
+    let e = load.call(0x10000).err().expect("error calling function");
+    assert!(e.message().contains("out of bounds memory access"));
+
+    // The accesses are checked against the current size of the memory.
+    assert_eq!(grow.call()?, 1);
+    assert_eq!(load.call(0x10000)?, 0);
+    assert_eq!(load_past_end.call()?, 0);
+    let e = load.call(0x20000).err().expect("error calling function");
+    assert!(e.message().contains("out of bounds memory access"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(any(feature = "test-llvm", target_arch = "aarch64"), ignore)]
+fn test_trap_explicit_stack_check() -> Result<()> {
+    let store = get_store_with_explicit_stack_checks();
+    let wat = r#"
+        (module $rec_mod
+            (func $run (export "run") (call $run))
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run_func = instance
+        .exports
+        .get_function("run")
+        .expect("expected function export");
+
+    let e = run_func.call(&[]).err().expect("error calling function");
+    assert!(e.message().contains("call stack exhausted"));
+
+    // The instance is still usable after the stack overflow.
+    let e = run_func.call(&[]).err().expect("error calling function");
+    assert!(e.message().contains("call stack exhausted"));
+
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(
     any(
@@ -198,7 +348,7 @@ RuntimeError: unreachable
     at die (m[0]:0x23)
     at <unnamed> (m[1]:0x27)
     at foo (m[2]:0x2c)
-    at <unnamed> (m[3]:0x31)"
+    at bar (m[3]:0x31)"
     );
     Ok(())
 }
@@ -257,9 +407,9 @@ RuntimeError: unreachable
     at die (a[0]:0x23)
     at <unnamed> (a[1]:0x27)
     at foo (a[2]:0x2c)
-    at <unnamed> (a[3]:0x31)
+    at bar (a[3]:0x31)
     at middle (b[1]:0x29)
-    at <unnamed> (b[2]:0x2e)"
+    at bar2 (b[2]:0x2e)"
     );
     Ok(())
 }
@@ -516,3 +666,70 @@ fn present_after_module_drop() -> Result<()> {
         // assert_eq!(t.trace()[0].func_index(), 0);
     }
 }
+
+/// Instantiates a module whose `run` export calls the host function `host`.
+fn instance_calling(store: &Store, host: Function) -> Result<Instance> {
+    let wat = r#"
+        (module
+            (import "" "host" (func $host))
+            (memory (export "memory") 1)
+            (func (export "run") (call $host))
+            (func (export "out_of_bounds") (result i32)
+                (i32.load (i32.const 0x10000)))
+        )
+    "#;
+    let module = Module::new(store, wat)?;
+    Ok(Instance::new(
+        &module,
+        &imports! { "" => { "host" => host } },
+    )?)
+}
+
+#[test]
+#[cfg(unix)]
+#[cfg_attr(all(feature = "test-native", not(target_os = "linux")), ignore)]
+fn host_function_fault_is_not_a_trap() -> Result<()> {
+    use std::os::unix::process::ExitStatusExt;
+
+    if !in_child() {
+        let status = run_in_child("traps::host_function_fault_is_not_a_trap");
+        assert!(
+            status.signal().is_some(),
+            "the child should have crashed, but {}",
+            status
+        );
+        return Ok(());
+    }
+
+    let store = get_store(false);
+    let host = Function::new_native(&store, || unsafe {
+        std::ptr::read_volatile(std::ptr::null::<u8>());
+    });
+    let instance = instance_calling(&store, host)?;
+    let run = instance.exports.get_function("run")?;
+    // Only reached if the fault of the host function was made a trap.
+    let error = run.call(&[]).unwrap_err();
+    panic!("the fault of the host function was caught: {}", error);
+}
+
+#[test]
+#[cfg(unix)]
+fn trap_under_host_function_is_caught() -> Result<()> {
+    if !in_child() {
+        let status = run_in_child("traps::trap_under_host_function_is_caught");
+        assert!(status.success(), "the child failed: {}", status);
+        return Ok(());
+    }
+
+    let store = get_store(false);
+    let other = instance_calling(&store, Function::new_native(&store, || {}))?;
+    let out_of_bounds = other.exports.get_function("out_of_bounds")?.clone();
+    let host =
+        Function::new_native_with_env(&store, out_of_bounds, |out_of_bounds: &mut Function| {
+            let error = out_of_bounds.call(&[]).unwrap_err();
+            assert_eq!(error.message(), "out of bounds memory access");
+        });
+    let instance = instance_calling(&store, host)?;
+    instance.exports.get_function("run")?.call(&[])?;
+    Ok(())
+}
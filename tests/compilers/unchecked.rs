@@ -0,0 +1,100 @@
+#![cfg(feature = "test-jit")]
+
+use crate::utils::{
+    get_headless_store, get_store, get_unchecked_headless_store, get_unchecked_store,
+};
+#[cfg(unix)]
+use crate::utils::{in_child, run_in_child};
+use anyhow::Result;
+use wasmer::*;
+
+static WAT: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (func (export "add") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1)))
+  (func (export "store_and_load") (param i32 i32) (result i32)
+    (i32.store (local.get 0) (local.get 1))
+    (i32.load (local.get 0)))
+  (func (export "out_of_bounds") (result i32)
+    (i32.load (i32.const 0x10000))))
+"#;
+
+#[test]
+fn unchecked_functions_run() -> Result<()> {
+    let store = get_unchecked_store();
+    assert!(store.engine().unchecked());
+    let module = Module::new(&store, WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let add: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("add")?;
+    assert_eq!(add.call(1, 2)?, 3);
+    let store_and_load = instance.exports.get_function("store_and_load")?;
+    assert_eq!(
+        store_and_load
+            .call(&[Val::I32(0xfff0), Val::I32(42)])?
+            .to_vec(),
+        vec![Val::I32(42)]
+    );
+    Ok(())
+}
+
+#[test]
+fn checked_functions_catch_traps() -> Result<()> {
+    let store = get_store(false);
+    assert!(!store.engine().unchecked());
+    let module = Module::new(&store, WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let out_of_bounds = instance.exports.get_function("out_of_bounds")?;
+    let error = out_of_bounds.call(&[]).unwrap_err();
+    assert_eq!(error.message(), "out of bounds memory access");
+    Ok(())
+}
+
+#[test]
+fn mode_mismatch_is_rejected() -> Result<()> {
+    let unchecked = Module::new(&get_unchecked_store(), WAT)?.serialize()?;
+    let checked = Module::new(&get_store(false), WAT)?.serialize()?;
+
+    match unsafe { Module::deserialize(&get_headless_store(), &unchecked) } {
+        Err(DeserializeError::Incompatible(message)) => {
+            assert_eq!(message, "The artifact runs unchecked, but the engine isn't")
+        }
+        _ => panic!("a checked engine deserialized an unchecked artifact"),
+    }
+    match unsafe { Module::deserialize(&get_unchecked_headless_store(), &checked) } {
+        Err(DeserializeError::Incompatible(message)) => {
+            assert_eq!(message, "The engine runs unchecked, but the artifact isn't")
+        }
+        _ => panic!("an unchecked engine deserialized a checked artifact"),
+    }
+
+    let store = get_unchecked_headless_store();
+    let module = unsafe { Module::deserialize(&store, &unchecked)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let add: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("add")?;
+    assert_eq!(add.call(1, 2)?, 3);
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn unchecked_instances_skip_signal_handlers() -> Result<()> {
+    if !in_child() {
+        let status = run_in_child("unchecked::unchecked_instances_skip_signal_handlers");
+        assert!(status.success(), "the child failed: {}", status);
+        return Ok(());
+    }
+
+    let store = get_unchecked_store();
+    let module = Module::new(&store, WAT)?;
+    Instance::new(&module, &imports! {})?;
+    assert!(!is_trap_handler_installed());
+
+    let store = get_store(false);
+    let module = Module::new(&store, WAT)?;
+    Instance::new(&module, &imports! {})?;
+    assert!(is_trap_handler_installed());
+    Ok(())
+}
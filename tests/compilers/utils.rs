@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use wasmer::{FunctionMiddlewareGenerator, Store};
+use wasmer::{DebugInfoPolicy, Features, FunctionMiddlewareGenerator, Store};
 use wasmer_compiler::CompilerConfig;
 use wasmer_engine::Engine;
 #[cfg(feature = "test-jit")]
@@ -64,12 +64,108 @@ pub fn get_store_with_middlewares<I: Iterator<Item = Arc<dyn FunctionMiddlewareG
     Store::new(&engine)
 }
 
+pub fn get_store_with_explicit_stack_checks() -> Store {
+    let mut compiler_config = get_compiler(false);
+    compiler_config.enable_explicit_stack_checks();
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(&compiler_config).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(&mut compiler_config).engine();
+    Store::new(&engine)
+}
+
+pub fn get_store_without_bounds_check_elimination() -> Store {
+    let mut compiler_config = get_compiler(false);
+    compiler_config.disable_bounds_check_elimination();
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(&compiler_config).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(&mut compiler_config).engine();
+    Store::new(&engine)
+}
+
+pub fn get_store_with_dead_function_pruning() -> Store {
+    let mut compiler_config = get_compiler(false);
+    compiler_config.enable_dead_function_pruning();
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(&compiler_config).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(&mut compiler_config).engine();
+    Store::new(&engine)
+}
+
+pub fn get_store_with_debug_info_policy(policy: DebugInfoPolicy) -> Store {
+    let mut compiler_config = get_compiler(false);
+    compiler_config.set_debug_info_policy(policy);
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(&compiler_config).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(&mut compiler_config).engine();
+    Store::new(&engine)
+}
+
+#[cfg(feature = "test-jit")]
+pub fn get_store_with_features(features: Features) -> Store {
+    let compiler_config = get_compiler(false);
+    Store::new(&JIT::new(&compiler_config).features(features).engine())
+}
+
+#[cfg(feature = "test-native")]
+pub fn get_store_with_features(features: Features) -> Store {
+    let mut compiler_config = get_compiler(false);
+    Store::new(
+        &Native::new(&mut compiler_config)
+            .features(features)
+            .engine(),
+    )
+}
+
+#[cfg(feature = "test-jit")]
+pub fn get_recompilable_store() -> Store {
+    let compiler_config = get_compiler(false);
+    Store::new(&JIT::new(&compiler_config).recompilable().engine())
+}
+
 #[cfg(feature = "test-jit")]
 pub fn get_headless_store() -> Store {
     Store::new(&JIT::headless().engine())
 }
 
+#[cfg(feature = "test-jit")]
+pub fn get_unchecked_store() -> Store {
+    let compiler_config = get_compiler(false);
+    Store::new(&unsafe { JIT::new(&compiler_config).unchecked() }.engine())
+}
+
+#[cfg(feature = "test-jit")]
+pub fn get_unchecked_headless_store() -> Store {
+    Store::new(&unsafe { JIT::headless().unchecked() }.engine())
+}
+
 #[cfg(feature = "test-native")]
 pub fn get_headless_store() -> Store {
     Store::new(&Native::headless().engine())
 }
+
+/// Set in the child processes running a test with `run_in_child`.
+#[cfg(unix)]
+const CHILD_PROCESS: &str = "WASMER_TEST_CHILD_PROCESS";
+
+/// Runs the test `name`, e.g. `traps::some_test`, in a child process,
+/// returning how the child exited. In the child, `in_child` returns true.
+#[cfg(unix)]
+pub fn run_in_child(name: &str) -> std::process::ExitStatus {
+    std::process::Command::new(std::env::current_exe().unwrap())
+        .arg(name)
+        .arg("--exact")
+        .arg("--test-threads=1")
+        .env(CHILD_PROCESS, "1")
+        .status()
+        .unwrap()
+}
+
+/// Whether this is a child process running a test with `run_in_child`.
+#[cfg(unix)]
+pub fn in_child() -> bool {
+    std::env::var_os(CHILD_PROCESS).is_some()
+}
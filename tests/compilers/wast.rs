@@ -84,7 +84,7 @@ pub fn run_wast(wast_path: &str, compiler: &str) -> anyhow::Result<()> {
     if is_simd {
         // We allow this, so tests can be run properly for `simd_const` test.
         wast.allow_instantiation_failures(&[
-            "Validation error: multiple tables: tables count must be at most 1",
+            "Unsupported feature reference types: multiple tables: tables count must be at most 1",
             "Validation error: unknown memory 0",
             "Validation error: Invalid var_u32",
         ]);
@@ -92,13 +92,15 @@ pub fn run_wast(wast_path: &str, compiler: &str) -> anyhow::Result<()> {
     if compiler == "singlepass" {
         // We don't support multivalue yet in singlepass
         wast.allow_instantiation_failures(&[
-            "Validation error: invalid result arity: func type returns multiple values",
-            "Validation error: blocks, loops, and ifs accept no parameters when multi-value is not enabled",
+            "Unsupported feature multi value: invalid result arity: func type returns multiple values",
+            "Unsupported feature multi value: blocks, loops, and ifs accept no parameters when multi-value is not enabled",
         ]);
     } else if compiler == "cranelift" && cfg!(windows) {
         // Cranelift 0.63 have a bug on multivalue in Windows
         // It's fixed by: https://github.com/bytecodealliance/wasmtime/pull/1774/files
-        wast.allow_instantiation_failures(&["Compilation error: Implementation limit exceeded"]);
+        wast.allow_instantiation_failures(&[
+            "Insufficient resources: Implementation limit exceeded",
+        ]);
     }
     wast.fail_fast = false;
     let path = Path::new(wast_path);
@@ -0,0 +1,40 @@
+//! Embedders (and thus our examples and the C API) must only use the
+//! public `wasmer` facade, and never depend on the internal
+//! `wasmer-types` crate directly.
+
+use std::fs;
+use std::path::Path;
+
+const INTERNAL_CRATES: &[&str] = &["wasmer_types"];
+
+fn assert_uses_facade_only(dir: &Path) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            assert_uses_facade_only(&path);
+            continue;
+        }
+        if path.extension().map_or(true, |ext| ext != "rs") {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+        for internal in INTERNAL_CRATES {
+            assert!(
+                !source.contains(internal),
+                "{} uses `{}` directly, please use the `wasmer` facade instead",
+                path.display(),
+                internal
+            );
+        }
+    }
+}
+
+#[test]
+fn examples_use_facade_only() {
+    assert_uses_facade_only(&Path::new(env!("CARGO_MANIFEST_DIR")).join("examples"));
+}
+
+#[test]
+fn c_api_uses_facade_only() {
+    assert_uses_facade_only(&Path::new(env!("CARGO_MANIFEST_DIR")).join("lib/c-api/src"));
+}
@@ -15,8 +15,8 @@ use wasmer_types::{
     TableIndex,
 };
 use wasmer_vm::{
-    FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMContext, VMFunctionBody,
-    VMSharedSignatureIndex,
+    ExportResolution, FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMContext,
+    VMFunctionBody, VMSharedSignatureIndex,
 };
 
 /// Serializable struct for the artifact
@@ -40,6 +40,7 @@ pub struct DummyArtifact {
     finished_functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
     finished_dynamic_function_trampolines: BoxedSlice<FunctionIndex, FunctionBodyPtr>,
     signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
+    export_resolutions: Vec<ExportResolution>,
 }
 
 extern "C" fn dummy_function(_context: *mut VMContext) {
@@ -63,7 +64,7 @@ impl DummyArtifact {
     ) -> Result<Self, CompileError> {
         let environ = ModuleEnvironment::new();
 
-        let translation = environ.translate(data).map_err(CompileError::Wasm)?;
+        let translation = environ.translate(data)?;
 
         let memory_styles: PrimaryMap<MemoryIndex, MemoryStyle> = translation
             .module
@@ -169,11 +170,13 @@ impl DummyArtifact {
             finished_dynamic_function_trampolines.into_boxed_slice();
         let signatures = signatures.into_boxed_slice();
 
+        let export_resolutions = metadata.module.export_resolutions();
         Ok(Self {
             metadata,
             finished_functions,
             finished_dynamic_function_trampolines,
             signatures,
+            export_resolutions,
         })
     }
 }
@@ -223,6 +226,10 @@ impl Artifact for DummyArtifact {
         &self.signatures
     }
 
+    fn export_resolutions(&self) -> &[ExportResolution] {
+        &self.export_resolutions
+    }
+
     #[cfg(feature = "serialize")]
     fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
         let bytes = bincode::serialize(&self.metadata)
@@ -3,7 +3,7 @@
 use crate::DummyArtifact;
 use std::sync::Arc;
 use wasmer_compiler::{CompileError, Features, Target};
-use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, Tunables};
+use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, EngineKind, Tunables};
 use wasmer_types::FunctionType;
 use wasmer_vm::{
     SignatureRegistry, VMContext, VMFunctionBody, VMSharedSignatureIndex, VMTrampoline,
@@ -109,6 +109,11 @@ impl Engine for DummyEngine {
         &self.engine_id
     }
 
+    fn kind(&self) -> EngineKind {
+        // The artifacts are deserialized in memory, like the JIT ones.
+        EngineKind::Jit
+    }
+
     fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
         Arc::new(self.clone())
     }